@@ -0,0 +1,103 @@
+//! Parse throughput and peak memory on large synthetic statements
+//!
+//! Statements come from `ib_flex::test_util::generate`, with the number of
+//! trades set by `IB_FLEX_BENCH_TRADES` (comma-separated, default
+//! `10000,100000`). A million trades is about 500 MiB of XML and needs
+//! several GiB of memory to parse, so it is left to an explicit run:
+//!
+//! ```text
+//! cargo bench --bench large_statements --features test-util
+//! IB_FLEX_BENCH_TRADES=1000000 cargo bench --bench large_statements --features test-util
+//! ```
+//!
+//! Criterion reports throughput in bytes of XML per second. Peak memory,
+//! the most the parser holds on top of the input while parsing, is printed
+//! for each size before it is timed.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::hint::black_box;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use ib_flex::parse_activity_flex;
+use ib_flex::test_util::{generate, FixtureSpec};
+
+/// The system allocator, counting bytes in use and their high-water mark
+struct CountingAllocator;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            let allocated = ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+            PEAK.fetch_max(allocated, Ordering::Relaxed);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+fn trade_counts() -> Vec<usize> {
+    std::env::var("IB_FLEX_BENCH_TRADES")
+        .unwrap_or_else(|_| "10000,100000".to_string())
+        .split(',')
+        .map(|n| {
+            n.trim()
+                .parse()
+                .expect("IB_FLEX_BENCH_TRADES must be numbers")
+        })
+        .collect()
+}
+
+/// Bytes allocated at the peak of parsing `xml`, over what was in use before
+fn peak_parse_memory(xml: &str) -> usize {
+    let before = ALLOCATED.load(Ordering::Relaxed);
+    PEAK.store(before, Ordering::Relaxed);
+    let statement = parse_activity_flex(xml).expect("generated statement parses");
+    let peak = PEAK.load(Ordering::Relaxed);
+    drop(statement);
+    peak - before
+}
+
+fn benchmark_large_statements(c: &mut Criterion) {
+    let mut group = c.benchmark_group("large_statements");
+    group.sample_size(10);
+    group.measurement_time(Duration::from_secs(20));
+
+    for trades in trade_counts() {
+        let xml = generate(&FixtureSpec {
+            trades,
+            symbols: 100,
+            ..Default::default()
+        });
+        let peak = peak_parse_memory(&xml);
+        eprintln!(
+            "large_statements/{}: {:.1} MiB of XML, peak parse memory {:.1} MiB ({} bytes per trade)",
+            trades,
+            xml.len() as f64 / (1 << 20) as f64,
+            peak as f64 / (1 << 20) as f64,
+            peak / trades.max(1)
+        );
+
+        group.throughput(Throughput::Bytes(xml.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(trades), &xml, |b, xml| {
+            b.iter(|| parse_activity_flex(black_box(xml)).unwrap())
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, benchmark_large_statements);
+criterion_main!(benches);
@@ -0,0 +1,49 @@
+//! Node.js bindings for ib-flex
+//!
+//! Parses FLEX XML into plain JavaScript objects with the field names and
+//! values of `ib_flex::export::json`: snake_case names, ISO 8601 dates,
+//! `null` for missing values, and amounts as strings, so no precision is
+//! lost. Errors are thrown as `Error`s with the parser's message.
+//!
+//! ```js
+//! const { parseActivityFlex } = require("ib-flex");
+//!
+//! const statement = parseActivityFlex(fs.readFileSync("activity.xml", "utf8"));
+//! console.log(statement.account_id, statement.open_positions.length);
+//! ```
+
+use napi::{Error, Result};
+use napi_derive::napi;
+use serde_json::Value;
+
+/// Parse an Activity FLEX statement
+#[napi]
+pub fn parse_activity_flex(xml: String) -> Result<Value> {
+    to_js(ib_flex::parse_activity_flex(&xml).and_then(|s| ib_flex::export::json::to_value(&s)))
+}
+
+/// Parse every statement of an Activity FLEX document
+#[napi]
+pub fn parse_activity_flex_all(xml: String) -> Result<Value> {
+    to_js(ib_flex::parse_activity_flex_all(&xml).and_then(|s| ib_flex::export::json::to_value(&s)))
+}
+
+/// Parse a Trade Confirmation FLEX statement
+#[napi]
+pub fn parse_trade_confirmation(xml: String) -> Result<Value> {
+    to_js(ib_flex::parse_trade_confirmation(&xml).and_then(|s| ib_flex::export::json::to_value(&s)))
+}
+
+/// `"activity"` or `"trade_confirmation"`, depending on the statement type
+#[napi]
+pub fn detect_statement_type(xml: String) -> Result<String> {
+    match ib_flex::detect_statement_type(&xml) {
+        Ok(ib_flex::StatementType::Activity) => Ok("activity".to_string()),
+        Ok(ib_flex::StatementType::TradeConfirmation) => Ok("trade_confirmation".to_string()),
+        Err(err) => Err(Error::from_reason(err.to_string())),
+    }
+}
+
+fn to_js(value: ib_flex::Result<Value>) -> Result<Value> {
+    value.map_err(|err| Error::from_reason(err.to_string()))
+}
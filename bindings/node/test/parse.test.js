@@ -0,0 +1,22 @@
+const assert = require("node:assert");
+const fs = require("node:fs");
+const path = require("node:path");
+const test = require("node:test");
+
+const { parseActivityFlex, parseActivityFlexAll, detectStatementType } = require("..");
+
+const fixture = (name) =>
+  fs.readFileSync(path.join(__dirname, "../../../tests/fixtures", name), "utf8");
+
+test("parses a statement to plain objects", () => {
+  const xml = fixture("activity_minimal.xml");
+  const statement = parseActivityFlex(xml);
+  assert.strictEqual(statement.account_id, "U1234567");
+  assert.ok(Array.isArray(statement.open_positions));
+  assert.strictEqual(parseActivityFlexAll(xml).length, 1);
+  assert.strictEqual(detectStatementType(xml), "activity");
+});
+
+test("throws on malformed XML", () => {
+  assert.throws(() => parseActivityFlex("<FlexQueryResponse"), Error);
+});
@@ -170,7 +170,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         let mut by_asset: HashMap<AssetCategory, (usize, Decimal)> = HashMap::new();
         for pos in positions.iter() {
             let entry = by_asset
-                .entry(pos.asset_category)
+                .entry(pos.asset_category.clone())
                 .or_insert((0, Decimal::ZERO));
             entry.0 += 1;
             entry.1 += pos.position_value;
@@ -220,7 +220,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         // By asset class
         let mut by_asset: HashMap<AssetCategory, TradeStats> = HashMap::new();
         for trade in &all_trades {
-            let stats = by_asset.entry(trade.asset_category).or_default();
+            let stats = by_asset.entry(trade.asset_category.clone()).or_default();
             stats.count += 1;
             if let Some(pnl) = trade.fifo_pnl_realized {
                 stats.realized_pnl += pnl;
@@ -418,7 +418,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         for txn in &all_cash_txns {
             let txn_type = txn
                 .transaction_type
-                .clone()
+                .as_ref()
+                .map(|t| t.to_string())
                 .unwrap_or_else(|| "Unknown".to_string());
             let entry = by_type.entry(txn_type).or_insert((0, Decimal::ZERO));
             entry.0 += 1;
@@ -440,7 +441,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             .filter(|t| {
                 t.transaction_type
                     .as_ref()
-                    .map(|s| s.contains("Dividend"))
+                    .map(|s| s.is_dividend())
                     .unwrap_or(false)
             })
             .map(|t| t.amount)
@@ -451,7 +452,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             .filter(|t| {
                 t.transaction_type
                     .as_ref()
-                    .map(|s| s.contains("Withholding"))
+                    .map(|s| s.is_withholding())
                     .unwrap_or(false)
             })
             .map(|t| t.amount)
@@ -462,7 +463,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             .filter(|t| {
                 t.transaction_type
                     .as_ref()
-                    .map(|s| s.contains("Interest"))
+                    .map(|s| s.is_interest())
                     .unwrap_or(false)
             })
             .map(|t| t.amount)
@@ -473,7 +474,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             .filter(|t| {
                 t.transaction_type
                     .as_ref()
-                    .map(|s| s.contains("Fee") || s.contains("Commission"))
+                    .map(|s| s.as_str().contains("Fee") || s.as_str().contains("Commission"))
                     .unwrap_or(false)
             })
             .map(|t| t.amount)
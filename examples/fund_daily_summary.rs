@@ -207,7 +207,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         // Group by type
         let mut by_type: HashMap<&str, Decimal> = HashMap::new();
         for txn in cash_txns {
-            let transaction_type = txn.transaction_type.as_deref().unwrap_or("Unknown");
+            let transaction_type = txn
+                .transaction_type
+                .as_ref()
+                .map(|t| t.as_str())
+                .unwrap_or("Unknown");
             *by_type.entry(transaction_type).or_insert(Decimal::ZERO) += txn.amount;
         }
 
@@ -223,7 +227,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             .iter()
             .filter(|t| {
                 matches!(
-                    t.transaction_type.as_deref(),
+                    t.transaction_type.as_ref().map(|t| t.as_str()),
                     Some(transaction_type) if transaction_type.contains("Dividend")
                 )
             })
@@ -238,7 +242,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             .iter()
             .filter(|t| {
                 matches!(
-                    t.transaction_type.as_deref(),
+                    t.transaction_type.as_ref().map(|t| t.as_str()),
                     Some(transaction_type) if transaction_type.contains("Withholding")
                 )
             })
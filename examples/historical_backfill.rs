@@ -168,7 +168,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Update status with this fetch
     status.last_fetch = Some(chrono::Utc::now().to_rfc3339());
-    status.account_id = Some(statement.account_id.clone());
+    status.account_id = Some(statement.account_id.to_string());
     status
         .fetched_ranges
         .push((statement.from_date, statement.to_date));
@@ -377,7 +377,7 @@ fn extract_daily_snapshots(
             .iter()
             .filter(|c| {
                 matches!(
-                    c.transaction_type.as_deref(),
+                    c.transaction_type.as_ref().map(|t| t.as_str()),
                     Some(transaction_type) if transaction_type.contains("Dividend")
                 )
             })
@@ -3,6 +3,7 @@
 //! This example demonstrates how to analyze trading activity for tax reporting,
 //! including:
 //! - Wash sale detection and tracking (using IB's WashSale records)
+//! - An independent wash sale screen rebuilt from trade history
 //! - Short-term vs long-term capital gains classification
 //! - Positions still under wash sale restriction
 //! - Tax lot tracking
@@ -14,6 +15,9 @@
 //! AFTER the basis adjustment.
 
 use chrono::{Duration, NaiveDate};
+use ib_flex::analytics::{
+    detect_wash_sales, trade_holding_period, HoldingPeriod, LotLedger, WashSaleConfig,
+};
 use ib_flex::parse_activity_flex_all;
 use rust_decimal::Decimal;
 use std::collections::HashMap;
@@ -150,26 +154,10 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             // Get realized P&L
             if let Some(pnl) = trade.fifo_pnl_realized {
                 if pnl != Decimal::ZERO {
-                    // Determine if long-term or short-term
-                    let is_long_term = if let Some(orig_date) = trade.orig_trade_date {
-                        let holding_period = trade_date - orig_date;
-                        holding_period > Duration::days(365)
-                    } else if let Some(hpdt) = &trade.holding_period_date_time {
-                        // Parse holding period datetime if available
-                        if hpdt.len() >= 10 {
-                            if let Ok(hp_date) = NaiveDate::parse_from_str(&hpdt[..10], "%Y-%m-%d")
-                            {
-                                let holding_period = trade_date - hp_date;
-                                holding_period > Duration::days(365)
-                            } else {
-                                false // Default to short-term if can't determine
-                            }
-                        } else {
-                            false
-                        }
-                    } else {
-                        false // Default to short-term if no original date
-                    };
+                    // Determine if long-term or short-term from IB's holding
+                    // period start, defaulting to short-term when it is missing
+                    let is_long_term =
+                        trade_holding_period(trade).is_some_and(HoldingPeriod::is_long_term);
 
                     // Categorize the gain/loss
                     if is_long_term {
@@ -208,21 +196,10 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Use positions from last statement (most recent)
     for position in &last.positions.items {
         // Parse acquisition date from open_date_time or holding_period_date_time
-        let acquisition_date = if let Some(odt) = &position.open_date_time {
-            if odt.len() >= 10 {
-                NaiveDate::parse_from_str(&odt[..10], "%Y-%m-%d").ok()
-            } else {
-                None
-            }
-        } else if let Some(hpdt) = &position.holding_period_date_time {
-            if hpdt.len() >= 10 {
-                NaiveDate::parse_from_str(&hpdt[..10], "%Y-%m-%d").ok()
-            } else {
-                None
-            }
-        } else {
-            None
-        };
+        let acquisition_date = position
+            .open_date_time
+            .or(position.holding_period_date_time)
+            .map(|dt| dt.date());
 
         if let Some(acq_date) = acquisition_date {
             // Check if this symbol had any loss sales within wash sale window
@@ -329,6 +306,41 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     }
     println!();
 
+    // 4b. Independent wash sale screen using the library's lot engine
+    println!("=======================================================");
+    println!("         WASH SALE SCREEN (FROM TRADE HISTORY)");
+    println!("=======================================================\n");
+
+    let all_trades: Vec<&ib_flex::Trade> =
+        statements.iter().flat_map(|s| &s.trades.items).collect();
+    let mut ledger = LotLedger::new();
+    ledger.apply_trades(all_trades.iter().copied());
+    let washes = detect_wash_sales(
+        ledger.closures(),
+        all_trades.iter().copied(),
+        &WashSaleConfig::default(),
+    );
+    if washes.is_empty() {
+        println!("No loss sales with replacement purchases within 30 days.\n");
+    } else {
+        println!(
+            "{:<10} {:<12} {:<12} {:>10} {:>15}",
+            "SYMBOL", "SOLD", "REPLACED", "QTY", "DISALLOWED"
+        );
+        println!("{}", "-".repeat(63));
+        for wash in &washes {
+            println!(
+                "{:<10} {:<12} {:<12} {:>10.2} {:>15.2}",
+                wash.symbol,
+                wash.sale_date,
+                wash.replacement_date,
+                wash.quantity,
+                wash.disallowed_loss
+            );
+        }
+        println!();
+    }
+
     // 5. Positions Under Wash Sale Restriction
     println!("=======================================================");
     println!("      POSITIONS UNDER WASH SALE RESTRICTION");
@@ -454,20 +466,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 }
             }
 
-            match cash_txn.transaction_type.as_deref() {
-                Some("Dividends") | Some("Payment In Lieu Of Dividends") => {
-                    total_dividends += cash_txn.amount;
-                }
-                Some("Withholding Tax") => {
-                    total_withholding += cash_txn.amount; // Usually negative
-                }
-                Some("Broker Interest Received") | Some("Bond Interest Received") => {
-                    total_interest += cash_txn.amount;
-                }
-                Some("Broker Interest Paid") => {
-                    total_interest += cash_txn.amount; // Usually negative
-                }
-                _ => {}
+            if cash_txn.is_dividend() {
+                total_dividends += cash_txn.amount;
+            } else if cash_txn.is_withholding_tax() {
+                total_withholding += cash_txn.amount; // Usually negative
+            } else if cash_txn.is_interest() {
+                total_interest += cash_txn.amount; // Paid interest is negative
             }
         }
     } // End statement loop
@@ -0,0 +1,52 @@
+/*
+ * C API of the ib-flex parser for Interactive Brokers FLEX XML statements.
+ *
+ * Build the library with the `ffi` feature:
+ *
+ *     cargo rustc --lib --release --features ffi --crate-type cdylib
+ *
+ * Strings returned as `const char *` belong to the handle and live until
+ * ib_flex_free(); strings returned as `char *` belong to the caller and are
+ * freed with ib_flex_string_free(). Failing functions return NULL (or 0 for
+ * counts); ib_flex_last_error() gives the message, per thread. JSON uses
+ * snake_case field names, ISO 8601 dates, and amounts as strings.
+ */
+
+#ifndef IB_FLEX_H
+#define IB_FLEX_H
+
+#include <stddef.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* The statements of one parsed document */
+typedef struct IbFlexStatements IbFlexStatements;
+
+/* Parsing and freeing */
+IbFlexStatements *ib_flex_parse_file(const char *path);
+IbFlexStatements *ib_flex_parse_string(const char *xml);
+void ib_flex_free(IbFlexStatements *statements);
+void ib_flex_string_free(char *string);
+const char *ib_flex_last_error(void);
+
+/* Statements */
+size_t ib_flex_statement_count(const IbFlexStatements *statements);
+const char *ib_flex_account_id(const IbFlexStatements *statements, size_t index);
+size_t ib_flex_trade_count(const IbFlexStatements *statements, size_t index);
+size_t ib_flex_position_count(const IbFlexStatements *statements, size_t index);
+size_t ib_flex_cash_transaction_count(const IbFlexStatements *statements, size_t index);
+
+/* JSON, owned by the caller */
+char *ib_flex_to_json(const IbFlexStatements *statements);
+char *ib_flex_statement_json(const IbFlexStatements *statements, size_t index);
+char *ib_flex_trades_json(const IbFlexStatements *statements, size_t index);
+char *ib_flex_positions_json(const IbFlexStatements *statements, size_t index);
+char *ib_flex_cash_transactions_json(const IbFlexStatements *statements, size_t index);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* IB_FLEX_H */
@@ -0,0 +1,352 @@
+//! Per-symbol performance attribution
+//!
+//! [`attribution_report`] splits a period's P&L by symbol or underlying into
+//! price P&L from trades and position marks, dividends, and fees, all in the
+//! base currency. Where the statements include `MTMPerformanceSummaryInBase`,
+//! each contribution carries IB's reported MTM so the two can be reconciled.
+//!
+//! # Example
+//! ```
+//! use ib_flex::analytics::{attribution_report, AttributionLevel};
+//! use ib_flex::parse_activity_flex;
+//!
+//! let xml = include_str!("../../tests/fixtures/activity_minimal.xml");
+//! let statement = parse_activity_flex(xml).unwrap();
+//!
+//! let report = attribution_report([&statement], &[], AttributionLevel::Underlying);
+//! for (symbol, contribution) in &report.contributions {
+//!     println!(
+//!         "{symbol}: price {} dividends {} fees {} total {}",
+//!         contribution.price,
+//!         contribution.dividends,
+//!         contribution.fees,
+//!         contribution.total()
+//!     );
+//! }
+//! ```
+
+use std::collections::BTreeMap;
+
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+#[cfg(feature = "serialize")]
+use serde::Serialize;
+
+use crate::types::traits::HasConid;
+use crate::types::{
+    ActivityFlexStatement, LevelOfDetail, MTMPerformanceSummaryUnderlying, Position,
+};
+
+/// How contributions are grouped
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub enum AttributionLevel {
+    /// One contribution per symbol, so each option contract stands alone
+    Symbol,
+    /// Options and other derivatives roll up into their underlying
+    #[default]
+    Underlying,
+}
+
+impl AttributionLevel {
+    fn key(self, symbol: &str, underlying_symbol: Option<&str>) -> String {
+        match self {
+            AttributionLevel::Symbol => symbol.to_string(),
+            AttributionLevel::Underlying => underlying_symbol
+                .filter(|u| !u.is_empty())
+                .unwrap_or(symbol)
+                .to_string(),
+        }
+    }
+}
+
+/// P&L contributed by one symbol or underlying, in the base currency
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct Contribution {
+    /// Change in market value plus trade proceeds, before commissions
+    ///
+    /// Covers both P&L realized on trades and the marks on positions held
+    /// at the start or end of the period.
+    pub price: Decimal,
+    /// Dividends and payments in lieu, net of withholding tax
+    pub dividends: Decimal,
+    /// Commissions and taxes on trades, plus fees charged against the symbol
+    pub fees: Decimal,
+    /// Total MTM from IB's performance summary, when present
+    pub reported: Option<Decimal>,
+}
+
+impl Contribution {
+    /// Total contribution
+    pub fn total(&self) -> Decimal {
+        self.price + self.dividends + self.fees
+    }
+
+    /// The part of the total IB's MTM performance summary covers
+    ///
+    /// IB reports dividends outside the MTM summary, so they are excluded.
+    pub fn mtm(&self) -> Decimal {
+        self.price + self.fees
+    }
+
+    /// Reported MTM minus [`mtm`](Self::mtm), when IB reported one
+    pub fn difference(&self) -> Option<Decimal> {
+        self.reported.map(|reported| reported - self.mtm())
+    }
+}
+
+/// Contributions for a period
+#[derive(Debug, Clone, PartialEq, Eq, Default, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct AttributionReport {
+    /// First day of the period
+    pub from_date: Option<NaiveDate>,
+    /// Last day of the period
+    pub to_date: Option<NaiveDate>,
+    /// Contribution per symbol or underlying
+    pub contributions: BTreeMap<String, Contribution>,
+}
+
+impl AttributionReport {
+    /// Sum of all contributions
+    pub fn total(&self) -> Decimal {
+        self.contributions.values().map(Contribution::total).sum()
+    }
+
+    /// Contributions whose MTM differs from IB's by more than `tolerance`
+    pub fn unreconciled(
+        &self,
+        tolerance: Decimal,
+    ) -> impl Iterator<Item = (&String, &Contribution)> + '_ {
+        self.contributions.iter().filter(move |(_, c)| {
+            c.difference()
+                .is_some_and(|difference| difference.abs() > tolerance)
+        })
+    }
+}
+
+/// Attribute the P&L of the period covered by `statements`
+///
+/// The closing marks come from the `OpenPositions` of the latest statement;
+/// `opening` is the snapshot from the end of the prior period, or `&[]`
+/// when the account started flat. Trades are execution rows, dividends and
+/// fees come from `CashTransactions` carrying a symbol, and amounts are
+/// converted with each row's `fxRateToBase`, with 1 when missing.
+/// Commissions charged in another currency than the trade are left out, as
+/// the row carries no rate for them.
+pub fn attribution_report<'a, I>(
+    statements: I,
+    opening: &[Position],
+    level: AttributionLevel,
+) -> AttributionReport
+where
+    I: IntoIterator<Item = &'a ActivityFlexStatement>,
+{
+    let statements: Vec<&ActivityFlexStatement> = statements.into_iter().collect();
+    let mut report = AttributionReport {
+        from_date: statements.iter().map(|s| s.from_date).min(),
+        to_date: statements.iter().map(|s| s.to_date).max(),
+        ..Default::default()
+    };
+
+    for trade in statements.iter().flat_map(|s| s.trades.iter()) {
+        if !matches!(trade.level_of_detail, None | Some(LevelOfDetail::Execution)) {
+            continue;
+        }
+        let contribution = report
+            .contributions
+            .entry(level.key(&trade.symbol, trade.underlying_symbol.as_deref()))
+            .or_default();
+        contribution.price += trade.proceeds_in_base().unwrap_or_default();
+        contribution.fees += trade.commission_in_base().unwrap_or_default()
+            + trade.taxes.unwrap_or_default() * trade.fx_rate_to_base.unwrap_or(Decimal::ONE);
+    }
+
+    if let Some(closing) = statements.iter().max_by_key(|s| s.to_date) {
+        for position in summary_rows(&closing.positions.items) {
+            report
+                .contributions
+                .entry(level.key(&position.symbol, position.underlying_symbol.as_deref()))
+                .or_default()
+                .price += position.market_value_in_base();
+        }
+    }
+    for position in summary_rows(opening) {
+        report
+            .contributions
+            .entry(level.key(&position.symbol, position.underlying_symbol.as_deref()))
+            .or_default()
+            .price -= position.market_value_in_base();
+    }
+
+    for tx in statements.iter().flat_map(|s| s.cash_transactions.iter()) {
+        let (Some(symbol), Some(kind)) = (tx.symbol.as_deref(), tx.transaction_type.as_ref())
+        else {
+            continue;
+        };
+        if symbol.is_empty() {
+            continue;
+        }
+        let amount = tx.amount * tx.fx_rate_to_base.unwrap_or(Decimal::ONE);
+        let key = level.key(symbol, tx.underlying_symbol.as_deref());
+        if kind.is_dividend() || (kind.is_withholding() && tx.conid().is_some()) {
+            report.contributions.entry(key).or_default().dividends += amount;
+        } else if kind.is_fee() {
+            report.contributions.entry(key).or_default().fees += amount;
+        }
+    }
+
+    for row in statements
+        .iter()
+        .flat_map(|s| s.mtm_performance_summary.iter())
+    {
+        let Some(symbol) = row.symbol.as_deref().filter(|s| !s.is_empty()) else {
+            continue;
+        };
+        let key = level.key(symbol, row.underlying_symbol.as_deref());
+        let reported = &mut report.contributions.entry(key).or_default().reported;
+        *reported = Some(reported.unwrap_or_default() + reported_mtm(row));
+    }
+
+    report
+}
+
+/// IB's total MTM for a row, summed from its parts when `total` is missing
+fn reported_mtm(row: &MTMPerformanceSummaryUnderlying) -> Decimal {
+    row.total.unwrap_or_else(|| {
+        [
+            row.prior_open_mtm,
+            row.transaction_mtm,
+            row.commissions,
+            row.other,
+        ]
+        .iter()
+        .flatten()
+        .sum()
+    })
+}
+
+/// Position rows without `LOT` rows, which repeat their summary row
+pub(super) fn summary_rows(positions: &[Position]) -> impl Iterator<Item = &Position> {
+    positions
+        .iter()
+        .filter(|p| p.level_of_detail != Some(LevelOfDetail::Lot))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{
+        AssetCategory, CashTransactionBuilder, CashTransactionType, PositionBuilder, TradeBuilder,
+    };
+
+    fn day(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 6, d).unwrap()
+    }
+
+    #[test]
+    fn test_attribution_by_underlying() {
+        let xml = include_str!("../../tests/fixtures/activity_minimal.xml");
+        let mut statement = crate::parse_activity_flex(xml).unwrap();
+
+        // Bought 100 AAPL at 190 and sold a covered call for 300
+        statement.trades.items = vec![
+            TradeBuilder::new("AAPL")
+                .conid(1)
+                .trade_date(day(2))
+                .quantity(100)
+                .proceeds(-19000)
+                .commission(-1)
+                .build(),
+            TradeBuilder::new("AAPL  250620C00200000")
+                .conid(2)
+                .asset_category(AssetCategory::Option)
+                .underlying_symbol("AAPL")
+                .trade_date(day(3))
+                .quantity(-1)
+                .proceeds(300)
+                .commission(-1)
+                .build(),
+        ];
+        statement.positions.items = vec![
+            PositionBuilder::new("AAPL")
+                .conid(1)
+                .quantity(100)
+                .position_value(19500)
+                .build(),
+            PositionBuilder::new("AAPL  250620C00200000")
+                .conid(2)
+                .asset_category(AssetCategory::Option)
+                .underlying_symbol("AAPL")
+                .quantity(-1)
+                .position_value(-200)
+                .build(),
+        ];
+        statement.cash_transactions.items =
+            vec![
+                CashTransactionBuilder::new(CashTransactionType::Dividends, 25)
+                    .date(day(10))
+                    .symbol("AAPL")
+                    .conid("1")
+                    .build(),
+            ];
+
+        let report = attribution_report([&statement], &[], AttributionLevel::Underlying);
+        let aapl = report.contributions["AAPL"];
+        assert_eq!(aapl.price, Decimal::from(600));
+        assert_eq!(aapl.dividends, Decimal::from(25));
+        assert_eq!(aapl.fees, Decimal::from(-2));
+        assert_eq!(aapl.total(), Decimal::from(623));
+        assert_eq!(aapl.reported, None);
+
+        let by_symbol = attribution_report([&statement], &[], AttributionLevel::Symbol);
+        assert_eq!(by_symbol.contributions.len(), 2);
+        assert_eq!(
+            by_symbol.contributions["AAPL  250620C00200000"].price,
+            Decimal::from(100)
+        );
+        assert_eq!(by_symbol.total(), report.total());
+    }
+
+    #[test]
+    fn test_reconciles_to_mtm_summary() {
+        let xml = r#"<FlexQueryResponse queryName="Test" type="AF">
+<FlexStatements count="1">
+<FlexStatement accountId="U1" fromDate="2025-06-01" toDate="2025-06-30" period="Custom" whenGenerated="2025-07-01;120000">
+<OpenPositions>
+<OpenPosition accountId="U1" conid="1" symbol="MSFT" assetCategory="STK" currency="USD" position="10" markPrice="450" positionValue="4500" reportDate="2025-06-30" />
+</OpenPositions>
+<Trades>
+<Trade accountId="U1" conid="1" symbol="MSFT" assetCategory="STK" currency="USD" tradeDate="2025-06-02" quantity="10" tradePrice="420" proceeds="-4200" ibCommission="-1" buySell="BUY" levelOfDetail="EXECUTION" />
+</Trades>
+<MTMPerformanceSummaryInBase>
+<MTMPerformanceSummaryUnderlying accountId="U1" symbol="MSFT" assetCategory="STK" priorOpenMtm="0" transactionMtm="300" commissions="-1" other="0" total="299" />
+<MTMPerformanceSummaryUnderlying accountId="U1" symbol="" assetCategory="STK" description="Total" total="299" />
+</MTMPerformanceSummaryInBase>
+</FlexStatement>
+</FlexStatements>
+</FlexQueryResponse>"#;
+        let statement = crate::parse_activity_flex(xml).unwrap();
+
+        let report = attribution_report([&statement], &[], AttributionLevel::Symbol);
+        assert_eq!(report.contributions.len(), 1);
+        let msft = report.contributions["MSFT"];
+        assert_eq!(msft.mtm(), Decimal::from(299));
+        assert_eq!(msft.difference(), Some(Decimal::ZERO));
+        assert_eq!(report.unreconciled(Decimal::ONE).count(), 0);
+
+        let opening = [PositionBuilder::new("MSFT")
+            .conid(1)
+            .quantity(5)
+            .position_value(2000)
+            .build()];
+        let shifted = attribution_report([&statement], &opening, AttributionLevel::Symbol);
+        assert_eq!(
+            shifted.contributions["MSFT"].difference(),
+            Some(Decimal::from(2000))
+        );
+        assert_eq!(shifted.unreconciled(Decimal::ONE).count(), 1);
+    }
+}
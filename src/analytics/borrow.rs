@@ -0,0 +1,235 @@
+//! Securities borrowing and lending costs
+//!
+//! [`borrow_report`] totals the daily borrow fees charged on short
+//! positions (`HardToBorrowDetails`) and the fees earned or paid on
+//! securities loans (`SLBFees`, which covers the Stock Yield Enhancement
+//! Program) per symbol and month, and relates each total to the value
+//! borrowed or lent to give an effective annual rate.
+//!
+//! # Example
+//! ```
+//! use ib_flex::analytics::borrow_report;
+//! use ib_flex::parse_activity_flex;
+//!
+//! let xml = include_str!("../../tests/fixtures/activity_minimal.xml");
+//! let statement = parse_activity_flex(xml).unwrap();
+//!
+//! let report = borrow_report([&statement]);
+//! for (symbol, summary) in &report.by_symbol {
+//!     println!("{symbol}: borrow {} at {:?}%", summary.borrow_fees, summary.borrow_rate());
+//! }
+//! ```
+
+use std::collections::BTreeMap;
+
+use chrono::{Datelike, NaiveDate};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+#[cfg(feature = "serialize")]
+use serde::Serialize;
+
+use super::interest::money_market_year;
+use crate::types::traits::Dated;
+use crate::types::ActivityFlexStatement;
+
+/// Borrow and lending amounts in the base currency
+///
+/// Fees keep IB's signs: borrow fees are negative and lending income
+/// positive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct BorrowSummary {
+    /// Borrow fees on short positions
+    pub borrow_fees: Decimal,
+    /// Days with a borrow fee row
+    pub days_borrowed: u32,
+    /// Value of the short positions summed over those days, in years of
+    /// the currency's interest basis
+    pub borrowed_value_years: Decimal,
+    /// Fees on securities loans, including carry charges
+    pub lending_fees: Decimal,
+    /// Days with a loan fee row
+    pub days_lent: u32,
+    /// Loan collateral summed over those days, in years of the currency's
+    /// interest basis
+    pub lent_value_years: Decimal,
+    /// `SLBActivities` rows (new loans, returns, and rate changes)
+    pub loan_activities: usize,
+}
+
+impl BorrowSummary {
+    /// Borrow fees plus lending fees
+    pub fn net(&self) -> Decimal {
+        self.borrow_fees + self.lending_fees
+    }
+
+    /// Effective annual borrow rate, in percent like IB's `borrowFeeRate`
+    ///
+    /// Returns `None` if nothing was borrowed.
+    pub fn borrow_rate(&self) -> Option<Decimal> {
+        annual_rate(-self.borrow_fees, self.borrowed_value_years)
+    }
+
+    /// Effective annual lending rate, in percent like IB's `feeRate`
+    ///
+    /// Returns `None` if nothing was lent.
+    pub fn lending_rate(&self) -> Option<Decimal> {
+        annual_rate(self.lending_fees, self.lent_value_years)
+    }
+
+    fn add(&mut self, other: &BorrowSummary) {
+        self.borrow_fees += other.borrow_fees;
+        self.days_borrowed += other.days_borrowed;
+        self.borrowed_value_years += other.borrowed_value_years;
+        self.lending_fees += other.lending_fees;
+        self.days_lent += other.days_lent;
+        self.lent_value_years += other.lent_value_years;
+        self.loan_activities += other.loan_activities;
+    }
+}
+
+fn annual_rate(fees: Decimal, value_years: Decimal) -> Option<Decimal> {
+    if value_years.is_zero() {
+        None
+    } else {
+        Some(fees / value_years * Decimal::ONE_HUNDRED)
+    }
+}
+
+/// Result of [`borrow_report`]
+///
+/// Rows without a symbol are keyed under `""`.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct BorrowReport {
+    /// Totals across all symbols
+    pub total: BorrowSummary,
+    /// Totals per symbol
+    pub by_symbol: BTreeMap<String, BorrowSummary>,
+    /// Totals per symbol and month, keyed by the first day of the month
+    pub by_month: BTreeMap<String, BTreeMap<NaiveDate, BorrowSummary>>,
+}
+
+impl BorrowReport {
+    fn add(&mut self, symbol: Option<&str>, date: Option<NaiveDate>, summary: BorrowSummary) {
+        let symbol = symbol.unwrap_or_default().to_string();
+        self.total.add(&summary);
+        self.by_symbol
+            .entry(symbol.clone())
+            .or_default()
+            .add(&summary);
+        if let Some(date) = date {
+            self.by_month
+                .entry(symbol)
+                .or_default()
+                .entry(date.with_day(1).unwrap_or(date))
+                .or_default()
+                .add(&summary);
+        }
+    }
+}
+
+/// Summarize borrow fees and securities lending per symbol and month
+///
+/// Each `HardToBorrowDetail` and `SLBFee` row covers one day. Amounts are
+/// converted with the row's `fxRateToBase`; value-years divide the day's
+/// value by the currency's 360- or 365-day interest year, the basis IB
+/// quotes borrow and loan rates on.
+pub fn borrow_report<'a, I>(statements: I) -> BorrowReport
+where
+    I: IntoIterator<Item = &'a ActivityFlexStatement>,
+{
+    let mut report = BorrowReport::default();
+    for statement in statements {
+        for row in statement.hard_to_borrow_details.iter() {
+            let fx = row.fx_rate_to_base.unwrap_or(Decimal::ONE);
+            let value = row
+                .value
+                .or_else(|| Some(row.quantity? * row.price?))
+                .unwrap_or_default();
+            let summary = BorrowSummary {
+                borrow_fees: row.borrow_fee.unwrap_or_default() * fx,
+                days_borrowed: 1,
+                borrowed_value_years: value_years(value * fx, row.currency.as_deref()),
+                ..Default::default()
+            };
+            report.add(row.symbol.as_deref(), row.date(), summary);
+        }
+        for row in statement.slb_fees.iter() {
+            let fx = row.fx_rate_to_base.unwrap_or(Decimal::ONE);
+            let fees = row.fee.unwrap_or_default() + row.carry_charge.unwrap_or_default();
+            let summary = BorrowSummary {
+                lending_fees: fees * fx,
+                days_lent: 1,
+                lent_value_years: value_years(
+                    row.collateral_amount.unwrap_or_default() * fx,
+                    row.currency.as_deref(),
+                ),
+                ..Default::default()
+            };
+            report.add(row.symbol.as_deref(), row.date(), summary);
+        }
+        for row in statement.slb_activities.iter() {
+            let summary = BorrowSummary {
+                loan_activities: 1,
+                ..Default::default()
+            };
+            report.add(row.symbol.as_deref(), row.date(), summary);
+        }
+    }
+    report
+}
+
+fn value_years(value: Decimal, currency: Option<&str>) -> Decimal {
+    value.abs() / money_market_year(currency.unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const XML: &str = r#"<FlexQueryResponse queryName="Test" type="AF">
+<FlexStatements count="1">
+<FlexStatement accountId="U1234567" fromDate="2025-01-01" toDate="2025-02-28" period="Custom" whenGenerated="2025-03-01;120000">
+<SLBActivities>
+<SLBActivity accountId="U1234567" symbol="AAPL" date="2025-01-02" activityType="ManagedLend" quantity="100" collateralAmount="18000" feeRate="0.5" />
+</SLBActivities>
+<SLBFees>
+<SLBFee accountId="U1234567" symbol="AAPL" valueDate="2025-01-02" quantity="100" collateralAmount="18000" feeRate="0.5" fee="0.25" currency="USD" fxRateToBase="1" />
+<SLBFee accountId="U1234567" symbol="AAPL" valueDate="2025-01-03" quantity="100" collateralAmount="18000" feeRate="0.5" fee="0.25" currency="USD" fxRateToBase="1" />
+</SLBFees>
+<HardToBorrowDetails>
+<HardToBorrowDetail accountId="U1234567" symbol="GME" valueDate="2025-01-31" quantity="-100" price="36" value="-3600" borrowFeeRate="10" borrowFee="-1.00" currency="USD" fxRateToBase="1" />
+<HardToBorrowDetail accountId="U1234567" symbol="GME" valueDate="2025-02-03" quantity="-100" price="36" borrowFeeRate="20" borrowFee="-2.00" currency="USD" fxRateToBase="1" />
+</HardToBorrowDetails>
+</FlexStatement>
+</FlexStatements>
+</FlexQueryResponse>"#;
+
+    fn day(month: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, month, d).unwrap()
+    }
+
+    #[test]
+    fn test_borrow_report() {
+        let statement = crate::parse_activity_flex(XML).unwrap();
+        let report = borrow_report([&statement]);
+
+        let gme = report.by_symbol["GME"];
+        assert_eq!(gme.borrow_fees, Decimal::from(-3));
+        assert_eq!(gme.days_borrowed, 2);
+        // 3.00 over two days of 3600 on a 360-day year
+        assert_eq!(gme.borrow_rate().unwrap().round_dp(6), Decimal::from(15));
+        assert_eq!(gme.lending_rate(), None);
+        assert_eq!(
+            report.by_month["GME"][&day(2, 1)].borrow_rate(),
+            Some(Decimal::from(20))
+        );
+
+        let aapl = report.by_symbol["AAPL"];
+        assert_eq!(aapl.lending_fees, Decimal::new(50, 2));
+        assert_eq!(aapl.lending_rate().unwrap().round_dp(6), Decimal::new(5, 1));
+        assert_eq!(aapl.loan_activities, 1);
+        assert_eq!(report.total.net(), Decimal::new(-250, 2));
+    }
+}
@@ -0,0 +1,369 @@
+//! Cash flow statements from the statement of funds
+//!
+//! [`cash_flow_statement`] sorts the `StmtFunds` lines of one or more
+//! statements into trading, dividend, interest, fee, and transfer flows per
+//! currency and period, and replays them as a running balance. Lines whose
+//! reported `balance` disagrees with the replay are listed as
+//! [`BalanceBreak`]s, which usually means a line is missing from the query.
+//!
+//! # Example
+//! ```
+//! use ib_flex::analytics::{cash_flow_statement, PnlPeriod};
+//! use ib_flex::parse_activity_flex;
+//!
+//! let xml = include_str!("../../tests/fixtures/activity_minimal.xml");
+//! let statement = parse_activity_flex(xml).unwrap();
+//!
+//! let report = cash_flow_statement([&statement], PnlPeriod::Month);
+//! for period in &report.periods {
+//!     println!(
+//!         "{} {}: dividends {} fees {}",
+//!         period.currency, period.start, period.flows.dividends, period.flows.fees
+//!     );
+//! }
+//! assert!(report.breaks.is_empty());
+//! ```
+
+use std::collections::BTreeMap;
+
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+#[cfg(feature = "serialize")]
+use serde::Serialize;
+
+use super::pnl::PnlPeriod;
+use super::reconcile::is_balance_line;
+use crate::types::traits::Dated;
+use crate::types::{ActivityFlexStatement, StatementOfFundsLine};
+
+/// Kind of cash movement, from a `StmtFunds` activity code
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub enum CashFlowCategory {
+    /// Buys, sells, exercises, assignments, and their commissions
+    Trading,
+    /// Dividends, payments in lieu, and withholding tax
+    Dividends,
+    /// Credit and debit interest
+    Interest,
+    /// Fees charged outside of trades (market data, advisor, other fees)
+    Fees,
+    /// Deposits, withdrawals, and internal transfers
+    Transfers,
+    /// Everything else, such as FX translation and adjustments
+    Other,
+}
+
+impl CashFlowCategory {
+    /// Category for an IB `activityCode`
+    pub fn from_activity_code(code: &str) -> Self {
+        match code.trim().to_ascii_uppercase().as_str() {
+            "BUY" | "SELL" | "ASSIGN" | "EXER" | "EXP" | "COMM" | "FUTVAR" | "CORP" => {
+                CashFlowCategory::Trading
+            }
+            "DIV" | "PIL" | "FRTAX" | "WHT" | "DIVACC" => CashFlowCategory::Dividends,
+            "CINT" | "DINT" | "INTR" | "INTP" | "BINT" | "SINT" | "BOINT" => {
+                CashFlowCategory::Interest
+            }
+            "OFEE" | "FEE" | "ADVFEE" | "CFEE" | "SLBFEE" | "MDF" => CashFlowCategory::Fees,
+            "DEP" | "WITH" | "XFER" | "INTCASH" | "ICT" => CashFlowCategory::Transfers,
+            _ => CashFlowCategory::Other,
+        }
+    }
+}
+
+/// Cash flows by category, in the currency of the lines
+///
+/// Amounts keep IB's signs, so money leaving the account is negative.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct CashFlowSummary {
+    /// Trading flows
+    pub trading: Decimal,
+    /// Dividend flows, net of withholding
+    pub dividends: Decimal,
+    /// Interest flows
+    pub interest: Decimal,
+    /// Fees outside of trades
+    pub fees: Decimal,
+    /// Deposits, withdrawals, and transfers
+    pub transfers: Decimal,
+    /// Uncategorized flows
+    pub other: Decimal,
+}
+
+impl CashFlowSummary {
+    /// Net change in cash
+    pub fn net(&self) -> Decimal {
+        self.trading + self.dividends + self.interest + self.fees + self.transfers + self.other
+    }
+
+    fn add(&mut self, category: CashFlowCategory, amount: Decimal) {
+        let bucket = match category {
+            CashFlowCategory::Trading => &mut self.trading,
+            CashFlowCategory::Dividends => &mut self.dividends,
+            CashFlowCategory::Interest => &mut self.interest,
+            CashFlowCategory::Fees => &mut self.fees,
+            CashFlowCategory::Transfers => &mut self.transfers,
+            CashFlowCategory::Other => &mut self.other,
+        };
+        *bucket += amount;
+    }
+}
+
+/// Cash flows in one currency over one period
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct CashFlowPeriod {
+    /// Currency of the lines
+    pub currency: String,
+    /// First day of the period
+    pub start: NaiveDate,
+    /// Balance before the period's first line, when known
+    pub opening_balance: Option<Decimal>,
+    /// Flows by category
+    pub flows: CashFlowSummary,
+    /// Running balance after the period's last line
+    pub closing_balance: Option<Decimal>,
+    /// `balance` IB reports on the period's last line
+    pub reported_balance: Option<Decimal>,
+}
+
+impl CashFlowPeriod {
+    /// `reported_balance - closing_balance`, when both are known
+    pub fn difference(&self) -> Option<Decimal> {
+        Some(self.reported_balance? - self.closing_balance?)
+    }
+}
+
+/// A line whose reported balance disagrees with the running balance
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct BalanceBreak {
+    /// Currency of the line
+    pub currency: String,
+    /// Date of the line
+    pub date: Option<NaiveDate>,
+    /// Activity description of the line
+    pub description: String,
+    /// Running balance after the line
+    pub computed: Decimal,
+    /// `balance` IB reports on the line
+    pub reported: Decimal,
+}
+
+impl BalanceBreak {
+    /// `reported - computed`
+    pub fn difference(&self) -> Decimal {
+        self.reported - self.computed
+    }
+}
+
+/// Result of [`cash_flow_statement`]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct CashFlowStatement {
+    /// Periods per currency, sorted by currency then date
+    pub periods: Vec<CashFlowPeriod>,
+    /// Lines where the running balance broke, in statement order
+    pub breaks: Vec<BalanceBreak>,
+}
+
+impl CashFlowStatement {
+    /// Flows per currency across all periods
+    pub fn totals(&self) -> BTreeMap<String, CashFlowSummary> {
+        let mut totals: BTreeMap<String, CashFlowSummary> = BTreeMap::new();
+        for period in &self.periods {
+            let total = totals.entry(period.currency.clone()).or_default();
+            let flows = &period.flows;
+            total.trading += flows.trading;
+            total.dividends += flows.dividends;
+            total.interest += flows.interest;
+            total.fees += flows.fees;
+            total.transfers += flows.transfers;
+            total.other += flows.other;
+        }
+        totals
+    }
+}
+
+/// Categorized cash flows from `StmtFunds`, per currency and period
+///
+/// Only currency-level lines are used; `BaseCurrency` lines repeat them
+/// converted to the base currency. The running balance starts from each
+/// statement's `Starting Balance` line (or the first line's balance less its
+/// amount) and, after a break, continues from the reported balance so one
+/// missing line is reported once. Pass statements in date order without
+/// overlaps; merge overlapping downloads with
+/// [`merge_statements`](crate::merge_statements) first.
+pub fn cash_flow_statement<'a, I>(statements: I, period: PnlPeriod) -> CashFlowStatement
+where
+    I: IntoIterator<Item = &'a ActivityFlexStatement>,
+{
+    let tolerance = Decimal::new(1, 2);
+    let mut report = CashFlowStatement::default();
+    let mut periods: BTreeMap<(String, NaiveDate), CashFlowPeriod> = BTreeMap::new();
+    let mut running: BTreeMap<String, Decimal> = BTreeMap::new();
+
+    for statement in statements {
+        let lines = statement.statement_of_funds.iter().filter(|line| {
+            !line
+                .level_of_detail
+                .as_deref()
+                .is_some_and(|level| level.eq_ignore_ascii_case("BaseCurrency"))
+        });
+        for line in lines {
+            let (Some(currency), Some(date)) = (line.currency.as_deref(), line.date()) else {
+                continue;
+            };
+            if currency == "BASE_SUMMARY" {
+                continue;
+            }
+            let amount = line.amount.unwrap_or_default();
+            let entry = periods
+                .entry((currency.to_string(), period.start(date)))
+                .or_insert_with(|| CashFlowPeriod {
+                    currency: currency.to_string(),
+                    start: period.start(date),
+                    opening_balance: running.get(currency).copied(),
+                    flows: CashFlowSummary::default(),
+                    closing_balance: None,
+                    reported_balance: None,
+                });
+
+            if is_balance_line(line) {
+                if let Some(balance) = line.balance.or(line.amount) {
+                    if is_starting_balance(line) {
+                        running.insert(currency.to_string(), balance);
+                        entry.opening_balance.get_or_insert(balance);
+                    }
+                    entry.reported_balance = Some(balance);
+                    entry.closing_balance = running.get(currency).copied();
+                }
+                continue;
+            }
+
+            let category = line.activity_code.as_deref().map_or(
+                CashFlowCategory::Other,
+                CashFlowCategory::from_activity_code,
+            );
+            entry.flows.add(category, amount);
+
+            let mut balance = match (running.get(currency), line.balance) {
+                (Some(previous), _) => previous + amount,
+                (None, Some(reported)) => {
+                    entry.opening_balance.get_or_insert(reported - amount);
+                    reported
+                }
+                (None, None) => continue,
+            };
+            if let Some(reported) = line.balance {
+                if (reported - balance).abs() > tolerance {
+                    report.breaks.push(BalanceBreak {
+                        currency: currency.to_string(),
+                        date: Some(date),
+                        description: line
+                            .activity_description
+                            .clone()
+                            .or_else(|| line.activity_code.clone())
+                            .unwrap_or_default(),
+                        computed: balance,
+                        reported,
+                    });
+                    balance = reported;
+                }
+                entry.reported_balance = Some(reported);
+            }
+            running.insert(currency.to_string(), balance);
+            entry.closing_balance = Some(balance);
+        }
+    }
+    report.periods = periods.into_values().collect();
+    report
+}
+
+fn is_starting_balance(line: &StatementOfFundsLine) -> bool {
+    line.activity_description
+        .as_deref()
+        .is_some_and(|d| d.eq_ignore_ascii_case("Starting Balance"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const XML: &str = r#"<FlexQueryResponse queryName="Test" type="AF">
+<FlexStatements count="1">
+<FlexStatement accountId="U1234567" fromDate="2025-01-01" toDate="2025-02-28" period="Custom" whenGenerated="2025-03-01;120000">
+<StmtFunds>
+<StatementOfFundsLine accountId="U1234567" currency="USD" date="2025-01-01" activityDescription="Starting Balance" amount="1000" balance="1000" levelOfDetail="Currency" />
+<StatementOfFundsLine accountId="U1234567" currency="USD" date="2025-01-10" activityCode="DEP" activityDescription="Cash Transfer" amount="5000" balance="6000" levelOfDetail="Currency" />
+<StatementOfFundsLine accountId="U1234567" currency="USD" date="2025-01-15" activityCode="BUY" activityDescription="Buy 10 AAPL" amount="-1851" balance="4149" levelOfDetail="Currency" />
+<StatementOfFundsLine accountId="U1234567" currency="USD" date="2025-01-15" activityCode="BUY" activityDescription="Buy 10 AAPL" amount="-1851" balance="4149" levelOfDetail="BaseCurrency" />
+<StatementOfFundsLine accountId="U1234567" currency="USD" date="2025-02-14" activityCode="DIV" activityDescription="AAPL Cash Dividend" amount="2.50" balance="4151.50" levelOfDetail="Currency" />
+<StatementOfFundsLine accountId="U1234567" currency="USD" date="2025-02-14" activityCode="FRTAX" activityDescription="AAPL Withholding" amount="-0.38" balance="4151.12" levelOfDetail="Currency" />
+<StatementOfFundsLine accountId="U1234567" currency="USD" date="2025-02-28" activityCode="OFEE" activityDescription="Market data" amount="-10" balance="4141.12" levelOfDetail="Currency" />
+<StatementOfFundsLine accountId="U1234567" currency="USD" date="2025-02-28" activityCode="CINT" activityDescription="Interest" amount="3" balance="4144.12" levelOfDetail="Currency" />
+<StatementOfFundsLine accountId="U1234567" currency="EUR" date="2025-02-03" activityCode="DINT" activityDescription="Debit interest" amount="-1" balance="-1" levelOfDetail="Currency" />
+</StmtFunds>
+</FlexStatement>
+</FlexStatements>
+</FlexQueryResponse>"#;
+
+    fn day(month: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, month, d).unwrap()
+    }
+
+    #[test]
+    fn test_categorized_periods() {
+        let statement = crate::parse_activity_flex(XML).unwrap();
+        let report = cash_flow_statement([&statement], PnlPeriod::Month);
+
+        let keys: Vec<_> = report
+            .periods
+            .iter()
+            .map(|p| (p.currency.as_str(), p.start))
+            .collect();
+        assert_eq!(
+            keys,
+            [("EUR", day(2, 1)), ("USD", day(1, 1)), ("USD", day(2, 1))]
+        );
+
+        let january = &report.periods[1];
+        assert_eq!(january.opening_balance, Some(Decimal::from(1000)));
+        assert_eq!(january.flows.transfers, Decimal::from(5000));
+        assert_eq!(january.flows.trading, Decimal::from(-1851));
+        assert_eq!(january.closing_balance, Some(Decimal::from(4149)));
+
+        let february = &report.periods[2];
+        assert_eq!(february.opening_balance, Some(Decimal::from(4149)));
+        assert_eq!(february.flows.dividends, Decimal::new(212, 2));
+        assert_eq!(february.flows.fees, Decimal::from(-10));
+        assert_eq!(february.flows.interest, Decimal::from(3));
+        assert_eq!(february.difference(), Some(Decimal::ZERO));
+
+        assert_eq!(report.periods[0].opening_balance, Some(Decimal::ZERO));
+        assert_eq!(report.totals()["USD"].net(), Decimal::new(314412, 2));
+        assert!(report.breaks.is_empty());
+    }
+
+    #[test]
+    fn test_missing_line_breaks_balance() {
+        let xml = XML.replace(
+            r#"<StatementOfFundsLine accountId="U1234567" currency="USD" date="2025-02-14" activityCode="DIV" activityDescription="AAPL Cash Dividend" amount="2.50" balance="4151.50" levelOfDetail="Currency" />"#,
+            "",
+        );
+        let statement = crate::parse_activity_flex(&xml).unwrap();
+        let report = cash_flow_statement([&statement], PnlPeriod::Month);
+
+        // Only the line after the gap breaks; the replay resyncs from there
+        assert_eq!(report.breaks.len(), 1);
+        assert_eq!(report.breaks[0].description, "AAPL Withholding");
+        assert_eq!(report.breaks[0].difference(), Decimal::new(250, 2));
+        assert_eq!(
+            report.periods[2].closing_balance,
+            Some(Decimal::new(414412, 2))
+        );
+    }
+}
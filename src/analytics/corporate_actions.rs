@@ -0,0 +1,622 @@
+//! Corporate action processing for lot ledgers
+//!
+//! IB reports one corporate action as one or more `CorporateAction` rows,
+//! one per affected conid, sharing an `actionID`: a forward split is a
+//! single row adding shares, while a merger is a row removing the old
+//! shares and another adding the new ones. [`CorporateActionEngine`] applies
+//! each action as a whole to a [`LotLedger`], carrying basis and holding
+//! periods over to new conids, and records a [`CorporateActionAdjustment`]
+//! for every conid it touched.
+//!
+//! # Example
+//! ```
+//! use ib_flex::analytics::{CorporateActionEngine, LotLedger};
+//! use ib_flex::parse_activity_flex;
+//!
+//! let xml = include_str!("../../tests/fixtures/activity_corporate_actions.xml");
+//! let statement = parse_activity_flex(xml).unwrap();
+//!
+//! let mut ledger = LotLedger::new();
+//! ledger.add_positions(&statement.positions);
+//! let mut engine = CorporateActionEngine::new();
+//! engine.apply_all(&mut ledger, &statement.corporate_actions);
+//! for adjustment in engine.adjustments() {
+//!     println!(
+//!         "{:?} {}: {} -> {}",
+//!         adjustment.kind, adjustment.symbol, adjustment.quantity_before, adjustment.quantity_after
+//!     );
+//! }
+//! ```
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+#[cfg(feature = "serialize")]
+use serde::Serialize;
+
+use super::lots::{Lot, LotClosure, LotLedger};
+use crate::types::traits::Dated;
+use crate::types::{Conid, CorporateAction};
+
+/// Type of corporate action, from IB's `type` code
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub enum CorporateActionKind {
+    /// Forward split (`FS`, `FA`)
+    ForwardSplit,
+    /// Reverse split (`RS`)
+    ReverseSplit,
+    /// Stock dividend (`SD`)
+    StockDividend,
+    /// Merger or acquisition (`TC`)
+    Merger,
+    /// Spinoff (`SO`)
+    Spinoff,
+    /// Symbol or CUSIP change (`IC`)
+    SymbolChange,
+    /// Tender offer (`TO`)
+    TenderOffer,
+    /// Delisting (`DW`)
+    Delisting,
+    /// Any other code (rights issues, bond maturities, ...)
+    Other(String),
+}
+
+impl CorporateActionKind {
+    /// Classify an IB corporate action `type` code
+    pub fn from_code(code: &str) -> Self {
+        match code.trim() {
+            "FS" | "FA" => CorporateActionKind::ForwardSplit,
+            "RS" => CorporateActionKind::ReverseSplit,
+            "SD" => CorporateActionKind::StockDividend,
+            "TC" => CorporateActionKind::Merger,
+            "SO" => CorporateActionKind::Spinoff,
+            "IC" => CorporateActionKind::SymbolChange,
+            "TO" => CorporateActionKind::TenderOffer,
+            "DW" => CorporateActionKind::Delisting,
+            other => CorporateActionKind::Other(other.to_string()),
+        }
+    }
+
+    /// Classify a corporate action row
+    pub fn of(action: &CorporateAction) -> Self {
+        Self::from_code(action.action_type.as_deref().unwrap_or_default())
+    }
+}
+
+/// What one corporate action did to one conid in the ledger
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct CorporateActionAdjustment {
+    /// IB action ID shared by the rows of the action, if reported
+    pub action_id: Option<String>,
+    /// Type of action
+    pub kind: CorporateActionKind,
+    /// Effective date of the action
+    pub date: NaiveDate,
+    /// Description of the first row of the action
+    pub description: Option<String>,
+    /// Conid the adjustment applies to
+    pub conid: Conid,
+    /// Symbol of `conid`
+    pub symbol: String,
+    /// Conid the lots were moved to, if they changed conid
+    pub new_conid: Option<Conid>,
+    /// Symbol of `new_conid`
+    pub new_symbol: Option<String>,
+    /// Open quantity before the action
+    pub quantity_before: Decimal,
+    /// Open quantity after the action (under `new_conid` if moved)
+    pub quantity_after: Decimal,
+    /// Cost basis before the action
+    pub basis_before: Decimal,
+    /// Cost basis after the action (under `new_conid` if moved)
+    pub basis_after: Decimal,
+    /// Cash received or paid by the action rows for this conid
+    pub cash: Decimal,
+    /// P&L realized by lots closed out for cash
+    pub realized_pnl: Decimal,
+    /// Number of lots adjusted
+    pub lots: usize,
+}
+
+/// Applies corporate actions to a [`LotLedger`] and keeps an audit trail
+///
+/// How each action is applied depends on the conids its rows touch, so
+/// unrecognized action types are still handled sensibly:
+///
+/// - A conid that stays held (splits, stock dividends) keeps its lots, with
+///   quantities scaled to the new total and the basis unchanged.
+/// - A conid closed out while other conids are added (mergers, symbol
+///   changes, reverse splits that change conid) moves its lots to the added
+///   conids, keeping open dates and basis. Cash paid alongside the new
+///   shares is recorded in the adjustment but not realized.
+/// - A conid closed out with nothing added (cash mergers, tender offers,
+///   delistings) is realized against the rows' proceeds.
+/// - A conid added with nothing closed out opens a lot at the rows' `cost`.
+///   For spinoffs registered with
+///   [`with_spinoff_allocation`](Self::with_spinoff_allocation), the new
+///   lots instead take their share of the parent's basis and open dates.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CorporateActionEngine {
+    spinoff_allocations: HashMap<Conid, (Conid, Decimal)>,
+    mappings: BTreeMap<Conid, (Conid, String)>,
+    adjustments: Vec<CorporateActionAdjustment>,
+}
+
+impl CorporateActionEngine {
+    /// Engine with no spinoff allocations
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Move `fraction` of `parent`'s basis to `child` when `child` is spun off
+    ///
+    /// IB does not report the basis allocation of a spinoff; issuers publish
+    /// it (e.g. on Form 8937).
+    pub fn with_spinoff_allocation(
+        mut self,
+        parent: impl Into<Conid>,
+        child: impl Into<Conid>,
+        fraction: Decimal,
+    ) -> Self {
+        self.spinoff_allocations
+            .insert(child.into(), (parent.into(), fraction));
+        self
+    }
+
+    /// Apply every action in `actions`, grouped by action and in date order
+    pub fn apply_all<'a, I>(&mut self, ledger: &mut LotLedger, actions: I)
+    where
+        I: IntoIterator<Item = &'a CorporateAction>,
+    {
+        for group in group_actions(actions) {
+            self.apply_action(ledger, &group);
+        }
+    }
+
+    /// Apply the rows of a single corporate action
+    pub fn apply_action(&mut self, ledger: &mut LotLedger, rows: &[&CorporateAction]) {
+        let Some(first) = rows.first() else {
+            return;
+        };
+        let date = action_date(first);
+        let kind = CorporateActionKind::of(first);
+        let adjustment = |conid: Conid, symbol: &str, quantity, basis| CorporateActionAdjustment {
+            action_id: first.action_id.clone(),
+            kind: kind.clone(),
+            date,
+            description: first.description.clone(),
+            conid,
+            symbol: symbol.to_string(),
+            new_conid: None,
+            new_symbol: None,
+            quantity_before: quantity,
+            quantity_after: quantity,
+            basis_before: basis,
+            basis_after: basis,
+            cash: Decimal::ZERO,
+            realized_pnl: Decimal::ZERO,
+            lots: 0,
+        };
+
+        // Net change per conid, in row order
+        let mut changes: Vec<Change<'_>> = Vec::new();
+        for row in rows {
+            let delta = row.quantity.unwrap_or_default();
+            let cash = row.proceeds.or(row.amount).unwrap_or_default();
+            match changes.iter_mut().find(|c| c.row.conid == row.conid) {
+                Some(change) => {
+                    change.delta += delta;
+                    change.cash += cash;
+                    change.cost += row.cost.unwrap_or_default();
+                }
+                None => changes.push(Change {
+                    row,
+                    delta,
+                    cash,
+                    cost: row.cost.unwrap_or_default(),
+                    before: ledger.quantity(row.conid),
+                    basis: ledger.lots(row.conid).map(|lot| lot.cost_basis).sum(),
+                }),
+            }
+        }
+        let closed: Vec<&Change<'_>> = changes.iter().filter(|c| c.closes_out()).collect();
+        let added: Vec<&Change<'_>> = changes.iter().filter(|c| c.opens()).collect();
+
+        for change in changes.iter().filter(|c| c.scales()) {
+            let after = change.before + change.delta;
+            let factor = after / change.before;
+            let mut record = adjustment(
+                change.row.conid,
+                &change.row.symbol,
+                change.before,
+                change.basis,
+            );
+            for lot in ledger.lots_mut(change.row.conid) {
+                lot.quantity *= factor;
+                record.lots += 1;
+            }
+            record.quantity_after = after;
+            record.cash = change.cash;
+            self.adjustments.push(record);
+        }
+
+        let added_total: Decimal = added.iter().map(|c| c.delta).sum();
+        for old in &closed {
+            let lots = ledger.take_lots(old.row.conid);
+            let mut record = adjustment(old.row.conid, &old.row.symbol, old.before, old.basis);
+            record.lots = lots.len();
+            record.cash = old.cash;
+            record.quantity_after = Decimal::ZERO;
+            record.basis_after = Decimal::ZERO;
+
+            if added.is_empty() || added_total.is_zero() {
+                record.realized_pnl = realize(ledger, &lots, old, date);
+                self.adjustments.push(record);
+                continue;
+            }
+            // Share of the old lots going to each new conid. With several
+            // closed conids (rare), each is moved in proportion to its size.
+            let old_share = old.before / closed.iter().map(|c| c.before).sum::<Decimal>();
+            for new in &added {
+                let quantity_share = new.delta * old_share / old.before;
+                let basis_share = new.delta / added_total;
+                for lot in &lots {
+                    ledger.push_lot(Lot {
+                        conid: new.row.conid,
+                        symbol: new.row.symbol.clone(),
+                        currency: lot.currency.clone(),
+                        trade_id: lot.trade_id.clone(),
+                        open_date: lot.open_date,
+                        quantity: lot.quantity * quantity_share,
+                        cost_basis: lot.cost_basis * basis_share,
+                        basis_adjustment: lot.basis_adjustment * basis_share,
+                    });
+                }
+            }
+            let primary = added[0];
+            self.mappings.insert(
+                old.row.conid,
+                (primary.row.conid, primary.row.symbol.clone()),
+            );
+            record.new_conid = Some(primary.row.conid);
+            record.new_symbol = Some(primary.row.symbol.clone());
+            record.quantity_after = ledger.quantity(primary.row.conid);
+            record.basis_after = ledger
+                .lots(primary.row.conid)
+                .map(|lot| lot.cost_basis)
+                .sum();
+            self.adjustments.push(record);
+        }
+
+        // Rows that would flip a position's sign are left alone: nothing
+        // sensible can be inferred from them
+        if closed.is_empty() {
+            for new in &added {
+                let record =
+                    adjustment(new.row.conid, &new.row.symbol, Decimal::ZERO, Decimal::ZERO);
+                let record = self.open(ledger, new, date, record);
+                self.adjustments.push(record);
+            }
+        }
+    }
+
+    /// Adjustments made so far, in the order they were applied
+    pub fn adjustments(&self) -> &[CorporateActionAdjustment] {
+        &self.adjustments
+    }
+
+    /// Conid and symbol lots of `conid` were last moved to, if any
+    pub fn mapping(&self, conid: Conid) -> Option<(Conid, &str)> {
+        self.mappings
+            .get(&conid)
+            .map(|(conid, symbol)| (*conid, symbol.as_str()))
+    }
+
+    /// Follow conid changes from `conid` to the conid it is held under now
+    pub fn resolve(&self, conid: Conid) -> Conid {
+        let mut current = conid;
+        // Bounded in case of a cycle in malformed data
+        for _ in 0..=self.mappings.len() {
+            match self.mappings.get(&current) {
+                Some((next, _)) if *next != current => current = *next,
+                _ => break,
+            }
+        }
+        current
+    }
+
+    /// Open lots for a conid added by an action that closes nothing out
+    fn open(
+        &mut self,
+        ledger: &mut LotLedger,
+        new: &Change<'_>,
+        date: NaiveDate,
+        mut record: CorporateActionAdjustment,
+    ) -> CorporateActionAdjustment {
+        let row = new.row;
+        let allocation = self.spinoff_allocations.get(&row.conid).copied();
+        match allocation {
+            Some((parent, fraction)) if ledger.quantity(parent) != Decimal::ZERO => {
+                let parent_quantity = ledger.quantity(parent);
+                let mut children = Vec::new();
+                for lot in ledger.lots_mut(parent) {
+                    let basis = lot.cost_basis * fraction;
+                    let adjustment = lot.basis_adjustment * fraction;
+                    lot.cost_basis -= basis;
+                    lot.basis_adjustment -= adjustment;
+                    children.push(Lot {
+                        conid: row.conid,
+                        symbol: row.symbol.clone(),
+                        currency: lot.currency.clone(),
+                        trade_id: lot.trade_id.clone(),
+                        open_date: lot.open_date,
+                        quantity: new.delta * lot.quantity / parent_quantity,
+                        cost_basis: basis,
+                        basis_adjustment: adjustment,
+                    });
+                }
+                record.lots = children.len();
+                for child in children {
+                    ledger.push_lot(child);
+                }
+            }
+            _ => {
+                ledger.push_lot(Lot {
+                    conid: row.conid,
+                    symbol: row.symbol.clone(),
+                    currency: row.currency.clone().unwrap_or_default(),
+                    trade_id: None,
+                    open_date: date,
+                    quantity: new.delta,
+                    cost_basis: new.cost,
+                    basis_adjustment: Decimal::ZERO,
+                });
+                record.lots = 1;
+            }
+        }
+        record.quantity_after = ledger.quantity(row.conid);
+        record.basis_after = ledger.lots(row.conid).map(|lot| lot.cost_basis).sum();
+        record.cash = new.cash;
+        record
+    }
+}
+
+/// Group corporate action rows into actions, ordered by date
+///
+/// Rows sharing an `actionID` form one action; rows without one are grouped
+/// by date and description.
+pub fn group_actions<'a, I>(actions: I) -> Vec<Vec<&'a CorporateAction>>
+where
+    I: IntoIterator<Item = &'a CorporateAction>,
+{
+    let mut groups: Vec<Vec<&CorporateAction>> = Vec::new();
+    for action in actions {
+        let key = group_key(action);
+        match groups.iter_mut().find(|g| group_key(g[0]) == key) {
+            Some(group) => group.push(action),
+            None => groups.push(vec![action]),
+        }
+    }
+    // Stable, so same-day actions keep their statement order
+    groups.sort_by_key(|g| action_date(g[0]));
+    groups
+}
+
+fn group_key(action: &CorporateAction) -> (Option<&str>, NaiveDate, Option<&str>) {
+    match action.action_id.as_deref().filter(|id| !id.is_empty()) {
+        Some(id) => (Some(id), NaiveDate::MIN, None),
+        None => (None, action_date(action), action.description.as_deref()),
+    }
+}
+
+fn action_date(action: &CorporateAction) -> NaiveDate {
+    action.date().unwrap_or(action.report_date)
+}
+
+/// Net effect of an action's rows on one conid
+struct Change<'a> {
+    row: &'a CorporateAction,
+    delta: Decimal,
+    cash: Decimal,
+    cost: Decimal,
+    before: Decimal,
+    basis: Decimal,
+}
+
+impl Change<'_> {
+    fn after(&self) -> Decimal {
+        self.before + self.delta
+    }
+
+    fn closes_out(&self) -> bool {
+        !self.before.is_zero() && self.after().is_zero()
+    }
+
+    fn opens(&self) -> bool {
+        self.before.is_zero() && !self.delta.is_zero()
+    }
+
+    fn scales(&self) -> bool {
+        !self.before.is_zero()
+            && !self.delta.is_zero()
+            && !self.after().is_zero()
+            && self.after().is_sign_negative() == self.before.is_sign_negative()
+    }
+}
+
+/// Close `lots` for the cash paid by `change`, returning the realized P&L
+fn realize(
+    ledger: &mut LotLedger,
+    lots: &VecDeque<Lot>,
+    change: &Change<'_>,
+    date: NaiveDate,
+) -> Decimal {
+    let mut pnl = Decimal::ZERO;
+    for lot in lots {
+        let cash = change.cash * lot.quantity / change.before;
+        let (proceeds, cost_basis) = if lot.is_long() {
+            (cash, lot.cost_basis)
+        } else {
+            (-lot.cost_basis, -cash)
+        };
+        pnl += proceeds - cost_basis;
+        ledger.push_closure(LotClosure {
+            conid: lot.conid,
+            symbol: lot.symbol.clone(),
+            currency: lot.currency.clone(),
+            opening_trade_id: lot.trade_id.clone(),
+            closing_trade_id: None,
+            open_date: lot.open_date,
+            close_date: date,
+            quantity: lot.quantity,
+            proceeds,
+            cost_basis,
+            realized_pnl: proceeds - cost_basis,
+            basis_adjustment: lot.basis_adjustment,
+            disallowed_loss: Decimal::ZERO,
+        });
+    }
+    pnl
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_activity_flex;
+    use crate::types::PositionBuilder;
+
+    const XML: &str = r#"<FlexQueryResponse queryName="Test" type="AF">
+<FlexStatements count="1">
+<FlexStatement accountId="U1234567" fromDate="2025-03-01" toDate="2025-03-31" period="Monthly" whenGenerated="2025-04-01;120000">
+<CorporateActions>
+<CorporateAction accountId="U1234567" currency="USD" assetCategory="STK" symbol="OLD" description="OLD MERGED WITH NEW 1 FOR 2" conid="1" reportDate="2025-03-10" dateTime="2025-03-10;000000" quantity="-100" type="TC" actionID="A1" />
+<CorporateAction accountId="U1234567" currency="USD" assetCategory="STK" symbol="NEW" description="OLD MERGED WITH NEW 1 FOR 2" conid="2" reportDate="2025-03-10" dateTime="2025-03-10;000000" quantity="50" type="TC" actionID="A1" />
+<CorporateAction accountId="U1234567" currency="USD" assetCategory="STK" symbol="CASHCO" description="CASHCO ACQUIRED FOR USD 60" conid="3" reportDate="2025-03-12" dateTime="2025-03-12;000000" quantity="-40" proceeds="2400" type="TC" actionID="A2" />
+<CorporateAction accountId="U1234567" currency="USD" assetCategory="STK" symbol="SPIN" description="PARENT SPINOFF 1 FOR 5" conid="5" reportDate="2025-03-14" dateTime="2025-03-14;000000" quantity="20" type="SO" actionID="A3" />
+<CorporateAction accountId="U1234567" currency="USD" assetCategory="STK" symbol="PARENT" description="PARENT 2 FOR 1 SPLIT" conid="4" reportDate="2025-03-20" dateTime="2025-03-20;000000" quantity="100" type="FS" actionID="A4" />
+</CorporateActions>
+</FlexStatement>
+</FlexStatements>
+</FlexQueryResponse>"#;
+
+    fn day(month: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, month, d).unwrap()
+    }
+
+    fn ledger() -> LotLedger {
+        let position = |symbol: &str, conid: u64, quantity: i64, basis: i64| {
+            PositionBuilder::new(symbol)
+                .conid(conid)
+                .quantity(quantity)
+                .report_date(day(1, 2))
+                .cost_basis_money(basis)
+                .build()
+        };
+        let mut ledger = LotLedger::new();
+        ledger.add_positions(&[
+            position("OLD", 1, 100, 5000),
+            position("CASHCO", 3, 40, 2000),
+            position("PARENT", 4, 100, 10000),
+        ]);
+        ledger
+    }
+
+    fn apply() -> (LotLedger, CorporateActionEngine) {
+        let statement = parse_activity_flex(XML).unwrap();
+        let mut ledger = ledger();
+        let mut engine =
+            CorporateActionEngine::new().with_spinoff_allocation(4, 5, Decimal::new(2, 1));
+        engine.apply_all(&mut ledger, &statement.corporate_actions);
+        (ledger, engine)
+    }
+
+    #[test]
+    fn test_group_actions_by_action_id() {
+        let statement = parse_activity_flex(XML).unwrap();
+        let groups = group_actions(&statement.corporate_actions);
+        let sizes: Vec<usize> = groups.iter().map(Vec::len).collect();
+        assert_eq!(sizes, vec![2, 1, 1, 1]);
+        assert_eq!(
+            CorporateActionKind::of(groups[0][0]),
+            CorporateActionKind::Merger
+        );
+    }
+
+    #[test]
+    fn test_merger_moves_lots_to_new_conid() {
+        let (ledger, engine) = apply();
+        assert_eq!(ledger.quantity(Conid::from(1)), Decimal::ZERO);
+        let lots: Vec<&Lot> = ledger.lots(Conid::from(2)).collect();
+        assert_eq!(lots.len(), 1);
+        assert_eq!(lots[0].quantity, Decimal::from(50));
+        assert_eq!(lots[0].cost_basis, Decimal::from(5000));
+        assert_eq!(lots[0].open_date, day(1, 2));
+        assert_eq!(engine.resolve(Conid::from(1)), Conid::from(2));
+        assert_eq!(
+            engine.mapping(Conid::from(1)),
+            Some((Conid::from(2), "NEW"))
+        );
+
+        let merger = &engine.adjustments()[0];
+        assert_eq!(merger.new_symbol.as_deref(), Some("NEW"));
+        assert_eq!(merger.quantity_before, Decimal::from(100));
+        assert_eq!(merger.quantity_after, Decimal::from(50));
+        assert_eq!(merger.basis_after, Decimal::from(5000));
+    }
+
+    #[test]
+    fn test_cash_merger_realizes_lots() {
+        let (ledger, engine) = apply();
+        assert_eq!(ledger.quantity(Conid::from(3)), Decimal::ZERO);
+        let closure = &ledger.closures()[0];
+        assert_eq!(closure.close_date, day(3, 12));
+        assert_eq!(closure.proceeds, Decimal::from(2400));
+        assert_eq!(closure.realized_pnl, Decimal::from(400));
+        assert!(closure.closing_trade_id.is_none());
+        assert_eq!(engine.adjustments()[1].realized_pnl, Decimal::from(400));
+    }
+
+    #[test]
+    fn test_spinoff_allocates_basis_then_split_scales() {
+        let (ledger, engine) = apply();
+        let child: Vec<&Lot> = ledger.lots(Conid::from(5)).collect();
+        assert_eq!(child[0].quantity, Decimal::from(20));
+        assert_eq!(child[0].cost_basis, Decimal::from(2000));
+        assert_eq!(child[0].open_date, day(1, 2));
+
+        // The split doubles the parent's shares and keeps its remaining basis
+        let parent: Vec<&Lot> = ledger.lots(Conid::from(4)).collect();
+        assert_eq!(parent[0].quantity, Decimal::from(200));
+        assert_eq!(parent[0].cost_basis, Decimal::from(8000));
+
+        let kinds: Vec<&CorporateActionKind> =
+            engine.adjustments().iter().map(|a| &a.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                &CorporateActionKind::Merger,
+                &CorporateActionKind::Merger,
+                &CorporateActionKind::Spinoff,
+                &CorporateActionKind::ForwardSplit,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unallocated_spinoff_opens_lot_at_cost() {
+        let statement = parse_activity_flex(XML).unwrap();
+        let mut ledger = ledger();
+        let mut engine = CorporateActionEngine::new();
+        engine.apply_all(&mut ledger, &statement.corporate_actions);
+        assert_eq!(ledger.quantity(Conid::from(5)), Decimal::from(20));
+        assert_eq!(
+            ledger.lots(Conid::from(5)).next().unwrap().cost_basis,
+            Decimal::ZERO
+        );
+        assert_eq!(ledger.quantity(Conid::from(4)), Decimal::from(200));
+    }
+}
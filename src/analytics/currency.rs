@@ -0,0 +1,409 @@
+//! Currency conversion across statements
+//!
+//! [`CurrencyConverter`] merges the `ConversionRates` sections of several
+//! statements into one [`FxRateTable`] and reports a
+//! [`ParseError::MissingFxRate`] instead of `None` when a conversion is
+//! impossible, so a gap in the rates fails loudly rather than silently
+//! dropping an amount.
+//!
+//! Analytics report in each statement's base currency: the one named in
+//! `AccountInformation`, found by [`account_base_currency`]. To report
+//! accounts with different bases together, [`rebase`] restates their
+//! statements in one chosen currency first, after which every analytics
+//! module reports in it.
+//!
+//! # Example
+//! ```
+//! use chrono::NaiveDate;
+//! use ib_flex::analytics::CurrencyConverter;
+//! use ib_flex::parse_activity_flex;
+//! use rust_decimal::Decimal;
+//!
+//! let xml = include_str!("../../tests/fixtures/activity_minimal.xml");
+//! let statement = parse_activity_flex(xml).unwrap();
+//!
+//! let converter = CurrencyConverter::from_statements([&statement]).with_base_currency("USD");
+//! let date = NaiveDate::from_ymd_opt(2025, 1, 15).unwrap();
+//! assert_eq!(converter.to_base(Decimal::ONE, "USD", date).unwrap(), Decimal::ONE);
+//! assert!(converter.to_base(Decimal::ONE, "EUR", date).is_err());
+//! ```
+
+use std::collections::BTreeSet;
+
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+
+use crate::error::{ParseError, Result};
+use crate::types::traits::Dated;
+use crate::types::{ActivityFlexStatement, ConversionRate, FxRateTable};
+
+/// IB's pseudo-currency for the base-currency totals of `CashReport`
+const BASE_SUMMARY: &str = "BASE_SUMMARY";
+
+/// Converts amounts between currencies using FLEX conversion rates
+///
+/// Lookups use the rate on the requested date, or the most recent earlier
+/// one when that date has none (weekends, holidays, gaps between
+/// statements). Pairs not reported directly are inverted or triangulated
+/// through the base currency. Statements for accounts with different base
+/// currencies should not share a converter.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CurrencyConverter {
+    table: FxRateTable,
+}
+
+impl CurrencyConverter {
+    /// Build a converter from the conversion rates of every statement
+    ///
+    /// The base currency is inferred as the most common `toCurrency`.
+    pub fn from_statements<'a, I>(statements: I) -> Self
+    where
+        I: IntoIterator<Item = &'a ActivityFlexStatement>,
+    {
+        let rates = statements
+            .into_iter()
+            .flat_map(|s| s.conversion_rates.items.iter());
+        CurrencyConverter {
+            table: FxRateTable::from_rates(rates),
+        }
+    }
+
+    /// Build a converter from an existing rate table
+    pub fn from_table(table: FxRateTable) -> Self {
+        CurrencyConverter { table }
+    }
+
+    /// Override the inferred base currency
+    pub fn with_base_currency(mut self, currency: impl Into<String>) -> Self {
+        self.table = self.table.with_base_currency(currency);
+        self
+    }
+
+    /// Base currency that [`to_base`](Self::to_base) converts into, if known
+    pub fn base_currency(&self) -> Option<&str> {
+        self.table.base_currency()
+    }
+
+    /// Underlying rate table
+    pub fn table(&self) -> &FxRateTable {
+        &self.table
+    }
+
+    /// Rate converting one unit of `from` into `to` as of `date`
+    pub fn rate(&self, from: &str, to: &str, date: NaiveDate) -> Result<Decimal> {
+        self.table
+            .rate(from, to, date)
+            .ok_or_else(|| ParseError::MissingFxRate {
+                from: from.to_string(),
+                to: to.to_string(),
+                date,
+            })
+    }
+
+    /// Convert `amount` from one currency to another as of `date`
+    pub fn convert(
+        &self,
+        amount: Decimal,
+        from: &str,
+        to: &str,
+        date: NaiveDate,
+    ) -> Result<Decimal> {
+        Ok(amount * self.rate(from, to, date)?)
+    }
+
+    /// Convert `amount` into the base currency as of `date`
+    ///
+    /// Fails when no base currency is known, even for amounts that would
+    /// need no conversion.
+    pub fn to_base(&self, amount: Decimal, currency: &str, date: NaiveDate) -> Result<Decimal> {
+        let base = self
+            .base_currency()
+            .ok_or_else(|| ParseError::MissingFxRate {
+                from: currency.to_string(),
+                to: "base currency".to_string(),
+                date,
+            })?;
+        self.convert(amount, currency, base, date)
+    }
+}
+
+/// Base currency of the account behind `statement`
+///
+/// This is the currency in `AccountInformation`, or when the query leaves
+/// that section out, the currency the `ConversionRates` are quoted against.
+pub fn account_base_currency(statement: &ActivityFlexStatement) -> Option<String> {
+    statement
+        .account_information
+        .as_ref()
+        .and_then(|info| info.currency.clone())
+        .filter(|c| !c.is_empty())
+        .or_else(|| {
+            statement
+                .conversion_rates
+                .to_table()
+                .base_currency()
+                .map(str::to_string)
+        })
+}
+
+/// Restate `statements` in `currency` in place of each account's base
+/// currency
+///
+/// Every row's `fxRateToBase` is changed to convert into `currency`, rows
+/// without one get the rate from their currency, and the amounts IB reports
+/// only in base (`EquitySummaryInBase`, `ChangeInNAV`, and the
+/// `BASE_SUMMARY` row of `CashReport`) are converted. `ConversionRates` are
+/// requoted against `currency` and `AccountInformation` names it, so
+/// analytics run on the result report in `currency` throughout.
+///
+/// Rates come from each statement's own `ConversionRates` as of each row's
+/// date, so every statement not already in `currency` must quote it; as in
+/// [`Portfolio`](super::Portfolio), a statement whose base currency cannot
+/// be found is taken to be in `currency` already. Fails
+/// with [`ParseError::MissingFxRate`] when a rate is missing, leaving every
+/// statement unchanged.
+pub fn rebase(statements: &mut [ActivityFlexStatement], currency: &str) -> Result<()> {
+    // Restate copies, so a rate missing partway through changes nothing
+    let mut rebased = statements.to_vec();
+    for statement in rebased.iter_mut() {
+        rebase_statement(statement, currency)?;
+    }
+    for (statement, rebased) in statements.iter_mut().zip(rebased) {
+        *statement = rebased;
+    }
+    Ok(())
+}
+
+/// Replace each row's `fxRateToBase` with the rate into the new currency
+macro_rules! rebase_rows {
+    ($rebased:ident; $($rows:expr => |$row:ident| $currency:expr;)+) => {
+        $(
+            for $row in $rows.iter_mut() {
+                let fx = $rebased($currency, $row.fx_rate_to_base, $row.date())?;
+                $row.fx_rate_to_base = Some(fx);
+            }
+        )+
+    };
+}
+
+/// Convert amounts reported in the base currency
+macro_rules! scale {
+    ($row:expr, $factor:expr; $($field:ident),+ $(,)?) => {
+        $(
+            $row.$field = $row.$field.map(|amount| amount * $factor);
+        )+
+    };
+}
+
+fn rebase_statement(statement: &mut ActivityFlexStatement, currency: &str) -> Result<()> {
+    let to_date = statement.to_date;
+    let base = match account_base_currency(statement) {
+        Some(base) if base != currency => base,
+        _ => return Ok(()),
+    };
+    let converter = CurrencyConverter::from_table(statement.conversion_rates.to_table())
+        .with_base_currency(base.clone());
+    let factor = |date: NaiveDate| converter.rate(&base, currency, date);
+    let rebased = |row_currency: Option<&str>, fx: Option<Decimal>, date: Option<NaiveDate>| {
+        let date = date.unwrap_or(to_date);
+        match fx {
+            Some(fx) => Ok(fx * factor(date)?),
+            None => converter.rate(row_currency.unwrap_or(&base), currency, date),
+        }
+    };
+
+    rebase_rows! {
+        rebased;
+        statement.trades.items => |t| Some(t.currency.as_str());
+        statement.trades.wash_sales => |t| Some(t.currency.as_str());
+        statement.trades.symbol_summaries => |t| Some(t.currency.as_str());
+        statement.positions.items => |p| Some(p.currency.as_str());
+        statement.cash_transactions.items => |c| Some(c.currency.as_str());
+        statement.corporate_actions.items => |c| c.currency.as_deref();
+        statement.trade_confirms.items => |t| t.currency.as_deref();
+        statement.option_eae.items => |o| o.currency.as_deref();
+        statement.fx_transactions.items => |f| f.functional_currency.as_deref();
+        statement.change_in_dividend_accruals.items => |c| c.currency.as_deref();
+        statement.open_dividend_accruals.items => |o| o.currency.as_deref();
+        statement.transfers.items => |t| t.currency.as_deref();
+        statement.deposits_withdrawals.items => |d| Some(d.currency.as_str());
+        statement.statement_of_funds.items => |s| s.currency.as_deref();
+        statement.client_fees.items => |c| c.currency.as_deref();
+        statement.client_fees_detail.items => |c| c.currency.as_deref();
+        statement.slb_activities.items => |s| s.currency.as_deref();
+        statement.slb_fees.items => |s| s.currency.as_deref();
+        statement.hard_to_borrow_details.items => |h| h.currency.as_deref();
+        statement.unsettled_transfers.items => |u| u.currency.as_deref();
+        statement.trade_transfers.items => |t| t.currency.as_deref();
+        statement.tier_interest_details.items => |t| t.currency.as_deref();
+        statement.debit_card_activities.items => |d| d.currency.as_deref();
+        statement.sales_tax.items => |s| s.currency.as_deref();
+    }
+
+    for row in statement.equity_summary.items.iter_mut() {
+        let factor = factor(row.report_date)?;
+        scale!(row, factor;
+            cash, cash_long, cash_short, settled_cash, slb_cash_collateral, stock, stock_long,
+            stock_short, slb_direct_securities_borrowed, slb_direct_securities_lent, options,
+            options_long, options_short, bonds, bonds_long, bonds_short, notes, funds, futures,
+            futures_long, futures_short, commodities, total, total_long, total_short,
+            interest_accruals, dividend_accruals, accrued_interest, accrued_dividend,
+            soft_dollars, forex_cfd_unrealized_pl, cfd_unrealized_pl, broker_cash_component,
+            broker_interest_accruals_component, gross_position_value, net_liquidation,
+            net_liquidation_uncertainty,
+        );
+    }
+    if let Some(nav) = statement.change_in_nav.as_mut() {
+        let factor = factor(nav.to_date)?;
+        nav.starting_value *= factor;
+        nav.ending_value *= factor;
+        nav.currency = Some(currency.to_string());
+        scale!(nav, factor;
+            mtm, realized, change_in_unrealized, deposits_withdrawals, dividends,
+            withholding_tax, change_in_dividend_accruals, interest, change_in_interest_accruals,
+            advisor_fees, client_fees, other_fees, commissions, fx_translation,
+            corporate_action_proceeds,
+        );
+    }
+    for row in statement.cash_report.items.iter_mut() {
+        if row.currency != BASE_SUMMARY {
+            continue;
+        }
+        let factor = factor(row.to_date)?;
+        row.starting_cash *= factor;
+        row.ending_cash *= factor;
+        scale!(row, factor;
+            starting_cash_sec, starting_cash_com, commissions, commissions_sec, commissions_com,
+            deposits, withdrawals, dividends, broker_interest, bond_interest, withholding_tax,
+            net_trades_sales, net_trades_purchases, account_transfers, internal_transfers,
+            external_transfers, linking_adjustments, other_fees, fx_translation_pnl,
+            billable_sales_tax, realized_forex_pnl, debit_card_activity, client_fees,
+            cash_settling_mtm, soft_dollars, ending_cash_sec, ending_cash_com,
+            ending_settled_cash, ending_settled_cash_sec, ending_settled_cash_com,
+        );
+    }
+
+    let mut rates = Vec::new();
+    let mut dates = BTreeSet::new();
+    for rate in &statement.conversion_rates.items {
+        if rate.to_currency != base {
+            rates.push(rate.clone());
+            continue;
+        }
+        dates.insert(rate.report_date);
+        if rate.from_currency != currency {
+            rates.push(ConversionRate {
+                to_currency: currency.to_string(),
+                rate: rate.rate * factor(rate.report_date)?,
+                ..rate.clone()
+            });
+        }
+    }
+    for date in dates {
+        rates.push(ConversionRate {
+            report_date: date,
+            from_currency: base.clone(),
+            to_currency: currency.to_string(),
+            rate: factor(date)?,
+        });
+    }
+    statement.conversion_rates.items = rates;
+    if let Some(info) = statement.account_information.as_mut() {
+        info.currency = Some(currency.to_string());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::ConversionRate;
+    use std::str::FromStr;
+
+    fn day(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 1, d).unwrap()
+    }
+
+    fn statement(rates: &[(&str, u32, &str)]) -> ActivityFlexStatement {
+        let xml = include_str!("../../tests/fixtures/activity_minimal.xml");
+        let mut statement = crate::parse_activity_flex(xml).unwrap();
+        statement.conversion_rates.items = rates
+            .iter()
+            .map(|(from, d, rate)| ConversionRate {
+                report_date: day(*d),
+                from_currency: from.to_string(),
+                to_currency: "USD".to_string(),
+                rate: Decimal::from_str(rate).unwrap(),
+            })
+            .collect();
+        statement
+    }
+
+    #[test]
+    fn test_merges_statements_and_uses_prior_rate() {
+        let first = statement(&[("EUR", 2, "1.10")]);
+        let second = statement(&[("EUR", 10, "1.20"), ("GBP", 10, "1.25")]);
+        let converter = CurrencyConverter::from_statements([&first, &second]);
+
+        assert_eq!(converter.base_currency(), Some("USD"));
+        let amount = Decimal::from(100);
+        assert_eq!(
+            converter.to_base(amount, "EUR", day(5)).unwrap(),
+            Decimal::from(110)
+        );
+        assert_eq!(
+            converter.to_base(amount, "EUR", day(12)).unwrap(),
+            Decimal::from(120)
+        );
+        // GBP -> EUR triangulates through USD
+        assert_eq!(
+            converter
+                .convert(Decimal::from(96), "GBP", "EUR", day(10))
+                .unwrap()
+                .round_dp(6),
+            Decimal::from(100)
+        );
+    }
+
+    #[test]
+    fn test_missing_rate_is_an_error() {
+        let converter = CurrencyConverter::from_statements([&statement(&[("EUR", 10, "1.1")])]);
+        let err = converter.to_base(Decimal::ONE, "EUR", day(9)).unwrap_err();
+        assert!(matches!(err, ParseError::MissingFxRate { ref from, .. } if from == "EUR"));
+        assert!(converter.to_base(Decimal::ONE, "JPY", day(10)).is_err());
+
+        let empty = CurrencyConverter::default();
+        assert!(empty.to_base(Decimal::ONE, "USD", day(10)).is_err());
+        assert_eq!(empty.rate("USD", "USD", day(10)).unwrap(), Decimal::ONE);
+    }
+
+    #[test]
+    fn test_rebase_restates_in_chosen_currency() {
+        let mut statements = vec![statement(&[("EUR", 10, "1.25")])];
+        statements[0].trades.items[0].fifo_pnl_realized = Some(Decimal::from(100));
+        let mut unchanged = statements.clone();
+        assert!(rebase(&mut unchanged, "JPY").is_err());
+        assert_eq!(unchanged, statements);
+        // A later statement without the rate leaves earlier ones untouched
+        let mut pair = vec![statements[0].clone(), statement(&[("GBP", 10, "1.25")])];
+        assert!(rebase(&mut pair, "EUR").is_err());
+        assert_eq!(pair[0], statements[0]);
+
+        rebase(&mut statements, "EUR").unwrap();
+        assert_eq!(
+            statements[0].trades.items[0].fx_rate_to_base,
+            Some(Decimal::new(8, 1))
+        );
+        assert_eq!(
+            account_base_currency(&statements[0]).as_deref(),
+            Some("EUR")
+        );
+        let report = crate::analytics::base_pnl(
+            &statements,
+            crate::analytics::PnlPeriod::Month,
+            &crate::analytics::RoundingPolicy::default(),
+        );
+        assert_eq!(report.base_currency.as_deref(), Some("EUR"));
+        assert_eq!(report.total.realized, Decimal::from(80));
+    }
+}
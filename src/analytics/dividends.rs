@@ -0,0 +1,540 @@
+//! Dividend income by tax year
+//!
+//! [`dividend_report`] totals dividends, payments in lieu, and withholding
+//! tax from `CashTransactions` per tax year, symbol, and issuer country,
+//! which is what 1099-DIV checks and foreign tax credit worksheets are built
+//! from, rounded by a [`RoundingPolicy`]. Declared but unpaid dividends
+//! from `OpenDividendAccruals` are reported separately.
+//! [`reconcile_dividend_accruals`] follows each accrual through to the cash
+//! dividend that settles it.
+//!
+//! # Example
+//! ```
+//! use ib_flex::analytics::{dividend_report, RoundingPolicy};
+//! use ib_flex::parse_activity_flex;
+//!
+//! let xml = include_str!("../../tests/fixtures/activity_cash.xml");
+//! let statement = parse_activity_flex(xml).unwrap();
+//!
+//! for (year, report) in dividend_report([&statement], &RoundingPolicy::cents()).years {
+//!     println!("{year}: gross {} withheld {}", report.total.gross, report.total.withholding_tax);
+//!     for (country, summary) in &report.by_country {
+//!         println!("  {country}: net {}", summary.net());
+//!     }
+//! }
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{Datelike, NaiveDate};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+#[cfg(feature = "serialize")]
+use serde::Serialize;
+
+use super::RoundingPolicy;
+use crate::types::traits::{Dated, HasConid};
+use crate::types::{ActivityFlexStatement, CashTransaction, CashTransactionType, Conid};
+
+/// Dividend amounts in the base currency
+///
+/// Amounts keep IB's signs: dividends are positive and withholding is
+/// negative, with reversals carrying the opposite sign of what they reverse.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct DividendSummary {
+    /// Ordinary dividends
+    pub gross: Decimal,
+    /// Payments in lieu of dividends (paid on shares lent out)
+    pub payment_in_lieu: Decimal,
+    /// Tax withheld at source on these dividends
+    pub withholding_tax: Decimal,
+}
+
+impl DividendSummary {
+    /// Dividends and payments in lieu after withholding
+    pub fn net(&self) -> Decimal {
+        self.gross + self.payment_in_lieu + self.withholding_tax
+    }
+
+    fn add(&mut self, other: &DividendSummary) {
+        self.gross += other.gross;
+        self.payment_in_lieu += other.payment_in_lieu;
+        self.withholding_tax += other.withholding_tax;
+    }
+
+    fn map(self, f: impl Fn(Decimal) -> Decimal) -> Self {
+        DividendSummary {
+            gross: f(self.gross),
+            payment_in_lieu: f(self.payment_in_lieu),
+            withholding_tax: f(self.withholding_tax),
+        }
+    }
+}
+
+/// Dividend totals for one tax year
+#[derive(Debug, Clone, PartialEq, Eq, Default, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct DividendYear {
+    /// Totals across all symbols
+    pub total: DividendSummary,
+    /// Totals per symbol
+    pub by_symbol: BTreeMap<String, DividendSummary>,
+    /// Totals per issuer country code, with `""` when the country is unknown
+    pub by_country: BTreeMap<String, DividendSummary>,
+    /// Open accruals whose pay date falls in this year, not yet paid
+    pub accrued: DividendSummary,
+}
+
+/// Result of [`dividend_report`]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct DividendReport {
+    /// Totals per tax year
+    pub years: BTreeMap<i32, DividendYear>,
+}
+
+/// Total dividend income per tax year
+///
+/// Cash rows fall in the year of their posting date and are converted with
+/// their `fxRateToBase` (1 when missing). Only withholding rows tied to a
+/// security are counted, which leaves out tax withheld on interest. The
+/// country comes from the row's `issuerCountryCode`, then the matching
+/// `SecuritiesInfo` row, then the ISIN prefix. Open accruals are taken from
+/// the last statement reporting each (symbol, ex-date), so overlapping
+/// statements are not double counted. `rounding` applies to the converted
+/// amounts of each row, or to the totals.
+pub fn dividend_report<'a, I>(statements: I, rounding: &RoundingPolicy) -> DividendReport
+where
+    I: IntoIterator<Item = &'a ActivityFlexStatement>,
+{
+    let statements: Vec<&ActivityFlexStatement> = statements.into_iter().collect();
+    let countries = security_countries(&statements);
+    let mut report = DividendReport::default();
+
+    for tx in statements.iter().flat_map(|s| s.cash_transactions.iter()) {
+        let Some(amounts) = amounts(tx) else {
+            continue;
+        };
+        let amounts = amounts.map(|a| rounding.record(a));
+        let Some(date) = tx.date() else {
+            continue;
+        };
+        let symbol = tx.symbol.clone().unwrap_or_default();
+        let country = country(tx, &countries);
+        let year = report.years.entry(date.year()).or_default();
+        year.total.add(&amounts);
+        year.by_symbol.entry(symbol).or_default().add(&amounts);
+        year.by_country.entry(country).or_default().add(&amounts);
+    }
+
+    let mut accruals: HashMap<(&str, NaiveDate), (NaiveDate, DividendSummary)> = HashMap::new();
+    for accrual in statements
+        .iter()
+        .flat_map(|s| s.open_dividend_accruals.iter())
+    {
+        let fx = accrual.fx_rate_to_base.unwrap_or(Decimal::ONE);
+        let gross = accrual
+            .gross_amount
+            .unwrap_or(accrual.gross_rate * accrual.quantity);
+        let summary = DividendSummary {
+            gross: gross * fx,
+            payment_in_lieu: Decimal::ZERO,
+            withholding_tax: -accrual.tax.unwrap_or_default().abs() * fx,
+        }
+        .map(|a| rounding.record(a));
+        let pay_date = accrual.pay_date.unwrap_or(accrual.ex_date);
+        accruals.insert((&accrual.symbol, accrual.ex_date), (pay_date, summary));
+    }
+    for (pay_date, summary) in accruals.into_values() {
+        let year = report.years.entry(pay_date.year()).or_default();
+        year.accrued.add(&summary);
+    }
+
+    let round = |summary: &mut DividendSummary| *summary = summary.map(|a| rounding.total(a));
+    for year in report.years.values_mut() {
+        round(&mut year.total);
+        round(&mut year.accrued);
+        year.by_symbol.values_mut().for_each(round);
+        year.by_country.values_mut().for_each(round);
+    }
+    report
+}
+
+/// Where an accrued dividend stands against the cash postings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub enum AccrualStatus {
+    /// Paid in the accrued amount
+    Paid,
+    /// Paid, but the cash differs from the accrual
+    Mismatched,
+    /// No dividend posted yet
+    Outstanding,
+}
+
+/// One dividend accrual and the cash that settled it
+///
+/// Amounts are in the dividend currency and keep IB's signs, so tax is
+/// negative.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct DividendAccrualMatch {
+    /// Contract ID, when the accrual carries one
+    pub conid: Option<Conid>,
+    /// Ticker symbol
+    pub symbol: String,
+    /// Currency of the dividend
+    pub currency: Option<String>,
+    /// Ex-dividend date
+    pub ex_date: NaiveDate,
+    /// Pay date announced with the accrual
+    pub pay_date: Option<NaiveDate>,
+    /// Dividend accrued before tax
+    pub accrued_gross: Decimal,
+    /// Withholding tax accrued
+    pub accrued_tax: Decimal,
+    /// Date the dividend was posted, if it has been
+    pub paid_date: Option<NaiveDate>,
+    /// Dividends and payments in lieu posted on that date
+    pub paid_gross: Decimal,
+    /// Withholding posted on that date
+    pub paid_tax: Decimal,
+}
+
+impl DividendAccrualMatch {
+    /// Accrued dividend after tax
+    pub fn accrued_net(&self) -> Decimal {
+        self.accrued_gross + self.accrued_tax
+    }
+
+    /// Posted dividend after tax
+    pub fn paid_net(&self) -> Decimal {
+        self.paid_gross + self.paid_tax
+    }
+
+    /// `paid_net - accrued_net`, once the dividend has been paid
+    pub fn difference(&self) -> Option<Decimal> {
+        self.paid_date?;
+        Some(self.paid_net() - self.accrued_net())
+    }
+
+    /// Status, treating gross and tax differences up to `tolerance` as equal
+    pub fn status(&self, tolerance: Decimal) -> AccrualStatus {
+        if self.paid_date.is_none() {
+            AccrualStatus::Outstanding
+        } else if (self.paid_gross - self.accrued_gross).abs() <= tolerance
+            && (self.paid_tax - self.accrued_tax).abs() <= tolerance
+        {
+            AccrualStatus::Paid
+        } else {
+            AccrualStatus::Mismatched
+        }
+    }
+}
+
+/// Match dividend accruals to the cash postings that settle them
+///
+/// Accruals come from the `ChangeInDividendAccruals` rows that post them
+/// (reversals are skipped) and from `OpenDividendAccruals`, one per contract
+/// and ex-date with the last statement winning. Each is matched to the
+/// dividend, payment in lieu, and withholding rows of the same contract
+/// (by conid, else symbol) posted on its pay date, or failing that on the
+/// first date after the ex-date with a dividend no other accrual claimed.
+/// Results are sorted by ex-date, then symbol.
+pub fn reconcile_dividend_accruals<'a, I>(statements: I) -> Vec<DividendAccrualMatch>
+where
+    I: IntoIterator<Item = &'a ActivityFlexStatement>,
+{
+    let statements: Vec<&ActivityFlexStatement> = statements.into_iter().collect();
+
+    let mut accruals: BTreeMap<(NaiveDate, String), DividendAccrualMatch> = BTreeMap::new();
+    let mut add = |conid: Option<Conid>, accrual: DividendAccrualMatch| {
+        let key = conid.map_or_else(|| accrual.symbol.clone(), |c| c.to_string());
+        accruals.insert((accrual.ex_date, key), accrual);
+    };
+    for statement in &statements {
+        for row in statement.change_in_dividend_accruals.iter() {
+            let reversal = row.code.as_deref().is_some_and(|c| c.contains("Re"));
+            if reversal || row.net_amount.is_sign_negative() {
+                continue;
+            }
+            let gross = row
+                .gross_amount
+                .unwrap_or(row.gross_rate * row.quantity.unwrap_or_default());
+            add(
+                row.conid(),
+                accrual(
+                    row.conid(),
+                    &row.symbol,
+                    &row.currency,
+                    row.ex_date,
+                    row.pay_date,
+                    gross,
+                    row.tax,
+                ),
+            );
+        }
+        for row in statement.open_dividend_accruals.iter() {
+            let gross = row.gross_amount.unwrap_or(row.gross_rate * row.quantity);
+            add(
+                row.conid(),
+                accrual(
+                    row.conid(),
+                    &row.symbol,
+                    &row.currency,
+                    row.ex_date,
+                    row.pay_date,
+                    gross,
+                    row.tax,
+                ),
+            );
+        }
+    }
+
+    // Dividend and withholding postings per contract and date
+    let mut postings: HashMap<String, BTreeMap<NaiveDate, (Decimal, Decimal)>> = HashMap::new();
+    for tx in statements.iter().flat_map(|s| s.cash_transactions.iter()) {
+        let (Some(kind), Some(date)) = (tx.transaction_type.as_ref(), tx.date()) else {
+            continue;
+        };
+        let key = match (tx.conid(), tx.symbol.as_deref()) {
+            (Some(conid), _) => conid.to_string(),
+            (None, Some(symbol)) => symbol.to_string(),
+            (None, None) => continue,
+        };
+        let entry = postings.entry(key).or_default().entry(date).or_default();
+        match kind {
+            CashTransactionType::Dividends | CashTransactionType::PaymentInLieuOfDividends => {
+                entry.0 += tx.amount
+            }
+            t if t.is_withholding() => entry.1 += tx.amount,
+            _ => {}
+        }
+    }
+
+    let mut claimed: HashMap<String, Vec<NaiveDate>> = HashMap::new();
+    let mut matches = Vec::new();
+    for ((ex_date, key), mut accrual) in accruals {
+        let Some(dates) = postings.get(&key) else {
+            matches.push(accrual);
+            continue;
+        };
+        let taken = claimed.entry(key).or_default();
+        let paid = accrual
+            .pay_date
+            .and_then(|pay| dates.get_key_value(&pay))
+            .or_else(|| {
+                dates
+                    .range(ex_date..)
+                    .find(|(date, (gross, _))| !gross.is_zero() && !taken.contains(date))
+            });
+        if let Some((date, (gross, tax))) = paid {
+            taken.push(*date);
+            accrual.paid_date = Some(*date);
+            accrual.paid_gross = *gross;
+            accrual.paid_tax = *tax;
+        }
+        matches.push(accrual);
+    }
+    matches.sort_by(|a, b| (a.ex_date, &a.symbol).cmp(&(b.ex_date, &b.symbol)));
+    matches
+}
+
+fn accrual(
+    conid: Option<Conid>,
+    symbol: &str,
+    currency: &Option<String>,
+    ex_date: NaiveDate,
+    pay_date: Option<NaiveDate>,
+    gross: Decimal,
+    tax: Option<Decimal>,
+) -> DividendAccrualMatch {
+    DividendAccrualMatch {
+        conid,
+        symbol: symbol.to_string(),
+        currency: currency.clone(),
+        ex_date,
+        pay_date,
+        accrued_gross: gross,
+        accrued_tax: -tax.unwrap_or_default().abs(),
+        paid_date: None,
+        paid_gross: Decimal::ZERO,
+        paid_tax: Decimal::ZERO,
+    }
+}
+
+/// Base-currency amounts of a dividend or dividend withholding row
+fn amounts(tx: &CashTransaction) -> Option<DividendSummary> {
+    let amount = tx.amount * tx.fx_rate_to_base.unwrap_or(Decimal::ONE);
+    let mut summary = DividendSummary::default();
+    match tx.transaction_type.as_ref()? {
+        CashTransactionType::Dividends => summary.gross = amount,
+        CashTransactionType::PaymentInLieuOfDividends => summary.payment_in_lieu = amount,
+        t if t.is_withholding() && tx.conid().is_some() => summary.withholding_tax = amount,
+        _ => return None,
+    }
+    Some(summary)
+}
+
+fn security_countries<'a>(statements: &[&'a ActivityFlexStatement]) -> HashMap<Conid, &'a str> {
+    statements
+        .iter()
+        .flat_map(|s| s.securities_info.items.iter())
+        .filter_map(|info| {
+            let country = info.issuer_country_code.as_deref()?;
+            (!country.is_empty()).then_some((info.conid, country))
+        })
+        .collect()
+}
+
+fn country(tx: &CashTransaction, countries: &HashMap<Conid, &str>) -> String {
+    if let Some(code) = tx.issuer_country_code.as_deref().filter(|c| !c.is_empty()) {
+        return code.to_string();
+    }
+    if let Some(code) = tx.conid().and_then(|conid| countries.get(&conid)) {
+        return code.to_string();
+    }
+    tx.isin
+        .as_deref()
+        .and_then(|isin| isin.get(..2))
+        .filter(|prefix| prefix.chars().all(|c| c.is_ascii_uppercase()))
+        .unwrap_or_default()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::CashTransactionBuilder;
+
+    fn day(year: i32, month: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, d).unwrap()
+    }
+
+    fn statement(transactions: Vec<CashTransaction>) -> ActivityFlexStatement {
+        let xml = include_str!("../../tests/fixtures/activity_minimal.xml");
+        let mut statement = crate::parse_activity_flex(xml).unwrap();
+        statement.cash_transactions.items = transactions;
+        statement
+    }
+
+    fn tx(
+        kind: CashTransactionType,
+        symbol: &str,
+        amount: i64,
+        date: NaiveDate,
+    ) -> CashTransaction {
+        let builder = CashTransactionBuilder::new(kind, amount).date(date);
+        if symbol.is_empty() {
+            return builder.build();
+        }
+        builder
+            .symbol(symbol)
+            .conid(symbol.len().to_string())
+            .build()
+    }
+
+    #[test]
+    fn test_dividend_report_by_year_symbol_and_country() {
+        let mut nestle = tx(CashTransactionType::Dividends, "NESN", 200, day(2024, 5, 2));
+        nestle.isin = Some("CH0038863350".to_string());
+        nestle.fx_rate_to_base = Some(Decimal::new(11, 1));
+        let mut nestle_tax = tx(
+            CashTransactionType::WithholdingTax,
+            "NESN",
+            -70,
+            day(2024, 5, 2),
+        );
+        nestle_tax.isin = nestle.isin.clone();
+        nestle_tax.fx_rate_to_base = nestle.fx_rate_to_base;
+        let mut apple = tx(CashTransactionType::Dividends, "AAPL", 50, day(2024, 8, 15));
+        apple.issuer_country_code = Some("US".to_string());
+        let mut lieu = tx(
+            CashTransactionType::PaymentInLieuOfDividends,
+            "AAPL",
+            10,
+            day(2025, 2, 13),
+        );
+        lieu.issuer_country_code = Some("US".to_string());
+        let interest_tax = tx(CashTransactionType::WithholdingTax, "", -5, day(2024, 6, 3));
+        let interest = tx(
+            CashTransactionType::BrokerInterestReceived,
+            "",
+            25,
+            day(2024, 6, 3),
+        );
+
+        let statement = statement(vec![
+            nestle,
+            nestle_tax,
+            apple,
+            lieu,
+            interest_tax,
+            interest,
+        ]);
+        let report = dividend_report([&statement], &RoundingPolicy::default());
+
+        let y2024 = &report.years[&2024];
+        assert_eq!(y2024.total.gross, Decimal::from(270));
+        assert_eq!(y2024.total.withholding_tax, Decimal::from(-77));
+        assert_eq!(y2024.by_country["CH"].net(), Decimal::from(143));
+        assert_eq!(y2024.by_country["US"].gross, Decimal::from(50));
+        assert_eq!(y2024.by_symbol["NESN"].withholding_tax, Decimal::from(-77));
+
+        let y2025 = &report.years[&2025];
+        assert_eq!(y2025.total.payment_in_lieu, Decimal::from(10));
+        assert_eq!(y2025.by_symbol["AAPL"].net(), Decimal::from(10));
+    }
+
+    #[test]
+    fn test_open_accruals_are_not_double_counted() {
+        let xml = include_str!("../../tests/fixtures/activity_extended.xml");
+        let statement = crate::parse_activity_flex(xml).unwrap();
+        let accrual = &statement.open_dividend_accruals.items[0];
+        let year = accrual.pay_date.unwrap_or(accrual.ex_date).year();
+
+        let once = dividend_report([&statement], &RoundingPolicy::default());
+        let twice = dividend_report([&statement, &statement], &RoundingPolicy::default());
+        assert_eq!(once.years[&year].accrued, twice.years[&year].accrued);
+        assert!(!once.years[&year].accrued.gross.is_zero());
+    }
+
+    #[test]
+    fn test_accruals_matched_to_cash() {
+        let xml = r#"<FlexQueryResponse queryName="Test" type="AF">
+<FlexStatements count="1">
+<FlexStatement accountId="U1234567" fromDate="2025-02-01" toDate="2025-03-31" period="Custom" whenGenerated="2025-04-01;120000">
+<CashTransactions>
+<CashTransaction accountId="U1234567" type="Dividends" symbol="AAPL" conid="265598" currency="USD" amount="25" date="2025-02-13" />
+<CashTransaction accountId="U1234567" type="Withholding Tax" symbol="AAPL" conid="265598" currency="USD" amount="-3.75" date="2025-02-13" />
+<CashTransaction accountId="U1234567" type="Dividends" symbol="MSFT" conid="272093" currency="USD" amount="40" date="2025-03-13" />
+</CashTransactions>
+<ChangeInDividendAccruals>
+<ChangeInDividendAccrual accountId="U1234567" currency="USD" symbol="AAPL" conid="265598" exDate="2025-02-10" payDate="2025-02-13" quantity="100" tax="3.75" grossRate="0.25" grossAmount="25" netAmount="21.25" code="Po" />
+<ChangeInDividendAccrual accountId="U1234567" currency="USD" symbol="AAPL" conid="265598" exDate="2025-02-10" payDate="2025-02-13" quantity="100" tax="-3.75" grossRate="0.25" grossAmount="-25" netAmount="-21.25" code="Re" />
+<ChangeInDividendAccrual accountId="U1234567" currency="USD" symbol="MSFT" conid="272093" exDate="2025-02-20" payDate="2025-03-13" quantity="50" grossRate="0.83" grossAmount="41.50" netAmount="41.50" code="Po" />
+</ChangeInDividendAccruals>
+<OpenDividendAccruals>
+<OpenDividendAccrual accountId="U1234567" currency="USD" symbol="KO" conid="8894" exDate="2025-03-14" payDate="2025-04-01" quantity="10" grossRate="0.51" grossAmount="5.10" netAmount="5.10" />
+</OpenDividendAccruals>
+</FlexStatement>
+</FlexStatements>
+</FlexQueryResponse>"#;
+        let statement = crate::parse_activity_flex(xml).unwrap();
+        let matches = reconcile_dividend_accruals([&statement]);
+        let cent = Decimal::new(1, 2);
+
+        let symbols: Vec<_> = matches.iter().map(|m| m.symbol.as_str()).collect();
+        assert_eq!(symbols, ["AAPL", "MSFT", "KO"]);
+        assert_eq!(matches[0].status(cent), AccrualStatus::Paid);
+        assert_eq!(matches[0].paid_tax, Decimal::new(-375, 2));
+        assert_eq!(matches[0].difference(), Some(Decimal::ZERO));
+
+        assert_eq!(matches[1].status(cent), AccrualStatus::Mismatched);
+        assert_eq!(matches[1].difference(), Some(Decimal::new(-150, 2)));
+
+        assert_eq!(matches[2].status(cent), AccrualStatus::Outstanding);
+        assert_eq!(matches[2].difference(), None);
+    }
+}
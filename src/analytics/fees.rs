@@ -0,0 +1,340 @@
+//! Commission and fee analytics
+//!
+//! [`fee_report`] totals execution costs per symbol, exchange, asset
+//! category, and month: IB commissions and transaction taxes from `Trades`,
+//! the commission components from `UnbundledCommissionDetails`, sales tax
+//! from `SalesTaxes`, and other fees from `CashTransactions`, rounded by a
+//! [`RoundingPolicy`].
+//!
+//! # Example
+//! ```
+//! use ib_flex::analytics::{fee_report, RoundingPolicy};
+//! use ib_flex::parse_activity_flex;
+//!
+//! let xml = include_str!("../../tests/fixtures/activity_minimal.xml");
+//! let statement = parse_activity_flex(xml).unwrap();
+//!
+//! let report = fee_report([&statement], &RoundingPolicy::cents());
+//! assert_eq!(report.by_symbol["AAPL"].commission, (-1).into());
+//! for (exchange, fees) in &report.by_exchange {
+//!     println!("{exchange}: {}", fees.total());
+//! }
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{Datelike, NaiveDate};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+#[cfg(feature = "serialize")]
+use serde::Serialize;
+
+use super::RoundingPolicy;
+use crate::parsers::parse_ib_date;
+use crate::types::traits::Dated;
+use crate::types::{ActivityFlexStatement, AssetCategory, LevelOfDetail, TradeId};
+
+/// Execution costs in the base currency
+///
+/// Amounts keep IB's signs, so costs are negative. The unbundled components
+/// break `commission` down further and are not part of [`total`](Self::total).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct FeeSummary {
+    /// IB commission charged on trades (`ibCommission`)
+    pub commission: Decimal,
+    /// Transaction taxes charged on trades
+    pub taxes: Decimal,
+    /// Sales tax (VAT, GST) on commissions and fees
+    pub sales_tax: Decimal,
+    /// Other fees from `CashTransactions`, such as market data and advisor fees
+    pub other_fees: Decimal,
+    /// Execution component of unbundled commissions
+    pub execution: Decimal,
+    /// Clearing component of unbundled commissions
+    pub clearing: Decimal,
+    /// Regulatory component of unbundled commissions
+    pub regulatory: Decimal,
+    /// Third-party component of unbundled commissions, including
+    /// third-party regulatory fees
+    pub third_party: Decimal,
+}
+
+impl FeeSummary {
+    /// Commissions, taxes, and fees
+    pub fn total(&self) -> Decimal {
+        self.commission + self.taxes + self.sales_tax + self.other_fees
+    }
+
+    fn add(&mut self, other: &FeeSummary) {
+        self.commission += other.commission;
+        self.taxes += other.taxes;
+        self.sales_tax += other.sales_tax;
+        self.other_fees += other.other_fees;
+        self.execution += other.execution;
+        self.clearing += other.clearing;
+        self.regulatory += other.regulatory;
+        self.third_party += other.third_party;
+    }
+
+    fn map(self, f: impl Fn(Decimal) -> Decimal) -> Self {
+        FeeSummary {
+            commission: f(self.commission),
+            taxes: f(self.taxes),
+            sales_tax: f(self.sales_tax),
+            other_fees: f(self.other_fees),
+            execution: f(self.execution),
+            clearing: f(self.clearing),
+            regulatory: f(self.regulatory),
+            third_party: f(self.third_party),
+        }
+    }
+}
+
+/// Result of [`fee_report`]
+///
+/// Rows without a symbol or exchange are keyed under `""`, and rows without
+/// an asset category are left out of `by_asset_category`.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct FeeReport {
+    /// Totals across all rows
+    pub total: FeeSummary,
+    /// Totals per symbol
+    pub by_symbol: BTreeMap<String, FeeSummary>,
+    /// Totals per execution exchange
+    pub by_exchange: BTreeMap<String, FeeSummary>,
+    /// Totals per asset category
+    pub by_asset_category: HashMap<AssetCategory, FeeSummary>,
+    /// Totals per month, keyed by the first day of the month
+    pub by_month: BTreeMap<NaiveDate, FeeSummary>,
+}
+
+struct FeeRow<'a> {
+    symbol: &'a str,
+    exchange: &'a str,
+    asset_category: Option<&'a AssetCategory>,
+    date: Option<NaiveDate>,
+    fees: FeeSummary,
+}
+
+impl FeeReport {
+    fn add(&mut self, mut row: FeeRow<'_>, rounding: &RoundingPolicy) {
+        row.fees = row.fees.map(|a| rounding.record(a));
+        self.total.add(&row.fees);
+        self.by_symbol
+            .entry(row.symbol.to_string())
+            .or_default()
+            .add(&row.fees);
+        self.by_exchange
+            .entry(row.exchange.to_string())
+            .or_default()
+            .add(&row.fees);
+        if let Some(category) = row.asset_category {
+            self.by_asset_category
+                .entry(category.clone())
+                .or_default()
+                .add(&row.fees);
+        }
+        if let Some(date) = row.date {
+            self.by_month
+                .entry(date.with_day(1).unwrap_or(date))
+                .or_default()
+                .add(&row.fees);
+        }
+    }
+
+    fn round_totals(&mut self, rounding: &RoundingPolicy) {
+        let round = |summary: &mut FeeSummary| *summary = summary.map(|a| rounding.total(a));
+        round(&mut self.total);
+        self.by_symbol.values_mut().for_each(round);
+        self.by_exchange.values_mut().for_each(round);
+        self.by_asset_category.values_mut().for_each(round);
+        self.by_month.values_mut().for_each(round);
+    }
+}
+
+/// Total commissions, taxes, and fees across statements
+///
+/// Trade amounts are converted with `fxRateToBase`; a commission charged in
+/// a currency other than the trade's is taken as reported. Unbundled
+/// components use the rate of the trade they belong to, and other rows
+/// their own `fxRateToBase`, with 1 when missing. `rounding` applies to the
+/// converted amounts of each row, or to the totals.
+pub fn fee_report<'a, I>(statements: I, rounding: &RoundingPolicy) -> FeeReport
+where
+    I: IntoIterator<Item = &'a ActivityFlexStatement>,
+{
+    let statements: Vec<&ActivityFlexStatement> = statements.into_iter().collect();
+    let mut report = FeeReport::default();
+    let mut trade_rates: HashMap<&TradeId, Decimal> = HashMap::new();
+
+    for trade in statements.iter().flat_map(|s| s.trades.iter()) {
+        if !matches!(trade.level_of_detail, None | Some(LevelOfDetail::Execution)) {
+            continue;
+        }
+        let fx = trade.fx_rate_to_base.unwrap_or(Decimal::ONE);
+        if let Some(id) = &trade.trade_id {
+            trade_rates.insert(id, fx);
+        }
+        let fees = FeeSummary {
+            commission: trade
+                .commission_in_base()
+                .or(trade.commission)
+                .unwrap_or_default(),
+            taxes: trade.taxes.unwrap_or_default() * fx,
+            ..Default::default()
+        };
+        report.add(
+            FeeRow {
+                symbol: &trade.symbol,
+                exchange: trade.exchange.as_deref().unwrap_or_default(),
+                asset_category: Some(&trade.asset_category),
+                date: trade.date(),
+                fees,
+            },
+            rounding,
+        );
+    }
+
+    for detail in statements
+        .iter()
+        .flat_map(|s| s.unbundled_commission_details.iter())
+    {
+        let fx = detail
+            .trade_id
+            .as_ref()
+            .and_then(|id| trade_rates.get(id))
+            .copied()
+            .unwrap_or(Decimal::ONE);
+        let component = |value: Option<Decimal>| value.unwrap_or_default() * fx;
+        let fees = FeeSummary {
+            execution: component(detail.execution_commission),
+            clearing: component(detail.clearing_commission),
+            regulatory: component(detail.regulatory_commission),
+            third_party: component(detail.third_party_commission)
+                + component(detail.third_party_regulatory_commission),
+            ..Default::default()
+        };
+        let date = detail
+            .date_time
+            .as_deref()
+            .and_then(|dt| parse_ib_date(dt.split(';').next()?).ok());
+        report.add(
+            FeeRow {
+                symbol: detail.symbol.as_deref().unwrap_or_default(),
+                exchange: detail.exchange.as_deref().unwrap_or_default(),
+                asset_category: detail.asset_category.as_ref(),
+                date,
+                fees,
+            },
+            rounding,
+        );
+    }
+
+    for tax in statements.iter().flat_map(|s| s.sales_tax.iter()) {
+        let fx = tax.fx_rate_to_base.unwrap_or(Decimal::ONE);
+        report.add(
+            FeeRow {
+                symbol: tax.symbol.as_deref().unwrap_or_default(),
+                exchange: "",
+                asset_category: None,
+                date: tax.date(),
+                fees: FeeSummary {
+                    sales_tax: tax.tax_amount.unwrap_or_default() * fx,
+                    ..Default::default()
+                },
+            },
+            rounding,
+        );
+    }
+
+    for tx in statements.iter().flat_map(|s| s.cash_transactions.iter()) {
+        if !tx.is_fee() {
+            continue;
+        }
+        let fx = tx.fx_rate_to_base.unwrap_or(Decimal::ONE);
+        report.add(
+            FeeRow {
+                symbol: tx.symbol.as_deref().unwrap_or_default(),
+                exchange: "",
+                asset_category: tx.asset_category.as_ref(),
+                date: tx.date(),
+                fees: FeeSummary {
+                    other_fees: tx.amount * fx,
+                    ..Default::default()
+                },
+            },
+            rounding,
+        );
+    }
+
+    report.round_totals(rounding);
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{CashTransactionBuilder, CashTransactionType, TradeBuilder};
+
+    fn day(month: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, month, d).unwrap()
+    }
+
+    const UNBUNDLED: &str = r#"<FlexQueryResponse queryName="Test" type="AF">
+<FlexStatements count="1">
+<FlexStatement accountId="U1234567" fromDate="2025-01-01" toDate="2025-01-31" period="Monthly" whenGenerated="2025-02-01;120000">
+<UnbundledCommissionDetails>
+<UnbundledCommissionDetail accountId="U1234567" symbol="AAPL" assetCategory="STK" tradeID="T1" dateTime="2025-01-10;093000" exchange="NASDAQ" executionCommission="-0.6" clearingCommission="-0.2" regulatoryCommission="-0.1" thirdPartyCommission="-0.05" thirdPartyRegulatoryCommission="-0.05" totalCommission="-1" />
+</UnbundledCommissionDetails>
+</FlexStatement>
+</FlexStatements>
+</FlexQueryResponse>"#;
+
+    #[test]
+    fn test_fee_report_groups_costs() {
+        let mut statement = crate::parse_activity_flex(UNBUNDLED).unwrap();
+        let mut trade = TradeBuilder::new("AAPL")
+            .trade_id("T1")
+            .trade_date(day(1, 10))
+            .quantity(100)
+            .commission(-1)
+            .build();
+        trade.exchange = Some("NASDAQ".to_string());
+        trade.taxes = Some(Decimal::new(-25, 2));
+        let mut eur = TradeBuilder::new("SAP")
+            .trade_date(day(2, 3))
+            .currency("EUR")
+            .quantity(10)
+            .commission(-3)
+            .fx_rate_to_base(Decimal::new(11, 1))
+            .build();
+        eur.exchange = Some("IBIS".to_string());
+        statement.trades.items = vec![trade, eur];
+        statement.cash_transactions.items =
+            vec![
+                CashTransactionBuilder::new(CashTransactionType::OtherFees, -10)
+                    .date(day(2, 1))
+                    .description("MARKET DATA")
+                    .build(),
+            ];
+
+        let report = fee_report([&statement], &RoundingPolicy::default());
+        let aapl = report.by_symbol["AAPL"];
+        assert_eq!(aapl.commission, Decimal::from(-1));
+        assert_eq!(aapl.taxes, Decimal::new(-25, 2));
+        assert_eq!(aapl.execution, Decimal::new(-6, 1));
+        assert_eq!(aapl.third_party, Decimal::new(-1, 1));
+        assert_eq!(aapl.total(), Decimal::new(-125, 2));
+
+        assert_eq!(report.by_exchange["IBIS"].commission, Decimal::new(-33, 1));
+        assert_eq!(report.by_exchange[""].other_fees, Decimal::from(-10));
+        assert_eq!(report.by_month[&day(2, 1)].total(), Decimal::new(-133, 1));
+        assert_eq!(
+            report.by_asset_category[&AssetCategory::Stock].commission,
+            Decimal::new(-43, 1)
+        );
+        assert_eq!(report.total.total(), Decimal::new(-1455, 2));
+    }
+}
@@ -0,0 +1,199 @@
+//! Form 8949-shaped realized gains
+//!
+//! [`form_8949_rows`] turns the closures of a [`LotLedger`](super::LotLedger)
+//! into one row per closed lot with the columns of IRS Form 8949: a
+//! description, acquisition and sale dates, proceeds, basis, adjustment
+//! codes and amount, and gain or loss. Rendering and export are left to
+//! the caller.
+//!
+//! # Example
+//! ```
+//! use ib_flex::analytics::{form_8949_rows, LotLedger};
+//! use ib_flex::parse_activity_flex;
+//!
+//! let xml = include_str!("../../tests/fixtures/activity_minimal.xml");
+//! let statement = parse_activity_flex(xml).unwrap();
+//!
+//! let mut ledger = LotLedger::new();
+//! ledger.apply_trades(&statement.trades);
+//! for row in form_8949_rows(ledger.closures()) {
+//!     println!(
+//!         "{} {} {} {} {} {} {}",
+//!         row.description, row.date_acquired, row.date_sold, row.proceeds, row.cost_basis,
+//!         row.codes(), row.gain_or_loss
+//!     );
+//! }
+//! ```
+
+use std::fmt;
+
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+#[cfg(feature = "serialize")]
+use serde::Serialize;
+
+use super::holding::HoldingPeriod;
+use super::lots::LotClosure;
+
+/// Form 8949 column (f) adjustment code
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub enum AdjustmentCode {
+    /// Wash sale loss disallowed (`W`)
+    WashSale,
+}
+
+impl AdjustmentCode {
+    /// Letter printed in column (f)
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AdjustmentCode::WashSale => "W",
+        }
+    }
+}
+
+impl fmt::Display for AdjustmentCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// One line of Form 8949
+///
+/// Amounts are in the currency of the lot; convert them before filing if
+/// that is not USD.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct Form8949Row {
+    /// (a) Description of property, such as `100 AAPL`
+    pub description: String,
+    /// (b) Date acquired
+    pub date_acquired: NaiveDate,
+    /// (c) Date sold or disposed of
+    pub date_sold: NaiveDate,
+    /// (d) Proceeds
+    pub proceeds: Decimal,
+    /// (e) Cost or other basis
+    pub cost_basis: Decimal,
+    /// (f) Adjustment codes
+    pub adjustment_codes: Vec<AdjustmentCode>,
+    /// (g) Amount of adjustment, positive for disallowed losses
+    pub adjustment: Decimal,
+    /// (h) Gain or loss: `proceeds - cost_basis + adjustment`
+    pub gain_or_loss: Decimal,
+    /// Part I (short-term) or Part II (long-term)
+    pub holding_period: HoldingPeriod,
+    /// Currency of the amounts
+    pub currency: String,
+}
+
+impl Form8949Row {
+    /// Build the row for a closed lot
+    ///
+    /// Covered short sales are reported with the covering date as both the
+    /// acquisition and sale date, as the form's instructions ask, and are
+    /// always short term.
+    pub fn from_closure(closure: &LotClosure) -> Self {
+        let short = closure.quantity.is_sign_negative();
+        let (date_acquired, holding_period) = if short {
+            (closure.close_date, HoldingPeriod::ShortTerm)
+        } else {
+            (closure.open_date, closure.holding_period())
+        };
+        let adjustment_codes = if closure.disallowed_loss.is_zero() {
+            Vec::new()
+        } else {
+            vec![AdjustmentCode::WashSale]
+        };
+        Form8949Row {
+            description: format!("{} {}", closure.quantity.abs().normalize(), closure.symbol),
+            date_acquired,
+            date_sold: closure.close_date,
+            proceeds: closure.proceeds,
+            cost_basis: closure.cost_basis,
+            adjustment_codes,
+            adjustment: closure.disallowed_loss,
+            gain_or_loss: closure.recognized_pnl(),
+            holding_period,
+            currency: closure.currency.clone(),
+        }
+    }
+
+    /// Column (f) as printed, e.g. `W`
+    pub fn codes(&self) -> String {
+        self.adjustment_codes
+            .iter()
+            .map(|code| code.as_str())
+            .collect()
+    }
+}
+
+/// Form 8949 rows for closed lots, short-term rows first, then by sale date
+pub fn form_8949_rows<'a, I>(closures: I) -> Vec<Form8949Row>
+where
+    I: IntoIterator<Item = &'a LotClosure>,
+{
+    let mut rows: Vec<Form8949Row> = closures
+        .into_iter()
+        .map(Form8949Row::from_closure)
+        .collect();
+    rows.sort_by_key(|row| (row.holding_period, row.date_sold));
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn closure(quantity: i64, open: NaiveDate, proceeds: i64, basis: i64) -> LotClosure {
+        LotClosure {
+            conid: 1.into(),
+            symbol: "AAPL".to_string(),
+            currency: "USD".to_string(),
+            opening_trade_id: None,
+            closing_trade_id: None,
+            open_date: open,
+            close_date: date(2025, 6, 2),
+            quantity: Decimal::from(quantity),
+            proceeds: Decimal::from(proceeds),
+            cost_basis: Decimal::from(basis),
+            realized_pnl: Decimal::from(proceeds - basis),
+            basis_adjustment: Decimal::ZERO,
+            disallowed_loss: Decimal::ZERO,
+        }
+    }
+
+    #[test]
+    fn test_rows_with_wash_sale_adjustment() {
+        let long_term = closure(50, date(2023, 4, 3), 9000, 6000);
+        let mut wash = closure(100, date(2025, 3, 3), 15000, 16000);
+        wash.disallowed_loss = Decimal::from(400);
+        let short = closure(-10, date(2025, 5, 1), 2000, 1800);
+
+        let rows = form_8949_rows([&long_term, &wash, &short]);
+        assert_eq!(rows.len(), 3);
+        assert!(rows[..2]
+            .iter()
+            .all(|r| r.holding_period == HoldingPeriod::ShortTerm));
+
+        let wash_row = rows.iter().find(|r| r.description == "100 AAPL").unwrap();
+        assert_eq!(wash_row.codes(), "W");
+        assert_eq!(wash_row.adjustment, Decimal::from(400));
+        assert_eq!(wash_row.gain_or_loss, Decimal::from(-600));
+
+        let short_row = rows.iter().find(|r| r.description == "10 AAPL").unwrap();
+        assert_eq!(short_row.date_acquired, date(2025, 6, 2));
+        assert_eq!(short_row.gain_or_loss, Decimal::from(200));
+
+        let long_row = &rows[2];
+        assert_eq!(long_row.holding_period, HoldingPeriod::LongTerm);
+        assert_eq!(long_row.date_acquired, date(2023, 4, 3));
+        assert!(long_row.adjustment_codes.is_empty());
+        assert_eq!(long_row.gain_or_loss, Decimal::from(3000));
+    }
+}
@@ -0,0 +1,356 @@
+//! Splitting base-currency P&L into price and currency effects
+//!
+//! A position in a foreign currency earns or loses in the base currency for
+//! two reasons: its local price moves, and the currency moves against the
+//! base currency. [`fx_attribution`] separates the two per symbol and per
+//! currency, using the `ConversionRates` of the statements.
+//!
+//! # Example
+//! ```
+//! use ib_flex::analytics::fx_attribution;
+//! use ib_flex::parse_activity_flex;
+//!
+//! let xml = include_str!("../../tests/fixtures/activity_minimal.xml");
+//! let statement = parse_activity_flex(xml).unwrap();
+//!
+//! let report = fx_attribution([&statement], &[]);
+//! for (currency, pnl) in &report.by_currency {
+//!     println!("{currency}: price {} currency {}", pnl.price_pnl, pnl.currency_pnl);
+//! }
+//! ```
+
+use std::collections::BTreeMap;
+
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+#[cfg(feature = "serialize")]
+use serde::Serialize;
+
+use super::attribution::summary_rows;
+use super::pnl::MissingRate;
+use crate::types::traits::Dated;
+use crate::types::{ActivityFlexStatement, Conid, FxRateTable, LevelOfDetail, Position};
+
+/// P&L of one symbol or currency, split into price and currency effects
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct FxContribution {
+    /// Currency the positions are denominated in
+    pub currency: String,
+    /// P&L in that currency, net of commissions
+    pub local_pnl: Decimal,
+    /// Local P&L converted at the starting rate, in the base currency
+    pub price_pnl: Decimal,
+    /// P&L from the currency moving against the base, in the base currency
+    pub currency_pnl: Decimal,
+}
+
+impl FxContribution {
+    /// P&L in the base currency
+    pub fn total(&self) -> Decimal {
+        self.price_pnl + self.currency_pnl
+    }
+
+    fn add(&mut self, other: &FxContribution) {
+        self.local_pnl += other.local_pnl;
+        self.price_pnl += other.price_pnl;
+        self.currency_pnl += other.currency_pnl;
+    }
+}
+
+/// Result of [`fx_attribution`]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct FxAttributionReport {
+    /// Base currency, when the conversion rates identify one
+    pub base_currency: Option<String>,
+    /// First day of the period
+    pub from_date: Option<NaiveDate>,
+    /// Last day of the period
+    pub to_date: Option<NaiveDate>,
+    /// Split per symbol
+    pub by_symbol: BTreeMap<String, FxContribution>,
+    /// Split per currency
+    pub by_currency: BTreeMap<String, FxContribution>,
+    /// Contracts left out because a rate was unavailable
+    pub missing_rates: Vec<MissingRate>,
+}
+
+impl FxAttributionReport {
+    /// Price P&L across all currencies, in the base currency
+    pub fn price_pnl(&self) -> Decimal {
+        self.by_currency.values().map(|c| c.price_pnl).sum()
+    }
+
+    /// Currency P&L across all currencies, in the base currency
+    pub fn currency_pnl(&self) -> Decimal {
+        self.by_currency.values().map(|c| c.currency_pnl).sum()
+    }
+}
+
+/// Local values and cash flows of one contract, with their rates to base
+#[derive(Default)]
+struct Holding {
+    symbol: String,
+    currency: String,
+    /// Market value and rate at the start
+    opening: Option<(Decimal, Decimal)>,
+    /// Trade cash flows and their rates
+    flows: Vec<(Decimal, Decimal)>,
+    /// Market value and rate at the end
+    closing: Option<(Decimal, Decimal)>,
+}
+
+impl Holding {
+    /// Split the holding's P&L, with the starting rate taken from the opening
+    /// position or, for contracts opened during the period, the first trade
+    fn split(&self) -> Option<FxContribution> {
+        let start_fx = self
+            .opening
+            .or_else(|| self.flows.first().copied())
+            .map(|(_, fx)| fx)?;
+        let (opening, _) = self.opening.unwrap_or_default();
+        let (closing, end_fx) = self.closing.unwrap_or((Decimal::ZERO, start_fx));
+        let flows: Decimal = self.flows.iter().map(|(amount, _)| amount).sum();
+        let local_pnl = closing - opening + flows;
+        let currency_pnl = closing * (end_fx - start_fx)
+            + self
+                .flows
+                .iter()
+                .map(|(amount, fx)| amount * (fx - start_fx))
+                .sum::<Decimal>();
+        Some(FxContribution {
+            currency: self.currency.clone(),
+            local_pnl,
+            price_pnl: local_pnl * start_fx,
+            currency_pnl,
+        })
+    }
+}
+
+/// Split each contract's base-currency P&L over the period covered by
+/// `statements`
+///
+/// A contract's base P&L is its closing value at the closing rate, less its
+/// opening value at the opening rate, plus each trade's net cash at the rate
+/// of its trade date. The price part is the local P&L at the starting rate;
+/// the currency part is the rest, which is the rate change applied to the
+/// closing value and to each trade's cash.
+///
+/// As in [`attribution_report`](super::attribution_report), closing values
+/// come from the `OpenPositions` of the latest statement and `opening` is
+/// the prior period's snapshot. Rates come from `ConversionRates`, falling
+/// back to each row's `fxRateToBase`; positions in the base currency have no
+/// currency P&L. Dividends, interest, and cash balances are not included.
+pub fn fx_attribution<'a, I>(statements: I, opening: &[Position]) -> FxAttributionReport
+where
+    I: IntoIterator<Item = &'a ActivityFlexStatement>,
+{
+    let statements: Vec<&ActivityFlexStatement> = statements.into_iter().collect();
+    let table = FxRateTable::from_rates(
+        statements
+            .iter()
+            .flat_map(|s| s.conversion_rates.items.iter()),
+    );
+    let mut report = FxAttributionReport {
+        base_currency: table.base_currency().map(str::to_string),
+        from_date: statements.iter().map(|s| s.from_date).min(),
+        to_date: statements.iter().map(|s| s.to_date).max(),
+        ..Default::default()
+    };
+    let mut missing: Vec<MissingRate> = Vec::new();
+    let mut skipped: Vec<Conid> = Vec::new();
+    let mut rate = |conid: Conid, currency: &str, fx_rate: Option<Decimal>, date: NaiveDate| {
+        let rate = table
+            .to_base(Decimal::ONE, currency, date)
+            .or(fx_rate)
+            .or_else(|| (table.base_currency() == Some(currency)).then_some(Decimal::ONE));
+        if rate.is_none() {
+            missing.push(MissingRate {
+                currency: currency.to_string(),
+                date,
+            });
+            skipped.push(conid);
+        }
+        rate
+    };
+
+    let mut holdings: BTreeMap<Conid, Holding> = BTreeMap::new();
+    for position in summary_rows(opening) {
+        let fx = rate(
+            position.conid,
+            &position.currency,
+            position.fx_rate_to_base,
+            position.report_date,
+        );
+        let entry = entry(
+            &mut holdings,
+            position.conid,
+            &position.symbol,
+            &position.currency,
+        );
+        entry.opening = fx.map(|fx| (position.market_value(), fx));
+    }
+    for trade in statements.iter().flat_map(|s| s.trades.iter()) {
+        if !matches!(trade.level_of_detail, None | Some(LevelOfDetail::Execution)) {
+            continue;
+        }
+        let (Some(amount), Some(date)) = (trade.net_proceeds(), trade.date()) else {
+            continue;
+        };
+        let fx = rate(trade.conid, &trade.currency, trade.fx_rate_to_base, date);
+        let entry = entry(&mut holdings, trade.conid, &trade.symbol, &trade.currency);
+        if let Some(fx) = fx {
+            entry.flows.push((amount, fx));
+        }
+    }
+    if let Some(closing) = statements.iter().max_by_key(|s| s.to_date) {
+        for position in summary_rows(&closing.positions.items) {
+            let fx = rate(
+                position.conid,
+                &position.currency,
+                position.fx_rate_to_base,
+                position.report_date,
+            );
+            let entry = entry(
+                &mut holdings,
+                position.conid,
+                &position.symbol,
+                &position.currency,
+            );
+            entry.closing = fx.map(|fx| (position.market_value(), fx));
+        }
+    }
+
+    for (conid, holding) in &holdings {
+        if skipped.contains(conid) {
+            continue;
+        }
+        let Some(split) = holding.split() else {
+            continue;
+        };
+        report
+            .by_symbol
+            .entry(holding.symbol.clone())
+            .or_insert_with(|| FxContribution {
+                currency: holding.currency.clone(),
+                ..Default::default()
+            })
+            .add(&split);
+        report
+            .by_currency
+            .entry(holding.currency.clone())
+            .or_insert_with(|| FxContribution {
+                currency: holding.currency.clone(),
+                ..Default::default()
+            })
+            .add(&split);
+    }
+
+    missing.sort();
+    missing.dedup();
+    report.missing_rates = missing;
+    report
+}
+
+fn entry<'h>(
+    holdings: &'h mut BTreeMap<Conid, Holding>,
+    conid: Conid,
+    symbol: &str,
+    currency: &str,
+) -> &'h mut Holding {
+    holdings.entry(conid).or_insert_with(|| Holding {
+        symbol: symbol.to_string(),
+        currency: currency.to_string(),
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{ConversionRate, PositionBuilder, TradeBuilder};
+
+    fn day(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 6, d).unwrap()
+    }
+
+    fn rate(d: u32, rate: i64) -> ConversionRate {
+        ConversionRate {
+            report_date: day(d),
+            from_currency: "EUR".to_string(),
+            to_currency: "USD".to_string(),
+            rate: Decimal::new(rate, 2),
+        }
+    }
+
+    #[test]
+    fn test_price_and_currency_split() {
+        let xml = include_str!("../../tests/fixtures/activity_minimal.xml");
+        let mut statement = crate::parse_activity_flex(xml).unwrap();
+        statement.from_date = day(1);
+        statement.to_date = day(30);
+        statement.conversion_rates.items = vec![rate(1, 110), rate(10, 115), rate(30, 120)];
+        // Held 100 at 10 EUR from the start, bought 100 more at 11 EUR mid-month
+        statement.trades.items = vec![TradeBuilder::new("SAP")
+            .conid(7)
+            .currency("EUR")
+            .trade_date(day(10))
+            .quantity(100)
+            .build()];
+        statement.trades.items[0].net_cash = Some(Decimal::from(-1100));
+        let opening = vec![PositionBuilder::new("SAP")
+            .conid(7)
+            .currency("EUR")
+            .quantity(100)
+            .mark_price(10)
+            .position_value(1000)
+            .report_date(day(1))
+            .build()];
+        statement.positions.items = vec![PositionBuilder::new("SAP")
+            .conid(7)
+            .currency("EUR")
+            .quantity(200)
+            .mark_price(12)
+            .position_value(2400)
+            .report_date(day(30))
+            .build()];
+
+        let report = fx_attribution([&statement], &opening);
+        let sap = &report.by_symbol["SAP"];
+        assert_eq!(sap.local_pnl, Decimal::from(300));
+        assert_eq!(sap.price_pnl, Decimal::from(330));
+        // 2400 * (1.20 - 1.10) - 1100 * (1.15 - 1.10)
+        assert_eq!(sap.currency_pnl, Decimal::from(185));
+        // The closing value at the closing rate, less the opening value and
+        // purchase at their own rates
+        assert_eq!(
+            sap.total(),
+            Decimal::from(2880) - Decimal::from(1100) - Decimal::from(1265)
+        );
+        assert_eq!(report.by_currency["EUR"], *sap);
+        assert_eq!(report.currency_pnl(), Decimal::from(185));
+        assert!(report.missing_rates.is_empty());
+    }
+
+    #[test]
+    fn test_missing_rate_skips_contract() {
+        let xml = include_str!("../../tests/fixtures/activity_minimal.xml");
+        let mut statement = crate::parse_activity_flex(xml).unwrap();
+        statement.conversion_rates.items = vec![rate(1, 110)];
+        statement.trades.items = vec![TradeBuilder::new("7203")
+            .conid(8)
+            .currency("JPY")
+            .trade_date(day(10))
+            .quantity(100)
+            .build()];
+        statement.trades.items[0].net_cash = Some(Decimal::from(-250000));
+        statement.trades.items[0].fx_rate_to_base = None;
+        statement.positions.items.clear();
+
+        let report = fx_attribution([&statement], &[]);
+        assert!(report.by_symbol.is_empty());
+        assert_eq!(report.missing_rates[0].currency, "JPY");
+    }
+}
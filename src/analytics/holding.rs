@@ -0,0 +1,213 @@
+//! Holding periods and short/long-term gain classification
+//!
+//! US capital gains are long term when the asset was held for more than one
+//! year: the holding period starts the day after acquisition, so a sale on
+//! the first anniversary is still short term and the day after is long
+//! term, including around February 29. [`holding_period`] applies that rule
+//! to a lot, and [`classify_gains`] totals closed lots by term.
+//!
+//! Acquisition dates tack like the tax rules require as long as the lots
+//! come from a [`LotLedger`](super::LotLedger): corporate actions applied by
+//! [`CorporateActionEngine`](super::CorporateActionEngine) and wash-sale
+//! adjustments carry the original open date over to the new lots. For IB's
+//! own view, [`trade_holding_period`] uses the `holdingPeriodDateTime` IB
+//! reports on closing trades, which is tacked the same way.
+//!
+//! # Example
+//! ```
+//! use ib_flex::analytics::{classify_gains, LotLedger};
+//! use ib_flex::parse_activity_flex;
+//!
+//! let xml = include_str!("../../tests/fixtures/activity_minimal.xml");
+//! let statement = parse_activity_flex(xml).unwrap();
+//!
+//! let mut ledger = LotLedger::new();
+//! ledger.apply_trades(&statement.trades);
+//! let gains = classify_gains(ledger.closures());
+//! println!("short {} long {}", gains.short_term(), gains.long_term());
+//! ```
+
+use chrono::{Months, NaiveDate};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+#[cfg(feature = "serialize")]
+use serde::Serialize;
+
+use super::lots::{Lot, LotClosure};
+use crate::types::Trade;
+
+/// Tax term of a gain or loss
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub enum HoldingPeriod {
+    /// Held one year or less
+    ShortTerm,
+    /// Held more than one year
+    LongTerm,
+}
+
+impl HoldingPeriod {
+    /// Term of an asset acquired on `acquired` and disposed of on `disposed`
+    pub fn between(acquired: NaiveDate, disposed: NaiveDate) -> Self {
+        match long_term_from(acquired) {
+            Some(first_long_term_day) if disposed >= first_long_term_day => HoldingPeriod::LongTerm,
+            _ => HoldingPeriod::ShortTerm,
+        }
+    }
+
+    /// Returns true for [`HoldingPeriod::LongTerm`]
+    pub fn is_long_term(self) -> bool {
+        self == HoldingPeriod::LongTerm
+    }
+}
+
+/// First date a sale of an asset acquired on `acquired` is long term
+///
+/// The day after the first anniversary; acquisitions on February 29 reach
+/// their anniversary on February 28.
+pub fn long_term_from(acquired: NaiveDate) -> Option<NaiveDate> {
+    acquired.checked_add_months(Months::new(12))?.succ_opt()
+}
+
+/// Term a lot would have if closed on `close_date`
+pub fn holding_period(lot: &Lot, close_date: NaiveDate) -> HoldingPeriod {
+    HoldingPeriod::between(lot.open_date, close_date)
+}
+
+/// Term of a closing trade from IB's `holdingPeriodDateTime`
+///
+/// Returns `None` when the trade has no trade date or IB did not report
+/// the holding period start (opening trades, or queries without the field).
+pub fn trade_holding_period(trade: &Trade) -> Option<HoldingPeriod> {
+    let acquired = trade.holding_period_date_time?.date();
+    let disposed = trade.trade_date?;
+    Some(HoldingPeriod::between(acquired, disposed))
+}
+
+/// Realized gains and losses split by term
+///
+/// Gains and losses are kept apart, with losses negative, as Schedule D
+/// nets them. Amounts are recognized P&L, after disallowed wash-sale losses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct GainsSummary {
+    /// Short-term gains
+    pub short_term_gains: Decimal,
+    /// Short-term losses
+    pub short_term_losses: Decimal,
+    /// Long-term gains
+    pub long_term_gains: Decimal,
+    /// Long-term losses
+    pub long_term_losses: Decimal,
+}
+
+impl GainsSummary {
+    /// Net short-term gain or loss
+    pub fn short_term(&self) -> Decimal {
+        self.short_term_gains + self.short_term_losses
+    }
+
+    /// Net long-term gain or loss
+    pub fn long_term(&self) -> Decimal {
+        self.long_term_gains + self.long_term_losses
+    }
+
+    /// Net gain or loss across both terms
+    pub fn total(&self) -> Decimal {
+        self.short_term() + self.long_term()
+    }
+
+    /// Add a gain or loss of the given term
+    pub fn add(&mut self, term: HoldingPeriod, pnl: Decimal) {
+        let bucket = match (term, pnl.is_sign_negative()) {
+            (HoldingPeriod::ShortTerm, false) => &mut self.short_term_gains,
+            (HoldingPeriod::ShortTerm, true) => &mut self.short_term_losses,
+            (HoldingPeriod::LongTerm, false) => &mut self.long_term_gains,
+            (HoldingPeriod::LongTerm, true) => &mut self.long_term_losses,
+        };
+        *bucket += pnl;
+    }
+}
+
+/// Total closed lots by term
+pub fn classify_gains<'a, I>(closures: I) -> GainsSummary
+where
+    I: IntoIterator<Item = &'a LotClosure>,
+{
+    let mut summary = GainsSummary::default();
+    for closure in closures {
+        summary.add(closure.holding_period(), closure.recognized_pnl());
+    }
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::TradeBuilder;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn test_one_year_boundary() {
+        let acquired = date(2024, 3, 15);
+        assert_eq!(
+            HoldingPeriod::between(acquired, date(2025, 3, 15)),
+            HoldingPeriod::ShortTerm
+        );
+        assert_eq!(
+            HoldingPeriod::between(acquired, date(2025, 3, 16)),
+            HoldingPeriod::LongTerm
+        );
+
+        // Leap day acquisitions turn long term on March 1
+        let leap = date(2024, 2, 29);
+        assert_eq!(long_term_from(leap), Some(date(2025, 3, 1)));
+        assert!(!HoldingPeriod::between(leap, date(2025, 2, 28)).is_long_term());
+        assert!(HoldingPeriod::between(leap, date(2025, 3, 1)).is_long_term());
+    }
+
+    #[test]
+    fn test_trade_holding_period_uses_ib_acquisition_date() {
+        let mut trade = TradeBuilder::new("AAPL")
+            .trade_date(date(2025, 6, 2))
+            .build();
+        assert_eq!(trade_holding_period(&trade), None);
+
+        trade.holding_period_date_time = date(2024, 6, 1).and_hms_opt(10, 0, 0);
+        assert_eq!(trade_holding_period(&trade), Some(HoldingPeriod::LongTerm));
+    }
+
+    #[test]
+    fn test_classify_gains() {
+        let closure = |open: NaiveDate, pnl: i64, disallowed: i64| LotClosure {
+            conid: 1.into(),
+            symbol: "AAPL".to_string(),
+            currency: "USD".to_string(),
+            opening_trade_id: None,
+            closing_trade_id: None,
+            open_date: open,
+            close_date: date(2025, 6, 2),
+            quantity: Decimal::ONE,
+            proceeds: Decimal::from(pnl),
+            cost_basis: Decimal::ZERO,
+            realized_pnl: Decimal::from(pnl),
+            basis_adjustment: Decimal::ZERO,
+            disallowed_loss: Decimal::from(disallowed),
+        };
+        let closures = [
+            closure(date(2025, 1, 2), 100, 0),
+            closure(date(2025, 1, 2), -40, 10),
+            closure(date(2023, 1, 2), 300, 0),
+            closure(date(2024, 6, 2), -50, 0),
+        ];
+
+        let gains = classify_gains(&closures);
+        assert_eq!(gains.short_term_gains, Decimal::from(100));
+        assert_eq!(gains.short_term_losses, Decimal::from(-80));
+        assert_eq!(gains.long_term(), Decimal::from(300));
+        assert_eq!(gains.total(), Decimal::from(320));
+    }
+}
@@ -0,0 +1,473 @@
+//! Interest income and expense
+//!
+//! [`interest_report`] splits interest postings in `CashTransactions` into
+//! broker interest received, margin interest paid, and bond interest, per
+//! currency and month, and checks each `InterestAccruals` row against the
+//! broker interest actually posted in its period. [`margin_interest_check`]
+//! recomputes debit interest from the daily balances and rates in
+//! `TierInterestDetails` and compares it to what was charged.
+//!
+//! # Example
+//! ```
+//! use ib_flex::analytics::interest_report;
+//! use ib_flex::parse_activity_flex;
+//!
+//! let xml = include_str!("../../tests/fixtures/activity_cash.xml");
+//! let statement = parse_activity_flex(xml).unwrap();
+//!
+//! let report = interest_report([&statement]);
+//! for (currency, summary) in &report.by_currency {
+//!     println!("{currency}: margin {} net {}", summary.broker_paid, summary.net());
+//! }
+//! ```
+
+use std::collections::BTreeMap;
+
+use chrono::{Datelike, NaiveDate};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+#[cfg(feature = "serialize")]
+use serde::Serialize;
+
+use crate::types::traits::Dated;
+use crate::types::{
+    ActivityFlexStatement, CashTransaction, CashTransactionType, TierInterestDetail,
+};
+
+/// Interest amounts in one currency
+///
+/// Amounts keep IB's signs: received interest is positive and paid interest
+/// negative.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct InterestSummary {
+    /// Credit interest on cash balances
+    pub broker_received: Decimal,
+    /// Margin and other debit interest charged by the broker
+    pub broker_paid: Decimal,
+    /// Coupon and accrued interest received on bonds
+    pub bond_received: Decimal,
+    /// Accrued interest paid when buying bonds
+    pub bond_paid: Decimal,
+}
+
+impl InterestSummary {
+    /// Interest received minus interest paid
+    pub fn net(&self) -> Decimal {
+        self.broker_received + self.broker_paid + self.bond_received + self.bond_paid
+    }
+
+    /// Broker interest received and paid, the part covered by accruals
+    pub fn broker_net(&self) -> Decimal {
+        self.broker_received + self.broker_paid
+    }
+
+    fn add(&mut self, other: &InterestSummary) {
+        self.broker_received += other.broker_received;
+        self.broker_paid += other.broker_paid;
+        self.bond_received += other.bond_received;
+        self.bond_paid += other.bond_paid;
+    }
+}
+
+/// An `InterestAccruals` row checked against posted broker interest
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct InterestAccrualCheck {
+    /// Currency of the accrual
+    pub currency: String,
+    /// Start of the accrual period
+    pub from_date: NaiveDate,
+    /// End of the accrual period
+    pub to_date: NaiveDate,
+    /// Interest accrued during the period
+    pub interest_accrued: Decimal,
+    /// Accruals released by postings: `starting + accrued - ending`
+    pub implied_postings: Decimal,
+    /// Broker interest posted in `CashTransactions` during the period
+    pub cash_postings: Decimal,
+    /// `implied_postings - cash_postings`
+    pub difference: Decimal,
+}
+
+impl InterestAccrualCheck {
+    /// Returns true if the postings match the accrual release within `tolerance`
+    pub fn is_reconciled(&self, tolerance: Decimal) -> bool {
+        self.difference.abs() <= tolerance
+    }
+}
+
+/// Result of [`interest_report`]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct InterestReport {
+    /// Totals per currency
+    pub by_currency: BTreeMap<String, InterestSummary>,
+    /// Totals per currency and month, keyed by the first day of the month
+    pub by_month: BTreeMap<String, BTreeMap<NaiveDate, InterestSummary>>,
+    /// One check per `InterestAccruals` row
+    pub accruals: Vec<InterestAccrualCheck>,
+}
+
+/// Summarize interest postings and reconcile them against accruals
+///
+/// Amounts stay in their own currency. Generic `Bond Interest` rows count
+/// as received or paid by their sign. Accrual checks compare only broker
+/// interest, since bond interest is not accrued in `InterestAccruals`.
+pub fn interest_report<'a, I>(statements: I) -> InterestReport
+where
+    I: IntoIterator<Item = &'a ActivityFlexStatement>,
+{
+    let statements: Vec<&ActivityFlexStatement> = statements.into_iter().collect();
+    let mut report = InterestReport::default();
+    let mut postings: Vec<(&str, NaiveDate, InterestSummary)> = Vec::new();
+
+    for tx in statements.iter().flat_map(|s| s.cash_transactions.iter()) {
+        let (Some(summary), Some(date)) = (amounts(tx), tx.date()) else {
+            continue;
+        };
+        let month = date.with_day(1).unwrap_or(date);
+        report
+            .by_currency
+            .entry(tx.currency.clone())
+            .or_default()
+            .add(&summary);
+        report
+            .by_month
+            .entry(tx.currency.clone())
+            .or_default()
+            .entry(month)
+            .or_default()
+            .add(&summary);
+        postings.push((&tx.currency, date, summary));
+    }
+
+    for accrual in statements.iter().flat_map(|s| s.interest_accruals.iter()) {
+        let implied_postings =
+            accrual.starting_balance + accrual.interest_accrued - accrual.ending_balance;
+        let cash_postings: Decimal = postings
+            .iter()
+            .filter(|(currency, date, _)| {
+                *currency == accrual.currency
+                    && (accrual.from_date..=accrual.to_date).contains(date)
+            })
+            .map(|(_, _, summary)| summary.broker_net())
+            .sum();
+        report.accruals.push(InterestAccrualCheck {
+            currency: accrual.currency.clone(),
+            from_date: accrual.from_date,
+            to_date: accrual.to_date,
+            interest_accrued: accrual.interest_accrued,
+            implied_postings,
+            cash_postings,
+            difference: implied_postings - cash_postings,
+        });
+    }
+
+    report
+}
+
+fn amounts(tx: &CashTransaction) -> Option<InterestSummary> {
+    let amount = tx.amount;
+    let mut summary = InterestSummary::default();
+    match tx.transaction_type.as_ref()? {
+        CashTransactionType::BrokerInterestReceived => summary.broker_received = amount,
+        CashTransactionType::BrokerInterestPaid => summary.broker_paid = amount,
+        CashTransactionType::BondInterestReceived => summary.bond_received = amount,
+        CashTransactionType::BondInterestPaid => summary.bond_paid = amount,
+        CashTransactionType::BondInterest if amount.is_sign_negative() => {
+            summary.bond_paid = amount
+        }
+        CashTransactionType::BondInterest => summary.bond_received = amount,
+        _ => return None,
+    }
+    Some(summary)
+}
+
+/// One `TierInterestDetail` row with its interest recomputed
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct TierInterestCheck {
+    /// Currency of the balance
+    pub currency: String,
+    /// Day the interest accrued for
+    pub value_date: NaiveDate,
+    /// IB's interest type, such as `Debit Interest`
+    pub interest_type: String,
+    /// Tier the balance falls in (`I`, `II`, ...)
+    pub tier: Option<String>,
+    /// Balance within the tier
+    pub principal: Decimal,
+    /// Annual rate, in percent
+    pub rate: Decimal,
+    /// `principal * rate` for one day, negative for debit interest
+    pub computed: Decimal,
+    /// `totalInterest` IB reports for the row
+    pub reported: Decimal,
+}
+
+impl TierInterestCheck {
+    /// `reported - computed`
+    pub fn difference(&self) -> Decimal {
+        self.reported - self.computed
+    }
+
+    /// Returns true if IB's figure matches the recomputation within `tolerance`
+    pub fn is_reconciled(&self, tolerance: Decimal) -> bool {
+        self.difference().abs() <= tolerance
+    }
+}
+
+/// Debit interest for one currency and month, recomputed and charged
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct MarginInterestCheck {
+    /// Currency of the balance
+    pub currency: String,
+    /// First day of the month the interest accrued in
+    pub month: NaiveDate,
+    /// Sum of [`TierInterestCheck::computed`] over the month
+    pub computed: Decimal,
+    /// Sum of IB's daily `totalInterest` over the month
+    pub accrued: Decimal,
+    /// `Broker Interest Paid` postings for the month in `CashTransactions`
+    pub charged: Decimal,
+}
+
+impl MarginInterestCheck {
+    /// `charged - computed`; negative when IB charged more than recomputed
+    pub fn difference(&self) -> Decimal {
+        self.charged - self.computed
+    }
+
+    /// Returns true if the charge matches the recomputation within `tolerance`
+    pub fn is_reconciled(&self, tolerance: Decimal) -> bool {
+        self.difference().abs() <= tolerance
+    }
+}
+
+/// Result of [`margin_interest_check`]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct MarginInterestReport {
+    /// Every tier row with a principal and rate, in statement order
+    pub tiers: Vec<TierInterestCheck>,
+    /// Debit interest per currency and month, sorted by currency then month
+    pub months: Vec<MarginInterestCheck>,
+}
+
+impl MarginInterestReport {
+    /// Months whose charge is off by more than `tolerance`
+    pub fn discrepancies(&self, tolerance: Decimal) -> impl Iterator<Item = &MarginInterestCheck> {
+        self.months
+            .iter()
+            .filter(move |m| !m.is_reconciled(tolerance))
+    }
+}
+
+/// Recompute margin interest and compare it to the interest charged
+///
+/// Each `TierInterestDetail` row accrues `totalPrincipal * rate / 100` over
+/// the currency's money market year: 365 days for GBP and the other
+/// currencies that quote on that basis, 360 for the rest. Debit interest
+/// rows are summed per month and compared to the `Broker Interest Paid`
+/// postings for that month. IB posts a month's interest early in the next
+/// month with a description like `USD DEBIT INT FOR JAN-2025`; postings
+/// without that suffix are taken to cover the month before their date.
+pub fn margin_interest_check<'a, I>(statements: I) -> MarginInterestReport
+where
+    I: IntoIterator<Item = &'a ActivityFlexStatement>,
+{
+    let statements: Vec<&ActivityFlexStatement> = statements.into_iter().collect();
+    let mut report = MarginInterestReport::default();
+    let mut months: BTreeMap<(String, NaiveDate), MarginInterestCheck> = BTreeMap::new();
+
+    for row in statements
+        .iter()
+        .flat_map(|s| s.tier_interest_details.iter())
+    {
+        let Some(check) = recompute(row) else {
+            continue;
+        };
+        if is_debit(&check.interest_type) {
+            let entry = month_entry(&mut months, &check.currency, month_of(check.value_date));
+            entry.computed += check.computed;
+            entry.accrued += check.reported;
+        }
+        report.tiers.push(check);
+    }
+
+    for tx in statements.iter().flat_map(|s| s.cash_transactions.iter()) {
+        if tx.transaction_type != Some(CashTransactionType::BrokerInterestPaid) {
+            continue;
+        }
+        let month = tx
+            .description
+            .as_deref()
+            .and_then(posting_month)
+            .or_else(|| Some(month_of(month_of(tx.date()?).pred_opt()?)));
+        if let Some(month) = month {
+            month_entry(&mut months, &tx.currency, month).charged += tx.amount;
+        }
+    }
+
+    report.months = months.into_values().collect();
+    report
+}
+
+fn recompute(row: &TierInterestDetail) -> Option<TierInterestCheck> {
+    let currency = row.currency.clone()?;
+    let value_date = row.date()?;
+    let principal = row.total_principal?;
+    let rate = row.rate?;
+    let interest_type = row.interest_type.clone().unwrap_or_default();
+    let magnitude =
+        principal.abs() * rate.abs() / Decimal::ONE_HUNDRED / money_market_year(&currency);
+    let computed = if is_debit(&interest_type) {
+        -magnitude
+    } else {
+        magnitude
+    };
+    Some(TierInterestCheck {
+        currency,
+        value_date,
+        interest_type,
+        tier: row.tier_break.clone(),
+        principal,
+        rate,
+        computed,
+        reported: row.total_interest.unwrap_or_default(),
+    })
+}
+
+fn month_entry<'m>(
+    months: &'m mut BTreeMap<(String, NaiveDate), MarginInterestCheck>,
+    currency: &str,
+    month: NaiveDate,
+) -> &'m mut MarginInterestCheck {
+    months
+        .entry((currency.to_string(), month))
+        .or_insert_with(|| MarginInterestCheck {
+            currency: currency.to_string(),
+            month,
+            computed: Decimal::ZERO,
+            accrued: Decimal::ZERO,
+            charged: Decimal::ZERO,
+        })
+}
+
+fn is_debit(interest_type: &str) -> bool {
+    interest_type.to_ascii_lowercase().contains("debit")
+}
+
+/// Days in the interest year IB uses for `currency`
+pub(super) fn money_market_year(currency: &str) -> Decimal {
+    match currency {
+        "GBP" | "AUD" | "NZD" | "HKD" | "SGD" | "CAD" | "ZAR" | "INR" | "ILS" => Decimal::from(365),
+        _ => Decimal::from(360),
+    }
+}
+
+fn month_of(date: NaiveDate) -> NaiveDate {
+    date.with_day(1).unwrap_or(date)
+}
+
+/// Month named by a `... FOR JAN-2025` posting description
+fn posting_month(description: &str) -> Option<NaiveDate> {
+    let (_, period) = description.rsplit_once(" FOR ")?;
+    NaiveDate::parse_from_str(&format!("01-{}", period.trim()), "%d-%b-%Y").ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{CashTransactionBuilder, InterestAccrualsCurrency};
+
+    const TIERS: &str = r#"<FlexQueryResponse queryName="Test" type="AF">
+<FlexStatements count="1">
+<FlexStatement accountId="U1234567" fromDate="2025-01-01" toDate="2025-02-28" period="Custom" whenGenerated="2025-03-01;120000">
+<CashTransactions>
+<CashTransaction accountId="U1234567" type="Broker Interest Paid" description="USD DEBIT INT FOR JAN-2025" amount="-3.24" currency="USD" date="2025-02-04" />
+<CashTransaction accountId="U1234567" type="Broker Interest Paid" description="USD DEBIT INT" amount="-2.50" currency="USD" date="2025-03-04" />
+</CashTransactions>
+<TierInterestDetails>
+<TierInterestDetail accountId="U1234567" currency="USD" interestType="Debit Interest" valueDate="2025-01-02" tierBreak="I" totalPrincipal="10000" rate="5.83" totalInterest="-1.62" />
+<TierInterestDetail accountId="U1234567" currency="USD" interestType="Debit Interest" valueDate="2025-01-03" tierBreak="I" totalPrincipal="10000" rate="5.83" totalInterest="-1.62" />
+<TierInterestDetail accountId="U1234567" currency="USD" interestType="Debit Interest" valueDate="2025-02-03" tierBreak="I" totalPrincipal="10000" rate="5.83" totalInterest="-1.62" />
+<TierInterestDetail accountId="U1234567" currency="GBP" interestType="Short Credit Interest" valueDate="2025-01-02" tierBreak="I" totalPrincipal="7300" rate="2" totalInterest="0.40" />
+</TierInterestDetails>
+</FlexStatement>
+</FlexStatements>
+</FlexQueryResponse>"#;
+
+    fn day(month: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, month, d).unwrap()
+    }
+
+    fn tx(kind: CashTransactionType, amount: i64, date: NaiveDate) -> CashTransaction {
+        CashTransactionBuilder::new(kind, amount).date(date).build()
+    }
+
+    #[test]
+    fn test_interest_report() {
+        let xml = include_str!("../../tests/fixtures/activity_minimal.xml");
+        let mut statement = crate::parse_activity_flex(xml).unwrap();
+        statement.cash_transactions.items = vec![
+            tx(CashTransactionType::BrokerInterestReceived, 40, day(1, 3)),
+            tx(CashTransactionType::BrokerInterestPaid, -15, day(1, 3)),
+            tx(CashTransactionType::BondInterest, -8, day(1, 20)),
+            tx(CashTransactionType::BondInterest, 30, day(2, 15)),
+            tx(CashTransactionType::BrokerInterestReceived, 45, day(2, 4)),
+            tx(CashTransactionType::Dividends, 100, day(2, 4)),
+        ];
+        statement.interest_accruals.items = vec![InterestAccrualsCurrency {
+            account_id: "U1234567".into(),
+            currency: "USD".to_string(),
+            from_date: day(2, 1),
+            to_date: day(2, 28),
+            starting_balance: Decimal::from(25),
+            interest_accrued: Decimal::from(42),
+            ending_balance: Decimal::from(22),
+        }];
+
+        let report = interest_report([&statement]);
+        let usd = report.by_currency["USD"];
+        assert_eq!(usd.broker_received, Decimal::from(85));
+        assert_eq!(usd.broker_paid, Decimal::from(-15));
+        assert_eq!(usd.bond_paid, Decimal::from(-8));
+        assert_eq!(usd.net(), Decimal::from(92));
+
+        let january = report.by_month["USD"][&day(1, 1)];
+        assert_eq!(january.net(), Decimal::from(17));
+
+        let check = &report.accruals[0];
+        assert_eq!(check.implied_postings, Decimal::from(45));
+        assert_eq!(check.cash_postings, Decimal::from(45));
+        assert!(check.is_reconciled(Decimal::ZERO));
+    }
+
+    #[test]
+    fn test_margin_interest_check() {
+        let statement = crate::parse_activity_flex(TIERS).unwrap();
+        let report = margin_interest_check([&statement]);
+        let cent = Decimal::new(1, 2);
+
+        assert_eq!(report.tiers.len(), 4);
+        assert!(report.tiers.iter().all(|t| t.is_reconciled(cent)));
+        // GBP accrues over a 365-day year: 7300 * 2% / 365
+        assert_eq!(report.tiers[3].computed, Decimal::new(40, 2));
+
+        let january = &report.months[0];
+        assert_eq!(january.month, day(1, 1));
+        assert_eq!(january.accrued, Decimal::new(-324, 2));
+        assert_eq!(january.charged, Decimal::new(-324, 2));
+        assert!(january.is_reconciled(cent));
+
+        // One day of February interest, but IB charged 2.50
+        let february = &report.months[1];
+        assert_eq!(february.computed.round_dp(2), Decimal::new(-162, 2));
+        assert_eq!(february.charged, Decimal::new(-250, 2));
+        let flagged: Vec<_> = report.discrepancies(cent).collect();
+        assert_eq!(flagged, [february]);
+    }
+}
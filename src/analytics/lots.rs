@@ -0,0 +1,923 @@
+//! Tax-lot tracking
+//!
+//! [`LotLedger`] replays trades in chronological order, opening a [`Lot`]
+//! for every acquisition and closing existing lots when a trade reduces the
+//! position. Each close produces a [`LotClosure`] with its proceeds, cost
+//! basis, realized P&L, and holding period.
+//!
+//! Lots are matched FIFO by default. A [`LotMethodPolicy`] can select LIFO,
+//! highest-cost, or average-cost matching per account or asset category,
+//! and [`LotLedger::designate`] picks specific lots for a closing trade.
+//!
+//! Costs and proceeds include commissions and the contract multiplier, and
+//! are in the trade currency. Short positions are lots with negative
+//! quantity and basis; buying to cover closes them the same way.
+//!
+//! # Example
+//! ```
+//! use chrono::NaiveDate;
+//! use ib_flex::analytics::LotLedger;
+//! use ib_flex::types::{BuySell, TradeBuilder};
+//!
+//! let day = |d| NaiveDate::from_ymd_opt(2025, 1, d).unwrap();
+//! let buy = |d, qty: i64, px: i64| {
+//!     TradeBuilder::new("AAPL").conid(265598).trade_date(day(d))
+//!         .buy_sell(BuySell::Buy).quantity(qty).price(px).build()
+//! };
+//! let sell = TradeBuilder::new("AAPL").conid(265598).trade_date(day(20))
+//!     .buy_sell(BuySell::Sell).quantity(-150).price(120).build();
+//!
+//! let mut ledger = LotLedger::new();
+//! ledger.apply_trades([&buy(2, 100, 100), &buy(10, 100, 110), &sell]);
+//!
+//! // 100 @ 100 and 50 @ 110 are closed at 120
+//! let pnl: rust_decimal::Decimal = ledger.closures().iter().map(|c| c.realized_pnl).sum();
+//! assert_eq!(pnl, 2500.into());
+//! assert_eq!(ledger.quantity(265598.into()), 50.into());
+//! ```
+
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+#[cfg(feature = "serialize")]
+use serde::Serialize;
+
+use super::corporate_actions::CorporateActionEngine;
+use super::holding::HoldingPeriod;
+use super::wash_sales::WashSale;
+use crate::types::traits::{Dated, HasConid};
+use crate::types::{
+    AccountId, AssetCategory, Conid, CorporateAction, InOut, LevelOfDetail, Position, Trade,
+    TradeId, Transfer,
+};
+
+/// An open tax lot
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct Lot {
+    /// Contract ID
+    pub conid: Conid,
+    /// Ticker symbol
+    pub symbol: String,
+    /// Currency of the cost basis
+    pub currency: String,
+    /// Trade that opened the lot, if known
+    pub trade_id: Option<TradeId>,
+    /// Acquisition date (short sale date for short lots)
+    pub open_date: NaiveDate,
+    /// Remaining quantity (negative for short lots)
+    pub quantity: Decimal,
+    /// Remaining cost basis, including commissions and multiplier
+    ///
+    /// Has the same sign as `quantity`: for short lots it is the (negative)
+    /// cash received when the position was opened.
+    pub cost_basis: Decimal,
+    /// Disallowed wash-sale losses included in `cost_basis`
+    #[serde(default)]
+    pub basis_adjustment: Decimal,
+}
+
+impl Lot {
+    /// Cost basis per unit of quantity
+    pub fn unit_cost(&self) -> Decimal {
+        if self.quantity.is_zero() {
+            Decimal::ZERO
+        } else {
+            self.cost_basis / self.quantity
+        }
+    }
+
+    /// Returns true for long lots
+    pub fn is_long(&self) -> bool {
+        self.quantity.is_sign_positive()
+    }
+
+    /// Days held as of `date`
+    pub fn holding_days(&self, date: NaiveDate) -> i64 {
+        (date - self.open_date).num_days()
+    }
+
+    /// Split off `quantity` units (same sign as the lot), returning their
+    /// share of the basis and of the basis adjustment
+    fn take(&mut self, quantity: Decimal) -> (Decimal, Decimal) {
+        let (basis, adjustment) = if quantity == self.quantity {
+            (self.cost_basis, self.basis_adjustment)
+        } else {
+            let share = quantity / self.quantity;
+            (self.cost_basis * share, self.basis_adjustment * share)
+        };
+        self.quantity -= quantity;
+        self.cost_basis -= basis;
+        self.basis_adjustment -= adjustment;
+        (basis, adjustment)
+    }
+}
+
+/// A (partial) lot closed by a trade
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct LotClosure {
+    /// Contract ID
+    pub conid: Conid,
+    /// Ticker symbol
+    pub symbol: String,
+    /// Currency of proceeds and basis
+    pub currency: String,
+    /// Trade that opened the lot, if known
+    pub opening_trade_id: Option<TradeId>,
+    /// Trade that closed the lot
+    pub closing_trade_id: Option<TradeId>,
+    /// Acquisition date of the lot
+    pub open_date: NaiveDate,
+    /// Date of the closing trade
+    pub close_date: NaiveDate,
+    /// Quantity closed, signed like the lot (negative for covered shorts)
+    pub quantity: Decimal,
+    /// Amount received: sale proceeds for longs, short sale proceeds for shorts
+    pub proceeds: Decimal,
+    /// Amount paid: purchase cost for longs, cost to cover for shorts
+    pub cost_basis: Decimal,
+    /// `proceeds - cost_basis`
+    pub realized_pnl: Decimal,
+    /// Disallowed wash-sale losses included in `cost_basis`
+    #[serde(default)]
+    pub basis_adjustment: Decimal,
+    /// Part of a realized loss disallowed by the wash-sale rule, as a
+    /// positive amount
+    #[serde(default)]
+    pub disallowed_loss: Decimal,
+}
+
+impl LotClosure {
+    /// Realized P&L after disallowed wash-sale losses
+    pub fn recognized_pnl(&self) -> Decimal {
+        self.realized_pnl + self.disallowed_loss
+    }
+
+    /// Days between opening and closing
+    pub fn holding_days(&self) -> i64 {
+        (self.close_date - self.open_date).num_days()
+    }
+
+    /// Short or long-term treatment of the realized P&L
+    pub fn holding_period(&self) -> HoldingPeriod {
+        HoldingPeriod::between(self.open_date, self.close_date)
+    }
+
+    /// Returns true if the lot was held for more than one year
+    ///
+    /// Closing on the anniversary of the open date is still short term.
+    pub fn is_long_term(&self) -> bool {
+        self.holding_period().is_long_term()
+    }
+}
+
+/// How closing trades pick the lots they close
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub enum LotMethod {
+    /// Oldest lots first
+    #[default]
+    Fifo,
+    /// Newest lots first
+    Lifo,
+    /// Lots with the highest unit cost first (for shorts, the lowest sale
+    /// price first), minimizing realized gains
+    HighestCost,
+    /// Every lot is carried at the average unit cost of the position;
+    /// quantities still close oldest first, which sets the holding periods
+    AverageCost,
+}
+
+/// Which [`LotMethod`] applies to a trade
+///
+/// An asset category override wins over an account override, which wins
+/// over the default.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LotMethodPolicy {
+    /// Method used when no override matches
+    pub default: LotMethod,
+    /// Per-account overrides
+    pub by_account: HashMap<AccountId, LotMethod>,
+    /// Per-asset category overrides
+    pub by_asset_category: HashMap<AssetCategory, LotMethod>,
+}
+
+impl LotMethodPolicy {
+    /// Use `method` for every trade
+    pub fn new(method: LotMethod) -> Self {
+        LotMethodPolicy {
+            default: method,
+            ..Default::default()
+        }
+    }
+
+    /// Use `method` for trades in `account`
+    pub fn with_account(mut self, account: impl Into<AccountId>, method: LotMethod) -> Self {
+        self.by_account.insert(account.into(), method);
+        self
+    }
+
+    /// Use `method` for trades in `asset_category`
+    pub fn with_asset_category(mut self, asset_category: AssetCategory, method: LotMethod) -> Self {
+        self.by_asset_category.insert(asset_category, method);
+        self
+    }
+
+    /// Method that applies to `trade`
+    pub fn method_for(&self, trade: &Trade) -> LotMethod {
+        self.by_asset_category
+            .get(&trade.asset_category)
+            .or_else(|| self.by_account.get(&trade.account_id))
+            .copied()
+            .unwrap_or(self.default)
+    }
+}
+
+/// Lot ledger keyed by contract ID
+///
+/// Feed it one account at a time; lots from different accounts sharing a
+/// conid would otherwise be matched against each other.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LotLedger {
+    lots: BTreeMap<Conid, VecDeque<Lot>>,
+    closures: Vec<LotClosure>,
+    policy: LotMethodPolicy,
+    designations: HashMap<TradeId, Vec<TradeId>>,
+}
+
+impl LotLedger {
+    /// Empty FIFO ledger
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Empty ledger using `policy` to choose lots
+    pub fn with_policy(policy: LotMethodPolicy) -> Self {
+        LotLedger {
+            policy,
+            ..Default::default()
+        }
+    }
+
+    /// Specific identification: have `closing` close the lots opened by
+    /// `lots` first, in the given order
+    ///
+    /// Any quantity left over is matched by the usual method. Must be called
+    /// before the closing trade is applied.
+    pub fn designate(
+        &mut self,
+        closing: impl Into<TradeId>,
+        lots: impl IntoIterator<Item = impl Into<TradeId>>,
+    ) {
+        self.designations
+            .insert(closing.into(), lots.into_iter().map(Into::into).collect());
+    }
+
+    /// Seed the ledger with opening positions, e.g. the prior period's
+    /// `OpenPositions`
+    ///
+    /// When a conid has `LOT` rows they are used as individual lots and its
+    /// summary rows are skipped; otherwise each row becomes one lot opened at
+    /// `openDateTime` (or the report date). Rows without a cost basis are
+    /// skipped.
+    pub fn add_positions<'a, I>(&mut self, positions: I)
+    where
+        I: IntoIterator<Item = &'a Position>,
+    {
+        let positions: Vec<&Position> = positions.into_iter().collect();
+        let with_lots: HashSet<Conid> = positions
+            .iter()
+            .filter(|p| p.level_of_detail == Some(LevelOfDetail::Lot))
+            .map(|p| p.conid)
+            .collect();
+        for position in positions {
+            let is_lot = position.level_of_detail == Some(LevelOfDetail::Lot);
+            if is_lot || !with_lots.contains(&position.conid) {
+                self.add_position(position);
+            }
+        }
+    }
+
+    /// Add a single position row as an open lot
+    ///
+    /// Flat rows and rows without a cost basis are skipped.
+    pub fn add_position(&mut self, position: &Position) {
+        let quantity = position.signed_quantity();
+        let Some(basis) = position.cost_basis_total() else {
+            return;
+        };
+        if quantity.is_zero() {
+            return;
+        }
+        let open_date = position
+            .open_date_time
+            .map(|dt| dt.date())
+            .unwrap_or(position.report_date);
+        // Keep the basis sign aligned with the quantity whatever IB reported
+        let cost_basis = if quantity.is_sign_negative() {
+            -basis.abs()
+        } else {
+            basis.abs()
+        };
+        self.lots.entry(position.conid).or_default().push_back(Lot {
+            conid: position.conid,
+            symbol: position.symbol.clone(),
+            currency: position.currency.clone(),
+            trade_id: None,
+            open_date,
+            quantity,
+            cost_basis,
+            basis_adjustment: Decimal::ZERO,
+        });
+    }
+
+    /// Apply trades in chronological order
+    ///
+    /// Trades are sorted by date, time, and transaction ID before applying,
+    /// so rows from several statements can be passed together.
+    pub fn apply_trades<'a, I>(&mut self, trades: I)
+    where
+        I: IntoIterator<Item = &'a Trade>,
+    {
+        let mut trades: Vec<&Trade> = trades.into_iter().collect();
+        trades.sort_by(|a, b| a.chronological_key().cmp(&b.chronological_key()));
+        for trade in trades {
+            self.apply_trade(trade);
+        }
+    }
+
+    /// Apply a single trade
+    ///
+    /// Closes opposite-side lots in the order given by the trade's
+    /// [`LotMethod`] (after any lots designated for it) and opens a new lot
+    /// with any remaining quantity. Order and summary rows, and rows without a
+    /// quantity, price, or date, are ignored.
+    pub fn apply_trade(&mut self, trade: &Trade) {
+        if !matches!(trade.level_of_detail, None | Some(LevelOfDetail::Execution)) {
+            return;
+        }
+        let (Some(quantity), Some(cost), Some(date)) =
+            (trade.signed_quantity(), trade.total_cost(), trade.date())
+        else {
+            return;
+        };
+        if quantity.is_zero() {
+            return;
+        }
+
+        let method = self.policy.method_for(trade);
+        let designated = trade
+            .trade_id
+            .as_ref()
+            .and_then(|id| self.designations.get(id))
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        let lots = self.lots.entry(trade.conid).or_default();
+        let mut remaining = quantity;
+        let mut remaining_cost = cost;
+        let closing = lots
+            .front()
+            .is_some_and(|lot| lot.quantity.is_sign_negative() != remaining.is_sign_negative());
+        if closing {
+            if method == LotMethod::AverageCost {
+                pool_basis(lots);
+            }
+            for idx in match_order(lots, method, designated) {
+                if remaining.is_zero() {
+                    break;
+                }
+                let lot = &mut lots[idx];
+
+                // Signed like the lot
+                let closed = if lot.quantity.abs() <= remaining.abs() {
+                    lot.quantity
+                } else {
+                    -remaining
+                };
+                let (lot_basis, lot_adjustment) = lot.take(closed);
+                let trade_cost = if closed == -remaining {
+                    remaining_cost
+                } else {
+                    remaining_cost * -closed / remaining
+                };
+                remaining += closed;
+                remaining_cost -= trade_cost;
+
+                let (proceeds, cost_basis) = if closed.is_sign_positive() {
+                    (-trade_cost, lot_basis)
+                } else {
+                    (-lot_basis, trade_cost)
+                };
+                self.closures.push(LotClosure {
+                    conid: trade.conid,
+                    symbol: trade.symbol.clone(),
+                    currency: trade.currency.clone(),
+                    opening_trade_id: lot.trade_id.clone(),
+                    closing_trade_id: trade.trade_id.clone(),
+                    open_date: lot.open_date,
+                    close_date: date,
+                    quantity: closed,
+                    proceeds,
+                    cost_basis,
+                    realized_pnl: proceeds - cost_basis,
+                    basis_adjustment: lot_adjustment,
+                    disallowed_loss: Decimal::ZERO,
+                });
+            }
+            lots.retain(|lot| !lot.quantity.is_zero());
+        }
+
+        if !remaining.is_zero() {
+            lots.push_back(Lot {
+                conid: trade.conid,
+                symbol: trade.symbol.clone(),
+                currency: trade.currency.clone(),
+                trade_id: trade.trade_id.clone(),
+                open_date: date,
+                quantity: remaining,
+                cost_basis: remaining_cost,
+                basis_adjustment: Decimal::ZERO,
+            });
+        }
+    }
+
+    /// Apply a position transfer in or out of the account
+    ///
+    /// Incoming transfers open a lot at `positionAmount` (or
+    /// `transferPrice * quantity`); outgoing ones remove lots oldest first
+    /// without realizing P&L. Transfers without a numeric conid are ignored.
+    pub fn apply_transfer(&mut self, transfer: &Transfer) {
+        let Some(conid) = transfer.conid() else {
+            return;
+        };
+        let quantity = match transfer.direction {
+            Some(InOut::OUT) => -transfer.quantity.abs(),
+            Some(InOut::IN) => transfer.quantity.abs(),
+            _ => transfer.quantity,
+        };
+        if quantity.is_zero() {
+            return;
+        }
+        let holding = self.quantity(conid);
+        if holding.is_zero() || holding.is_sign_negative() == quantity.is_sign_negative() {
+            let basis = transfer
+                .position_amount
+                .or_else(|| Some(transfer.transfer_price? * quantity.abs()))
+                .unwrap_or_default()
+                .abs();
+            self.lots.entry(conid).or_default().push_back(Lot {
+                conid,
+                symbol: transfer.symbol.clone(),
+                currency: transfer.currency.clone().unwrap_or_default(),
+                trade_id: None,
+                open_date: transfer.date,
+                quantity,
+                cost_basis: if quantity.is_sign_negative() {
+                    -basis
+                } else {
+                    basis
+                },
+                basis_adjustment: Decimal::ZERO,
+            });
+        } else {
+            self.remove_quantity(conid, -quantity);
+        }
+    }
+
+    /// Apply a single corporate action row
+    ///
+    /// Splits keep the position's basis and spread it over the new quantity;
+    /// a row that closes out a conid with cash realizes its lots, and a row
+    /// for a conid not yet held opens a lot at `cost`. Use
+    /// [`CorporateActionEngine`] for actions
+    /// reported as several rows (mergers, spinoffs, symbol changes) and for
+    /// an audit trail.
+    pub fn apply_corporate_action(&mut self, action: &CorporateAction) {
+        CorporateActionEngine::new().apply_action(self, &[action]);
+    }
+
+    /// Remove `quantity` (signed like the lots) oldest first, with its basis
+    fn remove_quantity(&mut self, conid: Conid, quantity: Decimal) {
+        let Some(lots) = self.lots.get_mut(&conid) else {
+            return;
+        };
+        let mut remaining = quantity;
+        for lot in lots.iter_mut() {
+            if remaining.is_zero() {
+                break;
+            }
+            let taken = if lot.quantity.abs() <= remaining.abs() {
+                lot.quantity
+            } else {
+                remaining
+            };
+            lot.take(taken);
+            remaining -= taken;
+        }
+        lots.retain(|lot| !lot.quantity.is_zero());
+    }
+
+    /// Remove and return every lot of `conid`
+    pub(super) fn take_lots(&mut self, conid: Conid) -> VecDeque<Lot> {
+        self.lots.remove(&conid).unwrap_or_default()
+    }
+
+    /// Open lots of `conid` for in-place adjustment
+    pub(super) fn lots_mut(&mut self, conid: Conid) -> impl Iterator<Item = &mut Lot> {
+        self.lots.get_mut(&conid).into_iter().flatten()
+    }
+
+    /// Append an open lot
+    pub(super) fn push_lot(&mut self, lot: Lot) {
+        self.lots.entry(lot.conid).or_default().push_back(lot);
+    }
+
+    /// Record a closure produced outside of trade matching
+    pub(super) fn push_closure(&mut self, closure: LotClosure) {
+        self.closures.push(closure);
+    }
+
+    /// All open lots, grouped by conid and oldest first
+    pub fn open_lots(&self) -> impl Iterator<Item = &Lot> {
+        self.lots.values().flatten()
+    }
+
+    /// Open lots for `conid`, oldest first
+    pub fn lots(&self, conid: Conid) -> impl Iterator<Item = &Lot> {
+        self.lots.get(&conid).into_iter().flatten()
+    }
+
+    /// Net open quantity for `conid`
+    pub fn quantity(&self, conid: Conid) -> Decimal {
+        self.lots(conid).map(|lot| lot.quantity).sum()
+    }
+
+    /// Closures produced so far, in the order they happened
+    pub fn closures(&self) -> &[LotClosure] {
+        &self.closures
+    }
+
+    /// Defer wash-sale losses into the basis of their replacement lots
+    ///
+    /// Marks each loss closure's [`disallowed_loss`](LotClosure::disallowed_loss),
+    /// adds the loss to the basis of the replacement shares, and moves their
+    /// open date back by the days the sold shares were held, so the holding
+    /// period carries over. Replacement shares that were already sold absorb
+    /// the adjustment first; open replacement lots are split so only the
+    /// replacement shares are adjusted.
+    ///
+    /// Call once, after all trades are applied, with every wash sale from
+    /// [`detect_wash_sales`](super::detect_wash_sales). Losses on replacement
+    /// shares are not re-screened, so chains of wash sales are only
+    /// deferred one step.
+    pub fn apply_wash_sales(&mut self, washes: &[WashSale]) {
+        for wash in washes {
+            if let Some(loss) = self.closures.iter_mut().find(|c| {
+                c.conid == wash.conid
+                    && c.closing_trade_id == wash.sale_trade_id
+                    && c.open_date == wash.sold_lot_open_date
+                    && c.close_date == wash.sale_date
+            }) {
+                loss.disallowed_loss += wash.disallowed_loss;
+            }
+        }
+
+        // Closures already adjusted as replacement shares; partially matched
+        // closures are split, so each is either fully adjusted or not at all
+        let mut adjusted = vec![false; self.closures.len()];
+        for wash in washes {
+            let Some(replacement) = wash.replacement_trade_id.as_ref() else {
+                continue;
+            };
+            if wash.replacement_quantity.is_zero() {
+                continue;
+            }
+            let tacked = wash.sale_date - wash.sold_lot_open_date;
+            let per_unit = wash.disallowed_loss / wash.replacement_quantity;
+            let mut remaining = wash.replacement_quantity;
+
+            let mut idx = 0;
+            while idx < self.closures.len() && !remaining.is_zero() {
+                let closure = &self.closures[idx];
+                if adjusted[idx]
+                    || closure.conid != wash.replacement_conid
+                    || closure.opening_trade_id.as_ref() != Some(replacement)
+                    || closure.quantity <= Decimal::ZERO
+                {
+                    idx += 1;
+                    continue;
+                }
+                let excess = closure.quantity - remaining;
+                if excess > Decimal::ZERO {
+                    // Split off the part that is not a replacement share
+                    let rest = split_closure(&mut self.closures[idx], excess);
+                    self.closures.insert(idx + 1, rest);
+                    adjusted.insert(idx + 1, false);
+                }
+                let closure = &mut self.closures[idx];
+                let units = closure.quantity;
+                let amount = per_unit * units;
+                closure.cost_basis += amount;
+                closure.basis_adjustment += amount;
+                closure.realized_pnl -= amount;
+                closure.open_date -= tacked;
+                adjusted[idx] = true;
+                remaining -= units;
+                idx += 1;
+            }
+
+            let Some(lots) = self.lots.get_mut(&wash.replacement_conid) else {
+                continue;
+            };
+            let mut idx = 0;
+            while idx < lots.len() && !remaining.is_zero() {
+                let lot = &mut lots[idx];
+                if lot.trade_id.as_ref() != Some(replacement) || !lot.basis_adjustment.is_zero() {
+                    idx += 1;
+                    continue;
+                }
+                if lot.quantity > remaining {
+                    // Split off the part that is not a replacement share
+                    let mut rest = lot.clone();
+                    let (basis, adjustment) = lot.take(lot.quantity - remaining);
+                    rest.quantity -= remaining;
+                    rest.cost_basis = basis;
+                    rest.basis_adjustment = adjustment;
+                    lots.insert(idx + 1, rest);
+                }
+                let lot = &mut lots[idx];
+                let amount = per_unit * lot.quantity;
+                lot.cost_basis += amount;
+                lot.basis_adjustment += amount;
+                lot.open_date -= tacked;
+                remaining -= lot.quantity;
+                idx += 1;
+            }
+        }
+    }
+}
+
+/// Split `quantity` units off the end of a closure, returning them as a new
+/// closure
+fn split_closure(closure: &mut LotClosure, quantity: Decimal) -> LotClosure {
+    let share = quantity / closure.quantity;
+    let mut rest = closure.clone();
+    rest.quantity = quantity;
+    rest.proceeds = closure.proceeds * share;
+    rest.cost_basis = closure.cost_basis * share;
+    rest.realized_pnl = rest.proceeds - rest.cost_basis;
+    rest.basis_adjustment = closure.basis_adjustment * share;
+    rest.disallowed_loss = closure.disallowed_loss * share;
+    closure.quantity -= rest.quantity;
+    closure.proceeds -= rest.proceeds;
+    closure.cost_basis -= rest.cost_basis;
+    closure.realized_pnl -= rest.realized_pnl;
+    closure.basis_adjustment -= rest.basis_adjustment;
+    closure.disallowed_loss -= rest.disallowed_loss;
+    rest
+}
+
+/// Indices of `lots` in the order they should be closed
+fn match_order(lots: &VecDeque<Lot>, method: LotMethod, designated: &[TradeId]) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..lots.len()).collect();
+    match method {
+        LotMethod::Fifo | LotMethod::AverageCost => {}
+        LotMethod::Lifo => order.reverse(),
+        LotMethod::HighestCost => {
+            // Stable, so equal-cost lots stay oldest first
+            let short = lots.front().is_some_and(|lot| !lot.is_long());
+            order.sort_by(|&a, &b| {
+                let (a, b) = (lots[a].unit_cost(), lots[b].unit_cost());
+                if short {
+                    a.cmp(&b)
+                } else {
+                    b.cmp(&a)
+                }
+            });
+        }
+    }
+    if !designated.is_empty() {
+        let rank = |idx: &usize| {
+            lots[*idx]
+                .trade_id
+                .as_ref()
+                .and_then(|id| designated.iter().position(|d| d == id))
+                .unwrap_or(designated.len())
+        };
+        order.sort_by_key(rank);
+    }
+    order
+}
+
+/// Re-spread the total basis of `lots` evenly across their quantity
+fn pool_basis(lots: &mut VecDeque<Lot>) {
+    let quantity: Decimal = lots.iter().map(|lot| lot.quantity).sum();
+    if quantity.is_zero() {
+        return;
+    }
+    let unit_cost = lots.iter().map(|lot| lot.cost_basis).sum::<Decimal>() / quantity;
+    for lot in lots.iter_mut() {
+        lot.cost_basis = lot.quantity * unit_cost;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{BuySell, PositionBuilder, TradeBuilder};
+
+    const CONID: u64 = 265598;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn trade(on: NaiveDate, side: BuySell, quantity: i64, price: i64, commission: i64) -> Trade {
+        let quantity = match side {
+            BuySell::Sell => -quantity,
+            _ => quantity,
+        };
+        TradeBuilder::new("AAPL")
+            .conid(CONID)
+            .trade_id(format!("T{}{}", on.format("%Y%m%d"), quantity))
+            .trade_date(on)
+            .buy_sell(side)
+            .quantity(quantity)
+            .price(price)
+            .commission(-commission)
+            .build()
+    }
+
+    #[test]
+    fn test_fifo_partial_closes_allocate_basis_and_fees() {
+        let first = trade(date(2024, 1, 2), BuySell::Buy, 100, 10, 1);
+        let second = trade(date(2024, 6, 3), BuySell::Buy, 100, 20, 1);
+        let sell = trade(date(2025, 1, 3), BuySell::Sell, 150, 30, 2);
+
+        let mut ledger = LotLedger::new();
+        // Out of order on purpose
+        ledger.apply_trades([&sell, &first, &second]);
+
+        let closures = ledger.closures();
+        assert_eq!(closures.len(), 2);
+
+        // 100 @ 10 (+1 fee) sold at 30 with 2/3 of the 2 fee
+        let c = &closures[0];
+        assert_eq!(c.quantity, Decimal::from(100));
+        assert_eq!(c.cost_basis, Decimal::from(1001));
+        assert_eq!(c.proceeds.round_dp(4), Decimal::new(29986667, 4));
+        assert!(c.is_long_term());
+        assert_eq!(c.opening_trade_id, first.trade_id);
+
+        // 50 @ 20 (+0.5 fee) sold at 30 with 1/3 of the fee
+        let c = &closures[1];
+        assert_eq!(c.quantity, Decimal::from(50));
+        assert_eq!(c.cost_basis, Decimal::new(10005, 1));
+        assert!(!c.is_long_term());
+        assert_eq!(c.holding_days(), 214);
+
+        let total: Decimal = closures.iter().map(|c| c.realized_pnl).sum();
+        assert_eq!(total.round_dp(8), Decimal::new(24965, 1));
+
+        let open: Vec<&Lot> = ledger.open_lots().collect();
+        assert_eq!(open.len(), 1);
+        assert_eq!(open[0].quantity, Decimal::from(50));
+        assert_eq!(open[0].cost_basis, Decimal::new(10005, 1));
+        assert_eq!(open[0].open_date, date(2024, 6, 3));
+    }
+
+    #[test]
+    fn test_short_lots_and_flip_through_zero() {
+        let short = trade(date(2025, 1, 2), BuySell::Sell, 100, 50, 0);
+        let flip = trade(date(2025, 1, 9), BuySell::Buy, 150, 40, 0);
+
+        let mut ledger = LotLedger::new();
+        ledger.apply_trades([&short, &flip]);
+
+        let c = &ledger.closures()[0];
+        assert_eq!(c.quantity, Decimal::from(-100));
+        assert_eq!(c.proceeds, Decimal::from(5000));
+        assert_eq!(c.cost_basis, Decimal::from(4000));
+        assert_eq!(c.realized_pnl, Decimal::from(1000));
+
+        let lot = ledger.lots(Conid(CONID)).next().unwrap();
+        assert!(lot.is_long());
+        assert_eq!(lot.quantity, Decimal::from(50));
+        assert_eq!(lot.unit_cost(), Decimal::from(40));
+    }
+
+    #[test]
+    fn test_opening_positions_prefer_lot_rows() {
+        let summary = PositionBuilder::new("AAPL")
+            .conid(CONID)
+            .quantity(30)
+            .mark_price(100)
+            .cost_basis_money(2400)
+            .report_date(date(2024, 12, 31))
+            .build();
+        let lot = |qty: i64, basis: i64, month: u32| {
+            PositionBuilder::new("AAPL")
+                .conid(CONID)
+                .quantity(qty)
+                .mark_price(100)
+                .cost_basis_money(basis)
+                .level_of_detail(LevelOfDetail::Lot)
+                .open_date_time(date(2024, month, 1).and_hms_opt(10, 0, 0).unwrap())
+                .report_date(date(2024, 12, 31))
+                .build()
+        };
+
+        let mut ledger = LotLedger::new();
+        ledger.add_positions([&summary, &lot(10, 700, 3), &lot(20, 1700, 5)]);
+        assert_eq!(ledger.quantity(Conid(CONID)), Decimal::from(30));
+
+        ledger.apply_trade(&trade(date(2025, 1, 6), BuySell::Sell, 10, 100, 0));
+        let c = &ledger.closures()[0];
+        assert_eq!(c.open_date, date(2024, 3, 1));
+        assert_eq!(c.realized_pnl, Decimal::from(300));
+    }
+
+    fn three_buys_then_sell(ledger: &mut LotLedger, sell_quantity: i64) {
+        ledger.apply_trades([
+            &trade(date(2025, 1, 2), BuySell::Buy, 10, 10, 0),
+            &trade(date(2025, 1, 3), BuySell::Buy, 10, 30, 0),
+            &trade(date(2025, 1, 6), BuySell::Buy, 10, 20, 0),
+            &trade(date(2025, 2, 3), BuySell::Sell, sell_quantity, 40, 0),
+        ]);
+    }
+
+    fn closed_open_dates(ledger: &LotLedger) -> Vec<u32> {
+        use chrono::Datelike;
+        ledger
+            .closures()
+            .iter()
+            .map(|c| c.open_date.day())
+            .collect()
+    }
+
+    #[test]
+    fn test_lifo_and_highest_cost_ordering() {
+        let mut lifo = LotLedger::with_policy(LotMethodPolicy::new(LotMethod::Lifo));
+        three_buys_then_sell(&mut lifo, 15);
+        assert_eq!(closed_open_dates(&lifo), vec![6, 3]);
+
+        let mut highest = LotLedger::with_policy(LotMethodPolicy::new(LotMethod::HighestCost));
+        three_buys_then_sell(&mut highest, 15);
+        assert_eq!(closed_open_dates(&highest), vec![3, 6]);
+        assert_eq!(highest.closures()[0].cost_basis, Decimal::from(300));
+        let remaining: Vec<Decimal> = highest.lots(Conid(CONID)).map(|l| l.quantity).collect();
+        assert_eq!(remaining, vec![Decimal::from(10), Decimal::from(5)]);
+    }
+
+    #[test]
+    fn test_average_cost_pools_basis() {
+        let mut ledger = LotLedger::with_policy(LotMethodPolicy::new(LotMethod::AverageCost));
+        three_buys_then_sell(&mut ledger, 15);
+
+        // Average of 10, 30, 20 is 20 per share
+        assert_eq!(closed_open_dates(&ledger), vec![2, 3]);
+        let basis: Decimal = ledger.closures().iter().map(|c| c.cost_basis).sum();
+        assert_eq!(basis, Decimal::from(300));
+        assert!(ledger
+            .open_lots()
+            .all(|l| l.unit_cost() == Decimal::from(20)));
+    }
+
+    #[test]
+    fn test_designated_lots_close_first() {
+        let mut ledger = LotLedger::new();
+        let sell = trade(date(2025, 2, 3), BuySell::Sell, 15, 40, 0);
+        ledger.designate(sell.trade_id.clone().unwrap(), ["T2025010610"]);
+        ledger.apply_trades([
+            &trade(date(2025, 1, 2), BuySell::Buy, 10, 10, 0),
+            &trade(date(2025, 1, 6), BuySell::Buy, 10, 20, 0),
+            &sell,
+        ]);
+
+        // The designated lot, then FIFO for the rest
+        assert_eq!(closed_open_dates(&ledger), vec![6, 2]);
+        assert_eq!(
+            ledger.closures()[0].opening_trade_id.as_deref(),
+            Some("T2025010610")
+        );
+    }
+
+    #[test]
+    fn test_policy_precedence() {
+        let policy = LotMethodPolicy::new(LotMethod::Fifo)
+            .with_account("U1234567", LotMethod::Lifo)
+            .with_asset_category(AssetCategory::Option, LotMethod::HighestCost);
+
+        let stock = TradeBuilder::new("AAPL").build();
+        let option = TradeBuilder::new("AAPL 250117C00150000")
+            .asset_category(AssetCategory::Option)
+            .build();
+        let other = TradeBuilder::new("AAPL").account_id("U7654321").build();
+        assert_eq!(policy.method_for(&stock), LotMethod::Lifo);
+        assert_eq!(policy.method_for(&option), LotMethod::HighestCost);
+        assert_eq!(policy.method_for(&other), LotMethod::Fifo);
+    }
+}
@@ -0,0 +1,86 @@
+//! Analytics built on parsed FLEX statements
+//!
+//! These modules compute results IB does not report directly, such as tax
+//! lots rebuilt from trade history.
+
+pub mod attribution;
+pub mod borrow;
+pub mod cash_flow;
+pub mod corporate_actions;
+pub mod currency;
+pub mod dividends;
+pub mod fees;
+pub mod form8949;
+pub mod fx_attribution;
+pub mod holding;
+pub mod interest;
+pub mod lots;
+pub mod nav;
+pub mod options;
+pub mod pnl;
+pub mod portfolio;
+pub mod positions;
+pub mod reconcile;
+pub mod returns;
+pub mod risk;
+pub mod round_trips;
+pub mod rounding;
+pub mod section1256;
+pub mod straddles;
+pub mod tax_lots;
+pub mod wash_sales;
+
+pub use attribution::{attribution_report, AttributionLevel, AttributionReport, Contribution};
+pub use borrow::{borrow_report, BorrowReport, BorrowSummary};
+pub use cash_flow::{
+    cash_flow_statement, BalanceBreak, CashFlowCategory, CashFlowPeriod, CashFlowStatement,
+    CashFlowSummary,
+};
+pub use corporate_actions::{
+    group_actions, CorporateActionAdjustment, CorporateActionEngine, CorporateActionKind,
+};
+pub use currency::{account_base_currency, rebase, CurrencyConverter};
+pub use dividends::{
+    dividend_report, reconcile_dividend_accruals, AccrualStatus, DividendAccrualMatch,
+    DividendReport, DividendSummary, DividendYear,
+};
+pub use fees::{fee_report, FeeReport, FeeSummary};
+pub use form8949::{form_8949_rows, AdjustmentCode, Form8949Row};
+pub use fx_attribution::{fx_attribution, FxAttributionReport, FxContribution};
+pub use holding::{
+    classify_gains, holding_period, long_term_from, trade_holding_period, GainsSummary,
+    HoldingPeriod,
+};
+pub use interest::{
+    interest_report, margin_interest_check, InterestAccrualCheck, InterestReport, InterestSummary,
+    MarginInterestCheck, MarginInterestReport, TierInterestCheck,
+};
+pub use lots::{Lot, LotClosure, LotLedger, LotMethod, LotMethodPolicy};
+pub use nav::{nav_series, GapFill, NavPoint, NavSeries};
+pub use options::{
+    option_lifecycles, OptionEvent, OptionEventKind, OptionLifecycle, OptionOutcome,
+    UnderlyingDelivery,
+};
+pub use pnl::{base_pnl, BasePnlReport, MissingRate, PnlPeriod, PnlSummary};
+pub use portfolio::{ConsolidatedPosition, Portfolio, PortfolioSummary};
+pub use positions::{positions_from_ledger, reconstruct_positions, ReconstructedPosition};
+pub use reconcile::{
+    reconcile_cash, reconcile_positions, reconcile_statement_positions, CashContribution,
+    CashDiscrepancy, CashSource, DiscrepancyKind, PositionDiscrepancy, PositionReconciliation,
+    ReconcileConfig,
+};
+pub use returns::{
+    money_weighted_return, time_weighted_return, xirr, DayCount, InvestorCashFlow,
+    MoneyWeightedReturn, PeriodReturn, TwrReport,
+};
+pub use risk::{risk_summary, Drawdown, RiskSummary};
+pub use round_trips::{round_trips, RoundTrip, RoundTripFill};
+pub use rounding::{RoundingMode, RoundingPolicy, RoundingStage};
+pub use section1256::{
+    section_1256_report, Section1256Classifier, Section1256Report, BROAD_BASED_INDEXES,
+};
+pub use straddles::{
+    straddle_flags, StraddleConfig, StraddleFlag, StraddleKind, StraddleLeg, TaxRule,
+};
+pub use tax_lots::{tax_lot_report, ClosedTaxLot, OpenTaxLot, TaxLotReport};
+pub use wash_sales::{detect_wash_sales, WashSale, WashSaleConfig};
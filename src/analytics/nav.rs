@@ -0,0 +1,282 @@
+//! Daily NAV history
+//!
+//! [`nav_series`] lines up the NAV, cash, and stock value IB reports per day
+//! across many statements, together with deposits and withdrawals, into one
+//! row per calendar day. Days IB doesn't report (weekends, holidays, gaps
+//! between downloads) are filled according to [`GapFill`], so the series can
+//! be charted or fed into return calculations directly.
+//!
+//! # Example
+//! ```
+//! use ib_flex::analytics::{nav_series, GapFill};
+//! use ib_flex::parse_activity_flex;
+//!
+//! let xml = include_str!("../../tests/fixtures/activity_daily_portfolio.xml");
+//! let statement = parse_activity_flex(xml).unwrap();
+//!
+//! let series = nav_series([&statement], GapFill::CarryForward);
+//! for point in &series.points {
+//!     println!("{} {} (cash {:?})", point.date, point.nav, point.cash);
+//! }
+//! ```
+
+use std::collections::BTreeMap;
+
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+#[cfg(feature = "serialize")]
+use serde::Serialize;
+
+use super::returns::cash_flows;
+use crate::types::ActivityFlexStatement;
+
+/// How days without a reported NAV are filled
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub enum GapFill {
+    /// Repeat the last reported values, as a closed market would
+    #[default]
+    CarryForward,
+    /// Interpolate linearly between the reported days on either side
+    Interpolate,
+}
+
+/// One day of account history, in the base currency
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct NavPoint {
+    /// Calendar day
+    pub date: NaiveDate,
+    /// Net asset value at the end of the day
+    pub nav: Decimal,
+    /// Cash balance, when IB reports the breakdown
+    pub cash: Option<Decimal>,
+    /// Stock value, when IB reports the breakdown
+    pub stock: Option<Decimal>,
+    /// Net deposits (positive) and withdrawals (negative) booked on the day
+    pub cash_flow: Decimal,
+    /// False for days filled in by [`GapFill`]
+    pub reported: bool,
+}
+
+/// Result of [`nav_series`]: one point per calendar day, in date order
+#[derive(Debug, Clone, PartialEq, Eq, Default, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct NavSeries {
+    /// Every day from the first reported NAV to the last
+    pub points: Vec<NavPoint>,
+}
+
+impl NavSeries {
+    /// Returns true if no NAV was reported
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// Point for `date`, if it is within the series
+    pub fn get(&self, date: NaiveDate) -> Option<&NavPoint> {
+        let first = self.points.first()?.date;
+        let offset = usize::try_from((date - first).num_days()).ok()?;
+        self.points.get(offset)
+    }
+
+    /// Net deposits and withdrawals over the whole series
+    pub fn net_cash_flow(&self) -> Decimal {
+        self.points.iter().map(|p| p.cash_flow).sum()
+    }
+
+    /// Return on each day after the first, net of that day's cash flow
+    ///
+    /// Flows are taken to arrive at the start of their day, so a day returns
+    /// `(nav - previous - flow) / (previous + flow)`. A flow booked on a
+    /// filled day is deferred to the next reported day, where the NAV first
+    /// reflects it. Days with nothing invested return zero.
+    pub fn daily_returns(&self) -> Vec<(NaiveDate, Decimal)> {
+        let mut returns = Vec::new();
+        let mut pending = Decimal::ZERO;
+        for pair in self.points.windows(2) {
+            let (previous, point) = (&pair[0], &pair[1]);
+            pending += point.cash_flow;
+            let flow = if point.reported {
+                std::mem::take(&mut pending)
+            } else {
+                Decimal::ZERO
+            };
+            let invested = previous.nav + flow;
+            let rate = if invested.is_zero() {
+                Decimal::ZERO
+            } else {
+                (point.nav - invested) / invested
+            };
+            returns.push((point.date, rate));
+        }
+        returns
+    }
+}
+
+/// Values reported for one day
+#[derive(Clone, Copy)]
+struct Observation {
+    nav: Decimal,
+    cash: Option<Decimal>,
+    stock: Option<Decimal>,
+}
+
+/// Dense daily NAV series across statements
+///
+/// NAV, cash, and stock come from `EquitySummaryInBase`, one row per report
+/// date (the last statement wins where they overlap). A statement without it
+/// contributes its `ChangeInNAV` starting value, dated the day before its
+/// period, and ending value, without a cash or stock breakdown. Cash flows
+/// are collected as by
+/// [`time_weighted_return`](super::returns::time_weighted_return).
+pub fn nav_series<'a, I>(statements: I, fill: GapFill) -> NavSeries
+where
+    I: IntoIterator<Item = &'a ActivityFlexStatement>,
+{
+    let statements: Vec<&ActivityFlexStatement> = statements.into_iter().collect();
+    let flows = cash_flows(&statements);
+
+    let mut observed: BTreeMap<NaiveDate, Observation> = BTreeMap::new();
+    let mut fallback: BTreeMap<NaiveDate, Decimal> = BTreeMap::new();
+    for statement in &statements {
+        let mut found = false;
+        for row in statement.equity_summary.iter() {
+            if let Some(nav) = row.total {
+                observed.insert(
+                    row.report_date,
+                    Observation {
+                        nav,
+                        cash: row.cash,
+                        stock: row.stock,
+                    },
+                );
+                found = true;
+            }
+        }
+        if let (false, Some(change)) = (found, &statement.change_in_nav) {
+            if let Some(before) = change.from_date.pred_opt() {
+                fallback.insert(before, change.starting_value);
+            }
+            fallback.insert(change.to_date, change.ending_value);
+        }
+    }
+    for (date, nav) in fallback {
+        observed.entry(date).or_insert(Observation {
+            nav,
+            cash: None,
+            stock: None,
+        });
+    }
+
+    let mut series = NavSeries::default();
+    let observations: Vec<(NaiveDate, Observation)> = observed.into_iter().collect();
+    for (i, &(date, value)) in observations.iter().enumerate() {
+        series.points.push(point(date, value, true, &flows));
+        let Some(&(next_date, next)) = observations.get(i + 1) else {
+            break;
+        };
+        let span = Decimal::from((next_date - date).num_days());
+        let mut day = date;
+        while let Some(gap) = day.succ_opt().filter(|d| *d < next_date) {
+            day = gap;
+            let value = match fill {
+                GapFill::CarryForward => value,
+                GapFill::Interpolate => {
+                    let t = Decimal::from((day - date).num_days()) / span;
+                    let lerp = |a: Decimal, b: Decimal| (a + (b - a) * t).round_dp(2);
+                    Observation {
+                        nav: lerp(value.nav, next.nav),
+                        cash: value.cash.zip(next.cash).map(|(a, b)| lerp(a, b)),
+                        stock: value.stock.zip(next.stock).map(|(a, b)| lerp(a, b)),
+                    }
+                }
+            };
+            series.points.push(point(day, value, false, &flows));
+        }
+    }
+    series
+}
+
+fn point(
+    date: NaiveDate,
+    value: Observation,
+    reported: bool,
+    flows: &BTreeMap<NaiveDate, Decimal>,
+) -> NavPoint {
+    NavPoint {
+        date,
+        nav: value.nav,
+        cash: value.cash,
+        stock: value.stock,
+        cash_flow: flows.get(&date).copied().unwrap_or_default(),
+        reported,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const XML: &str = r#"<FlexQueryResponse queryName="Test" type="AF">
+<FlexStatements count="2">
+<FlexStatement accountId="U1234567" fromDate="2025-01-30" toDate="2025-02-03" period="Custom" whenGenerated="2025-02-04;120000">
+<EquitySummaryInBase>
+<EquitySummaryByReportDateInBase accountId="U1234567" reportDate="2025-01-31" cash="100" stock="1000" total="1100" />
+<EquitySummaryByReportDateInBase accountId="U1234567" reportDate="2025-02-03" cash="1400" stock="1000" total="2400" />
+</EquitySummaryInBase>
+<DepositsWithdrawals>
+<DepositWithdrawal accountId="U1234567" currency="USD" amount="1000" date="2025-02-01" />
+</DepositsWithdrawals>
+</FlexStatement>
+<FlexStatement accountId="U1234567" fromDate="2025-02-04" toDate="2025-02-05" period="Custom" whenGenerated="2025-02-06;120000">
+<ChangeInNAV accountId="U1234567" fromDate="2025-02-04" toDate="2025-02-05" startingValue="2400" endingValue="2640" />
+</FlexStatement>
+</FlexStatements>
+</FlexQueryResponse>"#;
+
+    fn day(month: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, month, d).unwrap()
+    }
+
+    #[test]
+    fn test_gaps_are_filled() {
+        let statements = crate::parse_activity_flex_all(XML).unwrap();
+        let series = nav_series(&statements, GapFill::CarryForward);
+
+        let dates: Vec<_> = series.points.iter().map(|p| p.date).collect();
+        assert_eq!(dates.first(), Some(&day(1, 31)));
+        assert_eq!(dates.last(), Some(&day(2, 5)));
+        assert_eq!(dates.len(), 6);
+
+        let saturday = series.get(day(2, 1)).unwrap();
+        assert!(!saturday.reported);
+        assert_eq!(saturday.nav, Decimal::from(1100));
+        assert_eq!(saturday.cash_flow, Decimal::from(1000));
+        assert_eq!(
+            series.get(day(2, 4)).unwrap().stock,
+            Some(Decimal::from(1000))
+        );
+        assert_eq!(series.net_cash_flow(), Decimal::from(1000));
+
+        let interpolated = nav_series(&statements, GapFill::Interpolate);
+        let sunday = interpolated.get(day(2, 2)).unwrap();
+        assert_eq!(sunday.nav, Decimal::new(196667, 2));
+        assert_eq!(sunday.cash, Some(Decimal::new(96667, 2)));
+        // ChangeInNAV has no breakdown to interpolate towards
+        assert_eq!(interpolated.get(day(2, 4)).unwrap().cash, None);
+    }
+
+    #[test]
+    fn test_daily_returns_net_of_flows() {
+        let statements = crate::parse_activity_flex_all(XML).unwrap();
+        let series = nav_series(&statements, GapFill::CarryForward);
+        let returns: BTreeMap<_, _> = series.daily_returns().into_iter().collect();
+
+        assert_eq!(returns[&day(2, 1)], Decimal::ZERO);
+        // The weekend deposit lands on Monday: 1100 + 1000 grew to 2400
+        assert_eq!(returns[&day(2, 3)].round_dp(6), Decimal::new(142857, 6));
+        assert_eq!(returns[&day(2, 5)], Decimal::new(1, 1));
+    }
+}
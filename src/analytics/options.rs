@@ -0,0 +1,560 @@
+//! Option lifecycles from open to expiration, assignment, or exercise
+//!
+//! [`option_lifecycles`] follows each option contract from the trade that
+//! opens it to the trade, expiration, assignment, or exercise that closes
+//! it, and links assignments and exercises to the underlying trades they
+//! delivered. Each [`OptionLifecycle`] then reports the premium collected or
+//! paid and the resulting P&L, which is what covered-call and cash-secured
+//! put sellers look at rather than the option and stock legs in isolation.
+//!
+//! # Example
+//! ```
+//! use ib_flex::analytics::option_lifecycles;
+//! use ib_flex::parse_activity_flex;
+//!
+//! let xml = include_str!("../../tests/fixtures/activity_options.xml");
+//! let statement = parse_activity_flex(xml).unwrap();
+//!
+//! for chain in option_lifecycles([&statement]) {
+//!     println!(
+//!         "{} {:?}: premium {} total {}",
+//!         chain.symbol,
+//!         chain.outcome,
+//!         chain.premium(),
+//!         chain.total_pnl()
+//!     );
+//! }
+//! ```
+
+use std::collections::{HashMap, HashSet};
+
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+#[cfg(feature = "serialize")]
+use serde::Serialize;
+
+use crate::types::traits::Dated;
+use crate::types::{
+    ActivityFlexStatement, AssetCategory, Conid, LevelOfDetail, OptionAction, OptionEAE, PutCall,
+    Trade, TradeId,
+};
+
+/// How an option lifecycle ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub enum OptionOutcome {
+    /// Still open at the end of the history
+    Open,
+    /// Closed by an offsetting trade
+    Closed,
+    /// Expired worthless
+    Expired,
+    /// Exercised by the holder (this account was long)
+    Exercised,
+    /// Assigned to this account (this account was short)
+    Assigned,
+    /// Settled in cash at expiry
+    CashSettled,
+}
+
+/// Kind of event in an option lifecycle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub enum OptionEventKind {
+    /// Trade opening or adding to the position
+    Open,
+    /// Trade reducing the position
+    Close,
+    /// Expiration
+    Expiration,
+    /// Exercise
+    Exercise,
+    /// Assignment
+    Assignment,
+    /// Cash settlement
+    CashSettlement,
+}
+
+impl OptionEventKind {
+    fn outcome(self) -> OptionOutcome {
+        match self {
+            OptionEventKind::Open => OptionOutcome::Open,
+            OptionEventKind::Close => OptionOutcome::Closed,
+            OptionEventKind::Expiration => OptionOutcome::Expired,
+            OptionEventKind::Exercise => OptionOutcome::Exercised,
+            OptionEventKind::Assignment => OptionOutcome::Assigned,
+            OptionEventKind::CashSettlement => OptionOutcome::CashSettled,
+        }
+    }
+
+    fn from_codes(trade: &Trade) -> Option<Self> {
+        let codes = trade.codes();
+        if codes.is_assignment() {
+            Some(OptionEventKind::Assignment)
+        } else if codes.is_exercise() {
+            Some(OptionEventKind::Exercise)
+        } else if codes.is_expired() {
+            Some(OptionEventKind::Expiration)
+        } else {
+            None
+        }
+    }
+
+    fn from_action(action: &OptionAction) -> Option<Self> {
+        match action {
+            OptionAction::Assignment => Some(OptionEventKind::Assignment),
+            OptionAction::Exercise => Some(OptionEventKind::Exercise),
+            OptionAction::Expiration | OptionAction::Expire => Some(OptionEventKind::Expiration),
+            OptionAction::CashSettlement => Some(OptionEventKind::CashSettlement),
+            _ => None,
+        }
+    }
+
+    /// True for events that deliver the underlying
+    fn delivers(self) -> bool {
+        matches!(
+            self,
+            OptionEventKind::Assignment | OptionEventKind::Exercise
+        )
+    }
+}
+
+/// One trade or `OptionEAE` row in an option lifecycle
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct OptionEvent {
+    /// Date of the event
+    pub date: NaiveDate,
+    /// What happened
+    pub kind: OptionEventKind,
+    /// Trade booked for the event, if any
+    pub trade_id: Option<TradeId>,
+    /// Contracts bought (positive) or sold (negative)
+    pub quantity: Decimal,
+    /// Net cash of the event after commissions, positive when received
+    pub cash: Decimal,
+}
+
+/// Underlying trade delivered by an assignment or exercise
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct UnderlyingDelivery {
+    /// Trade date
+    pub date: NaiveDate,
+    /// Trade ID of the underlying trade
+    pub trade_id: Option<TradeId>,
+    /// Contract ID of the underlying
+    pub conid: Conid,
+    /// Symbol of the underlying
+    pub symbol: String,
+    /// Units bought (positive) or sold (negative)
+    pub quantity: Decimal,
+    /// Delivery price, normally the strike
+    pub price: Option<Decimal>,
+    /// Net cash of the trade after commissions, positive when received
+    pub cash: Decimal,
+    /// P&L IB realized on the underlying trade (`fifoPnlRealized`)
+    pub realized_pnl: Decimal,
+}
+
+/// An option position from opening to close, with what it delivered
+///
+/// A contract opened again after being closed out starts a new lifecycle.
+/// Amounts are in the option's currency.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct OptionLifecycle {
+    /// Contract ID of the option
+    pub conid: Conid,
+    /// Option symbol
+    pub symbol: String,
+    /// Contract ID of the underlying
+    pub underlying_conid: Option<Conid>,
+    /// Symbol of the underlying
+    pub underlying_symbol: Option<String>,
+    /// Put or call
+    pub put_call: Option<PutCall>,
+    /// Strike price
+    pub strike: Option<Decimal>,
+    /// Expiration date
+    pub expiry: Option<NaiveDate>,
+    /// Currency of the option
+    pub currency: String,
+    /// Contract multiplier
+    pub multiplier: Decimal,
+    /// Date of the opening trade
+    pub open_date: NaiveDate,
+    /// Date the position went flat, if it did
+    pub close_date: Option<NaiveDate>,
+    /// How the lifecycle ended
+    pub outcome: OptionOutcome,
+    /// Contracts still open (negative when short)
+    pub open_quantity: Decimal,
+    /// Trades and `OptionEAE` rows, in order
+    pub events: Vec<OptionEvent>,
+    /// Underlying trades delivered by assignment or exercise
+    pub deliveries: Vec<UnderlyingDelivery>,
+}
+
+impl OptionLifecycle {
+    /// Returns true if the lifecycle was opened by selling
+    pub fn is_short(&self) -> bool {
+        self.events
+            .first()
+            .is_some_and(|event| event.quantity.is_sign_negative())
+    }
+
+    /// Returns true once the position is flat
+    pub fn is_closed(&self) -> bool {
+        self.outcome != OptionOutcome::Open
+    }
+
+    /// Net option premium, positive when collected
+    ///
+    /// Includes every option trade in the lifecycle: the opening premium,
+    /// any closing trade, and commissions.
+    pub fn premium(&self) -> Decimal {
+        self.events.iter().map(|event| event.cash).sum()
+    }
+
+    /// P&L IB realized on the delivered underlying trades
+    pub fn delivery_pnl(&self) -> Decimal {
+        self.deliveries.iter().map(|d| d.realized_pnl).sum()
+    }
+
+    /// Strategy P&L: the premium plus P&L realized on delivered shares
+    ///
+    /// For a covered call that is assigned this includes the gain on the
+    /// called-away shares. Shares bought through a put assignment or call
+    /// exercise are still held, so their P&L is not realized here; see
+    /// [`effective_price`](Self::effective_price) for their premium-adjusted
+    /// cost.
+    pub fn total_pnl(&self) -> Decimal {
+        self.premium() + self.delivery_pnl()
+    }
+
+    /// Price per unit of the delivered underlying, net of the premium
+    ///
+    /// A put sold for 2 and assigned at 50 buys shares at an effective 48;
+    /// a call sold for 1.5 and assigned at 55 sells them at an effective
+    /// 56.5. Returns `None` when nothing was delivered.
+    pub fn effective_price(&self) -> Option<Decimal> {
+        let quantity: Decimal = self.deliveries.iter().map(|d| d.quantity).sum();
+        if quantity.is_zero() {
+            return None;
+        }
+        let cash: Decimal = self.deliveries.iter().map(|d| d.cash).sum();
+        Some(((cash + self.premium()) / quantity).abs())
+    }
+}
+
+/// Link option trades, `OptionEAE` rows, and delivered underlying trades
+///
+/// Trades are taken at execution level from every statement and applied in
+/// execution order; statements should not overlap. A lifecycle is closed by
+/// option trades, including the zero-price trades IB books for expirations
+/// and assignments, or by an `OptionEAE` row alone when the statement has
+/// no matching trade. Underlying trades coded as assignments or exercises
+/// on the same day are linked to the lifecycle, preferring trades at the
+/// strike when several options on the same underlying were assigned.
+/// Lifecycles are returned in order of their opening.
+pub fn option_lifecycles<'a, I>(statements: I) -> Vec<OptionLifecycle>
+where
+    I: IntoIterator<Item = &'a ActivityFlexStatement>,
+{
+    let statements: Vec<&ActivityFlexStatement> = statements.into_iter().collect();
+    let mut trades: Vec<&Trade> = statements
+        .iter()
+        .flat_map(|s| s.trades.iter())
+        .filter(|t| matches!(t.level_of_detail, None | Some(LevelOfDetail::Execution)))
+        .collect();
+    trades.sort_by(|a, b| a.chronological_key().cmp(&b.chronological_key()));
+
+    let mut events: Vec<(NaiveDate, Source<'_>)> = Vec::new();
+    for trade in trades.iter().filter(|t| is_option(&t.asset_category)) {
+        if let Some(date) = trade.date() {
+            events.push((date, Source::Trade(trade)));
+        }
+    }
+    for eae in statements.iter().flat_map(|s| s.option_eae.iter()) {
+        events.push((eae.date, Source::Eae(eae)));
+    }
+    // Stable sort keeps trades in execution order and EAE rows after the
+    // trades of their day
+    events.sort_by_key(|(date, source)| (*date, matches!(source, Source::Eae(_))));
+
+    let mut chains: Vec<OptionLifecycle> = Vec::new();
+    let mut active: HashMap<Conid, usize> = HashMap::new();
+    for (date, source) in events {
+        match source {
+            Source::Trade(trade) => apply_trade(&mut chains, &mut active, trade, date),
+            Source::Eae(eae) => apply_eae(&mut chains, &mut active, eae),
+        }
+    }
+
+    let mut used: HashSet<usize> = HashSet::new();
+    for chain in &mut chains {
+        link_deliveries(chain, &trades, &mut used);
+    }
+    chains
+}
+
+enum Source<'a> {
+    Trade(&'a Trade),
+    Eae(&'a OptionEAE),
+}
+
+fn is_option(category: &AssetCategory) -> bool {
+    matches!(
+        category,
+        AssetCategory::Option | AssetCategory::FutureOption
+    )
+}
+
+fn apply_trade(
+    chains: &mut Vec<OptionLifecycle>,
+    active: &mut HashMap<Conid, usize>,
+    trade: &Trade,
+    date: NaiveDate,
+) {
+    let Some(quantity) = trade.signed_quantity().filter(|q| !q.is_zero()) else {
+        return;
+    };
+    let index = *active.entry(trade.conid).or_insert_with(|| {
+        chains.push(OptionLifecycle {
+            conid: trade.conid,
+            symbol: trade.symbol.clone(),
+            underlying_conid: trade.underlying_conid,
+            underlying_symbol: trade.underlying_symbol.clone(),
+            put_call: trade.put_call.clone(),
+            strike: trade.strike,
+            expiry: trade.expiry,
+            currency: trade.currency.clone(),
+            multiplier: trade.multiplier.unwrap_or(Decimal::ONE),
+            open_date: date,
+            close_date: None,
+            outcome: OptionOutcome::Open,
+            open_quantity: Decimal::ZERO,
+            events: Vec::new(),
+            deliveries: Vec::new(),
+        });
+        chains.len() - 1
+    });
+    let chain = &mut chains[index];
+    let reduces = !chain.open_quantity.is_zero()
+        && quantity.is_sign_negative() != chain.open_quantity.is_sign_negative();
+    let kind = match OptionEventKind::from_codes(trade) {
+        Some(kind) => kind,
+        None if reduces => OptionEventKind::Close,
+        None => OptionEventKind::Open,
+    };
+    chain.events.push(OptionEvent {
+        date,
+        kind,
+        trade_id: trade.trade_id.clone(),
+        quantity,
+        cash: trade.net_proceeds().unwrap_or_default(),
+    });
+    chain.open_quantity += quantity;
+    if chain.open_quantity.is_zero() {
+        chain.close_date = Some(date);
+        chain.outcome = kind.outcome();
+        active.remove(&trade.conid);
+    }
+}
+
+fn apply_eae(chains: &mut [OptionLifecycle], active: &mut HashMap<Conid, usize>, eae: &OptionEAE) {
+    let Some(kind) = eae
+        .transaction_type
+        .as_ref()
+        .and_then(OptionEventKind::from_action)
+    else {
+        return;
+    };
+    let conid = eae
+        .conid
+        .as_deref()
+        .and_then(|c| c.trim().parse::<Conid>().ok());
+    let matches_contract = |chain: &OptionLifecycle| match conid {
+        Some(conid) => chain.conid == conid,
+        None => chain.symbol == eae.symbol,
+    };
+
+    // A trade booked for this event: make sure it is classified by the EAE row
+    let trade_id = eae.trade_id.as_ref().filter(|id| !id.is_empty());
+    let booked = chains
+        .iter_mut()
+        .rev()
+        .filter(|c| matches_contract(c))
+        .find_map(|chain| {
+            let event = chain.events.iter().position(|event| match trade_id {
+                Some(id) => event.trade_id.as_ref() == Some(id),
+                None => event.date == eae.date && event.kind == OptionEventKind::Close,
+            })?;
+            Some((chain, event))
+        });
+    if let Some((chain, event)) = booked {
+        chain.events[event].kind = kind;
+        if chain.is_closed() && chain.close_date == Some(chain.events[event].date) {
+            chain.outcome = kind.outcome();
+        }
+        return;
+    }
+    let already = chains.iter().filter(|c| matches_contract(c)).any(|chain| {
+        chain
+            .events
+            .iter()
+            .any(|e| e.date == eae.date && e.kind == kind)
+    });
+    if already {
+        return;
+    }
+
+    // No trade for the event: close what is still open
+    let Some((&conid, &index)) = active
+        .iter()
+        .find(|(_, &index)| matches_contract(&chains[index]))
+    else {
+        return;
+    };
+    let chain = &mut chains[index];
+    let mut quantity = -chain.open_quantity;
+    if !eae.quantity.is_zero() && eae.quantity.abs() < quantity.abs() {
+        quantity = if quantity.is_sign_negative() {
+            -eae.quantity.abs()
+        } else {
+            eae.quantity.abs()
+        };
+    }
+    chain.events.push(OptionEvent {
+        date: eae.date,
+        kind,
+        trade_id: None,
+        quantity,
+        cash: eae.proceeds.unwrap_or_default() + eae.commission.unwrap_or_default(),
+    });
+    chain.open_quantity += quantity;
+    if chain.open_quantity.is_zero() {
+        chain.close_date = Some(eae.date);
+        chain.outcome = kind.outcome();
+        active.remove(&conid);
+    }
+}
+
+fn link_deliveries(chain: &mut OptionLifecycle, trades: &[&Trade], used: &mut HashSet<usize>) {
+    let Some(underlying) = chain.underlying_conid else {
+        return;
+    };
+    let dates: Vec<NaiveDate> = chain
+        .events
+        .iter()
+        .filter(|event| event.kind.delivers())
+        .map(|event| event.date)
+        .collect();
+    for date in dates {
+        let candidates: Vec<usize> = trades
+            .iter()
+            .enumerate()
+            .filter(|(i, t)| {
+                !used.contains(i)
+                    && t.conid == underlying
+                    && t.date() == Some(date)
+                    && (t.codes().is_assignment() || t.codes().is_exercise())
+            })
+            .map(|(i, _)| i)
+            .collect();
+        let Some(index) = candidates
+            .iter()
+            .copied()
+            .find(|&i| chain.strike.is_some() && trades[i].price == chain.strike)
+            .or_else(|| candidates.first().copied())
+        else {
+            continue;
+        };
+        used.insert(index);
+        let trade = trades[index];
+        chain.deliveries.push(UnderlyingDelivery {
+            date,
+            trade_id: trade.trade_id.clone(),
+            conid: trade.conid,
+            symbol: trade.symbol.clone(),
+            quantity: trade.signed_quantity().unwrap_or_default(),
+            price: trade.price,
+            cash: trade.net_proceeds().unwrap_or_default(),
+            realized_pnl: trade.fifo_pnl_realized.unwrap_or_default(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_activity_flex;
+
+    const XML: &str = r#"<FlexQueryResponse queryName="Test" type="AF">
+<FlexStatements count="1">
+<FlexStatement accountId="U1234567" fromDate="2025-01-01" toDate="2025-03-31" period="Quarterly" whenGenerated="2025-04-01;120000">
+<Trades>
+<Trade accountId="U1234567" currency="USD" assetCategory="OPT" symbol="XYZ   250221P00050000" conid="101" underlyingConid="1" underlyingSymbol="XYZ" multiplier="100" strike="50" expiry="2025-02-21" putCall="P" transactionID="1" tradeID="1" tradeDate="2025-01-10" buySell="SELL" quantity="-1" price="2" proceeds="200" ibCommission="-1" netCash="199" levelOfDetail="EXECUTION" />
+<Trade accountId="U1234567" currency="USD" assetCategory="OPT" symbol="XYZ   250221P00050000" conid="101" underlyingConid="1" underlyingSymbol="XYZ" multiplier="100" strike="50" expiry="2025-02-21" putCall="P" transactionID="2" tradeID="2" tradeDate="2025-02-21" buySell="BUY" quantity="1" price="0" proceeds="0" ibCommission="0" netCash="0" notes="A" levelOfDetail="EXECUTION" />
+<Trade accountId="U1234567" currency="USD" assetCategory="STK" symbol="XYZ" conid="1" transactionID="3" tradeID="3" tradeDate="2025-02-21" buySell="BUY" quantity="100" price="50" proceeds="-5000" ibCommission="0" netCash="-5000" notes="A" levelOfDetail="EXECUTION" />
+<Trade accountId="U1234567" currency="USD" assetCategory="OPT" symbol="XYZ   250321C00055000" conid="102" underlyingConid="1" underlyingSymbol="XYZ" multiplier="100" strike="55" expiry="2025-03-21" putCall="C" transactionID="4" tradeID="4" tradeDate="2025-02-24" buySell="SELL" quantity="-1" price="1.5" proceeds="150" ibCommission="-1" netCash="149" levelOfDetail="EXECUTION" />
+<Trade accountId="U1234567" currency="USD" assetCategory="STK" symbol="XYZ" conid="1" transactionID="5" tradeID="5" tradeDate="2025-03-21" buySell="SELL" quantity="-100" price="55" proceeds="5500" ibCommission="0" netCash="5500" fifoPnlRealized="500" notes="A" levelOfDetail="EXECUTION" />
+<Trade accountId="U1234567" currency="USD" assetCategory="OPT" symbol="XYZ   250321P00045000" conid="103" underlyingConid="1" underlyingSymbol="XYZ" multiplier="100" strike="45" expiry="2025-03-21" putCall="P" transactionID="6" tradeID="6" tradeDate="2025-03-03" buySell="SELL" quantity="-2" price="0.5" proceeds="100" ibCommission="-2" netCash="98" levelOfDetail="EXECUTION" />
+</Trades>
+<OptionEAE>
+<OptionEAE accountId="U1234567" transactionType="Assignment" date="2025-02-21" conid="101" symbol="XYZ   250221P00050000" quantity="1" tradeID="2" />
+<OptionEAE accountId="U1234567" transactionType="Assignment" date="2025-03-21" conid="102" symbol="XYZ   250321C00055000" quantity="1" />
+<OptionEAE accountId="U1234567" transactionType="Expiration" date="2025-03-21" conid="103" symbol="XYZ   250321P00045000" quantity="2" />
+</OptionEAE>
+</FlexStatement>
+</FlexStatements>
+</FlexQueryResponse>"#;
+
+    #[test]
+    fn test_cash_secured_put_assignment() {
+        let statement = parse_activity_flex(XML).unwrap();
+        let chains = option_lifecycles([&statement]);
+        assert_eq!(chains.len(), 3);
+
+        let put = &chains[0];
+        assert!(put.is_short());
+        assert_eq!(put.outcome, OptionOutcome::Assigned);
+        assert_eq!(put.close_date, NaiveDate::from_ymd_opt(2025, 2, 21));
+        assert_eq!(put.premium(), Decimal::from(199));
+        assert_eq!(put.deliveries.len(), 1);
+        assert_eq!(put.deliveries[0].quantity, Decimal::from(100));
+        assert_eq!(put.effective_price(), Some(Decimal::new(4801, 2)));
+    }
+
+    #[test]
+    fn test_covered_call_assigned_by_eae_row_only() {
+        let statement = parse_activity_flex(XML).unwrap();
+        let chains = option_lifecycles([&statement]);
+
+        let call = &chains[1];
+        assert_eq!(call.outcome, OptionOutcome::Assigned);
+        assert_eq!(call.open_quantity, Decimal::ZERO);
+        assert_eq!(
+            call.events.last().unwrap().kind,
+            OptionEventKind::Assignment
+        );
+        assert_eq!(call.deliveries[0].trade_id, Some(TradeId::from("5")));
+        assert_eq!(call.total_pnl(), Decimal::from(649));
+        assert_eq!(call.effective_price(), Some(Decimal::new(5649, 2)));
+    }
+
+    #[test]
+    fn test_expired_put_keeps_premium() {
+        let statement = parse_activity_flex(XML).unwrap();
+        let chains = option_lifecycles([&statement]);
+
+        let expired = &chains[2];
+        assert_eq!(expired.outcome, OptionOutcome::Expired);
+        assert_eq!(expired.events.len(), 2);
+        assert!(expired.deliveries.is_empty());
+        assert_eq!(expired.total_pnl(), Decimal::from(98));
+        assert_eq!(expired.effective_price(), None);
+    }
+}
@@ -0,0 +1,335 @@
+//! Realized and unrealized P&L in the account base currency
+//!
+//! IB reports `fifoPnlRealized` and `fifoPnlUnrealized` in each row's own
+//! currency. [`base_pnl`] converts them with the row's `fxRateToBase`,
+//! falling back to the `ConversionRates` section, and totals them per
+//! symbol, asset category, and period, rounded by a [`RoundingPolicy`].
+//!
+//! # Example
+//! ```
+//! use ib_flex::analytics::{base_pnl, PnlPeriod, RoundingPolicy};
+//! use ib_flex::parse_activity_flex;
+//!
+//! let xml = include_str!("../../tests/fixtures/activity_minimal.xml");
+//! let statement = parse_activity_flex(xml).unwrap();
+//!
+//! let report = base_pnl([&statement], PnlPeriod::Month, &RoundingPolicy::cents());
+//! println!("realized {} unrealized {}", report.total.realized, report.total.unrealized);
+//! for (month, pnl) in &report.by_period {
+//!     println!("{month}: {}", pnl.total());
+//! }
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{Datelike, NaiveDate};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+#[cfg(feature = "serialize")]
+use serde::Serialize;
+
+use super::RoundingPolicy;
+use crate::types::traits::Dated;
+use crate::types::{ActivityFlexStatement, AssetCategory, FxRateTable, LevelOfDetail};
+
+/// Realized and unrealized P&L in the base currency
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct PnlSummary {
+    /// Realized P&L from closing trades
+    pub realized: Decimal,
+    /// Unrealized P&L on open positions
+    pub unrealized: Decimal,
+}
+
+impl PnlSummary {
+    /// Realized plus unrealized P&L
+    pub fn total(&self) -> Decimal {
+        self.realized + self.unrealized
+    }
+
+    fn map(self, f: impl Fn(Decimal) -> Decimal) -> Self {
+        PnlSummary {
+            realized: f(self.realized),
+            unrealized: f(self.unrealized),
+        }
+    }
+}
+
+/// Bucket size for [`BasePnlReport::by_period`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub enum PnlPeriod {
+    /// One bucket per calendar day
+    Day,
+    /// One bucket per calendar month
+    #[default]
+    Month,
+    /// One bucket per calendar year
+    Year,
+}
+
+impl PnlPeriod {
+    /// First day of the period containing `date`
+    pub fn start(&self, date: NaiveDate) -> NaiveDate {
+        match self {
+            PnlPeriod::Day => date,
+            PnlPeriod::Month => date.with_day(1).unwrap_or(date),
+            PnlPeriod::Year => date.with_ordinal(1).unwrap_or(date),
+        }
+    }
+}
+
+/// A row whose P&L could not be converted to the base currency
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct MissingRate {
+    /// Currency of the row
+    pub currency: String,
+    /// Date a rate was needed for
+    pub date: NaiveDate,
+}
+
+/// Result of [`base_pnl`]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct BasePnlReport {
+    /// Base currency, when the conversion rates identify one
+    pub base_currency: Option<String>,
+    /// Totals across all rows
+    pub total: PnlSummary,
+    /// Totals per symbol
+    pub by_symbol: BTreeMap<String, PnlSummary>,
+    /// Totals per asset category
+    pub by_asset_category: HashMap<AssetCategory, PnlSummary>,
+    /// Totals per period, keyed by the first day of the period
+    ///
+    /// Realized P&L falls in the period of the trade date; unrealized P&L in
+    /// the period of the position's report date.
+    pub by_period: BTreeMap<NaiveDate, PnlSummary>,
+    /// Rows left out of every total because no rate was available
+    pub missing_rates: Vec<MissingRate>,
+}
+
+impl BasePnlReport {
+    fn add(&mut self, symbol: &str, category: &AssetCategory, period: NaiveDate, pnl: PnlSummary) {
+        for summary in [
+            &mut self.total,
+            self.by_symbol.entry(symbol.to_string()).or_default(),
+            self.by_asset_category.entry(category.clone()).or_default(),
+            self.by_period.entry(period).or_default(),
+        ] {
+            summary.realized += pnl.realized;
+            summary.unrealized += pnl.unrealized;
+        }
+    }
+
+    fn round_totals(&mut self, rounding: &RoundingPolicy) {
+        let round = |summary: &mut PnlSummary| *summary = summary.map(|a| rounding.total(a));
+        round(&mut self.total);
+        self.by_symbol.values_mut().for_each(round);
+        self.by_asset_category.values_mut().for_each(round);
+        self.by_period.values_mut().for_each(round);
+    }
+}
+
+/// Total realized and unrealized P&L in the base currency
+///
+/// Realized P&L comes from trade executions' `fifoPnlRealized`; unrealized
+/// P&L from the summary rows of `OpenPositions`, so pass statements whose
+/// positions are the ones to report (usually just the latest). Rows without
+/// `fxRateToBase` are converted with the statements' combined
+/// `ConversionRates` as of the row date, and rows already in the base
+/// currency need no rate. `rounding` applies to the converted amount of
+/// each row, or to the totals.
+pub fn base_pnl<'a, I>(statements: I, period: PnlPeriod, rounding: &RoundingPolicy) -> BasePnlReport
+where
+    I: IntoIterator<Item = &'a ActivityFlexStatement>,
+{
+    let statements: Vec<&ActivityFlexStatement> = statements.into_iter().collect();
+    let table = FxRateTable::from_rates(
+        statements
+            .iter()
+            .flat_map(|s| s.conversion_rates.items.iter()),
+    );
+    let mut report = BasePnlReport {
+        base_currency: table.base_currency().map(str::to_string),
+        ..Default::default()
+    };
+    let mut missing = Vec::new();
+    let mut rate = |currency: &str, fx_rate: Option<Decimal>, date: NaiveDate| {
+        let rate = fx_rate
+            .or_else(|| table.to_base(Decimal::ONE, currency, date))
+            .or_else(|| (table.base_currency() == Some(currency)).then_some(Decimal::ONE));
+        if rate.is_none() {
+            missing.push(MissingRate {
+                currency: currency.to_string(),
+                date,
+            });
+        }
+        rate
+    };
+
+    for statement in &statements {
+        for trade in &statement.trades {
+            if !matches!(trade.level_of_detail, None | Some(LevelOfDetail::Execution)) {
+                continue;
+            }
+            let (Some(pnl), Some(date)) = (trade.fifo_pnl_realized, trade.date()) else {
+                continue;
+            };
+            if pnl.is_zero() {
+                continue;
+            }
+            let Some(fx) = rate(&trade.currency, trade.fx_rate_to_base, date) else {
+                continue;
+            };
+            let realized = PnlSummary {
+                realized: rounding.record(pnl * fx),
+                unrealized: Decimal::ZERO,
+            };
+            report.add(
+                &trade.symbol,
+                &trade.asset_category,
+                period.start(date),
+                realized,
+            );
+        }
+
+        for position in &statement.positions {
+            if position.level_of_detail == Some(LevelOfDetail::Lot) {
+                continue;
+            }
+            let Some(pnl) = position.unrealized_pnl() else {
+                continue;
+            };
+            let date = position.report_date;
+            let Some(fx) = rate(&position.currency, position.fx_rate_to_base, date) else {
+                continue;
+            };
+            let unrealized = PnlSummary {
+                realized: Decimal::ZERO,
+                unrealized: rounding.record(pnl * fx),
+            };
+            report.add(
+                &position.symbol,
+                &position.asset_category,
+                period.start(date),
+                unrealized,
+            );
+        }
+    }
+
+    missing.sort();
+    missing.dedup();
+    report.missing_rates = missing;
+    report.round_totals(rounding);
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{ConversionRate, PositionBuilder, TradeBuilder};
+
+    fn day(month: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, month, d).unwrap()
+    }
+
+    fn statement() -> ActivityFlexStatement {
+        let xml = include_str!("../../tests/fixtures/activity_minimal.xml");
+        let mut statement = crate::parse_activity_flex(xml).unwrap();
+        statement.trades.items.clear();
+        statement.conversion_rates.items.push(ConversionRate {
+            report_date: day(1, 2),
+            from_currency: "EUR".to_string(),
+            to_currency: "USD".to_string(),
+            rate: Decimal::new(11, 1),
+        });
+        statement
+    }
+
+    #[test]
+    fn test_base_pnl_breakdowns() {
+        let mut statement = statement();
+        let usd = TradeBuilder::new("AAPL")
+            .trade_date(day(1, 10))
+            .quantity(-10)
+            .fifo_pnl_realized(100)
+            .fx_rate_to_base(1)
+            .build();
+        let eur = TradeBuilder::new("SAP")
+            .trade_date(day(2, 3))
+            .currency("EUR")
+            .quantity(-5)
+            .fifo_pnl_realized(-50)
+            .build();
+        statement.trades.items.extend([usd, eur]);
+        statement.positions.items.push(
+            PositionBuilder::new("AAPL")
+                .quantity(10)
+                .mark_price(200)
+                .report_date(day(2, 28))
+                .fifo_pnl_unrealized(300)
+                .fx_rate_to_base(1)
+                .build(),
+        );
+
+        let report = base_pnl([&statement], PnlPeriod::Month, &RoundingPolicy::default());
+        assert_eq!(report.base_currency.as_deref(), Some("USD"));
+        assert!(report.missing_rates.is_empty());
+        assert_eq!(report.total.realized, Decimal::from(45));
+        assert_eq!(report.total.unrealized, Decimal::from(300));
+        assert_eq!(report.by_symbol["SAP"].realized, Decimal::from(-55));
+        assert_eq!(report.by_symbol["AAPL"].total(), Decimal::from(400));
+        assert_eq!(report.by_period[&day(1, 1)].realized, Decimal::from(100));
+        assert_eq!(report.by_period[&day(2, 1)].total(), Decimal::from(245));
+        assert_eq!(
+            report.by_asset_category[&AssetCategory::Stock].total(),
+            Decimal::from(345)
+        );
+    }
+
+    #[test]
+    fn test_rounding_per_record_or_final() {
+        let mut statement = statement();
+        for date in [day(1, 10), day(1, 17)] {
+            let trade = TradeBuilder::new("AAPL")
+                .trade_date(date)
+                .quantity(-1)
+                .fifo_pnl_realized(Decimal::new(1005, 3))
+                .fx_rate_to_base(1)
+                .build();
+            statement.trades.items.push(trade);
+        }
+
+        let per_record = base_pnl([&statement], PnlPeriod::Month, &RoundingPolicy::cents());
+        assert_eq!(per_record.total.realized, Decimal::new(202, 2));
+        let at_end = RoundingPolicy::cents().with_stage(crate::analytics::RoundingStage::Final);
+        let at_end = base_pnl([&statement], PnlPeriod::Month, &at_end);
+        assert_eq!(at_end.total.realized, Decimal::new(201, 2));
+        assert_eq!(at_end.by_symbol["AAPL"].realized, Decimal::new(201, 2));
+    }
+
+    #[test]
+    fn test_missing_rate_is_reported() {
+        let mut statement = statement();
+        let trade = TradeBuilder::new("TM")
+            .trade_date(day(1, 10))
+            .currency("JPY")
+            .quantity(-100)
+            .fifo_pnl_realized(1000)
+            .build();
+        statement.trades.items.push(trade);
+
+        let report = base_pnl([&statement], PnlPeriod::Year, &RoundingPolicy::default());
+        assert_eq!(report.total, PnlSummary::default());
+        assert_eq!(
+            report.missing_rates,
+            [MissingRate {
+                currency: "JPY".to_string(),
+                date: day(1, 10),
+            }]
+        );
+    }
+}
@@ -0,0 +1,454 @@
+//! Consolidation across accounts
+//!
+//! Advisor and family setups spread one portfolio over several IB
+//! accounts, possibly with different base currencies. [`Portfolio`] holds
+//! statements from all of them and reports consolidated positions, cash,
+//! P&L, and fees in a single reporting currency.
+//!
+//! Amounts are first taken to each account's base currency with the row's
+//! `fxRateToBase`, then converted into the reporting currency with the
+//! `ConversionRates` of all statements, plus any rates added with
+//! [`Portfolio::with_rate`]. A missing rate is an error rather than a
+//! silently dropped amount.
+//!
+//! # Example
+//! ```
+//! use ib_flex::analytics::Portfolio;
+//! use ib_flex::parse_activity_flex;
+//!
+//! let xml = include_str!("../../tests/fixtures/activity_minimal.xml");
+//! let statement = parse_activity_flex(xml).unwrap();
+//!
+//! let portfolio = Portfolio::new("USD").with_statement(statement);
+//! let summary = portfolio.summary().unwrap();
+//! println!(
+//!     "{} accounts: market value {} cash {} fees {}",
+//!     portfolio.accounts().len(),
+//!     summary.market_value,
+//!     summary.cash,
+//!     summary.fees
+//! );
+//! ```
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+#[cfg(feature = "serialize")]
+use serde::Serialize;
+
+use crate::error::{ParseError, Result};
+use crate::types::traits::Dated;
+use crate::types::{
+    AccountId, ActivityFlexStatement, AssetCategory, Conid, FxRateTable, LevelOfDetail,
+};
+
+/// IB's pseudo-currency for the base-currency totals of `CashReport`
+const BASE_SUMMARY: &str = "BASE_SUMMARY";
+
+/// A position held across one or more accounts
+///
+/// Values are in the portfolio's reporting currency.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct ConsolidatedPosition {
+    /// Contract held
+    pub conid: Conid,
+    /// Symbol of the contract
+    pub symbol: String,
+    /// Asset category
+    pub asset_category: AssetCategory,
+    /// Currency the contract trades in
+    pub currency: String,
+    /// Signed quantity summed over accounts
+    pub quantity: Decimal,
+    /// Market value
+    pub market_value: Decimal,
+    /// Unrealized P&L, from IB's `fifoPnlUnrealized`
+    pub unrealized_pnl: Decimal,
+    /// Accounts holding the contract
+    pub accounts: Vec<AccountId>,
+}
+
+/// Consolidated totals in the reporting currency
+#[derive(Debug, Clone, PartialEq, Eq, Default, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct PortfolioSummary {
+    /// Reporting currency
+    pub currency: String,
+    /// Market value of open positions
+    pub market_value: Decimal,
+    /// Ending cash balances
+    pub cash: Decimal,
+    /// P&L realized on trades
+    pub realized_pnl: Decimal,
+    /// Unrealized P&L of open positions
+    pub unrealized_pnl: Decimal,
+    /// Commissions, trade taxes, and other fees, negative when charged
+    pub fees: Decimal,
+}
+
+impl PortfolioSummary {
+    /// Market value plus cash
+    pub fn net_liquidation(&self) -> Decimal {
+        self.market_value + self.cash
+    }
+}
+
+/// Statements from several accounts, reported in one currency
+///
+/// Positions and cash come from the latest statement of each account, while
+/// P&L and fees add up every statement, so statements of one account should
+/// not overlap; combine overlapping downloads with
+/// [`merge_statements`](crate::merge_statements) first.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "eq", derive(PartialEq))]
+pub struct Portfolio {
+    currency: String,
+    statements: Vec<ActivityFlexStatement>,
+    rates: FxRateTable,
+}
+
+impl Portfolio {
+    /// Empty portfolio reporting in `currency`
+    pub fn new(currency: impl Into<String>) -> Self {
+        let currency = currency.into();
+        Portfolio {
+            rates: FxRateTable::new().with_base_currency(currency.clone()),
+            currency,
+            statements: Vec::new(),
+        }
+    }
+
+    /// Add a statement
+    pub fn with_statement(mut self, statement: ActivityFlexStatement) -> Self {
+        self.add_statement(statement);
+        self
+    }
+
+    /// Add a statement
+    pub fn add_statement(&mut self, statement: ActivityFlexStatement) {
+        for rate in statement.conversion_rates.iter() {
+            self.rates.insert(
+                &rate.from_currency,
+                &rate.to_currency,
+                rate.report_date,
+                rate.rate,
+            );
+        }
+        self.statements.push(statement);
+    }
+
+    /// Add a rate converting one unit of `from` into `to` from `date` on
+    ///
+    /// Needed when no statement reports the rate between an account's base
+    /// currency and the reporting currency.
+    pub fn with_rate(
+        mut self,
+        from: &str,
+        to: &str,
+        date: NaiveDate,
+        rate: impl Into<Decimal>,
+    ) -> Self {
+        self.rates.insert(from, to, date, rate.into());
+        self
+    }
+
+    /// Reporting currency
+    pub fn currency(&self) -> &str {
+        &self.currency
+    }
+
+    /// Statements in the portfolio
+    pub fn statements(&self) -> &[ActivityFlexStatement] {
+        &self.statements
+    }
+
+    /// Accounts with at least one statement
+    pub fn accounts(&self) -> Vec<&AccountId> {
+        let accounts: BTreeSet<&AccountId> =
+            self.statements.iter().map(|s| &s.account_id).collect();
+        accounts.into_iter().collect()
+    }
+
+    /// Open positions across accounts, one per contract, ordered by symbol
+    pub fn positions(&self) -> Result<Vec<ConsolidatedPosition>> {
+        let mut positions: HashMap<Conid, ConsolidatedPosition> = HashMap::new();
+        for statement in self.latest_statements() {
+            let base = account_currency(statement, &self.currency);
+            for position in statement
+                .positions
+                .iter()
+                .filter(|p| p.level_of_detail != Some(LevelOfDetail::Lot))
+            {
+                let date = position.report_date;
+                let market_value = self.convert(position.market_value_in_base(), &base, date)?;
+                let unrealized = position.fifo_pnl_unrealized.unwrap_or_default()
+                    * position.fx_rate_to_base.unwrap_or(Decimal::ONE);
+                let unrealized = self.convert(unrealized, &base, date)?;
+                let entry =
+                    positions
+                        .entry(position.conid)
+                        .or_insert_with(|| ConsolidatedPosition {
+                            conid: position.conid,
+                            symbol: position.symbol.clone(),
+                            asset_category: position.asset_category.clone(),
+                            currency: position.currency.clone(),
+                            quantity: Decimal::ZERO,
+                            market_value: Decimal::ZERO,
+                            unrealized_pnl: Decimal::ZERO,
+                            accounts: Vec::new(),
+                        });
+                entry.quantity += position.signed_quantity();
+                entry.market_value += market_value;
+                entry.unrealized_pnl += unrealized;
+                if !entry.accounts.contains(&statement.account_id) {
+                    entry.accounts.push(statement.account_id.clone());
+                }
+            }
+        }
+        let mut positions: Vec<ConsolidatedPosition> = positions.into_values().collect();
+        positions.sort_by(|a, b| a.symbol.cmp(&b.symbol).then(a.conid.cmp(&b.conid)));
+        Ok(positions)
+    }
+
+    /// Ending cash per currency across accounts, in each currency
+    pub fn cash_by_currency(&self) -> BTreeMap<String, Decimal> {
+        let mut cash: BTreeMap<String, Decimal> = BTreeMap::new();
+        for statement in self.latest_statements() {
+            for row in statement
+                .cash_report
+                .iter()
+                .filter(|r| r.currency != BASE_SUMMARY)
+            {
+                *cash.entry(row.currency.clone()).or_default() += row.ending_cash;
+            }
+        }
+        cash
+    }
+
+    /// Ending cash across accounts
+    ///
+    /// Uses each account's `BASE_SUMMARY` row when reported, and otherwise
+    /// converts the per-currency rows through the account's own rates.
+    pub fn cash(&self) -> Result<Decimal> {
+        let mut total = Decimal::ZERO;
+        for statement in self.latest_statements() {
+            let base = account_currency(statement, &self.currency);
+            let date = statement.to_date;
+            if let Some(summary) = statement
+                .cash_report
+                .iter()
+                .find(|r| r.currency == BASE_SUMMARY)
+            {
+                total += self.convert(summary.ending_cash, &base, date)?;
+                continue;
+            }
+            let rates = statement
+                .conversion_rates
+                .to_table()
+                .with_base_currency(base.clone());
+            for row in statement.cash_report.iter() {
+                let in_base = rates
+                    .convert(row.ending_cash, &row.currency, &base, date)
+                    .ok_or_else(|| missing_rate(&row.currency, &base, date))?;
+                total += self.convert(in_base, &base, date)?;
+            }
+        }
+        Ok(total)
+    }
+
+    /// P&L realized on trades (`fifoPnlRealized`) across all statements
+    pub fn realized_pnl(&self) -> Result<Decimal> {
+        let mut total = Decimal::ZERO;
+        for statement in &self.statements {
+            let base = account_currency(statement, &self.currency);
+            for trade in executions(statement) {
+                let pnl = trade.fifo_pnl_realized.unwrap_or_default()
+                    * trade.fx_rate_to_base.unwrap_or(Decimal::ONE);
+                let date = trade.date().unwrap_or(statement.to_date);
+                total += self.convert(pnl, &base, date)?;
+            }
+        }
+        Ok(total)
+    }
+
+    /// Commissions and taxes on trades plus fee cash transactions
+    pub fn fees(&self) -> Result<Decimal> {
+        let mut total = Decimal::ZERO;
+        for statement in &self.statements {
+            let base = account_currency(statement, &self.currency);
+            for trade in executions(statement) {
+                let fx = trade.fx_rate_to_base.unwrap_or(Decimal::ONE);
+                let cost =
+                    (trade.commission.unwrap_or_default() + trade.taxes.unwrap_or_default()) * fx;
+                let date = trade.date().unwrap_or(statement.to_date);
+                total += self.convert(cost, &base, date)?;
+            }
+            for tx in statement.cash_transactions.iter().filter(|t| t.is_fee()) {
+                let amount = tx.amount * tx.fx_rate_to_base.unwrap_or(Decimal::ONE);
+                let date = tx.date().unwrap_or(statement.to_date);
+                total += self.convert(amount, &base, date)?;
+            }
+        }
+        Ok(total)
+    }
+
+    /// Consolidated totals
+    pub fn summary(&self) -> Result<PortfolioSummary> {
+        let positions = self.positions()?;
+        Ok(PortfolioSummary {
+            currency: self.currency.clone(),
+            market_value: positions.iter().map(|p| p.market_value).sum(),
+            cash: self.cash()?,
+            realized_pnl: self.realized_pnl()?,
+            unrealized_pnl: positions.iter().map(|p| p.unrealized_pnl).sum(),
+            fees: self.fees()?,
+        })
+    }
+
+    /// Latest statement of each account
+    fn latest_statements(&self) -> impl Iterator<Item = &ActivityFlexStatement> {
+        let mut latest: BTreeMap<&AccountId, &ActivityFlexStatement> = BTreeMap::new();
+        for statement in &self.statements {
+            let entry = latest.entry(&statement.account_id).or_insert(statement);
+            if statement.to_date >= entry.to_date {
+                *entry = statement;
+            }
+        }
+        latest.into_values()
+    }
+
+    fn convert(&self, amount: Decimal, from: &str, date: NaiveDate) -> Result<Decimal> {
+        self.rates
+            .convert(amount, from, &self.currency, date)
+            .ok_or_else(|| missing_rate(from, &self.currency, date))
+    }
+}
+
+fn executions(
+    statement: &ActivityFlexStatement,
+) -> impl Iterator<Item = &crate::types::Trade> + '_ {
+    statement
+        .trades
+        .iter()
+        .filter(|t| matches!(t.level_of_detail, None | Some(LevelOfDetail::Execution)))
+}
+
+/// Base currency of the statement's account, assumed to be `fallback` when
+/// the statement does not say
+fn account_currency(statement: &ActivityFlexStatement, fallback: &str) -> String {
+    super::account_base_currency(statement).unwrap_or_else(|| fallback.to_string())
+}
+
+fn missing_rate(from: &str, to: &str, date: NaiveDate) -> ParseError {
+    ParseError::MissingFxRate {
+        from: from.to_string(),
+        to: to.to_string(),
+        date,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{
+        CashTransactionBuilder, CashTransactionType, ConversionRate, PositionBuilder, TradeBuilder,
+    };
+
+    fn day(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 6, d).unwrap()
+    }
+
+    fn account(id: &str, currency: &str) -> ActivityFlexStatement {
+        let xml = include_str!("../../tests/fixtures/activity_minimal.xml");
+        let mut statement = crate::parse_activity_flex(xml).unwrap();
+        statement.account_id = id.into();
+        statement.to_date = day(30);
+        statement.cash_report.items.clear();
+        statement.cash_transactions.items.clear();
+        // Rates against the base currency are how it is inferred without
+        // an AccountInformation section
+        statement.conversion_rates.items = vec![ConversionRate {
+            report_date: day(30),
+            from_currency: "GBP".to_string(),
+            to_currency: currency.to_string(),
+            rate: Decimal::new(115, 2),
+        }];
+        statement.positions.items = vec![PositionBuilder::new("AAPL")
+            .conid(265598)
+            .quantity(10)
+            .mark_price(200)
+            .report_date(day(30))
+            .fifo_pnl_unrealized(100)
+            .build()];
+        statement.trades.items = vec![TradeBuilder::new("AAPL")
+            .conid(265598)
+            .trade_date(day(10))
+            .quantity(-5)
+            .fifo_pnl_realized(50)
+            .commission(-1)
+            .build()];
+        statement
+    }
+
+    #[test]
+    fn test_consolidates_accounts_in_reporting_currency() {
+        let usd = account("U1", "USD");
+        // A EUR-based account holding the same USD stock
+        let mut eur = account("U2", "EUR");
+        for position in eur.positions.items.iter_mut() {
+            position.fx_rate_to_base = Some(Decimal::new(9, 1));
+        }
+        eur.trades.items[0].fx_rate_to_base = Some(Decimal::new(9, 1));
+        eur.cash_transactions.items =
+            vec![
+                CashTransactionBuilder::new(CashTransactionType::OtherFees, -9)
+                    .currency("EUR")
+                    .date(day(15))
+                    .build(),
+            ];
+
+        let portfolio = Portfolio::new("USD")
+            .with_statement(usd)
+            .with_statement(eur);
+        assert!(portfolio.summary().is_err());
+
+        let portfolio = portfolio.with_rate("EUR", "USD", day(1), Decimal::new(125, 2));
+        let positions = portfolio.positions().unwrap();
+        assert_eq!(positions.len(), 1);
+        assert_eq!(positions[0].quantity, Decimal::from(20));
+        assert_eq!(positions[0].accounts.len(), 2);
+        // 2000 + 2000 * 0.9 * 1.25
+        assert_eq!(positions[0].market_value, Decimal::new(42500, 1));
+
+        let summary = portfolio.summary().unwrap();
+        assert_eq!(summary.unrealized_pnl, Decimal::new(2125, 1));
+        assert_eq!(summary.realized_pnl, Decimal::new(10625, 2));
+        // -1 - 1 * 0.9 * 1.25 - 9 * 1.25
+        assert_eq!(summary.fees, Decimal::new(-13375, 3));
+    }
+
+    #[test]
+    fn test_cash_from_cash_report() {
+        let xml = r#"<FlexQueryResponse queryName="Test" type="AF">
+<FlexStatements count="1">
+<FlexStatement accountId="U1" fromDate="2025-06-01" toDate="2025-06-30" period="Custom" whenGenerated="2025-07-01;120000">
+<CashReport>
+<CashReportCurrency accountId="U1" currency="USD" fromDate="2025-06-01" toDate="2025-06-30" startingCash="0" endingCash="1000" />
+<CashReportCurrency accountId="U1" currency="EUR" fromDate="2025-06-01" toDate="2025-06-30" startingCash="0" endingCash="200" />
+</CashReport>
+<ConversionRates>
+<ConversionRate reportDate="2025-06-30" fromCurrency="EUR" toCurrency="USD" rate="1.1" />
+</ConversionRates>
+</FlexStatement>
+</FlexStatements>
+</FlexQueryResponse>"#;
+        let statement = crate::parse_activity_flex(xml).unwrap();
+
+        let portfolio = Portfolio::new("USD").with_statement(statement);
+        assert_eq!(portfolio.cash().unwrap(), Decimal::from(1220));
+        assert_eq!(portfolio.cash_by_currency()["EUR"], Decimal::from(200));
+    }
+}
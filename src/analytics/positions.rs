@@ -0,0 +1,193 @@
+//! Position reconstruction from trade history
+//!
+//! [`reconstruct_positions`] replays trades, position transfers, and
+//! corporate actions through a [`LotLedger`] in date order and reports the
+//! resulting quantity and cost basis per conid as of any date, without
+//! relying on the `OpenPositions` snapshot.
+//!
+//! # Example
+//! ```
+//! use chrono::NaiveDate;
+//! use ib_flex::analytics::reconstruct_positions;
+//! use ib_flex::parse_activity_flex;
+//!
+//! let xml = include_str!("../../tests/fixtures/activity_minimal.xml");
+//! let statement = parse_activity_flex(xml).unwrap();
+//!
+//! let as_of = NaiveDate::from_ymd_opt(2025, 1, 15).unwrap();
+//! let positions = reconstruct_positions([&statement], &[], as_of);
+//! assert_eq!(positions[0].symbol, "AAPL");
+//! assert_eq!(positions[0].quantity, 100.into());
+//! ```
+
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+#[cfg(feature = "serialize")]
+use serde::Serialize;
+
+use super::corporate_actions::{group_actions, CorporateActionEngine};
+use super::lots::LotLedger;
+use crate::types::traits::Dated;
+use crate::types::{ActivityFlexStatement, Conid, CorporateAction, Position, Trade, Transfer};
+
+/// Net holding in one contract, rebuilt from history
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct ReconstructedPosition {
+    /// Contract ID
+    pub conid: Conid,
+    /// Ticker symbol
+    pub symbol: String,
+    /// Currency of the cost basis
+    pub currency: String,
+    /// Net quantity (negative for shorts)
+    pub quantity: Decimal,
+    /// Total cost basis of the open lots, signed like `quantity`
+    pub cost_basis: Decimal,
+    /// Number of open lots
+    pub lots: usize,
+}
+
+enum Event<'a> {
+    CorporateAction(Vec<&'a CorporateAction>),
+    Transfer(&'a Transfer),
+    Trade(&'a Trade),
+}
+
+/// Rebuild positions as of the end of `as_of`
+///
+/// `opening` seeds the ledger (typically `OpenPositions` from the
+/// statement before the replayed period); pass `&[]` to start flat. Events
+/// on the same date apply corporate actions first, then transfers, then
+/// trades in execution order. Corporate actions go through a
+/// [`CorporateActionEngine`], so mergers and symbol changes carry lots over
+/// to the new conid. Statements should not overlap, or their events are
+/// applied twice. Flat positions are omitted.
+pub fn reconstruct_positions<'a, I>(
+    statements: I,
+    opening: &[Position],
+    as_of: NaiveDate,
+) -> Vec<ReconstructedPosition>
+where
+    I: IntoIterator<Item = &'a ActivityFlexStatement>,
+{
+    let mut events: Vec<(NaiveDate, Event<'a>)> = Vec::new();
+    let mut actions: Vec<&CorporateAction> = Vec::new();
+    for statement in statements {
+        actions.extend(statement.corporate_actions.iter());
+        for transfer in &statement.transfers {
+            events.push((transfer.date, Event::Transfer(transfer)));
+        }
+        for trade in &statement.trades {
+            if let Some(date) = trade.date() {
+                events.push((date, Event::Trade(trade)));
+            }
+        }
+    }
+    for group in group_actions(actions) {
+        let date = group[0].date().unwrap_or(group[0].report_date);
+        events.push((date, Event::CorporateAction(group)));
+    }
+    events.retain(|(date, _)| *date <= as_of);
+    events.sort_by(|(a_date, a), (b_date, b)| {
+        a_date.cmp(b_date).then_with(|| match (a, b) {
+            (Event::Trade(a), Event::Trade(b)) => a.chronological_key().cmp(&b.chronological_key()),
+            _ => rank(a).cmp(&rank(b)),
+        })
+    });
+
+    let mut ledger = LotLedger::new();
+    ledger.add_positions(opening);
+    let mut engine = CorporateActionEngine::new();
+    for (_, event) in events {
+        match event {
+            Event::CorporateAction(rows) => engine.apply_action(&mut ledger, &rows),
+            Event::Transfer(transfer) => ledger.apply_transfer(transfer),
+            Event::Trade(trade) => ledger.apply_trade(trade),
+        }
+    }
+    positions_from_ledger(&ledger)
+}
+
+/// Summarize the open lots of `ledger` per conid
+pub fn positions_from_ledger(ledger: &LotLedger) -> Vec<ReconstructedPosition> {
+    let mut positions: Vec<ReconstructedPosition> = Vec::new();
+    for lot in ledger.open_lots() {
+        match positions.last_mut() {
+            Some(position) if position.conid == lot.conid => {
+                position.quantity += lot.quantity;
+                position.cost_basis += lot.cost_basis;
+                position.lots += 1;
+            }
+            _ => positions.push(ReconstructedPosition {
+                conid: lot.conid,
+                symbol: lot.symbol.clone(),
+                currency: lot.currency.clone(),
+                quantity: lot.quantity,
+                cost_basis: lot.cost_basis,
+                lots: 1,
+            }),
+        }
+    }
+    positions.retain(|p| !p.quantity.is_zero());
+    positions
+}
+
+fn rank(event: &Event<'_>) -> u8 {
+    match event {
+        Event::CorporateAction(_) => 0,
+        Event::Transfer(_) => 1,
+        Event::Trade(_) => 2,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_activity_flex;
+
+    const XML: &str = r#"<FlexQueryResponse queryName="Test" type="AF">
+<FlexStatements count="1">
+<FlexStatement accountId="U1234567" fromDate="2025-01-01" toDate="2025-01-31" period="Monthly" whenGenerated="2025-02-01;120000">
+<Trades>
+<Trade accountId="U1234567" currency="USD" assetCategory="STK" symbol="AAPL" conid="265598" transactionID="1" tradeID="1" tradeDate="2025-01-10" buySell="BUY" quantity="100" price="150" ibCommission="-1" multiplier="1" levelOfDetail="EXECUTION" />
+<Trade accountId="U1234567" currency="USD" assetCategory="STK" symbol="AAPL" conid="265598" transactionID="2" tradeID="2" tradeDate="2025-01-20" buySell="SELL" quantity="-40" price="160" ibCommission="-1" multiplier="1" levelOfDetail="EXECUTION" />
+</Trades>
+<CorporateActions>
+<CorporateAction accountId="U1234567" currency="USD" assetCategory="STK" symbol="AAPL" description="AAPL 2:1 Split" conid="265598" reportDate="2025-01-25" dateTime="2025-01-25;000000" quantity="60" type="FS" />
+</CorporateActions>
+</FlexStatement>
+</FlexStatements>
+</FlexQueryResponse>"#;
+
+    fn day(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 1, d).unwrap()
+    }
+
+    #[test]
+    fn test_reconstruct_as_of_date() {
+        let statement = parse_activity_flex(XML).unwrap();
+
+        assert!(reconstruct_positions([&statement], &[], day(9)).is_empty());
+
+        let bought = reconstruct_positions([&statement], &[], day(19));
+        assert_eq!(bought.len(), 1);
+        assert_eq!(bought[0].quantity, Decimal::from(100));
+        assert_eq!(bought[0].cost_basis, Decimal::from(15001));
+
+        let sold = reconstruct_positions([&statement], &[], day(20));
+        assert_eq!(sold[0].quantity, Decimal::from(60));
+        assert_eq!(sold[0].cost_basis, Decimal::new(90006, 1));
+    }
+
+    #[test]
+    fn test_split_scales_quantity_and_keeps_basis() {
+        let statement = parse_activity_flex(XML).unwrap();
+
+        let positions = reconstruct_positions([&statement], &[], day(31));
+        assert_eq!(positions[0].quantity, Decimal::from(120));
+        assert_eq!(positions[0].cost_basis, Decimal::new(90006, 1));
+        assert_eq!(positions[0].lots, 1);
+    }
+}
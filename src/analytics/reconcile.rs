@@ -0,0 +1,572 @@
+//! Reconciliation of rebuilt positions and cash against reported totals
+//!
+//! Backfilled statement histories often have gaps: a missing month of
+//! trades, or a corporate action the replay does not model. Comparing the
+//! positions rebuilt by [`reconstruct_positions`] against the
+//! `OpenPositions` section of a later statement surfaces those gaps per
+//! conid, and [`reconcile_cash`] does the same for cash balances against
+//! the `CashReport` section.
+//!
+//! # Example
+//! ```
+//! use ib_flex::analytics::{reconcile_statement_positions, ReconcileConfig};
+//! use ib_flex::parse_activity_flex;
+//!
+//! let xml = include_str!("../../tests/fixtures/activity_minimal.xml");
+//! let statement = parse_activity_flex(xml).unwrap();
+//!
+//! let report =
+//!     reconcile_statement_positions([&statement], &[], &statement, &ReconcileConfig::default());
+//! for gap in &report.discrepancies {
+//!     println!("{}: {:?} off by {}", gap.symbol, gap.kind, gap.quantity_diff);
+//! }
+//! ```
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+#[cfg(feature = "serialize")]
+use serde::Serialize;
+
+use super::positions::{reconstruct_positions, ReconstructedPosition};
+use crate::types::traits::Dated;
+use crate::types::{
+    ActivityFlexStatement, CashReportCurrency, Conid, LevelOfDetail, Position, StatementOfFundsLine,
+};
+
+/// Tolerances for [`reconcile_positions`] and [`reconcile_cash`]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct ReconcileConfig {
+    /// Largest quantity difference still treated as a match
+    pub quantity_tolerance: Decimal,
+    /// Largest cost basis difference still treated as a match
+    pub cost_basis_tolerance: Decimal,
+    /// Largest ending cash difference still treated as a match
+    pub cash_tolerance: Decimal,
+}
+
+impl Default for ReconcileConfig {
+    fn default() -> Self {
+        ReconcileConfig {
+            quantity_tolerance: Decimal::ZERO,
+            cost_basis_tolerance: Decimal::ONE,
+            cash_tolerance: Decimal::new(1, 2),
+        }
+    }
+}
+
+/// How a rebuilt position disagrees with the reported one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub enum DiscrepancyKind {
+    /// Rebuilt from history but absent from `OpenPositions`, usually a
+    /// missing closing trade
+    NotReported,
+    /// In `OpenPositions` but not rebuilt, usually a missing opening trade
+    /// or transfer
+    NotReconstructed,
+    /// Both sides hold the contract in different quantities
+    Quantity,
+    /// Quantities match but cost bases differ
+    CostBasis,
+}
+
+/// One conid whose rebuilt and reported positions disagree
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct PositionDiscrepancy {
+    /// Contract ID
+    pub conid: Conid,
+    /// Ticker symbol
+    pub symbol: String,
+    /// Type of mismatch
+    pub kind: DiscrepancyKind,
+    /// Quantity rebuilt from history
+    pub reconstructed_quantity: Decimal,
+    /// Quantity in `OpenPositions`
+    pub reported_quantity: Decimal,
+    /// `reported_quantity - reconstructed_quantity`
+    pub quantity_diff: Decimal,
+    /// Cost basis rebuilt from history
+    pub reconstructed_cost_basis: Decimal,
+    /// Cost basis in `OpenPositions`, if reported
+    pub reported_cost_basis: Option<Decimal>,
+    /// `reported_cost_basis - reconstructed_cost_basis`, if reported
+    pub cost_basis_diff: Option<Decimal>,
+    /// Market value of `quantity_diff` at the reported mark price
+    pub value_diff: Option<Decimal>,
+    /// Whether the replayed statements hold a corporate action for this
+    /// conid, which points to an action the replay could not model
+    pub has_corporate_action: bool,
+}
+
+/// Result of [`reconcile_statement_positions`]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct PositionReconciliation {
+    /// Date the positions were compared at
+    pub as_of: NaiveDate,
+    /// Number of conids that matched within tolerance
+    pub matched: usize,
+    /// Conids that did not match
+    pub discrepancies: Vec<PositionDiscrepancy>,
+}
+
+impl PositionReconciliation {
+    /// Returns true if every position matched
+    pub fn is_clean(&self) -> bool {
+        self.discrepancies.is_empty()
+    }
+}
+
+/// Reported position totals for one conid
+struct Reported<'a> {
+    row: &'a Position,
+    quantity: Decimal,
+    cost_basis: Option<Decimal>,
+}
+
+/// Compare rebuilt positions against `OpenPositions` rows
+///
+/// Summary rows are used when present; a conid reported only as `LOT` rows
+/// is compared against their sum. Discrepancies are ordered by conid and
+/// `has_corporate_action` is always false, since no statements are given.
+pub fn reconcile_positions(
+    reconstructed: &[ReconstructedPosition],
+    reported: &[Position],
+    config: &ReconcileConfig,
+) -> Vec<PositionDiscrepancy> {
+    let reported = reported_by_conid(reported);
+    let rebuilt: BTreeMap<Conid, &ReconstructedPosition> =
+        reconstructed.iter().map(|p| (p.conid, p)).collect();
+
+    let mut conids: Vec<Conid> = rebuilt.keys().chain(reported.keys()).copied().collect();
+    conids.sort();
+    conids.dedup();
+
+    conids
+        .into_iter()
+        .filter_map(|conid| compare(rebuilt.get(&conid).copied(), reported.get(&conid), config))
+        .collect()
+}
+
+/// Rebuild positions from `history` and reconcile them with `reported`
+///
+/// Positions are rebuilt as of `reported.to_date` and compared with its
+/// `OpenPositions`. `history` normally includes `reported` itself;
+/// `opening` seeds the replay as in [`reconstruct_positions`].
+pub fn reconcile_statement_positions<'a, I>(
+    history: I,
+    opening: &[Position],
+    reported: &ActivityFlexStatement,
+    config: &ReconcileConfig,
+) -> PositionReconciliation
+where
+    I: IntoIterator<Item = &'a ActivityFlexStatement>,
+{
+    let history: Vec<&ActivityFlexStatement> = history.into_iter().collect();
+    let as_of = reported.to_date;
+    let reconstructed = reconstruct_positions(history.iter().copied(), opening, as_of);
+    let mut discrepancies = reconcile_positions(&reconstructed, &reported.positions, config);
+
+    for discrepancy in &mut discrepancies {
+        discrepancy.has_corporate_action = history
+            .iter()
+            .chain(std::iter::once(&reported))
+            .flat_map(|s| s.corporate_actions.iter())
+            .any(|a| a.conid == discrepancy.conid);
+    }
+
+    let total = reconstructed.len()
+        + reported_by_conid(&reported.positions)
+            .keys()
+            .filter(|c| !reconstructed.iter().any(|p| p.conid == **c))
+            .count();
+    PositionReconciliation {
+        as_of,
+        matched: total - discrepancies.len(),
+        discrepancies,
+    }
+}
+
+fn reported_by_conid(positions: &[Position]) -> BTreeMap<Conid, Reported<'_>> {
+    let is_lot = |p: &Position| p.level_of_detail == Some(LevelOfDetail::Lot);
+    let summarized: BTreeSet<Conid> = positions
+        .iter()
+        .filter(|p| !is_lot(p))
+        .map(|p| p.conid)
+        .collect();
+
+    let mut reported: BTreeMap<Conid, Reported<'_>> = BTreeMap::new();
+    for position in positions {
+        if is_lot(position) && summarized.contains(&position.conid) {
+            continue;
+        }
+        let quantity = position.signed_quantity();
+        let cost_basis = position.cost_basis_total();
+        reported
+            .entry(position.conid)
+            .and_modify(|r| {
+                r.quantity += quantity;
+                r.cost_basis = r.cost_basis.zip(cost_basis).map(|(a, b)| a + b);
+            })
+            .or_insert(Reported {
+                row: position,
+                quantity,
+                cost_basis,
+            });
+    }
+    reported.retain(|_, r| !r.quantity.is_zero());
+    reported
+}
+
+fn compare(
+    rebuilt: Option<&ReconstructedPosition>,
+    reported: Option<&Reported<'_>>,
+    config: &ReconcileConfig,
+) -> Option<PositionDiscrepancy> {
+    let reconstructed_quantity = rebuilt.map(|p| p.quantity).unwrap_or_default();
+    let reconstructed_cost_basis = rebuilt.map(|p| p.cost_basis).unwrap_or_default();
+    let reported_quantity = reported.map(|r| r.quantity).unwrap_or_default();
+    let reported_cost_basis = reported.and_then(|r| r.cost_basis);
+
+    let quantity_diff = reported_quantity - reconstructed_quantity;
+    let cost_basis_diff = reported_cost_basis.map(|b| b - reconstructed_cost_basis);
+
+    let kind = match (rebuilt, reported) {
+        (Some(_), None) => DiscrepancyKind::NotReported,
+        (None, Some(_)) => DiscrepancyKind::NotReconstructed,
+        _ if quantity_diff.abs() > config.quantity_tolerance => DiscrepancyKind::Quantity,
+        _ if cost_basis_diff.is_some_and(|d| d.abs() > config.cost_basis_tolerance) => {
+            DiscrepancyKind::CostBasis
+        }
+        _ => return None,
+    };
+
+    let value_diff = reported
+        .map(|r| quantity_diff * r.row.mark_price * r.row.multiplier.unwrap_or(Decimal::ONE));
+    let (conid, symbol) = match (rebuilt, reported) {
+        (Some(p), _) => (p.conid, p.symbol.clone()),
+        (None, Some(r)) => (r.row.conid, r.row.symbol.clone()),
+        (None, None) => return None,
+    };
+    Some(PositionDiscrepancy {
+        conid,
+        symbol,
+        kind,
+        reconstructed_quantity,
+        reported_quantity,
+        quantity_diff,
+        reconstructed_cost_basis,
+        reported_cost_basis,
+        cost_basis_diff,
+        value_diff,
+        has_corporate_action: false,
+    })
+}
+
+/// Section a cash movement was summed from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub enum CashSource {
+    /// A `StmtFunds` line
+    StatementOfFunds,
+    /// A `CashTransactions` row
+    CashTransaction,
+    /// Net cash of a `Trades` execution
+    Trade,
+}
+
+/// One record summed into a computed cash balance
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct CashContribution {
+    /// Section the record came from
+    pub source: CashSource,
+    /// Record date, if reported
+    pub date: Option<NaiveDate>,
+    /// Description, activity code, or symbol of the record
+    pub description: String,
+    /// Signed cash amount
+    pub amount: Decimal,
+}
+
+/// A currency whose computed ending cash disagrees with the `CashReport`
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct CashDiscrepancy {
+    /// Currency of the balance
+    pub currency: String,
+    /// `startingCash` from the `CashReport`
+    pub starting_cash: Decimal,
+    /// `endingCash` from the `CashReport`
+    pub reported_ending_cash: Decimal,
+    /// `starting_cash` plus every contribution
+    pub computed_ending_cash: Decimal,
+    /// `reported_ending_cash - computed_ending_cash`
+    pub difference: Decimal,
+    /// Section the contributions were summed from
+    pub source: CashSource,
+    /// Records summed into `computed_ending_cash`
+    pub contributions: Vec<CashContribution>,
+}
+
+/// Check each currency's ending cash in the `CashReport`
+///
+/// The computed balance is `startingCash` plus every `StmtFunds` line in
+/// that currency. Statements without `StmtFunds` lines for a currency fall
+/// back to its `CashTransactions` plus the net cash of its trade executions,
+/// which misses cash movements reported in neither section (such as some
+/// fees), so expect more false positives there. The `BASE_SUMMARY` row is
+/// skipped.
+pub fn reconcile_cash(
+    statement: &ActivityFlexStatement,
+    config: &ReconcileConfig,
+) -> Vec<CashDiscrepancy> {
+    statement
+        .cash_report
+        .iter()
+        .filter(|row| row.currency != "BASE_SUMMARY")
+        .filter_map(|row| {
+            let (source, contributions) = cash_contributions(statement, &row.currency);
+            check_cash(row, source, contributions, config)
+        })
+        .collect()
+}
+
+fn cash_contributions(
+    statement: &ActivityFlexStatement,
+    currency: &str,
+) -> (CashSource, Vec<CashContribution>) {
+    let funds: Vec<CashContribution> = statement
+        .statement_of_funds
+        .iter()
+        .filter(|line| line.currency.as_deref() == Some(currency))
+        .filter(|line| !is_balance_line(line))
+        .filter_map(|line| {
+            Some(CashContribution {
+                source: CashSource::StatementOfFunds,
+                date: line.date.or(line.report_date),
+                description: line
+                    .activity_description
+                    .clone()
+                    .or_else(|| line.activity_code.clone())
+                    .unwrap_or_default(),
+                amount: line.amount?,
+            })
+        })
+        .collect();
+    if !funds.is_empty() {
+        return (CashSource::StatementOfFunds, funds);
+    }
+
+    let mut contributions: Vec<CashContribution> = statement
+        .cash_transactions
+        .iter()
+        .filter(|tx| tx.currency == currency)
+        .map(|tx| CashContribution {
+            source: CashSource::CashTransaction,
+            date: tx.date,
+            description: tx.description.clone().unwrap_or_default(),
+            amount: tx.amount,
+        })
+        .collect();
+    contributions.extend(
+        statement
+            .trades
+            .iter()
+            .filter(|t| t.currency == currency)
+            .filter(|t| matches!(t.level_of_detail, None | Some(LevelOfDetail::Execution)))
+            .filter_map(|t| {
+                Some(CashContribution {
+                    source: CashSource::Trade,
+                    date: t.date(),
+                    description: t.symbol.clone(),
+                    amount: t.net_proceeds()?,
+                })
+            }),
+    );
+    (CashSource::CashTransaction, contributions)
+}
+
+/// Opening and closing balance lines carry a balance, not a movement
+pub(super) fn is_balance_line(line: &StatementOfFundsLine) -> bool {
+    line.activity_description.as_deref().is_some_and(|d| {
+        d.eq_ignore_ascii_case("Starting Balance") || d.eq_ignore_ascii_case("Ending Balance")
+    })
+}
+
+fn check_cash(
+    row: &CashReportCurrency,
+    source: CashSource,
+    contributions: Vec<CashContribution>,
+    config: &ReconcileConfig,
+) -> Option<CashDiscrepancy> {
+    let computed = row.starting_cash + contributions.iter().map(|c| c.amount).sum::<Decimal>();
+    let difference = row.ending_cash - computed;
+    if difference.abs() <= config.cash_tolerance {
+        return None;
+    }
+    Some(CashDiscrepancy {
+        currency: row.currency.clone(),
+        starting_cash: row.starting_cash,
+        reported_ending_cash: row.ending_cash,
+        computed_ending_cash: computed,
+        difference,
+        source,
+        contributions,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::PositionBuilder;
+
+    fn rebuilt(conid: u64, quantity: i64, cost_basis: i64) -> ReconstructedPosition {
+        ReconstructedPosition {
+            conid: Conid(conid),
+            symbol: format!("C{conid}"),
+            currency: "USD".to_string(),
+            quantity: quantity.into(),
+            cost_basis: cost_basis.into(),
+            lots: 1,
+        }
+    }
+
+    fn reported(conid: u64, quantity: i64, cost_basis: i64) -> Position {
+        PositionBuilder::new(format!("C{conid}"))
+            .conid(Conid(conid))
+            .quantity(quantity)
+            .mark_price(10)
+            .cost_basis_money(cost_basis)
+            .build()
+    }
+
+    #[test]
+    fn test_reconcile_positions_kinds() {
+        let config = ReconcileConfig::default();
+        let rebuilt = [
+            rebuilt(1, 100, 1000),
+            rebuilt(2, 50, 500),
+            rebuilt(3, 10, 100),
+            rebuilt(4, 20, 200),
+        ];
+        let reported = [
+            reported(1, 100, 1000),
+            reported(2, 80, 800),
+            reported(4, 20, 250),
+            reported(5, 5, 50),
+        ];
+
+        let gaps = reconcile_positions(&rebuilt, &reported, &config);
+        let kinds: Vec<(u64, DiscrepancyKind)> = gaps.iter().map(|d| (d.conid.0, d.kind)).collect();
+        assert_eq!(
+            kinds,
+            [
+                (2, DiscrepancyKind::Quantity),
+                (3, DiscrepancyKind::NotReported),
+                (4, DiscrepancyKind::CostBasis),
+                (5, DiscrepancyKind::NotReconstructed),
+            ]
+        );
+        assert_eq!(gaps[0].quantity_diff, Decimal::from(30));
+        assert_eq!(gaps[0].value_diff, Some(Decimal::from(300)));
+        assert_eq!(gaps[1].quantity_diff, Decimal::from(-10));
+        assert_eq!(gaps[1].value_diff, None);
+        assert_eq!(gaps[2].cost_basis_diff, Some(Decimal::from(50)));
+    }
+
+    #[test]
+    fn test_reconcile_statement_positions() {
+        let xml = include_str!("../../tests/fixtures/activity_minimal.xml");
+        let mut statement = crate::parse_activity_flex(xml).unwrap();
+        statement.positions.items.push(
+            PositionBuilder::new("AAPL")
+                .conid(Conid(265598))
+                .quantity(100)
+                .mark_price(190)
+                .cost_basis_money(18551)
+                .build(),
+        );
+
+        let report =
+            reconcile_statement_positions([&statement], &[], &statement, &Default::default());
+        assert!(report.is_clean());
+        assert_eq!(report.matched, 1);
+
+        statement.positions.items[0].quantity = Decimal::from(150);
+        let report =
+            reconcile_statement_positions([&statement], &[], &statement, &Default::default());
+        assert_eq!(report.matched, 0);
+        assert_eq!(report.discrepancies[0].kind, DiscrepancyKind::Quantity);
+        assert_eq!(
+            report.discrepancies[0].value_diff,
+            Some(Decimal::from(9500))
+        );
+        assert!(!report.discrepancies[0].has_corporate_action);
+    }
+
+    #[test]
+    fn test_reconcile_cash_from_cash_transactions() {
+        let xml = r#"<FlexQueryResponse queryName="Test" type="AF">
+<FlexStatements count="1">
+<FlexStatement accountId="U1234567" fromDate="2025-01-01" toDate="2025-01-31" period="Monthly" whenGenerated="2025-02-01;120000">
+<Trades>
+<Trade accountId="U1234567" currency="USD" assetCategory="STK" symbol="AAPL" conid="265598" tradeDate="2025-01-10" buySell="BUY" quantity="10" price="150" netCash="-1501" />
+</Trades>
+<CashTransactions>
+<CashTransaction accountId="U1234567" type="Deposits/Withdrawals" description="DEPOSIT" amount="5000" currency="USD" date="2025-01-02" />
+<CashTransaction accountId="U1234567" type="Dividends" description="AAPL DIVIDEND" amount="100" currency="USD" date="2025-01-20" />
+</CashTransactions>
+<CashReport>
+<CashReportCurrency accountId="U1234567" currency="BASE_SUMMARY" fromDate="2025-01-01" toDate="2025-01-31" startingCash="1000" endingCash="0" />
+<CashReportCurrency accountId="U1234567" currency="USD" fromDate="2025-01-01" toDate="2025-01-31" startingCash="1000" endingCash="4599" />
+</CashReport>
+</FlexStatement>
+</FlexStatements>
+</FlexQueryResponse>"#;
+        let mut statement = crate::parse_activity_flex(xml).unwrap();
+        assert!(reconcile_cash(&statement, &ReconcileConfig::default()).is_empty());
+
+        statement.cash_report.items[1].ending_cash = Decimal::from(4600);
+        let gaps = reconcile_cash(&statement, &ReconcileConfig::default());
+        assert_eq!(gaps.len(), 1);
+        assert_eq!(gaps[0].currency, "USD");
+        assert_eq!(gaps[0].difference, Decimal::ONE);
+        assert_eq!(gaps[0].source, CashSource::CashTransaction);
+        assert_eq!(gaps[0].contributions.len(), 3);
+    }
+
+    #[test]
+    fn test_reconcile_cash_prefers_statement_of_funds() {
+        let xml = r#"<FlexQueryResponse queryName="Test" type="AF">
+<FlexStatements count="1">
+<FlexStatement accountId="U1234567" fromDate="2025-01-01" toDate="2025-01-31" period="Monthly" whenGenerated="2025-02-01;120000">
+<CashTransactions>
+<CashTransaction accountId="U1234567" type="Dividends" description="AAPL DIVIDEND" amount="100" currency="USD" date="2025-01-20" />
+</CashTransactions>
+<CashReport>
+<CashReportCurrency accountId="U1234567" currency="USD" fromDate="2025-01-01" toDate="2025-01-31" startingCash="1000" endingCash="1075" />
+</CashReport>
+<StmtFunds>
+<StatementOfFundsLine accountId="U1234567" currency="USD" date="2025-01-01" activityDescription="Starting Balance" amount="1000" balance="1000" />
+<StatementOfFundsLine accountId="U1234567" currency="USD" date="2025-01-20" activityCode="DIV" activityDescription="AAPL DIVIDEND" amount="100" balance="1100" />
+<StatementOfFundsLine accountId="U1234567" currency="USD" date="2025-01-20" activityCode="FRTAX" activityDescription="AAPL WITHHOLDING" amount="-25" balance="1075" />
+</StmtFunds>
+</FlexStatement>
+</FlexStatements>
+</FlexQueryResponse>"#;
+        let statement = crate::parse_activity_flex(xml).unwrap();
+        assert!(reconcile_cash(&statement, &ReconcileConfig::default()).is_empty());
+
+        let strict = ReconcileConfig {
+            cash_tolerance: Decimal::ZERO,
+            ..Default::default()
+        };
+        assert!(reconcile_cash(&statement, &strict).is_empty());
+    }
+}
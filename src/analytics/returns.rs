@@ -0,0 +1,528 @@
+//! Account returns
+//!
+//! [`time_weighted_return`] chain-links returns between consecutive NAV
+//! observations after removing deposits and withdrawals, which measures the
+//! investment decisions independently of when money was added or taken out.
+//! This is the method behind IB's own `twr` figure in `ChangeInNAV`.
+//! [`money_weighted_return`] instead solves for the internal rate of return
+//! of the money actually invested (XIRR), which is the investor's personal
+//! return and does depend on the timing of deposits and withdrawals.
+//!
+//! Rates are fractions (`0.05` is 5%), unlike IB's `twr`, which is a
+//! percentage.
+//!
+//! # Example
+//! ```
+//! use ib_flex::analytics::{time_weighted_return, PnlPeriod};
+//! use ib_flex::parse_activity_flex;
+//!
+//! let xml = include_str!("../../tests/fixtures/activity_daily_portfolio.xml");
+//! let statement = parse_activity_flex(xml).unwrap();
+//!
+//! let twr = time_weighted_return([&statement], PnlPeriod::Month);
+//! println!("cumulative {} (IB reports {:?})", twr.cumulative, twr.reported);
+//! for (month, rate) in &twr.by_period {
+//!     println!("{month}: {rate}");
+//! }
+//! ```
+
+use std::collections::BTreeMap;
+
+use chrono::{Datelike, NaiveDate};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::{Decimal, MathematicalOps};
+use serde::Deserialize;
+#[cfg(feature = "serialize")]
+use serde::Serialize;
+
+use super::pnl::PnlPeriod;
+use crate::types::traits::Dated;
+use crate::types::ActivityFlexStatement;
+
+/// Return over one interval between NAV observations, in the base currency
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct PeriodReturn {
+    /// Date of the starting NAV
+    pub from_date: NaiveDate,
+    /// Date of the ending NAV
+    pub to_date: NaiveDate,
+    /// NAV at the start of the interval
+    pub starting_value: Decimal,
+    /// NAV at the end of the interval
+    pub ending_value: Decimal,
+    /// Net deposits (positive) and withdrawals (negative) in the interval
+    pub cash_flow: Decimal,
+    /// Return over the interval
+    pub rate: Decimal,
+    /// Chain-linked return from the first interval through this one
+    pub cumulative: Decimal,
+}
+
+/// Result of [`time_weighted_return`]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct TwrReport {
+    /// Returns between consecutive NAV observations, in date order
+    pub periods: Vec<PeriodReturn>,
+    /// Chain-linked return over all periods
+    pub cumulative: Decimal,
+    /// Chain-linked return per calendar period, keyed by its first day
+    pub by_period: BTreeMap<NaiveDate, Decimal>,
+    /// IB's `twr` from `ChangeInNAV`, chain-linked across statements and
+    /// converted to a fraction
+    pub reported: Option<Decimal>,
+}
+
+impl TwrReport {
+    /// First NAV date of the series
+    pub fn from_date(&self) -> Option<NaiveDate> {
+        self.periods.first().map(|p| p.from_date)
+    }
+
+    /// Last NAV date of the series
+    pub fn to_date(&self) -> Option<NaiveDate> {
+        self.periods.last().map(|p| p.to_date)
+    }
+
+    /// Cumulative return scaled to a 365-day year
+    ///
+    /// Returns `None` for an empty series or one shorter than a day.
+    pub fn annualized(&self) -> Option<Decimal> {
+        let days = (self.to_date()? - self.from_date()?).num_days();
+        if days <= 0 {
+            return None;
+        }
+        let years = Decimal::from(days) / Decimal::from(365);
+        let growth = (Decimal::ONE + self.cumulative).checked_powd(Decimal::ONE / years)?;
+        Some(growth - Decimal::ONE)
+    }
+}
+
+/// Time-weighted return from NAV history and external cash flows
+///
+/// NAVs come from `EquitySummaryInBase`, one per report date (the last
+/// statement wins where they overlap). Statements without it contribute one
+/// interval from their `ChangeInNAV` starting and ending values. Cash flows
+/// are the `DepositsWithdrawals` rows of each statement, or its deposit and
+/// withdrawal `CashTransactions` when that section is missing, converted with
+/// `fxRateToBase`. Flows are assumed to arrive at the start of their day, so
+/// each interval returns `(end - start - flow) / (start + flow)`; intervals
+/// with nothing invested have a zero return.
+pub fn time_weighted_return<'a, I>(statements: I, period: PnlPeriod) -> TwrReport
+where
+    I: IntoIterator<Item = &'a ActivityFlexStatement>,
+{
+    let statements: Vec<&ActivityFlexStatement> = statements.into_iter().collect();
+    let flows = cash_flows(&statements);
+
+    let mut navs: BTreeMap<NaiveDate, Decimal> = BTreeMap::new();
+    let mut intervals: Vec<(NaiveDate, NaiveDate, Decimal, Decimal)> = Vec::new();
+    for statement in &statements {
+        let mut found = false;
+        for row in statement.equity_summary.iter() {
+            if let Some(total) = row.total {
+                navs.insert(row.report_date, total);
+                found = true;
+            }
+        }
+        if let (false, Some(nav)) = (found, &statement.change_in_nav) {
+            intervals.push((
+                nav.from_date,
+                nav.to_date,
+                nav.starting_value,
+                nav.ending_value,
+            ));
+        }
+    }
+    let points: Vec<(NaiveDate, Decimal)> = navs.into_iter().collect();
+    for pair in points.windows(2) {
+        let ((from, start), (to, end)) = (pair[0], pair[1]);
+        // Flows on the starting date are already in its NAV
+        intervals.push((from.succ_opt().unwrap_or(from), to, start, end));
+    }
+    intervals.sort_by_key(|(from, to, _, _)| (*from, *to));
+
+    let mut report = TwrReport::default();
+    let mut growth = Decimal::ONE;
+    for (from, to, start, end) in intervals {
+        let cash_flow: Decimal = flows.range(from..=to).map(|(_, amount)| *amount).sum();
+        let invested = start + cash_flow;
+        let rate = if invested.is_zero() {
+            Decimal::ZERO
+        } else {
+            (end - invested) / invested
+        };
+        growth *= Decimal::ONE + rate;
+        let bucket = report
+            .by_period
+            .entry(period.start(to))
+            .or_insert(Decimal::ZERO);
+        *bucket = (Decimal::ONE + *bucket) * (Decimal::ONE + rate) - Decimal::ONE;
+        report.periods.push(PeriodReturn {
+            from_date: from,
+            to_date: to,
+            starting_value: start,
+            ending_value: end,
+            cash_flow,
+            rate,
+            cumulative: growth - Decimal::ONE,
+        });
+    }
+    report.cumulative = growth - Decimal::ONE;
+
+    let reported: Vec<Decimal> = statements
+        .iter()
+        .filter_map(|s| s.change_in_nav.as_ref()?.twr)
+        .collect();
+    if !reported.is_empty() {
+        let linked = reported.iter().fold(Decimal::ONE, |acc, twr| {
+            acc * (Decimal::ONE + twr / Decimal::ONE_HUNDRED)
+        });
+        report.reported = Some(linked - Decimal::ONE);
+    }
+    report
+}
+
+/// External cash flows in the base currency per date
+pub(super) fn cash_flows(statements: &[&ActivityFlexStatement]) -> BTreeMap<NaiveDate, Decimal> {
+    let mut flows: BTreeMap<NaiveDate, Decimal> = BTreeMap::new();
+    for statement in statements {
+        if !statement.deposits_withdrawals.is_empty() {
+            for row in statement.deposits_withdrawals.iter() {
+                if let Some(date) = row.date() {
+                    *flows.entry(date).or_default() +=
+                        row.amount * row.fx_rate_to_base.unwrap_or(Decimal::ONE);
+                }
+            }
+            continue;
+        }
+        for tx in statement.cash_transactions.iter() {
+            if !tx.is_deposit_or_withdrawal() {
+                continue;
+            }
+            if let Some(date) = tx.date() {
+                *flows.entry(date).or_default() +=
+                    tx.amount * tx.fx_rate_to_base.unwrap_or(Decimal::ONE);
+            }
+        }
+    }
+    flows
+}
+
+/// Day-count convention for money-weighted returns
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub enum DayCount {
+    /// Actual days over 365, as spreadsheet `XIRR` uses
+    #[default]
+    Actual365,
+    /// Actual days over 360
+    Actual360,
+    /// Actual days over the length of each calendar year spanned
+    ActualActual,
+}
+
+impl DayCount {
+    /// Length of `from..to` in years
+    pub fn year_fraction(self, from: NaiveDate, to: NaiveDate) -> f64 {
+        match self {
+            DayCount::Actual365 => (to - from).num_days() as f64 / 365.0,
+            DayCount::Actual360 => (to - from).num_days() as f64 / 360.0,
+            DayCount::ActualActual => {
+                if to < from {
+                    return -DayCount::ActualActual.year_fraction(to, from);
+                }
+                let mut years = 0.0;
+                let mut start = from;
+                while start < to {
+                    let next_year = NaiveDate::from_ymd_opt(start.year() + 1, 1, 1).unwrap_or(to);
+                    let end = next_year.min(to);
+                    let year_days = if is_leap_year(start.year()) {
+                        366.0
+                    } else {
+                        365.0
+                    };
+                    years += (end - start).num_days() as f64 / year_days;
+                    start = end;
+                }
+                years
+            }
+        }
+    }
+}
+
+fn is_leap_year(year: i32) -> bool {
+    NaiveDate::from_ymd_opt(year, 2, 29).is_some()
+}
+
+/// Cash flow from the investor's side: negative when money goes in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct InvestorCashFlow {
+    /// Date of the flow
+    pub date: NaiveDate,
+    /// Amount in the base currency
+    pub amount: Decimal,
+}
+
+/// Result of [`money_weighted_return`]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct MoneyWeightedReturn {
+    /// Annualized internal rate of return, if it could be solved
+    pub rate: Option<Decimal>,
+    /// Flows the rate was solved over: the starting NAV, deposits and
+    /// withdrawals, and the ending NAV
+    pub flows: Vec<InvestorCashFlow>,
+}
+
+/// Annualized internal rate of return of dated cash flows
+///
+/// Solves `sum(amount / (1 + rate)^years) = 0`, with `years` measured from
+/// the first flow. Returns `None` unless there is at least one inflow and
+/// one outflow, or when no rate above -100% solves the equation.
+pub fn xirr(flows: &[InvestorCashFlow], day_count: DayCount) -> Option<Decimal> {
+    let first = flows.iter().map(|f| f.date).min()?;
+    let points: Vec<(f64, f64)> = flows
+        .iter()
+        .map(|f| {
+            let amount = f.amount.to_f64().unwrap_or(0.0);
+            (day_count.year_fraction(first, f.date), amount)
+        })
+        .collect();
+    if !points.iter().any(|(_, a)| *a > 0.0) || !points.iter().any(|(_, a)| *a < 0.0) {
+        return None;
+    }
+    let npv = |rate: f64| -> f64 {
+        points
+            .iter()
+            .map(|(t, amount)| amount / (1.0 + rate).powf(*t))
+            .sum()
+    };
+    let derivative = |rate: f64| -> f64 {
+        points
+            .iter()
+            .map(|(t, amount)| -t * amount / (1.0 + rate).powf(t + 1.0))
+            .sum()
+    };
+
+    const TOLERANCE: f64 = 1e-10;
+    // Newton's method converges quickly from a reasonable guess...
+    let mut rate = 0.1;
+    for _ in 0..100 {
+        let value = npv(rate);
+        if value.abs() < TOLERANCE {
+            return to_decimal(rate);
+        }
+        let slope = derivative(rate);
+        if slope == 0.0 || !slope.is_finite() {
+            break;
+        }
+        let next = rate - value / slope;
+        if !next.is_finite() || next <= -1.0 {
+            break;
+        }
+        rate = next;
+    }
+
+    // ...and bisection covers the cases where it does not
+    let (mut low, mut high) = (-0.999_999_999, 1.0);
+    while npv(low).signum() == npv(high).signum() {
+        high *= 2.0;
+        if high > 1e9 {
+            return None;
+        }
+    }
+    for _ in 0..500 {
+        let mid = (low + high) / 2.0;
+        let value = npv(mid);
+        if value.abs() < TOLERANCE || (high - low) < 1e-14 {
+            return to_decimal(mid);
+        }
+        if value.signum() == npv(low).signum() {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    to_decimal((low + high) / 2.0)
+}
+
+fn to_decimal(rate: f64) -> Option<Decimal> {
+    Decimal::from_f64_retain(rate).map(|r| r.round_dp(10))
+}
+
+/// Money-weighted return of the account over the statements
+///
+/// The starting NAV counts as an investment on the first NAV date, each
+/// deposit and withdrawal as an investment or return of money on its date,
+/// and the ending NAV as money returned on the last NAV date. NAVs and cash
+/// flows are taken as in [`time_weighted_return`].
+pub fn money_weighted_return<'a, I>(statements: I, day_count: DayCount) -> MoneyWeightedReturn
+where
+    I: IntoIterator<Item = &'a ActivityFlexStatement>,
+{
+    let statements: Vec<&ActivityFlexStatement> = statements.into_iter().collect();
+    let account_flows = cash_flows(&statements);
+
+    // (date, value, whether flows on the date are already included)
+    let mut navs: Vec<(NaiveDate, Decimal, bool)> = Vec::new();
+    for statement in &statements {
+        let equity: Vec<(NaiveDate, Decimal, bool)> = statement
+            .equity_summary
+            .iter()
+            .filter_map(|row| Some((row.report_date, row.total?, true)))
+            .collect();
+        if !equity.is_empty() {
+            navs.extend(equity);
+        } else if let Some(nav) = &statement.change_in_nav {
+            navs.push((nav.from_date, nav.starting_value, false));
+            navs.push((nav.to_date, nav.ending_value, true));
+        }
+    }
+    navs.sort_by_key(|(date, _, _)| *date);
+
+    let mut flows: Vec<InvestorCashFlow> = Vec::new();
+    if let (Some(&(start, opening, included)), Some(&(end, closing, _))) =
+        (navs.first(), navs.last())
+    {
+        flows.push(InvestorCashFlow {
+            date: start,
+            amount: -opening,
+        });
+        for (&date, &amount) in account_flows.range(start..=end) {
+            if date == start && included {
+                continue;
+            }
+            flows.push(InvestorCashFlow {
+                date,
+                amount: -amount,
+            });
+        }
+        flows.push(InvestorCashFlow {
+            date: end,
+            amount: closing,
+        });
+    }
+    MoneyWeightedReturn {
+        rate: xirr(&flows, day_count),
+        flows,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{CashTransactionBuilder, CashTransactionType};
+
+    const XML: &str = r#"<FlexQueryResponse queryName="Test" type="AF">
+<FlexStatements count="1">
+<FlexStatement accountId="U1234567" fromDate="2025-01-01" toDate="2025-02-28" period="Custom" whenGenerated="2025-03-01;120000">
+<EquitySummaryInBase>
+<EquitySummaryByReportDateInBase accountId="U1234567" reportDate="2025-01-30" total="1000" />
+<EquitySummaryByReportDateInBase accountId="U1234567" reportDate="2025-01-31" total="1100" />
+<EquitySummaryByReportDateInBase accountId="U1234567" reportDate="2025-02-03" total="2200" />
+<EquitySummaryByReportDateInBase accountId="U1234567" reportDate="2025-02-04" total="1980" />
+</EquitySummaryInBase>
+<DepositsWithdrawals>
+<DepositWithdrawal accountId="U1234567" currency="USD" amount="1000" date="2025-02-03" />
+</DepositsWithdrawals>
+</FlexStatement>
+</FlexStatements>
+</FlexQueryResponse>"#;
+
+    fn day(month: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, month, d).unwrap()
+    }
+
+    #[test]
+    fn test_twr_removes_cash_flows() {
+        let statement = crate::parse_activity_flex(XML).unwrap();
+        let report = time_weighted_return([&statement], PnlPeriod::Month);
+
+        let rates: Vec<Decimal> = report.periods.iter().map(|p| p.rate).collect();
+        assert_eq!(rates[0], Decimal::new(1, 1));
+        // 1100 + 1000 deposited grew to 2200
+        assert_eq!(rates[1].round_dp(6), Decimal::new(47619, 6));
+        assert_eq!(rates[2], Decimal::new(-1, 1));
+        assert_eq!(report.periods[1].cash_flow, Decimal::from(1000));
+
+        let expected =
+            Decimal::new(11, 1) * (Decimal::new(22, 1) / Decimal::new(21, 1)) * Decimal::new(9, 1)
+                - Decimal::ONE;
+        assert_eq!(report.cumulative.round_dp(10), expected.round_dp(10));
+        assert_eq!(report.by_period[&day(1, 1)], Decimal::new(1, 1));
+        assert_eq!(report.by_period.len(), 2);
+        assert!(report.annualized().is_some());
+        assert_eq!(report.reported, None);
+    }
+
+    #[test]
+    fn test_xirr() {
+        let flow = |month: u32, d: u32, amount: i64| InvestorCashFlow {
+            date: day(month, d),
+            amount: Decimal::from(amount),
+        };
+        // 1000 growing to 1100 over exactly one year returns 10%
+        let one_year = [
+            InvestorCashFlow {
+                date: NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+                amount: Decimal::from(-1000),
+            },
+            InvestorCashFlow {
+                date: NaiveDate::from_ymd_opt(2025, 1, 1).unwrap(),
+                amount: Decimal::from(1100),
+            },
+        ];
+        let rate = xirr(&one_year, DayCount::ActualActual).unwrap();
+        assert_eq!(rate.round_dp(8), Decimal::new(1, 1));
+
+        assert_eq!(xirr(&[flow(1, 1, -1000)], DayCount::Actual365), None);
+        let loss = [flow(1, 1, -1000), flow(7, 1, 500)];
+        assert!(xirr(&loss, DayCount::Actual365).unwrap() < Decimal::new(-5, 1));
+    }
+
+    #[test]
+    fn test_money_weighted_return_weights_deposits() {
+        let statement = crate::parse_activity_flex(XML).unwrap();
+        let mwr = money_weighted_return([&statement], DayCount::Actual365);
+        let amounts: Vec<Decimal> = mwr.flows.iter().map(|f| f.amount).collect();
+        assert_eq!(
+            amounts,
+            vec![
+                Decimal::from(-1000),
+                Decimal::from(-1000),
+                Decimal::from(1980)
+            ]
+        );
+        // Losing 1% of the money invested over five days is a large
+        // negative annualized rate
+        let rate = mwr.rate.unwrap();
+        assert!(rate < Decimal::ZERO && rate > Decimal::NEGATIVE_ONE);
+    }
+
+    #[test]
+    fn test_twr_falls_back_to_change_in_nav_and_cash_transactions() {
+        let xml = include_str!("../../tests/fixtures/activity_daily_portfolio.xml");
+        let mut statement = crate::parse_activity_flex(xml).unwrap();
+        statement.equity_summary.items.clear();
+        statement.deposits_withdrawals.items.clear();
+        let nav = statement.change_in_nav.as_mut().unwrap();
+        nav.starting_value = Decimal::from(1000);
+        nav.ending_value = Decimal::from(1650);
+        let date = nav.from_date;
+        statement.cash_transactions.items =
+            vec![
+                CashTransactionBuilder::new(CashTransactionType::DepositsWithdrawals, 500)
+                    .date(date)
+                    .build(),
+            ];
+
+        let report = time_weighted_return([&statement], PnlPeriod::Day);
+        assert_eq!(report.periods.len(), 1);
+        assert_eq!(report.cumulative, Decimal::new(1, 1));
+        let reported = report.reported.unwrap();
+        assert_eq!(reported.round_dp(6), Decimal::new(13152, 6));
+    }
+}
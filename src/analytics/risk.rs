@@ -0,0 +1,294 @@
+//! Risk statistics from the equity curve
+//!
+//! [`risk_summary`] turns a [`NavSeries`] into the usual risk figures:
+//! drawdowns, volatility, and Sharpe and Sortino ratios. Returns are taken
+//! between reported NAVs net of deposits and withdrawals, so money moving in
+//! or out of the account is not mistaken for gains or losses.
+//!
+//! Rates are fractions (`0.05` is 5%) and annualize over 252 trading days.
+//!
+//! # Example
+//! ```
+//! use ib_flex::analytics::{nav_series, risk_summary, GapFill};
+//! use ib_flex::parse_activity_flex;
+//! use rust_decimal::Decimal;
+//!
+//! let xml = include_str!("../../tests/fixtures/activity_daily_portfolio.xml");
+//! let statement = parse_activity_flex(xml).unwrap();
+//!
+//! let series = nav_series([&statement], GapFill::CarryForward);
+//! let risk = risk_summary(&series, Decimal::new(4, 2));
+//! println!("vol {} sharpe {:?}", risk.annualized_volatility, risk.sharpe_ratio);
+//! if let Some(drawdown) = &risk.max_drawdown {
+//!     println!("max drawdown {} from {}", drawdown.depth, drawdown.peak_date);
+//! }
+//! ```
+
+use chrono::NaiveDate;
+use rust_decimal::{Decimal, MathematicalOps};
+use serde::Deserialize;
+#[cfg(feature = "serialize")]
+use serde::Serialize;
+
+use super::nav::NavSeries;
+
+/// Trading days per year used to annualize daily figures
+const TRADING_DAYS: i64 = 252;
+
+/// A fall from a peak of the cumulative return and the recovery from it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct Drawdown {
+    /// Date of the peak
+    pub peak_date: NaiveDate,
+    /// Date of the lowest point before recovery
+    pub trough_date: NaiveDate,
+    /// First date back at the peak, `None` if still below it
+    pub recovery_date: Option<NaiveDate>,
+    /// Fall from the peak to the trough, as a negative fraction
+    pub depth: Decimal,
+}
+
+impl Drawdown {
+    /// Calendar days from the peak to the recovery, or to `as_of` while the
+    /// drawdown is still open
+    pub fn duration_days(&self, as_of: NaiveDate) -> i64 {
+        (self.recovery_date.unwrap_or(as_of) - self.peak_date).num_days()
+    }
+}
+
+/// Result of [`risk_summary`]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct RiskSummary {
+    /// First reported NAV date
+    pub from_date: Option<NaiveDate>,
+    /// Last reported NAV date
+    pub to_date: Option<NaiveDate>,
+    /// Returns between reported NAVs
+    pub daily_returns: Vec<(NaiveDate, Decimal)>,
+    /// Chain-linked return over the series
+    pub total_return: Decimal,
+    /// Standard deviation of the daily returns
+    pub daily_volatility: Decimal,
+    /// Daily volatility scaled by the square root of 252
+    pub annualized_volatility: Decimal,
+    /// Annualized excess return over annualized volatility, `None` when the
+    /// returns don't vary
+    pub sharpe_ratio: Option<Decimal>,
+    /// Like the Sharpe ratio, counting only returns below the risk-free rate
+    pub sortino_ratio: Option<Decimal>,
+    /// Deepest drawdown
+    pub max_drawdown: Option<Drawdown>,
+    /// Drawdown that took longest to recover (or is still open)
+    pub longest_drawdown: Option<Drawdown>,
+}
+
+/// Risk figures for a NAV series
+///
+/// `risk_free_rate` is annual; it is spread evenly over 252 trading days
+/// for the ratios. Volatility is the sample standard deviation, so at least
+/// two returns are needed for it to be nonzero. Drawdowns are measured on
+/// the cumulative return rather than the raw NAV, so a withdrawal is not a
+/// drawdown.
+pub fn risk_summary(series: &NavSeries, risk_free_rate: Decimal) -> RiskSummary {
+    let returns = reported_returns(series);
+    let mut summary = RiskSummary {
+        from_date: series.points.iter().find(|p| p.reported).map(|p| p.date),
+        to_date: series
+            .points
+            .iter()
+            .rev()
+            .find(|p| p.reported)
+            .map(|p| p.date),
+        ..Default::default()
+    };
+
+    let (max_drawdown, longest_drawdown) = drawdowns(summary.from_date, &returns, summary.to_date);
+    summary.max_drawdown = max_drawdown;
+    summary.longest_drawdown = longest_drawdown;
+    summary.total_return = returns.iter().fold(Decimal::ONE, |growth, (_, rate)| {
+        growth * (Decimal::ONE + rate)
+    }) - Decimal::ONE;
+
+    let days = Decimal::from(TRADING_DAYS);
+    let daily_risk_free = risk_free_rate / days;
+    let excess: Vec<Decimal> = returns
+        .iter()
+        .map(|(_, rate)| rate - daily_risk_free)
+        .collect();
+    summary.daily_volatility = deviation(returns.iter().map(|(_, rate)| *rate)).unwrap_or_default();
+    summary.annualized_volatility = summary.daily_volatility * days.sqrt().unwrap_or_default();
+
+    if !excess.is_empty() {
+        let mean = excess.iter().sum::<Decimal>() / Decimal::from(excess.len());
+        let annual_excess = mean * days;
+        summary.sharpe_ratio = (!summary.annualized_volatility.is_zero())
+            .then(|| annual_excess / summary.annualized_volatility);
+        let downside = excess
+            .iter()
+            .map(|r| r.min(&Decimal::ZERO) * r.min(&Decimal::ZERO));
+        let downside = (downside.sum::<Decimal>() / Decimal::from(excess.len()))
+            .sqrt()
+            .unwrap_or_default()
+            * days.sqrt().unwrap_or_default();
+        summary.sortino_ratio = (!downside.is_zero()).then(|| annual_excess / downside);
+    }
+
+    summary.daily_returns = returns;
+    summary
+}
+
+/// Returns between reported points, with flows on filled days deferred to
+/// the next reported day
+fn reported_returns(series: &NavSeries) -> Vec<(NaiveDate, Decimal)> {
+    let mut returns = Vec::new();
+    let mut previous: Option<Decimal> = None;
+    let mut pending = Decimal::ZERO;
+    for point in &series.points {
+        pending += point.cash_flow;
+        if !point.reported {
+            continue;
+        }
+        if let Some(start) = previous {
+            let invested = start + pending;
+            let rate = if invested.is_zero() {
+                Decimal::ZERO
+            } else {
+                (point.nav - invested) / invested
+            };
+            returns.push((point.date, rate));
+        }
+        previous = Some(point.nav);
+        pending = Decimal::ZERO;
+    }
+    returns
+}
+
+/// Sample standard deviation, `None` for fewer than two values
+fn deviation(values: impl Iterator<Item = Decimal>) -> Option<Decimal> {
+    let values: Vec<Decimal> = values.collect();
+    if values.len() < 2 {
+        return None;
+    }
+    let mean = values.iter().sum::<Decimal>() / Decimal::from(values.len());
+    let variance = values
+        .iter()
+        .map(|v| (v - mean) * (v - mean))
+        .sum::<Decimal>()
+        / Decimal::from(values.len() - 1);
+    variance.sqrt()
+}
+
+/// Deepest and longest drawdowns of the cumulative return
+fn drawdowns(
+    start: Option<NaiveDate>,
+    returns: &[(NaiveDate, Decimal)],
+    end: Option<NaiveDate>,
+) -> (Option<Drawdown>, Option<Drawdown>) {
+    let (Some(start), Some(end)) = (start, end) else {
+        return (None, None);
+    };
+    let mut all: Vec<Drawdown> = Vec::new();
+    let mut growth = Decimal::ONE;
+    let mut peak = (start, Decimal::ONE);
+    let mut current: Option<Drawdown> = None;
+    for &(date, rate) in returns {
+        growth *= Decimal::ONE + rate;
+        // Rounded so a full recovery isn't missed by a last-digit error
+        growth = growth.round_dp(12);
+        if growth >= peak.1 {
+            if let Some(mut drawdown) = current.take() {
+                drawdown.recovery_date = Some(date);
+                all.push(drawdown);
+            }
+            peak = (date, growth);
+            continue;
+        }
+        let depth = growth / peak.1 - Decimal::ONE;
+        let drawdown = current.get_or_insert(Drawdown {
+            peak_date: peak.0,
+            trough_date: date,
+            recovery_date: None,
+            depth,
+        });
+        if depth < drawdown.depth {
+            drawdown.depth = depth;
+            drawdown.trough_date = date;
+        }
+    }
+    all.extend(current);
+
+    let deepest = all.iter().min_by_key(|d| d.depth).copied();
+    let longest = all.iter().max_by_key(|d| d.duration_days(end)).copied();
+    (deepest, longest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analytics::nav::NavPoint;
+
+    fn day(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 6, d).unwrap()
+    }
+
+    fn series(navs: &[(u32, i64, i64)]) -> NavSeries {
+        NavSeries {
+            points: navs
+                .iter()
+                .map(|&(d, nav, flow)| NavPoint {
+                    date: day(d),
+                    nav: Decimal::from(nav),
+                    cash: None,
+                    stock: None,
+                    cash_flow: Decimal::from(flow),
+                    reported: true,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_drawdowns_ignore_withdrawals() {
+        // Down 20% and back, then a withdrawal, then down 10% and still open
+        let series = series(&[
+            (2, 1000, 0),
+            (3, 900, 0),
+            (4, 800, 0),
+            (5, 1000, 0),
+            (6, 500, -500),
+            (8, 450, 0),
+        ]);
+        let risk = risk_summary(&series, Decimal::ZERO);
+
+        assert_eq!(risk.daily_returns.len(), 5);
+        assert_eq!(risk.daily_returns[3], (day(6), Decimal::ZERO));
+        let max = risk.max_drawdown.unwrap();
+        assert_eq!(max.depth, Decimal::new(-2, 1));
+        assert_eq!((max.peak_date, max.trough_date), (day(2), day(4)));
+        assert_eq!(max.recovery_date, Some(day(5)));
+
+        let longest = risk.longest_drawdown.unwrap();
+        assert_eq!(longest.peak_date, day(2));
+        assert_eq!(longest.duration_days(day(8)), 3);
+        assert_eq!(risk.total_return.round_dp(6), Decimal::new(-1, 1));
+    }
+
+    #[test]
+    fn test_volatility_and_ratios() {
+        let series = series(&[(2, 100, 0), (3, 101, 0), (4, 100, 0), (5, 102, 0)]);
+        let risk = risk_summary(&series, Decimal::ZERO);
+        assert!(risk.daily_volatility > Decimal::ZERO);
+        assert_eq!(
+            risk.annualized_volatility.round_dp(8),
+            (risk.daily_volatility * Decimal::from(252).sqrt().unwrap()).round_dp(8)
+        );
+        assert!(risk.sharpe_ratio.unwrap() > Decimal::ZERO);
+        assert!(risk.sortino_ratio.unwrap() > risk.sharpe_ratio.unwrap());
+
+        let flat = risk_summary(&self::series(&[(2, 100, 0), (3, 100, 0)]), Decimal::ZERO);
+        assert_eq!(flat.sharpe_ratio, None);
+        assert!(flat.max_drawdown.is_none());
+    }
+}
@@ -0,0 +1,412 @@
+//! Round trips from flat to flat
+//!
+//! [`round_trips`] pairs the executions on each contract into round trips:
+//! a trip opens when the position leaves zero and closes when it returns
+//! there, however many fills it took to scale in and out. A trade that
+//! reverses the position is split into the fill closing the old trip and
+//! the fill opening the next one. Each [`RoundTrip`] reports its P&L,
+//! duration, and, where daily marks are available, its maximum adverse and
+//! favorable excursion, which is the core of most trading journals.
+//!
+//! # Example
+//! ```
+//! use ib_flex::analytics::round_trips;
+//! use ib_flex::parse_activity_flex;
+//!
+//! let xml = include_str!("../../tests/fixtures/activity_minimal.xml");
+//! let statement = parse_activity_flex(xml).unwrap();
+//!
+//! for trip in round_trips([&statement]).iter().filter(|t| t.is_closed()) {
+//!     println!(
+//!         "{} {} -> {:?}: pnl {} mae {:?} mfe {:?}",
+//!         trip.symbol,
+//!         trip.open_date,
+//!         trip.close_date,
+//!         trip.pnl(),
+//!         trip.mae,
+//!         trip.mfe
+//!     );
+//! }
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+#[cfg(feature = "serialize")]
+use serde::Serialize;
+
+use crate::types::traits::{Dated, HasConid};
+use crate::types::{ActivityFlexStatement, AssetCategory, Conid, LevelOfDetail, Trade, TradeId};
+
+/// The part of an execution that belongs to a round trip
+///
+/// Amounts are the share of the execution's quantity that fell in this
+/// trip, so a reversing trade shows up as one fill in each trip.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct RoundTripFill {
+    /// IB trade ID of the execution
+    pub trade_id: Option<TradeId>,
+    /// Trade date
+    pub date: NaiveDate,
+    /// Execution time, when reported
+    pub time: Option<NaiveDateTime>,
+    /// Signed quantity, positive for buys
+    pub quantity: Decimal,
+    /// Execution price
+    pub price: Decimal,
+    /// Commission and taxes, negative when charged
+    pub commission: Decimal,
+    /// Cash received (positive) or paid (negative), including commission
+    pub net_cash: Decimal,
+}
+
+/// One round trip on a contract
+///
+/// Amounts are in the trade currency.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct RoundTrip {
+    /// Contract traded
+    pub conid: Conid,
+    /// Symbol of the contract
+    pub symbol: String,
+    /// Asset category
+    pub asset_category: AssetCategory,
+    /// Trade currency
+    pub currency: String,
+    /// Contract multiplier
+    pub multiplier: Decimal,
+    /// Opening, scaling, and closing fills in execution order
+    pub fills: Vec<RoundTripFill>,
+    /// Date the position left zero
+    pub open_date: NaiveDate,
+    /// Date the position returned to zero, `None` while still open
+    pub close_date: Option<NaiveDate>,
+    /// Largest absolute position held during the trip
+    pub max_quantity: Decimal,
+    /// Maximum adverse excursion: the lowest P&L at a daily mark while open
+    pub mae: Option<Decimal>,
+    /// Maximum favorable excursion: the highest P&L at a daily mark while open
+    pub mfe: Option<Decimal>,
+}
+
+impl RoundTrip {
+    /// Returns true if the trip opened with a sale
+    pub fn is_short(&self) -> bool {
+        self.fills
+            .first()
+            .is_some_and(|fill| fill.quantity.is_sign_negative())
+    }
+
+    /// Returns true once the position returned to zero
+    pub fn is_closed(&self) -> bool {
+        self.close_date.is_some()
+    }
+
+    /// Position still open at the end of the history
+    pub fn open_quantity(&self) -> Decimal {
+        self.fills.iter().map(|fill| fill.quantity).sum()
+    }
+
+    /// Net cash of all fills, which is the P&L once the trip is closed
+    pub fn pnl(&self) -> Decimal {
+        self.fills.iter().map(|fill| fill.net_cash).sum()
+    }
+
+    /// Commission and taxes paid over the trip
+    pub fn commissions(&self) -> Decimal {
+        self.fills.iter().map(|fill| fill.commission).sum()
+    }
+
+    /// Average price of the fills that added to the position
+    pub fn entry_price(&self) -> Option<Decimal> {
+        average_price(self.fills.iter().filter(|f| self.adds(f)))
+    }
+
+    /// Average price of the fills that reduced the position
+    pub fn exit_price(&self) -> Option<Decimal> {
+        average_price(self.fills.iter().filter(|f| !self.adds(f)))
+    }
+
+    /// Time from the first to the last fill of a closed trip
+    ///
+    /// Uses execution times when both ends have one, otherwise whole days.
+    pub fn duration(&self) -> Option<Duration> {
+        let close_date = self.close_date?;
+        let first = self.fills.first()?;
+        let last = self.fills.last()?;
+        Some(match (first.time, last.time) {
+            (Some(open), Some(close)) => close - open,
+            _ => close_date - self.open_date,
+        })
+    }
+
+    fn adds(&self, fill: &RoundTripFill) -> bool {
+        fill.quantity.is_sign_negative() == self.is_short()
+    }
+}
+
+fn average_price<'a>(fills: impl Iterator<Item = &'a RoundTripFill>) -> Option<Decimal> {
+    let (quantity, notional) = fills.fold((Decimal::ZERO, Decimal::ZERO), |(q, n), fill| {
+        (
+            q + fill.quantity.abs(),
+            n + fill.quantity.abs() * fill.price,
+        )
+    });
+    (!quantity.is_zero()).then(|| notional / quantity)
+}
+
+/// Match executions into round trips
+///
+/// Executions are taken from the `Trades` of all statements (duplicate
+/// `ORDER` and `CLOSED_LOT` rows are skipped) and cash conversions are
+/// ignored. Excursions are measured at end-of-day marks from
+/// `PriorPeriodPositions`, `OpenPositions`, and the trades' `closePrice`,
+/// as the P&L the trip would have had if closed at the mark; without
+/// intraday prices they miss moves that reversed within a day. Trips are
+/// returned in order of their opening.
+pub fn round_trips<'a, I>(statements: I) -> Vec<RoundTrip>
+where
+    I: IntoIterator<Item = &'a ActivityFlexStatement>,
+{
+    let statements: Vec<&ActivityFlexStatement> = statements.into_iter().collect();
+    let mut trades: Vec<&Trade> = statements
+        .iter()
+        .flat_map(|s| s.trades.iter())
+        .filter(|t| matches!(t.level_of_detail, None | Some(LevelOfDetail::Execution)))
+        .filter(|t| t.asset_category != AssetCategory::Cash)
+        .collect();
+    trades.sort_by(|a, b| a.chronological_key().cmp(&b.chronological_key()));
+
+    let mut trips: Vec<RoundTrip> = Vec::new();
+    let mut active: HashMap<Conid, usize> = HashMap::new();
+    for trade in trades {
+        apply_trade(&mut trips, &mut active, trade);
+    }
+
+    let marks = marks(&statements);
+    for trip in &mut trips {
+        if let Some(marks) = marks.get(&trip.conid) {
+            measure_excursions(trip, marks);
+        }
+    }
+    trips
+}
+
+fn apply_trade(trips: &mut Vec<RoundTrip>, active: &mut HashMap<Conid, usize>, trade: &Trade) {
+    let (Some(quantity), Some(date)) = (trade.signed_quantity(), trade.date()) else {
+        return;
+    };
+    if quantity.is_zero() {
+        return;
+    }
+    let mut remaining = quantity;
+    while !remaining.is_zero() {
+        let index = *active.entry(trade.conid).or_insert_with(|| {
+            trips.push(RoundTrip {
+                conid: trade.conid,
+                symbol: trade.symbol.clone(),
+                asset_category: trade.asset_category.clone(),
+                currency: trade.currency.clone(),
+                multiplier: trade.multiplier.unwrap_or(Decimal::ONE),
+                fills: Vec::new(),
+                open_date: date,
+                close_date: None,
+                max_quantity: Decimal::ZERO,
+                mae: None,
+                mfe: None,
+            });
+            trips.len() - 1
+        });
+        let trip = &mut trips[index];
+        let position = trip.open_quantity();
+        // A fill crossing zero only closes what is open; the rest opens a new trip
+        let part = if !position.is_zero()
+            && position.is_sign_negative() != remaining.is_sign_negative()
+            && remaining.abs() > position.abs()
+        {
+            -position
+        } else {
+            remaining
+        };
+        trip.fills.push(fill(trade, date, part / quantity, part));
+        let position = position + part;
+        trip.max_quantity = trip.max_quantity.max(position.abs());
+        if position.is_zero() {
+            trip.close_date = Some(date);
+            active.remove(&trade.conid);
+        }
+        remaining -= part;
+    }
+}
+
+fn fill(trade: &Trade, date: NaiveDate, share: Decimal, quantity: Decimal) -> RoundTripFill {
+    let commission = trade.commission.unwrap_or_default() + trade.taxes.unwrap_or_default();
+    RoundTripFill {
+        trade_id: trade.trade_id.clone(),
+        date,
+        time: trade.trade_time,
+        quantity,
+        price: trade.price.unwrap_or_default(),
+        commission: commission * share,
+        net_cash: trade.net_proceeds().unwrap_or_default() * share,
+    }
+}
+
+/// End-of-day marks per contract
+fn marks(statements: &[&ActivityFlexStatement]) -> HashMap<Conid, BTreeMap<NaiveDate, Decimal>> {
+    let mut marks: HashMap<Conid, BTreeMap<NaiveDate, Decimal>> = HashMap::new();
+    for statement in statements {
+        for trade in statement.trades.iter() {
+            if let (Some(date), Some(price)) = (trade.date(), trade.close_price) {
+                marks.entry(trade.conid).or_default().insert(date, price);
+            }
+        }
+        for prior in statement.prior_period_positions.iter() {
+            if let (Some(conid), Some(date), Some(price)) = (prior.conid(), prior.date, prior.price)
+            {
+                marks.entry(conid).or_default().insert(date, price);
+            }
+        }
+        for position in statement.positions.iter() {
+            marks
+                .entry(position.conid)
+                .or_default()
+                .insert(position.report_date, position.mark_price);
+        }
+    }
+    marks
+}
+
+/// P&L at each mark the trip was open over, closing fills excluded
+fn measure_excursions(trip: &mut RoundTrip, marks: &BTreeMap<NaiveDate, Decimal>) {
+    let end = trip.close_date;
+    for (&date, &price) in marks.range(trip.open_date..) {
+        if end.is_some_and(|close| date >= close) {
+            break;
+        }
+        let (quantity, cash) = trip
+            .fills
+            .iter()
+            .filter(|fill| fill.date <= date)
+            .fold((Decimal::ZERO, Decimal::ZERO), |(q, c), fill| {
+                (q + fill.quantity, c + fill.net_cash)
+            });
+        let pnl = cash + quantity * price * trip.multiplier;
+        trip.mae = Some(trip.mae.map_or(pnl, |mae| mae.min(pnl)));
+        trip.mfe = Some(trip.mfe.map_or(pnl, |mfe| mfe.max(pnl)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{BuySell, PositionBuilder, TradeBuilder};
+
+    fn day(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 6, d).unwrap()
+    }
+
+    fn trade(id: &str, d: u32, side: BuySell, quantity: i64, price: i64) -> Trade {
+        let quantity = Decimal::from(quantity);
+        let proceeds = -quantity * Decimal::from(price);
+        let proceeds = if side == BuySell::Buy {
+            proceeds
+        } else {
+            -proceeds
+        };
+        TradeBuilder::new("AAPL")
+            .conid(1)
+            .trade_id(id)
+            .trade_date(day(d))
+            .buy_sell(side)
+            .quantity(quantity)
+            .price(price)
+            .proceeds(proceeds)
+            .commission(-1)
+            .build()
+    }
+
+    fn statement(trades: Vec<Trade>) -> ActivityFlexStatement {
+        let xml = include_str!("../../tests/fixtures/activity_minimal.xml");
+        let mut statement = crate::parse_activity_flex(xml).unwrap();
+        statement.trades.items = trades;
+        statement.positions.items.clear();
+        statement.prior_period_positions.items.clear();
+        statement
+    }
+
+    #[test]
+    fn test_scaling_and_reversal() {
+        let statement = statement(vec![
+            trade("1", 2, BuySell::Buy, 100, 10),
+            trade("2", 3, BuySell::Buy, 100, 12),
+            trade("3", 4, BuySell::Sell, 150, 13),
+            // Sells the last 50 and goes short 50
+            trade("4", 5, BuySell::Sell, 100, 14),
+            trade("5", 6, BuySell::Buy, 50, 11),
+        ]);
+
+        let trips = round_trips([&statement]);
+        assert_eq!(trips.len(), 2);
+
+        let long = &trips[0];
+        assert!(!long.is_short());
+        assert_eq!(long.close_date, Some(day(5)));
+        assert_eq!(long.fills.len(), 4);
+        assert_eq!(long.max_quantity, Decimal::from(200));
+        assert_eq!(long.entry_price(), Some(Decimal::from(11)));
+        // 150 @ 13 and 50 @ 14
+        assert_eq!(long.exit_price(), Some(Decimal::new(1325, 2)));
+        // 2650 - 2200 gross, less 3.5 of commission
+        assert_eq!(long.commissions(), Decimal::new(-35, 1));
+        assert_eq!(long.pnl(), Decimal::new(4465, 1));
+        assert_eq!(long.duration(), Some(Duration::days(3)));
+
+        let short = &trips[1];
+        assert!(short.is_short() && short.is_closed());
+        assert_eq!(short.open_date, day(5));
+        assert_eq!(short.fills[0].quantity, Decimal::from(-50));
+        assert_eq!(short.pnl(), Decimal::new(1485, 1));
+    }
+
+    #[test]
+    fn test_excursions_from_marks() {
+        let mut statement = statement(vec![
+            trade("1", 2, BuySell::Buy, 100, 10),
+            trade("2", 6, BuySell::Sell, 100, 12),
+        ]);
+        let xml = r#"<PriorPeriodPositions>
+<PriorPeriodPosition accountId="U1" conid="1" symbol="AAPL" date="2025-06-02" price="10.5" />
+<PriorPeriodPosition accountId="U1" conid="1" symbol="AAPL" date="2025-06-03" price="9" />
+<PriorPeriodPosition accountId="U1" conid="1" symbol="AAPL" date="2025-06-04" price="13" />
+<PriorPeriodPosition accountId="U1" conid="1" symbol="AAPL" date="2025-06-06" price="20" />
+</PriorPeriodPositions>"#;
+        statement.prior_period_positions = quick_xml::de::from_str(xml).unwrap();
+
+        let trips = round_trips([&statement]);
+        let trip = &trips[0];
+        // Marks at 9 and 13 on a 100 share long bought at 10 with 1 of commission
+        assert_eq!(trip.mae, Some(Decimal::from(-101)));
+        assert_eq!(trip.mfe, Some(Decimal::from(299)));
+        assert_eq!(trip.pnl(), Decimal::from(198));
+
+        // Open trips are marked through the latest position snapshot
+        let mut open = statement.clone();
+        open.trades.items.truncate(1);
+        open.prior_period_positions.items.clear();
+        open.positions.items = vec![PositionBuilder::new("AAPL")
+            .conid(1)
+            .quantity(100)
+            .mark_price(8)
+            .report_date(day(30))
+            .build()];
+        let trips = round_trips([&open]);
+        assert!(!trips[0].is_closed());
+        assert_eq!(trips[0].mae, Some(Decimal::from(-201)));
+        assert_eq!(trips[0].duration(), None);
+    }
+}
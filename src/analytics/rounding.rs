@@ -0,0 +1,166 @@
+//! Rounding of aggregated amounts
+//!
+//! IB rounds each row of a statement to the cent before totalling it, so
+//! sums of exact converted amounts can be a few cents away from the totals
+//! printed on the statement. A [`RoundingPolicy`] passed to [`base_pnl`],
+//! [`fee_report`], and [`dividend_report`] rounds either every converted
+//! row before it is added (matching IB) or only the finished totals. The
+//! default policy leaves amounts exact.
+//!
+//! [`base_pnl`]: super::base_pnl
+//! [`fee_report`]: super::fee_report
+//! [`dividend_report`]: super::dividend_report
+//!
+//! # Example
+//! ```
+//! use ib_flex::analytics::{RoundingMode, RoundingPolicy, RoundingStage};
+//! use rust_decimal::Decimal;
+//!
+//! let policy = RoundingPolicy::new(2).with_mode(RoundingMode::HalfEven);
+//! assert_eq!(policy.stage, RoundingStage::PerRecord);
+//! assert_eq!(policy.round(Decimal::new(12345, 3)), Decimal::new(1234, 2));
+//! assert_eq!(RoundingPolicy::default().round(Decimal::new(12345, 3)), Decimal::new(12345, 3));
+//! ```
+
+use rust_decimal::{Decimal, RoundingStrategy};
+use serde::Deserialize;
+#[cfg(feature = "serialize")]
+use serde::Serialize;
+
+/// How a value halfway or partway between two results is rounded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub enum RoundingMode {
+    /// Halves away from zero (2.345 → 2.35, -2.345 → -2.35), as IB displays
+    #[default]
+    HalfUp,
+    /// Halves to the even digit (2.345 → 2.34, 2.355 → 2.36)
+    HalfEven,
+    /// Halves toward zero (2.345 → 2.34)
+    HalfDown,
+    /// Toward zero, dropping extra digits
+    Truncate,
+    /// Away from zero whenever extra digits are nonzero
+    Up,
+}
+
+impl RoundingMode {
+    fn strategy(self) -> RoundingStrategy {
+        match self {
+            RoundingMode::HalfUp => RoundingStrategy::MidpointAwayFromZero,
+            RoundingMode::HalfEven => RoundingStrategy::MidpointNearestEven,
+            RoundingMode::HalfDown => RoundingStrategy::MidpointTowardZero,
+            RoundingMode::Truncate => RoundingStrategy::ToZero,
+            RoundingMode::Up => RoundingStrategy::AwayFromZero,
+        }
+    }
+}
+
+/// When a [`RoundingPolicy`] rounds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub enum RoundingStage {
+    /// Each row after conversion to the base currency, before it is added
+    #[default]
+    PerRecord,
+    /// Only the finished totals
+    Final,
+}
+
+/// Decimal places, mode, and stage of rounding for aggregations
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct RoundingPolicy {
+    /// Decimal places to keep, or `None` to leave amounts exact
+    pub decimal_places: Option<u32>,
+    /// How extra digits are rounded
+    pub mode: RoundingMode,
+    /// Whether rows or totals are rounded
+    pub stage: RoundingStage,
+}
+
+impl RoundingPolicy {
+    /// Round rows to `decimal_places`, halves away from zero
+    pub fn new(decimal_places: u32) -> Self {
+        RoundingPolicy {
+            decimal_places: Some(decimal_places),
+            ..Default::default()
+        }
+    }
+
+    /// Round to the cent per row, the way IB totals its statements
+    pub fn cents() -> Self {
+        RoundingPolicy::new(2)
+    }
+
+    /// Use `mode` for extra digits
+    pub fn with_mode(mut self, mode: RoundingMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Round at `stage`
+    pub fn with_stage(mut self, stage: RoundingStage) -> Self {
+        self.stage = stage;
+        self
+    }
+
+    /// `amount` rounded by this policy, regardless of stage
+    pub fn round(&self, amount: Decimal) -> Decimal {
+        match self.decimal_places {
+            Some(dp) => amount.round_dp_with_strategy(dp, self.mode.strategy()),
+            None => amount,
+        }
+    }
+
+    /// Round a row amount when rounding per record
+    pub(crate) fn record(&self, amount: Decimal) -> Decimal {
+        match self.stage {
+            RoundingStage::PerRecord => self.round(amount),
+            RoundingStage::Final => amount,
+        }
+    }
+
+    /// Round a total when rounding only the final result
+    pub(crate) fn total(&self, amount: Decimal) -> Decimal {
+        match self.stage {
+            RoundingStage::PerRecord => amount,
+            RoundingStage::Final => self.round(amount),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_modes() {
+        let half = Decimal::new(-2345, 3);
+        let cases = [
+            (RoundingMode::HalfUp, -235),
+            (RoundingMode::HalfEven, -234),
+            (RoundingMode::HalfDown, -234),
+            (RoundingMode::Truncate, -234),
+            (RoundingMode::Up, -235),
+        ];
+        for (mode, expected) in cases {
+            let policy = RoundingPolicy::cents().with_mode(mode);
+            assert_eq!(policy.round(half), Decimal::new(expected, 2), "{mode:?}");
+        }
+        let policy = RoundingPolicy::cents().with_mode(RoundingMode::Up);
+        assert_eq!(policy.round(Decimal::new(2341, 3)), Decimal::new(235, 2));
+    }
+
+    #[test]
+    fn test_stage_selects_record_or_total() {
+        let amount = Decimal::new(1005, 3);
+        let per_record = RoundingPolicy::cents();
+        assert_eq!(per_record.record(amount), Decimal::new(101, 2));
+        assert_eq!(per_record.total(amount), amount);
+
+        let at_end = per_record.with_stage(RoundingStage::Final);
+        assert_eq!(at_end.record(amount), amount);
+        assert_eq!(at_end.total(amount), Decimal::new(101, 2));
+    }
+}
@@ -0,0 +1,355 @@
+//! Section 1256 contracts and 60/40 P&L
+//!
+//! Regulated futures, options on futures, and options on broad-based
+//! indexes are section 1256 contracts under the US tax code: they are marked
+//! to market at year end, and their net gain or loss is treated as 60%
+//! long-term and 40% short-term regardless of holding period.
+//! [`Section1256Classifier`] identifies them from asset category and
+//! underlying, and [`section_1256_report`] computes the year's 60/40 P&L
+//! apart from ordinary capital gains.
+//!
+//! # Example
+//! ```
+//! use ib_flex::analytics::{section_1256_report, Section1256Classifier};
+//! use ib_flex::parse_activity_flex;
+//!
+//! let xml = include_str!("../../tests/fixtures/activity_futures.xml");
+//! let statement = parse_activity_flex(xml).unwrap();
+//!
+//! let report = section_1256_report([&statement], 2025, &[], &Section1256Classifier::new());
+//! println!(
+//!     "1256 net {}: long-term {} short-term {}",
+//!     report.net(),
+//!     report.long_term(),
+//!     report.short_term()
+//! );
+//! ```
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use chrono::Datelike;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+#[cfg(feature = "serialize")]
+use serde::Serialize;
+
+use crate::types::traits::Dated;
+use crate::types::{
+    ActivityFlexStatement, AssetCategory, Conid, LevelOfDetail, Position, SecurityInfo, Trade,
+};
+
+/// Underlyings of index options treated as broad-based by default
+///
+/// Covers the common cash-settled CBOE and Nasdaq index options. ETF
+/// options such as SPY or QQQ are equity options, not section 1256
+/// contracts.
+pub const BROAD_BASED_INDEXES: &[&str] = &[
+    "SPX", "SPXW", "XSP", "NDX", "NDXP", "XND", "RUT", "MRUT", "VIX", "DJX", "OEX", "XEO",
+];
+
+/// Identifies section 1256 contracts
+///
+/// Futures and futures options always qualify. Options qualify when their
+/// underlying is a broad-based index, taken from the row's underlying
+/// symbol or else from the option's `SecuritiesInfo` row.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Section1256Classifier {
+    indexes: BTreeSet<String>,
+    underlyings: HashMap<Conid, String>,
+}
+
+impl Default for Section1256Classifier {
+    fn default() -> Self {
+        Section1256Classifier {
+            indexes: BROAD_BASED_INDEXES.iter().map(|s| s.to_string()).collect(),
+            underlyings: HashMap::new(),
+        }
+    }
+}
+
+impl Section1256Classifier {
+    /// Classifier using [`BROAD_BASED_INDEXES`]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Treat options on `symbol` as broad-based index options
+    pub fn with_index(mut self, symbol: impl Into<String>) -> Self {
+        self.indexes.insert(symbol.into().to_uppercase());
+        self
+    }
+
+    /// Use `SecuritiesInfo` rows to fill in missing underlyings
+    pub fn with_securities<'a, I>(mut self, securities: I) -> Self
+    where
+        I: IntoIterator<Item = &'a SecurityInfo>,
+    {
+        for info in securities {
+            if let Some(underlying) = info.underlying_symbol.as_deref().filter(|u| !u.is_empty()) {
+                self.underlyings.insert(info.conid, underlying.to_string());
+            }
+        }
+        self
+    }
+
+    /// Returns true if `symbol` is a broad-based index
+    pub fn is_broad_based_index(&self, symbol: &str) -> bool {
+        self.indexes.contains(symbol.trim().to_uppercase().as_str())
+    }
+
+    /// Classify a contract from its asset category and underlying
+    pub fn is_section_1256(
+        &self,
+        asset_category: &AssetCategory,
+        conid: Conid,
+        underlying_symbol: Option<&str>,
+    ) -> bool {
+        let underlying = underlying_symbol
+            .filter(|u| !u.is_empty())
+            .or_else(|| self.underlyings.get(&conid).map(String::as_str));
+        match asset_category {
+            AssetCategory::Future | AssetCategory::FutureOption => true,
+            AssetCategory::Option => underlying.is_some_and(|u| self.is_broad_based_index(u)),
+            _ => false,
+        }
+    }
+
+    /// Classify the contract of a trade
+    pub fn is_section_1256_trade(&self, trade: &Trade) -> bool {
+        self.is_section_1256(
+            &trade.asset_category,
+            trade.conid,
+            trade.underlying_symbol.as_deref(),
+        )
+    }
+
+    /// Classify the contract of a position
+    pub fn is_section_1256_position(&self, position: &Position) -> bool {
+        self.is_section_1256(
+            &position.asset_category,
+            position.conid,
+            position.underlying_symbol.as_deref(),
+        )
+    }
+}
+
+/// Section 1256 P&L for one tax year, in the base currency
+///
+/// IB's realized P&L on a contract held over a prior year end is measured
+/// from its cost, but the prior year's mark was already taxed, so it is
+/// reversed through [`prior_year_unrealized`](Self::prior_year_unrealized).
+#[derive(Debug, Clone, PartialEq, Eq, Default, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct Section1256Report {
+    /// Tax year reported
+    pub tax_year: i32,
+    /// P&L realized on section 1256 contracts closed during the year
+    pub realized: Decimal,
+    /// Unrealized P&L of section 1256 contracts open at year end
+    pub year_end_unrealized: Decimal,
+    /// Unrealized P&L marked at the prior year end, already recognized then
+    pub prior_year_unrealized: Decimal,
+    /// Net section 1256 P&L per underlying (or symbol when none)
+    pub by_underlying: BTreeMap<String, Decimal>,
+    /// P&L realized on other contracts, reported as ordinary capital gains
+    pub other_realized: Decimal,
+}
+
+impl Section1256Report {
+    /// Net section 1256 gain or loss for the year
+    pub fn net(&self) -> Decimal {
+        self.realized + self.year_end_unrealized - self.prior_year_unrealized
+    }
+
+    /// 60% of [`net`](Self::net), treated as long-term
+    pub fn long_term(&self) -> Decimal {
+        self.net() * Decimal::new(6, 1)
+    }
+
+    /// 40% of [`net`](Self::net), treated as short-term
+    pub fn short_term(&self) -> Decimal {
+        self.net() - self.long_term()
+    }
+}
+
+/// Compute section 1256 P&L for `tax_year`
+///
+/// Realized P&L is IB's `fifoPnlRealized` on execution rows dated in the
+/// year. The year-end mark comes from the `OpenPositions` of the latest
+/// statement ending in the year, so that statement should end on the last
+/// trading day. `prior_year_end` is the `OpenPositions` snapshot from the
+/// prior year end; pass `&[]` for the first year. Amounts are converted
+/// with each row's `fxRateToBase`, with 1 when missing.
+pub fn section_1256_report<'a, I>(
+    statements: I,
+    tax_year: i32,
+    prior_year_end: &[Position],
+    classifier: &Section1256Classifier,
+) -> Section1256Report
+where
+    I: IntoIterator<Item = &'a ActivityFlexStatement>,
+{
+    let statements: Vec<&ActivityFlexStatement> = statements.into_iter().collect();
+    let mut report = Section1256Report {
+        tax_year,
+        ..Default::default()
+    };
+
+    for trade in statements.iter().flat_map(|s| s.trades.iter()) {
+        if !matches!(trade.level_of_detail, None | Some(LevelOfDetail::Execution))
+            || trade.date().map(|d| d.year()) != Some(tax_year)
+        {
+            continue;
+        }
+        let pnl = trade.fifo_pnl_realized.unwrap_or_default()
+            * trade.fx_rate_to_base.unwrap_or(Decimal::ONE);
+        if classifier.is_section_1256_trade(trade) {
+            report.realized += pnl;
+            *report
+                .by_underlying
+                .entry(underlying(
+                    &trade.symbol,
+                    trade.underlying_symbol.as_deref(),
+                ))
+                .or_default() += pnl;
+        } else {
+            report.other_realized += pnl;
+        }
+    }
+
+    let year_end = statements
+        .iter()
+        .filter(|s| s.to_date.year() == tax_year)
+        .max_by_key(|s| s.to_date);
+    if let Some(statement) = year_end {
+        for position in summary_rows(&statement.positions.items) {
+            if classifier.is_section_1256_position(position) {
+                let mark = unrealized(position);
+                report.year_end_unrealized += mark;
+                *report
+                    .by_underlying
+                    .entry(underlying(
+                        &position.symbol,
+                        position.underlying_symbol.as_deref(),
+                    ))
+                    .or_default() += mark;
+            }
+        }
+    }
+    for position in summary_rows(prior_year_end) {
+        if classifier.is_section_1256_position(position) {
+            let mark = unrealized(position);
+            report.prior_year_unrealized += mark;
+            *report
+                .by_underlying
+                .entry(underlying(
+                    &position.symbol,
+                    position.underlying_symbol.as_deref(),
+                ))
+                .or_default() -= mark;
+        }
+    }
+
+    report
+}
+
+fn underlying(symbol: &str, underlying_symbol: Option<&str>) -> String {
+    underlying_symbol
+        .filter(|u| !u.is_empty())
+        .unwrap_or(symbol)
+        .to_string()
+}
+
+fn unrealized(position: &Position) -> Decimal {
+    position.fifo_pnl_unrealized.unwrap_or_default()
+        * position.fx_rate_to_base.unwrap_or(Decimal::ONE)
+}
+
+/// Position rows without `LOT` duplicates of a summary row
+fn summary_rows(positions: &[Position]) -> impl Iterator<Item = &Position> {
+    let summarized: BTreeSet<Conid> = positions
+        .iter()
+        .filter(|p| p.level_of_detail != Some(LevelOfDetail::Lot))
+        .map(|p| p.conid)
+        .collect();
+    positions.iter().filter(move |p| {
+        p.level_of_detail != Some(LevelOfDetail::Lot) || !summarized.contains(&p.conid)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{PositionBuilder, TradeBuilder};
+    use chrono::NaiveDate;
+
+    fn day(year: i32, month: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, d).unwrap()
+    }
+
+    #[test]
+    fn test_classifier() {
+        let classifier = Section1256Classifier::new();
+        let conid = Conid::from(1);
+        assert!(classifier.is_section_1256(&AssetCategory::Future, conid, None));
+        assert!(classifier.is_section_1256(&AssetCategory::FutureOption, conid, Some("ES")));
+        assert!(classifier.is_section_1256(&AssetCategory::Option, conid, Some("SPX")));
+        assert!(!classifier.is_section_1256(&AssetCategory::Option, conid, Some("SPY")));
+        assert!(!classifier.is_section_1256(&AssetCategory::Stock, conid, Some("SPX")));
+
+        let custom = classifier.with_index("ndxp2");
+        assert!(custom.is_broad_based_index("NDXP2"));
+    }
+
+    #[test]
+    fn test_section_1256_report() {
+        let xml = include_str!("../../tests/fixtures/activity_minimal.xml");
+        let mut statement = crate::parse_activity_flex(xml).unwrap();
+        statement.to_date = day(2025, 12, 31);
+
+        let mut spx = TradeBuilder::new("SPX   250321C05000000")
+            .conid(2)
+            .asset_category(AssetCategory::Option)
+            .underlying_symbol("SPX")
+            .trade_date(day(2025, 3, 10))
+            .fifo_pnl_realized(1000)
+            .build();
+        spx.quantity = Some(Decimal::ONE);
+        let stock = TradeBuilder::new("AAPL")
+            .conid(3)
+            .trade_date(day(2025, 5, 1))
+            .fifo_pnl_realized(250)
+            .build();
+        let last_year = TradeBuilder::new("ESH5")
+            .conid(4)
+            .asset_category(AssetCategory::Future)
+            .trade_date(day(2024, 12, 20))
+            .fifo_pnl_realized(999)
+            .build();
+        statement.trades.items = vec![spx, stock, last_year];
+
+        let es = |pnl: i64, date: NaiveDate| {
+            PositionBuilder::new("ESH6")
+                .conid(5)
+                .asset_category(AssetCategory::Future)
+                .underlying_symbol("ES")
+                .quantity(1)
+                .report_date(date)
+                .fifo_pnl_unrealized(pnl)
+                .build()
+        };
+        statement.positions.items = vec![es(500, day(2025, 12, 31))];
+        let prior = vec![es(-200, day(2024, 12, 31))];
+
+        let report = section_1256_report([&statement], 2025, &prior, &Section1256Classifier::new());
+        assert_eq!(report.realized, Decimal::from(1000));
+        assert_eq!(report.year_end_unrealized, Decimal::from(500));
+        assert_eq!(report.prior_year_unrealized, Decimal::from(-200));
+        assert_eq!(report.net(), Decimal::from(1700));
+        assert_eq!(report.long_term(), Decimal::from(1020));
+        assert_eq!(report.short_term(), Decimal::from(680));
+        assert_eq!(report.other_realized, Decimal::from(250));
+        assert_eq!(report.by_underlying["ES"], Decimal::from(700));
+        assert_eq!(report.by_underlying["SPX"], Decimal::from(1000));
+    }
+}
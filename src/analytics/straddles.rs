@@ -0,0 +1,383 @@
+//! Straddle and constructive-sale screening
+//!
+//! Holding a position together with one that offsets its risk can bring in
+//! the straddle rules (losses deferred, holding periods suspended) or, when
+//! the offset is close to complete, a constructive sale of the appreciated
+//! position. [`straddle_flags`] looks for the common shapes in the latest
+//! `OpenPositions` of each account: long and short stock in the same name,
+//! conversions, deep in-the-money covered calls, and protective options.
+//!
+//! The results are heuristics for a tax professional to review, not
+//! determinations: whether a covered call is qualified, or an offset
+//! substantial, depends on facts a statement doesn't contain.
+//!
+//! # Example
+//! ```
+//! use ib_flex::analytics::{straddle_flags, StraddleConfig};
+//! use ib_flex::parse_activity_flex;
+//!
+//! let xml = include_str!("../../tests/fixtures/activity_minimal.xml");
+//! let statement = parse_activity_flex(xml).unwrap();
+//!
+//! for flag in straddle_flags([&statement], &StraddleConfig::default()) {
+//!     println!("{} {:?}: {}", flag.underlying, flag.rule, flag.kind.description());
+//! }
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+#[cfg(feature = "serialize")]
+use serde::Serialize;
+
+use super::attribution::summary_rows;
+use crate::types::{
+    AccountId, ActivityFlexStatement, AssetCategory, Conid, LevelOfDetail, Position, PutCall,
+    TradeId,
+};
+
+/// Settings for [`straddle_flags`]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct StraddleConfig {
+    /// How far below the stock price, as a fraction of it, a short call's
+    /// strike must be to count as deep in the money (default `0.10`)
+    pub deep_in_the_money: Decimal,
+}
+
+impl Default for StraddleConfig {
+    fn default() -> Self {
+        StraddleConfig {
+            deep_in_the_money: Decimal::new(10, 2),
+        }
+    }
+}
+
+/// Tax rule a flagged combination may fall under
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub enum TaxRule {
+    /// Constructive sale of an appreciated position (IRC §1259)
+    ConstructiveSale,
+    /// Straddle loss deferral and holding-period rules (IRC §1092)
+    Straddle,
+}
+
+/// Shape of a flagged combination
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub enum StraddleKind {
+    /// Long and short stock in the same name, possibly in different accounts
+    ShortAgainstTheBox,
+    /// Long stock with a long put and short call at the same strike and
+    /// expiry
+    Conversion,
+    /// Long stock with a short call struck well below the stock price
+    DeepInTheMoneyCall,
+    /// Long stock with long puts, alone or as part of a collar
+    ProtectivePut,
+    /// Short stock with long calls
+    ProtectiveCall,
+}
+
+impl StraddleKind {
+    /// Rule the combination is most likely to fall under
+    pub fn rule(self) -> TaxRule {
+        match self {
+            StraddleKind::ShortAgainstTheBox | StraddleKind::Conversion => {
+                TaxRule::ConstructiveSale
+            }
+            StraddleKind::DeepInTheMoneyCall
+            | StraddleKind::ProtectivePut
+            | StraddleKind::ProtectiveCall => TaxRule::Straddle,
+        }
+    }
+
+    /// One-line explanation for a reviewer
+    pub fn description(self) -> &'static str {
+        match self {
+            StraddleKind::ShortAgainstTheBox => {
+                "long and short positions in the same stock may be a constructive sale"
+            }
+            StraddleKind::Conversion => {
+                "a long put and short call at one strike lock in the stock's value and may be a constructive sale"
+            }
+            StraddleKind::DeepInTheMoneyCall => {
+                "a deep in-the-money call may not be a qualified covered call, making the position a straddle"
+            }
+            StraddleKind::ProtectivePut => {
+                "puts held against long stock may form a straddle"
+            }
+            StraddleKind::ProtectiveCall => {
+                "calls held against short stock may form a straddle"
+            }
+        }
+    }
+}
+
+/// One position in a flagged combination
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct StraddleLeg {
+    /// Account holding the position
+    pub account_id: AccountId,
+    /// Contract ID
+    pub conid: Conid,
+    /// Symbol of the contract
+    pub symbol: String,
+    /// Stock or option
+    pub asset_category: AssetCategory,
+    /// Position quantity (negative for short)
+    pub quantity: Decimal,
+    /// Option strike
+    pub strike: Option<Decimal>,
+    /// Option expiry
+    pub expiry: Option<NaiveDate>,
+    /// Put or call
+    pub put_call: Option<PutCall>,
+}
+
+/// A combination of positions that may need a tax professional's review
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct StraddleFlag {
+    /// Shape of the combination
+    pub kind: StraddleKind,
+    /// Rule it may fall under
+    pub rule: TaxRule,
+    /// Symbol of the underlying stock
+    pub underlying: String,
+    /// Positions making up the combination
+    pub legs: Vec<StraddleLeg>,
+    /// Trades in the statements that opened or changed the legs
+    pub trades: Vec<TradeId>,
+}
+
+/// Flag possible straddles and constructive sales
+///
+/// Positions are the summary rows of the latest statement of each account,
+/// grouped by underlying symbol across accounts, since the rules apply to
+/// related accounts together. Options are stock and equity options;
+/// futures and other categories are not screened. The stock price for the
+/// deep in-the-money test is the mark of the long stock position.
+pub fn straddle_flags<'a, I>(statements: I, config: &StraddleConfig) -> Vec<StraddleFlag>
+where
+    I: IntoIterator<Item = &'a ActivityFlexStatement>,
+{
+    let statements: Vec<&ActivityFlexStatement> = statements.into_iter().collect();
+    let mut latest: HashMap<&AccountId, &ActivityFlexStatement> = HashMap::new();
+    for statement in &statements {
+        let entry = latest.entry(&statement.account_id).or_insert(statement);
+        if statement.to_date > entry.to_date {
+            *entry = statement;
+        }
+    }
+
+    let mut by_underlying: BTreeMap<String, Vec<&Position>> = BTreeMap::new();
+    for statement in latest.values() {
+        for position in summary_rows(&statement.positions.items) {
+            let underlying = match position.asset_category {
+                AssetCategory::Stock => position.symbol.clone(),
+                AssetCategory::Option => match &position.underlying_symbol {
+                    Some(symbol) => symbol.clone(),
+                    None => continue,
+                },
+                _ => continue,
+            };
+            if !position.quantity.is_zero() {
+                by_underlying.entry(underlying).or_default().push(position);
+            }
+        }
+    }
+
+    let mut flags = Vec::new();
+    for (underlying, positions) in by_underlying {
+        screen(&underlying, &positions, config, &mut flags);
+    }
+    for flag in &mut flags {
+        flag.trades = statements
+            .iter()
+            .flat_map(|s| s.trades.iter())
+            .filter(|t| matches!(t.level_of_detail, None | Some(LevelOfDetail::Execution)))
+            .filter(|t| flag.legs.iter().any(|leg| leg.conid == t.conid))
+            .filter_map(|t| t.trade_id.clone())
+            .collect();
+    }
+    flags
+}
+
+fn screen(
+    underlying: &str,
+    positions: &[&Position],
+    config: &StraddleConfig,
+    flags: &mut Vec<StraddleFlag>,
+) {
+    let stock = |long: bool| -> Vec<&Position> {
+        positions
+            .iter()
+            .copied()
+            .filter(|p| p.asset_category == AssetCategory::Stock)
+            .filter(|p| p.quantity.is_sign_positive() == long)
+            .collect()
+    };
+    let options = |put_call: PutCall, long: bool| -> Vec<&Position> {
+        positions
+            .iter()
+            .copied()
+            .filter(|p| p.asset_category == AssetCategory::Option)
+            .filter(|p| {
+                p.put_call.as_ref() == Some(&put_call) && p.quantity.is_sign_positive() == long
+            })
+            .collect()
+    };
+    let (long_stock, short_stock) = (stock(true), stock(false));
+    let mut flag = |kind: StraddleKind, legs: Vec<&Position>| {
+        flags.push(StraddleFlag {
+            kind,
+            rule: kind.rule(),
+            underlying: underlying.to_string(),
+            legs: legs.into_iter().map(leg).collect(),
+            trades: Vec::new(),
+        });
+    };
+
+    if !long_stock.is_empty() && !short_stock.is_empty() {
+        flag(
+            StraddleKind::ShortAgainstTheBox,
+            [long_stock.clone(), short_stock.clone()].concat(),
+        );
+    }
+
+    if !long_stock.is_empty() {
+        let mut puts = options(PutCall::Put, true);
+        let mut calls = options(PutCall::Call, false);
+        let mut conversions = Vec::new();
+        puts.retain(|put| {
+            let Some(idx) = calls
+                .iter()
+                .position(|call| call.strike == put.strike && call.expiry == put.expiry)
+            else {
+                return true;
+            };
+            conversions.push((*put, calls.remove(idx)));
+            false
+        });
+        for (put, call) in conversions {
+            flag(
+                StraddleKind::Conversion,
+                [long_stock.clone(), vec![put, call]].concat(),
+            );
+        }
+
+        let price = long_stock[0].mark_price;
+        let threshold = price * (Decimal::ONE - config.deep_in_the_money);
+        let deep: Vec<&Position> = calls
+            .into_iter()
+            .filter(|call| call.strike.is_some_and(|strike| strike <= threshold))
+            .collect();
+        if !deep.is_empty() {
+            flag(
+                StraddleKind::DeepInTheMoneyCall,
+                [long_stock.clone(), deep].concat(),
+            );
+        }
+        if !puts.is_empty() {
+            flag(
+                StraddleKind::ProtectivePut,
+                [long_stock.clone(), puts].concat(),
+            );
+        }
+    }
+
+    if !short_stock.is_empty() {
+        let calls = options(PutCall::Call, true);
+        if !calls.is_empty() {
+            flag(StraddleKind::ProtectiveCall, [short_stock, calls].concat());
+        }
+    }
+}
+
+fn leg(position: &Position) -> StraddleLeg {
+    StraddleLeg {
+        account_id: position.account_id.clone(),
+        conid: position.conid,
+        symbol: position.symbol.clone(),
+        asset_category: position.asset_category.clone(),
+        quantity: position.quantity,
+        strike: position.strike,
+        expiry: position.expiry,
+        put_call: position.put_call.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const XML: &str = r#"<FlexQueryResponse queryName="Test" type="AF">
+<FlexStatements count="2">
+<FlexStatement accountId="U1234567" fromDate="2025-01-01" toDate="2025-01-31" period="Custom" whenGenerated="2025-02-01;120000">
+<OpenPositions>
+<OpenPosition accountId="U1234567" currency="USD" assetCategory="STK" symbol="XYZ" conid="1" position="200" markPrice="60" positionValue="12000" reportDate="2025-01-31" />
+<OpenPosition accountId="U1234567" currency="USD" assetCategory="OPT" symbol="XYZ   250620C00040000" conid="11" underlyingConid="1" underlyingSymbol="XYZ" multiplier="100" strike="40" expiry="2025-06-20" putCall="C" position="-1" markPrice="20.5" positionValue="-2050" reportDate="2025-01-31" />
+<OpenPosition accountId="U1234567" currency="USD" assetCategory="OPT" symbol="XYZ   250620C00065000" conid="12" underlyingConid="1" underlyingSymbol="XYZ" multiplier="100" strike="65" expiry="2025-06-20" putCall="C" position="-1" markPrice="2" positionValue="-200" reportDate="2025-01-31" />
+<OpenPosition accountId="U1234567" currency="USD" assetCategory="OPT" symbol="XYZ   250620P00055000" conid="13" underlyingConid="1" underlyingSymbol="XYZ" multiplier="100" strike="55" expiry="2025-06-20" putCall="P" position="1" markPrice="3" positionValue="300" reportDate="2025-01-31" />
+<OpenPosition accountId="U1234567" currency="USD" assetCategory="STK" symbol="ABC" conid="2" position="100" markPrice="30" positionValue="3000" reportDate="2025-01-31" />
+</OpenPositions>
+<Trades>
+<Trade accountId="U1234567" currency="USD" assetCategory="OPT" symbol="XYZ   250620C00040000" conid="11" underlyingConid="1" underlyingSymbol="XYZ" multiplier="100" strike="40" expiry="2025-06-20" putCall="C" transactionID="1" tradeID="1" tradeDate="2025-01-15" buySell="SELL" quantity="-1" price="20" proceeds="2000" netCash="1999" levelOfDetail="EXECUTION" />
+</Trades>
+</FlexStatement>
+<FlexStatement accountId="U7654321" fromDate="2025-01-01" toDate="2025-01-31" period="Custom" whenGenerated="2025-02-01;120000">
+<OpenPositions>
+<OpenPosition accountId="U7654321" currency="USD" assetCategory="STK" symbol="ABC" conid="2" position="-100" markPrice="30" positionValue="-3000" reportDate="2025-01-31" />
+</OpenPositions>
+</FlexStatement>
+</FlexStatements>
+</FlexQueryResponse>"#;
+
+    #[test]
+    fn test_flags_offsetting_positions() {
+        let statements = crate::parse_activity_flex_all(XML).unwrap();
+        let flags = straddle_flags(&statements, &StraddleConfig::default());
+        let kinds: Vec<(&str, StraddleKind)> = flags
+            .iter()
+            .map(|f| (f.underlying.as_str(), f.kind))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ("ABC", StraddleKind::ShortAgainstTheBox),
+                ("XYZ", StraddleKind::DeepInTheMoneyCall),
+                ("XYZ", StraddleKind::ProtectivePut),
+            ]
+        );
+
+        let abc = &flags[0];
+        assert_eq!(abc.rule, TaxRule::ConstructiveSale);
+        assert_eq!(abc.legs.len(), 2);
+        assert_ne!(abc.legs[0].account_id, abc.legs[1].account_id);
+
+        // Only the 40 strike is deep in the money at 60; the 65 call is not
+        let deep = &flags[1];
+        assert_eq!(deep.legs.len(), 2);
+        assert_eq!(deep.legs[1].strike, Some(Decimal::from(40)));
+        assert_eq!(deep.trades, vec![TradeId::from("1")]);
+    }
+
+    #[test]
+    fn test_conversion_is_constructive_sale() {
+        let xml = XML.replace("strike=\"65\"", "strike=\"55\"");
+        let statements = crate::parse_activity_flex_all(&xml).unwrap();
+        let flags = straddle_flags(&statements, &StraddleConfig::default());
+        let conversion = flags
+            .iter()
+            .find(|f| f.kind == StraddleKind::Conversion)
+            .unwrap();
+        assert_eq!(conversion.rule, TaxRule::ConstructiveSale);
+        assert_eq!(conversion.legs.len(), 3);
+        assert!(!flags.iter().any(|f| f.kind == StraddleKind::ProtectivePut));
+    }
+}
@@ -0,0 +1,268 @@
+//! Wash-sale-adjusted tax lots
+//!
+//! [`TaxLotReport`] puts the output of a [`LotLedger`] and of
+//! [`detect_wash_sales`] side by side: every open lot with its acquisition
+//! date and adjusted basis, every closed lot with its recognized gain or
+//! loss, and the wash sales linking them. Each lot lists the wash sales
+//! that disallowed its loss or deferred a loss into its basis, so a figure
+//! on a tax form can be traced back to the trades behind it.
+//!
+//! # Example
+//! ```
+//! use chrono::NaiveDate;
+//! use ib_flex::analytics::{tax_lot_report, LotLedger, WashSaleConfig};
+//! use ib_flex::types::{BuySell, TradeBuilder};
+//!
+//! let day = |d| NaiveDate::from_ymd_opt(2025, 3, d).unwrap();
+//! let trade = |id: &str, d, side, qty: i64, px: i64| {
+//!     TradeBuilder::new("AAPL").conid(265598).trade_id(id).trade_date(day(d))
+//!         .buy_sell(side).quantity(qty).price(px).build()
+//! };
+//! let trades = vec![
+//!     trade("1", 3, BuySell::Buy, 100, 50),
+//!     trade("2", 10, BuySell::Sell, -100, 40),
+//!     trade("3", 20, BuySell::Buy, 100, 42),
+//! ];
+//!
+//! let mut ledger = LotLedger::new();
+//! ledger.apply_trades(&trades);
+//! let report = tax_lot_report(ledger, &trades, &WashSaleConfig::default());
+//!
+//! // The 1000 loss is deferred into the replacement lot
+//! assert_eq!(report.closed_lots[0].gain_or_loss, 0.into());
+//! assert_eq!(report.open_lots[0].lot.cost_basis, 5200.into());
+//! assert_eq!(report.open_lots[0].deferred_from, vec![0]);
+//! ```
+//!
+//! [`LotLedger`]: super::LotLedger
+
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+#[cfg(feature = "serialize")]
+use serde::Serialize;
+
+use super::form8949::Form8949Row;
+use super::holding::{long_term_from, GainsSummary, HoldingPeriod};
+use super::lots::{Lot, LotClosure, LotLedger};
+use super::wash_sales::{detect_wash_sales, WashSale, WashSaleConfig};
+use crate::types::{Conid, Trade, TradeId};
+
+/// A lot still held at the end of the ledger
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct OpenTaxLot {
+    /// The lot, with its basis and open date adjusted for wash sales
+    pub lot: Lot,
+    /// First day a sale of the lot would be long term
+    pub long_term_from: Option<NaiveDate>,
+    /// Indices into [`TaxLotReport::wash_sales`] of the losses deferred into
+    /// this lot's basis
+    pub deferred_from: Vec<usize>,
+}
+
+/// A lot closed by a trade
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct ClosedTaxLot {
+    /// The closure, with its basis and open date adjusted for wash sales
+    pub closure: LotClosure,
+    /// Short or long term, after any tacked-on holding period
+    pub holding_period: HoldingPeriod,
+    /// Gain or loss to report: realized P&L plus any disallowed loss
+    pub gain_or_loss: Decimal,
+    /// Indices into [`TaxLotReport::wash_sales`] that disallowed this lot's
+    /// loss
+    pub disallowed_by: Vec<usize>,
+    /// Indices into [`TaxLotReport::wash_sales`] of the losses deferred into
+    /// this lot's basis
+    pub deferred_from: Vec<usize>,
+}
+
+/// Open and closed tax lots with the wash sales between them
+///
+/// Amounts are in the currency of each lot.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct TaxLotReport {
+    /// Lots still held
+    pub open_lots: Vec<OpenTaxLot>,
+    /// Lots closed, in the order they were closed
+    pub closed_lots: Vec<ClosedTaxLot>,
+    /// Wash sales applied to the lots
+    pub wash_sales: Vec<WashSale>,
+}
+
+impl TaxLotReport {
+    /// Build the report from a ledger that already had `wash_sales` applied
+    /// with [`LotLedger::apply_wash_sales`]
+    pub fn new(ledger: &LotLedger, wash_sales: Vec<WashSale>) -> Self {
+        let open_lots = ledger
+            .open_lots()
+            .map(|lot| OpenTaxLot {
+                lot: lot.clone(),
+                long_term_from: long_term_from(lot.open_date),
+                deferred_from: replaced_by(
+                    &wash_sales,
+                    lot.conid,
+                    lot.trade_id.as_ref(),
+                    lot.basis_adjustment,
+                ),
+            })
+            .collect();
+        let closed_lots = ledger
+            .closures()
+            .iter()
+            .map(|closure| ClosedTaxLot {
+                closure: closure.clone(),
+                holding_period: closure.holding_period(),
+                gain_or_loss: closure.recognized_pnl(),
+                disallowed_by: wash_sales
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, wash)| {
+                        !closure.disallowed_loss.is_zero()
+                            && wash.conid == closure.conid
+                            && wash.sale_trade_id == closure.closing_trade_id
+                            && wash.sale_date == closure.close_date
+                    })
+                    .map(|(i, _)| i)
+                    .collect(),
+                deferred_from: replaced_by(
+                    &wash_sales,
+                    closure.conid,
+                    closure.opening_trade_id.as_ref(),
+                    closure.basis_adjustment,
+                ),
+            })
+            .collect();
+        TaxLotReport {
+            open_lots,
+            closed_lots,
+            wash_sales,
+        }
+    }
+
+    /// Recognized gains and losses of the closed lots, by term
+    pub fn gains(&self) -> GainsSummary {
+        let mut summary = GainsSummary::default();
+        for lot in &self.closed_lots {
+            summary.add(lot.holding_period, lot.gain_or_loss);
+        }
+        summary
+    }
+
+    /// Disallowed losses still deferred in the basis of open lots
+    pub fn deferred_losses(&self) -> Decimal {
+        self.open_lots.iter().map(|l| l.lot.basis_adjustment).sum()
+    }
+
+    /// Form 8949 rows for the closed lots, in the same order
+    pub fn form_8949_rows(&self) -> Vec<Form8949Row> {
+        self.closed_lots
+            .iter()
+            .map(|lot| Form8949Row::from_closure(&lot.closure))
+            .collect()
+    }
+}
+
+/// Detect and apply wash sales on `ledger`, then build the report
+///
+/// `ledger` must have all trades applied but no wash sales yet; `trades`
+/// are passed to [`detect_wash_sales`] and must include any replacement
+/// purchases.
+pub fn tax_lot_report<'a, I>(
+    mut ledger: LotLedger,
+    trades: I,
+    config: &WashSaleConfig,
+) -> TaxLotReport
+where
+    I: IntoIterator<Item = &'a Trade>,
+{
+    let wash_sales = detect_wash_sales(ledger.closures(), trades, config);
+    ledger.apply_wash_sales(&wash_sales);
+    TaxLotReport::new(&ledger, wash_sales)
+}
+
+/// Wash sales whose replacement shares came from `opening_trade`
+fn replaced_by(
+    wash_sales: &[WashSale],
+    conid: Conid,
+    opening_trade: Option<&TradeId>,
+    basis_adjustment: Decimal,
+) -> Vec<usize> {
+    if basis_adjustment.is_zero() {
+        return Vec::new();
+    }
+    wash_sales
+        .iter()
+        .enumerate()
+        .filter(|(_, wash)| {
+            wash.replacement_conid == conid
+                && opening_trade.is_some()
+                && wash.replacement_trade_id.as_ref() == opening_trade
+        })
+        .map(|(i, _)| i)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{BuySell, TradeBuilder};
+
+    fn day(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 3, d).unwrap()
+    }
+
+    fn stock(id: &str, d: u32, quantity: i64, price: i64) -> Trade {
+        TradeBuilder::new("AAPL")
+            .conid(265598)
+            .trade_id(id)
+            .trade_date(day(d))
+            .buy_sell(if quantity > 0 {
+                BuySell::Buy
+            } else {
+                BuySell::Sell
+            })
+            .quantity(quantity)
+            .price(price)
+            .build()
+    }
+
+    #[test]
+    fn test_links_between_closed_and_open_lots() {
+        let trades = vec![
+            stock("1", 3, 100, 50),
+            stock("2", 10, -100, 40),
+            stock("3", 20, 100, 42),
+            stock("4", 25, -50, 45),
+        ];
+        let mut ledger = LotLedger::new();
+        ledger.apply_trades(&trades);
+        let report = tax_lot_report(ledger, &trades, &WashSaleConfig::default());
+
+        assert_eq!(report.wash_sales.len(), 1);
+        let [loss, partial] = &report.closed_lots[..] else {
+            panic!("expected two closed lots");
+        };
+        assert_eq!(loss.disallowed_by, vec![0]);
+        assert!(loss.deferred_from.is_empty());
+        assert_eq!(loss.gain_or_loss, Decimal::ZERO);
+
+        // Half the replacement lot was sold, carrying half the deferred loss
+        // and the 7 days the first lot was held
+        assert_eq!(partial.deferred_from, vec![0]);
+        assert_eq!(partial.closure.cost_basis, Decimal::from(2600));
+        assert_eq!(partial.closure.open_date, day(13));
+        assert_eq!(partial.gain_or_loss, Decimal::from(-350));
+
+        let open = &report.open_lots[0];
+        assert_eq!(open.lot.cost_basis, Decimal::from(2600));
+        assert_eq!(open.deferred_from, vec![0]);
+        assert_eq!(open.long_term_from, NaiveDate::from_ymd_opt(2026, 3, 14));
+        assert_eq!(report.deferred_losses(), Decimal::from(500));
+        assert_eq!(report.gains().short_term(), Decimal::from(-350));
+        assert_eq!(report.form_8949_rows()[0].codes(), "W");
+    }
+}
@@ -0,0 +1,391 @@
+//! Wash-sale detection
+//!
+//! A loss on a sale of stock or securities is disallowed when substantially
+//! identical securities are bought within 30 days before or after the sale.
+//! [`detect_wash_sales`] takes the loss closures from a [`LotLedger`] run
+//! and the trades behind them, and links each disallowed share to the
+//! purchase that replaced it. [`LotLedger::apply_wash_sales`] then adds the
+//! disallowed losses to the basis of the replacement lots.
+//!
+//! Replacement purchases are consumed in the order they were made, and each
+//! replacement share absorbs at most one loss share. Options count as
+//! `quantity * multiplier` shares of their underlying when
+//! [`WashSaleConfig::options_on_same_underlying`] is set. Only losses on long
+//! lots are considered; short-sale wash rules are not modeled.
+//!
+//! This is a screening tool, not tax advice: IB's own `WashSale` rows and
+//! 1099-B remain authoritative.
+//!
+//! # Example
+//! ```
+//! use chrono::NaiveDate;
+//! use ib_flex::analytics::{detect_wash_sales, LotLedger, WashSaleConfig};
+//! use ib_flex::types::{BuySell, TradeBuilder};
+//!
+//! let day = |d| NaiveDate::from_ymd_opt(2025, 3, d).unwrap();
+//! let trade = |id: &str, d, side, qty: i64, px: i64| {
+//!     TradeBuilder::new("AAPL").conid(265598).trade_id(id).trade_date(day(d))
+//!         .buy_sell(side).quantity(qty).price(px).build()
+//! };
+//! let trades = vec![
+//!     trade("1", 3, BuySell::Buy, 100, 50),
+//!     trade("2", 10, BuySell::Sell, -100, 40),
+//!     trade("3", 20, BuySell::Buy, 100, 42),
+//! ];
+//!
+//! let mut ledger = LotLedger::new();
+//! ledger.apply_trades(&trades);
+//! let washes = detect_wash_sales(ledger.closures(), &trades, &WashSaleConfig::default());
+//!
+//! assert_eq!(washes.len(), 1);
+//! assert_eq!(washes[0].disallowed_loss, 1000.into());
+//! assert_eq!(washes[0].replacement_trade_id.as_deref(), Some("3"));
+//!
+//! ledger.apply_wash_sales(&washes);
+//! let replacement = ledger.open_lots().next().unwrap();
+//! assert_eq!(replacement.cost_basis, (4200 + 1000).into());
+//! ```
+//!
+//! [`LotLedger`]: super::LotLedger
+//! [`LotLedger::apply_wash_sales`]: super::LotLedger::apply_wash_sales
+
+use std::collections::HashMap;
+
+use chrono::{Duration, NaiveDate};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+#[cfg(feature = "serialize")]
+use serde::Serialize;
+
+use super::lots::LotClosure;
+use crate::types::traits::Dated;
+use crate::types::{Conid, LevelOfDetail, Trade, TradeId};
+
+/// Settings for [`detect_wash_sales`]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct WashSaleConfig {
+    /// Days before and after a loss sale in which a purchase triggers a
+    /// wash sale
+    pub window_days: i64,
+    /// Treat options as substantially identical to their underlying
+    pub options_on_same_underlying: bool,
+}
+
+impl Default for WashSaleConfig {
+    fn default() -> Self {
+        WashSaleConfig {
+            window_days: 30,
+            options_on_same_underlying: true,
+        }
+    }
+}
+
+/// A disallowed loss linked to its replacement purchase
+///
+/// A loss sale replaced by several purchases produces one record per
+/// purchase.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct WashSale {
+    /// Contract sold at a loss
+    pub conid: Conid,
+    /// Symbol sold at a loss
+    pub symbol: String,
+    /// Currency of the loss
+    pub currency: String,
+    /// Trade that realized the loss
+    pub sale_trade_id: Option<TradeId>,
+    /// Date of the loss sale
+    pub sale_date: NaiveDate,
+    /// Acquisition date of the lot sold, for holding-period tacking
+    pub sold_lot_open_date: NaiveDate,
+    /// Shares of the sold lot whose loss is disallowed
+    pub quantity: Decimal,
+    /// Disallowed portion of the loss, as a positive amount
+    pub disallowed_loss: Decimal,
+    /// Contract bought as the replacement
+    pub replacement_conid: Conid,
+    /// Trade that bought the replacement, and opened its lot
+    pub replacement_trade_id: Option<TradeId>,
+    /// Date of the replacement purchase
+    pub replacement_date: NaiveDate,
+    /// Replacement quantity used, in the replacement's own units
+    /// (contracts for options)
+    pub replacement_quantity: Decimal,
+}
+
+struct Acquisition<'a> {
+    trade: &'a Trade,
+    date: NaiveDate,
+    key: String,
+    /// Share-equivalents not yet used as a replacement
+    available: Decimal,
+    multiplier: Decimal,
+}
+
+/// Find wash sales among `closures`
+///
+/// `trades` must include the trades that produced `closures` and any
+/// purchases that may replace them, including those after the last sale.
+pub fn detect_wash_sales<'a, I>(
+    closures: &[LotClosure],
+    trades: I,
+    config: &WashSaleConfig,
+) -> Vec<WashSale>
+where
+    I: IntoIterator<Item = &'a Trade>,
+{
+    let mut keys: HashMap<Conid, String> = HashMap::new();
+    let mut acquisitions: Vec<Acquisition<'a>> = Vec::new();
+    for trade in trades {
+        if !matches!(trade.level_of_detail, None | Some(LevelOfDetail::Execution)) {
+            continue;
+        }
+        let key = identity_key(trade, config);
+        keys.entry(trade.conid).or_insert_with(|| key.clone());
+        let (Some(quantity), Some(date)) = (trade.signed_quantity(), trade.date()) else {
+            continue;
+        };
+        if quantity.is_sign_positive() && !quantity.is_zero() {
+            let multiplier = share_multiplier(trade, config);
+            acquisitions.push(Acquisition {
+                trade,
+                date,
+                key,
+                available: quantity * multiplier,
+                multiplier,
+            });
+        }
+    }
+    acquisitions.sort_by(|a, b| {
+        a.trade
+            .chronological_key()
+            .cmp(&b.trade.chronological_key())
+    });
+
+    let mut losses: Vec<&LotClosure> = closures
+        .iter()
+        .filter(|c| c.quantity.is_sign_positive() && c.realized_pnl < Decimal::ZERO)
+        .collect();
+    losses.sort_by_key(|c| c.close_date);
+
+    let window = Duration::days(config.window_days);
+    let mut washes = Vec::new();
+    for loss in losses {
+        let key = keys
+            .get(&loss.conid)
+            .cloned()
+            .unwrap_or_else(|| loss.symbol.clone());
+        let loss_multiplier = acquisitions
+            .iter()
+            .find(|a| a.trade.conid == loss.conid)
+            .map_or(Decimal::ONE, |a| a.multiplier);
+        let loss_per_share = -loss.realized_pnl / (loss.quantity * loss_multiplier);
+        let mut unmatched = loss.quantity * loss_multiplier;
+
+        for acquisition in acquisitions.iter_mut() {
+            if unmatched.is_zero() {
+                break;
+            }
+            if acquisition.available.is_zero()
+                || acquisition.key != key
+                || (acquisition.date - loss.close_date).abs() > window
+                || opened_sold_lot(acquisition, loss)
+            {
+                continue;
+            }
+            let shares = unmatched.min(acquisition.available);
+            acquisition.available -= shares;
+            unmatched -= shares;
+            washes.push(WashSale {
+                conid: loss.conid,
+                symbol: loss.symbol.clone(),
+                currency: loss.currency.clone(),
+                sale_trade_id: loss.closing_trade_id.clone(),
+                sale_date: loss.close_date,
+                sold_lot_open_date: loss.open_date,
+                quantity: shares / loss_multiplier,
+                disallowed_loss: loss_per_share * shares,
+                replacement_conid: acquisition.trade.conid,
+                replacement_trade_id: acquisition.trade.trade_id.clone(),
+                replacement_date: acquisition.date,
+                replacement_quantity: shares / acquisition.multiplier,
+            });
+        }
+    }
+    washes
+}
+
+/// Key under which substantially identical securities group together
+fn identity_key(trade: &Trade, config: &WashSaleConfig) -> String {
+    if config.options_on_same_underlying && trade.asset_category.is_option() {
+        if let Some(underlying) = trade
+            .underlying_symbol
+            .clone()
+            .filter(|s| !s.is_empty())
+            .or_else(|| trade.option_contract().map(|c| c.underlying))
+        {
+            return underlying;
+        }
+    }
+    trade.symbol.clone()
+}
+
+fn share_multiplier(trade: &Trade, config: &WashSaleConfig) -> Decimal {
+    if config.options_on_same_underlying && trade.asset_category.is_option() {
+        trade.multiplier.unwrap_or(Decimal::ONE)
+    } else {
+        Decimal::ONE
+    }
+}
+
+/// The purchase that created the lot being sold is never its own replacement
+fn opened_sold_lot(acquisition: &Acquisition<'_>, loss: &LotClosure) -> bool {
+    match (&acquisition.trade.trade_id, &loss.opening_trade_id) {
+        (Some(a), Some(b)) => a == b,
+        _ => acquisition.trade.conid == loss.conid && acquisition.date == loss.open_date,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analytics::LotLedger;
+    use crate::types::{AssetCategory, BuySell, PutCall, TradeBuilder};
+
+    fn day(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 3, d).unwrap()
+    }
+
+    fn stock(id: &str, d: u32, quantity: i64, price: i64) -> Trade {
+        TradeBuilder::new("AAPL")
+            .conid(265598)
+            .trade_id(id)
+            .trade_date(day(d))
+            .buy_sell(if quantity > 0 {
+                BuySell::Buy
+            } else {
+                BuySell::Sell
+            })
+            .quantity(quantity)
+            .price(price)
+            .build()
+    }
+
+    fn run(trades: &[Trade], config: &WashSaleConfig) -> Vec<WashSale> {
+        let mut ledger = LotLedger::new();
+        ledger.apply_trades(trades);
+        detect_wash_sales(ledger.closures(), trades, config)
+    }
+
+    #[test]
+    fn test_partial_replacement_before_and_after_sale() {
+        let trades = vec![
+            stock("1", 2, 100, 50),
+            stock("2", 5, 30, 45),
+            stock("3", 10, -100, 40),
+            stock("4", 31, 50, 41),
+        ];
+        let washes = run(&trades, &WashSaleConfig::default());
+
+        // 30 replaced by the earlier purchase, 50 by the later one; 20 allowed
+        assert_eq!(washes.len(), 2);
+        assert_eq!(washes[0].replacement_trade_id.as_deref(), Some("2"));
+        assert_eq!(washes[0].quantity, Decimal::from(30));
+        assert_eq!(washes[0].disallowed_loss, Decimal::from(300));
+        assert_eq!(washes[1].replacement_trade_id.as_deref(), Some("4"));
+        assert_eq!(washes[1].disallowed_loss, Decimal::from(500));
+        assert_eq!(washes[1].sold_lot_open_date, day(2));
+    }
+
+    #[test]
+    fn test_outside_window_and_gains_are_ignored() {
+        let mut trades = vec![stock("1", 2, 100, 50), stock("2", 3, -100, 40)];
+        trades.push(
+            TradeBuilder::new("AAPL")
+                .conid(265598)
+                .trade_id("3")
+                .trade_date(NaiveDate::from_ymd_opt(2025, 4, 3).unwrap())
+                .buy_sell(BuySell::Buy)
+                .quantity(100)
+                .price(40)
+                .build(),
+        );
+        assert!(run(&trades, &WashSaleConfig::default()).is_empty());
+
+        let gains = vec![
+            stock("1", 2, 100, 40),
+            stock("2", 3, -100, 50),
+            stock("3", 4, 100, 50),
+        ];
+        assert!(run(&gains, &WashSaleConfig::default()).is_empty());
+    }
+
+    #[test]
+    fn test_call_option_replaces_stock_when_configured() {
+        let call = TradeBuilder::new("AAPL  250620C00150000")
+            .conid(700001)
+            .trade_id("9")
+            .asset_category(AssetCategory::Option)
+            .underlying_symbol("AAPL")
+            .put_call(PutCall::Call)
+            .multiplier(100)
+            .trade_date(day(12))
+            .buy_sell(BuySell::Buy)
+            .quantity(1)
+            .price(5)
+            .build();
+        let trades = vec![stock("1", 2, 200, 50), stock("2", 10, -200, 40), call];
+
+        let washes = run(&trades, &WashSaleConfig::default());
+        assert_eq!(washes.len(), 1);
+        assert_eq!(washes[0].quantity, Decimal::from(100));
+        assert_eq!(washes[0].replacement_quantity, Decimal::ONE);
+        assert_eq!(washes[0].disallowed_loss, Decimal::from(1000));
+
+        let strict = WashSaleConfig {
+            options_on_same_underlying: false,
+            ..Default::default()
+        };
+        assert!(run(&trades, &strict).is_empty());
+    }
+
+    #[test]
+    fn test_apply_wash_sales_defers_loss_into_replacement_shares() {
+        let trades = vec![
+            stock("1", 3, 50, 50),
+            stock("2", 10, -50, 40),
+            stock("3", 20, 100, 42),
+            stock("4", 25, -30, 45),
+        ];
+        let mut ledger = LotLedger::new();
+        ledger.apply_trades(&trades);
+        let washes = detect_wash_sales(ledger.closures(), &trades, &WashSaleConfig::default());
+        assert_eq!(washes.len(), 1);
+        assert_eq!(washes[0].disallowed_loss, Decimal::from(500));
+        ledger.apply_wash_sales(&washes);
+
+        let closures = ledger.closures();
+        assert_eq!(closures[0].realized_pnl, Decimal::from(-500));
+        assert_eq!(closures[0].recognized_pnl(), Decimal::ZERO);
+
+        // The 30 replacement shares already sold absorb 300 and the 7 days
+        // the sold shares were held
+        assert_eq!(closures[1].quantity, Decimal::from(30));
+        assert_eq!(closures[1].basis_adjustment, Decimal::from(300));
+        assert_eq!(closures[1].realized_pnl, Decimal::from(90 - 300));
+        assert_eq!(closures[1].open_date, day(13));
+
+        // The remaining 20 replacement shares are split from the open lot
+        let lots: Vec<&crate::analytics::Lot> = ledger.open_lots().collect();
+        assert_eq!(lots.len(), 2);
+        assert_eq!(lots[0].quantity, Decimal::from(20));
+        assert_eq!(lots[0].cost_basis, Decimal::from(20 * 42 + 200));
+        assert_eq!(lots[0].basis_adjustment, Decimal::from(200));
+        assert_eq!(lots[0].open_date, day(13));
+        assert_eq!(lots[1].quantity, Decimal::from(50));
+        assert_eq!(lots[1].cost_basis, Decimal::from(50 * 42));
+        assert_eq!(lots[1].open_date, day(20));
+    }
+}
@@ -0,0 +1,293 @@
+//! Statement anonymizer
+//!
+//! Scrubs what identifies an account holder from a statement, so it can be
+//! shared in a bug report or used as a test fixture:
+//!
+//! - Account IDs are replaced wherever they appear, including inside
+//!   descriptions such as a transfer's, by pseudonyms of the same kind and
+//!   length (`U1234567` becomes `U` and seven other digits).
+//! - Aliases, models, names, addresses, and emails are emptied.
+//! - Optionally, quantities and amounts are multiplied by
+//!   [`AnonymizeOptions::scale`]. Prices, rates, and multipliers are kept, so
+//!   amounts stay consistent with quantity × price without revealing the
+//!   size of the account.
+//!
+//! Pseudonyms are derived from the ID and [`AnonymizeOptions::key`], not
+//! numbered, so an account gets the same pseudonym in every file scrubbed
+//! with the same key and transfers between accounts still line up. With the
+//! default empty key anyone can map a known account to its pseudonym; use a
+//! secret key when that matters. Distinct accounts can, rarely, share a
+//! pseudonym.
+//!
+//! [`anonymize_xml`] rewrites the attributes of a FLEX document and keeps
+//! everything else, including sections the parser does not read.
+//! [`anonymize`] does the same to a parsed statement.
+//!
+//! # Example
+//! ```
+//! use ib_flex::{anonymize_xml, parse_activity_flex, AnonymizeOptions};
+//!
+//! let xml = include_str!("../tests/fixtures/activity_minimal.xml");
+//! let options = AnonymizeOptions::default();
+//!
+//! let scrubbed = anonymize_xml(xml, &options).unwrap();
+//! assert!(!scrubbed.contains("U1234567"));
+//! let statement = parse_activity_flex(&scrubbed).unwrap();
+//! assert_eq!(statement.account_id, options.account_pseudonym("U1234567"));
+//! ```
+
+use quick_xml::events::{BytesStart, Event};
+use rust_decimal::Decimal;
+
+use crate::types::AccountId;
+#[cfg(feature = "serialize")]
+use crate::types::ActivityFlexStatement;
+use crate::{ParseError, Result};
+
+/// Settings for [`anonymize`] and [`anonymize_xml`]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct AnonymizeOptions {
+    /// Mixed into every pseudonym (default empty); keep it to get the same
+    /// pseudonyms in later files, and keep it secret to stop pseudonyms of
+    /// known accounts from being recomputed
+    pub key: String,
+    /// Factor for quantities and amounts (default `None`: unchanged)
+    pub scale: Option<Decimal>,
+}
+
+impl AnonymizeOptions {
+    /// Pseudonym of an account ID: the same prefix followed by as many
+    /// digits, derived from the ID and [`key`](Self::key)
+    pub fn account_pseudonym(&self, account: &str) -> String {
+        let digits = account
+            .find(|c: char| c.is_ascii_digit())
+            .unwrap_or(account.len());
+        let (prefix, number) = account.split_at(digits);
+        let width = number.len().min(18);
+        let modulus = 10u64.pow(width as u32);
+        let n = fnv1a([self.key.as_bytes(), &[0], account.as_bytes()]) % modulus;
+        format!("{}{:0width$}", prefix, n, width = width)
+    }
+}
+
+/// Attributes that identify the account holder, written empty, along
+/// with the `...ResidentialAddress` variants of the address attributes
+const PERSONAL_ATTRIBUTES: &[&str] = &[
+    "acctAlias",
+    "model",
+    "name",
+    "accountAlias",
+    "accountTitle",
+    "masterName",
+    "accountRepName",
+    "accountRepPhone",
+    "street",
+    "street2",
+    "streetAddress",
+    "streetAddress2",
+    "city",
+    "state",
+    "postalCode",
+    "country",
+    "primaryEmail",
+    "dateOfBirth",
+];
+
+/// Parts of attribute names holding quantities or money amounts
+const AMOUNT_ATTRIBUTES: &[&str] = &[
+    "quantity",
+    "position",
+    "proceeds",
+    "cash",
+    "money",
+    "amount",
+    "commission",
+    "tax",
+    "pnl",
+    "value",
+    "cost",
+    "fee",
+    "total",
+    "balance",
+    "interest",
+    "dividend",
+];
+
+/// Parts of attribute names with "amount" words that are not amounts
+const UNSCALED_ATTRIBUTES: &[&str] =
+    &["price", "rate", "multiplier", "strike", "percent", "factor"];
+
+/// Anonymize a parsed statement in place
+///
+/// The statement is written as FLEX XML, scrubbed with [`anonymize_xml`],
+/// and parsed back, so every section the parser reads is covered. Requires
+/// the `serialize` feature.
+#[cfg(feature = "serialize")]
+pub fn anonymize(statement: &mut ActivityFlexStatement, options: &AnonymizeOptions) -> Result<()> {
+    let xml = crate::write_activity_flex(std::slice::from_ref(statement))?;
+    *statement = crate::parse_activity_flex(&anonymize_xml(&xml, options)?)?;
+    Ok(())
+}
+
+/// Anonymize a FLEX XML document
+///
+/// Each element's attributes are rewritten; elements, comments, and text
+/// are kept, although attributes are written on one line.
+pub fn anonymize_xml(xml: &str, options: &AnonymizeOptions) -> Result<String> {
+    let mut reader = quick_xml::Reader::from_str(xml);
+    let mut writer = quick_xml::Writer::new(Vec::new());
+    loop {
+        let event = reader.read_event().map_err(xml_error)?;
+        let (element, empty) = match event {
+            Event::Eof => break,
+            Event::Start(element) => (element, false),
+            Event::Empty(element) => (element, true),
+            event => {
+                writer.write_event(event)?;
+                continue;
+            }
+        };
+        let name = String::from_utf8_lossy(element.name().as_ref()).into_owned();
+        let mut scrubbed = BytesStart::new(name);
+        for attribute in element.attributes() {
+            let attribute = attribute.map_err(|err| xml_error(err.into()))?;
+            let key = String::from_utf8_lossy(attribute.key.as_ref()).into_owned();
+            let value = attribute
+                .normalized_value(quick_xml::XmlVersion::Implicit1_0)
+                .map_err(xml_error)?;
+            let value = if PERSONAL_ATTRIBUTES.contains(&key.as_str())
+                || key.ends_with("ResidentialAddress")
+            {
+                String::new()
+            } else {
+                let value = replace_accounts(&value, options);
+                match options.scale {
+                    Some(factor) if is_amount(&key) => scale_amount(&value, factor),
+                    _ => value,
+                }
+            };
+            scrubbed.push_attribute((key.as_str(), value.as_str()));
+        }
+        writer.write_event(if empty {
+            Event::Empty(scrubbed)
+        } else {
+            Event::Start(scrubbed)
+        })?;
+    }
+    String::from_utf8(writer.into_inner()).map_err(|err| ParseError::XmlError {
+        message: err.to_string(),
+        location: None,
+    })
+}
+
+fn xml_error(err: quick_xml::Error) -> ParseError {
+    ParseError::XmlError {
+        message: format!("Failed to anonymize FLEX XML: {}", err),
+        location: None,
+    }
+}
+
+/// Replace the account IDs among the words of `value`
+fn replace_accounts(value: &str, options: &AnonymizeOptions) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start) = rest.find(|c: char| c.is_ascii_alphanumeric()) {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let end = rest
+            .find(|c: char| !c.is_ascii_alphanumeric())
+            .unwrap_or(rest.len());
+        let word = &rest[..end];
+        match word.parse::<AccountId>() {
+            // Short numbers after an `F` or `I` are rarely accounts
+            Ok(_) if word.len() >= 6 => out.push_str(&options.account_pseudonym(word)),
+            _ => out.push_str(word),
+        }
+        rest = &rest[end..];
+    }
+    out.push_str(rest);
+    out
+}
+
+fn is_amount(attribute: &str) -> bool {
+    let name = attribute.to_ascii_lowercase();
+    AMOUNT_ATTRIBUTES.iter().any(|part| name.contains(part))
+        && !UNSCALED_ATTRIBUTES.iter().any(|part| name.contains(part))
+        && !attribute.ends_with("ID")
+        && !attribute.ends_with("Id")
+}
+
+/// `value` times `factor`, to the same number of decimal places, or
+/// unchanged if it is not a number
+fn scale_amount(value: &str, factor: Decimal) -> String {
+    match value.parse::<Decimal>() {
+        Ok(amount) => (amount * factor).round_dp(amount.scale()).to_string(),
+        Err(_) => value.to_string(),
+    }
+}
+
+/// 64-bit FNV-1a, which unlike the standard library's hasher is the same
+/// in every build
+pub(crate) fn fnv1a<'a>(parts: impl IntoIterator<Item = &'a [u8]>) -> u64 {
+    let mut hash = 0xcbf2_9ce4_8422_2325_u64;
+    for byte in parts.into_iter().flatten() {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pseudonyms_are_consistent() {
+        let options = AnonymizeOptions {
+            key: "secret".to_string(),
+            ..Default::default()
+        };
+        let pseudonym = options.account_pseudonym("U1234567");
+        assert_eq!(pseudonym.len(), 8);
+        assert!(pseudonym.starts_with('U') && pseudonym != "U1234567");
+        assert_eq!(options.account_pseudonym("U1234567"), pseudonym);
+        assert_ne!(
+            AnonymizeOptions::default().account_pseudonym("U1234567"),
+            pseudonym
+        );
+        assert!(options.account_pseudonym("DU1234567").starts_with("DU"));
+
+        assert_eq!(
+            replace_accounts("Transfer to U1234567 (FX)", &options),
+            format!("Transfer to {} (FX)", pseudonym)
+        );
+        assert_eq!(replace_accounts("F5 I2 USD.CAD", &options), "F5 I2 USD.CAD");
+    }
+
+    #[test]
+    fn test_anonymize_statement() {
+        let xml = include_str!("../tests/fixtures/activity_daily_portfolio.xml");
+        let original = crate::parse_activity_flex(xml).unwrap();
+        let options = AnonymizeOptions {
+            scale: Some(Decimal::TWO),
+            ..Default::default()
+        };
+
+        let mut statement = original.clone();
+        anonymize(&mut statement, &options).unwrap();
+        let account = options.account_pseudonym("U1234567");
+        assert_eq!(statement.account_id, account.as_str());
+        let info = statement.account_information.as_ref().unwrap();
+        assert_eq!(info.name, None);
+        for (before, after) in original.trades.iter().zip(&statement.trades) {
+            assert_eq!(after.account_id, account.as_str());
+            assert_eq!(after.price, before.price);
+            assert_eq!(after.quantity, before.quantity.map(|q| q * Decimal::TWO));
+        }
+
+        // Typed and raw variants agree
+        let raw = crate::parse_activity_flex(&anonymize_xml(xml, &options).unwrap()).unwrap();
+        assert_eq!(raw.account_id, statement.account_id);
+        assert_eq!(raw.trades, statement.trades);
+    }
+}
@@ -0,0 +1,429 @@
+//! proptest strategies for statement types (requires the `proptest` feature)
+//!
+//! [`Trade`], [`Position`], [`CashTransaction`], and
+//! [`ActivityFlexStatement`] implement [`Arbitrary`], so `any::<Trade>()`
+//! works in `proptest!` blocks, and [`statement_xml`] pairs a statement with
+//! the FLEX XML it is written as, for round-trip properties:
+//!
+//! ```
+//! use ib_flex::arbitrary::statement_xml;
+//! use ib_flex::parse_activity_flex;
+//! use proptest::prelude::*;
+//!
+//! proptest!(ProptestConfig::with_cases(8), |((statement, xml) in statement_xml())| {
+//!     prop_assert_eq!(parse_activity_flex(&xml).unwrap(), statement);
+//! });
+//! ```
+//!
+//! Values fill the fields most code reads: identifiers, contract fields,
+//! dates, quantities, prices, and amounts. Other fields keep their
+//! defaults. Strings are non-empty and include XML's special characters,
+//! decimals have up to six places, and enums take their known variants only,
+//! so every generated value is one the parser can give back.
+
+use chrono::{Days, NaiveDate, NaiveDateTime, NaiveTime};
+use proptest::prelude::*;
+use rust_decimal::Decimal;
+
+use crate::types::{
+    AccountId, ActivityFlexStatement, AssetCategory, BuySell, CashTransaction, CashTransactionType,
+    Conid, LevelOfDetail, LongShort, OpenClose, Position, PutCall, Trade,
+};
+
+impl Arbitrary for Trade {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        let ids = (
+            account_id(),
+            proptest::option::of(numeric_id()),
+            proptest::option::of(numeric_id()),
+            proptest::option::of(numeric_id()),
+            date_time(),
+            proptest::option::of(prop_oneof![
+                Just(LevelOfDetail::Execution),
+                Just(LevelOfDetail::Order),
+                Just(LevelOfDetail::Summary),
+            ]),
+        );
+        let trade = (
+            proptest::option::of(prop_oneof![
+                Just(BuySell::Buy),
+                Just(BuySell::Sell),
+                Just(BuySell::CancelBuy),
+                Just(BuySell::CancelSell),
+            ]),
+            proptest::option::of(prop_oneof![
+                Just(OpenClose::Open),
+                Just(OpenClose::Close),
+                Just(OpenClose::CloseOpen),
+            ]),
+            proptest::option::of(decimal()),
+            proptest::option::of(decimal()),
+            proptest::option::of(decimal()),
+            proptest::option::of(decimal()),
+            proptest::option::of(decimal()),
+            proptest::option::of(decimal()),
+            currency(),
+            proptest::option::of(decimal()),
+        );
+        (ids, contract(), trade)
+            .prop_map(|(ids, contract, trade)| {
+                let (account_id, transaction_id, trade_id, ib_order_id, time, level) = ids;
+                let (buy_sell, open_close, quantity, price, proceeds, commission, net_cash, pnl) = (
+                    trade.0, trade.1, trade.2, trade.3, trade.4, trade.5, trade.6, trade.7,
+                );
+                Trade {
+                    account_id,
+                    transaction_id,
+                    conid: contract.conid,
+                    symbol: contract.symbol,
+                    description: contract.description,
+                    asset_category: contract.asset_category,
+                    multiplier: contract.multiplier,
+                    strike: contract.strike,
+                    expiry: contract.expiry,
+                    put_call: contract.put_call,
+                    trade_date: time.map(|t| t.date()),
+                    settle_date: time.and_then(|t| t.date().checked_add_days(Days::new(2))),
+                    trade_time: time,
+                    trade_time_raw: time.map(|t| t.format("%Y%m%d;%H%M%S").to_string()),
+                    buy_sell,
+                    open_close,
+                    quantity,
+                    price,
+                    proceeds,
+                    commission,
+                    net_cash,
+                    fifo_pnl_realized: pnl,
+                    currency: trade.8,
+                    fx_rate_to_base: trade.9,
+                    trade_id: trade_id.map(Into::into),
+                    ib_order_id: ib_order_id.map(Into::into),
+                    level_of_detail: level,
+                    ..Default::default()
+                }
+            })
+            .boxed()
+    }
+}
+
+impl Arbitrary for Position {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        let values = (
+            decimal(),
+            decimal(),
+            decimal(),
+            proptest::option::of(prop_oneof![Just(LongShort::Long), Just(LongShort::Short)]),
+            proptest::option::of(decimal()),
+            proptest::option::of(decimal()),
+            proptest::option::of(decimal()),
+            currency(),
+            proptest::option::of(decimal()),
+            date(),
+            proptest::option::of(prop_oneof![
+                Just(LevelOfDetail::Summary),
+                Just(LevelOfDetail::Lot),
+            ]),
+        );
+        (account_id(), contract(), values)
+            .prop_map(|(account_id, contract, values)| Position {
+                account_id,
+                conid: contract.conid,
+                symbol: contract.symbol,
+                description: contract.description,
+                asset_category: contract.asset_category,
+                multiplier: contract.multiplier,
+                strike: contract.strike,
+                expiry: contract.expiry,
+                put_call: contract.put_call,
+                quantity: values.0,
+                mark_price: values.1,
+                position_value: values.2,
+                side: values.3,
+                cost_basis_price: values.4,
+                cost_basis_money: values.5,
+                fifo_pnl_unrealized: values.6,
+                currency: values.7,
+                fx_rate_to_base: values.8,
+                report_date: values.9,
+                level_of_detail: values.10,
+                ..Default::default()
+            })
+            .boxed()
+    }
+}
+
+impl Arbitrary for CashTransaction {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        (
+            account_id(),
+            proptest::option::of(numeric_id()),
+            proptest::option::of(prop_oneof![
+                Just(CashTransactionType::Dividends),
+                Just(CashTransactionType::WithholdingTax),
+                Just(CashTransactionType::BrokerInterestReceived),
+                Just(CashTransactionType::BrokerInterestPaid),
+                Just(CashTransactionType::DepositsWithdrawals),
+                Just(CashTransactionType::OtherFees),
+                Just(CashTransactionType::PaymentInLieuOfDividends),
+            ]),
+            proptest::option::of(text()),
+            decimal(),
+            currency(),
+            proptest::option::of(decimal()),
+            proptest::option::of(date()),
+            proptest::option::of(date()),
+            proptest::option::of((1u64..=u32::MAX as u64, symbol())),
+        )
+            .prop_map(
+                |(
+                    account_id,
+                    id,
+                    kind,
+                    description,
+                    amount,
+                    currency,
+                    fx,
+                    date,
+                    settle,
+                    security,
+                )| {
+                    let (conid, symbol) = security.unzip();
+                    CashTransaction {
+                        account_id,
+                        transaction_id: id,
+                        transaction_type: kind,
+                        description,
+                        amount,
+                        currency,
+                        fx_rate_to_base: fx,
+                        date,
+                        settle_date: settle,
+                        conid: conid.map(|c| c.to_string()),
+                        symbol,
+                        ..Default::default()
+                    }
+                },
+            )
+            .boxed()
+    }
+}
+
+impl Arbitrary for ActivityFlexStatement {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        (
+            account_id(),
+            date(),
+            0u64..400,
+            proptest::collection::vec(any::<Trade>(), 0..8),
+            proptest::collection::vec(any::<Position>(), 0..8),
+            proptest::collection::vec(any::<CashTransaction>(), 0..8),
+        )
+            .prop_map(|(account_id, from_date, days, trades, positions, cash)| {
+                let to_date = from_date + Days::new(days);
+                let when_generated = format!("{};083000", to_date.format("%Y%m%d"));
+                let mut statement =
+                    ActivityFlexStatement::empty(account_id, from_date, to_date, when_generated);
+                // Rows belong to the statement's account
+                statement.trades.items = trades
+                    .into_iter()
+                    .map(|t| Trade {
+                        account_id: statement.account_id.clone(),
+                        ..t
+                    })
+                    .collect();
+                statement.positions.items = positions
+                    .into_iter()
+                    .map(|p| Position {
+                        account_id: statement.account_id.clone(),
+                        ..p
+                    })
+                    .collect();
+                statement.cash_transactions.items = cash
+                    .into_iter()
+                    .map(|c| CashTransaction {
+                        account_id: statement.account_id.clone(),
+                        ..c
+                    })
+                    .collect();
+                statement
+            })
+            .boxed()
+    }
+}
+
+/// An arbitrary statement and the FLEX XML it is written as
+pub fn statement_xml() -> impl Strategy<Value = (ActivityFlexStatement, String)> {
+    any::<ActivityFlexStatement>().prop_map(|statement| {
+        let xml = crate::write_activity_flex(std::slice::from_ref(&statement))
+            .expect("generated statements serialize");
+        (statement, xml)
+    })
+}
+
+/// Contract fields shared by trades and positions
+#[derive(Debug, Clone)]
+struct Contract {
+    conid: Conid,
+    symbol: String,
+    description: Option<String>,
+    asset_category: AssetCategory,
+    multiplier: Option<Decimal>,
+    strike: Option<Decimal>,
+    expiry: Option<NaiveDate>,
+    put_call: Option<PutCall>,
+}
+
+fn contract() -> impl Strategy<Value = Contract> {
+    let stock = (
+        1u64..=u32::MAX as u64,
+        symbol(),
+        proptest::option::of(text()),
+    )
+        .prop_map(|(conid, symbol, description)| Contract {
+            conid: Conid(conid),
+            symbol,
+            description,
+            asset_category: AssetCategory::Stock,
+            multiplier: Some(Decimal::ONE),
+            strike: None,
+            expiry: None,
+            put_call: None,
+        });
+    let option = (
+        1u64..=u32::MAX as u64,
+        symbol(),
+        proptest::option::of(text()),
+        decimal(),
+        date(),
+        prop_oneof![Just(PutCall::Put), Just(PutCall::Call)],
+    )
+        .prop_map(
+            |(conid, symbol, description, strike, expiry, put_call)| Contract {
+                conid: Conid(conid),
+                symbol,
+                description,
+                asset_category: AssetCategory::Option,
+                multiplier: Some(Decimal::ONE_HUNDRED),
+                strike: Some(strike.abs()),
+                expiry: Some(expiry),
+                put_call: Some(put_call),
+            },
+        );
+    let other = (
+        1u64..=u32::MAX as u64,
+        symbol(),
+        proptest::option::of(text()),
+        prop_oneof![
+            Just(AssetCategory::Future),
+            Just(AssetCategory::Cash),
+            Just(AssetCategory::Bond),
+            Just(AssetCategory::Fund),
+            Just(AssetCategory::Warrant),
+            Just(AssetCategory::Cryptocurrency),
+        ],
+        proptest::option::of(decimal()),
+    )
+        .prop_map(
+            |(conid, symbol, description, asset_category, multiplier)| Contract {
+                conid: Conid(conid),
+                symbol,
+                description,
+                asset_category,
+                multiplier,
+                strike: None,
+                expiry: None,
+                put_call: None,
+            },
+        );
+    prop_oneof![3 => stock, 1 => option, 1 => other]
+}
+
+fn account_id() -> impl Strategy<Value = AccountId> {
+    "U[0-9]{7}".prop_map(AccountId::from)
+}
+
+fn numeric_id() -> impl Strategy<Value = String> {
+    "[1-9][0-9]{0,11}"
+}
+
+fn symbol() -> impl Strategy<Value = String> {
+    "[A-Z]{1,5}"
+}
+
+fn currency() -> impl Strategy<Value = String> {
+    prop_oneof![
+        Just("USD"),
+        Just("EUR"),
+        Just("GBP"),
+        Just("CAD"),
+        Just("JPY")
+    ]
+    .prop_map(String::from)
+}
+
+/// Text with XML's special characters, without leading or trailing spaces
+fn text() -> impl Strategy<Value = String> {
+    "[A-Za-z0-9&<>'\"]([A-Za-z0-9&<>'\" .,/()-]{0,30}[A-Za-z0-9)])?"
+}
+
+fn decimal() -> impl Strategy<Value = Decimal> {
+    (-1_000_000_000_000i64..1_000_000_000_000, 0u32..=6)
+        .prop_map(|(mantissa, scale)| Decimal::new(mantissa, scale))
+}
+
+fn date() -> impl Strategy<Value = NaiveDate> {
+    let epoch = NaiveDate::from_ymd_opt(2000, 1, 1).expect("valid date");
+    (0u64..15_000).prop_map(move |days| epoch + Days::new(days))
+}
+
+fn date_time() -> impl Strategy<Value = Option<NaiveDateTime>> {
+    proptest::option::of((date(), 0u32..86_400)).prop_map(|value| {
+        value.map(|(date, seconds)| {
+            let time = NaiveTime::from_num_seconds_from_midnight_opt(seconds, 0)
+                .expect("seconds within a day");
+            date.and_time(time)
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn test_statements_round_trip((statement, xml) in statement_xml()) {
+            prop_assert_eq!(crate::parse_activity_flex(&xml).unwrap(), statement);
+        }
+
+        #[test]
+        fn test_rows_round_trip(trade in any::<Trade>(), position in any::<Position>()) {
+            let mut statement = ActivityFlexStatement::empty(
+                trade.account_id.clone(),
+                NaiveDate::from_ymd_opt(2025, 1, 1).unwrap(),
+                NaiveDate::from_ymd_opt(2025, 1, 31).unwrap(),
+                "20250131;083000".to_string(),
+            );
+            statement.trades.items.push(trade.clone());
+            statement.positions.items.push(position.clone());
+            let xml = crate::write_activity_flex(&[statement]).unwrap();
+            let parsed = crate::parse_activity_flex(&xml).unwrap();
+            prop_assert_eq!(&parsed.trades[0], &trade);
+            prop_assert_eq!(&parsed.positions[0], &position);
+        }
+    }
+}
@@ -0,0 +1,722 @@
+//! `ib-flex-tui` statement viewer (requires the `tui` feature)
+//!
+//! ```text
+//! ib-flex-tui statement.xml [more.xml ...]
+//! ```
+//!
+//! Loads every Activity FLEX statement in the files and shows one section
+//! at a time: trades, open positions, cash transactions, corporate actions,
+//! and P&L per symbol over all the statements. Keys:
+//!
+//! ```text
+//! Tab / Shift-Tab, ← / →   next / previous section (or 1-5)
+//! ↑ / ↓, j / k             scroll a row
+//! PgUp / PgDn, Space       scroll a page
+//! g / G, Home / End        first / last row
+//! /                        filter by symbol (case-insensitive substring)
+//! d                        filter by date: 2025-01-01..2025-03-31, either side optional
+//! c                        clear filters
+//! q, Esc                   quit
+//! ```
+//!
+//! The terminal is driven with ANSI escape sequences, so the viewer needs a
+//! Unix terminal.
+
+use std::error::Error;
+use std::process::ExitCode;
+
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+
+use ib_flex::analytics::{attribution_report, AttributionLevel};
+use ib_flex::{parse_activity_flex_all, ActivityFlexStatement};
+
+type TuiResult<T> = Result<T, Box<dyn Error>>;
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run() -> TuiResult<()> {
+    let files: Vec<String> = std::env::args().skip(1).collect();
+    if files.is_empty() || files.iter().any(|f| f == "-h" || f == "--help") {
+        println!("Usage: ib-flex-tui <FILE>...");
+        return Ok(());
+    }
+    let mut statements = Vec::new();
+    for path in &files {
+        let xml = std::fs::read_to_string(path).map_err(|err| format!("{}: {}", path, err))?;
+        statements
+            .extend(parse_activity_flex_all(&xml).map_err(|err| format!("{}: {}", path, err))?);
+    }
+    let mut app = App::new(&statements);
+    terminal::run(&mut app)
+}
+
+// ==================== State ====================
+
+/// A section of the viewer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Section {
+    Trades,
+    Positions,
+    CashTransactions,
+    CorporateActions,
+    Pnl,
+}
+
+impl Section {
+    const ALL: [Section; 5] = [
+        Section::Trades,
+        Section::Positions,
+        Section::CashTransactions,
+        Section::CorporateActions,
+        Section::Pnl,
+    ];
+
+    fn title(self) -> &'static str {
+        match self {
+            Section::Trades => "Trades",
+            Section::Positions => "Positions",
+            Section::CashTransactions => "Cash",
+            Section::CorporateActions => "Corporate actions",
+            Section::Pnl => "P&L by symbol",
+        }
+    }
+
+    fn headers(self) -> &'static [&'static str] {
+        match self {
+            Section::Trades => &[
+                "Date",
+                "Symbol",
+                "Side",
+                "Quantity",
+                "Price",
+                "Proceeds",
+                "Commission",
+                "Realized",
+                "Currency",
+            ],
+            Section::Positions => &[
+                "Date",
+                "Symbol",
+                "Quantity",
+                "Mark",
+                "Value",
+                "Unrealized",
+                "Currency",
+            ],
+            Section::CashTransactions => &[
+                "Date",
+                "Symbol",
+                "Type",
+                "Amount",
+                "Currency",
+                "Description",
+            ],
+            Section::CorporateActions => &["Date", "Symbol", "Type", "Quantity", "Description"],
+            Section::Pnl => &["Symbol", "Price", "Dividends", "Fees", "Total"],
+        }
+    }
+}
+
+/// One table row, with the fields the filters look at
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Row {
+    date: Option<NaiveDate>,
+    symbol: String,
+    cells: Vec<String>,
+}
+
+/// What a prompt at the bottom of the screen is asking for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Prompt {
+    Symbol,
+    Dates,
+}
+
+/// A key press
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Key {
+    Char(char),
+    Up,
+    Down,
+    Left,
+    Right,
+    PageUp,
+    PageDown,
+    Home,
+    End,
+    Tab,
+    BackTab,
+    Enter,
+    Backspace,
+    Esc,
+}
+
+/// The viewer: every section's rows, the filters, and the scroll position
+struct App {
+    rows: Vec<Vec<Row>>,
+    section: usize,
+    /// First visible row of each section
+    offsets: Vec<usize>,
+    symbol: String,
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+    prompt: Option<(Prompt, String)>,
+    message: Option<String>,
+    /// Rows on the last page drawn, for paging
+    page: usize,
+    quit: bool,
+}
+
+impl App {
+    fn new(statements: &[ActivityFlexStatement]) -> Self {
+        let rows: Vec<Vec<Row>> = Section::ALL
+            .iter()
+            .map(|&section| section_rows(section, statements))
+            .collect();
+        App {
+            offsets: vec![0; rows.len()],
+            rows,
+            section: 0,
+            symbol: String::new(),
+            from: None,
+            to: None,
+            prompt: None,
+            message: None,
+            page: 20,
+            quit: false,
+        }
+    }
+
+    /// Rows of the current section that pass the filters
+    fn visible(&self) -> Vec<&Row> {
+        let symbol = self.symbol.to_uppercase();
+        self.rows[self.section]
+            .iter()
+            .filter(|row| row.symbol.to_uppercase().contains(&symbol))
+            .filter(|row| match row.date {
+                Some(date) => {
+                    self.from.map_or(true, |from| date >= from)
+                        && self.to.map_or(true, |to| date <= to)
+                }
+                None => true,
+            })
+            .collect()
+    }
+
+    fn handle(&mut self, key: Key) {
+        self.message = None;
+        if let Some((prompt, mut text)) = self.prompt.take() {
+            match key {
+                Key::Enter => self.apply(prompt, &text),
+                Key::Esc => {}
+                Key::Backspace => {
+                    text.pop();
+                    self.prompt = Some((prompt, text));
+                }
+                Key::Char(c) => {
+                    text.push(c);
+                    self.prompt = Some((prompt, text));
+                }
+                _ => self.prompt = Some((prompt, text)),
+            }
+            return;
+        }
+
+        let sections = Section::ALL.len();
+        let last = self.visible().len().saturating_sub(1);
+        let offset = &mut self.offsets[self.section];
+        match key {
+            Key::Char('q') | Key::Esc => self.quit = true,
+            Key::Tab | Key::Right => self.section = (self.section + 1) % sections,
+            Key::BackTab | Key::Left => self.section = (self.section + sections - 1) % sections,
+            Key::Char(c @ '1'..='5') => self.section = c as usize - '1' as usize,
+            Key::Down | Key::Char('j') => *offset = (*offset + 1).min(last),
+            Key::Up | Key::Char('k') => *offset = offset.saturating_sub(1),
+            Key::PageDown | Key::Char(' ') => *offset = (*offset + self.page).min(last),
+            Key::PageUp => *offset = offset.saturating_sub(self.page),
+            Key::Home | Key::Char('g') => *offset = 0,
+            Key::End | Key::Char('G') => *offset = last,
+            Key::Char('/') => self.prompt = Some((Prompt::Symbol, self.symbol.clone())),
+            Key::Char('d') => self.prompt = Some((Prompt::Dates, String::new())),
+            Key::Char('c') => {
+                self.symbol.clear();
+                self.from = None;
+                self.to = None;
+                self.offsets.iter_mut().for_each(|o| *o = 0);
+            }
+            _ => {}
+        }
+    }
+
+    fn apply(&mut self, prompt: Prompt, text: &str) {
+        match prompt {
+            Prompt::Symbol => self.symbol = text.trim().to_string(),
+            Prompt::Dates => match parse_dates(text) {
+                Some((from, to)) => {
+                    self.from = from;
+                    self.to = to;
+                }
+                None => {
+                    self.message = Some(format!("Not a date range: {:?}", text));
+                    return;
+                }
+            },
+        }
+        self.offsets.iter_mut().for_each(|o| *o = 0);
+    }
+
+    /// The whole screen, `width` columns by `height` lines
+    fn render(&mut self, width: usize, height: usize) -> String {
+        let section = Section::ALL[self.section];
+        let mut screen = String::from("\x1b[H\x1b[2J");
+
+        let mut tabs = String::new();
+        for (i, s) in Section::ALL.iter().enumerate() {
+            let tab = format!(" {} {} ", i + 1, s.title());
+            if i == self.section {
+                tabs.push_str(&format!("\x1b[7m{}\x1b[0m", tab));
+            } else {
+                tabs.push_str(&tab);
+            }
+        }
+        screen.push_str(&tabs);
+        screen.push_str("\r\n");
+
+        let rows = self.visible();
+        let mut filters = format!("{} of {} rows", rows.len(), self.rows[self.section].len());
+        if !self.symbol.is_empty() {
+            filters.push_str(&format!("  symbol ~ {}", self.symbol));
+        }
+        if self.from.is_some() || self.to.is_some() {
+            filters.push_str(&format!(
+                "  dates {}..{}",
+                self.from.map(|d| d.to_string()).unwrap_or_default(),
+                self.to.map(|d| d.to_string()).unwrap_or_default()
+            ));
+        }
+        screen.push_str(&fit(&filters, width));
+        screen.push_str("\r\n");
+
+        let headers: Vec<String> = section.headers().iter().map(|h| h.to_string()).collect();
+        let mut widths: Vec<usize> = headers.iter().map(|h| h.chars().count()).collect();
+        for row in &rows {
+            for (w, cell) in widths.iter_mut().zip(&row.cells) {
+                *w = (*w).max(cell.chars().count()).min(40);
+            }
+        }
+        screen.push_str(&format!(
+            "\x1b[1m{}\x1b[0m\r\n",
+            fit(&table_line(&headers, &widths), width)
+        ));
+
+        // Tabs, filters, and column headers above; status below
+        let page = height.saturating_sub(4).max(1);
+        let rows_len = rows.len();
+        let offset = self.offsets[self.section].min(rows_len.saturating_sub(1));
+        let mut lines = 0;
+        for row in rows.iter().skip(offset).take(page) {
+            screen.push_str(&fit(&table_line(&row.cells, &widths), width));
+            screen.push_str("\r\n");
+            lines += 1;
+        }
+        for _ in lines..page {
+            screen.push_str("\r\n");
+        }
+
+        let status = match (&self.prompt, &self.message) {
+            (Some((Prompt::Symbol, text)), _) => format!("Symbol: {}", text),
+            (Some((Prompt::Dates, text)), _) => format!("Dates (FROM..TO): {}", text),
+            (None, Some(message)) => message.clone(),
+            (None, None) => {
+                "Tab section  ↑↓ scroll  / symbol  d dates  c clear  q quit".to_string()
+            }
+        };
+        screen.push_str(&format!("\x1b[7m{}\x1b[0m", fit(&status, width)));
+        self.page = page;
+        screen
+    }
+}
+
+/// Rows of one section across every statement, in statement order
+fn section_rows(section: Section, statements: &[ActivityFlexStatement]) -> Vec<Row> {
+    let mut rows = Vec::new();
+    match section {
+        Section::Trades => {
+            for t in statements.iter().flat_map(|s| s.trades.iter()) {
+                rows.push(Row {
+                    date: t.trade_date,
+                    symbol: t.symbol.clone(),
+                    cells: vec![
+                        cell(t.trade_date),
+                        t.symbol.clone(),
+                        cell(t.buy_sell.as_ref().map(|b| b.as_str())),
+                        cell(t.quantity),
+                        cell(t.price),
+                        cell(t.proceeds),
+                        cell(t.commission),
+                        cell(t.fifo_pnl_realized),
+                        t.currency.clone(),
+                    ],
+                });
+            }
+        }
+        Section::Positions => {
+            for p in statements.iter().flat_map(|s| s.positions.iter()) {
+                rows.push(Row {
+                    date: Some(p.report_date),
+                    symbol: p.symbol.clone(),
+                    cells: vec![
+                        p.report_date.to_string(),
+                        p.symbol.clone(),
+                        p.quantity.to_string(),
+                        p.mark_price.to_string(),
+                        p.position_value.to_string(),
+                        cell(p.fifo_pnl_unrealized),
+                        p.currency.clone(),
+                    ],
+                });
+            }
+        }
+        Section::CashTransactions => {
+            for c in statements.iter().flat_map(|s| s.cash_transactions.iter()) {
+                let symbol = c.symbol.clone().unwrap_or_default();
+                rows.push(Row {
+                    date: c.date,
+                    symbol: symbol.clone(),
+                    cells: vec![
+                        cell(c.date),
+                        symbol,
+                        cell(c.transaction_type.as_ref()),
+                        c.amount.to_string(),
+                        c.currency.clone(),
+                        cell(c.description.as_deref()),
+                    ],
+                });
+            }
+        }
+        Section::CorporateActions => {
+            for a in statements.iter().flat_map(|s| s.corporate_actions.iter()) {
+                rows.push(Row {
+                    date: Some(a.report_date),
+                    symbol: a.symbol.clone(),
+                    cells: vec![
+                        a.report_date.to_string(),
+                        a.symbol.clone(),
+                        cell(a.action_type.as_deref()),
+                        cell(a.quantity),
+                        cell(a.description.as_deref()),
+                    ],
+                });
+            }
+        }
+        Section::Pnl => {
+            let report = attribution_report(statements, &[], AttributionLevel::Symbol);
+            for (symbol, c) in &report.contributions {
+                rows.push(Row {
+                    date: None,
+                    symbol: symbol.clone(),
+                    cells: vec![
+                        symbol.clone(),
+                        money(c.price),
+                        money(c.dividends),
+                        money(c.fees),
+                        money(c.total()),
+                    ],
+                });
+            }
+            rows.push(Row {
+                date: None,
+                symbol: String::new(),
+                cells: vec![
+                    "Total".to_string(),
+                    money(report.contributions.values().map(|c| c.price).sum()),
+                    money(report.contributions.values().map(|c| c.dividends).sum()),
+                    money(report.contributions.values().map(|c| c.fees).sum()),
+                    money(report.total()),
+                ],
+            });
+        }
+    }
+    rows
+}
+
+fn cell<T: ToString>(value: Option<T>) -> String {
+    value.map(|v| v.to_string()).unwrap_or_default()
+}
+
+fn money(amount: Decimal) -> String {
+    amount.round_dp(2).to_string()
+}
+
+/// `FROM..TO`, either side optional, or a single date
+fn parse_dates(text: &str) -> Option<(Option<NaiveDate>, Option<NaiveDate>)> {
+    let date = |s: &str| -> Option<Option<NaiveDate>> {
+        let s = s.trim();
+        if s.is_empty() {
+            Some(None)
+        } else {
+            NaiveDate::parse_from_str(s, "%Y-%m-%d").ok().map(Some)
+        }
+    };
+    match text.split_once("..") {
+        Some((from, to)) => Some((date(from)?, date(to)?)),
+        None => {
+            let day = date(text)?;
+            Some((day, day))
+        }
+    }
+}
+
+/// Cells padded to their column widths, numbers right-aligned
+fn table_line(cells: &[String], widths: &[usize]) -> String {
+    let mut line = String::new();
+    for (cell, &width) in cells.iter().zip(widths) {
+        let cell: String = cell.chars().take(width).collect();
+        let numeric = cell.parse::<Decimal>().is_ok();
+        if numeric {
+            line.push_str(&format!("{:>width$}  ", cell, width = width));
+        } else {
+            line.push_str(&format!("{:<width$}  ", cell, width = width));
+        }
+    }
+    line.trim_end().to_string()
+}
+
+/// `text` cut to `width` characters
+fn fit(text: &str, width: usize) -> String {
+    text.chars().take(width).collect()
+}
+
+// ==================== Terminal ====================
+
+#[cfg(unix)]
+mod terminal {
+    use std::io::{self, Read, Write};
+    use std::mem::MaybeUninit;
+
+    use super::{App, Key, TuiResult};
+
+    /// Raw mode and the alternate screen, restored on drop
+    struct Screen {
+        original: libc::termios,
+    }
+
+    impl Screen {
+        fn enter() -> TuiResult<Self> {
+            // SAFETY: tcgetattr fills the termios struct on success
+            let original = unsafe {
+                let mut termios = MaybeUninit::<libc::termios>::uninit();
+                if libc::tcgetattr(libc::STDIN_FILENO, termios.as_mut_ptr()) != 0 {
+                    return Err("standard input is not a terminal".into());
+                }
+                termios.assume_init()
+            };
+            let mut raw = original;
+            raw.c_lflag &= !(libc::ICANON | libc::ECHO | libc::ISIG | libc::IEXTEN);
+            raw.c_iflag &= !(libc::IXON | libc::ICRNL);
+            // Reads return after a tenth of a second without input, so a
+            // lone Esc can be told from an escape sequence
+            raw.c_cc[libc::VMIN] = 0;
+            raw.c_cc[libc::VTIME] = 1;
+            // SAFETY: raw is a valid termios copied from the terminal's own
+            if unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSAFLUSH, &raw) } != 0 {
+                return Err(io::Error::last_os_error().into());
+            }
+            print!("\x1b[?1049h\x1b[?25l");
+            io::stdout().flush()?;
+            Ok(Screen { original })
+        }
+    }
+
+    impl Drop for Screen {
+        fn drop(&mut self) {
+            print!("\x1b[?25h\x1b[?1049l");
+            let _ = io::stdout().flush();
+            // SAFETY: restores the settings read in `enter`
+            unsafe {
+                libc::tcsetattr(libc::STDIN_FILENO, libc::TCSAFLUSH, &self.original);
+            }
+        }
+    }
+
+    /// Columns and lines of the terminal
+    fn size() -> (usize, usize) {
+        // SAFETY: TIOCGWINSZ fills a winsize struct
+        let mut size: libc::winsize = unsafe { std::mem::zeroed() };
+        let ok = unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) } == 0;
+        if ok && size.ws_col > 0 && size.ws_row > 0 {
+            (size.ws_col as usize, size.ws_row as usize)
+        } else {
+            (80, 24)
+        }
+    }
+
+    fn read_byte(stdin: &mut impl Read) -> io::Result<Option<u8>> {
+        let mut byte = [0];
+        Ok(match stdin.read(&mut byte)? {
+            0 => None,
+            _ => Some(byte[0]),
+        })
+    }
+
+    /// The next key, or `None` when none was pressed in the read timeout
+    fn read_key(stdin: &mut impl Read) -> io::Result<Option<Key>> {
+        let Some(byte) = read_byte(stdin)? else {
+            return Ok(None);
+        };
+        let key = match byte {
+            b'\r' | b'\n' => Key::Enter,
+            b'\t' => Key::Tab,
+            0x7f | 0x08 => Key::Backspace,
+            // Ctrl-C
+            0x03 => Key::Char('q'),
+            0x1b => match read_byte(stdin)? {
+                None => Key::Esc,
+                Some(b'[') | Some(b'O') => {
+                    let mut sequence = Vec::new();
+                    while let Some(b) = read_byte(stdin)? {
+                        sequence.push(b);
+                        if b.is_ascii_alphabetic() || b == b'~' {
+                            break;
+                        }
+                    }
+                    match sequence.as_slice() {
+                        b"A" => Key::Up,
+                        b"B" => Key::Down,
+                        b"C" => Key::Right,
+                        b"D" => Key::Left,
+                        b"H" | b"1~" => Key::Home,
+                        b"F" | b"4~" => Key::End,
+                        b"5~" => Key::PageUp,
+                        b"6~" => Key::PageDown,
+                        b"Z" => Key::BackTab,
+                        _ => return Ok(None),
+                    }
+                }
+                Some(_) => return Ok(None),
+            },
+            b if b.is_ascii() => Key::Char(b as char),
+            // The rest of a UTF-8 character
+            b => {
+                let mut bytes = vec![b];
+                while bytes.len() < 4 && std::str::from_utf8(&bytes).is_err() {
+                    match read_byte(stdin)? {
+                        Some(b) => bytes.push(b),
+                        None => break,
+                    }
+                }
+                match std::str::from_utf8(&bytes)
+                    .ok()
+                    .and_then(|s| s.chars().next())
+                {
+                    Some(c) => Key::Char(c),
+                    None => return Ok(None),
+                }
+            }
+        };
+        Ok(Some(key))
+    }
+
+    pub(super) fn run(app: &mut App) -> TuiResult<()> {
+        let _screen = Screen::enter()?;
+        let mut stdin = io::stdin().lock();
+        let mut last_size = None;
+        let mut dirty = true;
+        while !app.quit {
+            let size = size();
+            if dirty || last_size != Some(size) {
+                let mut out = io::stdout().lock();
+                out.write_all(app.render(size.0, size.1).as_bytes())?;
+                out.flush()?;
+                last_size = Some(size);
+            }
+            dirty = match read_key(&mut stdin)? {
+                Some(key) => {
+                    app.handle(key);
+                    true
+                }
+                None => false,
+            };
+        }
+        Ok(())
+    }
+}
+
+#[cfg(not(unix))]
+mod terminal {
+    use super::{App, TuiResult};
+
+    pub(super) fn run(_: &mut App) -> TuiResult<()> {
+        Err("ib-flex-tui needs a Unix terminal".into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app() -> App {
+        let xml = include_str!("../../tests/fixtures/activity_daily_portfolio.xml");
+        App::new(&parse_activity_flex_all(xml).unwrap())
+    }
+
+    #[test]
+    fn test_sections_and_filters() {
+        let mut app = app();
+        assert_eq!(app.visible().len(), 3);
+        app.handle(Key::Tab);
+        assert_eq!(Section::ALL[app.section], Section::Positions);
+        assert_eq!(app.visible().len(), 5);
+
+        for key in [
+            Key::Char('/'),
+            Key::Char('a'),
+            Key::Char('a'),
+            Key::Backspace,
+        ] {
+            app.handle(key);
+        }
+        for key in "apl".chars().map(Key::Char).chain([Key::Enter]) {
+            app.handle(key);
+        }
+        assert_eq!(app.symbol, "aapl");
+        let rows = app.visible();
+        assert!(!rows.is_empty() && rows.len() < 5);
+        assert!(rows.iter().all(|row| row.symbol.starts_with("AAPL")));
+
+        for key in "d2025-02-01..".chars().map(Key::Char).chain([Key::Enter]) {
+            app.handle(key);
+        }
+        assert!(app.visible().is_empty());
+        app.handle(Key::Char('c'));
+        assert_eq!(app.visible().len(), 5);
+
+        for key in "dnot a date".chars().map(Key::Char).chain([Key::Enter]) {
+            app.handle(key);
+        }
+        assert!(app.message.is_some());
+        app.handle(Key::Char('q'));
+        assert!(app.quit);
+    }
+
+    #[test]
+    fn test_render_pnl() {
+        let mut app = app();
+        app.handle(Key::Char('5'));
+        let screen = app.render(100, 30);
+        assert!(screen.contains("\x1b[7m 5 P&L by symbol \x1b[0m"));
+        assert!(screen.contains("Symbol"));
+        assert!(screen.contains("Total"));
+        let lines = screen.split("\r\n").count();
+        assert_eq!(lines, 30);
+    }
+}
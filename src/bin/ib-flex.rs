@@ -0,0 +1,830 @@
+//! `ib-flex` command-line tool (requires the `cli` feature)
+//!
+//! ```text
+//! ib-flex parse statement.xml             count rows per section
+//! ib-flex validate *.xml                  consistency checks for pipelines
+//! ib-flex summary --period year *.xml     P&L, dividends, and fees
+//! ib-flex export --format csv --section trades statement.xml > trades.csv
+//! ib-flex export --format json statement.xml
+//! ib-flex export --format parquet --output out/ *.xml
+//! ib-flex gains --year 2025 2024.xml 2025.xml realized gains by term
+//! ib-flex fetch --query 123456 --out flex/ --parse
+//! ib-flex anonymize --scale 0.37 statement.xml shareable.xml
+//! ```
+//!
+//! Each file may hold any number of Activity FLEX statements; `-` reads
+//! standard input. Parquet export also needs the `arrow` feature, and
+//! `fetch` the `api-client` feature.
+
+use std::collections::{BTreeMap, HashSet};
+use std::error::Error;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::process::ExitCode;
+
+use chrono::Datelike;
+use chrono::NaiveDate;
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+use ib_flex::analytics::{
+    base_pnl, dividend_report, fee_report, reconcile_cash, reconcile_statement_positions,
+    tax_lot_report, Form8949Row, GainsSummary, LotLedger, LotMethod, LotMethodPolicy, PnlPeriod,
+    ReconcileConfig, RoundingPolicy, WashSaleConfig,
+};
+use ib_flex::export::csv::{self, CsvOptions};
+use ib_flex::types::{AssetCategory, BuySell, CashTransactionType, LevelOfDetail};
+use ib_flex::{anonymize_xml, ActivityFlexStatement, AnonymizeOptions, TradeConfirmationStatement};
+use ib_flex::{merge_statements, parse_activity_flex_all, parse_trade_confirmation, StatementType};
+
+type CliResult<T> = Result<T, Box<dyn Error>>;
+
+fn main() -> ExitCode {
+    match run(command().get_matches()) {
+        Ok(()) => ExitCode::SUCCESS,
+        // Output piped into `head` and the like
+        Err(err) if is_broken_pipe(err.as_ref()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn is_broken_pipe(err: &(dyn Error + 'static)) -> bool {
+    let io = match err.downcast_ref::<ib_flex::ParseError>() {
+        Some(ib_flex::ParseError::Io(io)) => Some(io),
+        _ => err.downcast_ref::<io::Error>(),
+    };
+    io.is_some_and(|io| io.kind() == io::ErrorKind::BrokenPipe)
+}
+
+fn command() -> Command {
+    let files = Arg::new("files")
+        .value_name("FILE")
+        .help("FLEX XML files; - reads standard input")
+        .required(true)
+        .action(ArgAction::Append);
+    Command::new("ib-flex")
+        .version(env!("CARGO_PKG_VERSION"))
+        .about("Parse, summarize, and export Interactive Brokers FLEX statements")
+        .subcommand_required(true)
+        .arg_required_else_help(true)
+        .subcommand(
+            Command::new("parse")
+                .about("Validate statements and count the rows of each section")
+                .arg(files.clone()),
+        )
+        .subcommand(
+            Command::new("validate")
+                .about("Check statements for problems; exit nonzero if any are found")
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .short('f')
+                        .value_parser(["text", "json"])
+                        .default_value("text")
+                        .help("Report format"),
+                )
+                .arg(files.clone()),
+        )
+        .subcommand(
+            Command::new("summary")
+                .about("P&L per period, dividends per year, and fees per month")
+                .arg(
+                    Arg::new("period")
+                        .long("period")
+                        .value_parser(["day", "month", "year"])
+                        .default_value("month")
+                        .help("P&L period"),
+                )
+                .arg(files.clone()),
+        )
+        .subcommand(
+            Command::new("export")
+                .about("Export statements as JSON, CSV, or Parquet")
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .short('f')
+                        .value_parser(["json", "csv", "parquet"])
+                        .default_value("json"),
+                )
+                .arg(
+                    Arg::new("section")
+                        .long("section")
+                        .short('s')
+                        .value_parser([
+                            "trades",
+                            "positions",
+                            "cash-transactions",
+                            "corporate-actions",
+                            "dividends",
+                            "daily-positions",
+                        ])
+                        .default_value("trades")
+                        .help("Section to write as CSV"),
+                )
+                .arg(
+                    Arg::new("output")
+                        .long("output")
+                        .short('o')
+                        .value_name("PATH")
+                        .help("Output file, or directory for Parquet; standard output by default"),
+                )
+                .arg(files.clone()),
+        )
+        .subcommand(
+            Command::new("gains")
+                .about("Realized gains by symbol and term for a tax year, after wash sales")
+                .arg(
+                    Arg::new("year")
+                        .long("year")
+                        .short('y')
+                        .value_name("YEAR")
+                        .value_parser(clap::value_parser!(i32))
+                        .required(true)
+                        .help("Tax year of the sales"),
+                )
+                .arg(
+                    Arg::new("method")
+                        .long("method")
+                        .short('m')
+                        .value_parser(["fifo", "lifo", "highest-cost", "average-cost"])
+                        .default_value("fifo")
+                        .help("Lots that sales close"),
+                )
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .short('f')
+                        .value_parser(["table", "csv"])
+                        .default_value("table")
+                        .help("Summary table, or Form 8949 rows as CSV"),
+                )
+                .arg(
+                    Arg::new("output")
+                        .long("output")
+                        .short('o')
+                        .value_name("PATH")
+                        .help("Output file; standard output by default"),
+                )
+                .arg(files),
+        )
+        .subcommand(
+            Command::new("anonymize")
+                .about(
+                    "Scrub account IDs, aliases, and names, to share a statement in a bug report",
+                )
+                .arg(
+                    Arg::new("scale")
+                        .long("scale")
+                        .value_name("FACTOR")
+                        .value_parser(clap::value_parser!(Decimal))
+                        .help("Multiply quantities and amounts, keeping prices and rates"),
+                )
+                .arg(
+                    Arg::new("key")
+                        .long("key")
+                        .value_name("KEY")
+                        .help("Secret for the account pseudonyms; reuse it for related files"),
+                )
+                .arg(
+                    Arg::new("input")
+                        .value_name("IN")
+                        .required(true)
+                        .help("FLEX XML file; - reads standard input"),
+                )
+                .arg(
+                    Arg::new("output")
+                        .value_name("OUT")
+                        .help("Output file; standard output by default"),
+                ),
+        )
+        .subcommand(
+            Command::new("fetch")
+                .about("Download a statement from the FLEX Web Service")
+                .arg(
+                    Arg::new("query")
+                        .long("query")
+                        .short('q')
+                        .value_name("ID")
+                        .required(true)
+                        .help("FLEX query ID"),
+                )
+                .arg(
+                    Arg::new("token-env")
+                        .long("token-env")
+                        .value_name("VAR")
+                        .default_value("IB_FLEX_TOKEN")
+                        .help("Environment variable holding the Web Service token"),
+                )
+                .arg(
+                    Arg::new("out")
+                        .long("out")
+                        .short('o')
+                        .value_name("DIR")
+                        .help("Directory for QUERY_REFERENCE.xml; standard output by default"),
+                )
+                .arg(
+                    Arg::new("retries")
+                        .long("retries")
+                        .value_parser(clap::value_parser!(usize))
+                        .default_value("10")
+                        .help("Polls while IB generates the statement"),
+                )
+                .arg(
+                    Arg::new("retry-delay")
+                        .long("retry-delay")
+                        .value_name("SECONDS")
+                        .value_parser(clap::value_parser!(u64))
+                        .default_value("5")
+                        .help("Seconds between polls"),
+                )
+                .arg(
+                    Arg::new("parse")
+                        .long("parse")
+                        .action(ArgAction::SetTrue)
+                        .help("Fail unless the statement parses; count its rows on standard error"),
+                ),
+        )
+}
+
+fn run(matches: ArgMatches) -> CliResult<()> {
+    let (name, args) = matches.subcommand().expect("a subcommand is required");
+    match name {
+        "fetch" => return fetch(args),
+        "anonymize" => return anonymize(args),
+        _ => {}
+    }
+    let files: Vec<&String> = args.get_many("files").unwrap_or_default().collect();
+    match name {
+        "parse" => parse(&files),
+        "validate" => validate(args, &files),
+        "summary" => {
+            let period = match args.get_one::<String>("period").map(String::as_str) {
+                Some("day") => PnlPeriod::Day,
+                Some("year") => PnlPeriod::Year,
+                _ => PnlPeriod::Month,
+            };
+            summary(&activity_statements(&files)?, period)
+        }
+        "export" => export(args, &activity_statements(&files)?),
+        "gains" => gains(args, activity_statements(&files)?),
+        _ => unreachable!("unknown subcommands are rejected by clap"),
+    }
+}
+
+fn read(path: &str) -> CliResult<String> {
+    let mut text = String::new();
+    if path == "-" {
+        io::stdin().read_to_string(&mut text)?;
+    } else {
+        File::open(path)
+            .map_err(|err| format!("{}: {}", path, err))?
+            .read_to_string(&mut text)?;
+    }
+    Ok(text)
+}
+
+/// Activity statements of every file, in order
+fn activity_statements(files: &[&String]) -> CliResult<Vec<ActivityFlexStatement>> {
+    let mut statements = Vec::new();
+    for path in files {
+        let xml = read(path)?;
+        statements
+            .extend(parse_activity_flex_all(&xml).map_err(|err| format!("{}: {}", path, err))?);
+    }
+    Ok(statements)
+}
+
+fn parse(files: &[&String]) -> CliResult<()> {
+    let mut out = io::stdout().lock();
+    for path in files {
+        describe(&mut out, path, &read(path)?)?;
+    }
+    Ok(())
+}
+
+/// Parse one file and count the rows of each section
+fn describe(out: &mut impl Write, path: &str, xml: &str) -> CliResult<()> {
+    let in_file = |err: ib_flex::ParseError| format!("{}: {}", path, err);
+    // Activity is the most common kind, and parsing reports what is
+    // wrong with a file that is neither
+    let kind = ib_flex::detect_statement_type(xml).unwrap_or(StatementType::Activity);
+    match kind {
+        StatementType::Activity => {
+            for s in parse_activity_flex_all(xml).map_err(in_file)? {
+                writeln!(out, "{}: activity statement", path)?;
+                writeln!(
+                    out,
+                    "  account {}, {} to {}",
+                    s.account_id, s.from_date, s.to_date
+                )?;
+                for (section, rows) in [
+                    ("trades", s.trades.len()),
+                    ("open positions", s.positions.len()),
+                    ("cash transactions", s.cash_transactions.len()),
+                    ("corporate actions", s.corporate_actions.len()),
+                    ("securities", s.securities_info.len()),
+                    ("conversion rates", s.conversion_rates.items.len()),
+                ] {
+                    writeln!(out, "  {:<18} {:>8}", section, rows)?;
+                }
+            }
+        }
+        StatementType::TradeConfirmation => {
+            let s: TradeConfirmationStatement = parse_trade_confirmation(xml).map_err(in_file)?;
+            writeln!(out, "{}: trade confirmation statement", path)?;
+            writeln!(out, "  account {}", s.account_id)?;
+            writeln!(out, "  {:<18} {:>8}", "trades", s.trades.items.len())?;
+        }
+    }
+    Ok(())
+}
+
+/// A failed consistency check
+#[derive(Serialize)]
+struct Problem {
+    file: String,
+    account: String,
+    to_date: NaiveDate,
+    check: &'static str,
+    message: String,
+}
+
+/// Parse every file and run the consistency checks:
+///
+/// - `unknown-value`: enum values the parser does not recognize
+/// - `duplicate-id`: transaction IDs repeated within one statement
+/// - `cash`: `CashReport` ending cash against the cash movements
+/// - `positions`: `OpenPositions` against the previous day's positions
+///   plus the trades in between, for statements of an account that follow
+///   each other without a gap and both report positions
+fn validate(args: &ArgMatches, files: &[&String]) -> CliResult<()> {
+    let mut statements: Vec<(&str, ActivityFlexStatement)> = Vec::new();
+    for path in files {
+        let xml = read(path)?;
+        let parsed = parse_activity_flex_all(&xml).map_err(|err| format!("{}: {}", path, err))?;
+        statements.extend(parsed.into_iter().map(|s| (path.as_str(), s)));
+    }
+
+    let mut problems = Vec::new();
+    let config = ReconcileConfig::default();
+    for (path, s) in &statements {
+        let mut problem = |check, message| {
+            problems.push(Problem {
+                file: path.to_string(),
+                account: s.account_id.to_string(),
+                to_date: s.to_date,
+                check,
+                message,
+            })
+        };
+        for value in unknown_values(s) {
+            problem("unknown-value", value);
+        }
+        for id in duplicate_ids(s) {
+            problem("duplicate-id", id);
+        }
+        for gap in reconcile_cash(s, &config) {
+            problem(
+                "cash",
+                format!(
+                    "{} ending cash {} but computed {}, off by {}",
+                    gap.currency,
+                    gap.reported_ending_cash,
+                    gap.computed_ending_cash,
+                    gap.difference
+                ),
+            );
+        }
+    }
+
+    let mut order: Vec<&(&str, ActivityFlexStatement)> = statements.iter().collect();
+    order.sort_by_key(|(_, s)| (s.account_id.to_string(), s.from_date, s.to_date));
+    for pair in order.windows(2) {
+        let [(_, previous), (path, s)] = pair else {
+            continue;
+        };
+        let follows =
+            previous.account_id == s.account_id && previous.to_date.succ_opt() == Some(s.from_date);
+        if !follows || previous.positions.is_empty() || s.positions.is_empty() {
+            continue;
+        }
+        let report = reconcile_statement_positions([s], &previous.positions, s, &config);
+        for gap in report.discrepancies {
+            problems.push(Problem {
+                file: path.to_string(),
+                account: s.account_id.to_string(),
+                to_date: s.to_date,
+                check: "positions",
+                message: format!(
+                    "{} ({}) reported {} but rebuilt {} ({:?})",
+                    gap.symbol,
+                    gap.conid,
+                    gap.reported_quantity,
+                    gap.reconstructed_quantity,
+                    gap.kind
+                ),
+            });
+        }
+    }
+
+    let mut out = io::stdout().lock();
+    if args.get_one::<String>("format").map(String::as_str) == Some("json") {
+        writeln!(out, "{}", serde_json::to_string_pretty(&problems)?)?;
+    } else {
+        for p in &problems {
+            writeln!(
+                out,
+                "{}: {} {}: {}: {}",
+                p.file, p.account, p.to_date, p.check, p.message
+            )?;
+        }
+    }
+    out.flush()?;
+    match problems.len() {
+        0 => Ok(()),
+        1 => Err("1 problem found".into()),
+        n => Err(format!("{} problems found", n).into()),
+    }
+}
+
+/// Asset categories, sides, and cash transaction types the parser kept
+/// verbatim
+fn unknown_values(s: &ActivityFlexStatement) -> Vec<String> {
+    // Missing categories parse as an empty unknown value
+    let unknown = |category: &AssetCategory| match category {
+        AssetCategory::Unknown(raw) if !raw.is_empty() => Some(raw.clone()),
+        _ => None,
+    };
+    let mut values = Vec::new();
+    for t in &s.trades {
+        if let Some(raw) = unknown(&t.asset_category) {
+            values.push(format!("trade {}: asset category {:?}", t.symbol, raw));
+        }
+        if let Some(BuySell::Unknown(raw)) = &t.buy_sell {
+            values.push(format!("trade {}: buy/sell {:?}", t.symbol, raw));
+        }
+    }
+    for p in &s.positions {
+        if let Some(raw) = unknown(&p.asset_category) {
+            values.push(format!("position {}: asset category {:?}", p.symbol, raw));
+        }
+    }
+    for c in &s.cash_transactions {
+        if let Some(CashTransactionType::Unknown(raw)) = &c.transaction_type {
+            values.push(format!("cash transaction: type {:?}", raw));
+        }
+    }
+    values.sort();
+    values.dedup();
+    values
+}
+
+/// Transaction IDs reported more than once in the same section
+fn duplicate_ids(s: &ActivityFlexStatement) -> Vec<String> {
+    let executions = s
+        .trades
+        .iter()
+        .filter(|t| matches!(t.level_of_detail, None | Some(LevelOfDetail::Execution)))
+        .filter_map(|t| t.transaction_id.as_deref().map(|id| ("trade", id)));
+    let cash = s.cash_transactions.iter().filter_map(|c| {
+        c.transaction_id
+            .as_deref()
+            .map(|id| ("cash transaction", id))
+    });
+    let actions = s.corporate_actions.iter().filter_map(|a| {
+        a.transaction_id
+            .as_deref()
+            .map(|id| ("corporate action", id))
+    });
+
+    let mut seen = HashSet::new();
+    let mut duplicates: Vec<String> = executions
+        .chain(cash)
+        .chain(actions)
+        .filter(|key| !seen.insert(*key))
+        .map(|(section, id)| format!("{} transaction ID {} repeated", section, id))
+        .collect();
+    duplicates.sort();
+    duplicates.dedup();
+    duplicates
+}
+
+fn summary(statements: &[ActivityFlexStatement], period: PnlPeriod) -> CliResult<()> {
+    let mut out = io::stdout().lock();
+    let (Some(from), Some(to)) = (
+        statements.iter().map(|s| s.from_date).min(),
+        statements.iter().map(|s| s.to_date).max(),
+    ) else {
+        return Err("no activity statements".into());
+    };
+    let pnl = base_pnl(statements, period, &RoundingPolicy::cents());
+    let mut accounts: Vec<&str> = statements.iter().map(|s| s.account_id.as_str()).collect();
+    accounts.sort_unstable();
+    accounts.dedup();
+    writeln!(out, "Accounts {}, {} to {}", accounts.join(", "), from, to)?;
+    if let Some(base) = &pnl.base_currency {
+        writeln!(out, "Amounts in {}", base)?;
+    }
+
+    let label = |date: chrono::NaiveDate| match period {
+        PnlPeriod::Day => date.format("%Y-%m-%d").to_string(),
+        PnlPeriod::Month => date.format("%Y-%m").to_string(),
+        PnlPeriod::Year => date.format("%Y").to_string(),
+    };
+    writeln!(out)?;
+    table_row(&mut out, "P&L", &["realized", "unrealized", "total"])?;
+    for (start, p) in &pnl.by_period {
+        amounts_row(
+            &mut out,
+            &label(*start),
+            &[p.realized, p.unrealized, p.total()],
+        )?;
+    }
+    let t = pnl.total;
+    amounts_row(&mut out, "total", &[t.realized, t.unrealized, t.total()])?;
+    if !pnl.missing_rates.is_empty() {
+        writeln!(
+            out,
+            "({} rows left out: no rate to the base currency)",
+            pnl.missing_rates.len()
+        )?;
+    }
+
+    writeln!(out)?;
+    table_row(
+        &mut out,
+        "Dividends",
+        &["gross", "in lieu", "withheld", "net"],
+    )?;
+    for (year, y) in &dividend_report(statements, &RoundingPolicy::cents()).years {
+        let d = y.total;
+        amounts_row(
+            &mut out,
+            &year.to_string(),
+            &[d.gross, d.payment_in_lieu, d.withholding_tax, d.net()],
+        )?;
+    }
+
+    writeln!(out)?;
+    let fees = fee_report(statements, &RoundingPolicy::cents());
+    table_row(&mut out, "Fees", &["commission", "taxes", "other", "total"])?;
+    for (month, f) in &fees.by_month {
+        amounts_row(
+            &mut out,
+            &month.format("%Y-%m").to_string(),
+            &[f.commission, f.taxes + f.sales_tax, f.other_fees, f.total()],
+        )?;
+    }
+    let f = fees.total;
+    amounts_row(
+        &mut out,
+        "total",
+        &[f.commission, f.taxes + f.sales_tax, f.other_fees, f.total()],
+    )?;
+    Ok(())
+}
+
+fn table_row(out: &mut impl Write, label: &str, columns: &[&str]) -> io::Result<()> {
+    write!(out, "{:<12}", label)?;
+    for column in columns {
+        write!(out, " {:>14}", column)?;
+    }
+    writeln!(out)
+}
+
+fn amounts_row(out: &mut impl Write, label: &str, amounts: &[Decimal]) -> io::Result<()> {
+    write!(out, "{:<12}", label)?;
+    for amount in amounts {
+        write!(out, " {:>14}", amount.round_dp(2))?;
+    }
+    writeln!(out)
+}
+
+fn export(args: &ArgMatches, statements: &[ActivityFlexStatement]) -> CliResult<()> {
+    let output = args.get_one::<String>("output");
+    let format = args.get_one::<String>("format").map(String::as_str);
+    if format == Some("parquet") {
+        return export_parquet(output, statements);
+    }
+
+    let mut writer = create(output)?;
+    if format == Some("csv") {
+        let options = CsvOptions::default();
+        let trades = statements.iter().flat_map(|s| &s.trades);
+        let cash = statements.iter().flat_map(|s| &s.cash_transactions);
+        match args.get_one::<String>("section").map(String::as_str) {
+            Some("positions") => {
+                let positions = statements.iter().flat_map(|s| &s.positions);
+                csv::write_positions(writer, positions, &options)?
+            }
+            Some("cash-transactions") => csv::write_cash_transactions(writer, cash, &options)?,
+            Some("corporate-actions") => {
+                let actions = statements.iter().flat_map(|s| &s.corporate_actions);
+                csv::write_corporate_actions(writer, actions, &options)?
+            }
+            Some("dividends") => csv::write_dividends(writer, cash, &options)?,
+            Some("daily-positions") => csv::write_daily_positions(writer, statements, &options)?,
+            _ => csv::write_trades(writer, trades, &options)?,
+        }
+        return Ok(());
+    }
+
+    let json = match statements {
+        [statement] => ib_flex::export::json::to_string_pretty(statement)?,
+        _ => ib_flex::export::json::to_string_pretty(statements)?,
+    };
+    writeln!(writer, "{}", json)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// `path`, or standard output
+fn create(path: Option<&String>) -> CliResult<Box<dyn Write>> {
+    Ok(match path {
+        Some(path) => Box::new(File::create(path).map_err(|err| format!("{}: {}", path, err))?),
+        None => Box::new(io::stdout().lock()),
+    })
+}
+
+/// Run the lot engine over each account's whole history, then report the
+/// lots closed in the tax year
+///
+/// Earlier years are needed for the acquisition dates and basis of lots
+/// sold in the year, and the following January for wash sales of December
+/// losses, so all statements go through the ledger.
+fn gains(args: &ArgMatches, statements: Vec<ActivityFlexStatement>) -> CliResult<()> {
+    let year = *args.get_one::<i32>("year").expect("--year is required");
+    let method = match args.get_one::<String>("method").map(String::as_str) {
+        Some("lifo") => LotMethod::Lifo,
+        Some("highest-cost") => LotMethod::HighestCost,
+        Some("average-cost") => LotMethod::AverageCost,
+        _ => LotMethod::Fifo,
+    };
+    let policy = LotMethodPolicy {
+        default: method,
+        ..Default::default()
+    };
+
+    // Lots are per account, and overlapping statements repeat trades
+    let mut accounts: BTreeMap<String, Vec<ActivityFlexStatement>> = BTreeMap::new();
+    for statement in statements {
+        accounts
+            .entry(statement.account_id.to_string())
+            .or_default()
+            .push(statement);
+    }
+    let mut closed = Vec::new();
+    for statements in accounts.into_values() {
+        let statement = merge_statements(statements)?;
+        let mut ledger = LotLedger::with_policy(policy.clone());
+        ledger.apply_trades(&statement.trades);
+        let report = tax_lot_report(ledger, &statement.trades, &WashSaleConfig::default());
+        closed.extend(
+            report
+                .closed_lots
+                .into_iter()
+                .filter(|lot| lot.closure.close_date.year() == year),
+        );
+    }
+
+    let mut out = create(args.get_one::<String>("output"))?;
+    if args.get_one::<String>("format").map(String::as_str) == Some("csv") {
+        let rows: Vec<Form8949Row> = closed
+            .iter()
+            .map(|lot| Form8949Row::from_closure(&lot.closure))
+            .collect();
+        csv::write_form_8949(out, &rows, &CsvOptions::default())?;
+        return Ok(());
+    }
+
+    let mut by_symbol: BTreeMap<(&str, &str), (GainsSummary, Decimal)> = BTreeMap::new();
+    let mut by_currency: BTreeMap<&str, (GainsSummary, Decimal)> = BTreeMap::new();
+    for lot in &closed {
+        let c = &lot.closure;
+        for (gains, disallowed) in [
+            by_symbol.entry((&c.symbol, &c.currency)).or_default(),
+            by_currency.entry(&c.currency).or_default(),
+        ] {
+            gains.add(lot.holding_period, lot.gain_or_loss);
+            *disallowed += c.disallowed_loss;
+        }
+    }
+
+    writeln!(
+        out,
+        "Realized gains for {}, {} lots closed",
+        year,
+        closed.len()
+    )?;
+    writeln!(out)?;
+    table_row(
+        &mut out,
+        "Symbol",
+        &[
+            "currency",
+            "short term",
+            "long term",
+            "total",
+            "wash sale adj",
+        ],
+    )?;
+    let rows = by_symbol
+        .iter()
+        .map(|((symbol, currency), row)| (symbol.to_string(), *currency, row))
+        .chain(
+            by_currency
+                .iter()
+                .map(|(currency, row)| ("total".to_string(), *currency, row)),
+        );
+    for (label, currency, (gains, disallowed)) in rows {
+        write!(out, "{:<12} {:>14}", label, currency)?;
+        for amount in [
+            gains.short_term(),
+            gains.long_term(),
+            gains.total(),
+            *disallowed,
+        ] {
+            write!(out, " {:>14}", amount.round_dp(2))?;
+        }
+        writeln!(out)?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+fn anonymize(args: &ArgMatches) -> CliResult<()> {
+    let path = args.get_one::<String>("input").expect("IN is required");
+    let options = AnonymizeOptions {
+        key: args.get_one::<String>("key").cloned().unwrap_or_default(),
+        scale: args.get_one::<Decimal>("scale").copied(),
+    };
+    let xml = anonymize_xml(&read(path)?, &options).map_err(|err| format!("{}: {}", path, err))?;
+    let mut out = create(args.get_one::<String>("output"))?;
+    out.write_all(xml.as_bytes())?;
+    out.flush()?;
+    Ok(())
+}
+
+#[cfg(feature = "api-client")]
+fn fetch(args: &ArgMatches) -> CliResult<()> {
+    use ib_flex::api::FlexApiClient;
+
+    let query = args
+        .get_one::<String>("query")
+        .expect("--query is required");
+    let var = args.get_one::<String>("token-env").expect("has a default");
+    let token = std::env::var(var)
+        .map_err(|_| format!("{} is not set to a FLEX Web Service token", var))?;
+    let retries = *args.get_one::<usize>("retries").expect("has a default");
+    let delay = *args.get_one::<u64>("retry-delay").expect("has a default");
+
+    let client = FlexApiClient::new(token);
+    let runtime = tokio::runtime::Runtime::new()?;
+    let (reference, xml) = runtime.block_on(async {
+        let reference = client.send_request(query).await?;
+        let xml = client
+            .get_statement_with_retry(&reference, retries, std::time::Duration::from_secs(delay))
+            .await?;
+        Ok::<_, ib_flex::api::FlexApiError>((reference, xml))
+    })?;
+
+    let name = format!("{}_{}.xml", query, reference);
+    if args.get_flag("parse") {
+        describe(&mut io::stderr().lock(), &name, &xml)?;
+    }
+    match args.get_one::<String>("out") {
+        Some(dir) => {
+            std::fs::create_dir_all(dir)?;
+            let path = std::path::Path::new(dir).join(&name);
+            std::fs::write(&path, &xml).map_err(|err| format!("{}: {}", path.display(), err))?;
+            println!("{}", path.display());
+        }
+        None => io::stdout().lock().write_all(xml.as_bytes())?,
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "api-client"))]
+fn fetch(_: &ArgMatches) -> CliResult<()> {
+    Err("fetch needs ib-flex built with the `api-client` feature".into())
+}
+
+#[cfg(feature = "arrow")]
+fn export_parquet(output: Option<&String>, statements: &[ActivityFlexStatement]) -> CliResult<()> {
+    let dir = output.ok_or("Parquet export needs --output with a directory")?;
+    std::fs::create_dir_all(dir)?;
+    for path in ib_flex::export::parquet::write_statements(dir, statements)? {
+        println!("{}", path.display());
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "arrow"))]
+fn export_parquet(_: Option<&String>, _: &[ActivityFlexStatement]) -> CliResult<()> {
+    Err("Parquet export needs ib-flex built with the `arrow` feature".into())
+}
@@ -0,0 +1,255 @@
+//! Binary snapshots of parsed statements
+//!
+//! Parsing years of FLEX XML takes a while. [`save`] writes parsed
+//! statements to a compact binary file that [`load`] reads back many times
+//! faster, so a history can be parsed once and reloaded from the snapshot
+//! until new statements arrive.
+//!
+//! Snapshots are MessagePack, written and read as a stream, with a short
+//! header naming the crate version that wrote them. The types change
+//! between versions, so [`load`] rejects snapshots from any other version
+//! with [`ParseError::CacheError`]: parse the XML again and save a new
+//! snapshot. Snapshots are a cache, not an archive; keep the XML.
+//!
+//! [`save_parsed`] and [`load_parsed`] keep each statement's
+//! [`Provenance`] in the snapshot as well, so numbers
+//! computed from a reloaded history still trace back to their XML files.
+//!
+//! MessagePack describes its own values, which the types need: their
+//! catch-all enum variants and sections are read by what the data holds.
+//! Formats that do not, such as bincode, cannot read them back.
+//!
+//! Requires the `cache` feature.
+//!
+//! # Example
+//! ```
+//! use ib_flex::cache;
+//! use ib_flex::parse_activity_flex;
+//!
+//! let xml = include_str!("../tests/fixtures/activity_minimal.xml");
+//! let statement = parse_activity_flex(xml).unwrap();
+//!
+//! let mut snapshot = Vec::new();
+//! cache::write(&mut snapshot, [&statement]).unwrap();
+//! let statements = cache::read(snapshot.as_slice()).unwrap();
+//! assert_eq!(statements, [statement]);
+//! ```
+
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::types::ActivityFlexStatement;
+use crate::{ParseError, ParsedStatement, Provenance, Result};
+
+/// First bytes of every snapshot, before the crate version
+const MAGIC: &[u8; 8] = b"IBFLEXMP";
+
+/// Save `statements` to a snapshot at `path`, replacing any file there
+pub fn save<'a, I>(path: impl AsRef<Path>, statements: I) -> Result<()>
+where
+    I: IntoIterator<Item = &'a ActivityFlexStatement>,
+{
+    write(BufWriter::new(File::create(path)?), statements)
+}
+
+/// Load the statements of the snapshot at `path`
+pub fn load(path: impl AsRef<Path>) -> Result<Vec<ActivityFlexStatement>> {
+    read(BufReader::new(File::open(path)?))
+}
+
+/// Save `statements` with their provenance to a snapshot at `path`,
+/// replacing any file there
+pub fn save_parsed<'a, I>(path: impl AsRef<Path>, statements: I) -> Result<()>
+where
+    I: IntoIterator<Item = &'a ParsedStatement>,
+{
+    write_parsed(BufWriter::new(File::create(path)?), statements)
+}
+
+/// Load the statements and provenance of the snapshot at `path`
+pub fn load_parsed(path: impl AsRef<Path>) -> Result<Vec<ParsedStatement>> {
+    read_parsed(BufReader::new(File::open(path)?))
+}
+
+/// Write `statements` as a snapshot
+pub fn write<'a, W, I>(writer: W, statements: I) -> Result<()>
+where
+    W: Write,
+    I: IntoIterator<Item = &'a ActivityFlexStatement>,
+{
+    let entries = statements
+        .into_iter()
+        .map(|statement| Entry {
+            timezone: statement.timezone.clone(),
+            provenance: None,
+            statement,
+        })
+        .collect();
+    write_entries(writer, entries)
+}
+
+/// Write `statements` and their provenance as a snapshot
+pub fn write_parsed<'a, W, I>(writer: W, statements: I) -> Result<()>
+where
+    W: Write,
+    I: IntoIterator<Item = &'a ParsedStatement>,
+{
+    let entries = statements
+        .into_iter()
+        .map(|parsed| Entry {
+            timezone: parsed.statement.timezone.clone(),
+            provenance: Some(parsed.provenance.clone()),
+            statement: &parsed.statement,
+        })
+        .collect();
+    write_entries(writer, entries)
+}
+
+/// Read the statements of a snapshot, dropping any provenance
+pub fn read<R: Read>(reader: R) -> Result<Vec<ActivityFlexStatement>> {
+    Ok(read_entries(reader)?
+        .into_iter()
+        .map(Entry::into_statement)
+        .collect())
+}
+
+/// Read the statements and provenance of a snapshot
+///
+/// Fails with [`ParseError::CacheError`] if a statement was written
+/// without provenance, by [`write()`].
+pub fn read_parsed<R: Read>(reader: R) -> Result<Vec<ParsedStatement>> {
+    read_entries(reader)?
+        .into_iter()
+        .map(|mut entry| {
+            let provenance = entry.provenance.take().ok_or_else(|| {
+                ParseError::CacheError("snapshot has no statement provenance".to_string())
+            })?;
+            Ok(ParsedStatement {
+                statement: entry.into_statement(),
+                provenance,
+            })
+        })
+        .collect()
+}
+
+fn write_entries<W: Write>(
+    mut writer: W,
+    entries: Vec<Entry<&ActivityFlexStatement>>,
+) -> Result<()> {
+    writer.write_all(MAGIC)?;
+    write_version(&mut writer)?;
+    rmp_serde::encode::write_named(&mut writer, &entries).map_err(cache_error)?;
+    writer.flush()?;
+    Ok(())
+}
+
+fn read_entries<R: Read>(mut reader: R) -> Result<Vec<Entry<ActivityFlexStatement>>> {
+    let mut magic = [0; 8];
+    reader.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(ParseError::CacheError(
+            "not an ib-flex statement snapshot".to_string(),
+        ));
+    }
+    let mut len = [0; 1];
+    reader.read_exact(&mut len)?;
+    let mut version = vec![0; usize::from(len[0])];
+    reader.read_exact(&mut version)?;
+    if version != env!("CARGO_PKG_VERSION").as_bytes() {
+        return Err(ParseError::CacheError(format!(
+            "snapshot written by ib-flex {}, not {}",
+            String::from_utf8_lossy(&version),
+            env!("CARGO_PKG_VERSION")
+        )));
+    }
+
+    rmp_serde::decode::from_read(reader).map_err(cache_error)
+}
+
+/// A statement with the fields its `Serialize` impl leaves out
+#[derive(Serialize, Deserialize)]
+struct Entry<S> {
+    timezone: Option<String>,
+    #[serde(default)]
+    provenance: Option<Provenance>,
+    statement: S,
+}
+
+impl Entry<ActivityFlexStatement> {
+    fn into_statement(self) -> ActivityFlexStatement {
+        let mut statement = self.statement;
+        statement.timezone = self.timezone;
+        statement
+    }
+}
+
+fn write_version<W: Write>(writer: &mut W) -> Result<()> {
+    let version = env!("CARGO_PKG_VERSION").as_bytes();
+    writer.write_all(&[version.len() as u8])?;
+    writer.write_all(version)?;
+    Ok(())
+}
+
+fn cache_error(e: impl std::fmt::Display) -> ParseError {
+    ParseError::CacheError(e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip_fixtures() {
+        let fixtures = [
+            include_str!("../tests/fixtures/activity_backfill.xml"),
+            include_str!("../tests/fixtures/activity_bonds.xml"),
+            include_str!("../tests/fixtures/activity_cancelled_trades.xml"),
+            include_str!("../tests/fixtures/activity_cash.xml"),
+            include_str!("../tests/fixtures/activity_cfds.xml"),
+            include_str!("../tests/fixtures/activity_complex_corporate_actions.xml"),
+            include_str!("../tests/fixtures/activity_corporate_actions.xml"),
+            include_str!("../tests/fixtures/activity_daily_portfolio.xml"),
+            include_str!("../tests/fixtures/activity_extended.xml"),
+            include_str!("../tests/fixtures/activity_forex.xml"),
+            include_str!("../tests/fixtures/activity_fractional_shares.xml"),
+            include_str!("../tests/fixtures/activity_futures.xml"),
+            include_str!("../tests/fixtures/activity_minimal.xml"),
+            include_str!("../tests/fixtures/activity_options.xml"),
+            include_str!("../tests/fixtures/activity_tbills.xml"),
+            include_str!("../tests/fixtures/activity_warrants.xml"),
+        ];
+        for xml in fixtures {
+            let statements = crate::parse_activity_flex_all(xml).unwrap();
+            let mut snapshot = Vec::new();
+            write(&mut snapshot, &statements).unwrap();
+            assert_eq!(read(snapshot.as_slice()).unwrap(), statements);
+        }
+    }
+
+    #[test]
+    fn test_round_trip_provenance() {
+        let xml = include_str!("../tests/fixtures/activity_minimal.xml");
+        let parsed = ParsedStatement::parse_all(xml, Some("activity_minimal.xml")).unwrap();
+        let mut snapshot = Vec::new();
+        write_parsed(&mut snapshot, &parsed).unwrap();
+        assert_eq!(read_parsed(snapshot.as_slice()).unwrap(), parsed);
+        assert_eq!(read(snapshot.as_slice()).unwrap()[0], parsed[0].statement);
+
+        let mut plain = Vec::new();
+        write(&mut plain, [&parsed[0].statement]).unwrap();
+        assert!(read_parsed(plain.as_slice()).is_err());
+    }
+
+    #[test]
+    fn test_rejects_other_versions() {
+        let mut snapshot = MAGIC.to_vec();
+        snapshot.extend_from_slice(b"\x050.0.0");
+        rmp_serde::encode::write_named(&mut snapshot, &Vec::<Entry<()>>::new()).unwrap();
+        let err = read(snapshot.as_slice()).unwrap_err();
+        assert!(err.to_string().contains("ib-flex 0.0.0"), "{}", err);
+        assert!(read(&b"<FlexQueryResponse>"[..]).is_err());
+    }
+}
@@ -0,0 +1,233 @@
+//! Spotting sections and fields a FLEX query leaves out
+//!
+//! What a statement contains is decided by the Flex Query it was generated
+//! from: every section and most fields are opt-in in IB's query editor. A
+//! missing `SecuritiesInfo` or an `origTradeDate` that is never set looks
+//! like a parser bug but is almost always a box left unticked.
+//! [`query_gaps`] looks for data the rest of the statement implies should be
+//! there and names the query setting that would add it.
+//!
+//! # Example
+//! ```
+//! use ib_flex::diagnostics::query_gaps;
+//! use ib_flex::parse_activity_flex;
+//!
+//! let xml = include_str!("../tests/fixtures/activity_minimal.xml");
+//! let statement = parse_activity_flex(xml).unwrap();
+//! let gaps = query_gaps(&statement);
+//! assert_eq!(
+//!     gaps[0].to_string(),
+//!     "no SecuritiesInfo — enable 'Financial Instrument Information' in your Flex Query"
+//! );
+//! ```
+
+use std::fmt;
+
+use serde::Deserialize;
+#[cfg(feature = "serialize")]
+use serde::Serialize;
+
+use crate::types::{ActivityFlexStatement, OpenClose};
+
+/// A section or field the statement's Flex Query does not include
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct QueryGap {
+    /// Section as IB names it in the XML (e.g. `SecuritiesInfo`)
+    pub section: String,
+    /// XML attribute missing from every row of the section, or `None` when
+    /// the whole section is
+    pub field: Option<String>,
+    /// Rows that would carry the field; records elsewhere in the statement
+    /// that need the section
+    pub rows: usize,
+    /// Flex Query setting that adds it, as labelled in IB's query editor
+    pub setting: String,
+}
+
+impl fmt::Display for QueryGap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.field {
+            Some(field) => write!(f, "{} missing {}", self.section, field)?,
+            None => write!(f, "no {}", self.section)?,
+        }
+        write!(f, " — enable '{}' in your Flex Query", self.setting)
+    }
+}
+
+/// List what the Flex Query behind `statement` leaves out
+///
+/// A section is reported when it is empty but other sections have records
+/// it would describe, such as trades with no `SecuritiesInfo`. A field is
+/// reported when no row it applies to has a value for it: IB writes every
+/// field a query selects on every row, so a field set on some rows is in
+/// the query and just empty on the others.
+pub fn query_gaps(statement: &ActivityFlexStatement) -> Vec<QueryGap> {
+    let mut gaps = Vec::new();
+    let trades = &statement.trades.items;
+    let positions = &statement.positions.items;
+    let cash_transactions = &statement.cash_transactions.items;
+
+    let mut section = |name: &str, rows: usize, setting: &str| {
+        if rows > 0 {
+            gaps.push(QueryGap {
+                section: name.to_string(),
+                field: None,
+                rows,
+                setting: setting.to_string(),
+            });
+        }
+    };
+    if statement.securities_info.items.is_empty() {
+        section(
+            "SecuritiesInfo",
+            trades.len() + positions.len(),
+            "Financial Instrument Information",
+        );
+    }
+    let mut currencies: Vec<&str> = trades
+        .iter()
+        .map(|t| t.currency.as_str())
+        .chain(positions.iter().map(|p| p.currency.as_str()))
+        .chain(cash_transactions.iter().map(|tx| tx.currency.as_str()))
+        .collect();
+    currencies.sort_unstable();
+    currencies.dedup();
+    if statement.conversion_rates.items.is_empty() && currencies.len() > 1 {
+        section("ConversionRates", currencies.len(), "Conversion Rates");
+    }
+    if statement.cash_report.items.is_empty() {
+        section(
+            "CashReport",
+            trades.len() + cash_transactions.len(),
+            "Cash Report",
+        );
+    }
+    if statement.equity_summary.items.is_empty() {
+        section(
+            "EquitySummaryInBase",
+            positions.len(),
+            "Net Asset Value (NAV) in Base",
+        );
+    }
+
+    let closing = |open_close: &Option<OpenClose>| {
+        matches!(open_close, Some(OpenClose::Close | OpenClose::CloseOpen))
+    };
+    gaps.extend(
+        [
+            field(
+                "Trades",
+                "origTradeDate",
+                "Closed Lots",
+                trades.iter().filter(|t| closing(&t.open_close)),
+                |t| t.orig_trade_date.is_some(),
+            ),
+            field(
+                "Trades",
+                "openCloseIndicator",
+                "Open/Close Indicator",
+                trades.iter(),
+                |t| t.open_close.is_some(),
+            ),
+            field(
+                "Trades",
+                "fifoPnlRealized",
+                "Realized P/L",
+                trades.iter(),
+                |t| t.fifo_pnl_realized.is_some(),
+            ),
+            field("Trades", "tradeID", "Trade ID", trades.iter(), |t| {
+                t.trade_id.is_some()
+            }),
+            field(
+                "OpenPositions",
+                "costBasisMoney",
+                "Cost Basis Money",
+                positions.iter(),
+                |p| p.cost_basis_money.is_some(),
+            ),
+            field(
+                "OpenPositions",
+                "fifoPnlUnrealized",
+                "Unrealized P/L",
+                positions.iter(),
+                |p| p.fifo_pnl_unrealized.is_some(),
+            ),
+            field(
+                "CashTransactions",
+                "type",
+                "Type",
+                cash_transactions.iter(),
+                |tx| tx.transaction_type.is_some(),
+            ),
+        ]
+        .into_iter()
+        .flatten(),
+    );
+    gaps
+}
+
+/// A gap for `field` if none of `rows` has it
+fn field<'a, T: 'a>(
+    section: &str,
+    field: &str,
+    setting: &str,
+    rows: impl Iterator<Item = &'a T>,
+    has: impl Fn(&T) -> bool,
+) -> Option<QueryGap> {
+    let mut count = 0;
+    for row in rows {
+        if has(row) {
+            return None;
+        }
+        count += 1;
+    }
+    (count > 0).then(|| QueryGap {
+        section: section.to_string(),
+        field: Some(field.to_string()),
+        rows: count,
+        setting: setting.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_query_gaps() {
+        let xml = include_str!("../tests/fixtures/activity_minimal.xml");
+        let mut statement = crate::parse_activity_flex(xml).unwrap();
+        statement.trades.items[0].open_close = Some(OpenClose::Close);
+        let gaps: Vec<String> = query_gaps(&statement)
+            .iter()
+            .map(|gap| gap.to_string())
+            .collect();
+        assert_eq!(
+            gaps,
+            [
+                "no SecuritiesInfo — enable 'Financial Instrument Information' in your Flex Query",
+                "no CashReport — enable 'Cash Report' in your Flex Query",
+                "Trades missing origTradeDate — enable 'Closed Lots' in your Flex Query",
+                "Trades missing fifoPnlRealized — enable 'Realized P/L' in your Flex Query",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_field_on_some_rows_is_selected() {
+        let xml = include_str!("../tests/fixtures/activity_daily_portfolio.xml");
+        let mut statement = crate::parse_activity_flex(xml).unwrap();
+        let before = query_gaps(&statement);
+        statement.trades.items[0].trade_id = None;
+        assert_eq!(query_gaps(&statement), before);
+        for trade in &mut statement.trades.items {
+            trade.trade_id = None;
+        }
+        let after = query_gaps(&statement);
+        assert!(after
+            .iter()
+            .any(|gap| gap.field.as_deref() == Some("tradeID") && gap.rows == 3));
+    }
+}
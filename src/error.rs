@@ -35,19 +35,123 @@ pub enum ParseError {
     },
 
     /// Unknown enum variant
-    #[error("Unknown enum variant: {variant} for type {enum_type}")]
+    #[error("Unknown enum variant: {variant} for type {enum_type}{}", at(.location))]
     UnknownEnumVariant {
         /// The unknown variant value
         variant: String,
         /// The enum type name
         enum_type: String,
+        /// Row and attribute the value was on, when parsing with
+        /// [`ParseOptions::with_strict_enums`](crate::ParseOptions::with_strict_enums)
+        /// (e.g. `Trades/Trade[0] buySell`)
+        location: Option<String>,
+    },
+
+    /// Symbol that doesn't follow the expected convention
+    #[error("Invalid {kind} symbol: {symbol}")]
+    InvalidSymbol {
+        /// The symbol as given
+        symbol: String,
+        /// The symbol convention that was expected (e.g., "OSI option")
+        kind: String,
+    },
+
+    /// Security identifier with a bad format or check digit
+    #[error("Invalid {kind} identifier: {value}")]
+    InvalidIdentifier {
+        /// The identifier as given
+        value: String,
+        /// Identifier scheme (e.g., "ISIN", "CUSIP")
+        kind: String,
+    },
+
+    /// No FX rate available to convert between two currencies
+    #[error("No FX rate from {from} to {to} on or before {date}")]
+    MissingFxRate {
+        /// Currency converted from
+        from: String,
+        /// Currency converted to
+        to: String,
+        /// Date the rate was needed for
+        date: chrono::NaiveDate,
     },
 
+    /// Statements that cannot be merged into one
+    #[error("Cannot merge statements: {0}")]
+    CannotMerge(String),
+
     /// Unsupported FLEX schema version
     #[error("Unsupported FLEX schema version: {0}")]
     UnsupportedSchemaVersion(String),
 
+    /// JSON export error
+    #[error("JSON serialization error: {0}")]
+    JsonError(String),
+
+    /// Arrow or Parquet export error
+    #[error("Arrow export error: {0}")]
+    ArrowError(String),
+
+    /// Polars DataFrame error
+    #[error("Polars error: {0}")]
+    PolarsError(String),
+
+    /// SQLite export error
+    #[error("SQLite error: {0}")]
+    SqliteError(String),
+
+    /// Postgres export error
+    #[error("Postgres error: {0}")]
+    PostgresError(String),
+
+    /// Excel workbook export error
+    #[error("Excel export error: {0}")]
+    XlsxError(String),
+
+    /// Statement snapshot error
+    #[error("Cache error: {0}")]
+    CacheError(String),
+
+    /// Protobuf encoding or decoding error
+    #[error("Protobuf error: {0}")]
+    ProtoError(String),
+
+    /// Invalid argument passed through the C API
+    #[error("Invalid argument: {0}")]
+    InvalidArgument(String),
+
+    /// Untrusted input over the configured size limit
+    #[error("Input of {size} bytes exceeds the limit of {limit} bytes")]
+    InputTooLarge {
+        /// Size of the input in bytes
+        size: usize,
+        /// Configured limit in bytes
+        limit: usize,
+    },
+
     /// IO error
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 }
+
+/// ` at {location}`, or nothing without one
+fn at(location: &Option<String>) -> String {
+    location
+        .as_ref()
+        .map(|location| format!(" at {}", location))
+        .unwrap_or_default()
+}
+
+/// A statement section that failed to parse and was left empty
+///
+/// Recorded on [`ActivityFlexStatement::section_errors`](crate::ActivityFlexStatement::section_errors)
+/// when [`ParseOptions::with_section_recovery`](crate::ParseOptions::with_section_recovery)
+/// is set.
+#[derive(Error, Debug, Clone, PartialEq, Eq, Hash)]
+#[error("{section}: {message}")]
+pub struct SectionError {
+    /// Section element name (e.g. `CorporateActions`)
+    pub section: String,
+    /// Why it failed to parse
+    pub message: String,
+}
@@ -0,0 +1,188 @@
+//! Arrow record batches per section
+//!
+//! [`ToRecordBatch`] turns trades, open positions, cash transactions, and
+//! corporate actions into an Arrow [`RecordBatch`], so they can be queried
+//! in memory with DataFusion without writing files first. The
+//! columns are those of the [`csv`](super::csv) writers, typed: amounts are
+//! `Decimal128(38, s)` with the largest scale in the column, dates are
+//! `Date32`, date/times are `Timestamp(Microsecond)` without a time zone,
+//! and everything else is `Utf8`. Missing values are null.
+//!
+//! Requires the `arrow` feature.
+//!
+//! # Example
+//! ```
+//! use ib_flex::export::arrow::ToRecordBatch;
+//! use ib_flex::parse_activity_flex_all;
+//!
+//! let xml = include_str!("../../tests/fixtures/activity_backfill.xml");
+//! let statements = parse_activity_flex_all(xml).unwrap();
+//!
+//! // One section, or the rows of many statements
+//! let batch = statements[0].trades.to_record_batch().unwrap();
+//! assert_eq!(batch.num_rows(), statements[0].trades.len());
+//!
+//! let trades: Vec<_> = statements.iter().flat_map(|s| s.trades.items.clone()).collect();
+//! let batch = trades.to_record_batch().unwrap();
+//! assert!(batch.schema().field_with_name("trade_date").is_ok());
+//! ```
+
+use std::sync::Arc;
+
+use arrow_array::types::Date32Type;
+use arrow_array::{
+    Array, ArrayRef, Date32Array, Decimal128Array, RecordBatch, StringArray,
+    TimestampMicrosecondArray,
+};
+use arrow_schema::{Field, Schema};
+
+use super::columns::{
+    rescale, Cell, Column, Kind, ACTION_COLUMNS, CASH_COLUMNS, POSITION_COLUMNS, TRADE_COLUMNS,
+};
+use crate::types::{
+    CashTransaction, CashTransactionsWrapper, CorporateAction, CorporateActionsWrapper, Position,
+    PositionsWrapper, Trade, TradesWrapper,
+};
+use crate::{ParseError, Result};
+
+/// Rows that convert to one Arrow record batch
+pub trait ToRecordBatch {
+    /// One row per item, in order, with the section's columns
+    fn to_record_batch(&self) -> Result<RecordBatch>;
+}
+
+macro_rules! impl_to_record_batch {
+    ($($row:ty, $wrapper:ty => $columns:expr;)+) => {
+        $(
+            impl ToRecordBatch for [$row] {
+                fn to_record_batch(&self) -> Result<RecordBatch> {
+                    record_batch($columns, self)
+                }
+            }
+
+            impl ToRecordBatch for $wrapper {
+                fn to_record_batch(&self) -> Result<RecordBatch> {
+                    self.items.to_record_batch()
+                }
+            }
+        )+
+    };
+}
+
+impl_to_record_batch! {
+    Trade, TradesWrapper => TRADE_COLUMNS;
+    Position, PositionsWrapper => POSITION_COLUMNS;
+    CashTransaction, CashTransactionsWrapper => CASH_COLUMNS;
+    CorporateAction, CorporateActionsWrapper => ACTION_COLUMNS;
+}
+
+/// Rows as a record batch with one column per entry of `columns`
+pub(super) fn record_batch<'a, T, I>(columns: &[Column<T>], rows: I) -> Result<RecordBatch>
+where
+    T: 'a,
+    I: IntoIterator<Item = &'a T>,
+{
+    let rows: Vec<&T> = rows.into_iter().collect();
+    let mut fields = Vec::with_capacity(columns.len());
+    let mut arrays = Vec::with_capacity(columns.len());
+    for column in columns {
+        let array = to_array(column.kind, rows.iter().map(|row| (column.value)(row)))?;
+        fields.push(Field::new(column.name, array.data_type().clone(), true));
+        arrays.push(array);
+    }
+    RecordBatch::try_new(Arc::new(Schema::new(fields)), arrays).map_err(arrow_error)
+}
+
+/// One column of cells as an Arrow array of `kind`
+fn to_array<'a>(kind: Kind, cells: impl Iterator<Item = Cell<'a>>) -> Result<ArrayRef> {
+    Ok(match kind {
+        Kind::Text => Arc::new(
+            cells
+                .map(|cell| match cell {
+                    Cell::Text(text) => text,
+                    _ => None,
+                })
+                .collect::<StringArray>(),
+        ),
+        Kind::Date => Arc::new(
+            cells
+                .map(|cell| match cell {
+                    Cell::Date(date) => date.map(Date32Type::from_naive_date),
+                    _ => None,
+                })
+                .collect::<Date32Array>(),
+        ),
+        Kind::DateTime => Arc::new(
+            cells
+                .map(|cell| match cell {
+                    Cell::DateTime(dt) => dt.map(|dt| dt.and_utc().timestamp_micros()),
+                    _ => None,
+                })
+                .collect::<TimestampMicrosecondArray>(),
+        ),
+        Kind::Decimal => {
+            let values: Vec<_> = cells
+                .map(|cell| match cell {
+                    Cell::Decimal(value) => value,
+                    _ => None,
+                })
+                .collect();
+            let (scale, mantissas) = rescale(&values).map_err(ParseError::ArrowError)?;
+            let mantissas = Decimal128Array::from(mantissas);
+            Arc::new(
+                mantissas
+                    .with_precision_and_scale(38, scale as i8)
+                    .map_err(arrow_error)?,
+            )
+        }
+    })
+}
+
+pub(super) fn arrow_error(e: impl std::fmt::Display) -> ParseError {
+    ParseError::ArrowError(e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_array::cast::AsArray;
+    use arrow_array::types::Decimal128Type;
+    use arrow_schema::DataType;
+
+    #[test]
+    fn test_cash_batch_types_and_nulls() {
+        let xml = include_str!("../../tests/fixtures/activity_cash.xml");
+        let statement = crate::parse_activity_flex(xml).unwrap();
+        let rows = &statement.cash_transactions.items;
+
+        let batch = statement.cash_transactions.to_record_batch().unwrap();
+        assert_eq!(batch.num_rows(), rows.len());
+        assert_eq!(batch.num_columns(), CASH_COLUMNS.len());
+        assert_eq!(
+            batch.schema().field_with_name("date").unwrap().data_type(),
+            &DataType::Date32
+        );
+
+        let amounts = batch.column_by_name("amount").unwrap();
+        let DataType::Decimal128(_, scale) = amounts.data_type() else {
+            panic!("amount should be a decimal");
+        };
+        let amounts = amounts.as_primitive::<Decimal128Type>();
+        for (i, row) in rows.iter().enumerate() {
+            let amount =
+                rust_decimal::Decimal::from_i128_with_scale(amounts.value(i), *scale as u32);
+            assert_eq!(amount, row.amount);
+        }
+
+        let codes = batch.column_by_name("code").unwrap();
+        let missing = rows.iter().filter(|row| row.code.is_none()).count();
+        assert_eq!(codes.null_count(), missing);
+    }
+
+    #[test]
+    fn test_empty_section() {
+        let batch = TradesWrapper::default().to_record_batch().unwrap();
+        assert_eq!(batch.num_rows(), 0);
+        assert_eq!(batch.num_columns(), TRADE_COLUMNS.len());
+    }
+}
@@ -0,0 +1,318 @@
+//! Beancount export
+//!
+//! [`write_statements`] writes statements as balanced Beancount
+//! directives:
+//!
+//! - trades as postings to one account per security, held at cost: opening
+//!   trades add a lot at the trade price, closing trades reduce lots with
+//!   `{}` and leave the realized P&L posting for Beancount to fill in;
+//! - FX conversions (`CASH` trades) as a conversion between currencies of
+//!   the cash account, priced at the proceeds;
+//! - commissions and transaction taxes as expense postings;
+//! - cash transactions against dividend, interest, fee, tax, or transfer
+//!   accounts;
+//! - each statement's ending cash per currency, from the cash report, as
+//!   `balance` assertions the day after the statement ends.
+//!
+//! The cash account is padded from an opening balances account before the
+//! first statement, so its starting cash needs no history. Security lots
+//! opened before the first statement are not known: start from a statement
+//! covering the account's history, or add those lots by hand. Every account
+//! used is opened the day before the first statement. Statements should be
+//! given in date order.
+//!
+//! Accounts are named by [`JournalOptions`], as for
+//! [`ledger`](super::ledger). Each transaction carries IB's
+//! `transaction_id` as metadata.
+//!
+//! # Example
+//! ```
+//! use ib_flex::export::beancount::write_statements;
+//! use ib_flex::export::journal::JournalOptions;
+//! use ib_flex::parse_activity_flex;
+//!
+//! let xml = include_str!("../../tests/fixtures/activity_minimal.xml");
+//! let statement = parse_activity_flex(xml).unwrap();
+//!
+//! let mut out = Vec::new();
+//! write_statements(&mut out, [&statement], &JournalOptions::default()).unwrap();
+//! let ledger = String::from_utf8(out).unwrap();
+//! assert!(ledger.contains("open Assets:IB:Cash"));
+//! assert!(ledger.contains("Assets:IB:AAPL  100 AAPL {185.5 USD}"));
+//! ```
+
+use std::collections::BTreeSet;
+use std::fmt::Write as _;
+use std::io::Write;
+
+use chrono::{Days, NaiveDate};
+use rust_decimal::Decimal;
+
+use super::journal::{
+    cash_narration, is_closing, is_execution, security_name, trade_narration, JournalOptions,
+};
+use crate::types::{
+    ActivityFlexStatement, AssetCategory, CashReportCurrency, CashTransaction, Dated, Trade,
+};
+use crate::Result;
+
+/// Write `statements` as Beancount directives
+pub fn write_statements<'a, W, I>(
+    mut writer: W,
+    statements: I,
+    options: &JournalOptions,
+) -> Result<()>
+where
+    W: Write,
+    I: IntoIterator<Item = &'a ActivityFlexStatement>,
+{
+    let statements: Vec<&ActivityFlexStatement> = statements.into_iter().collect();
+    let mut ledger = Ledger {
+        options,
+        accounts: BTreeSet::new(),
+        text: String::new(),
+    };
+    let cash = ledger.account(options.cash());
+    for (i, statement) in statements.iter().enumerate() {
+        if i == 0 && ledger.balances(statement, statement.from_date, |row| row.starting_cash) {
+            let pad = statement.from_date - Days::new(1);
+            ledger.accounts.insert(options.opening_balances.clone());
+            let _ = writeln!(
+                ledger.text,
+                "{} pad {} {}\n",
+                pad, cash, options.opening_balances
+            );
+        }
+        for trade in statement.trades.iter() {
+            ledger.trade(trade);
+        }
+        for transaction in statement.cash_transactions.iter() {
+            ledger.cash_transaction(transaction);
+        }
+        ledger.balances(statement, statement.to_date + Days::new(1), |row| {
+            row.ending_cash
+        });
+    }
+
+    if let Some(first) = statements.iter().map(|s| s.from_date).min() {
+        let opened = first - Days::new(1);
+        for account in &ledger.accounts {
+            writeln!(writer, "{} open {}", opened, account)?;
+        }
+        writeln!(writer)?;
+    }
+    writer.write_all(ledger.text.as_bytes())?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Directives written so far, and the accounts they use
+struct Ledger<'a> {
+    options: &'a JournalOptions,
+    accounts: BTreeSet<String>,
+    text: String,
+}
+
+impl Ledger<'_> {
+    /// `account`, noting that it must be opened
+    fn account(&mut self, account: String) -> String {
+        self.accounts.insert(account.clone());
+        account
+    }
+
+    fn header(&mut self, date: NaiveDate, narration: &str, transaction_id: Option<&str>) {
+        let _ = writeln!(self.text, "{} * \"{}\"", date, quoted(narration));
+        if let Some(id) = transaction_id.filter(|id| !id.is_empty()) {
+            let _ = writeln!(self.text, "  transaction_id: \"{}\"", quoted(id));
+        }
+    }
+
+    fn posting(&mut self, account: &str, amount: Decimal, currency: &str) {
+        let _ = writeln!(
+            self.text,
+            "  {}  {} {}",
+            account,
+            amount.normalize(),
+            currency
+        );
+    }
+
+    fn trade(&mut self, t: &Trade) {
+        if !is_execution(t) {
+            return;
+        }
+        let (Some(date), Some(quantity)) = (t.date(), t.quantity) else {
+            return;
+        };
+        if quantity.is_zero() {
+            return;
+        }
+        let options = self.options;
+        let narration = trade_narration(t, quantity);
+        if t.asset_category == AssetCategory::Cash {
+            let Some((base, _)) = t.symbol.split_once('.') else {
+                return;
+            };
+            if base == t.currency {
+                return;
+            }
+            let cash = self.account(options.cash());
+            self.header(date, &narration, t.transaction_id.as_deref());
+            let _ = writeln!(
+                self.text,
+                "  {}  {} {} @@ {} {}",
+                cash,
+                quantity.normalize(),
+                base,
+                t.proceeds.unwrap_or_default().abs().normalize(),
+                t.currency
+            );
+        } else {
+            let Some(price) = t.price else {
+                return;
+            };
+            let commodity = security_name(&t.symbol);
+            let holding = self.account(options.security(&t.symbol));
+            let cost = (price * t.multiplier.unwrap_or(Decimal::ONE)).normalize();
+            self.header(date, &narration, t.transaction_id.as_deref());
+            let lot = if is_closing(t) {
+                format!("{{}} @ {} {}", cost, t.currency)
+            } else {
+                format!("{{{} {}}}", cost, t.currency)
+            };
+            let _ = writeln!(
+                self.text,
+                "  {}  {} {} {}",
+                holding,
+                quantity.normalize(),
+                commodity,
+                lot
+            );
+        }
+
+        for posting in options.trade_postings(t) {
+            let account = self.account(posting.account);
+            self.posting(&account, posting.amount, &posting.currency);
+        }
+        if t.asset_category != AssetCategory::Cash && is_closing(t) {
+            let account = self.account(options.pnl());
+            let _ = writeln!(self.text, "  {}", account);
+        }
+        self.text.push('\n');
+    }
+
+    fn cash_transaction(&mut self, c: &CashTransaction) {
+        let Some(date) = c.date() else {
+            return;
+        };
+        let cash = self.account(self.options.cash());
+        let counterpart = self.account(self.options.counterpart(c));
+        self.header(date, cash_narration(c), c.transaction_id.as_deref());
+        self.posting(&cash, c.amount, &c.currency);
+        self.posting(&counterpart, -c.amount, &c.currency);
+        self.text.push('\n');
+    }
+
+    /// `balance` assertions on the cash account, one per currency of the
+    /// cash report; returns whether there were any
+    fn balances(
+        &mut self,
+        statement: &ActivityFlexStatement,
+        date: NaiveDate,
+        amount: impl Fn(&CashReportCurrency) -> Decimal,
+    ) -> bool {
+        let cash = self.account(self.options.cash());
+        let rows = statement
+            .cash_report
+            .iter()
+            .filter(|row| row.currency != "BASE_SUMMARY");
+        let mut any = false;
+        for row in rows {
+            any = true;
+            let _ = writeln!(
+                self.text,
+                "{} balance {}  {} {}",
+                date,
+                cash,
+                amount(row).normalize(),
+                row.currency
+            );
+        }
+        if any {
+            self.text.push('\n');
+        }
+        any
+    }
+}
+
+fn quoted(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn ledger(fixture: &str) -> String {
+        let statements = crate::parse_activity_flex_all(fixture).unwrap();
+        let mut out = Vec::new();
+        write_statements(&mut out, &statements, &JournalOptions::default()).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    /// Sum of the postings of each transaction without costs, prices, or
+    /// elided amounts, per currency
+    fn simple_sums(ledger: &str) -> Vec<HashMap<String, Decimal>> {
+        ledger
+            .split("\n\n")
+            .filter(|block| block.contains(" * \""))
+            .filter(|block| !block.contains('{') && !block.contains('@'))
+            .map(|block| {
+                let mut sums: HashMap<String, Decimal> = HashMap::new();
+                for posting in block.lines().skip(1) {
+                    let fields: Vec<&str> = posting.split_whitespace().collect();
+                    if let [_, amount, currency] = fields[..] {
+                        *sums.entry(currency.to_string()).or_default() +=
+                            amount.parse::<Decimal>().unwrap();
+                    }
+                }
+                sums
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_trades_and_balances() {
+        let ledger = ledger(include_str!(
+            "../../tests/fixtures/activity_daily_portfolio.xml"
+        ));
+        assert!(ledger.contains("2025-01-14 open Assets:IB:GOOGL"));
+        assert!(ledger.contains("  Assets:IB:GOOGL  100 GOOGL {175.5 USD}"));
+        assert!(ledger.contains("  Assets:IB:MSFT  -50 MSFT {} @ 420.25 USD"));
+        assert!(
+            ledger.contains("  Assets:IB:SPY-250221C00600000  10 SPY-250221C00600000 {250 USD}")
+        );
+        assert!(ledger.contains("  Expenses:IB:Commissions  0.75 USD\n  Income:IB:PnL\n"));
+        assert!(ledger.contains("2025-01-16 balance Assets:IB:Cash  150000.5 USD"));
+        assert!(!ledger.contains("BASE_SUMMARY"));
+    }
+
+    #[test]
+    fn test_cash_transactions_balance() {
+        let ledger = ledger(include_str!("../../tests/fixtures/activity_cash.xml"));
+        let sums = simple_sums(&ledger);
+        assert!(sums.len() > 10);
+        for sum in sums {
+            assert!(sum.values().all(Decimal::is_zero), "{:?}", sum);
+        }
+        assert!(ledger.contains("  Income:IB:Dividends  "));
+        assert!(ledger.contains("  Equity:IB:Transfers  "));
+    }
+
+    #[test]
+    fn test_fx_conversions() {
+        let ledger = ledger(include_str!("../../tests/fixtures/activity_forex.xml"));
+        assert!(ledger.contains("  Assets:IB:Cash  50000 EUR @@ 54250 USD\n  Assets:IB:Cash  -54252 USD\n  Expenses:IB:Commissions  2 USD\n"));
+    }
+}
@@ -0,0 +1,280 @@
+//! Column lists shared by the per-section writers
+
+use std::borrow::Cow;
+
+use chrono::{NaiveDate, NaiveDateTime};
+use rust_decimal::Decimal;
+
+use super::ghostfolio::Activity;
+use super::koinly::Row as KoinlyRow;
+use super::portfolio_performance::Row as PerformanceRow;
+use crate::analytics::Form8949Row;
+use crate::types::{CashTransaction, CorporateAction, Position, Trade};
+use crate::ParsedStatement;
+
+/// Type of a column, whether or not any row has a value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Kind {
+    Text,
+    Decimal,
+    Date,
+    DateTime,
+}
+
+/// Value of one field; `None` when the row has no value
+pub(super) enum Cell<'a> {
+    Text(Option<Cow<'a, str>>),
+    Decimal(Option<Decimal>),
+    Date(Option<NaiveDate>),
+    DateTime(Option<NaiveDateTime>),
+}
+
+pub(super) struct Column<T> {
+    pub(super) name: &'static str,
+    #[cfg_attr(
+        not(any(feature = "arrow", feature = "polars", feature = "xlsx")),
+        allow(dead_code)
+    )]
+    pub(super) kind: Kind,
+    pub(super) value: fn(&T) -> Cell<'_>,
+}
+
+fn borrowed(value: &str) -> Option<Cow<'_, str>> {
+    Some(Cow::Borrowed(value))
+}
+
+fn text(value: Option<&str>) -> Option<Cow<'_, str>> {
+    value.map(Cow::Borrowed)
+}
+
+fn display<T: std::fmt::Display>(value: Option<T>) -> Option<Cow<'static, str>> {
+    value.map(|v| Cow::Owned(v.to_string()))
+}
+
+/// Mantissas of `values` at the largest scale among them, for a
+/// 38-digit decimal column, with that scale
+#[cfg_attr(not(any(feature = "arrow", feature = "polars")), allow(dead_code))]
+pub(super) fn rescale(values: &[Option<Decimal>]) -> Result<(u32, Vec<Option<i128>>), String> {
+    const LIMIT: i128 = 10i128.pow(38);
+    let scale = values
+        .iter()
+        .flatten()
+        .map(|v| v.scale())
+        .max()
+        .unwrap_or(0);
+    let mantissas = values
+        .iter()
+        .map(|value| {
+            value
+                .map(|v| {
+                    10i128
+                        .checked_pow(scale - v.scale())
+                        .and_then(|factor| v.mantissa().checked_mul(factor))
+                        .filter(|m| m.abs() < LIMIT)
+                        .ok_or_else(|| format!("{} does not fit DECIMAL(38, {})", v, scale))
+                })
+                .transpose()
+        })
+        .collect::<Result<_, _>>()?;
+    Ok((scale, mantissas))
+}
+
+macro_rules! columns {
+    ($ty:ty { $($kind:ident $name:literal => |$row:ident| $value:expr),+ $(,)? }) => {
+        &[$(Column::<$ty> {
+            name: $name,
+            kind: Kind::$kind,
+            value: |$row| Cell::$kind($value),
+        }),+]
+    };
+}
+
+pub(super) const TRADE_COLUMNS: &[Column<Trade>] = columns!(Trade {
+    Text "account_id" => |t| borrowed(t.account_id.as_str()),
+    Date "trade_date" => |t| t.trade_date,
+    DateTime "trade_time" => |t| t.trade_time,
+    Date "settle_date" => |t| t.settle_date,
+    Text "symbol" => |t| borrowed(&t.symbol),
+    Text "conid" => |t| display(Some(t.conid)),
+    Text "description" => |t| text(t.description.as_deref()),
+    Text "asset_category" => |t| display(Some(&t.asset_category)),
+    Text "put_call" => |t| display(t.put_call.as_ref()),
+    Decimal "strike" => |t| t.strike,
+    Date "expiry" => |t| t.expiry,
+    Decimal "multiplier" => |t| t.multiplier,
+    Text "buy_sell" => |t| display(t.buy_sell.as_ref()),
+    Text "open_close" => |t| display(t.open_close.as_ref()),
+    Decimal "quantity" => |t| t.quantity,
+    Decimal "price" => |t| t.price,
+    Decimal "proceeds" => |t| t.proceeds,
+    Decimal "commission" => |t| t.commission,
+    Decimal "taxes" => |t| t.taxes,
+    Decimal "net_cash" => |t| t.net_cash,
+    Decimal "fifo_pnl_realized" => |t| t.fifo_pnl_realized,
+    Text "currency" => |t| borrowed(&t.currency),
+    Decimal "fx_rate_to_base" => |t| t.fx_rate_to_base,
+    Text "trade_id" => |t| display(t.trade_id.as_ref()),
+    Text "order_id" => |t| display(t.ib_order_id.as_ref()),
+    Text "level_of_detail" => |t| display(t.level_of_detail.as_ref()),
+    Text "notes" => |t| text(t.notes.as_deref()),
+});
+
+pub(super) const POSITION_COLUMNS: &[Column<Position>] = columns!(Position {
+    Text "account_id" => |p| borrowed(p.account_id.as_str()),
+    Date "report_date" => |p| Some(p.report_date),
+    Text "symbol" => |p| borrowed(&p.symbol),
+    Text "conid" => |p| display(Some(p.conid)),
+    Text "description" => |p| text(p.description.as_deref()),
+    Text "asset_category" => |p| display(Some(&p.asset_category)),
+    Text "put_call" => |p| display(p.put_call.as_ref()),
+    Decimal "strike" => |p| p.strike,
+    Date "expiry" => |p| p.expiry,
+    Decimal "multiplier" => |p| p.multiplier,
+    Text "side" => |p| display(p.side.as_ref()),
+    Decimal "quantity" => |p| Some(p.quantity),
+    Decimal "mark_price" => |p| Some(p.mark_price),
+    Decimal "position_value" => |p| Some(p.position_value),
+    Decimal "cost_basis_price" => |p| p.cost_basis_price,
+    Decimal "cost_basis_money" => |p| p.cost_basis_money,
+    Decimal "fifo_pnl_unrealized" => |p| p.fifo_pnl_unrealized,
+    Decimal "percent_of_nav" => |p| p.percent_of_nav,
+    Text "currency" => |p| borrowed(&p.currency),
+    Decimal "fx_rate_to_base" => |p| p.fx_rate_to_base,
+    DateTime "open_date_time" => |p| p.open_date_time,
+    Text "level_of_detail" => |p| display(p.level_of_detail.as_ref()),
+});
+
+/// Long-format position history: one row per position per day
+pub(super) const DAILY_POSITION_COLUMNS: &[Column<Position>] = columns!(Position {
+    Date "date" => |p| Some(p.report_date),
+    Text "account_id" => |p| borrowed(p.account_id.as_str()),
+    Text "conid" => |p| display(Some(p.conid)),
+    Text "symbol" => |p| borrowed(&p.symbol),
+    Decimal "quantity" => |p| Some(p.quantity),
+    Decimal "mark_price" => |p| Some(p.mark_price),
+    Decimal "position_value" => |p| Some(p.position_value),
+    Decimal "fifo_pnl_unrealized" => |p| p.fifo_pnl_unrealized,
+    Text "currency" => |p| borrowed(&p.currency),
+});
+
+pub(super) const CASH_COLUMNS: &[Column<CashTransaction>] = columns!(CashTransaction {
+    Text "account_id" => |c| borrowed(c.account_id.as_str()),
+    Date "date" => |c| c.date,
+    Date "settle_date" => |c| c.settle_date,
+    Text "type" => |c| display(c.transaction_type.as_ref()),
+    Text "symbol" => |c| text(c.symbol.as_deref()),
+    Text "conid" => |c| text(c.conid.as_deref()),
+    Text "description" => |c| text(c.description.as_deref()),
+    Decimal "amount" => |c| Some(c.amount),
+    Text "currency" => |c| borrowed(&c.currency),
+    Decimal "fx_rate_to_base" => |c| c.fx_rate_to_base,
+    Text "transaction_id" => |c| text(c.transaction_id.as_deref()),
+    Text "code" => |c| text(c.code.as_deref()),
+    Text "level_of_detail" => |c| display(c.level_of_detail.as_ref()),
+});
+
+pub(super) const ACTION_COLUMNS: &[Column<CorporateAction>] = columns!(CorporateAction {
+    Text "account_id" => |a| borrowed(a.account_id.as_str()),
+    Date "report_date" => |a| Some(a.report_date),
+    Date "action_date" => |a| a.action_date,
+    Date "ex_date" => |a| a.ex_date,
+    Date "pay_date" => |a| a.pay_date,
+    Text "type" => |a| text(a.action_type.as_deref()),
+    Text "symbol" => |a| borrowed(&a.symbol),
+    Text "conid" => |a| display(Some(a.conid)),
+    Text "description" => |a| text(a.description.as_deref()),
+    Decimal "quantity" => |a| a.quantity,
+    Decimal "amount" => |a| a.amount,
+    Decimal "proceeds" => |a| a.proceeds,
+    Decimal "value" => |a| a.value,
+    Decimal "fifo_pnl_realized" => |a| a.fifo_pnl_realized,
+    Text "currency" => |a| text(a.currency.as_deref()),
+    Text "transaction_id" => |a| text(a.transaction_id.as_deref()),
+    Text "action_id" => |a| text(a.action_id.as_deref()),
+});
+
+pub(super) const DIVIDEND_COLUMNS: &[Column<CashTransaction>] = columns!(CashTransaction {
+    Text "account_id" => |c| borrowed(c.account_id.as_str()),
+    Date "date" => |c| c.date,
+    Date "ex_date" => |c| c.ex_date,
+    Text "type" => |c| display(c.transaction_type.as_ref()),
+    Text "symbol" => |c| text(c.symbol.as_deref()),
+    Text "conid" => |c| text(c.conid.as_deref()),
+    Text "isin" => |c| text(c.isin.as_deref()),
+    Text "description" => |c| text(c.description.as_deref()),
+    Decimal "amount" => |c| Some(c.amount),
+    Text "currency" => |c| borrowed(&c.currency),
+    Decimal "fx_rate_to_base" => |c| c.fx_rate_to_base,
+    Decimal "amount_in_base" => |c| c.fx_rate_to_base.map(|fx| c.amount * fx),
+    Text "transaction_id" => |c| text(c.transaction_id.as_deref()),
+});
+
+pub(super) const PROVENANCE_COLUMNS: &[Column<ParsedStatement>] = columns!(ParsedStatement {
+    Text "account_id" => |p| borrowed(p.statement.account_id.as_str()),
+    Date "from_date" => |p| Some(p.statement.from_date),
+    Date "to_date" => |p| Some(p.statement.to_date),
+    Text "query_name" => |p| text(p.provenance.query_name.as_deref()),
+    Text "when_generated" => |p| borrowed(&p.provenance.when_generated),
+    Text "source" => |p| text(p.provenance.source.as_deref()),
+    Text "sha256" => |p| borrowed(&p.provenance.sha256),
+    DateTime "parsed_at" => |p| Some(p.provenance.parsed_at.naive_utc()),
+});
+
+/// Form 8949 columns (a) through (h), named as on the form
+pub(super) const FORM_8949_COLUMNS: &[Column<Form8949Row>] = columns!(Form8949Row {
+    Text "Description" => |r| borrowed(&r.description),
+    Date "Date Acquired" => |r| Some(r.date_acquired),
+    Date "Date Sold" => |r| Some(r.date_sold),
+    Decimal "Proceeds" => |r| Some(r.proceeds),
+    Decimal "Cost Basis" => |r| Some(r.cost_basis),
+    Text "Code" => |r| Some(Cow::<str>::Owned(r.codes())).filter(|codes| !codes.is_empty()),
+    Decimal "Adjustment" => |r| Some(r.adjustment).filter(|a| !a.is_zero()),
+    Decimal "Gain or Loss" => |r| Some(r.gain_or_loss),
+});
+
+/// Columns of Portfolio Performance's CSV import, named as it names them
+pub(super) const PORTFOLIO_PERFORMANCE_COLUMNS: &[Column<PerformanceRow>] = columns!(PerformanceRow {
+    Date "Date" => |r| Some(r.date),
+    Text "Time" => |r| display(r.time.map(|t| t.format("%H:%M:%S"))),
+    Text "Type" => |r| borrowed(r.kind),
+    Text "Security Name" => |r| text(r.security_name.as_deref()),
+    Text "ISIN" => |r| text(r.isin.as_deref()),
+    Text "Ticker Symbol" => |r| text(r.symbol.as_deref()),
+    Decimal "Shares" => |r| r.shares,
+    Decimal "Value" => |r| Some(r.value),
+    Text "Transaction Currency" => |r| borrowed(&r.currency),
+    Decimal "Fees" => |r| r.fees,
+    Decimal "Taxes" => |r| r.taxes,
+    Text "Note" => |r| text(r.note.as_deref()),
+    Text "Cash Account" => |r| borrowed(&r.cash_account),
+    Text "Securities Account" => |r| borrowed(&r.securities_account),
+});
+
+/// Columns of the Koinly universal layout
+pub(super) const KOINLY_COLUMNS: &[Column<KoinlyRow>] = columns!(KoinlyRow {
+    DateTime "Date" => |r| Some(r.time),
+    Decimal "Sent Amount" => |r| r.sent.as_ref().map(|(amount, _)| *amount),
+    Text "Sent Currency" => |r| text(r.sent.as_ref().map(|(_, currency)| currency.as_str())),
+    Decimal "Received Amount" => |r| r.received.as_ref().map(|(amount, _)| *amount),
+    Text "Received Currency" => |r| text(r.received.as_ref().map(|(_, currency)| currency.as_str())),
+    Decimal "Fee Amount" => |r| r.fee.as_ref().map(|(amount, _)| *amount),
+    Text "Fee Currency" => |r| text(r.fee.as_ref().map(|(_, currency)| currency.as_str())),
+    Text "Label" => |r| text(r.label),
+    Text "Description" => |r| borrowed(&r.description),
+    Text "TxHash" => |r| text(r.tx_hash.as_deref()),
+});
+
+/// Activity columns, named as tracker imports name them
+pub(super) const ACTIVITY_COLUMNS: &[Column<Activity>] = columns!(Activity {
+    Date "date" => |a| Some(a.date),
+    Text "type" => |a| borrowed(a.activity_type.as_str()),
+    Text "symbol" => |a| borrowed(&a.symbol),
+    Decimal "quantity" => |a| Some(a.quantity),
+    Decimal "unitPrice" => |a| Some(a.unit_price),
+    Decimal "fee" => |a| Some(a.fee),
+    Text "currency" => |a| borrowed(&a.currency),
+    Text "dataSource" => |a| borrowed(a.data_source),
+    Text "assetClass" => |a| text(a.asset_class),
+    Text "assetSubClass" => |a| text(a.asset_sub_class),
+    Text "comment" => |a| text(a.comment.as_deref()),
+});
@@ -0,0 +1,525 @@
+//! CSV export per section
+//!
+//! One writer each for trades, open positions, cash transactions, corporate
+//! actions, dividends, and Form 8949 rows, plus [`write_daily_positions`]
+//! for the position history of many daily statements and
+//! [`write_provenance`] for a manifest of where statements came from. Every
+//! writer has a fixed set of columns, listed on the function, so
+//! spreadsheets and scripts built on the output keep working as the types
+//! grow. [`CsvOptions`] controls the delimiter and how dates and amounts are
+//! formatted.
+//!
+//! Rows are written as given: filter them first to drop, say, summary rows.
+//!
+//! # Example
+//! ```
+//! use ib_flex::export::csv::{write_trades, CsvOptions};
+//! use ib_flex::parse_activity_flex;
+//!
+//! let xml = include_str!("../../tests/fixtures/activity_minimal.xml");
+//! let statement = parse_activity_flex(xml).unwrap();
+//!
+//! let mut out = Vec::new();
+//! write_trades(&mut out, &statement.trades, &CsvOptions::default()).unwrap();
+//! let csv = String::from_utf8(out).unwrap();
+//! assert!(csv.starts_with("account_id,trade_date,"));
+//! ```
+
+use std::borrow::Cow;
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use super::columns::{
+    Cell, Column, ACTION_COLUMNS, CASH_COLUMNS, DAILY_POSITION_COLUMNS, DIVIDEND_COLUMNS,
+    FORM_8949_COLUMNS, POSITION_COLUMNS, PROVENANCE_COLUMNS, TRADE_COLUMNS,
+};
+use crate::analytics::{Form8949Row, HoldingPeriod};
+use crate::types::{
+    ActivityFlexStatement, CashTransaction, CorporateAction, LevelOfDetail, Position, Trade,
+};
+use crate::{ParsedStatement, Result};
+
+/// Formatting for the CSV writers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CsvOptions {
+    /// Field delimiter (default `,`)
+    pub delimiter: char,
+    /// Write a header row with the column names (default true)
+    pub header: bool,
+    /// `chrono` format for dates (default `%Y-%m-%d`)
+    pub date_format: String,
+    /// `chrono` format for date/times (default `%Y-%m-%d %H:%M:%S`)
+    pub datetime_format: String,
+    /// Round amounts to this many places; `None` writes them as reported
+    pub decimal_places: Option<u32>,
+    /// Decimal separator (default `.`); fields containing the delimiter
+    /// are quoted, so `,` works with either delimiter
+    pub decimal_separator: char,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        CsvOptions {
+            delimiter: ',',
+            header: true,
+            date_format: "%Y-%m-%d".to_string(),
+            datetime_format: "%Y-%m-%d %H:%M:%S".to_string(),
+            decimal_places: None,
+            decimal_separator: '.',
+        }
+    }
+}
+
+/// Trades, one row each
+///
+/// Columns: `account_id`, `trade_date`, `trade_time`, `settle_date`,
+/// `symbol`, `conid`, `description`, `asset_category`, `put_call`, `strike`,
+/// `expiry`, `multiplier`, `buy_sell`, `open_close`, `quantity`, `price`,
+/// `proceeds`, `commission`, `taxes`, `net_cash`, `fifo_pnl_realized`,
+/// `currency`, `fx_rate_to_base`, `trade_id`, `order_id`,
+/// `level_of_detail`, `notes`.
+pub fn write_trades<'a, W, I>(writer: W, trades: I, options: &CsvOptions) -> Result<()>
+where
+    W: Write,
+    I: IntoIterator<Item = &'a Trade>,
+{
+    write_rows(writer, TRADE_COLUMNS, trades, options)
+}
+
+/// Open positions, one row each
+///
+/// Columns: `account_id`, `report_date`, `symbol`, `conid`, `description`,
+/// `asset_category`, `put_call`, `strike`, `expiry`, `multiplier`, `side`,
+/// `quantity`, `mark_price`, `position_value`, `cost_basis_price`,
+/// `cost_basis_money`, `fifo_pnl_unrealized`, `percent_of_nav`,
+/// `currency`, `fx_rate_to_base`, `open_date_time`, `level_of_detail`.
+pub fn write_positions<'a, W, I>(writer: W, positions: I, options: &CsvOptions) -> Result<()>
+where
+    W: Write,
+    I: IntoIterator<Item = &'a Position>,
+{
+    write_rows(writer, POSITION_COLUMNS, positions, options)
+}
+
+/// Position history of many statements, one row per position per day
+///
+/// The long-format table most portfolio analytics take as input. Lot rows
+/// are skipped, rows are sorted by date, account, and conid, and a position
+/// reported by more than one statement for the same day is written once.
+/// Columns: `date`, `account_id`, `conid`, `symbol`, `quantity`,
+/// `mark_price`, `position_value`, `fifo_pnl_unrealized`, `currency`.
+pub fn write_daily_positions<'a, W, I>(writer: W, statements: I, options: &CsvOptions) -> Result<()>
+where
+    W: Write,
+    I: IntoIterator<Item = &'a ActivityFlexStatement>,
+{
+    let rows = daily_positions(statements);
+    write_rows(writer, DAILY_POSITION_COLUMNS, rows, options)
+}
+
+/// Summary positions of `statements` by date, account, and conid, without
+/// repeats
+pub(super) fn daily_positions<'a, I>(statements: I) -> Vec<&'a Position>
+where
+    I: IntoIterator<Item = &'a ActivityFlexStatement>,
+{
+    let mut rows: Vec<&Position> = statements
+        .into_iter()
+        .flat_map(|s| &s.positions)
+        .filter(|p| matches!(p.level_of_detail, None | Some(LevelOfDetail::Summary)))
+        .collect();
+    let key = |p: &Position| {
+        (
+            p.report_date,
+            p.account_id.clone(),
+            p.conid,
+            p.symbol.clone(),
+        )
+    };
+    rows.sort_by_key(|p| key(p));
+    rows.dedup_by_key(|p| key(p));
+    rows
+}
+
+/// Provenance of statements, one row each
+///
+/// A manifest to keep beside the other exports, tying each account and
+/// period back to the file it was parsed from. `parsed_at` is in UTC.
+/// Columns: `account_id`, `from_date`, `to_date`, `query_name`,
+/// `when_generated`, `source`, `sha256`, `parsed_at`.
+pub fn write_provenance<'a, W, I>(writer: W, statements: I, options: &CsvOptions) -> Result<()>
+where
+    W: Write,
+    I: IntoIterator<Item = &'a ParsedStatement>,
+{
+    write_rows(writer, PROVENANCE_COLUMNS, statements, options)
+}
+
+/// Cash transactions, one row each
+///
+/// Columns: `account_id`, `date`, `settle_date`, `type`, `symbol`,
+/// `conid`, `description`, `amount`, `currency`, `fx_rate_to_base`,
+/// `transaction_id`, `code`, `level_of_detail`.
+pub fn write_cash_transactions<'a, W, I>(
+    writer: W,
+    transactions: I,
+    options: &CsvOptions,
+) -> Result<()>
+where
+    W: Write,
+    I: IntoIterator<Item = &'a CashTransaction>,
+{
+    write_rows(writer, CASH_COLUMNS, transactions, options)
+}
+
+/// Corporate actions, one row each
+///
+/// Columns: `account_id`, `report_date`, `action_date`, `ex_date`,
+/// `pay_date`, `type`, `symbol`, `conid`, `description`, `quantity`,
+/// `amount`, `proceeds`, `value`, `fifo_pnl_realized`, `currency`,
+/// `transaction_id`, `action_id`.
+pub fn write_corporate_actions<'a, W, I>(writer: W, actions: I, options: &CsvOptions) -> Result<()>
+where
+    W: Write,
+    I: IntoIterator<Item = &'a CorporateAction>,
+{
+    write_rows(writer, ACTION_COLUMNS, actions, options)
+}
+
+/// Dividends and the tax withheld on them, from cash transactions
+///
+/// Other cash transactions are skipped. Columns: `account_id`, `date`,
+/// `ex_date`, `type`, `symbol`, `conid`, `isin`, `description`, `amount`,
+/// `currency`, `fx_rate_to_base`, `amount_in_base`, `transaction_id`.
+pub fn write_dividends<'a, W, I>(writer: W, transactions: I, options: &CsvOptions) -> Result<()>
+where
+    W: Write,
+    I: IntoIterator<Item = &'a CashTransaction>,
+{
+    let rows = transactions
+        .into_iter()
+        .filter(|tx| tx.is_dividend() || tx.is_withholding_tax());
+    write_rows(writer, DIVIDEND_COLUMNS, rows, options)
+}
+
+/// Form 8949 rows, one line each, in the order given
+///
+/// Columns, named as on the form: `Description`, `Date Acquired`,
+/// `Date Sold`, `Proceeds`, `Cost Basis`, `Code`, `Adjustment`, and
+/// `Gain or Loss`. `Code` and `Adjustment` are empty for rows without an
+/// adjustment. E-file tools usually want US dates: set
+/// [`CsvOptions::date_format`] to `%m/%d/%Y`.
+pub fn write_form_8949<'a, W, I>(writer: W, rows: I, options: &CsvOptions) -> Result<()>
+where
+    W: Write,
+    I: IntoIterator<Item = &'a Form8949Row>,
+{
+    write_rows(writer, FORM_8949_COLUMNS, rows, options)
+}
+
+/// Write Form 8949 rows into `dir`, one file per part of the form
+///
+/// `covered` tells whether the broker reported a row's basis to the IRS.
+/// The files are `form_8949_short_term_covered.csv` (box A),
+/// `form_8949_short_term_noncovered.csv` (box B),
+/// `form_8949_long_term_covered.csv` (box D), and
+/// `form_8949_long_term_noncovered.csv` (box E), each with the columns of
+/// [`write_form_8949`]. Parts without rows are not written. Returns the
+/// paths written.
+pub fn write_form_8949_parts<'a, I, F>(
+    dir: impl AsRef<Path>,
+    rows: I,
+    covered: F,
+    options: &CsvOptions,
+) -> Result<Vec<PathBuf>>
+where
+    I: IntoIterator<Item = &'a Form8949Row>,
+    F: Fn(&Form8949Row) -> bool,
+{
+    let mut parts: [Vec<&Form8949Row>; 4] = Default::default();
+    for row in rows {
+        let long_term = row.holding_period == HoldingPeriod::LongTerm;
+        parts[usize::from(long_term) * 2 + usize::from(!covered(row))].push(row);
+    }
+    let names = [
+        "short_term_covered",
+        "short_term_noncovered",
+        "long_term_covered",
+        "long_term_noncovered",
+    ];
+    let mut paths = Vec::new();
+    for (name, rows) in names.iter().zip(&parts) {
+        if rows.is_empty() {
+            continue;
+        }
+        let path = dir.as_ref().join(format!("form_8949_{}.csv", name));
+        write_form_8949(File::create(&path)?, rows.iter().copied(), options)?;
+        paths.push(path);
+    }
+    Ok(paths)
+}
+
+pub(super) fn write_rows<'a, T, W, I>(
+    mut writer: W,
+    columns: &[Column<T>],
+    rows: I,
+    options: &CsvOptions,
+) -> Result<()>
+where
+    T: 'a,
+    W: Write,
+    I: IntoIterator<Item = &'a T>,
+{
+    let mut line = String::new();
+    if options.header {
+        let names = columns.iter().map(|c| Cow::Borrowed(c.name));
+        write_line(&mut writer, &mut line, names, options)?;
+    }
+    for row in rows {
+        let fields = columns.iter().map(|c| format_cell((c.value)(row), options));
+        write_line(&mut writer, &mut line, fields, options)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+pub(super) fn write_line<'a, W: Write>(
+    writer: &mut W,
+    line: &mut String,
+    fields: impl Iterator<Item = Cow<'a, str>>,
+    options: &CsvOptions,
+) -> Result<()> {
+    line.clear();
+    for (i, field) in fields.enumerate() {
+        if i > 0 {
+            line.push(options.delimiter);
+        }
+        let needs_quotes = field
+            .chars()
+            .any(|c| c == options.delimiter || c == '"' || c == '\n' || c == '\r');
+        if needs_quotes {
+            line.push('"');
+            line.push_str(&field.replace('"', "\"\""));
+            line.push('"');
+        } else {
+            line.push_str(&field);
+        }
+    }
+    line.push_str("\r\n");
+    writer.write_all(line.as_bytes())?;
+    Ok(())
+}
+
+fn format_cell<'a>(cell: Cell<'a>, options: &CsvOptions) -> Cow<'a, str> {
+    match cell {
+        Cell::Text(Some(text)) => text,
+        Cell::Text(None) | Cell::Decimal(None) | Cell::Date(None) | Cell::DateTime(None) => {
+            Cow::Borrowed("")
+        }
+        Cell::Decimal(Some(value)) => {
+            let value = match options.decimal_places {
+                Some(places) => value.round_dp(places),
+                None => value,
+            };
+            let text = value.to_string();
+            if options.decimal_separator == '.' {
+                Cow::Owned(text)
+            } else {
+                Cow::Owned(text.replace('.', &options.decimal_separator.to_string()))
+            }
+        }
+        Cell::Date(Some(date)) => Cow::Owned(date.format(&options.date_format).to_string()),
+        Cell::DateTime(Some(dt)) => Cow::Owned(dt.format(&options.datetime_format).to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn csv_lines(out: Vec<u8>) -> Vec<String> {
+        String::from_utf8(out)
+            .unwrap()
+            .lines()
+            .map(str::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn test_trades_and_formatting() {
+        let xml = include_str!("../../tests/fixtures/activity_minimal.xml");
+        let statement = crate::parse_activity_flex(xml).unwrap();
+
+        let mut out = Vec::new();
+        write_trades(&mut out, &statement.trades, &CsvOptions::default()).unwrap();
+        let lines = csv_lines(out);
+        assert_eq!(lines.len(), 1 + statement.trades.len());
+        assert_eq!(lines[0].split(',').count(), TRADE_COLUMNS.len());
+        assert!(lines[1].starts_with("U1234567,2025-01-15,"));
+
+        let options = CsvOptions {
+            delimiter: ';',
+            header: false,
+            date_format: "%d.%m.%Y".to_string(),
+            decimal_places: Some(1),
+            decimal_separator: ',',
+            ..Default::default()
+        };
+        let mut out = Vec::new();
+        write_positions(&mut out, &statement.positions, &options).unwrap();
+        write_trades(&mut out, &statement.trades, &options).unwrap();
+        let lines = csv_lines(out);
+        assert_eq!(
+            lines.len(),
+            statement.positions.len() + statement.trades.len()
+        );
+        let trade = lines.last().unwrap();
+        assert!(trade.starts_with("U1234567;15.01.2025;"));
+        assert!(trade.contains(";185,5;"));
+        assert!(trade.contains(";-18551,0;"));
+    }
+
+    #[test]
+    fn test_daily_positions() {
+        let xml = include_str!("../../tests/fixtures/activity_backfill.xml");
+        let statements = crate::parse_activity_flex_all(xml).unwrap();
+        let twice: Vec<_> = statements.iter().chain(&statements).collect();
+
+        let mut out = Vec::new();
+        write_daily_positions(&mut out, twice, &CsvOptions::default()).unwrap();
+        let lines = csv_lines(out);
+        assert_eq!(
+            lines[0],
+            "date,account_id,conid,symbol,quantity,mark_price,position_value,\
+             fifo_pnl_unrealized,currency"
+        );
+        let positions: usize = statements.iter().map(|s| s.positions.len()).sum();
+        assert_eq!(lines.len(), 1 + positions);
+        let dates: Vec<&str> = lines[1..].iter().map(|l| &l[..10]).collect();
+        assert!(dates.windows(2).all(|w| w[0] <= w[1]));
+        assert!(lines[1].starts_with("2025-01-01,U1234567,"));
+    }
+
+    #[test]
+    fn test_provenance_manifest() {
+        let xml = include_str!("../../tests/fixtures/activity_backfill.xml");
+        let parsed = ParsedStatement::parse_all(xml, Some("backfill.xml")).unwrap();
+
+        let mut out = Vec::new();
+        write_provenance(&mut out, &parsed, &CsvOptions::default()).unwrap();
+        let lines = csv_lines(out);
+        assert_eq!(lines.len(), parsed.len() + 1);
+        assert_eq!(
+            lines[0],
+            "account_id,from_date,to_date,query_name,when_generated,source,sha256,parsed_at"
+        );
+        let fields: Vec<&str> = lines[1].split(',').collect();
+        assert_eq!(fields[3], "Last X Days Portfolio Feed");
+        assert_eq!(fields[5], "backfill.xml");
+        assert_eq!(fields[6], parsed[0].provenance.sha256);
+    }
+
+    #[test]
+    fn test_form_8949_parts() {
+        let date = |y, m, d| chrono::NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        let row = |acquired, adjustment: i64| Form8949Row {
+            description: "100 AAPL".to_string(),
+            date_acquired: acquired,
+            date_sold: date(2025, 6, 2),
+            proceeds: 15000.into(),
+            cost_basis: 16000.into(),
+            adjustment_codes: if adjustment == 0 {
+                Vec::new()
+            } else {
+                vec![crate::analytics::AdjustmentCode::WashSale]
+            },
+            adjustment: adjustment.into(),
+            gain_or_loss: (adjustment - 1000).into(),
+            holding_period: HoldingPeriod::between(acquired, date(2025, 6, 2)),
+            currency: "USD".to_string(),
+        };
+        let rows = [
+            row(date(2025, 3, 3), 400),
+            row(date(2010, 4, 1), 0),
+            row(date(2023, 4, 3), 0),
+        ];
+        let dir = std::env::temp_dir().join(format!("ib-flex-8949-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let options = CsvOptions {
+            date_format: "%m/%d/%Y".to_string(),
+            ..Default::default()
+        };
+        let paths = write_form_8949_parts(
+            &dir,
+            &rows,
+            |r| r.date_acquired >= date(2011, 1, 1),
+            &options,
+        )
+        .unwrap();
+        let names: Vec<_> = paths
+            .iter()
+            .map(|p| p.file_name().unwrap().to_str().unwrap())
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "form_8949_short_term_covered.csv",
+                "form_8949_long_term_covered.csv",
+                "form_8949_long_term_noncovered.csv"
+            ]
+        );
+
+        let lines = csv_lines(std::fs::read(&paths[0]).unwrap());
+        assert_eq!(
+            lines,
+            [
+                "Description,Date Acquired,Date Sold,Proceeds,Cost Basis,Code,Adjustment,Gain or Loss",
+                "100 AAPL,03/03/2025,06/02/2025,15000,16000,W,400,-600"
+            ]
+        );
+        let lines = csv_lines(std::fs::read(&paths[1]).unwrap());
+        assert_eq!(
+            lines[1],
+            "100 AAPL,04/03/2023,06/02/2025,15000,16000,,,-1000"
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_dividends_and_quoting() {
+        let xml = include_str!("../../tests/fixtures/activity_cash.xml");
+        let statement = crate::parse_activity_flex(xml).unwrap();
+
+        let mut out = Vec::new();
+        write_dividends(
+            &mut out,
+            &statement.cash_transactions,
+            &CsvOptions::default(),
+        )
+        .unwrap();
+        let lines = csv_lines(out);
+        let expected = statement
+            .cash_transactions
+            .iter()
+            .filter(|tx| tx.is_dividend() || tx.is_withholding_tax())
+            .count();
+        assert!(expected > 0);
+        assert_eq!(lines.len(), 1 + expected);
+
+        let mut out = Vec::new();
+        let mut line = String::new();
+        let fields = ["a,b", "say \"hi\"", "plain"].map(Cow::Borrowed);
+        write_line(
+            &mut out,
+            &mut line,
+            fields.into_iter(),
+            &CsvOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "\"a,b\",\"say \"\"hi\"\"\",plain\r\n"
+        );
+    }
+}
@@ -0,0 +1,350 @@
+//! Diesel table definitions and row structs
+//!
+//! [`schema`] declares the `trades`, `positions`, and `cash_transactions`
+//! tables of the Postgres [`SCHEMA`](super::postgres::SCHEMA) for Diesel,
+//! and [`TradeRow`], [`PositionRow`], and [`CashTransactionRow`] mirror
+//! them. Each row struct is `Insertable`, `Queryable`, and `AsChangeset`,
+//! and converts from the parsed record with `From`, keyed the same way as
+//! the sqlx export, so the two write the same rows.
+//!
+//! Requires the `diesel` feature, which supports Postgres. Create the
+//! tables with the SQL of the `postgres` feature or your own migrations.
+//!
+//! # Example
+//! ```
+//! use diesel::pg::Pg;
+//! use diesel::prelude::*;
+//! use ib_flex::export::diesel::{schema::trades, TradeRow};
+//! use ib_flex::parse_activity_flex;
+//!
+//! let xml = include_str!("../../tests/fixtures/activity_minimal.xml");
+//! let statement = parse_activity_flex(xml).unwrap();
+//! let rows: Vec<TradeRow> = statement.trades.iter().map(TradeRow::from).collect();
+//!
+//! // Upsert on the row key, as `conn.execute(...)` would run it
+//! let upsert = diesel::insert_into(trades::table)
+//!     .values(&rows[0])
+//!     .on_conflict(trades::row_key)
+//!     .do_update()
+//!     .set(&rows[0]);
+//! let sql = diesel::debug_query::<Pg, _>(&upsert).to_string();
+//! assert!(sql.starts_with("INSERT INTO \"trades\""));
+//! ```
+
+use chrono::{NaiveDate, NaiveDateTime};
+use diesel::prelude::{AsChangeset, Insertable, Queryable, Selectable};
+use rust_decimal::Decimal;
+
+use super::records::{cash_key, position_key, trade_key};
+use crate::types::{CashTransaction, Position, Trade};
+
+/// Diesel `table!` definitions, with the columns of the Postgres schema
+#[allow(missing_docs)]
+pub mod schema {
+    diesel::table! {
+        /// Trades, keyed by `row_key`
+        trades (row_key) {
+            row_key -> Text,
+            transaction_id -> Nullable<Text>,
+            account_id -> Text,
+            conid -> BigInt,
+            trade_id -> Nullable<Text>,
+            order_id -> Nullable<Text>,
+            trade_date -> Nullable<Date>,
+            trade_time -> Nullable<Timestamp>,
+            settle_date -> Nullable<Date>,
+            buy_sell -> Nullable<Text>,
+            open_close -> Nullable<Text>,
+            quantity -> Nullable<Numeric>,
+            price -> Nullable<Numeric>,
+            proceeds -> Nullable<Numeric>,
+            commission -> Nullable<Numeric>,
+            taxes -> Nullable<Numeric>,
+            net_cash -> Nullable<Numeric>,
+            fifo_pnl_realized -> Nullable<Numeric>,
+            currency -> Text,
+            fx_rate_to_base -> Nullable<Numeric>,
+            level_of_detail -> Nullable<Text>,
+            notes -> Nullable<Text>,
+        }
+    }
+
+    diesel::table! {
+        /// Open positions, keyed by `row_key`
+        positions (row_key) {
+            row_key -> Text,
+            account_id -> Text,
+            conid -> BigInt,
+            report_date -> Date,
+            side -> Nullable<Text>,
+            quantity -> Numeric,
+            mark_price -> Numeric,
+            position_value -> Numeric,
+            cost_basis_price -> Nullable<Numeric>,
+            cost_basis_money -> Nullable<Numeric>,
+            fifo_pnl_unrealized -> Nullable<Numeric>,
+            percent_of_nav -> Nullable<Numeric>,
+            currency -> Text,
+            fx_rate_to_base -> Nullable<Numeric>,
+            open_date_time -> Nullable<Timestamp>,
+            level_of_detail -> Nullable<Text>,
+        }
+    }
+
+    diesel::table! {
+        /// Cash transactions, keyed by `row_key`
+        cash_transactions (row_key) {
+            row_key -> Text,
+            transaction_id -> Nullable<Text>,
+            account_id -> Text,
+            conid -> Nullable<BigInt>,
+            #[sql_name = "type"]
+            transaction_type -> Nullable<Text>,
+            description -> Nullable<Text>,
+            date -> Nullable<Date>,
+            settle_date -> Nullable<Date>,
+            ex_date -> Nullable<Date>,
+            amount -> Numeric,
+            currency -> Text,
+            fx_rate_to_base -> Nullable<Numeric>,
+            code -> Nullable<Text>,
+            action_id -> Nullable<Text>,
+            trade_id -> Nullable<Text>,
+            level_of_detail -> Nullable<Text>,
+        }
+    }
+}
+
+/// A row of `trades`
+#[derive(Debug, Clone, PartialEq, Eq, Queryable, Selectable, Insertable, AsChangeset)]
+#[diesel(table_name = schema::trades, primary_key(row_key), treat_none_as_null = true)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct TradeRow {
+    /// IB's transaction ID, or the fields identifying the trade
+    pub row_key: String,
+    /// IB transaction ID
+    pub transaction_id: Option<String>,
+    /// IB account number
+    pub account_id: String,
+    /// IB contract ID
+    pub conid: i64,
+    /// IB trade ID
+    pub trade_id: Option<String>,
+    /// IB order ID
+    pub order_id: Option<String>,
+    /// Trade date
+    pub trade_date: Option<NaiveDate>,
+    /// Execution date/time
+    pub trade_time: Option<NaiveDateTime>,
+    /// Settlement date
+    pub settle_date: Option<NaiveDate>,
+    /// `BUY` or `SELL`
+    pub buy_sell: Option<String>,
+    /// Open/close indicator
+    pub open_close: Option<String>,
+    /// Quantity, negative for sales
+    pub quantity: Option<Decimal>,
+    /// Execution price
+    pub price: Option<Decimal>,
+    /// Proceeds
+    pub proceeds: Option<Decimal>,
+    /// Commission
+    pub commission: Option<Decimal>,
+    /// Taxes
+    pub taxes: Option<Decimal>,
+    /// Net cash
+    pub net_cash: Option<Decimal>,
+    /// FIFO realized P&L
+    pub fifo_pnl_realized: Option<Decimal>,
+    /// Trade currency
+    pub currency: String,
+    /// FX rate to the base currency
+    pub fx_rate_to_base: Option<Decimal>,
+    /// Level of detail (`EXECUTION`, `ORDER`, ...)
+    pub level_of_detail: Option<String>,
+    /// Trade notes
+    pub notes: Option<String>,
+}
+
+impl From<&Trade> for TradeRow {
+    fn from(t: &Trade) -> Self {
+        TradeRow {
+            row_key: trade_key(t),
+            transaction_id: t.transaction_id.clone(),
+            account_id: t.account_id.to_string(),
+            conid: t.conid.get() as i64,
+            trade_id: t.trade_id.as_ref().map(|id| id.to_string()),
+            order_id: t.ib_order_id.as_ref().map(|id| id.to_string()),
+            trade_date: t.trade_date,
+            trade_time: t.trade_time,
+            settle_date: t.settle_date,
+            buy_sell: t.buy_sell.as_ref().map(|v| v.to_string()),
+            open_close: t.open_close.as_ref().map(|v| v.to_string()),
+            quantity: t.quantity,
+            price: t.price,
+            proceeds: t.proceeds,
+            commission: t.commission,
+            taxes: t.taxes,
+            net_cash: t.net_cash,
+            fifo_pnl_realized: t.fifo_pnl_realized,
+            currency: t.currency.clone(),
+            fx_rate_to_base: t.fx_rate_to_base,
+            level_of_detail: t.level_of_detail.as_ref().map(|v| v.to_string()),
+            notes: t.notes.clone(),
+        }
+    }
+}
+
+/// A row of `positions`
+#[derive(Debug, Clone, PartialEq, Eq, Queryable, Selectable, Insertable, AsChangeset)]
+#[diesel(table_name = schema::positions, primary_key(row_key), treat_none_as_null = true)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct PositionRow {
+    /// The fields identifying the position, joined with `|`
+    pub row_key: String,
+    /// IB account number
+    pub account_id: String,
+    /// IB contract ID
+    pub conid: i64,
+    /// Date of the position
+    pub report_date: NaiveDate,
+    /// `Long` or `Short`
+    pub side: Option<String>,
+    /// Quantity held
+    pub quantity: Decimal,
+    /// Mark price
+    pub mark_price: Decimal,
+    /// Position value
+    pub position_value: Decimal,
+    /// Cost basis per unit
+    pub cost_basis_price: Option<Decimal>,
+    /// Total cost basis
+    pub cost_basis_money: Option<Decimal>,
+    /// FIFO unrealized P&L
+    pub fifo_pnl_unrealized: Option<Decimal>,
+    /// Percent of NAV
+    pub percent_of_nav: Option<Decimal>,
+    /// Position currency
+    pub currency: String,
+    /// FX rate to the base currency
+    pub fx_rate_to_base: Option<Decimal>,
+    /// When the position was opened
+    pub open_date_time: Option<NaiveDateTime>,
+    /// Level of detail (`SUMMARY`, `LOT`)
+    pub level_of_detail: Option<String>,
+}
+
+impl From<&Position> for PositionRow {
+    fn from(p: &Position) -> Self {
+        PositionRow {
+            row_key: position_key(p),
+            account_id: p.account_id.to_string(),
+            conid: p.conid.get() as i64,
+            report_date: p.report_date,
+            side: p.side.as_ref().map(|v| v.to_string()),
+            quantity: p.quantity,
+            mark_price: p.mark_price,
+            position_value: p.position_value,
+            cost_basis_price: p.cost_basis_price,
+            cost_basis_money: p.cost_basis_money,
+            fifo_pnl_unrealized: p.fifo_pnl_unrealized,
+            percent_of_nav: p.percent_of_nav,
+            currency: p.currency.clone(),
+            fx_rate_to_base: p.fx_rate_to_base,
+            open_date_time: p.open_date_time,
+            level_of_detail: p.level_of_detail.as_ref().map(|v| v.to_string()),
+        }
+    }
+}
+
+/// A row of `cash_transactions`
+#[derive(Debug, Clone, PartialEq, Eq, Queryable, Selectable, Insertable, AsChangeset)]
+#[diesel(table_name = schema::cash_transactions, primary_key(row_key), treat_none_as_null = true)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct CashTransactionRow {
+    /// IB's transaction ID, or the fields identifying the transaction
+    pub row_key: String,
+    /// IB transaction ID
+    pub transaction_id: Option<String>,
+    /// IB account number
+    pub account_id: String,
+    /// IB contract ID of the security, if any
+    pub conid: Option<i64>,
+    /// Transaction type
+    pub transaction_type: Option<String>,
+    /// Description
+    pub description: Option<String>,
+    /// Transaction date
+    pub date: Option<NaiveDate>,
+    /// Settlement date
+    pub settle_date: Option<NaiveDate>,
+    /// Ex-dividend date
+    pub ex_date: Option<NaiveDate>,
+    /// Amount
+    pub amount: Decimal,
+    /// Currency
+    pub currency: String,
+    /// FX rate to the base currency
+    pub fx_rate_to_base: Option<Decimal>,
+    /// Transaction code
+    pub code: Option<String>,
+    /// Corporate action ID
+    pub action_id: Option<String>,
+    /// Related trade ID
+    pub trade_id: Option<String>,
+    /// Level of detail
+    pub level_of_detail: Option<String>,
+}
+
+impl From<&CashTransaction> for CashTransactionRow {
+    fn from(c: &CashTransaction) -> Self {
+        CashTransactionRow {
+            row_key: cash_key(c),
+            transaction_id: c.transaction_id.clone(),
+            account_id: c.account_id.to_string(),
+            conid: c.conid.as_deref().and_then(|id| id.parse().ok()),
+            transaction_type: c.transaction_type.as_ref().map(|v| v.to_string()),
+            description: c.description.clone(),
+            date: c.date,
+            settle_date: c.settle_date,
+            ex_date: c.ex_date,
+            amount: c.amount,
+            currency: c.currency.clone(),
+            fx_rate_to_base: c.fx_rate_to_base,
+            code: c.code.clone(),
+            action_id: c.action_id.clone(),
+            trade_id: c.trade_id.as_ref().map(|id| id.to_string()),
+            level_of_detail: c.level_of_detail.as_ref().map(|v| v.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diesel::pg::Pg;
+
+    #[test]
+    fn test_rows_match_records() {
+        let xml = include_str!("../../tests/fixtures/activity_cash.xml");
+        let statement = crate::parse_activity_flex(xml).unwrap();
+
+        let cash = &statement.cash_transactions[0];
+        let row = CashTransactionRow::from(cash);
+        assert_eq!(row.row_key, cash_key(cash));
+        assert_eq!(row.amount, cash.amount);
+        assert_eq!(
+            row.transaction_type,
+            cash.transaction_type.as_ref().map(|t| t.to_string())
+        );
+
+        let insert = diesel::insert_into(schema::cash_transactions::table).values(&row);
+        let sql = diesel::debug_query::<Pg, _>(&insert).to_string();
+        assert!(sql.contains("\"type\""));
+
+        let xml = include_str!("../../tests/fixtures/activity_daily_portfolio.xml");
+        let statement = crate::parse_activity_flex(xml).unwrap();
+        let position = PositionRow::from(&statement.positions[0]);
+        assert_eq!(position.report_date, statement.positions[0].report_date);
+    }
+}
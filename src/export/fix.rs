@@ -0,0 +1,273 @@
+//! FIX execution report export
+//!
+//! [`write_trades`] writes trade executions as FIX 4.2 ExecutionReport
+//! (`35=8`) messages, one filled order per message, to replay FLEX history
+//! into an OMS, EMS, or FIX engine test environment. Each message is
+//! followed by a newline, which FIX log readers and replay tools accept.
+//!
+//! Messages carry the standard header (`8`, `9`, `35`, `49`, `56`, `34`,
+//! `52`), the order and execution IDs (`37`, `17`), the fill (`150=2`,
+//! `39=2`, `54`, `38`, `32`, `31`, `14`, `151`, `6`), the instrument (`55`,
+//! `167` where FIX has a type for it, and for derivatives `200`, `205`,
+//! `201`, `202`, `231`), the account, currency, and commission (`1`, `15`,
+//! `12`, `13=3`), the dates (`60`, `75`, `64`), and the `10` checksum. The sending time is the
+//! execution time, so a replay keeps the original timeline. FIX times are
+//! UTC, but IB reports them in the statement's timezone, and they are
+//! written as reported.
+//!
+//! # Example
+//! ```
+//! use ib_flex::export::fix::{write_trades, FixOptions};
+//! use ib_flex::parse_activity_flex;
+//!
+//! let xml = include_str!("../../tests/fixtures/activity_minimal.xml");
+//! let statement = parse_activity_flex(xml).unwrap();
+//!
+//! let mut out = Vec::new();
+//! write_trades(&mut out, &statement.trades.items, &FixOptions::default()).unwrap();
+//! let fix = String::from_utf8(out).unwrap();
+//! assert!(fix.starts_with("8=FIX.4.2\x019="));
+//! assert!(fix.contains("\x0135=8\x01"));
+//! assert!(fix.contains("\x0155=AAPL\x01"));
+//! ```
+
+use std::fmt::Write as _;
+use std::io::Write;
+
+use chrono::NaiveDateTime;
+use rust_decimal::Decimal;
+
+use super::journal::is_execution;
+use crate::types::{AssetCategory, Dated, PutCall, Trade};
+use crate::Result;
+
+/// Session fields and framing for the FIX writer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FixOptions {
+    /// `SenderCompID` (49) (default `IB`)
+    pub sender_comp_id: String,
+    /// `TargetCompID` (56) (default `CLIENT`)
+    pub target_comp_id: String,
+    /// `MsgSeqNum` (34) of the first message, counting up (default 1)
+    pub first_seq_num: u64,
+    /// Field separator (default SOH, `\x01`); `|` makes logs readable, but
+    /// FIX engines expect SOH
+    pub separator: char,
+}
+
+impl Default for FixOptions {
+    fn default() -> Self {
+        FixOptions {
+            sender_comp_id: "IB".to_string(),
+            target_comp_id: "CLIENT".to_string(),
+            first_seq_num: 1,
+            separator: '\x01',
+        }
+    }
+}
+
+/// Write the executions among `trades` as FIX 4.2 ExecutionReports
+///
+/// Trades without a quantity, price, or date, and rows that summarize
+/// executions rather than report one, are skipped.
+pub fn write_trades<'a, W, I>(mut writer: W, trades: I, options: &FixOptions) -> Result<()>
+where
+    W: Write,
+    I: IntoIterator<Item = &'a Trade>,
+{
+    let mut out = String::new();
+    let mut seq_num = options.first_seq_num;
+    for trade in trades {
+        let Some(body) = execution_report(trade, seq_num, options) else {
+            continue;
+        };
+        out.push_str(&frame(&body, options.separator));
+        out.push('\n');
+        seq_num += 1;
+    }
+    writer.write_all(out.as_bytes())?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Fields of one message from `35` through the last body field, each
+/// followed by the separator
+fn execution_report(t: &Trade, seq_num: u64, options: &FixOptions) -> Option<String> {
+    if !is_execution(t) {
+        return None;
+    }
+    let quantity = t.quantity.filter(|q| !q.is_zero())?;
+    let price = t.price?;
+    let time = t.trade_time.or_else(|| t.date()?.and_hms_opt(0, 0, 0))?;
+    let qty = quantity.abs().normalize();
+    let price = price.normalize();
+
+    let mut body = Fields::new(options.separator);
+    body.push(35, "8");
+    body.push(49, &options.sender_comp_id);
+    body.push(56, &options.target_comp_id);
+    body.push(34, seq_num);
+    body.push(52, timestamp(time));
+    body.push(1, t.account_id.as_str());
+    let order_id = t.ib_order_id.as_deref().or(t.trade_id.as_deref());
+    body.push(37, order_id.unwrap_or("NONE"));
+    let exec_id = t.exec_id.as_deref().or(t.transaction_id.as_deref());
+    body.push(17, exec_id.unwrap_or("NONE"));
+    body.push(20, "0");
+    body.push(150, "2");
+    body.push(39, "2");
+    body.push(55, &t.symbol);
+    if let Some(security_type) = security_type(&t.asset_category) {
+        body.push(167, security_type);
+    }
+    if let Some(expiry) = t.expiry {
+        body.push(200, expiry.format("%Y%m"));
+        body.push(205, expiry.format("%d"));
+    }
+    match t.put_call {
+        Some(PutCall::Put) => body.push(201, "0"),
+        Some(PutCall::Call) => body.push(201, "1"),
+        _ => {}
+    }
+    if let Some(strike) = t.strike.filter(|_| t.asset_category.is_option()) {
+        body.push(202, strike.normalize());
+    }
+    if let Some(multiplier) = t.multiplier.filter(|m| *m != Decimal::ONE) {
+        body.push(231, multiplier.normalize());
+    }
+    let side = if quantity.is_sign_positive() {
+        "1"
+    } else {
+        "2"
+    };
+    body.push(54, side);
+    body.push(38, qty);
+    body.push(32, qty);
+    body.push(31, price);
+    body.push(151, "0");
+    body.push(14, qty);
+    body.push(6, price);
+    body.push(15, &t.currency);
+    if let Some(commission) = t.commission.filter(|c| !c.is_zero()) {
+        body.push(12, commission.abs().normalize());
+        body.push(13, "3");
+    }
+    body.push(60, timestamp(time));
+    body.push(75, time.format("%Y%m%d"));
+    if let Some(settle) = t.settle_date {
+        body.push(64, settle.format("%Y%m%d"));
+    }
+    Some(body.0)
+}
+
+/// `tag=value` pairs, each followed by the separator
+struct Fields(String, char);
+
+impl Fields {
+    fn new(separator: char) -> Self {
+        Fields(String::new(), separator)
+    }
+
+    fn push(&mut self, tag: u32, value: impl std::fmt::Display) {
+        let _ = write!(self.0, "{}={}{}", tag, value, self.1);
+    }
+}
+
+/// Wrap a body in the `8`/`9` prefix and the `10` checksum trailer
+fn frame(body: &str, separator: char) -> String {
+    let mut message = format!(
+        "8=FIX.4.2{sep}9={}{sep}{}",
+        body.len(),
+        body,
+        sep = separator
+    );
+    let checksum = message.bytes().map(u32::from).sum::<u32>() % 256;
+    let _ = write!(message, "10={:03}{}", checksum, separator);
+    message
+}
+
+/// `UTCTimestamp` format, from IB's local time
+fn timestamp(time: NaiveDateTime) -> impl std::fmt::Display {
+    time.format("%Y%m%d-%H:%M:%S")
+}
+
+/// `SecurityType` (167) of an asset category, if FIX 4.2 has one
+fn security_type(category: &AssetCategory) -> Option<&'static str> {
+    Some(match category {
+        AssetCategory::Stock => "CS",
+        AssetCategory::Option | AssetCategory::Warrant => "OPT",
+        AssetCategory::Future | AssetCategory::SingleStockFuture => "FUT",
+        AssetCategory::FutureOption => "FOP",
+        AssetCategory::Cash => "FOR",
+        AssetCategory::Bond => "CORP",
+        AssetCategory::Bill => "TBILL",
+        AssetCategory::Fund => "MF",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tags and values of each message, checking the length and checksum
+    fn messages(fix: &str, separator: char) -> Vec<Vec<(u32, String)>> {
+        fix.lines()
+            .map(|message| {
+                let fields: Vec<(u32, String)> = message
+                    .split_terminator(separator)
+                    .map(|field| {
+                        let (tag, value) = field.split_once('=').unwrap();
+                        (tag.parse().unwrap(), value.to_string())
+                    })
+                    .collect();
+                let body_start = message.find("35=").unwrap();
+                let trailer = message.rfind("10=").unwrap();
+                assert_eq!(fields[1].1, (trailer - body_start).to_string());
+                let sum: u32 = message[..trailer].bytes().map(u32::from).sum();
+                assert_eq!(fields.last().unwrap().1, format!("{:03}", sum % 256));
+                fields
+            })
+            .collect()
+    }
+
+    fn value(fields: &[(u32, String)], tag: u32) -> Option<&str> {
+        fields
+            .iter()
+            .find(|(t, _)| *t == tag)
+            .map(|(_, v)| v.as_str())
+    }
+
+    #[test]
+    fn test_execution_reports() {
+        let xml = include_str!("../../tests/fixtures/activity_daily_portfolio.xml");
+        let statement = crate::parse_activity_flex(xml).unwrap();
+        let options = FixOptions {
+            first_seq_num: 100,
+            ..Default::default()
+        };
+        let mut out = Vec::new();
+        write_trades(&mut out, &statement.trades.items, &options).unwrap();
+        let messages = messages(std::str::from_utf8(&out).unwrap(), '\x01');
+        assert_eq!(value(&messages[0], 34), Some("100"));
+
+        let sell = messages
+            .iter()
+            .find(|m| value(m, 55) == Some("MSFT"))
+            .expect("the MSFT sale");
+        assert_eq!(value(sell, 35), Some("8"));
+        assert_eq!(value(sell, 167), Some("CS"));
+        assert_eq!(value(sell, 54), Some("2"));
+        assert_eq!(value(sell, 32), Some("50"));
+        assert_eq!(value(sell, 31), Some("420.25"));
+        assert_eq!(value(sell, 12), Some("0.75"));
+
+        let option = messages
+            .iter()
+            .find(|m| value(m, 167) == Some("OPT"))
+            .expect("the SPY option");
+        assert_eq!(value(option, 54), Some("1"));
+        assert_eq!(value(option, 231), Some("100"));
+        assert!(value(option, 200).is_some() && value(option, 202).is_some());
+    }
+}
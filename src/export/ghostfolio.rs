@@ -0,0 +1,335 @@
+//! Activity export for Ghostfolio-style trackers
+//!
+//! [`activities`] turns statements into the activities that self-hosted
+//! portfolio trackers such as Ghostfolio and Wealthfolio import: a type,
+//! symbol, quantity, unit price, fee, currency, and date each.
+//! [`write_csv`] writes them as CSV and, with the `json` feature,
+//! `write_json` as an `{"activities": [...]}` import file.
+//!
+//! | IB row | Type | Quantity × unit price | Fee |
+//! |---|---|---|---|
+//! | Trade | `BUY` or `SELL` | Shares and price | Commission and taxes |
+//! | Dividend or payment in lieu | `DIVIDEND` | 1 × amount | 0 |
+//! | Interest received | `INTEREST` | 1 × amount | 0 |
+//! | Fee, withholding tax, interest paid, or dividend reversal | `FEE` | 0 | Amount |
+//!
+//! Deposits, withdrawals, refunds, FX conversions, and other cash
+//! transactions have no activity type and are skipped. Shares of options
+//! and futures are contracts times the multiplier. Rows without a symbol,
+//! such as account fees, use their currency as the symbol.
+//!
+//! Each activity carries the asset class and subclass of its security,
+//! from IB's asset category, and a data source: `YAHOO` for stocks, funds,
+//! bonds, commodities, and crypto, `MANUAL` otherwise. IB symbols match
+//! Yahoo's for US listings; others may need a suffix, such as `.TO`.
+//!
+//! # Example
+//! ```
+//! use ib_flex::export::csv::CsvOptions;
+//! use ib_flex::export::ghostfolio::{activities, write_csv, ActivityType};
+//! use ib_flex::parse_activity_flex;
+//!
+//! let xml = include_str!("../../tests/fixtures/activity_minimal.xml");
+//! let statement = parse_activity_flex(xml).unwrap();
+//!
+//! let activities = activities([&statement]);
+//! assert_eq!(activities[0].activity_type, ActivityType::Buy);
+//! assert_eq!(activities[0].asset_sub_class, Some("STOCK"));
+//!
+//! let mut out = Vec::new();
+//! write_csv(&mut out, &activities, &CsvOptions::default()).unwrap();
+//! assert!(out.starts_with(b"date,type,symbol,quantity,unitPrice,fee,currency,"));
+//! ```
+
+use std::io::Write;
+
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+
+use super::columns::ACTIVITY_COLUMNS;
+use super::csv::{write_rows, CsvOptions};
+use super::journal::is_execution;
+use crate::types::{
+    ActivityFlexStatement, AssetCategory, CashTransaction, Dated, SubCategory, Trade,
+};
+use crate::Result;
+
+/// Kind of an [`Activity`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ActivityType {
+    /// Shares bought
+    Buy,
+    /// Shares sold
+    Sell,
+    /// Dividend received
+    Dividend,
+    /// Interest received
+    Interest,
+    /// Fee or tax paid
+    Fee,
+}
+
+impl ActivityType {
+    /// Name of the type in import files, as `BUY`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ActivityType::Buy => "BUY",
+            ActivityType::Sell => "SELL",
+            ActivityType::Dividend => "DIVIDEND",
+            ActivityType::Interest => "INTEREST",
+            ActivityType::Fee => "FEE",
+        }
+    }
+}
+
+impl std::fmt::Display for ActivityType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// One activity for a portfolio tracker
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Activity {
+    /// Trade or payment date
+    pub date: NaiveDate,
+    /// What happened
+    pub activity_type: ActivityType,
+    /// Security symbol, or the currency for rows without one
+    pub symbol: String,
+    /// Shares, without a sign
+    pub quantity: Decimal,
+    /// Price per share, without a sign
+    pub unit_price: Decimal,
+    /// Fees and taxes, without a sign
+    pub fee: Decimal,
+    /// Currency of the price and fee
+    pub currency: String,
+    /// Where the tracker looks up prices: `YAHOO` or `MANUAL`
+    pub data_source: &'static str,
+    /// Asset class, as `EQUITY`; `None` when IB's category has none
+    pub asset_class: Option<&'static str>,
+    /// Asset subclass, as `ETF`; `None` when IB's category has none
+    pub asset_sub_class: Option<&'static str>,
+    /// IB's description of the row
+    pub comment: Option<String>,
+}
+
+/// The activities of `statements`, trades first in each statement, then
+/// cash transactions
+pub fn activities<'a, I>(statements: I) -> Vec<Activity>
+where
+    I: IntoIterator<Item = &'a ActivityFlexStatement>,
+{
+    let mut activities = Vec::new();
+    for statement in statements {
+        activities.extend(statement.trades.iter().filter_map(trade_activity));
+        activities.extend(statement.cash_transactions.iter().filter_map(cash_activity));
+    }
+    activities
+}
+
+/// Activities as CSV, one row each
+///
+/// Columns: `date`, `type`, `symbol`, `quantity`, `unitPrice`, `fee`,
+/// `currency`, `dataSource`, `assetClass`, `assetSubClass`, `comment`.
+pub fn write_csv<'a, W, I>(writer: W, activities: I, options: &CsvOptions) -> Result<()>
+where
+    W: Write,
+    I: IntoIterator<Item = &'a Activity>,
+{
+    write_rows(writer, ACTIVITY_COLUMNS, activities, options)
+}
+
+/// Activities as a JSON import file, `{"activities": [...]}`
+///
+/// Fields are named as the CSV columns, with the type as `type`, dates as
+/// `YYYY-MM-DD`, and amounts as numbers. Requires the `json` feature.
+#[cfg(feature = "json")]
+pub fn write_json<'a, W, I>(mut writer: W, activities: I) -> Result<()>
+where
+    W: Write,
+    I: IntoIterator<Item = &'a Activity>,
+{
+    use rust_decimal::prelude::ToPrimitive;
+    use serde_json::json;
+
+    let number = |value: Decimal| json!(value.to_f64());
+    let activities: Vec<_> = activities
+        .into_iter()
+        .map(|a| {
+            json!({
+                "date": a.date.to_string(),
+                "type": a.activity_type.as_str(),
+                "symbol": a.symbol,
+                "quantity": number(a.quantity),
+                "unitPrice": number(a.unit_price),
+                "fee": number(a.fee),
+                "currency": a.currency,
+                "dataSource": a.data_source,
+                "assetClass": a.asset_class,
+                "assetSubClass": a.asset_sub_class,
+                "comment": a.comment,
+            })
+        })
+        .collect();
+    serde_json::to_writer_pretty(&mut writer, &json!({ "activities": activities }))
+        .map_err(|e| crate::ParseError::JsonError(e.to_string()))?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Asset class and subclass for an IB asset category
+fn asset_classes(
+    category: &AssetCategory,
+    sub_category: Option<&SubCategory>,
+) -> (Option<&'static str>, Option<&'static str>) {
+    let (class, sub_class) = match category {
+        AssetCategory::Stock if sub_category == Some(&SubCategory::Etf) => ("EQUITY", "ETF"),
+        AssetCategory::Stock => ("EQUITY", "STOCK"),
+        AssetCategory::Fund => ("EQUITY", "MUTUALFUND"),
+        AssetCategory::Bond | AssetCategory::Bill => ("FIXED_INCOME", "BOND"),
+        AssetCategory::Commodity => ("COMMODITY", "COMMODITY"),
+        AssetCategory::Cryptocurrency => ("LIQUIDITY", "CRYPTOCURRENCY"),
+        AssetCategory::Cash => ("LIQUIDITY", "CASH"),
+        _ => return (None, None),
+    };
+    (Some(class), Some(sub_class))
+}
+
+fn data_source(asset_class: Option<&str>) -> &'static str {
+    match asset_class {
+        Some("LIQUIDITY") | None => "MANUAL",
+        Some(_) => "YAHOO",
+    }
+}
+
+fn trade_activity(t: &Trade) -> Option<Activity> {
+    if !is_execution(t) || t.asset_category == AssetCategory::Cash {
+        return None;
+    }
+    let (date, quantity, price) = (t.date()?, t.quantity?, t.price?);
+    if quantity.is_zero() {
+        return None;
+    }
+    let (asset_class, asset_sub_class) = asset_classes(&t.asset_category, t.sub_category.as_ref());
+    Some(Activity {
+        date,
+        activity_type: if quantity.is_sign_positive() {
+            ActivityType::Buy
+        } else {
+            ActivityType::Sell
+        },
+        symbol: t.symbol.clone(),
+        quantity: (quantity * t.multiplier.unwrap_or(Decimal::ONE)).abs(),
+        unit_price: price.abs(),
+        fee: (t.commission.unwrap_or_default() + t.taxes.unwrap_or_default()).abs(),
+        currency: t.currency.clone(),
+        data_source: data_source(asset_class),
+        asset_class,
+        asset_sub_class,
+        comment: t.description.clone(),
+    })
+}
+
+fn cash_activity(c: &CashTransaction) -> Option<Activity> {
+    let date = c.date()?;
+    let received = c.amount.is_sign_positive() && !c.amount.is_zero();
+    let activity_type = if c.is_dividend() && received {
+        ActivityType::Dividend
+    } else if c.is_interest() && received {
+        ActivityType::Interest
+    } else if !received
+        && (c.is_dividend() || c.is_interest() || c.is_fee() || c.is_withholding_tax())
+    {
+        ActivityType::Fee
+    } else {
+        return None;
+    };
+    let symbol = c.symbol.as_deref().filter(|s| !s.is_empty());
+    let (asset_class, asset_sub_class) = match (symbol, &c.asset_category) {
+        (Some(_), Some(category)) => asset_classes(category, None),
+        _ => (None, None),
+    };
+    let amount = c.amount.abs();
+    let (quantity, unit_price, fee) = if activity_type == ActivityType::Fee {
+        (Decimal::ZERO, Decimal::ZERO, amount)
+    } else {
+        (Decimal::ONE, amount, Decimal::ZERO)
+    };
+    Some(Activity {
+        date,
+        activity_type,
+        symbol: symbol.unwrap_or(&c.currency).to_string(),
+        quantity,
+        unit_price,
+        fee,
+        currency: c.currency.clone(),
+        data_source: data_source(asset_class),
+        asset_class,
+        asset_sub_class,
+        comment: c.description.clone(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trade_activities() {
+        let xml = include_str!("../../tests/fixtures/activity_daily_portfolio.xml");
+        let statement = crate::parse_activity_flex(xml).unwrap();
+        let activities = activities([&statement]);
+
+        let sell = activities.iter().find(|a| a.symbol == "MSFT").unwrap();
+        assert_eq!(sell.activity_type, ActivityType::Sell);
+        assert_eq!(sell.quantity, Decimal::from(50));
+        assert_eq!(sell.unit_price.to_string(), "420.25");
+        assert_eq!(sell.fee.to_string(), "0.75");
+        assert_eq!(sell.data_source, "YAHOO");
+
+        let option = activities
+            .iter()
+            .find(|a| a.symbol.starts_with("SPY"))
+            .unwrap();
+        assert_eq!(option.quantity, Decimal::from(1000));
+        assert_eq!((option.asset_class, option.data_source), (None, "MANUAL"));
+    }
+
+    #[test]
+    fn test_cash_activities() {
+        let xml = include_str!("../../tests/fixtures/activity_cash.xml");
+        let statement = crate::parse_activity_flex(xml).unwrap();
+        let activities = activities([&statement]);
+        assert!(!activities.is_empty());
+        assert!(activities.len() < statement.cash_transactions.len());
+
+        let dividend = activities
+            .iter()
+            .find(|a| a.activity_type == ActivityType::Dividend)
+            .unwrap();
+        assert_eq!(dividend.quantity, Decimal::ONE);
+        assert!(dividend.unit_price > Decimal::ZERO);
+        assert_eq!(dividend.asset_class, Some("EQUITY"));
+        assert!(activities
+            .iter()
+            .filter(|a| a.activity_type == ActivityType::Fee)
+            .all(|a| a.quantity.is_zero() && a.fee > Decimal::ZERO));
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_json() {
+        let xml = include_str!("../../tests/fixtures/activity_minimal.xml");
+        let statement = crate::parse_activity_flex(xml).unwrap();
+        let mut out = Vec::new();
+        write_json(&mut out, &activities([&statement])).unwrap();
+        let value: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        let buy = &value["activities"][0];
+        assert_eq!(buy["type"], "BUY");
+        assert_eq!(buy["date"], "2025-01-15");
+        assert_eq!(buy["quantity"], 100.0);
+        assert_eq!(buy["unitPrice"], 185.5);
+    }
+}
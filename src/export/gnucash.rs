@@ -0,0 +1,377 @@
+//! GnuCash transaction CSV export
+//!
+//! [`write_statements`] writes trades and cash transactions in the layout
+//! of GnuCash's own transaction export, which its CSV importer reads back
+//! with the "GnuCash Export Format" preset and the multi-split option:
+//! one row per split, with the date, description, and currency on the
+//! first row of each transaction and every row carrying its transaction
+//! ID.
+//!
+//! - Trades post the security bought or sold, the cash leg, and any
+//!   commission and transaction tax. GnuCash works out realized gains when
+//!   it scrubs lots, so none are posted.
+//! - FX conversions (`CASH` trades) post between the cash accounts of the
+//!   two currencies.
+//! - Cash transactions post against dividend, interest, fee, tax, or
+//!   transfer accounts.
+//!
+//! Accounts are named as by [`JournalOptions`], except that GnuCash
+//! accounts hold one commodity each, so cash is kept per currency, in
+//! `{assets}:Cash:{CURRENCY}`. [`GnuCashOptions`] maps IB accounts and
+//! symbols to other account paths.
+//!
+//! # Example
+//! ```
+//! use ib_flex::export::gnucash::{write_statements, GnuCashOptions};
+//! use ib_flex::parse_activity_flex;
+//!
+//! let xml = include_str!("../../tests/fixtures/activity_minimal.xml");
+//! let statement = parse_activity_flex(xml).unwrap();
+//!
+//! let mut options = GnuCashOptions::default();
+//! options
+//!     .account_paths
+//!     .insert("U1234567".to_string(), "Assets:Brokerage".to_string());
+//!
+//! let mut out = Vec::new();
+//! write_statements(&mut out, [&statement], &options).unwrap();
+//! let csv = String::from_utf8(out).unwrap();
+//! assert!(csv.contains(",Assets:Brokerage:AAPL,"));
+//! ```
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::io::Write;
+
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+
+use super::csv::{write_line, CsvOptions};
+use super::journal::{cash_narration, is_execution, trade_narration, JournalOptions};
+use crate::types::{ActivityFlexStatement, AssetCategory, CashTransaction, Dated, Trade};
+use crate::Result;
+
+/// Columns of GnuCash's transaction export, in order
+const COLUMNS: &[&str] = &[
+    "Date",
+    "Transaction ID",
+    "Number",
+    "Description",
+    "Notes",
+    "Commodity/Currency",
+    "Void Reason",
+    "Action",
+    "Memo",
+    "Full Account Name",
+    "Account Name",
+    "Amount With Sym",
+    "Amount Num.",
+    "Value With Sym",
+    "Value Num.",
+    "Reconcile",
+    "Reconcile Date",
+    "Rate/Price",
+];
+
+/// Account mapping and date format for the GnuCash writer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GnuCashOptions {
+    /// Parent accounts, as for the journal writers
+    pub accounts: JournalOptions,
+    /// Parent of the cash and security accounts per IB account ID, in place
+    /// of `accounts.assets`
+    pub account_paths: HashMap<String, String>,
+    /// Account per IB symbol, in place of `{assets}:{SECURITY}`
+    pub security_accounts: HashMap<String, String>,
+    /// `chrono` format for dates (default `%Y-%m-%d`)
+    pub date_format: String,
+}
+
+impl Default for GnuCashOptions {
+    fn default() -> Self {
+        GnuCashOptions {
+            accounts: JournalOptions::default(),
+            account_paths: HashMap::new(),
+            security_accounts: HashMap::new(),
+            date_format: "%Y-%m-%d".to_string(),
+        }
+    }
+}
+
+impl GnuCashOptions {
+    /// Accounts for the statements of `account_id`
+    fn accounts_for(&self, account_id: &str) -> JournalOptions {
+        let mut accounts = self.accounts.clone();
+        if let Some(assets) = self.account_paths.get(account_id) {
+            accounts.assets = assets.clone();
+        }
+        accounts
+    }
+}
+
+/// Write the trades and cash transactions of `statements` as GnuCash CSV
+///
+/// Rows without a date are skipped.
+pub fn write_statements<'a, W, I>(
+    mut writer: W,
+    statements: I,
+    options: &GnuCashOptions,
+) -> Result<()>
+where
+    W: Write,
+    I: IntoIterator<Item = &'a ActivityFlexStatement>,
+{
+    let csv = CsvOptions::default();
+    let mut line = String::new();
+    let header = COLUMNS.iter().map(|&name| Cow::Borrowed(name));
+    write_line(&mut writer, &mut line, header, &csv)?;
+
+    let mut transactions = Vec::new();
+    for statement in statements {
+        let accounts = options.accounts_for(statement.account_id.as_str());
+        let mapping = Mapping { options, accounts };
+        transactions.extend(statement.trades.iter().filter_map(|t| mapping.trade(t)));
+        transactions.extend(
+            statement
+                .cash_transactions
+                .iter()
+                .filter_map(|c| mapping.cash_transaction(c)),
+        );
+    }
+    for (n, transaction) in transactions.iter().enumerate() {
+        let id = match transaction.id.filter(|id| !id.is_empty()) {
+            Some(id) => Cow::Borrowed(id),
+            None => Cow::Owned(format!("ib-flex-{}", n + 1)),
+        };
+        for (i, split) in transaction.splits.iter().enumerate() {
+            let first = i == 0;
+            let first_only = |value: String| if first { value } else { String::new() };
+            let leaf = split.account.rsplit(':').next().unwrap_or_default();
+            let fields = [
+                first_only(transaction.date.format(&options.date_format).to_string()),
+                id.to_string(),
+                first_only(transaction.id.unwrap_or_default().to_string()),
+                first_only(transaction.description.replace(['\r', '\n'], " ")),
+                String::new(),
+                first_only(format!("CURRENCY::{}", transaction.currency)),
+                String::new(),
+                split.action.to_string(),
+                String::new(),
+                split.account.clone(),
+                leaf.to_string(),
+                String::new(),
+                split.amount.normalize().to_string(),
+                String::new(),
+                split.value.normalize().to_string(),
+                "n".to_string(),
+                String::new(),
+                split.price.normalize().to_string(),
+            ];
+            write_line(
+                &mut writer,
+                &mut line,
+                fields.into_iter().map(Cow::Owned),
+                &csv,
+            )?;
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// A transaction in its currency, `value`s summing to zero
+struct Transaction<'a> {
+    date: NaiveDate,
+    id: Option<&'a str>,
+    description: String,
+    currency: &'a str,
+    splits: Vec<Split>,
+}
+
+/// One split: `amount` in the account's commodity, `value` in the
+/// transaction's currency
+struct Split {
+    account: String,
+    action: &'static str,
+    amount: Decimal,
+    value: Decimal,
+    price: Decimal,
+}
+
+/// Accounts for one statement
+struct Mapping<'a> {
+    options: &'a GnuCashOptions,
+    accounts: JournalOptions,
+}
+
+impl Mapping<'_> {
+    fn cash(&self, currency: &str) -> String {
+        format!("{}:{}", self.accounts.cash(), currency)
+    }
+
+    fn trade<'t>(&self, t: &'t Trade) -> Option<Transaction<'t>> {
+        if !is_execution(t) {
+            return None;
+        }
+        let (date, quantity) = (t.date()?, t.quantity?);
+        if quantity.is_zero() {
+            return None;
+        }
+        let value = -t.proceeds.unwrap_or_default();
+        let held = if t.asset_category == AssetCategory::Cash {
+            let (base, _) = t.symbol.split_once('.')?;
+            if base == t.currency {
+                return None;
+            }
+            Split {
+                account: self.cash(base),
+                action: "",
+                amount: quantity,
+                value,
+                price: value / quantity,
+            }
+        } else {
+            let account = match self.options.security_accounts.get(&t.symbol) {
+                Some(account) => account.clone(),
+                None => self.accounts.security(&t.symbol),
+            };
+            Split {
+                account,
+                action: if quantity.is_sign_positive() {
+                    "Buy"
+                } else {
+                    "Sell"
+                },
+                amount: quantity,
+                value,
+                price: t.price? * t.multiplier.unwrap_or(Decimal::ONE),
+            }
+        };
+
+        let mut splits = vec![held];
+        for posting in self.accounts.trade_postings(t) {
+            let account = if posting.account == self.accounts.cash() {
+                self.cash(&posting.currency)
+            } else {
+                posting.account
+            };
+            // A commission charged in another currency is IB's base
+            // currency, which fxRateToBase converts the trade currency to
+            let rate = match t.fx_rate_to_base.filter(|r| !r.is_zero()) {
+                Some(rate) if posting.currency != t.currency => rate,
+                _ => Decimal::ONE,
+            };
+            splits.push(Split {
+                account,
+                action: "",
+                amount: posting.amount,
+                value: (posting.amount / rate).round_dp(2),
+                price: (Decimal::ONE / rate).round_dp(8),
+            });
+        }
+        Some(Transaction {
+            date,
+            id: t.transaction_id.as_deref(),
+            description: trade_narration(t, quantity),
+            currency: &t.currency,
+            splits,
+        })
+    }
+
+    fn cash_transaction<'c>(&self, c: &'c CashTransaction) -> Option<Transaction<'c>> {
+        let split = |account: String, amount: Decimal| Split {
+            account,
+            action: "",
+            amount,
+            value: amount,
+            price: Decimal::ONE,
+        };
+        Some(Transaction {
+            date: c.date()?,
+            id: c.transaction_id.as_deref(),
+            description: cash_narration(c).to_string(),
+            currency: &c.currency,
+            splits: vec![
+                split(self.cash(&c.currency), c.amount),
+                split(self.accounts.counterpart(c), -c.amount),
+            ],
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Rows of each transaction, split into fields, grouped by ID
+    fn transactions(csv: &str) -> Vec<Vec<Vec<String>>> {
+        let mut transactions: Vec<Vec<Vec<String>>> = Vec::new();
+        for line in csv.lines().skip(1) {
+            let fields: Vec<String> = line.split(',').map(str::to_string).collect();
+            assert_eq!(fields.len(), COLUMNS.len(), "{}", line);
+            match transactions.last_mut() {
+                Some(rows) if rows[0][1] == fields[1] => rows.push(fields),
+                _ => transactions.push(vec![fields]),
+            }
+        }
+        transactions
+    }
+
+    fn write(fixture: &str, options: &GnuCashOptions) -> String {
+        let statements = crate::parse_activity_flex_all(fixture).unwrap();
+        let mut out = Vec::new();
+        write_statements(&mut out, &statements, options).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_trade_splits_and_mapping() {
+        let mut options = GnuCashOptions::default();
+        options
+            .security_accounts
+            .insert("MSFT".to_string(), "Assets:Stocks:Microsoft".to_string());
+        let csv = write(
+            include_str!("../../tests/fixtures/activity_daily_portfolio.xml"),
+            &options,
+        );
+        assert!(csv.starts_with("Date,Transaction ID,Number,Description,"));
+
+        let transactions = transactions(&csv);
+        let sell = transactions
+            .iter()
+            .find(|rows| rows[0][3] == "SELL 50 MSFT")
+            .expect("the MSFT sale");
+        assert_eq!(sell[0][5], "CURRENCY::USD");
+        assert_eq!(sell[0][7], "Sell");
+        assert_eq!(sell[0][9], "Assets:Stocks:Microsoft");
+        assert_eq!(sell[0][12], "-50");
+        assert_eq!(sell[0][17], "420.25");
+        assert!(sell[1..].iter().all(|row| row[0].is_empty()));
+        assert!(sell
+            .iter()
+            .any(|row| row[9] == "Assets:IB:Cash:USD" && row[14] == "21011.75"));
+        assert!(sell
+            .iter()
+            .any(|row| row[9] == "Expenses:IB:Commissions" && row[14] == "0.75"));
+    }
+
+    #[test]
+    fn test_transactions_balance() {
+        for fixture in [
+            include_str!("../../tests/fixtures/activity_cash.xml"),
+            include_str!("../../tests/fixtures/activity_forex.xml"),
+            include_str!("../../tests/fixtures/activity_daily_portfolio.xml"),
+        ] {
+            let csv = write(fixture, &GnuCashOptions::default());
+            let transactions = transactions(&csv);
+            assert!(!transactions.is_empty());
+            for rows in transactions {
+                let total: Decimal = rows
+                    .iter()
+                    .map(|row| row[14].parse::<Decimal>().unwrap())
+                    .sum();
+                assert!(total.is_zero(), "{:?}", rows);
+            }
+        }
+    }
+}
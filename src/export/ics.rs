@@ -0,0 +1,304 @@
+//! iCalendar deadlines
+//!
+//! [`write_statements`] writes the upcoming dates that need attention in a
+//! portfolio as an iCalendar (`.ics`) file, for any calendar application:
+//!
+//! | Source | Event |
+//! |---|---|
+//! | Open option, futures option, or warrant position | `Expiry: AAPL 250221C00200000` on the expiry |
+//! | Open futures position | `Last trade: ESH5` on the expiry |
+//! | Open bond or bill position | `Maturity: T 4.5 11/15/33` on the maturity |
+//! | Open dividend accrual | `Dividend: MSFT` on the pay date |
+//!
+//! Events are all-day, and each description gives the account and the
+//! quantity or amount; a contract reported only as lots gets one event
+//! with the lots' total. Only dates on or after a statement's last day are
+//! written. Bonds mature on the security's `maturity` where the statement
+//! lists it in the securities section, and on the position's expiry
+//! otherwise.
+//!
+//! Each event has a `UID` made from its kind, account, contract, and date,
+//! so reimporting a later file updates events rather than duplicating
+//! them. When statements repeat an event, the last statement's version is
+//! written.
+//!
+//! # Example
+//! ```
+//! use ib_flex::export::ics::write_statements;
+//! use ib_flex::parse_activity_flex;
+//!
+//! let xml = include_str!("../../tests/fixtures/activity_minimal.xml");
+//! let statement = parse_activity_flex(xml).unwrap();
+//!
+//! let mut out = Vec::new();
+//! write_statements(&mut out, [&statement]).unwrap();
+//! let ics = String::from_utf8(out).unwrap();
+//! assert!(ics.starts_with("BEGIN:VCALENDAR\r\n"));
+//! assert!(!ics.contains("BEGIN:VEVENT")); // no positions
+//! ```
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::Write;
+
+use chrono::{Days, NaiveDate, NaiveDateTime};
+use rust_decimal::Decimal;
+
+use crate::types::{ActivityFlexStatement, AssetCategory, Conid, LevelOfDetail, Position};
+use crate::Result;
+
+/// One all-day event
+struct Event {
+    date: NaiveDate,
+    summary: String,
+    description: String,
+    stamp: NaiveDateTime,
+}
+
+/// Write the upcoming expiries, maturities, and dividend payments of
+/// `statements` as one iCalendar file
+///
+/// Events are sorted by date. `DTSTAMP` is the statement's generation
+/// time, written as UTC.
+pub fn write_statements<'a, W, I>(mut writer: W, statements: I) -> Result<()>
+where
+    W: Write,
+    I: IntoIterator<Item = &'a ActivityFlexStatement>,
+{
+    let mut events = BTreeMap::new();
+    for statement in statements {
+        add_statement(&mut events, statement);
+    }
+
+    let mut out = String::new();
+    line(&mut out, "BEGIN:VCALENDAR");
+    line(&mut out, "VERSION:2.0");
+    line(
+        &mut out,
+        &format!(
+            "PRODID:-//ib-flex//ib-flex {}//EN",
+            env!("CARGO_PKG_VERSION")
+        ),
+    );
+    line(&mut out, "CALSCALE:GREGORIAN");
+    let mut events: Vec<(String, Event)> = events.into_iter().collect();
+    events.sort_by_key(|(_, event)| event.date);
+    for (uid, event) in &events {
+        line(&mut out, "BEGIN:VEVENT");
+        line(&mut out, &format!("UID:{}", uid));
+        line(
+            &mut out,
+            &format!("DTSTAMP:{}", event.stamp.format("%Y%m%dT%H%M%SZ")),
+        );
+        line(
+            &mut out,
+            &format!("DTSTART;VALUE=DATE:{}", event.date.format("%Y%m%d")),
+        );
+        let end = event.date + Days::new(1);
+        line(
+            &mut out,
+            &format!("DTEND;VALUE=DATE:{}", end.format("%Y%m%d")),
+        );
+        line(&mut out, &format!("SUMMARY:{}", escape(&event.summary)));
+        line(
+            &mut out,
+            &format!("DESCRIPTION:{}", escape(&event.description)),
+        );
+        line(&mut out, "TRANSP:TRANSPARENT");
+        line(&mut out, "END:VEVENT");
+    }
+    line(&mut out, "END:VCALENDAR");
+
+    writer.write_all(out.as_bytes())?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Add the events of one statement, keyed by UID
+fn add_statement(events: &mut BTreeMap<String, Event>, statement: &ActivityFlexStatement) {
+    let stamp = statement
+        .when_generated_datetime()
+        .ok()
+        .or_else(|| statement.to_date.and_hms_opt(0, 0, 0))
+        .unwrap_or_default();
+    let maturities: HashMap<Conid, NaiveDate> = statement
+        .securities_info
+        .items
+        .iter()
+        .filter_map(|s| Some((s.conid, s.maturity?)))
+        .collect();
+    let account = statement.account_id.as_str();
+    let upcoming = |date: &NaiveDate| *date >= statement.to_date;
+
+    // Quantity per contract: the summary row's, or the sum of the lots when
+    // the statement reports lots only
+    let mut holdings: Vec<(&Position, Decimal)> = Vec::new();
+    let summarized: HashSet<Conid> = statement
+        .positions
+        .iter()
+        .filter(|p| is_summary(p))
+        .map(|p| p.conid)
+        .collect();
+    for p in statement.positions.iter() {
+        if is_summary(p) != summarized.contains(&p.conid) {
+            continue;
+        }
+        match holdings.iter_mut().find(|(h, _)| h.conid == p.conid) {
+            Some((_, quantity)) => *quantity += p.quantity,
+            None => holdings.push((p, p.quantity)),
+        }
+    }
+
+    for (p, quantity) in holdings {
+        let (kind, title, date) = if p.asset_category.is_option() {
+            ("expiry", "Expiry", p.expiry)
+        } else if p.asset_category.is_future() {
+            ("last-trade", "Last trade", p.expiry)
+        } else if p.asset_category.is_fixed_income() {
+            let maturity = maturities.get(&p.conid).copied().or(p.expiry);
+            ("maturity", "Maturity", maturity)
+        } else {
+            continue;
+        };
+        let Some(date) = date.filter(upcoming) else {
+            continue;
+        };
+        let quantity = match p.asset_category {
+            AssetCategory::Bond | AssetCategory::Bill => {
+                format!("{} face {}", quantity.normalize(), p.currency)
+            }
+            _ => quantity.normalize().to_string(),
+        };
+        events.insert(
+            uid(kind, account, &p.conid.to_string(), date),
+            Event {
+                date,
+                summary: format!("{}: {}", title, p.symbol.trim()),
+                description: format!(
+                    "{}\nAccount {}, quantity {}",
+                    p.description.as_deref().unwrap_or(&p.symbol),
+                    account,
+                    quantity
+                ),
+                stamp,
+            },
+        );
+    }
+
+    for accrual in &statement.open_dividend_accruals.items {
+        let Some(date) = accrual.pay_date.filter(upcoming) else {
+            continue;
+        };
+        let contract = accrual.conid.as_deref().unwrap_or(&accrual.symbol);
+        let amount = accrual
+            .net_amount
+            .or(accrual.gross_amount)
+            .map(|amount| {
+                let currency = accrual.currency.as_deref().unwrap_or_default();
+                format!(", {} {}", amount.normalize(), currency)
+            })
+            .unwrap_or_default();
+        events.insert(
+            uid("dividend", account, contract, date),
+            Event {
+                date,
+                summary: format!("Dividend: {}", accrual.symbol),
+                description: format!(
+                    "{}\nAccount {}, {} shares{}, ex-date {}",
+                    accrual.description.as_deref().unwrap_or(&accrual.symbol),
+                    account,
+                    accrual.quantity.normalize(),
+                    amount,
+                    accrual.ex_date
+                ),
+                stamp,
+            },
+        );
+    }
+}
+
+fn is_summary(p: &Position) -> bool {
+    matches!(p.level_of_detail, None | Some(LevelOfDetail::Summary))
+}
+
+fn uid(kind: &str, account: &str, contract: &str, date: NaiveDate) -> String {
+    format!(
+        "{}-{}-{}-{}@ib-flex",
+        kind,
+        account,
+        contract,
+        date.format("%Y%m%d")
+    )
+}
+
+/// Escape a text value
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' | ';' | ',' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Append a content line, folded into lines of at most 75 octets
+fn line(out: &mut String, content: &str) {
+    let mut width = 0;
+    for c in content.chars() {
+        if width + c.len_utf8() > 75 {
+            out.push_str("\r\n ");
+            width = 1;
+        }
+        out.push(c);
+        width += c.len_utf8();
+    }
+    out.push_str("\r\n");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ics(fixture: &str) -> String {
+        let statement = crate::parse_activity_flex(fixture).unwrap();
+        let mut out = Vec::new();
+        write_statements(&mut out, [&statement, &statement]).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_expiries_and_dividends() {
+        let ics = ics(include_str!(
+            "../../tests/fixtures/activity_daily_portfolio.xml"
+        ));
+        assert!(ics.lines().all(|l| l.len() <= 75));
+        assert!(ics.contains("SUMMARY:Dividend: MSFT\r\n"));
+        assert!(ics.contains("DTSTART;VALUE=DATE:20250210\r\n"));
+        assert!(ics.contains("UID:dividend-U1234567-272093-20250210@ib-flex\r\n"));
+        assert!(ics.contains("SUMMARY:Expiry: "));
+        assert_eq!(
+            ics.matches("BEGIN:VEVENT").count(),
+            ics.matches("UID:").count()
+        );
+        let uids: std::collections::HashSet<_> =
+            ics.lines().filter(|l| l.starts_with("UID:")).collect();
+        assert_eq!(uids.len(), ics.matches("UID:").count());
+    }
+
+    #[test]
+    fn test_bond_maturities() {
+        let ics = ics(include_str!("../../tests/fixtures/activity_bonds.xml"));
+        assert!(ics.contains("SUMMARY:Maturity: T 4.5 11/15/33\r\n"));
+        assert!(ics.contains("DTSTART;VALUE=DATE:20331115\r\n"));
+        assert!(ics.contains("DTEND;VALUE=DATE:20331116\r\n"));
+        let unfolded = ics.replace("\r\n ", "");
+        assert!(unfolded.contains("\\nAccount U1234567\\, quantity 10000 face USD\r\n"));
+        assert_eq!(escape("a,b;c\\d\ne"), "a\\,b\\;c\\\\d\\ne");
+    }
+}
@@ -0,0 +1,207 @@
+//! Account mapping shared by the plain-text accounting writers
+//!
+//! [`beancount`](super::beancount) and [`ledger`](super::ledger) post to
+//! the same accounts, under the parents set in [`JournalOptions`]. The
+//! leaves are fixed:
+//!
+//! | Account | Used for |
+//! |---|---|
+//! | `{assets}:Cash` | Cash, in every currency |
+//! | `{assets}:{SECURITY}` | Each security held |
+//! | `{income}:PnL` | Realized P&L |
+//! | `{income}:Dividends` | Dividends and payments in lieu |
+//! | `{income}:Interest` | Interest paid or received |
+//! | `{income}:Other` | Other cash transactions |
+//! | `{expenses}:Commissions` | Trade commissions |
+//! | `{expenses}:Fees` | Fees and commission adjustments |
+//! | `{expenses}:Taxes` | Withholding and transaction taxes |
+//!
+//! Securities are named after their symbol, upper-cased with characters
+//! other than `A-Z0-9'._-` replaced by `-`, so the same security has the
+//! same name in both formats.
+
+use rust_decimal::Decimal;
+
+use crate::types::{CashTransaction, LevelOfDetail, OpenClose, Trade};
+
+/// Parent accounts for the journal writers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JournalOptions {
+    /// Parent of the cash and security accounts (default `Assets:IB`)
+    pub assets: String,
+    /// Parent of the P&L, dividend, and interest accounts (default
+    /// `Income:IB`)
+    pub income: String,
+    /// Parent of the commission, fee, and tax accounts (default
+    /// `Expenses:IB`)
+    pub expenses: String,
+    /// Account for deposits and withdrawals (default `Equity:IB:Transfers`)
+    pub transfers: String,
+    /// Account the opening cash comes from (default
+    /// `Equity:IB:Opening-Balances`)
+    pub opening_balances: String,
+}
+
+impl Default for JournalOptions {
+    fn default() -> Self {
+        JournalOptions {
+            assets: "Assets:IB".to_string(),
+            income: "Income:IB".to_string(),
+            expenses: "Expenses:IB".to_string(),
+            transfers: "Equity:IB:Transfers".to_string(),
+            opening_balances: "Equity:IB:Opening-Balances".to_string(),
+        }
+    }
+}
+
+/// One leg of a transaction
+pub(super) struct Posting {
+    pub(super) account: String,
+    pub(super) amount: Decimal,
+    pub(super) currency: String,
+}
+
+impl JournalOptions {
+    pub(super) fn cash(&self) -> String {
+        format!("{}:Cash", self.assets)
+    }
+
+    /// Account holding the security IB calls `symbol`
+    pub(super) fn security(&self, symbol: &str) -> String {
+        let leaf = security_name(symbol).replace(['.', '_', '\''], "-");
+        format!("{}:{}", self.assets, leaf)
+    }
+
+    pub(super) fn pnl(&self) -> String {
+        format!("{}:PnL", self.income)
+    }
+
+    /// Account on the other side of a cash transaction
+    pub(super) fn counterpart(&self, c: &CashTransaction) -> String {
+        if c.is_dividend() {
+            format!("{}:Dividends", self.income)
+        } else if c.is_interest() {
+            format!("{}:Interest", self.income)
+        } else if c.is_fee() {
+            format!("{}:Fees", self.expenses)
+        } else if c.is_withholding_tax() {
+            format!("{}:Taxes", self.expenses)
+        } else if c.is_deposit_or_withdrawal() {
+            self.transfers.clone()
+        } else {
+            format!("{}:Other", self.income)
+        }
+    }
+
+    /// Cash, commission, and tax postings of a trade: everything but the
+    /// security or currency bought or sold, which balances them
+    pub(super) fn trade_postings(&self, t: &Trade) -> Vec<Posting> {
+        let posting = |account: String, amount: Decimal, currency: &str| Posting {
+            account,
+            amount,
+            currency: currency.to_string(),
+        };
+        let proceeds = t.proceeds.unwrap_or_default();
+        let commission = t.commission.unwrap_or_default();
+        let taxes = t.taxes.unwrap_or_default();
+        let commission_currency = t
+            .commission_currency
+            .as_deref()
+            .filter(|c| !c.is_empty())
+            .unwrap_or(&t.currency);
+
+        let mut postings = Vec::new();
+        if commission_currency == t.currency {
+            postings.push(posting(
+                self.cash(),
+                proceeds + commission + taxes,
+                &t.currency,
+            ));
+        } else {
+            postings.push(posting(self.cash(), proceeds + taxes, &t.currency));
+            if !commission.is_zero() {
+                postings.push(posting(self.cash(), commission, commission_currency));
+            }
+        }
+        if !commission.is_zero() {
+            let account = format!("{}:Commissions", self.expenses);
+            postings.push(posting(account, -commission, commission_currency));
+        }
+        if !taxes.is_zero() {
+            let account = format!("{}:Taxes", self.expenses);
+            postings.push(posting(account, -taxes, &t.currency));
+        }
+        postings
+    }
+}
+
+/// Returns true for the trade rows the journals post: executions
+pub(super) fn is_execution(t: &Trade) -> bool {
+    matches!(t.level_of_detail, None | Some(LevelOfDetail::Execution))
+}
+
+/// Returns true for trades that reduce a position
+pub(super) fn is_closing(t: &Trade) -> bool {
+    matches!(
+        t.open_close,
+        Some(OpenClose::Close) | Some(OpenClose::CloseOpen)
+    )
+}
+
+/// `BUY 100 AAPL` or `SELL 100 AAPL`
+pub(super) fn trade_narration(t: &Trade, quantity: Decimal) -> String {
+    let side = if quantity.is_sign_positive() {
+        "BUY"
+    } else {
+        "SELL"
+    };
+    format!("{} {} {}", side, quantity.abs().normalize(), t.symbol)
+}
+
+/// The description of a cash transaction, or its type
+pub(super) fn cash_narration(c: &CashTransaction) -> &str {
+    c.description
+        .as_deref()
+        .or(c.transaction_type.as_ref().map(|t| t.as_str()))
+        .unwrap_or("Cash transaction")
+}
+
+/// Commodity name for an IB symbol
+///
+/// Names are upper case, start with a letter, end with a letter or digit,
+/// and are at most 24 characters of `A-Z0-9'._-`, which suits Beancount.
+pub(super) fn security_name(symbol: &str) -> String {
+    let mut name = String::new();
+    for c in symbol.trim().chars() {
+        let c = c.to_ascii_uppercase();
+        if c.is_ascii_uppercase() || c.is_ascii_digit() || matches!(c, '\'' | '.' | '_' | '-') {
+            name.push(c);
+        } else if !name.ends_with('-') {
+            name.push('-');
+        }
+    }
+    if !name.starts_with(|c: char| c.is_ascii_uppercase()) {
+        name.insert(0, 'X');
+    }
+    name.truncate(24);
+    while name.ends_with(|c: char| !c.is_ascii_alphanumeric()) {
+        name.pop();
+    }
+    name
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_security_names() {
+        assert_eq!(security_name("AAPL"), "AAPL");
+        assert_eq!(security_name("BRK B"), "BRK-B");
+        assert_eq!(security_name("700"), "X700");
+        assert_eq!(security_name("rds.a"), "RDS.A");
+
+        let options = JournalOptions::default();
+        assert_eq!(options.security("rds.a"), "Assets:IB:RDS-A");
+    }
+}
@@ -0,0 +1,505 @@
+//! JSON export with plain field names
+//!
+//! The types' `Serialize` impls are shaped for FLEX XML: attributes are
+//! named `@accountId`, sections nest their rows one level down, and dates
+//! use IB's `yyyyMMdd`. The functions here serialize any statement, section,
+//! or row to JSON meant for other programs instead:
+//!
+//! - field names are snake_case, without the `@` (`fxRateToBase` becomes
+//!   `fx_rate_to_base`, `tradeID` becomes `trade_id`)
+//! - a section holding one kind of row is an array of its rows
+//! - dates and date/times are ISO 8601, booleans are `true`/`false`, and
+//!   missing values are `null`
+//! - amounts stay strings, so no precision is lost
+//!
+//! A [`ParsedStatement`](crate::ParsedStatement) exports as an object with
+//! the `statement` and its `provenance`. With the `schemars` feature,
+//! `schema_for_activity_statement()` returns the JSON Schema of an exported
+//! statement, for validating exports or generating clients in other
+//! languages.
+//!
+//! # Example
+//! ```
+//! use ib_flex::export::json;
+//! use ib_flex::parse_activity_flex;
+//!
+//! let xml = include_str!("../../tests/fixtures/activity_minimal.xml");
+//! let statement = parse_activity_flex(xml).unwrap();
+//!
+//! let value = json::to_value(&statement).unwrap();
+//! assert_eq!(value["account_id"], "U1234567");
+//! assert!(value["open_positions"].is_array());
+//!
+//! // Sections and rows export the same way
+//! let trades = json::to_string(&statement.trades.items).unwrap();
+//! assert!(trades.contains(r#""trade_date":"2025-01-15""#));
+//! ```
+
+use chrono::NaiveDate;
+use serde::ser::{self, Error as _, Serialize, Serializer};
+use serde_json::{Map, Value};
+
+use crate::parsers::xml_utils::{parse_optional_datetime, BOOL, DATE, DATE_TIME};
+use crate::{ParseError, Result};
+
+/// Convert to a JSON value
+pub fn to_value<T: Serialize + ?Sized>(value: &T) -> Result<Value> {
+    let value = value
+        .serialize(Plain(serde_json::value::Serializer))
+        .map_err(|e| ParseError::JsonError(e.to_string()))?;
+    Ok(clean(value))
+}
+
+/// Serialize to a compact JSON string
+pub fn to_string<T: Serialize + ?Sized>(value: &T) -> Result<String> {
+    serde_json::to_string(&to_value(value)?).map_err(|e| ParseError::JsonError(e.to_string()))
+}
+
+/// Serialize to an indented JSON string
+pub fn to_string_pretty<T: Serialize + ?Sized>(value: &T) -> Result<String> {
+    serde_json::to_string_pretty(&to_value(value)?)
+        .map_err(|e| ParseError::JsonError(e.to_string()))
+}
+
+/// JSON Schema (draft 2020-12) of an [`ActivityFlexStatement`] exported by
+/// [`to_value`]
+///
+/// [`ActivityFlexStatement`]: crate::ActivityFlexStatement
+#[cfg(feature = "schemars")]
+pub fn schema_for_activity_statement() -> Value {
+    let schema = schemars::generate::SchemaSettings::draft2020_12()
+        .for_serialize()
+        .into_generator()
+        .into_root_schema_for::<crate::ActivityFlexStatement>();
+    clean_schema(schema.to_value())
+}
+
+/// [`clean`] applied to a schema: properties renamed, objects holding only
+/// an array replaced by the array, and defaults cleaned
+///
+/// Defaults are serialized in FLEX form, where a missing date or flag is an
+/// empty string; a default of a value that can be null is written as null,
+/// as the export writes it.
+#[cfg(feature = "schemars")]
+fn clean_schema(schema: Value) -> Value {
+    let Value::Object(mut keywords) = schema else {
+        return schema;
+    };
+    let nullable = match keywords.get("type") {
+        Some(Value::Array(types)) => types.iter().any(|t| t == "null"),
+        Some(t) => t == "null",
+        None => false,
+    };
+    if let Some(Value::Object(properties)) = keywords.remove("properties") {
+        let properties: Map<String, Value> = properties
+            .into_iter()
+            .map(|(key, value)| (field_name(&key), clean_schema(value)))
+            .collect();
+        if properties.len() == 1
+            && properties
+                .values()
+                .all(|p| p.get("type") == Some(&"array".into()))
+        {
+            return properties
+                .into_iter()
+                .map(|(_, p)| p)
+                .next()
+                .unwrap_or_default();
+        }
+        keywords.insert("properties".into(), Value::Object(properties));
+    }
+    if let Some(Value::Array(required)) = keywords.get_mut("required") {
+        for name in required.iter_mut() {
+            if let Value::String(key) = name {
+                *key = field_name(key);
+            }
+        }
+    }
+    for (keyword, value) in keywords.iter_mut() {
+        match (keyword.as_str(), value) {
+            ("default", value) if nullable && *value == "" => *value = Value::Null,
+            ("default", value) => *value = clean(value.take()),
+            ("items" | "additionalProperties" | "not", value) => {
+                *value = clean_schema(value.take());
+            }
+            ("anyOf" | "oneOf" | "allOf" | "prefixItems", Value::Array(schemas)) => {
+                for schema in schemas.iter_mut() {
+                    *schema = clean_schema(schema.take());
+                }
+            }
+            ("$defs", Value::Object(defs)) => {
+                for schema in defs.values_mut() {
+                    *schema = clean_schema(schema.take());
+                }
+            }
+            _ => {}
+        }
+    }
+    Value::Object(keywords)
+}
+
+/// Rename fields and unwrap single-row-kind sections, children first
+fn clean(value: Value) -> Value {
+    match value {
+        Value::Array(items) => Value::Array(items.into_iter().map(clean).collect()),
+        Value::Object(fields) => {
+            let cleaned: Map<String, Value> = fields
+                .into_iter()
+                .map(|(key, value)| (field_name(&key), clean(value)))
+                .collect();
+            if cleaned.len() == 1 && cleaned.values().all(Value::is_array) {
+                return cleaned
+                    .into_iter()
+                    .map(|(_, rows)| rows)
+                    .next()
+                    .unwrap_or_default();
+            }
+            Value::Object(cleaned)
+        }
+        other => other,
+    }
+}
+
+/// `@fxRateToBase` to `fx_rate_to_base`, keeping acronyms together
+/// (`isAPIOrder` to `is_api_order`)
+fn field_name(key: &str) -> String {
+    let chars: Vec<char> = key.trim_start_matches('@').chars().collect();
+    let mut name = String::with_capacity(chars.len() + 4);
+    for (i, &c) in chars.iter().enumerate() {
+        if c.is_ascii_uppercase() && i > 0 {
+            let previous = chars[i - 1];
+            let next_lower = chars.get(i + 1).is_some_and(|n| n.is_ascii_lowercase());
+            if previous.is_ascii_lowercase()
+                || previous.is_ascii_digit()
+                || (previous.is_ascii_uppercase() && next_lower)
+            {
+                name.push('_');
+            }
+        }
+        name.push(c.to_ascii_lowercase());
+    }
+    name
+}
+
+/// Serializer writing the values the FLEX serializers mark in
+/// [`xml_utils`](crate::parsers::xml_utils) in plain form: ISO 8601 dates
+/// and date/times, native booleans, and nulls for missing values
+struct Plain<S>(S);
+
+/// A value serialized through [`Plain`]
+struct PlainValue<'a, T: ?Sized>(&'a T);
+
+impl<T: Serialize + ?Sized> Serialize for PlainValue<'_, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        self.0.serialize(Plain(serializer))
+    }
+}
+
+/// A compound of the serializer [`Plain`] wraps, serializing its values
+/// through [`Plain`]
+struct Compound<S>(S);
+
+macro_rules! forward_serialize {
+    ($($method:ident($($arg:ident: $ty:ty),*)),+ $(,)?) => {
+        $(
+            fn $method(self, $($arg: $ty),*) -> std::result::Result<S::Ok, S::Error> {
+                self.0.$method($($arg),*)
+            }
+        )+
+    };
+}
+
+impl<S: Serializer> Serializer for Plain<S> {
+    type Ok = S::Ok;
+    type Error = S::Error;
+    type SerializeSeq = Compound<S::SerializeSeq>;
+    type SerializeTuple = Compound<S::SerializeTuple>;
+    type SerializeTupleStruct = Compound<S::SerializeTupleStruct>;
+    type SerializeTupleVariant = Compound<S::SerializeTupleVariant>;
+    type SerializeMap = Compound<S::SerializeMap>;
+    type SerializeStruct = Compound<S::SerializeStruct>;
+    type SerializeStructVariant = Compound<S::SerializeStructVariant>;
+
+    forward_serialize! {
+        serialize_bool(v: bool),
+        serialize_i8(v: i8),
+        serialize_i16(v: i16),
+        serialize_i32(v: i32),
+        serialize_i64(v: i64),
+        serialize_i128(v: i128),
+        serialize_u8(v: u8),
+        serialize_u16(v: u16),
+        serialize_u32(v: u32),
+        serialize_u64(v: u64),
+        serialize_u128(v: u128),
+        serialize_f32(v: f32),
+        serialize_f64(v: f64),
+        serialize_char(v: char),
+        serialize_str(v: &str),
+        serialize_bytes(v: &[u8]),
+        serialize_none(),
+        serialize_unit(),
+        serialize_unit_struct(name: &'static str),
+        serialize_unit_variant(name: &'static str, index: u32, variant: &'static str),
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(
+        self,
+        value: &T,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        self.0.serialize_some(&PlainValue(value))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        if ![DATE, DATE_TIME, BOOL].contains(&name) {
+            return self.0.serialize_newtype_struct(name, &PlainValue(value));
+        }
+        let text = match serde_json::to_value(value) {
+            Ok(Value::String(text)) => text,
+            _ => return Err(S::Error::custom(format!("expected text for {}", name))),
+        };
+        match (name, text.as_str()) {
+            (_, "") => self.0.serialize_none(),
+            (BOOL, text) => self.0.serialize_bool(text == "Y"),
+            (DATE, text) => match NaiveDate::parse_from_str(text, "%Y%m%d") {
+                Ok(date) => self.0.collect_str(&date),
+                Err(_) => self.0.serialize_str(text),
+            },
+            (_, text) => match parse_optional_datetime(text) {
+                Some(dt) => self.0.collect_str(&dt.format("%Y-%m-%dT%H:%M:%S")),
+                None => self.0.serialize_str(text),
+            },
+        }
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        self.0
+            .serialize_newtype_variant(name, index, variant, &PlainValue(value))
+    }
+
+    fn serialize_seq(
+        self,
+        len: Option<usize>,
+    ) -> std::result::Result<Self::SerializeSeq, S::Error> {
+        self.0.serialize_seq(len).map(Compound)
+    }
+
+    fn serialize_tuple(self, len: usize) -> std::result::Result<Self::SerializeTuple, S::Error> {
+        self.0.serialize_tuple(len).map(Compound)
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> std::result::Result<Self::SerializeTupleStruct, S::Error> {
+        self.0.serialize_tuple_struct(name, len).map(Compound)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        name: &'static str,
+        index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> std::result::Result<Self::SerializeTupleVariant, S::Error> {
+        self.0
+            .serialize_tuple_variant(name, index, variant, len)
+            .map(Compound)
+    }
+
+    fn serialize_map(
+        self,
+        len: Option<usize>,
+    ) -> std::result::Result<Self::SerializeMap, S::Error> {
+        self.0.serialize_map(len).map(Compound)
+    }
+
+    fn serialize_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> std::result::Result<Self::SerializeStruct, S::Error> {
+        self.0.serialize_struct(name, len).map(Compound)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        name: &'static str,
+        index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> std::result::Result<Self::SerializeStructVariant, S::Error> {
+        self.0
+            .serialize_struct_variant(name, index, variant, len)
+            .map(Compound)
+    }
+
+    fn is_human_readable(&self) -> bool {
+        self.0.is_human_readable()
+    }
+}
+
+/// Implement a compound serializer trait for [`Compound`], passing each
+/// value through [`Plain`]
+macro_rules! plain_compound {
+    ($($trait:ident { $($method:ident($($arg:ident: $ty:ty),*);)+ })+) => {
+        $(
+            impl<S: ser::$trait> ser::$trait for Compound<S> {
+                type Ok = S::Ok;
+                type Error = S::Error;
+
+                $(
+                    fn $method<T: Serialize + ?Sized>(
+                        &mut self,
+                        $($arg: $ty,)*
+                        value: &T,
+                    ) -> std::result::Result<(), S::Error> {
+                        self.0.$method($($arg,)* &PlainValue(value))
+                    }
+                )+
+
+                fn end(self) -> std::result::Result<S::Ok, S::Error> {
+                    self.0.end()
+                }
+            }
+        )+
+    };
+}
+
+plain_compound! {
+    SerializeSeq { serialize_element(); }
+    SerializeTuple { serialize_element(); }
+    SerializeTupleStruct { serialize_field(); }
+    SerializeTupleVariant { serialize_field(); }
+    SerializeMap { serialize_key(); serialize_value(); }
+    SerializeStruct { serialize_field(key: &'static str); }
+    SerializeStructVariant { serialize_field(key: &'static str); }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_field_names() {
+        assert_eq!(field_name("@accountId"), "account_id");
+        assert_eq!(field_name("@tradeID"), "trade_id");
+        assert_eq!(field_name("@isAPIOrder"), "is_api_order");
+        assert_eq!(field_name("@fifoPnlRealized"), "fifo_pnl_realized");
+        assert_eq!(field_name("EquitySummaryInBase"), "equity_summary_in_base");
+        assert_eq!(field_name("SLBFees"), "slb_fees");
+        assert_eq!(field_name("conid"), "conid");
+    }
+
+    #[test]
+    fn test_statement_export() {
+        let xml = include_str!("../../tests/fixtures/activity_daily_portfolio.xml");
+        let statement = crate::parse_activity_flex(xml).unwrap();
+        let json = to_string(&statement).unwrap();
+        assert!(!json.contains(r#""@"#));
+
+        let value = to_value(&statement).unwrap();
+        assert_eq!(value["from_date"], "2025-01-15");
+        let row = &value["equity_summary_in_base"][0];
+        assert_eq!(row["report_date"], "2025-01-15");
+        // Trades hold two row kinds, so they stay keyed
+        assert!(value["trades"]["trade"].is_array());
+        assert!(value["trades"]["wash_sale"].is_array());
+    }
+
+    #[test]
+    fn test_provenance_export() {
+        let xml = include_str!("../../tests/fixtures/activity_minimal.xml");
+        let parsed = crate::ParsedStatement::parse_all(xml, Some("minimal.xml")).unwrap();
+        let value = to_value(&parsed[0]).unwrap();
+        assert_eq!(value["statement"]["account_id"], "U1234567");
+        assert_eq!(value["provenance"]["source"], "minimal.xml");
+        assert_eq!(value["provenance"]["sha256"], parsed[0].provenance.sha256);
+        assert!(value["provenance"]["parsed_at"].is_string());
+    }
+
+    /// Whether `value` matches `schema`, checking types, constants, and
+    /// property names
+    #[cfg(feature = "schemars")]
+    fn conforms(value: &Value, schema: &Value, root: &Value) -> bool {
+        if let Some(Value::String(reference)) = schema.get("$ref") {
+            let name = reference.trim_start_matches("#/$defs/");
+            return conforms(value, &root["$defs"][name], root);
+        }
+        if let Some(Value::Array(options)) = schema.get("anyOf").or(schema.get("oneOf")) {
+            return options.iter().any(|option| conforms(value, option, root));
+        }
+        if let Some(constant) = schema.get("const") {
+            return value == constant;
+        }
+        let kind = match value {
+            Value::Null => "null",
+            Value::Bool(_) => "boolean",
+            Value::Number(n) if n.is_u64() || n.is_i64() => "integer",
+            Value::Number(_) => "number",
+            Value::String(_) => "string",
+            Value::Array(_) => "array",
+            Value::Object(_) => "object",
+        };
+        let types = match schema.get("type") {
+            Some(Value::String(t)) => vec![t.as_str()],
+            Some(Value::Array(ts)) => ts.iter().filter_map(Value::as_str).collect(),
+            _ => return true,
+        };
+        let number = kind == "integer" && types.contains(&"number");
+        if !types.contains(&kind) && !number {
+            return false;
+        }
+        match value {
+            Value::Array(items) => items
+                .iter()
+                .all(|item| conforms(item, &schema["items"], root)),
+            Value::Object(fields) => {
+                let properties = &schema["properties"];
+                fields.iter().all(|(key, field)| match properties.get(key) {
+                    Some(property) => conforms(field, property, root),
+                    None => false,
+                })
+            }
+            _ => true,
+        }
+    }
+
+    #[cfg(feature = "schemars")]
+    #[test]
+    fn test_schema_matches_export() {
+        let schema = schema_for_activity_statement();
+        let text = schema.to_string();
+        assert!(!text.contains(r#""@"#));
+        assert_eq!(schema["properties"]["account_id"]["type"], "string");
+
+        for xml in [
+            include_str!("../../tests/fixtures/activity_daily_portfolio.xml"),
+            include_str!("../../tests/fixtures/activity_options.xml"),
+            include_str!("../../tests/fixtures/activity_backfill.xml"),
+        ] {
+            for statement in crate::parse_activity_flex_all(xml).unwrap() {
+                let value = to_value(&statement).unwrap();
+                assert!(conforms(&value, &schema, &schema));
+            }
+        }
+
+        // Sections of one row kind are arrays, as in the export
+        let section = &schema["properties"]["open_positions"]["$ref"];
+        let name = section.as_str().unwrap().trim_start_matches("#/$defs/");
+        assert_eq!(schema["$defs"][name]["type"], "array");
+        let mismatched = serde_json::json!({ "account_id": 1 });
+        assert!(!conforms(&mismatched, &schema, &schema));
+    }
+}
@@ -0,0 +1,192 @@
+//! Crypto tax tool CSV export
+//!
+//! [`write_statements`] writes the crypto activity of statements in the
+//! universal CSV layout of [Koinly](https://koinly.io), which other crypto
+//! tax tools such as CoinTracking also import through their custom CSV
+//! importers: one row per transaction, with what left the account, what
+//! arrived, and the fee.
+//!
+//! | IB row | Sent | Received | Label |
+//! |---|---|---|---|
+//! | Crypto buy | cash | coins | |
+//! | Crypto sell | coins | cash | |
+//! | Crypto fee | cash | | `cost` |
+//! | Other crypto cash transaction | cash, if negative | cash, if positive | |
+//!
+//! Only trades and cash transactions in the `CRYPTO` asset category are
+//! written, so the file holds nothing for accounts without crypto
+//! activity. Trade commissions go in the fee columns. Coins are named by
+//! the trade's symbol, with any `.USD`-style suffix dropped. IB reports no
+//! blockchain hashes, so `TxHash` holds the IB transaction ID, which keeps
+//! reimports from creating duplicates.
+//!
+//! Times are the statement's local times, as IB reports them; set the
+//! timezone in the importer to match.
+//!
+//! # Example
+//! ```
+//! use ib_flex::export::csv::CsvOptions;
+//! use ib_flex::export::koinly::write_statements;
+//! use ib_flex::parse_activity_flex;
+//!
+//! let xml = include_str!("../../tests/fixtures/activity_minimal.xml");
+//! let statement = parse_activity_flex(xml).unwrap();
+//!
+//! let mut out = Vec::new();
+//! write_statements(&mut out, [&statement], &CsvOptions::default()).unwrap();
+//! let csv = String::from_utf8(out).unwrap();
+//! assert!(csv.starts_with("Date,Sent Amount,Sent Currency,Received Amount,"));
+//! assert_eq!(csv.lines().count(), 1); // no crypto trades
+//! ```
+
+use std::io::Write;
+
+use chrono::NaiveDateTime;
+use rust_decimal::Decimal;
+
+use super::columns::KOINLY_COLUMNS;
+use super::csv::{write_rows, CsvOptions};
+use super::journal::{cash_narration, is_execution, trade_narration};
+use crate::parsers::datetime::parse_ib_datetime;
+use crate::types::{ActivityFlexStatement, AssetCategory, CashTransaction, Dated, Trade};
+use crate::Result;
+
+/// One row of the import
+pub(super) struct Row {
+    pub(super) time: NaiveDateTime,
+    pub(super) sent: Option<(Decimal, String)>,
+    pub(super) received: Option<(Decimal, String)>,
+    pub(super) fee: Option<(Decimal, String)>,
+    pub(super) label: Option<&'static str>,
+    pub(super) description: String,
+    pub(super) tx_hash: Option<String>,
+}
+
+/// Write the crypto trades and cash transactions of `statements` as
+/// Koinly universal CSV
+///
+/// Columns: `Date`, `Sent Amount`, `Sent Currency`, `Received Amount`,
+/// `Received Currency`, `Fee Amount`, `Fee Currency`, `Label`,
+/// `Description`, `TxHash`. Dates use `options.datetime_format`; rows
+/// without a date are skipped.
+pub fn write_statements<'a, W, I>(writer: W, statements: I, options: &CsvOptions) -> Result<()>
+where
+    W: Write,
+    I: IntoIterator<Item = &'a ActivityFlexStatement>,
+{
+    let mut rows = Vec::new();
+    for statement in statements {
+        rows.extend(statement.trades.iter().filter_map(trade_row));
+        rows.extend(statement.cash_transactions.iter().filter_map(cash_row));
+    }
+    rows.sort_by_key(|row| row.time);
+    write_rows(writer, KOINLY_COLUMNS, &rows, options)
+}
+
+fn trade_row(t: &Trade) -> Option<Row> {
+    if t.asset_category != AssetCategory::Cryptocurrency || !is_execution(t) {
+        return None;
+    }
+    let quantity = t.quantity.filter(|q| !q.is_zero())?;
+    let time = t.trade_time.or_else(|| t.date()?.and_hms_opt(0, 0, 0))?;
+    let coin = t.symbol.split('.').next().unwrap_or(&t.symbol).to_string();
+    let coins = (quantity.abs(), coin);
+    let cash = (t.proceeds.unwrap_or_default().abs(), t.currency.clone());
+    let (sent, received) = if quantity.is_sign_positive() {
+        (cash, coins)
+    } else {
+        (coins, cash)
+    };
+    let fee_currency = t
+        .commission_currency
+        .clone()
+        .filter(|c| !c.is_empty())
+        .unwrap_or_else(|| t.currency.clone());
+    Some(Row {
+        time,
+        sent: Some(sent),
+        received: Some(received),
+        fee: t
+            .commission
+            .filter(|c| !c.is_zero())
+            .map(|c| (c.abs(), fee_currency)),
+        label: None,
+        description: trade_narration(t, quantity),
+        tx_hash: t.transaction_id.clone(),
+    })
+}
+
+fn cash_row(c: &CashTransaction) -> Option<Row> {
+    if c.asset_category != Some(AssetCategory::Cryptocurrency) || c.amount.is_zero() {
+        return None;
+    }
+    let time = c
+        .date_time
+        .as_deref()
+        .and_then(|dt| parse_ib_datetime(dt).ok())
+        .or_else(|| c.date()?.and_hms_opt(0, 0, 0))?;
+    let cash = Some((c.amount.abs(), c.currency.clone()));
+    let received = c.amount.is_sign_positive();
+    Some(Row {
+        time,
+        sent: if received { None } else { cash.clone() },
+        received: if received { cash } else { None },
+        fee: None,
+        label: (c.is_fee() && !received).then_some("cost"),
+        description: cash_narration(c).to_string(),
+        tx_hash: c.transaction_id.clone(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{CashTransactionBuilder, CashTransactionType, TradeBuilder};
+    use chrono::NaiveDate;
+
+    #[test]
+    fn test_crypto_rows() {
+        let xml = include_str!("../../tests/fixtures/activity_minimal.xml");
+        let mut statement = crate::parse_activity_flex(xml).unwrap();
+        let day = NaiveDate::from_ymd_opt(2025, 1, 15).unwrap();
+        let crypto = |symbol: &str, quantity: i64, proceeds: i64| {
+            TradeBuilder::new(symbol)
+                .asset_category(AssetCategory::Cryptocurrency)
+                .transaction_id(format!("T{}", quantity))
+                .trade_time(day.and_hms_opt(10, 30, 0).unwrap())
+                .quantity(quantity)
+                .proceeds(proceeds)
+                .commission(Decimal::new(-150, 2))
+                .build()
+        };
+        statement
+            .trades
+            .items
+            .extend([crypto("BTC.USD", 1, -40000), crypto("ETH", -2, 6000)]);
+        statement.cash_transactions.items = vec![
+            CashTransactionBuilder::new(CashTransactionType::OtherFees, -5)
+                .asset_category(AssetCategory::Cryptocurrency)
+                .description("CRYPTO CUSTODY FEE")
+                .date(day)
+                .build(),
+            CashTransactionBuilder::new(CashTransactionType::OtherFees, -10)
+                .date(day)
+                .build(),
+        ];
+
+        let mut out = Vec::new();
+        write_statements(&mut out, [&statement], &CsvOptions::default()).unwrap();
+        let csv = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(
+            lines,
+            [
+                "Date,Sent Amount,Sent Currency,Received Amount,Received Currency,\
+                 Fee Amount,Fee Currency,Label,Description,TxHash",
+                "2025-01-15 00:00:00,5,USD,,,,,cost,CRYPTO CUSTODY FEE,",
+                "2025-01-15 10:30:00,40000,USD,1,BTC,1.50,USD,,BUY 1 BTC.USD,T1",
+                "2025-01-15 10:30:00,2,ETH,6000,USD,1.50,USD,,SELL 2 ETH,T-2",
+            ]
+        );
+    }
+}
@@ -0,0 +1,339 @@
+//! ledger and hledger journals
+//!
+//! [`write_statements`] writes statements as a journal that both ledger
+//! and hledger read, with the accounts of [`JournalOptions`], as for
+//! [`beancount`](super::beancount):
+//!
+//! - trades as a conversion between the security and cash at the trade
+//!   price (`@`), with commission and tax postings; realized P&L is left to
+//!   the tool's own lot tracking;
+//! - FX conversions (`CASH` trades) as a conversion between currencies of
+//!   the cash account at the proceeds (`@@`);
+//! - cash transactions against dividend, interest, fee, tax, or transfer
+//!   accounts;
+//! - each statement's ending cash per currency, from the cash report, as
+//!   balance assertions on the statement's last day;
+//! - `P` price directives from open positions' mark prices, per unit of
+//!   the security as traded, and from the conversion rates.
+//!
+//! Transactions are coded with IB's transaction ID. The first statement's
+//! starting cash is assigned from the opening balances account, and every
+//! account used is declared up front. Statements should be given in date
+//! order. Commodities that are not plain letters are quoted.
+//!
+//! # Example
+//! ```
+//! use ib_flex::export::journal::JournalOptions;
+//! use ib_flex::export::ledger::write_statements;
+//! use ib_flex::parse_activity_flex;
+//!
+//! let xml = include_str!("../../tests/fixtures/activity_minimal.xml");
+//! let statement = parse_activity_flex(xml).unwrap();
+//!
+//! let mut out = Vec::new();
+//! write_statements(&mut out, [&statement], &JournalOptions::default()).unwrap();
+//! let journal = String::from_utf8(out).unwrap();
+//! assert!(journal.contains("    Assets:IB:AAPL  100 AAPL @ 185.5 USD"));
+//! ```
+
+use std::collections::BTreeSet;
+use std::fmt::Write as _;
+use std::io::Write;
+
+use chrono::{Days, NaiveDate};
+use rust_decimal::Decimal;
+
+use super::journal::{
+    cash_narration, is_execution, security_name, trade_narration, JournalOptions,
+};
+use crate::types::{ActivityFlexStatement, AssetCategory, CashTransaction, Dated, Trade};
+use crate::Result;
+
+/// Write `statements` as a ledger/hledger journal
+pub fn write_statements<'a, W, I>(
+    mut writer: W,
+    statements: I,
+    options: &JournalOptions,
+) -> Result<()>
+where
+    W: Write,
+    I: IntoIterator<Item = &'a ActivityFlexStatement>,
+{
+    let statements: Vec<&ActivityFlexStatement> = statements.into_iter().collect();
+    let mut journal = Journal {
+        options,
+        accounts: BTreeSet::new(),
+        text: String::new(),
+    };
+    for (i, statement) in statements.iter().enumerate() {
+        if i == 0 {
+            journal.opening(statement);
+        }
+        for trade in statement.trades.iter() {
+            journal.trade(trade);
+        }
+        for transaction in statement.cash_transactions.iter() {
+            journal.cash_transaction(transaction);
+        }
+        journal.assertions(statement);
+        journal.prices(statement);
+    }
+
+    for account in &journal.accounts {
+        writeln!(writer, "account {}", account)?;
+    }
+    if !journal.accounts.is_empty() {
+        writeln!(writer)?;
+    }
+    writer.write_all(journal.text.as_bytes())?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Transactions written so far, and the accounts they use
+struct Journal<'a> {
+    options: &'a JournalOptions,
+    accounts: BTreeSet<String>,
+    text: String,
+}
+
+impl Journal<'_> {
+    /// `account`, noting that it must be declared
+    fn account(&mut self, account: String) -> String {
+        self.accounts.insert(account.clone());
+        account
+    }
+
+    fn header(&mut self, date: NaiveDate, description: &str, code: Option<&str>) {
+        let _ = write!(self.text, "{} *", date);
+        if let Some(code) = code.filter(|code| !code.is_empty()) {
+            let _ = write!(self.text, " ({})", code);
+        }
+        let _ = writeln!(self.text, " {}", line(description));
+    }
+
+    fn posting(&mut self, account: &str, amount: Decimal, commodity: &str) {
+        let _ = writeln!(
+            self.text,
+            "    {}  {}",
+            account,
+            quantity(amount, commodity)
+        );
+    }
+
+    /// Starting cash of the first statement, assigned from the opening
+    /// balances account
+    fn opening(&mut self, statement: &ActivityFlexStatement) {
+        let rows: Vec<_> = statement
+            .cash_report
+            .iter()
+            .filter(|row| row.currency != "BASE_SUMMARY")
+            .collect();
+        if rows.is_empty() {
+            return;
+        }
+        let cash = self.account(self.options.cash());
+        let opening = self.account(self.options.opening_balances.clone());
+        self.header(statement.from_date - Days::new(1), "Opening balance", None);
+        for row in rows {
+            let _ = writeln!(
+                self.text,
+                "    {}  = {}",
+                cash,
+                quantity(row.starting_cash, &row.currency)
+            );
+        }
+        let _ = writeln!(self.text, "    {}\n", opening);
+    }
+
+    fn trade(&mut self, t: &Trade) {
+        if !is_execution(t) {
+            return;
+        }
+        let (Some(date), Some(units)) = (t.date(), t.quantity) else {
+            return;
+        };
+        if units.is_zero() {
+            return;
+        }
+        let options = self.options;
+        let narration = trade_narration(t, units);
+        if t.asset_category == AssetCategory::Cash {
+            let Some((base, _)) = t.symbol.split_once('.') else {
+                return;
+            };
+            if base == t.currency {
+                return;
+            }
+            let cash = self.account(options.cash());
+            self.header(date, &narration, t.transaction_id.as_deref());
+            let _ = writeln!(
+                self.text,
+                "    {}  {} @@ {}",
+                cash,
+                quantity(units, base),
+                quantity(t.proceeds.unwrap_or_default().abs(), &t.currency)
+            );
+        } else {
+            let Some(price) = t.price else {
+                return;
+            };
+            let holding = self.account(options.security(&t.symbol));
+            let unit_price = price * t.multiplier.unwrap_or(Decimal::ONE);
+            self.header(date, &narration, t.transaction_id.as_deref());
+            let _ = writeln!(
+                self.text,
+                "    {}  {} @ {}",
+                holding,
+                quantity(units, &security_name(&t.symbol)),
+                quantity(unit_price, &t.currency)
+            );
+        }
+        for posting in options.trade_postings(t) {
+            let account = self.account(posting.account);
+            self.posting(&account, posting.amount, &posting.currency);
+        }
+        self.text.push('\n');
+    }
+
+    fn cash_transaction(&mut self, c: &CashTransaction) {
+        let Some(date) = c.date() else {
+            return;
+        };
+        let cash = self.account(self.options.cash());
+        let counterpart = self.account(self.options.counterpart(c));
+        self.header(date, cash_narration(c), c.transaction_id.as_deref());
+        self.posting(&cash, c.amount, &c.currency);
+        self.posting(&counterpart, -c.amount, &c.currency);
+        self.text.push('\n');
+    }
+
+    /// Ending cash per currency, asserted with zero postings
+    fn assertions(&mut self, statement: &ActivityFlexStatement) {
+        let rows: Vec<_> = statement
+            .cash_report
+            .iter()
+            .filter(|row| row.currency != "BASE_SUMMARY")
+            .collect();
+        if rows.is_empty() {
+            return;
+        }
+        let cash = self.account(self.options.cash());
+        self.header(statement.to_date, "Ending cash", None);
+        for row in rows {
+            let _ = writeln!(
+                self.text,
+                "    {}  {} = {}",
+                cash,
+                quantity(Decimal::ZERO, &row.currency),
+                quantity(row.ending_cash, &row.currency)
+            );
+        }
+        self.text.push('\n');
+    }
+
+    /// `P` directives from mark prices and conversion rates
+    fn prices(&mut self, statement: &ActivityFlexStatement) {
+        let mut any = false;
+        for p in statement.positions.iter() {
+            if p.asset_category == AssetCategory::Cash {
+                continue;
+            }
+            let price = p.mark_price * p.multiplier.unwrap_or(Decimal::ONE);
+            let _ = writeln!(
+                self.text,
+                "P {} {} {}",
+                p.report_date,
+                commodity(&security_name(&p.symbol)),
+                quantity(price, &p.currency)
+            );
+            any = true;
+        }
+        for rate in statement.conversion_rates.iter() {
+            if rate.from_currency == rate.to_currency || rate.rate <= Decimal::ZERO {
+                continue;
+            }
+            let _ = writeln!(
+                self.text,
+                "P {} {} {}",
+                rate.report_date,
+                commodity(&rate.from_currency),
+                quantity(rate.rate, &rate.to_currency)
+            );
+            any = true;
+        }
+        if any {
+            self.text.push('\n');
+        }
+    }
+}
+
+/// An amount with its commodity after it, as `-12.5 USD`
+fn quantity(amount: Decimal, name: &str) -> String {
+    format!("{} {}", amount.normalize(), commodity(name))
+}
+
+/// `name`, quoted unless it is all letters
+fn commodity(name: &str) -> String {
+    if !name.is_empty() && name.chars().all(|c| c.is_ascii_alphabetic()) {
+        name.to_string()
+    } else {
+        format!("\"{}\"", name.replace('"', ""))
+    }
+}
+
+/// `text` on one line: transaction descriptions end at the newline
+fn line(text: &str) -> String {
+    text.replace(['\r', '\n'], " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn journal(fixture: &str) -> String {
+        let statements = crate::parse_activity_flex_all(fixture).unwrap();
+        let mut out = Vec::new();
+        write_statements(&mut out, &statements, &JournalOptions::default()).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_trades_assertions_and_prices() {
+        let journal = journal(include_str!(
+            "../../tests/fixtures/activity_daily_portfolio.xml"
+        ));
+        assert!(journal.contains("account Assets:IB:GOOGL\n"));
+        assert!(journal.contains("2025-01-15 * (20002) SELL 50 MSFT\n"));
+        assert!(journal.contains("    Assets:IB:MSFT  -50 MSFT @ 420.25 USD\n"));
+        assert!(journal
+            .contains("    Assets:IB:SPY-250221C00600000  10 \"SPY-250221C00600000\" @ 250 USD\n"));
+        assert!(journal.contains("    Assets:IB:Cash  = 145000 USD\n"));
+        assert!(journal.contains("    Assets:IB:Cash  0 USD = 150000.5 USD\n"));
+        assert!(journal.contains("P 2025-01-15 AAPL 185.5 USD\n"));
+        assert!(journal.contains("P 2025-01-15 \"AAPL-250221C00190000\" 325 USD\n"));
+        assert!(journal.contains("P 2025-01-15 EUR "));
+    }
+
+    #[test]
+    fn test_transactions_balance() {
+        let journal = journal(include_str!("../../tests/fixtures/activity_cash.xml"));
+        let transactions: Vec<&str> = journal
+            .split("\n\n")
+            .filter(|block| block.contains(" * ") && !block.contains('='))
+            .collect();
+        assert!(transactions.len() > 10);
+        for transaction in transactions {
+            let mut sums: HashMap<&str, Decimal> = HashMap::new();
+            for posting in transaction.lines().skip(1) {
+                let fields: Vec<&str> = posting.split_whitespace().collect();
+                let [_, amount, commodity] = fields[..] else {
+                    panic!("unexpected posting {:?}", posting);
+                };
+                *sums.entry(commodity).or_default() += amount.parse::<Decimal>().unwrap();
+            }
+            assert!(sums.values().all(Decimal::is_zero), "{}", transaction);
+        }
+    }
+}
@@ -0,0 +1,49 @@
+//! Exports to formats other than FLEX XML
+//!
+//! [`csv`] writes one section per file. [`ofx`] (with its Quicken QFX
+//! variant), [`qif`], [`gnucash`], [`portfolio_performance`], and
+//! [`ghostfolio`] write statements for personal finance, accounting, and
+//! portfolio tracking tools,
+//! [`beancount`] and [`ledger`] write double-entry journals with the
+//! accounts of [`journal`], [`txf`] writes realized gains for US tax
+//! software, [`koinly`] writes crypto activity for crypto tax tools,
+//! [`fix`] writes executions as FIX messages, and [`ics`] writes upcoming
+//! expiries, maturities, and dividend payments as calendar events.
+//! `json` requires the `json` feature, `arrow` and `parquet` the `arrow`
+//! feature, `polars` the `polars` feature, `postgres` the `postgres`
+//! feature, `diesel` the `diesel` feature, `sqlite` the `sqlite` feature,
+//! and `xlsx` the `xlsx` feature.
+
+#[cfg(feature = "arrow")]
+pub mod arrow;
+pub mod beancount;
+mod columns;
+pub mod csv;
+#[cfg(feature = "diesel")]
+pub mod diesel;
+pub mod fix;
+pub mod ghostfolio;
+pub mod gnucash;
+pub mod ics;
+pub mod journal;
+#[cfg(feature = "json")]
+pub mod json;
+pub mod koinly;
+pub mod ledger;
+pub mod ofx;
+#[cfg(feature = "arrow")]
+pub mod parquet;
+#[cfg(feature = "polars")]
+pub mod polars;
+pub mod portfolio_performance;
+#[cfg(feature = "postgres")]
+pub mod postgres;
+pub mod qif;
+#[cfg(any(feature = "sqlite", feature = "postgres", feature = "diesel"))]
+#[cfg_attr(not(any(feature = "sqlite", feature = "postgres")), allow(dead_code))]
+mod records;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+pub mod txf;
+#[cfg(feature = "xlsx")]
+pub mod xlsx;
@@ -0,0 +1,582 @@
+//! OFX investment statements
+//!
+//! [`write_statements`] writes an OFX 2.2 document for personal finance
+//! tools that import OFX but not FLEX. Each statement becomes one
+//! `INVSTMTTRNRS` in `INVSTMTMSGSRSV1`, with:
+//!
+//! - stock trades as `BUYSTOCK` and `SELLSTOCK`, typed `BUYTOCOVER` and
+//!   `SELLSHORT` when IB marks them as closing a short or opening one;
+//! - dividends, and interest paid on a security, as `INCOME`;
+//! - open stock positions as `POSSTOCK`.
+//!
+//! Every security those records refer to is listed once in `SECLIST`,
+//! identified by CUSIP, then ISIN, then IB's conid (`UNIQUEIDTYPE` `CONID`).
+//!
+//! Other asset categories, trade rows other than executions, position
+//! lots, cancellations, and cash transactions without a security
+//! (deposits, fees, broker interest) are skipped, as is withholding tax. Amounts follow OFX signs: `TOTAL` is the
+//! cash moved, negative for buys, and commissions and taxes are positive.
+//! Amounts in another currency than the account's carry a `CURRENCY`
+//! aggregate with IB's rate to base.
+//!
+//! [`write_qfx_statements`] writes the same document in Quicken's QFX
+//! dialect, which adds the institution (`FI`) and Intuit broker ID
+//! (`INTU.BID`) to the sign-on response. Quicken rejects web connect files
+//! without them.
+//!
+//! # Example
+//! ```
+//! use ib_flex::export::ofx::write_statements;
+//! use ib_flex::parse_activity_flex;
+//!
+//! let xml = include_str!("../../tests/fixtures/activity_minimal.xml");
+//! let statement = parse_activity_flex(xml).unwrap();
+//!
+//! let mut out = Vec::new();
+//! write_statements(&mut out, [&statement]).unwrap();
+//! let ofx = String::from_utf8(out).unwrap();
+//! assert!(ofx.contains("<BUYSTOCK>"));
+//! ```
+
+use std::collections::HashSet;
+use std::fmt::Display;
+use std::io::Write;
+
+use chrono::{NaiveDate, NaiveDateTime};
+use quick_xml::escape::escape;
+
+use crate::types::{
+    ActivityFlexStatement, AssetCategory, BuySell, CashTransaction, LevelOfDetail, LongShort,
+    OpenClose, Position, Trade,
+};
+use crate::Result;
+
+/// `BROKERID` written in `INVACCTFROM`
+pub const BROKER_ID: &str = "interactivebrokers.com";
+
+/// Currency assumed when a statement has no account information
+const DEFAULT_CURRENCY: &str = "USD";
+
+/// Institution fields of the QFX sign-on response
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QfxOptions {
+    /// `ORG` of the `FI` aggregate (default `Interactive Brokers`)
+    pub org: String,
+    /// `FID` of the `FI` aggregate (default `4705`, IB's OFX FID)
+    pub fid: String,
+    /// Intuit broker ID in `INTU.BID`, which Quicken uses to match the
+    /// file to a financial institution (default `4705`)
+    pub intu_bid: String,
+}
+
+impl Default for QfxOptions {
+    fn default() -> Self {
+        QfxOptions {
+            org: "Interactive Brokers".to_string(),
+            fid: "4705".to_string(),
+            intu_bid: "4705".to_string(),
+        }
+    }
+}
+
+/// Write `statements` as one OFX document
+///
+/// The statements keep their order; statements for the same account are
+/// written as separate statement responses.
+pub fn write_statements<'a, W, I>(writer: W, statements: I) -> Result<()>
+where
+    W: Write,
+    I: IntoIterator<Item = &'a ActivityFlexStatement>,
+{
+    write_document(writer, statements, None)
+}
+
+/// Write `statements` as one QFX document for Quicken
+///
+/// The document is the one [`write_statements`] writes, with `FI` and
+/// `INTU.BID` from `options` in the sign-on response.
+pub fn write_qfx_statements<'a, W, I>(writer: W, statements: I, options: &QfxOptions) -> Result<()>
+where
+    W: Write,
+    I: IntoIterator<Item = &'a ActivityFlexStatement>,
+{
+    write_document(writer, statements, Some(options))
+}
+
+fn write_document<'a, W, I>(mut writer: W, statements: I, qfx: Option<&QfxOptions>) -> Result<()>
+where
+    W: Write,
+    I: IntoIterator<Item = &'a ActivityFlexStatement>,
+{
+    let statements: Vec<&ActivityFlexStatement> = statements.into_iter().collect();
+    let generated = statements
+        .iter()
+        .filter_map(|s| s.when_generated_datetime().ok())
+        .max();
+
+    let mut out = Ofx::default();
+    out.line("<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"no\"?>");
+    out.line(
+        "<?OFX OFXHEADER=\"200\" VERSION=\"220\" SECURITY=\"NONE\" \
+         OLDFILEUID=\"NONE\" NEWFILEUID=\"NONE\"?>",
+    );
+    out.open("OFX");
+    out.open("SIGNONMSGSRSV1");
+    out.open("SONRS");
+    out.status();
+    if let Some(generated) = generated {
+        out.element("DTSERVER", ofx_datetime(generated));
+    }
+    out.element("LANGUAGE", "ENG");
+    if let Some(qfx) = qfx {
+        out.open("FI");
+        out.element("ORG", &qfx.org);
+        out.element("FID", &qfx.fid);
+        out.close("FI");
+        out.element("INTU.BID", &qfx.intu_bid);
+    }
+    out.close("SONRS");
+    out.close("SIGNONMSGSRSV1");
+
+    out.open("INVSTMTMSGSRSV1");
+    let mut securities = Securities::default();
+    for (i, statement) in statements.iter().enumerate() {
+        write_statement(&mut out, &mut securities, statement, i + 1);
+    }
+    out.close("INVSTMTMSGSRSV1");
+
+    if !securities.list.is_empty() {
+        out.open("SECLISTMSGSRSV1");
+        out.open("SECLIST");
+        for security in &securities.list {
+            out.open("STOCKINFO");
+            out.open("SECINFO");
+            out.sec_id(&security.id);
+            out.element("SECNAME", &security.name);
+            out.element("TICKER", &security.ticker);
+            out.close("SECINFO");
+            out.close("STOCKINFO");
+        }
+        out.close("SECLIST");
+        out.close("SECLISTMSGSRSV1");
+    }
+    out.close("OFX");
+
+    writer.write_all(out.text.as_bytes())?;
+    writer.flush()?;
+    Ok(())
+}
+
+fn write_statement(
+    out: &mut Ofx,
+    securities: &mut Securities,
+    statement: &ActivityFlexStatement,
+    trnuid: usize,
+) {
+    let base = statement
+        .account_information
+        .as_ref()
+        .and_then(|info| info.currency.as_deref())
+        .unwrap_or(DEFAULT_CURRENCY);
+
+    out.open("INVSTMTTRNRS");
+    out.element("TRNUID", trnuid);
+    out.status();
+    out.open("INVSTMTRS");
+    out.element("DTASOF", ofx_date(statement.to_date));
+    out.element("CURDEF", base);
+    out.open("INVACCTFROM");
+    out.element("BROKERID", BROKER_ID);
+    out.element("ACCTID", statement.account_id.as_str());
+    out.close("INVACCTFROM");
+
+    out.open("INVTRANLIST");
+    out.element("DTSTART", ofx_date(statement.from_date));
+    out.element("DTEND", ofx_date(statement.to_date));
+    for trade in statement.trades.iter() {
+        write_trade(out, securities, trade, base);
+    }
+    for transaction in statement.cash_transactions.iter() {
+        write_income(out, securities, transaction, statement.to_date, base);
+    }
+    out.close("INVTRANLIST");
+
+    out.open("INVPOSLIST");
+    for position in statement.positions.iter() {
+        write_position(out, securities, position, base);
+    }
+    out.close("INVPOSLIST");
+    out.close("INVSTMTRS");
+    out.close("INVSTMTTRNRS");
+}
+
+fn write_trade(out: &mut Ofx, securities: &mut Securities, t: &Trade, base: &str) {
+    if t.asset_category != AssetCategory::Stock
+        || !matches!(t.level_of_detail, None | Some(LevelOfDetail::Execution))
+    {
+        return;
+    }
+    let (Some(quantity), Some(price), Some(date)) = (t.quantity, t.price, t.trade_date) else {
+        return;
+    };
+    let (tag, kind, action) = match (&t.buy_sell, &t.open_close) {
+        (Some(BuySell::Buy), Some(OpenClose::Close)) => ("BUYSTOCK", "INVBUY", "BUYTOCOVER"),
+        (Some(BuySell::Buy), _) => ("BUYSTOCK", "INVBUY", "BUY"),
+        (Some(BuySell::Sell), Some(OpenClose::Open)) => ("SELLSTOCK", "INVSELL", "SELLSHORT"),
+        (Some(BuySell::Sell), _) => ("SELLSTOCK", "INVSELL", "SELL"),
+        _ => return,
+    };
+    let fitid = t
+        .transaction_id
+        .clone()
+        .or_else(|| t.trade_id.as_ref().map(|id| id.to_string()))
+        .or_else(|| t.exec_id.as_ref().map(|id| id.to_string()))
+        .unwrap_or_else(|| {
+            format!(
+                "{}-{}-{}",
+                t.conid,
+                ofx_time_or_date(t.trade_time, date),
+                quantity
+            )
+        });
+    let id = securities.add(
+        t.cusip.as_deref(),
+        t.isin.as_deref(),
+        &t.conid.to_string(),
+        &t.symbol,
+        t.description.as_deref(),
+    );
+    let commission = t.commission.unwrap_or_default();
+    let taxes = t.taxes.unwrap_or_default();
+    let total = t
+        .net_cash
+        .unwrap_or_else(|| t.proceeds.unwrap_or_default() + commission + taxes);
+    let sub_account = if matches!(action, "BUYTOCOVER" | "SELLSHORT") {
+        "SHORT"
+    } else {
+        "CASH"
+    };
+
+    out.open(tag);
+    out.open(kind);
+    out.open("INVTRAN");
+    out.element("FITID", fitid);
+    out.element("DTTRADE", ofx_time_or_date(t.trade_time, date));
+    if let Some(settle) = t.settle_date {
+        out.element("DTSETTLE", ofx_date(settle));
+    }
+    if let Some(description) = t.description.as_deref() {
+        out.element("MEMO", description);
+    }
+    out.close("INVTRAN");
+    out.sec_id(&id);
+    out.element("UNITS", quantity.normalize());
+    out.element("UNITPRICE", price.normalize());
+    out.element("COMMISSION", (-commission).normalize());
+    out.element("TAXES", (-taxes).normalize());
+    out.element("TOTAL", total.normalize());
+    out.currency(&t.currency, t.fx_rate_to_base, base);
+    out.element("SUBACCTSEC", sub_account);
+    out.element("SUBACCTFUND", "CASH");
+    out.close(kind);
+    out.element(
+        if kind == "INVBUY" {
+            "BUYTYPE"
+        } else {
+            "SELLTYPE"
+        },
+        action,
+    );
+    out.close(tag);
+}
+
+fn write_income(
+    out: &mut Ofx,
+    securities: &mut Securities,
+    c: &CashTransaction,
+    as_of: NaiveDate,
+    base: &str,
+) {
+    let income_type = if c.is_dividend() {
+        "DIV"
+    } else if c.is_interest() {
+        "INTEREST"
+    } else {
+        return;
+    };
+    let Some(conid) = c.conid.as_deref().filter(|conid| !conid.is_empty()) else {
+        return;
+    };
+    let date = c.date.or(c.report_date).unwrap_or(as_of);
+    let fitid = c
+        .transaction_id
+        .clone()
+        .unwrap_or_else(|| format!("{}-{}-{}-{}", conid, ofx_date(date), income_type, c.amount));
+    let symbol = c.symbol.as_deref().unwrap_or(conid);
+    let id = securities.add(
+        c.cusip.as_deref(),
+        c.isin.as_deref(),
+        conid,
+        symbol,
+        c.description.as_deref(),
+    );
+
+    out.open("INCOME");
+    out.open("INVTRAN");
+    out.element("FITID", fitid);
+    out.element("DTTRADE", ofx_date(date));
+    if let Some(settle) = c.settle_date {
+        out.element("DTSETTLE", ofx_date(settle));
+    }
+    if let Some(description) = c.description.as_deref() {
+        out.element("MEMO", description);
+    }
+    out.close("INVTRAN");
+    out.sec_id(&id);
+    out.element("INCOMETYPE", income_type);
+    out.element("TOTAL", c.amount.normalize());
+    out.element("SUBACCTSEC", "CASH");
+    out.element("SUBACCTFUND", "CASH");
+    out.currency(&c.currency, c.fx_rate_to_base, base);
+    out.close("INCOME");
+}
+
+fn write_position(out: &mut Ofx, securities: &mut Securities, p: &Position, base: &str) {
+    if p.asset_category != AssetCategory::Stock
+        || !matches!(p.level_of_detail, None | Some(LevelOfDetail::Summary))
+    {
+        return;
+    }
+    let short = match &p.side {
+        Some(LongShort::Short) => true,
+        Some(LongShort::Long) => false,
+        _ => p.quantity.is_sign_negative(),
+    };
+    let id = securities.add(
+        p.cusip.as_deref(),
+        p.isin.as_deref(),
+        &p.conid.to_string(),
+        &p.symbol,
+        p.description.as_deref(),
+    );
+
+    out.open("POSSTOCK");
+    out.open("INVPOS");
+    out.sec_id(&id);
+    out.element("HELDINACCT", if short { "SHORT" } else { "CASH" });
+    out.element("POSTYPE", if short { "SHORT" } else { "LONG" });
+    out.element("UNITS", p.quantity.normalize());
+    out.element("UNITPRICE", p.mark_price.normalize());
+    out.element("MKTVAL", p.position_value.normalize());
+    out.element("DTPRICEASOF", ofx_date(p.report_date));
+    out.currency(&p.currency, p.fx_rate_to_base, base);
+    out.close("INVPOS");
+    out.close("POSSTOCK");
+}
+
+/// `UNIQUEID` and `UNIQUEIDTYPE` of a security
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct SecId {
+    id: String,
+    kind: &'static str,
+}
+
+struct Security {
+    id: SecId,
+    name: String,
+    ticker: String,
+}
+
+/// Securities referred to so far, in the order first seen
+#[derive(Default)]
+struct Securities {
+    seen: HashSet<SecId>,
+    list: Vec<Security>,
+}
+
+impl Securities {
+    fn add(
+        &mut self,
+        cusip: Option<&str>,
+        isin: Option<&str>,
+        conid: &str,
+        symbol: &str,
+        description: Option<&str>,
+    ) -> SecId {
+        let present = |value: Option<&str>| value.filter(|v| !v.is_empty()).map(str::to_string);
+        let id = match (present(cusip), present(isin)) {
+            (Some(cusip), _) => SecId {
+                id: cusip,
+                kind: "CUSIP",
+            },
+            (None, Some(isin)) => SecId {
+                id: isin,
+                kind: "ISIN",
+            },
+            (None, None) => SecId {
+                id: conid.to_string(),
+                kind: "CONID",
+            },
+        };
+        if self.seen.insert(id.clone()) {
+            self.list.push(Security {
+                id: id.clone(),
+                name: description.unwrap_or(symbol).to_string(),
+                ticker: symbol.to_string(),
+            });
+        }
+        id
+    }
+}
+
+/// OFX text being built, one element per line
+#[derive(Default)]
+struct Ofx {
+    text: String,
+    depth: usize,
+}
+
+impl Ofx {
+    fn line(&mut self, line: &str) {
+        for _ in 0..self.depth {
+            self.text.push_str("  ");
+        }
+        self.text.push_str(line);
+        self.text.push('\n');
+    }
+
+    fn open(&mut self, tag: &str) {
+        self.line(&format!("<{}>", tag));
+        self.depth += 1;
+    }
+
+    fn close(&mut self, tag: &str) {
+        self.depth -= 1;
+        self.line(&format!("</{}>", tag));
+    }
+
+    fn element(&mut self, tag: &str, value: impl Display) {
+        let value = value.to_string();
+        self.line(&format!("<{0}>{1}</{0}>", tag, escape(value.as_str())));
+    }
+
+    fn status(&mut self) {
+        self.open("STATUS");
+        self.element("CODE", 0);
+        self.element("SEVERITY", "INFO");
+        self.close("STATUS");
+    }
+
+    fn sec_id(&mut self, id: &SecId) {
+        self.open("SECID");
+        self.element("UNIQUEID", &id.id);
+        self.element("UNIQUEIDTYPE", id.kind);
+        self.close("SECID");
+    }
+
+    /// `CURRENCY` aggregate for amounts not in the account's currency
+    fn currency(&mut self, currency: &str, rate: Option<rust_decimal::Decimal>, base: &str) {
+        let Some(rate) = rate.filter(|_| currency != base) else {
+            return;
+        };
+        self.open("CURRENCY");
+        self.element("CURRATE", rate.normalize());
+        self.element("CURSYM", currency);
+        self.close("CURRENCY");
+    }
+}
+
+fn ofx_date(date: NaiveDate) -> String {
+    date.format("%Y%m%d").to_string()
+}
+
+fn ofx_datetime(dt: NaiveDateTime) -> String {
+    dt.format("%Y%m%d%H%M%S").to_string()
+}
+
+/// `dt`, or `date` when the time is unknown
+fn ofx_time_or_date(dt: Option<NaiveDateTime>, date: NaiveDate) -> String {
+    dt.map_or_else(|| ofx_date(date), ofx_datetime)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ofx(fixture: &str) -> (ActivityFlexStatement, String) {
+        let statement = crate::parse_activity_flex(fixture).unwrap();
+        let mut out = Vec::new();
+        write_statements(&mut out, [&statement]).unwrap();
+        (statement, String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn test_trades_and_positions() {
+        let (statement, ofx) = ofx(include_str!(
+            "../../tests/fixtures/activity_daily_portfolio.xml"
+        ));
+        assert!(ofx.contains("<ACCTID>U1234567</ACCTID>"));
+
+        let stock = |category: &AssetCategory| *category == AssetCategory::Stock;
+        let buys = statement
+            .trades
+            .iter()
+            .filter(|t| stock(&t.asset_category) && t.buy_sell == Some(BuySell::Buy))
+            .count();
+        let positions = statement
+            .positions
+            .iter()
+            .filter(|p| stock(&p.asset_category))
+            .count();
+        assert_eq!(ofx.matches("<BUYSTOCK>").count(), buys);
+        assert_eq!(ofx.matches("<POSSTOCK>").count(), positions);
+        assert!(ofx.contains("<SELLTYPE>SELL</SELLTYPE>"));
+        assert!(ofx.contains("<POSTYPE>SHORT</POSTYPE>"));
+        assert!(ofx.contains("<UNIQUEID>037833100</UNIQUEID>"));
+        assert!(ofx.contains("<CURSYM>CAD</CURSYM>"));
+        assert!(!ofx.contains("<UNIQUEID>700222333</UNIQUEID>"));
+
+        let mut reader = quick_xml::Reader::from_str(&ofx);
+        loop {
+            match reader.read_event().unwrap() {
+                quick_xml::events::Event::Eof => break,
+                _ => continue,
+            }
+        }
+    }
+
+    #[test]
+    fn test_qfx_sign_on() {
+        let statement =
+            crate::parse_activity_flex(include_str!("../../tests/fixtures/activity_minimal.xml"))
+                .unwrap();
+        let mut out = Vec::new();
+        write_qfx_statements(&mut out, [&statement], &QfxOptions::default()).unwrap();
+        let qfx = String::from_utf8(out).unwrap();
+        let sign_on = &qfx[qfx.find("<SONRS>").unwrap()..qfx.find("</SONRS>").unwrap()];
+        assert!(sign_on.contains("<ORG>Interactive Brokers</ORG>"));
+        assert!(sign_on.contains("<FID>4705</FID>"));
+        assert!(sign_on.contains("<INTU.BID>4705</INTU.BID>"));
+
+        let mut out = Vec::new();
+        write_statements(&mut out, [&statement]).unwrap();
+        let ofx = String::from_utf8(out).unwrap();
+        assert!(!ofx.contains("INTU.BID"));
+        let after_sign_on = |doc: &str| doc[doc.find("</SONRS>").unwrap()..].to_string();
+        assert_eq!(after_sign_on(&qfx), after_sign_on(&ofx));
+    }
+
+    #[test]
+    fn test_income() {
+        let (statement, ofx) = ofx(include_str!("../../tests/fixtures/activity_cash.xml"));
+        let expected = statement
+            .cash_transactions
+            .iter()
+            .filter(|c| c.is_dividend() || c.is_interest())
+            .filter(|c| c.conid.as_deref().is_some_and(|id| !id.is_empty()))
+            .count();
+        assert!(expected > 0);
+        assert_eq!(ofx.matches("<INCOME>").count(), expected);
+        assert!(ofx.contains("<INCOMETYPE>DIV</INCOMETYPE>"));
+    }
+}
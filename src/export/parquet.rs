@@ -0,0 +1,247 @@
+//! Parquet export per section
+//!
+//! The same sections and columns as [`csv`](super::csv), written as Parquet
+//! with the column types of [`arrow`](super::arrow): amounts are `DECIMAL(38, s)` with the largest scale
+//! in the column, dates are `DATE`, date/times are `TIMESTAMP(MICROS)`
+//! without a time zone, and everything else is a UTF-8 string. Missing
+//! values are null.
+//!
+//! Each writer takes any number of rows, so a file can hold one statement
+//! or years of them. [`write_statements`] writes every section of many
+//! statements at once, one file per section.
+//!
+//! Requires the `arrow` feature. The Arrow and Parquet crates need a newer
+//! Rust than the rest of this crate.
+//!
+//! # Example
+//! ```
+//! use ib_flex::export::parquet::write_trades;
+//! use ib_flex::parse_activity_flex;
+//!
+//! let xml = include_str!("../../tests/fixtures/activity_minimal.xml");
+//! let statement = parse_activity_flex(xml).unwrap();
+//!
+//! let mut out = Vec::new();
+//! write_trades(&mut out, &statement.trades).unwrap();
+//! assert!(out.starts_with(b"PAR1"));
+//! ```
+
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use parquet::arrow::ArrowWriter;
+
+use super::arrow::{arrow_error, record_batch};
+use super::columns::{
+    Column, ACTION_COLUMNS, CASH_COLUMNS, DAILY_POSITION_COLUMNS, DIVIDEND_COLUMNS,
+    POSITION_COLUMNS, TRADE_COLUMNS,
+};
+use super::csv::daily_positions;
+use crate::types::{ActivityFlexStatement, CashTransaction, CorporateAction, Position, Trade};
+use crate::Result;
+
+/// Trades, one row each, with the columns of [`csv::write_trades`]
+///
+/// [`csv::write_trades`]: super::csv::write_trades
+pub fn write_trades<'a, W, I>(writer: W, trades: I) -> Result<()>
+where
+    W: Write + Send,
+    I: IntoIterator<Item = &'a Trade>,
+{
+    write_rows(writer, TRADE_COLUMNS, trades)
+}
+
+/// Open positions, one row each, with the columns of
+/// [`csv::write_positions`]
+///
+/// [`csv::write_positions`]: super::csv::write_positions
+pub fn write_positions<'a, W, I>(writer: W, positions: I) -> Result<()>
+where
+    W: Write + Send,
+    I: IntoIterator<Item = &'a Position>,
+{
+    write_rows(writer, POSITION_COLUMNS, positions)
+}
+
+/// Position history of many statements, one row per position per day,
+/// with the rows and columns of [`csv::write_daily_positions`]
+///
+/// [`csv::write_daily_positions`]: super::csv::write_daily_positions
+pub fn write_daily_positions<'a, W, I>(writer: W, statements: I) -> Result<()>
+where
+    W: Write + Send,
+    I: IntoIterator<Item = &'a ActivityFlexStatement>,
+{
+    write_rows(writer, DAILY_POSITION_COLUMNS, daily_positions(statements))
+}
+
+/// Cash transactions, one row each, with the columns of
+/// [`csv::write_cash_transactions`]
+///
+/// [`csv::write_cash_transactions`]: super::csv::write_cash_transactions
+pub fn write_cash_transactions<'a, W, I>(writer: W, transactions: I) -> Result<()>
+where
+    W: Write + Send,
+    I: IntoIterator<Item = &'a CashTransaction>,
+{
+    write_rows(writer, CASH_COLUMNS, transactions)
+}
+
+/// Corporate actions, one row each, with the columns of
+/// [`csv::write_corporate_actions`]
+///
+/// [`csv::write_corporate_actions`]: super::csv::write_corporate_actions
+pub fn write_corporate_actions<'a, W, I>(writer: W, actions: I) -> Result<()>
+where
+    W: Write + Send,
+    I: IntoIterator<Item = &'a CorporateAction>,
+{
+    write_rows(writer, ACTION_COLUMNS, actions)
+}
+
+/// Dividends and the tax withheld on them, with the columns of
+/// [`csv::write_dividends`]
+///
+/// Other cash transactions are skipped.
+///
+/// [`csv::write_dividends`]: super::csv::write_dividends
+pub fn write_dividends<'a, W, I>(writer: W, transactions: I) -> Result<()>
+where
+    W: Write + Send,
+    I: IntoIterator<Item = &'a CashTransaction>,
+{
+    let rows = transactions
+        .into_iter()
+        .filter(|tx| tx.is_dividend() || tx.is_withholding_tax());
+    write_rows(writer, DIVIDEND_COLUMNS, rows)
+}
+
+/// Write every section of `statements` into `dir`, one file per section
+///
+/// The files are `trades.parquet`, `positions.parquet`,
+/// `cash_transactions.parquet`, `corporate_actions.parquet`, and
+/// `dividends.parquet`, replacing any already there. Each holds the rows of
+/// all statements, in order. Returns the paths written.
+pub fn write_statements<'a, I>(dir: impl AsRef<Path>, statements: I) -> Result<Vec<PathBuf>>
+where
+    I: IntoIterator<Item = &'a ActivityFlexStatement>,
+{
+    let statements: Vec<&ActivityFlexStatement> = statements.into_iter().collect();
+    let dir = dir.as_ref();
+    let path = |name: &str| dir.join(format!("{}.parquet", name));
+    let paths = vec![
+        path("trades"),
+        path("positions"),
+        path("cash_transactions"),
+        path("corporate_actions"),
+        path("dividends"),
+    ];
+
+    write_trades(
+        File::create(&paths[0])?,
+        statements.iter().flat_map(|s| &s.trades),
+    )?;
+    write_positions(
+        File::create(&paths[1])?,
+        statements.iter().flat_map(|s| &s.positions),
+    )?;
+    write_cash_transactions(
+        File::create(&paths[2])?,
+        statements.iter().flat_map(|s| &s.cash_transactions),
+    )?;
+    write_corporate_actions(
+        File::create(&paths[3])?,
+        statements.iter().flat_map(|s| &s.corporate_actions),
+    )?;
+    write_dividends(
+        File::create(&paths[4])?,
+        statements.iter().flat_map(|s| &s.cash_transactions),
+    )?;
+    Ok(paths)
+}
+
+fn write_rows<'a, T, W, I>(writer: W, columns: &[Column<T>], rows: I) -> Result<()>
+where
+    T: 'a,
+    W: Write + Send,
+    I: IntoIterator<Item = &'a T>,
+{
+    let batch = record_batch(columns, rows)?;
+    let mut writer = ArrowWriter::try_new(writer, batch.schema(), None).map_err(arrow_error)?;
+    writer.write(&batch).map_err(arrow_error)?;
+    writer.close().map_err(arrow_error)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_array::cast::AsArray;
+    use arrow_array::types::{Date32Type, Decimal128Type};
+    use arrow_array::{Array, RecordBatch};
+    use arrow_schema::{DataType, TimeUnit};
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+    use rust_decimal::Decimal;
+
+    fn read(path: &Path) -> Vec<RecordBatch> {
+        ParquetRecordBatchReaderBuilder::try_new(File::open(path).unwrap())
+            .unwrap()
+            .build()
+            .unwrap()
+            .map(|batch| batch.unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_statements_round_trip_with_types() {
+        let xml = include_str!("../../tests/fixtures/activity_backfill.xml");
+        let statements = crate::parse_activity_flex_all(xml).unwrap();
+        let dir = std::env::temp_dir().join(format!("ib-flex-parquet-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let paths = write_statements(&dir, &statements).unwrap();
+        assert_eq!(paths.len(), 5);
+
+        let trades = &read(&paths[0])[0];
+        let expected: Vec<&Trade> = statements.iter().flat_map(|s| &s.trades).collect();
+        assert!(!expected.is_empty());
+        assert_eq!(trades.num_rows(), expected.len());
+        assert_eq!(trades.num_columns(), TRADE_COLUMNS.len());
+
+        let schema = trades.schema();
+        let field = |name: &str| schema.field_with_name(name).unwrap().data_type().clone();
+        assert_eq!(field("trade_date"), DataType::Date32);
+        assert_eq!(
+            field("trade_time"),
+            DataType::Timestamp(TimeUnit::Microsecond, None)
+        );
+        assert_eq!(field("symbol"), DataType::Utf8);
+        let DataType::Decimal128(38, scale) = field("price") else {
+            panic!("price should be a decimal");
+        };
+
+        let prices = trades
+            .column_by_name("price")
+            .unwrap()
+            .as_primitive::<Decimal128Type>();
+        for (i, trade) in expected.iter().enumerate() {
+            let price = (!prices.is_null(i))
+                .then(|| Decimal::from_i128_with_scale(prices.value(i), scale as u32));
+            assert_eq!(price, trade.price);
+        }
+        let dates = trades
+            .column_by_name("trade_date")
+            .unwrap()
+            .as_primitive::<Date32Type>();
+        assert_eq!(
+            Date32Type::to_naive_date_opt(dates.value(0)),
+            expected[0].trade_date
+        );
+
+        let positions: usize = read(&paths[1]).iter().map(|b| b.num_rows()).sum();
+        let expected: usize = statements.iter().map(|s| s.positions.len()).sum();
+        assert_eq!(positions, expected);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
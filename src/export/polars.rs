@@ -0,0 +1,180 @@
+//! Polars DataFrames per section
+//!
+//! [`ActivityFlexStatement::trades_df`], [`positions_df`], and [`cash_df`]
+//! turn a statement's sections into DataFrames with the columns of the
+//! [`csv`](super::csv) writers. The functions here do the same for rows
+//! gathered from many statements. Amounts are `Decimal(38, s)` with the
+//! largest scale in the column, dates are `Date`, date/times are
+//! `Datetime(Microseconds)` without a time zone, and everything else is a
+//! string. Missing values are null.
+//!
+//! Requires the `polars` feature. Polars needs a newer Rust than the rest
+//! of this crate.
+//!
+//! # Example
+//! ```
+//! use ib_flex::parse_activity_flex;
+//!
+//! let xml = include_str!("../../tests/fixtures/activity_minimal.xml");
+//! let statement = parse_activity_flex(xml).unwrap();
+//!
+//! let trades = statement.trades_df().unwrap();
+//! assert_eq!(trades.height(), statement.trades.len());
+//! assert!(trades.column("fifo_pnl_realized").is_ok());
+//! ```
+//!
+//! [`positions_df`]: ActivityFlexStatement::positions_df
+//! [`cash_df`]: ActivityFlexStatement::cash_df
+
+use polars::prelude::{DataFrame, IntoColumn, NamedFrom, Series, TimeUnit};
+
+use super::columns::{rescale, Cell, Column, Kind, CASH_COLUMNS, POSITION_COLUMNS, TRADE_COLUMNS};
+use crate::types::{ActivityFlexStatement, CashTransaction, Position, Trade};
+use crate::{ParseError, Result};
+
+impl ActivityFlexStatement {
+    /// Trades as a DataFrame, one row each
+    pub fn trades_df(&self) -> Result<DataFrame> {
+        trades_df(&self.trades)
+    }
+
+    /// Open positions as a DataFrame, one row each
+    pub fn positions_df(&self) -> Result<DataFrame> {
+        positions_df(&self.positions)
+    }
+
+    /// Cash transactions as a DataFrame, one row each
+    pub fn cash_df(&self) -> Result<DataFrame> {
+        cash_df(&self.cash_transactions)
+    }
+}
+
+/// Trades as a DataFrame, with the columns of [`csv::write_trades`]
+///
+/// [`csv::write_trades`]: super::csv::write_trades
+pub fn trades_df<'a, I>(trades: I) -> Result<DataFrame>
+where
+    I: IntoIterator<Item = &'a Trade>,
+{
+    data_frame(TRADE_COLUMNS, trades)
+}
+
+/// Open positions as a DataFrame, with the columns of
+/// [`csv::write_positions`]
+///
+/// [`csv::write_positions`]: super::csv::write_positions
+pub fn positions_df<'a, I>(positions: I) -> Result<DataFrame>
+where
+    I: IntoIterator<Item = &'a Position>,
+{
+    data_frame(POSITION_COLUMNS, positions)
+}
+
+/// Cash transactions as a DataFrame, with the columns of
+/// [`csv::write_cash_transactions`]
+///
+/// [`csv::write_cash_transactions`]: super::csv::write_cash_transactions
+pub fn cash_df<'a, I>(transactions: I) -> Result<DataFrame>
+where
+    I: IntoIterator<Item = &'a CashTransaction>,
+{
+    data_frame(CASH_COLUMNS, transactions)
+}
+
+fn data_frame<'a, T, I>(columns: &[Column<T>], rows: I) -> Result<DataFrame>
+where
+    T: 'a,
+    I: IntoIterator<Item = &'a T>,
+{
+    let rows: Vec<&T> = rows.into_iter().collect();
+    let series = columns
+        .iter()
+        .map(|column| {
+            let cells = rows.iter().map(|row| (column.value)(row));
+            to_series(column.name, column.kind, cells).map(IntoColumn::into_column)
+        })
+        .collect::<Result<Vec<_>>>()?;
+    DataFrame::new(rows.len(), series).map_err(polars_error)
+}
+
+/// One column of cells as a Series of `kind`
+fn to_series<'a>(name: &str, kind: Kind, cells: impl Iterator<Item = Cell<'a>>) -> Result<Series> {
+    let epoch = chrono::NaiveDate::from_ymd_opt(1970, 1, 1).unwrap_or_default();
+    Ok(match kind {
+        Kind::Text => {
+            let values: Vec<_> = cells
+                .map(|cell| match cell {
+                    Cell::Text(text) => text,
+                    _ => None,
+                })
+                .collect();
+            let values: Vec<Option<&str>> = values.iter().map(|v| v.as_deref()).collect();
+            Series::new(name.into(), values)
+        }
+        Kind::Date => {
+            let days: Vec<Option<i32>> = cells
+                .map(|cell| match cell {
+                    Cell::Date(date) => date.map(|d| (d - epoch).num_days() as i32),
+                    _ => None,
+                })
+                .collect();
+            Series::new(name.into(), days).into_date()
+        }
+        Kind::DateTime => {
+            let micros: Vec<Option<i64>> = cells
+                .map(|cell| match cell {
+                    Cell::DateTime(dt) => dt.map(|dt| dt.and_utc().timestamp_micros()),
+                    _ => None,
+                })
+                .collect();
+            Series::new(name.into(), micros).into_datetime(TimeUnit::Microseconds, None)
+        }
+        Kind::Decimal => {
+            let values: Vec<_> = cells
+                .map(|cell| match cell {
+                    Cell::Decimal(value) => value,
+                    _ => None,
+                })
+                .collect();
+            let (scale, mantissas) = rescale(&values).map_err(ParseError::PolarsError)?;
+            Series::new(name.into(), mantissas)
+                .into_decimal(38, scale as usize)
+                .map_err(polars_error)?
+        }
+    })
+}
+
+fn polars_error(e: impl std::fmt::Display) -> ParseError {
+    ParseError::PolarsError(e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use polars::prelude::DataType;
+
+    #[test]
+    fn test_statement_frames() {
+        let xml = include_str!("../../tests/fixtures/activity_backfill.xml");
+        let statements = crate::parse_activity_flex_all(xml).unwrap();
+
+        let trades = trades_df(statements.iter().flat_map(|s| &s.trades)).unwrap();
+        let expected: usize = statements.iter().map(|s| s.trades.len()).sum();
+        assert_eq!(trades.height(), expected);
+        assert_eq!(trades.width(), TRADE_COLUMNS.len());
+
+        let dtype = |name: &str| trades.column(name).unwrap().dtype().clone();
+        assert_eq!(dtype("trade_date"), DataType::Date);
+        assert_eq!(
+            dtype("trade_time"),
+            DataType::Datetime(TimeUnit::Microseconds, None)
+        );
+        assert_eq!(dtype("symbol"), DataType::String);
+        assert!(matches!(dtype("price"), DataType::Decimal(38, _)));
+
+        let cash = statements[0].cash_df().unwrap();
+        assert_eq!(cash.height(), statements[0].cash_transactions.len());
+        let positions = statements[0].positions_df().unwrap();
+        assert_eq!(positions.height(), statements[0].positions.len());
+    }
+}
@@ -0,0 +1,228 @@
+//! Portfolio Performance CSV export
+//!
+//! [`write_statements`] writes trades and cash transactions as one CSV file
+//! for the "Account Transactions" import of
+//! [Portfolio Performance](https://www.portfolio-performance.info), which
+//! books buys and sells against both the cash account and the securities
+//! account:
+//!
+//! | IB row | Type |
+//! |---|---|
+//! | Trade | `Buy` or `Sell` |
+//! | Dividend or payment in lieu | `Dividend` (`Removal` if reversed) |
+//! | Withholding tax | `Taxes` or `Tax Refund` |
+//! | Fee or commission adjustment | `Fees` or `Fees Refund` |
+//! | Interest | `Interest` or `Interest Charge` |
+//! | Anything else | `Deposit` or `Removal`, by sign |
+//!
+//! Values, shares, fees, and taxes are written without a sign, as the
+//! import expects; a buy's value includes its fees and taxes. Shares of
+//! options and futures are contracts times the multiplier, so that value
+//! over shares is the price. FX conversions are skipped. The cash account
+//! of each row is named `{account ID} {currency}` and the securities
+//! account after the IB account ID; rename accounts in Portfolio
+//! Performance to match, or map the columns in the import dialog.
+//!
+//! [`CsvOptions`] sets the delimiter, date format, and decimal separator to
+//! pick in the import dialog.
+//!
+//! # Example
+//! ```
+//! use ib_flex::export::csv::CsvOptions;
+//! use ib_flex::export::portfolio_performance::write_statements;
+//! use ib_flex::parse_activity_flex;
+//!
+//! let xml = include_str!("../../tests/fixtures/activity_minimal.xml");
+//! let statement = parse_activity_flex(xml).unwrap();
+//!
+//! let mut out = Vec::new();
+//! write_statements(&mut out, [&statement], &CsvOptions::default()).unwrap();
+//! let csv = String::from_utf8(out).unwrap();
+//! assert!(csv.starts_with("Date,Time,Type,Security Name,ISIN,Ticker Symbol,"));
+//! ```
+
+use std::io::Write;
+
+use chrono::{NaiveDate, NaiveTime};
+use rust_decimal::Decimal;
+
+use super::columns::PORTFOLIO_PERFORMANCE_COLUMNS;
+use super::csv::{write_rows, CsvOptions};
+use crate::types::{ActivityFlexStatement, AssetCategory, CashTransaction, Dated, Trade};
+use crate::Result;
+
+/// One row of the import
+pub(super) struct Row {
+    pub(super) date: NaiveDate,
+    pub(super) time: Option<NaiveTime>,
+    pub(super) kind: &'static str,
+    pub(super) security_name: Option<String>,
+    pub(super) isin: Option<String>,
+    pub(super) symbol: Option<String>,
+    pub(super) shares: Option<Decimal>,
+    pub(super) value: Decimal,
+    pub(super) currency: String,
+    pub(super) fees: Option<Decimal>,
+    pub(super) taxes: Option<Decimal>,
+    pub(super) note: Option<String>,
+    pub(super) cash_account: String,
+    pub(super) securities_account: String,
+}
+
+/// Write the trades and cash transactions of `statements` for Portfolio
+/// Performance
+///
+/// Columns: `Date`, `Time`, `Type`, `Security Name`, `ISIN`,
+/// `Ticker Symbol`, `Shares`, `Value`, `Transaction Currency`, `Fees`,
+/// `Taxes`, `Note`, `Cash Account`, `Securities Account`. Rows without a
+/// date are skipped.
+pub fn write_statements<'a, W, I>(writer: W, statements: I, options: &CsvOptions) -> Result<()>
+where
+    W: Write,
+    I: IntoIterator<Item = &'a ActivityFlexStatement>,
+{
+    let mut rows = Vec::new();
+    for statement in statements {
+        rows.extend(statement.trades.iter().filter_map(trade_row));
+        rows.extend(statement.cash_transactions.iter().filter_map(cash_row));
+    }
+    write_rows(writer, PORTFOLIO_PERFORMANCE_COLUMNS, &rows, options)
+}
+
+fn trade_row(t: &Trade) -> Option<Row> {
+    if !super::journal::is_execution(t) || t.asset_category == AssetCategory::Cash {
+        return None;
+    }
+    let (date, quantity) = (t.date()?, t.quantity?);
+    if quantity.is_zero() {
+        return None;
+    }
+    let commission = t.commission.unwrap_or_default();
+    let taxes = t.taxes.unwrap_or_default();
+    let value = t
+        .net_cash
+        .unwrap_or_else(|| t.proceeds.unwrap_or_default() + commission + taxes);
+    let nonzero = |amount: Decimal| Some(amount.abs()).filter(|a| !a.is_zero());
+    Some(Row {
+        date,
+        time: t.trade_time.map(|dt| dt.time()),
+        kind: if quantity.is_sign_positive() {
+            "Buy"
+        } else {
+            "Sell"
+        },
+        security_name: t.description.clone(),
+        isin: t.isin.clone(),
+        symbol: Some(t.symbol.clone()),
+        shares: Some((quantity * t.multiplier.unwrap_or(Decimal::ONE)).abs()),
+        value: value.abs(),
+        currency: t.currency.clone(),
+        fees: nonzero(commission),
+        taxes: nonzero(taxes),
+        note: t.transaction_id.clone(),
+        cash_account: format!("{} {}", t.account_id.as_str(), t.currency),
+        securities_account: t.account_id.as_str().to_string(),
+    })
+}
+
+fn cash_row(c: &CashTransaction) -> Option<Row> {
+    let date = c.date()?;
+    let received = c.amount.is_sign_positive();
+    let (kind, security) = if c.is_dividend() {
+        (if received { "Dividend" } else { "Removal" }, received)
+    } else if c.is_withholding_tax() {
+        (if received { "Tax Refund" } else { "Taxes" }, true)
+    } else if c.is_fee() {
+        (if received { "Fees Refund" } else { "Fees" }, false)
+    } else if c.is_interest() {
+        (
+            if received {
+                "Interest"
+            } else {
+                "Interest Charge"
+            },
+            false,
+        )
+    } else {
+        (if received { "Deposit" } else { "Removal" }, false)
+    };
+    let security = |value: &Option<String>| value.clone().filter(|v| security && !v.is_empty());
+    Some(Row {
+        date,
+        time: None,
+        kind,
+        security_name: security(&c.description),
+        isin: security(&c.isin),
+        symbol: security(&c.symbol),
+        shares: None,
+        value: c.amount.abs(),
+        currency: c.currency.clone(),
+        fees: None,
+        taxes: None,
+        note: c.description.clone(),
+        cash_account: format!("{} {}", c.account_id.as_str(), c.currency),
+        securities_account: c.account_id.as_str().to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows(fixture: &str) -> Vec<Vec<String>> {
+        let statement = crate::parse_activity_flex(fixture).unwrap();
+        let mut out = Vec::new();
+        let options = CsvOptions {
+            delimiter: ';',
+            ..Default::default()
+        };
+        write_statements(&mut out, [&statement], &options).unwrap();
+        String::from_utf8(out)
+            .unwrap()
+            .lines()
+            .skip(1)
+            .map(|line| line.split(';').map(str::to_string).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_trades() {
+        let rows = rows(include_str!(
+            "../../tests/fixtures/activity_daily_portfolio.xml"
+        ));
+        let sell = rows
+            .iter()
+            .find(|row| row[5] == "MSFT")
+            .expect("the MSFT sale");
+        assert_eq!(sell[0], "2025-01-15");
+        assert_eq!(sell[2], "Sell");
+        assert_eq!(sell[6], "50");
+        assert_eq!(sell[7], "21011.75");
+        assert_eq!(sell[8], "USD");
+        assert_eq!(sell[9], "0.75");
+        assert_eq!(sell[12], "U1234567 USD");
+
+        let option = rows
+            .iter()
+            .find(|row| row[5].starts_with("SPY"))
+            .expect("the SPY option");
+        assert_eq!(option[2], "Buy");
+        assert_eq!(option[6], "1000");
+    }
+
+    #[test]
+    fn test_cash_transaction_types() {
+        let rows = rows(include_str!("../../tests/fixtures/activity_cash.xml"));
+        let kinds: Vec<&str> = rows.iter().map(|row| row[2].as_str()).collect();
+        for kind in ["Dividend", "Taxes", "Fees", "Deposit", "Removal"] {
+            assert!(kinds.contains(&kind), "no {} in {:?}", kind, kinds);
+        }
+        let dividend = rows.iter().find(|row| row[2] == "Dividend").unwrap();
+        assert!(!dividend[5].is_empty());
+        assert!(!dividend[7].starts_with('-'));
+        assert!(rows
+            .iter()
+            .filter(|row| row[2] == "Fees")
+            .all(|row| row[5].is_empty()));
+    }
+}
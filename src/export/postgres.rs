@@ -0,0 +1,542 @@
+//! Postgres ingestion with sqlx
+//!
+//! [`upsert_statements`] loads statements into the tables of [`SCHEMA`],
+//! the tables of [`sqlite`](super::sqlite) with Postgres types: amounts are
+//! `NUMERIC`, dates `DATE`, and date/times `TIMESTAMP`. Rows are keyed the
+//! same way, so loading a statement again changes nothing.
+//!
+//! Services with their own write path can use the pieces instead:
+//! [`Record::upsert_sql`] is the `INSERT ... ON CONFLICT` statement for a
+//! record type and [`Record::bind`] binds a record to it. The `*Row`
+//! structs implement [`sqlx::FromRow`] for reading the tables back.
+//!
+//! Requires the `postgres` feature.
+//!
+//! # Example
+//! ```no_run
+//! use ib_flex::export::postgres::{create_schema, upsert_statements, TradeRow};
+//! use ib_flex::parse_activity_flex;
+//!
+//! # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let pool = sqlx::PgPool::connect("postgres://localhost/flex").await?;
+//! create_schema(&pool).await?;
+//!
+//! let xml = std::fs::read_to_string("statement.xml")?;
+//! let statement = parse_activity_flex(&xml)?;
+//! upsert_statements(&pool, [&statement]).await?;
+//!
+//! let trades: Vec<TradeRow> = sqlx::query_as("SELECT * FROM trades")
+//!     .fetch_all(&pool)
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::sync::OnceLock;
+
+use chrono::{NaiveDate, NaiveDateTime};
+use rust_decimal::Decimal;
+use sqlx::postgres::PgArguments;
+use sqlx::query::Query;
+use sqlx::{PgPool, Postgres};
+
+use super::records::{self, statement_rows, Table, Value};
+use crate::types::{
+    ActivityFlexStatement, CashTransaction, CorporateAction, Position, SecurityInfo, Trade,
+};
+use crate::{ParseError, Result};
+
+/// A query with Postgres arguments, as built by [`sqlx::query()`]
+pub type PgQuery<'q> = Query<'q, Postgres, PgArguments>;
+
+/// Tables created by [`create_schema`]
+///
+/// `row_key` is built as described on the SQLite
+/// [`SCHEMA`](super::sqlite::SCHEMA).
+pub const SCHEMA: &str = "\
+CREATE TABLE IF NOT EXISTS accounts (
+    account_id TEXT PRIMARY KEY,
+    from_date DATE NOT NULL,
+    to_date DATE NOT NULL,
+    when_generated TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS securities (
+    conid BIGINT PRIMARY KEY,
+    symbol TEXT NOT NULL,
+    description TEXT,
+    asset_category TEXT,
+    currency TEXT,
+    isin TEXT,
+    cusip TEXT,
+    figi TEXT,
+    underlying_conid BIGINT,
+    underlying_symbol TEXT,
+    put_call TEXT,
+    strike NUMERIC,
+    expiry DATE,
+    multiplier NUMERIC
+);
+
+CREATE TABLE IF NOT EXISTS trades (
+    row_key TEXT PRIMARY KEY,
+    transaction_id TEXT UNIQUE,
+    account_id TEXT NOT NULL REFERENCES accounts (account_id),
+    conid BIGINT NOT NULL REFERENCES securities (conid),
+    trade_id TEXT,
+    order_id TEXT,
+    trade_date DATE,
+    trade_time TIMESTAMP,
+    settle_date DATE,
+    buy_sell TEXT,
+    open_close TEXT,
+    quantity NUMERIC,
+    price NUMERIC,
+    proceeds NUMERIC,
+    commission NUMERIC,
+    taxes NUMERIC,
+    net_cash NUMERIC,
+    fifo_pnl_realized NUMERIC,
+    currency TEXT NOT NULL,
+    fx_rate_to_base NUMERIC,
+    level_of_detail TEXT,
+    notes TEXT
+);
+
+CREATE TABLE IF NOT EXISTS positions (
+    row_key TEXT PRIMARY KEY,
+    account_id TEXT NOT NULL REFERENCES accounts (account_id),
+    conid BIGINT NOT NULL REFERENCES securities (conid),
+    report_date DATE NOT NULL,
+    side TEXT,
+    quantity NUMERIC NOT NULL,
+    mark_price NUMERIC NOT NULL,
+    position_value NUMERIC NOT NULL,
+    cost_basis_price NUMERIC,
+    cost_basis_money NUMERIC,
+    fifo_pnl_unrealized NUMERIC,
+    percent_of_nav NUMERIC,
+    currency TEXT NOT NULL,
+    fx_rate_to_base NUMERIC,
+    open_date_time TIMESTAMP,
+    level_of_detail TEXT
+);
+
+CREATE TABLE IF NOT EXISTS cash_transactions (
+    row_key TEXT PRIMARY KEY,
+    transaction_id TEXT UNIQUE,
+    account_id TEXT NOT NULL REFERENCES accounts (account_id),
+    conid BIGINT,
+    type TEXT,
+    description TEXT,
+    date DATE,
+    settle_date DATE,
+    ex_date DATE,
+    amount NUMERIC NOT NULL,
+    currency TEXT NOT NULL,
+    fx_rate_to_base NUMERIC,
+    code TEXT,
+    action_id TEXT,
+    trade_id TEXT,
+    level_of_detail TEXT
+);
+
+CREATE TABLE IF NOT EXISTS corporate_actions (
+    row_key TEXT PRIMARY KEY,
+    transaction_id TEXT UNIQUE,
+    account_id TEXT NOT NULL REFERENCES accounts (account_id),
+    conid BIGINT NOT NULL REFERENCES securities (conid),
+    action_id TEXT,
+    type TEXT,
+    description TEXT,
+    report_date DATE NOT NULL,
+    action_date DATE,
+    ex_date DATE,
+    pay_date DATE,
+    quantity NUMERIC,
+    amount NUMERIC,
+    proceeds NUMERIC,
+    value NUMERIC,
+    fifo_pnl_realized NUMERIC,
+    currency TEXT,
+    code TEXT
+);
+";
+
+/// Create the tables of [`SCHEMA`] that do not exist yet
+pub async fn create_schema(pool: &PgPool) -> Result<()> {
+    sqlx::raw_sql(SCHEMA)
+        .execute(pool)
+        .await
+        .map_err(postgres_error)?;
+    Ok(())
+}
+
+/// Insert or update every record of `statements`, in one transaction
+///
+/// Records already in the database are replaced by the version in the
+/// statement; securities keep fields the statement leaves out. An
+/// account's date range widens to cover every statement loaded for it.
+pub async fn upsert_statements<'a, I>(pool: &PgPool, statements: I) -> Result<()>
+where
+    I: IntoIterator<Item = &'a ActivityFlexStatement>,
+{
+    let mut tx = pool.begin().await.map_err(postgres_error)?;
+    for statement in statements {
+        for row in statement_rows(statement) {
+            bind_values(sqlx::query(upsert_sql(row.table)), row.values)
+                .execute(&mut *tx)
+                .await
+                .map_err(postgres_error)?;
+        }
+    }
+    tx.commit().await.map_err(postgres_error)
+}
+
+/// A record type stored in one table of [`SCHEMA`]
+///
+/// Trades, positions, and corporate actions refer to `accounts` and
+/// `securities`, so those rows must exist first; [`upsert_statements`]
+/// writes them in that order.
+pub trait Record {
+    /// Name of the table
+    fn table() -> &'static str;
+
+    /// `INSERT ... ON CONFLICT DO UPDATE` for the table, with one `$n`
+    /// placeholder per column
+    fn upsert_sql() -> &'static str;
+
+    /// Bind this record's columns to a query from [`Record::upsert_sql`]
+    fn bind<'q>(&self, query: PgQuery<'q>) -> PgQuery<'q>;
+}
+
+macro_rules! impl_record {
+    ($($ty:ty => $table:ident, $values:ident;)+) => {
+        $(
+            impl Record for $ty {
+                fn table() -> &'static str {
+                    records::$table.name
+                }
+
+                fn upsert_sql() -> &'static str {
+                    upsert_sql(&records::$table)
+                }
+
+                fn bind<'q>(&self, query: PgQuery<'q>) -> PgQuery<'q> {
+                    bind_values(query, records::$values(self))
+                }
+            }
+        )+
+    };
+}
+
+impl_record! {
+    SecurityInfo => SECURITIES, security_info_values;
+    Trade => TRADES, trade_values;
+    Position => POSITIONS, position_values;
+    CashTransaction => CASH_TRANSACTIONS, cash_values;
+    CorporateAction => CORPORATE_ACTIONS, action_values;
+}
+
+/// Upsert statement for `table`, built once
+fn upsert_sql(table: &Table) -> &'static str {
+    static SQL: OnceLock<Vec<(&'static str, String)>> = OnceLock::new();
+    let all = SQL.get_or_init(|| {
+        [
+            &records::ACCOUNTS,
+            &records::SECURITIES,
+            &records::TRADES,
+            &records::POSITIONS,
+            &records::CASH_TRANSACTIONS,
+            &records::CORPORATE_ACTIONS,
+        ]
+        .iter()
+        .map(|t| (t.name, t.upsert_sql(|i| format!("${}", i))))
+        .collect()
+    });
+    all.iter()
+        .find(|(name, _)| *name == table.name)
+        .map(|(_, sql)| sql.as_str())
+        .unwrap_or_default()
+}
+
+fn bind_values(query: PgQuery<'_>, values: Vec<Value>) -> PgQuery<'_> {
+    values.into_iter().fold(query, |query, value| match value {
+        Value::Text(value) => query.bind(value),
+        Value::Integer(value) => query.bind(value),
+        Value::Decimal(value) => query.bind(value),
+        Value::Date(value) => query.bind(value),
+        Value::DateTime(value) => query.bind(value),
+    })
+}
+
+fn postgres_error(e: sqlx::Error) -> ParseError {
+    ParseError::PostgresError(e.to_string())
+}
+
+/// A row of `accounts`
+#[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow)]
+pub struct AccountRow {
+    /// IB account number
+    pub account_id: String,
+    /// Start of the earliest statement loaded
+    pub from_date: NaiveDate,
+    /// End of the latest statement loaded
+    pub to_date: NaiveDate,
+    /// `whenGenerated` of the statement loaded last
+    pub when_generated: String,
+}
+
+/// A row of `securities`
+#[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow)]
+pub struct SecurityRow {
+    /// IB contract ID
+    pub conid: i64,
+    /// Ticker symbol
+    pub symbol: String,
+    /// Security description
+    pub description: Option<String>,
+    /// Asset category code (`STK`, `OPT`, ...)
+    pub asset_category: Option<String>,
+    /// Currency the security trades in
+    pub currency: Option<String>,
+    /// ISIN
+    pub isin: Option<String>,
+    /// CUSIP
+    pub cusip: Option<String>,
+    /// FIGI
+    pub figi: Option<String>,
+    /// Contract ID of the underlying
+    pub underlying_conid: Option<i64>,
+    /// Symbol of the underlying
+    pub underlying_symbol: Option<String>,
+    /// `P` or `C` for options
+    pub put_call: Option<String>,
+    /// Strike price
+    pub strike: Option<Decimal>,
+    /// Expiration date
+    pub expiry: Option<NaiveDate>,
+    /// Contract multiplier
+    pub multiplier: Option<Decimal>,
+}
+
+/// A row of `trades`
+#[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow)]
+pub struct TradeRow {
+    /// Key of the row; see [`SCHEMA`]
+    pub row_key: String,
+    /// IB transaction ID
+    pub transaction_id: Option<String>,
+    /// IB account number
+    pub account_id: String,
+    /// IB contract ID
+    pub conid: i64,
+    /// IB trade ID
+    pub trade_id: Option<String>,
+    /// IB order ID
+    pub order_id: Option<String>,
+    /// Trade date
+    pub trade_date: Option<NaiveDate>,
+    /// Execution date/time
+    pub trade_time: Option<NaiveDateTime>,
+    /// Settlement date
+    pub settle_date: Option<NaiveDate>,
+    /// `BUY` or `SELL`
+    pub buy_sell: Option<String>,
+    /// Open/close indicator
+    pub open_close: Option<String>,
+    /// Quantity, negative for sales
+    pub quantity: Option<Decimal>,
+    /// Execution price
+    pub price: Option<Decimal>,
+    /// Proceeds
+    pub proceeds: Option<Decimal>,
+    /// Commission
+    pub commission: Option<Decimal>,
+    /// Taxes
+    pub taxes: Option<Decimal>,
+    /// Net cash
+    pub net_cash: Option<Decimal>,
+    /// FIFO realized P&L
+    pub fifo_pnl_realized: Option<Decimal>,
+    /// Trade currency
+    pub currency: String,
+    /// FX rate to the base currency
+    pub fx_rate_to_base: Option<Decimal>,
+    /// Level of detail (`EXECUTION`, `ORDER`, ...)
+    pub level_of_detail: Option<String>,
+    /// Trade notes
+    pub notes: Option<String>,
+}
+
+/// A row of `positions`
+#[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow)]
+pub struct PositionRow {
+    /// Key of the row; see [`SCHEMA`]
+    pub row_key: String,
+    /// IB account number
+    pub account_id: String,
+    /// IB contract ID
+    pub conid: i64,
+    /// Date of the position
+    pub report_date: NaiveDate,
+    /// `Long` or `Short`
+    pub side: Option<String>,
+    /// Quantity held
+    pub quantity: Decimal,
+    /// Mark price
+    pub mark_price: Decimal,
+    /// Position value
+    pub position_value: Decimal,
+    /// Cost basis per unit
+    pub cost_basis_price: Option<Decimal>,
+    /// Total cost basis
+    pub cost_basis_money: Option<Decimal>,
+    /// FIFO unrealized P&L
+    pub fifo_pnl_unrealized: Option<Decimal>,
+    /// Percent of NAV
+    pub percent_of_nav: Option<Decimal>,
+    /// Position currency
+    pub currency: String,
+    /// FX rate to the base currency
+    pub fx_rate_to_base: Option<Decimal>,
+    /// When the position was opened
+    pub open_date_time: Option<NaiveDateTime>,
+    /// Level of detail (`SUMMARY`, `LOT`)
+    pub level_of_detail: Option<String>,
+}
+
+/// A row of `cash_transactions`
+#[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow)]
+pub struct CashTransactionRow {
+    /// Key of the row; see [`SCHEMA`]
+    pub row_key: String,
+    /// IB transaction ID
+    pub transaction_id: Option<String>,
+    /// IB account number
+    pub account_id: String,
+    /// IB contract ID of the security, if any
+    pub conid: Option<i64>,
+    /// Transaction type
+    #[sqlx(rename = "type")]
+    pub transaction_type: Option<String>,
+    /// Description
+    pub description: Option<String>,
+    /// Transaction date
+    pub date: Option<NaiveDate>,
+    /// Settlement date
+    pub settle_date: Option<NaiveDate>,
+    /// Ex-dividend date
+    pub ex_date: Option<NaiveDate>,
+    /// Amount
+    pub amount: Decimal,
+    /// Currency
+    pub currency: String,
+    /// FX rate to the base currency
+    pub fx_rate_to_base: Option<Decimal>,
+    /// Transaction code
+    pub code: Option<String>,
+    /// Corporate action ID
+    pub action_id: Option<String>,
+    /// Related trade ID
+    pub trade_id: Option<String>,
+    /// Level of detail
+    pub level_of_detail: Option<String>,
+}
+
+/// A row of `corporate_actions`
+#[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow)]
+pub struct CorporateActionRow {
+    /// Key of the row; see [`SCHEMA`]
+    pub row_key: String,
+    /// IB transaction ID
+    pub transaction_id: Option<String>,
+    /// IB account number
+    pub account_id: String,
+    /// IB contract ID
+    pub conid: i64,
+    /// Corporate action ID
+    pub action_id: Option<String>,
+    /// Action type code
+    #[sqlx(rename = "type")]
+    pub action_type: Option<String>,
+    /// Description
+    pub description: Option<String>,
+    /// Report date
+    pub report_date: NaiveDate,
+    /// Date of the action
+    pub action_date: Option<NaiveDate>,
+    /// Ex date
+    pub ex_date: Option<NaiveDate>,
+    /// Pay date
+    pub pay_date: Option<NaiveDate>,
+    /// Quantity
+    pub quantity: Option<Decimal>,
+    /// Amount
+    pub amount: Option<Decimal>,
+    /// Proceeds
+    pub proceeds: Option<Decimal>,
+    /// Value
+    pub value: Option<Decimal>,
+    /// FIFO realized P&L
+    pub fifo_pnl_realized: Option<Decimal>,
+    /// Currency
+    pub currency: Option<String>,
+    /// Transaction code
+    pub code: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Column names of each `CREATE TABLE` in `schema`
+    fn schema_columns(schema: &str) -> Vec<(String, Vec<String>)> {
+        schema
+            .split("CREATE TABLE IF NOT EXISTS ")
+            .skip(1)
+            .map(|table| {
+                let (name, body) = table.split_once(" (").unwrap();
+                let columns = body
+                    .lines()
+                    .skip(1)
+                    .take_while(|line| !line.starts_with(')'))
+                    .map(|line| line.split_whitespace().next().unwrap().to_string())
+                    .collect();
+                (name.to_string(), columns)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_schemas_match_records() {
+        let tables = [
+            &records::ACCOUNTS,
+            &records::SECURITIES,
+            &records::TRADES,
+            &records::POSITIONS,
+            &records::CASH_TRANSACTIONS,
+            &records::CORPORATE_ACTIONS,
+        ];
+        let expected: Vec<(String, Vec<String>)> = tables
+            .iter()
+            .map(|t| {
+                let columns = t.columns.iter().map(|c| c.to_string()).collect();
+                (t.name.to_string(), columns)
+            })
+            .collect();
+        assert_eq!(schema_columns(SCHEMA), expected);
+        #[cfg(feature = "sqlite")]
+        assert_eq!(schema_columns(super::super::sqlite::SCHEMA), expected);
+    }
+
+    #[test]
+    fn test_upsert_sql() {
+        let sql = Trade::upsert_sql();
+        assert!(sql.starts_with("INSERT INTO trades (row_key, transaction_id,"));
+        assert!(sql.contains("ON CONFLICT (row_key) DO UPDATE SET"));
+        assert!(sql.contains(&format!("${}", records::TRADES.columns.len())));
+        assert!(SecurityInfo::upsert_sql().contains("coalesce(excluded.symbol, securities.symbol)"));
+        assert_eq!(CashTransaction::table(), "cash_transactions");
+    }
+}
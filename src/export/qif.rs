@@ -0,0 +1,273 @@
+//! QIF export of trades and cash transactions
+//!
+//! [`write_statements`] writes each statement as a QIF investment account
+//! (`!Type:Invst`) for accounting tools that import QIF. Execution-level
+//! trades become `Buy`, `Sell`, `ShtSell`, and `CvrShrt` records; cash
+//! transactions become:
+//!
+//! - dividends: `Div`;
+//! - interest received: `IntInc`;
+//! - deposits and withdrawals: `XIn` and `XOut`;
+//! - everything else: `MiscInc` or `MiscExp`, by the sign of the amount.
+//!
+//! [`QifOptions`] names the accounts and maps dividends, interest, fees,
+//! and withholding tax to categories, written in the `L` field.
+//! Quantities and amounts are written without a sign, as QIF expects.
+//!
+//! # Example
+//! ```
+//! use ib_flex::export::qif::{write_statements, QifOptions};
+//! use ib_flex::parse_activity_flex;
+//!
+//! let xml = include_str!("../../tests/fixtures/activity_minimal.xml");
+//! let statement = parse_activity_flex(xml).unwrap();
+//!
+//! let mut options = QifOptions::default();
+//! options
+//!     .account_names
+//!     .insert("U1234567".to_string(), "Brokerage".to_string());
+//!
+//! let mut out = Vec::new();
+//! write_statements(&mut out, [&statement], &options).unwrap();
+//! let qif = String::from_utf8(out).unwrap();
+//! assert!(qif.starts_with("!Account\nNBrokerage\nTInvst\n^\n!Type:Invst\n"));
+//! ```
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::io::Write;
+
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+
+use crate::types::{
+    ActivityFlexStatement, BuySell, CashTransaction, Dated, LevelOfDetail, OpenClose, Trade,
+};
+use crate::Result;
+
+/// Account names and categories for the QIF writer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QifOptions {
+    /// QIF account name per IB account ID; accounts not listed are named
+    /// by their ID
+    pub account_names: HashMap<String, String>,
+    /// `chrono` format for dates (default `%m/%d/%Y`)
+    pub date_format: String,
+    /// Category for dividends and payments in lieu (default `Dividends`)
+    pub dividend_category: String,
+    /// Category for interest paid or received (default `Interest`)
+    pub interest_category: String,
+    /// Category for fees and commission adjustments (default `Fees`)
+    pub fee_category: String,
+    /// Category for withholding tax (default `Taxes`)
+    pub withholding_category: String,
+    /// Category for other cash transactions; `None` writes no category
+    pub other_category: Option<String>,
+}
+
+impl Default for QifOptions {
+    fn default() -> Self {
+        QifOptions {
+            account_names: HashMap::new(),
+            date_format: "%m/%d/%Y".to_string(),
+            dividend_category: "Dividends".to_string(),
+            interest_category: "Interest".to_string(),
+            fee_category: "Fees".to_string(),
+            withholding_category: "Taxes".to_string(),
+            other_category: None,
+        }
+    }
+}
+
+impl QifOptions {
+    fn account_name<'a>(&'a self, account_id: &'a str) -> &'a str {
+        self.account_names
+            .get(account_id)
+            .map_or(account_id, String::as_str)
+    }
+
+    fn category(&self, c: &CashTransaction) -> Option<&str> {
+        if c.is_dividend() {
+            Some(&self.dividend_category)
+        } else if c.is_interest() {
+            Some(&self.interest_category)
+        } else if c.is_fee() {
+            Some(&self.fee_category)
+        } else if c.is_withholding_tax() {
+            Some(&self.withholding_category)
+        } else {
+            self.other_category.as_deref()
+        }
+    }
+}
+
+/// Write `statements` as QIF, one investment account per statement
+///
+/// Trades come first, in statement order, then cash transactions. Rows
+/// without a date are skipped.
+pub fn write_statements<'a, W, I>(mut writer: W, statements: I, options: &QifOptions) -> Result<()>
+where
+    W: Write,
+    I: IntoIterator<Item = &'a ActivityFlexStatement>,
+{
+    let mut out = String::new();
+    for statement in statements {
+        let name = options.account_name(statement.account_id.as_str());
+        let _ = write!(out, "!Account\nN{}\nTInvst\n^\n!Type:Invst\n", line(name));
+        for trade in statement.trades.iter() {
+            write_trade(&mut out, trade, options);
+        }
+        for transaction in statement.cash_transactions.iter() {
+            write_cash_transaction(&mut out, transaction, options);
+        }
+    }
+    writer.write_all(out.as_bytes())?;
+    writer.flush()?;
+    Ok(())
+}
+
+fn write_trade(out: &mut String, t: &Trade, options: &QifOptions) {
+    if !matches!(t.level_of_detail, None | Some(LevelOfDetail::Execution)) {
+        return;
+    }
+    let (Some(date), Some(quantity)) = (t.date(), t.quantity) else {
+        return;
+    };
+    let action = match (&t.buy_sell, &t.open_close) {
+        (Some(BuySell::Buy), Some(OpenClose::Close)) => "CvrShrt",
+        (Some(BuySell::Buy), _) => "Buy",
+        (Some(BuySell::Sell), Some(OpenClose::Open)) => "ShtSell",
+        (Some(BuySell::Sell), _) => "Sell",
+        _ => return,
+    };
+    let commission = t.commission.unwrap_or_default() + t.taxes.unwrap_or_default();
+    let total = t
+        .net_cash
+        .unwrap_or_else(|| t.proceeds.unwrap_or_default() + commission);
+
+    record_start(out, date, action, options);
+    let _ = writeln!(out, "Y{}", line(&t.symbol));
+    if let Some(price) = t.price {
+        let _ = writeln!(out, "I{}", price.abs().normalize());
+    }
+    let _ = writeln!(out, "Q{}", quantity.abs().normalize());
+    let _ = writeln!(out, "T{}", amount(total));
+    let _ = writeln!(out, "O{}", amount(commission));
+    record_end(out, t.description.as_deref());
+}
+
+fn write_cash_transaction(out: &mut String, c: &CashTransaction, options: &QifOptions) {
+    let Some(date) = c.date() else {
+        return;
+    };
+    let received = c.amount.is_sign_positive();
+    let action = if c.is_dividend() {
+        "Div"
+    } else if c.is_interest() && received {
+        "IntInc"
+    } else if c.is_deposit_or_withdrawal() {
+        if received {
+            "XIn"
+        } else {
+            "XOut"
+        }
+    } else if received {
+        "MiscInc"
+    } else {
+        "MiscExp"
+    };
+
+    record_start(out, date, action, options);
+    if let Some(symbol) = c.symbol.as_deref().filter(|s| !s.is_empty()) {
+        let _ = writeln!(out, "Y{}", line(symbol));
+    }
+    let _ = writeln!(out, "T{}", amount(c.amount));
+    if !c.is_deposit_or_withdrawal() {
+        if let Some(category) = options.category(c) {
+            let _ = writeln!(out, "L{}", line(category));
+        }
+    }
+    record_end(out, c.description.as_deref());
+}
+
+fn record_start(out: &mut String, date: NaiveDate, action: &str, options: &QifOptions) {
+    let _ = writeln!(out, "D{}", date.format(&options.date_format));
+    let _ = writeln!(out, "N{}", action);
+}
+
+fn record_end(out: &mut String, memo: Option<&str>) {
+    if let Some(memo) = memo.filter(|m| !m.is_empty()) {
+        let _ = writeln!(out, "M{}", line(memo));
+    }
+    out.push_str("^\n");
+}
+
+fn amount(value: Decimal) -> Decimal {
+    value.abs().normalize()
+}
+
+/// `text` on one line: QIF fields end at the newline
+fn line(text: &str) -> String {
+    text.replace(['\r', '\n'], " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn records(qif: &str) -> Vec<Vec<&str>> {
+        qif.split("^\n")
+            .map(|record| record.lines().collect::<Vec<_>>())
+            .filter(|record| !record.is_empty())
+            .collect()
+    }
+
+    #[test]
+    fn test_trades() {
+        let xml = include_str!("../../tests/fixtures/activity_daily_portfolio.xml");
+        let statement = crate::parse_activity_flex(xml).unwrap();
+        let mut out = Vec::new();
+        write_statements(&mut out, [&statement], &QifOptions::default()).unwrap();
+        let qif = String::from_utf8(out).unwrap();
+
+        let records = records(&qif);
+        let sell = records
+            .iter()
+            .find(|r| r.contains(&"NSell"))
+            .expect("a sell record");
+        assert!(sell.contains(&"D01/15/2025"));
+        assert!(sell.contains(&"YMSFT"));
+        assert!(sell.contains(&"Q50"));
+        assert!(sell.contains(&"T21011.75"));
+        assert!(sell.contains(&"O0.75"));
+    }
+
+    #[test]
+    fn test_cash_categories() {
+        let xml = include_str!("../../tests/fixtures/activity_cash.xml");
+        let statement = crate::parse_activity_flex(xml).unwrap();
+        let options = QifOptions {
+            dividend_category: "Income:Dividends".to_string(),
+            other_category: Some("Misc".to_string()),
+            ..Default::default()
+        };
+        let mut out = Vec::new();
+        write_statements(&mut out, [&statement], &options).unwrap();
+        let qif = String::from_utf8(out).unwrap();
+
+        let records = records(&qif);
+        let dated = statement
+            .cash_transactions
+            .iter()
+            .filter(|c| c.date().is_some())
+            .count();
+        // The account header and one record per dated transaction
+        assert_eq!(records.len(), 1 + dated);
+        assert!(records
+            .iter()
+            .filter(|r| r.contains(&"NDiv"))
+            .all(|r| r.contains(&"LIncome:Dividends")));
+        assert!(records.iter().any(|r| r.contains(&"LFees")));
+        assert!(records.iter().any(|r| r.contains(&"NXOut")));
+    }
+}
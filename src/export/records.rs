@@ -0,0 +1,523 @@
+//! Rows of the SQL exports, whatever the database
+//!
+//! Both [`sqlite`](super::sqlite) and [`postgres`](super::postgres) store
+//! the same tables; this module turns statements into their rows and
+//! writes the upsert statements, leaving binding to each backend.
+
+use std::fmt::Display;
+
+use chrono::{NaiveDate, NaiveDateTime};
+use rust_decimal::Decimal;
+
+use crate::types::{
+    ActivityFlexStatement, CashTransaction, Conid, CorporateAction, Position, SecurityInfo, Trade,
+};
+
+/// One column value of a row
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) enum Value {
+    Text(Option<String>),
+    Integer(Option<i64>),
+    Decimal(Option<Decimal>),
+    Date(Option<NaiveDate>),
+    DateTime(Option<NaiveDateTime>),
+}
+
+/// How an upsert changes a column of a row already stored
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Update {
+    /// Take the new value
+    Replace,
+    /// Take the new value unless it is null
+    KeepKnown,
+    /// Keep the earlier of the two
+    Earliest,
+    /// Keep the later of the two
+    Latest,
+}
+
+/// A table, keyed on its first column
+pub(super) struct Table {
+    pub(super) name: &'static str,
+    pub(super) columns: &'static [&'static str],
+    update: fn(&str) -> Update,
+}
+
+impl Table {
+    /// `INSERT ... ON CONFLICT` on the key, with `placeholder(n)` for the
+    /// n-th value, counting from 1
+    pub(super) fn upsert_sql(&self, placeholder: impl Fn(usize) -> String) -> String {
+        let placeholders: Vec<String> = (1..=self.columns.len()).map(placeholder).collect();
+        let updates: Vec<String> = self.columns[1..]
+            .iter()
+            .map(|column| {
+                let (new, old) = (
+                    format!("excluded.{}", column),
+                    format!("{}.{}", self.name, column),
+                );
+                let value = match (self.update)(column) {
+                    Update::Replace => new,
+                    Update::KeepKnown => format!("coalesce({}, {})", new, old),
+                    Update::Earliest => {
+                        format!("CASE WHEN {0} < {1} THEN {0} ELSE {1} END", new, old)
+                    }
+                    Update::Latest => {
+                        format!("CASE WHEN {0} > {1} THEN {0} ELSE {1} END", new, old)
+                    }
+                };
+                format!("{} = {}", column, value)
+            })
+            .collect();
+        format!(
+            "INSERT INTO {} ({}) VALUES ({}) ON CONFLICT ({}) DO UPDATE SET {}",
+            self.name,
+            self.columns.join(", "),
+            placeholders.join(", "),
+            self.columns[0],
+            updates.join(", ")
+        )
+    }
+}
+
+fn account_update(column: &str) -> Update {
+    match column {
+        "from_date" => Update::Earliest,
+        "to_date" => Update::Latest,
+        _ => Update::Replace,
+    }
+}
+
+pub(super) const ACCOUNTS: Table = Table {
+    name: "accounts",
+    columns: &["account_id", "from_date", "to_date", "when_generated"],
+    update: account_update,
+};
+
+pub(super) const SECURITIES: Table = Table {
+    name: "securities",
+    columns: &[
+        "conid",
+        "symbol",
+        "description",
+        "asset_category",
+        "currency",
+        "isin",
+        "cusip",
+        "figi",
+        "underlying_conid",
+        "underlying_symbol",
+        "put_call",
+        "strike",
+        "expiry",
+        "multiplier",
+    ],
+    update: |_| Update::KeepKnown,
+};
+
+pub(super) const TRADES: Table = Table {
+    name: "trades",
+    columns: &[
+        "row_key",
+        "transaction_id",
+        "account_id",
+        "conid",
+        "trade_id",
+        "order_id",
+        "trade_date",
+        "trade_time",
+        "settle_date",
+        "buy_sell",
+        "open_close",
+        "quantity",
+        "price",
+        "proceeds",
+        "commission",
+        "taxes",
+        "net_cash",
+        "fifo_pnl_realized",
+        "currency",
+        "fx_rate_to_base",
+        "level_of_detail",
+        "notes",
+    ],
+    update: |_| Update::Replace,
+};
+
+pub(super) const POSITIONS: Table = Table {
+    name: "positions",
+    columns: &[
+        "row_key",
+        "account_id",
+        "conid",
+        "report_date",
+        "side",
+        "quantity",
+        "mark_price",
+        "position_value",
+        "cost_basis_price",
+        "cost_basis_money",
+        "fifo_pnl_unrealized",
+        "percent_of_nav",
+        "currency",
+        "fx_rate_to_base",
+        "open_date_time",
+        "level_of_detail",
+    ],
+    update: |_| Update::Replace,
+};
+
+pub(super) const CASH_TRANSACTIONS: Table = Table {
+    name: "cash_transactions",
+    columns: &[
+        "row_key",
+        "transaction_id",
+        "account_id",
+        "conid",
+        "type",
+        "description",
+        "date",
+        "settle_date",
+        "ex_date",
+        "amount",
+        "currency",
+        "fx_rate_to_base",
+        "code",
+        "action_id",
+        "trade_id",
+        "level_of_detail",
+    ],
+    update: |_| Update::Replace,
+};
+
+pub(super) const CORPORATE_ACTIONS: Table = Table {
+    name: "corporate_actions",
+    columns: &[
+        "row_key",
+        "transaction_id",
+        "account_id",
+        "conid",
+        "action_id",
+        "type",
+        "description",
+        "report_date",
+        "action_date",
+        "ex_date",
+        "pay_date",
+        "quantity",
+        "amount",
+        "proceeds",
+        "value",
+        "fifo_pnl_realized",
+        "currency",
+        "code",
+    ],
+    update: |_| Update::Replace,
+};
+
+/// Values for one row of `table`, in column order
+pub(super) struct Row {
+    pub(super) table: &'static Table,
+    pub(super) values: Vec<Value>,
+}
+
+impl Row {
+    fn new(table: &'static Table, values: Vec<Value>) -> Self {
+        debug_assert_eq!(values.len(), table.columns.len(), "{}", table.name);
+        Row { table, values }
+    }
+}
+
+/// Rows of every table for `statement`, each security before the rows
+/// that refer to it
+pub(super) fn statement_rows(statement: &ActivityFlexStatement) -> Vec<Row> {
+    let mut rows = vec![Row::new(&ACCOUNTS, account_values(statement))];
+    for security in &statement.securities_info {
+        rows.push(Row::new(&SECURITIES, security_info_values(security)));
+    }
+    for trade in &statement.trades {
+        rows.push(Row::new(&SECURITIES, trade_security_values(trade)));
+        rows.push(Row::new(&TRADES, trade_values(trade)));
+    }
+    for position in &statement.positions {
+        rows.push(Row::new(&SECURITIES, position_security_values(position)));
+        rows.push(Row::new(&POSITIONS, position_values(position)));
+    }
+    for transaction in &statement.cash_transactions {
+        rows.push(Row::new(&CASH_TRANSACTIONS, cash_values(transaction)));
+    }
+    for action in &statement.corporate_actions {
+        rows.push(Row::new(&SECURITIES, action_security_values(action)));
+        rows.push(Row::new(&CORPORATE_ACTIONS, action_values(action)));
+    }
+    rows
+}
+
+fn text<T: Display>(value: Option<T>) -> Value {
+    Value::Text(value.map(|v| v.to_string()))
+}
+
+fn decimal(value: Option<Decimal>) -> Value {
+    Value::Decimal(value)
+}
+
+fn conid(value: Option<Conid>) -> Value {
+    Value::Integer(value.map(|c| c.get() as i64))
+}
+
+/// `transaction_id`, or the identifying fields joined with `|`
+fn row_key(transaction_id: Option<&str>, parts: &[String]) -> String {
+    match transaction_id {
+        Some(id) => id.to_string(),
+        None => parts.join("|"),
+    }
+}
+
+fn part<T: Display>(value: Option<T>) -> String {
+    value.map(|v| v.to_string()).unwrap_or_default()
+}
+
+pub(super) fn trade_key(t: &Trade) -> String {
+    row_key(
+        t.transaction_id.as_deref(),
+        &[
+            t.account_id.to_string(),
+            t.conid.to_string(),
+            part(t.trade_id.as_ref()),
+            part(
+                t.trade_time
+                    .map(|dt| dt.to_string())
+                    .or(t.trade_date.map(|d| d.to_string())),
+            ),
+            part(t.quantity),
+            part(t.price),
+            part(t.level_of_detail.as_ref()),
+        ],
+    )
+}
+
+pub(super) fn position_key(p: &Position) -> String {
+    row_key(
+        None,
+        &[
+            p.account_id.to_string(),
+            p.report_date.to_string(),
+            p.conid.to_string(),
+            part(p.side.as_ref()),
+            part(p.level_of_detail.as_ref()),
+            part(p.open_date_time),
+        ],
+    )
+}
+
+pub(super) fn cash_key(c: &CashTransaction) -> String {
+    row_key(
+        c.transaction_id.as_deref(),
+        &[
+            c.account_id.to_string(),
+            part(c.conid.as_ref()),
+            part(c.transaction_type.as_ref()),
+            part(c.date_time.clone().or(c.date.map(|d| d.to_string()))),
+            c.amount.to_string(),
+            c.currency.clone(),
+            part(c.action_id.as_ref()),
+            part(c.level_of_detail.as_ref()),
+        ],
+    )
+}
+
+pub(super) fn action_key(a: &CorporateAction) -> String {
+    row_key(
+        a.transaction_id.as_deref(),
+        &[
+            a.account_id.to_string(),
+            a.conid.to_string(),
+            part(a.action_id.as_ref()),
+            a.report_date.to_string(),
+            part(a.action_type.as_ref()),
+            part(a.quantity),
+            part(a.amount),
+        ],
+    )
+}
+
+fn account_values(statement: &ActivityFlexStatement) -> Vec<Value> {
+    vec![
+        text(Some(&statement.account_id)),
+        Value::Date(Some(statement.from_date)),
+        Value::Date(Some(statement.to_date)),
+        text(Some(&statement.when_generated)),
+    ]
+}
+
+pub(super) fn security_info_values(s: &SecurityInfo) -> Vec<Value> {
+    vec![
+        conid(Some(s.conid)),
+        text(Some(&s.symbol)),
+        text(s.description.as_ref()),
+        text(Some(&s.asset_category)),
+        text(s.currency.as_ref()),
+        text(s.isin.as_ref()),
+        text(s.cusip.as_ref()),
+        text(s.figi.as_ref()),
+        conid(s.underlying_conid),
+        text(s.underlying_symbol.as_ref()),
+        text(s.put_call.as_ref()),
+        decimal(s.strike),
+        Value::Date(s.expiry),
+        decimal(s.multiplier),
+    ]
+}
+
+fn trade_security_values(t: &Trade) -> Vec<Value> {
+    vec![
+        conid(Some(t.conid)),
+        text(Some(&t.symbol)),
+        text(t.description.as_ref()),
+        text(Some(&t.asset_category)),
+        text(Some(&t.currency)),
+        text(t.isin.as_ref()),
+        text(t.cusip.as_ref()),
+        text(t.figi.as_ref()),
+        conid(t.underlying_conid),
+        text(t.underlying_symbol.as_ref()),
+        text(t.put_call.as_ref()),
+        decimal(t.strike),
+        Value::Date(t.expiry),
+        decimal(t.multiplier),
+    ]
+}
+
+fn position_security_values(p: &Position) -> Vec<Value> {
+    vec![
+        conid(Some(p.conid)),
+        text(Some(&p.symbol)),
+        text(p.description.as_ref()),
+        text(Some(&p.asset_category)),
+        text(Some(&p.currency)),
+        text(p.isin.as_ref()),
+        text(p.cusip.as_ref()),
+        text(p.figi.as_ref()),
+        conid(p.underlying_conid),
+        text(p.underlying_symbol.as_ref()),
+        text(p.put_call.as_ref()),
+        decimal(p.strike),
+        Value::Date(p.expiry),
+        decimal(p.multiplier),
+    ]
+}
+
+fn action_security_values(a: &CorporateAction) -> Vec<Value> {
+    vec![
+        conid(Some(a.conid)),
+        text(Some(&a.symbol)),
+        Value::Text(None),
+        text(a.asset_category.as_ref()),
+        text(a.currency.as_ref()),
+        text(a.isin.as_ref()),
+        text(a.cusip.as_ref()),
+        text(a.figi.as_ref()),
+        conid(a.underlying_conid),
+        text(a.underlying_symbol.as_ref()),
+        text(a.put_call.as_ref()),
+        decimal(a.strike),
+        Value::Date(a.expiry),
+        decimal(a.multiplier),
+    ]
+}
+
+pub(super) fn trade_values(t: &Trade) -> Vec<Value> {
+    let key = Value::Text(Some(trade_key(t)));
+    vec![
+        key,
+        text(t.transaction_id.as_ref()),
+        text(Some(&t.account_id)),
+        conid(Some(t.conid)),
+        text(t.trade_id.as_ref()),
+        text(t.ib_order_id.as_ref()),
+        Value::Date(t.trade_date),
+        Value::DateTime(t.trade_time),
+        Value::Date(t.settle_date),
+        text(t.buy_sell.as_ref()),
+        text(t.open_close.as_ref()),
+        decimal(t.quantity),
+        decimal(t.price),
+        decimal(t.proceeds),
+        decimal(t.commission),
+        decimal(t.taxes),
+        decimal(t.net_cash),
+        decimal(t.fifo_pnl_realized),
+        text(Some(&t.currency)),
+        decimal(t.fx_rate_to_base),
+        text(t.level_of_detail.as_ref()),
+        text(t.notes.as_ref()),
+    ]
+}
+
+pub(super) fn position_values(p: &Position) -> Vec<Value> {
+    let key = Value::Text(Some(position_key(p)));
+    vec![
+        key,
+        text(Some(&p.account_id)),
+        conid(Some(p.conid)),
+        Value::Date(Some(p.report_date)),
+        text(p.side.as_ref()),
+        decimal(Some(p.quantity)),
+        decimal(Some(p.mark_price)),
+        decimal(Some(p.position_value)),
+        decimal(p.cost_basis_price),
+        decimal(p.cost_basis_money),
+        decimal(p.fifo_pnl_unrealized),
+        decimal(p.percent_of_nav),
+        text(Some(&p.currency)),
+        decimal(p.fx_rate_to_base),
+        Value::DateTime(p.open_date_time),
+        text(p.level_of_detail.as_ref()),
+    ]
+}
+
+pub(super) fn cash_values(c: &CashTransaction) -> Vec<Value> {
+    let key = Value::Text(Some(cash_key(c)));
+    let conid = c.conid.as_deref().and_then(|id| id.parse::<i64>().ok());
+    vec![
+        key,
+        text(c.transaction_id.as_ref()),
+        text(Some(&c.account_id)),
+        Value::Integer(conid),
+        text(c.transaction_type.as_ref()),
+        text(c.description.as_ref()),
+        Value::Date(c.date),
+        Value::Date(c.settle_date),
+        Value::Date(c.ex_date),
+        decimal(Some(c.amount)),
+        text(Some(&c.currency)),
+        decimal(c.fx_rate_to_base),
+        text(c.code.as_ref()),
+        text(c.action_id.as_ref()),
+        text(c.trade_id.as_ref()),
+        text(c.level_of_detail.as_ref()),
+    ]
+}
+
+pub(super) fn action_values(a: &CorporateAction) -> Vec<Value> {
+    let key = Value::Text(Some(action_key(a)));
+    vec![
+        key,
+        text(a.transaction_id.as_ref()),
+        text(Some(&a.account_id)),
+        conid(Some(a.conid)),
+        text(a.action_id.as_ref()),
+        text(a.action_type.as_ref()),
+        text(a.description.as_ref()),
+        Value::Date(Some(a.report_date)),
+        Value::Date(a.action_date),
+        Value::Date(a.ex_date),
+        Value::Date(a.pay_date),
+        decimal(a.quantity),
+        decimal(a.amount),
+        decimal(a.proceeds),
+        decimal(a.value),
+        decimal(a.fifo_pnl_realized),
+        text(a.currency.as_ref()),
+        text(a.code.as_ref()),
+    ]
+}
@@ -0,0 +1,295 @@
+//! SQLite export with a fixed schema
+//!
+//! [`upsert_statements`] loads statements into the tables of [`SCHEMA`]:
+//! `accounts`, `securities`, `trades`, `positions`, `cash_transactions`,
+//! and `corporate_actions`. Loading the same statement twice, or
+//! overlapping statements, leaves one row per record: rows are keyed by
+//! IB's `transactionID` where IB reports one, and by the fields that
+//! identify the record otherwise.
+//!
+//! Amounts are stored as `TEXT` so no precision is lost; cast them in
+//! queries that need arithmetic. Dates are `YYYY-MM-DD` and date/times
+//! `YYYY-MM-DD HH:MM:SS`, which sort and compare as SQLite's date functions
+//! expect.
+//!
+//! Requires the `sqlite` feature, which bundles SQLite.
+//!
+//! # Example
+//! ```
+//! use ib_flex::export::sqlite::{create_schema, upsert_statements, Connection};
+//! use ib_flex::parse_activity_flex;
+//!
+//! let xml = include_str!("../../tests/fixtures/activity_minimal.xml");
+//! let statement = parse_activity_flex(xml).unwrap();
+//!
+//! let mut conn = Connection::open_in_memory().unwrap();
+//! create_schema(&conn).unwrap();
+//! upsert_statements(&mut conn, [&statement]).unwrap();
+//! upsert_statements(&mut conn, [&statement]).unwrap();
+//!
+//! let trades: i64 = conn
+//!     .query_row("SELECT count(*) FROM trades", [], |row| row.get(0))
+//!     .unwrap();
+//! assert_eq!(trades as usize, statement.trades.len());
+//! ```
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use rusqlite::params_from_iter;
+use rusqlite::types::Value;
+
+pub use rusqlite::Connection;
+
+use super::records::{self, statement_rows};
+use crate::types::ActivityFlexStatement;
+use crate::{ParseError, Result};
+
+/// Tables created by [`create_schema`]
+///
+/// `row_key` is the `transactionID` when IB reports one. Otherwise it joins
+/// the fields that identify the record with `|`: for trades the account,
+/// conid, trade ID, time, quantity, price, and level of detail; for
+/// positions the account, report date, conid, side, level of detail, and
+/// open date/time; for cash transactions the account, conid, type, date,
+/// amount, currency, action ID, and level of detail; and for corporate
+/// actions the account, conid, action ID, report date, type, quantity, and
+/// amount.
+pub const SCHEMA: &str = "\
+CREATE TABLE IF NOT EXISTS accounts (
+    account_id TEXT PRIMARY KEY,
+    from_date TEXT NOT NULL,
+    to_date TEXT NOT NULL,
+    when_generated TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS securities (
+    conid INTEGER PRIMARY KEY,
+    symbol TEXT NOT NULL,
+    description TEXT,
+    asset_category TEXT,
+    currency TEXT,
+    isin TEXT,
+    cusip TEXT,
+    figi TEXT,
+    underlying_conid INTEGER,
+    underlying_symbol TEXT,
+    put_call TEXT,
+    strike TEXT,
+    expiry TEXT,
+    multiplier TEXT
+);
+
+CREATE TABLE IF NOT EXISTS trades (
+    row_key TEXT PRIMARY KEY,
+    transaction_id TEXT UNIQUE,
+    account_id TEXT NOT NULL REFERENCES accounts (account_id),
+    conid INTEGER NOT NULL REFERENCES securities (conid),
+    trade_id TEXT,
+    order_id TEXT,
+    trade_date TEXT,
+    trade_time TEXT,
+    settle_date TEXT,
+    buy_sell TEXT,
+    open_close TEXT,
+    quantity TEXT,
+    price TEXT,
+    proceeds TEXT,
+    commission TEXT,
+    taxes TEXT,
+    net_cash TEXT,
+    fifo_pnl_realized TEXT,
+    currency TEXT NOT NULL,
+    fx_rate_to_base TEXT,
+    level_of_detail TEXT,
+    notes TEXT
+);
+
+CREATE TABLE IF NOT EXISTS positions (
+    row_key TEXT PRIMARY KEY,
+    account_id TEXT NOT NULL REFERENCES accounts (account_id),
+    conid INTEGER NOT NULL REFERENCES securities (conid),
+    report_date TEXT NOT NULL,
+    side TEXT,
+    quantity TEXT NOT NULL,
+    mark_price TEXT NOT NULL,
+    position_value TEXT NOT NULL,
+    cost_basis_price TEXT,
+    cost_basis_money TEXT,
+    fifo_pnl_unrealized TEXT,
+    percent_of_nav TEXT,
+    currency TEXT NOT NULL,
+    fx_rate_to_base TEXT,
+    open_date_time TEXT,
+    level_of_detail TEXT
+);
+
+CREATE TABLE IF NOT EXISTS cash_transactions (
+    row_key TEXT PRIMARY KEY,
+    transaction_id TEXT UNIQUE,
+    account_id TEXT NOT NULL REFERENCES accounts (account_id),
+    conid INTEGER,
+    type TEXT,
+    description TEXT,
+    date TEXT,
+    settle_date TEXT,
+    ex_date TEXT,
+    amount TEXT NOT NULL,
+    currency TEXT NOT NULL,
+    fx_rate_to_base TEXT,
+    code TEXT,
+    action_id TEXT,
+    trade_id TEXT,
+    level_of_detail TEXT
+);
+
+CREATE TABLE IF NOT EXISTS corporate_actions (
+    row_key TEXT PRIMARY KEY,
+    transaction_id TEXT UNIQUE,
+    account_id TEXT NOT NULL REFERENCES accounts (account_id),
+    conid INTEGER NOT NULL REFERENCES securities (conid),
+    action_id TEXT,
+    type TEXT,
+    description TEXT,
+    report_date TEXT NOT NULL,
+    action_date TEXT,
+    ex_date TEXT,
+    pay_date TEXT,
+    quantity TEXT,
+    amount TEXT,
+    proceeds TEXT,
+    value TEXT,
+    fifo_pnl_realized TEXT,
+    currency TEXT,
+    code TEXT
+);
+";
+
+/// Open or create a database at `path` and create any missing tables
+pub fn open(path: impl AsRef<Path>) -> Result<Connection> {
+    let conn = Connection::open(path).map_err(sqlite_error)?;
+    create_schema(&conn)?;
+    Ok(conn)
+}
+
+/// Create the tables of [`SCHEMA`] that do not exist yet
+pub fn create_schema(conn: &Connection) -> Result<()> {
+    conn.execute_batch(SCHEMA).map_err(sqlite_error)
+}
+
+/// Insert or update every record of `statements`, in one transaction
+///
+/// Records already in the database are replaced by the version in the
+/// statement; securities keep fields the statement leaves out. An
+/// account's date range widens to cover every statement loaded for it.
+pub fn upsert_statements<'a, I>(conn: &mut Connection, statements: I) -> Result<()>
+where
+    I: IntoIterator<Item = &'a ActivityFlexStatement>,
+{
+    let tx = conn.transaction().map_err(sqlite_error)?;
+    let mut upserts = HashMap::new();
+    for statement in statements {
+        for row in statement_rows(statement) {
+            let sql = upserts
+                .entry(row.table.name)
+                .or_insert_with(|| row.table.upsert_sql(|i| format!("?{}", i)));
+            let mut stmt = tx.prepare_cached(sql).map_err(sqlite_error)?;
+            stmt.execute(params_from_iter(row.values.into_iter().map(to_sql)))
+                .map_err(sqlite_error)?;
+        }
+    }
+    tx.commit().map_err(sqlite_error)
+}
+
+/// Stored form of a value: amounts, dates, and date/times as text
+fn to_sql(value: records::Value) -> Value {
+    let text = |text: Option<String>| text.map_or(Value::Null, Value::Text);
+    match value {
+        records::Value::Text(value) => text(value),
+        records::Value::Integer(value) => value.map_or(Value::Null, Value::Integer),
+        records::Value::Decimal(value) => text(value.map(|v| v.to_string())),
+        records::Value::Date(value) => text(value.map(|v| v.to_string())),
+        records::Value::DateTime(value) => text(value.map(|v| v.to_string())),
+    }
+}
+
+fn sqlite_error(e: rusqlite::Error) -> ParseError {
+    ParseError::SqliteError(e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal::Decimal;
+
+    fn count(conn: &Connection, table: &str) -> usize {
+        let count: i64 = conn
+            .query_row(&format!("SELECT count(*) FROM {}", table), [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        count as usize
+    }
+
+    #[test]
+    fn test_upserts_are_idempotent() {
+        let fixtures = [
+            include_str!("../../tests/fixtures/activity_cash.xml"),
+            include_str!("../../tests/fixtures/activity_corporate_actions.xml"),
+            include_str!("../../tests/fixtures/activity_backfill.xml"),
+        ];
+        let statements: Vec<ActivityFlexStatement> = fixtures
+            .iter()
+            .flat_map(|xml| crate::parse_activity_flex_all(xml).unwrap())
+            .collect();
+
+        let mut conn = Connection::open_in_memory().unwrap();
+        create_schema(&conn).unwrap();
+        upsert_statements(&mut conn, &statements).unwrap();
+        let tables = [
+            "accounts",
+            "securities",
+            "trades",
+            "positions",
+            "cash_transactions",
+            "corporate_actions",
+        ];
+        let counts: Vec<usize> = tables.iter().map(|t| count(&conn, t)).collect();
+
+        create_schema(&conn).unwrap();
+        upsert_statements(&mut conn, statements.iter().rev()).unwrap();
+        let again: Vec<usize> = tables.iter().map(|t| count(&conn, t)).collect();
+        assert_eq!(counts, again);
+
+        let cash: usize = statements.iter().map(|s| s.cash_transactions.len()).sum();
+        assert_eq!(count(&conn, "cash_transactions"), cash);
+        assert!(count(&conn, "corporate_actions") > 0);
+
+        let (from, to): (String, String) = conn
+            .query_row(
+                "SELECT from_date, to_date FROM accounts WHERE account_id = ?1",
+                [statements[2].account_id.as_str()],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert!(from <= to);
+    }
+
+    #[test]
+    fn test_amounts_keep_precision() {
+        let xml = include_str!("../../tests/fixtures/activity_minimal.xml");
+        let statement = crate::parse_activity_flex(xml).unwrap();
+        let mut conn = Connection::open_in_memory().unwrap();
+        create_schema(&conn).unwrap();
+        upsert_statements(&mut conn, [&statement]).unwrap();
+
+        let trade = &statement.trades[0];
+        let (price, date): (String, String) = conn
+            .query_row("SELECT price, trade_date FROM trades", [], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .unwrap();
+        assert_eq!(Some(price.parse::<Decimal>().unwrap()), trade.price);
+        assert_eq!(date, "2025-01-15");
+    }
+}
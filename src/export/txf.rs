@@ -0,0 +1,179 @@
+//! TXF export of realized gains
+//!
+//! [`write_rows`] writes [`Form8949Row`]s as TXF (Tax Exchange Format,
+//! version 042) records that US tax software imports as Form 8949 lines:
+//!
+//! | | Basis reported to the IRS | Basis not reported |
+//! |---|---|---|
+//! | Short term | 321 (box A) | 711 (box B) |
+//! | Long term | 323 (box D) | 713 (box E) |
+//! | Short term, wash sale | 682 (box A) | 715 (box B) |
+//! | Long term, wash sale | 718 (box D) | 719 (box E) |
+//!
+//! Each record gives the description, acquisition and sale dates, cost
+//! basis, and proceeds; wash-sale records add the disallowed loss.
+//! Amounts are written as given, so convert rows to USD first.
+//!
+//! # Example
+//! ```
+//! use ib_flex::analytics::{form_8949_rows, LotLedger};
+//! use ib_flex::export::txf::{write_rows, TxfOptions};
+//! use ib_flex::parse_activity_flex;
+//!
+//! let xml = include_str!("../../tests/fixtures/activity_minimal.xml");
+//! let statement = parse_activity_flex(xml).unwrap();
+//!
+//! let mut ledger = LotLedger::new();
+//! ledger.apply_trades(&statement.trades);
+//!
+//! let mut out = Vec::new();
+//! let rows = form_8949_rows(ledger.closures());
+//! write_rows(&mut out, &rows, &TxfOptions::default()).unwrap();
+//! assert!(out.starts_with(b"V042\nAib-flex\n"));
+//! ```
+
+use std::fmt::Write as _;
+use std::io::Write;
+
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+
+use crate::analytics::{Form8949Row, HoldingPeriod};
+use crate::Result;
+
+/// Header fields and reporting category for the TXF writer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TxfOptions {
+    /// Program named in the header (default `ib-flex`)
+    pub program: String,
+    /// Export date in the header; `None` uses today
+    pub date: Option<NaiveDate>,
+    /// Whether the broker reported the cost basis to the IRS (boxes A and
+    /// D), as IB does for covered securities (default true)
+    pub basis_reported: bool,
+}
+
+impl Default for TxfOptions {
+    fn default() -> Self {
+        TxfOptions {
+            program: "ib-flex".to_string(),
+            date: None,
+            basis_reported: true,
+        }
+    }
+}
+
+/// Write `rows` as a TXF file, one record each
+pub fn write_rows<'a, W, I>(mut writer: W, rows: I, options: &TxfOptions) -> Result<()>
+where
+    W: Write,
+    I: IntoIterator<Item = &'a Form8949Row>,
+{
+    let date = options
+        .date
+        .unwrap_or_else(|| chrono::Local::now().date_naive());
+    let mut out = String::new();
+    let _ = write!(out, "V042\nA{}\nD{}\n^\n", options.program, txf_date(date));
+    for row in rows {
+        let wash_sale = !row.adjustment.is_zero();
+        let _ = write!(
+            out,
+            "TD\nN{}\nC1\nL1\nP{}\nD{}\nD{}\n${}\n${}\n",
+            refnum(row, wash_sale, options.basis_reported),
+            row.description,
+            txf_date(row.date_acquired),
+            txf_date(row.date_sold),
+            amount(row.cost_basis),
+            amount(row.proceeds)
+        );
+        if wash_sale {
+            let _ = writeln!(out, "${}", amount(row.adjustment));
+        }
+        out.push_str("^\n");
+    }
+    writer.write_all(out.as_bytes())?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// TXF reference number of the Form 8949 category a row falls in
+fn refnum(row: &Form8949Row, wash_sale: bool, basis_reported: bool) -> u32 {
+    let long_term = row.holding_period == HoldingPeriod::LongTerm;
+    match (long_term, wash_sale, basis_reported) {
+        (false, false, true) => 321,
+        (true, false, true) => 323,
+        (false, false, false) => 711,
+        (true, false, false) => 713,
+        (false, true, true) => 682,
+        (true, true, true) => 718,
+        (false, true, false) => 715,
+        (true, true, false) => 719,
+    }
+}
+
+fn txf_date(date: NaiveDate) -> String {
+    date.format("%m/%d/%Y").to_string()
+}
+
+/// `value` to the cent, as `1234.50`
+fn amount(value: Decimal) -> String {
+    format!("{:.2}", value.round_dp(2))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analytics::{form_8949_rows, LotClosure};
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn closure(open: NaiveDate, proceeds: i64, basis: i64, disallowed: i64) -> LotClosure {
+        LotClosure {
+            conid: 1.into(),
+            symbol: "AAPL".to_string(),
+            currency: "USD".to_string(),
+            opening_trade_id: None,
+            closing_trade_id: None,
+            open_date: open,
+            close_date: date(2025, 6, 2),
+            quantity: Decimal::from(100),
+            proceeds: Decimal::from(proceeds),
+            cost_basis: Decimal::from(basis),
+            realized_pnl: Decimal::from(proceeds - basis),
+            basis_adjustment: Decimal::ZERO,
+            disallowed_loss: Decimal::from(disallowed),
+        }
+    }
+
+    #[test]
+    fn test_records_by_category() {
+        let closures = [
+            closure(date(2025, 3, 3), 15000, 16000, 400),
+            closure(date(2023, 4, 3), 9000, 6000, 0),
+        ];
+        let rows = form_8949_rows(&closures);
+        let options = TxfOptions {
+            date: Some(date(2026, 2, 1)),
+            ..Default::default()
+        };
+        let mut out = Vec::new();
+        write_rows(&mut out, &rows, &options).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "V042\nAib-flex\nD02/01/2026\n^\n\
+             TD\nN682\nC1\nL1\nP100 AAPL\nD03/03/2025\nD06/02/2025\n$16000.00\n$15000.00\n$400.00\n^\n\
+             TD\nN323\nC1\nL1\nP100 AAPL\nD04/03/2023\nD06/02/2025\n$6000.00\n$9000.00\n^\n"
+        );
+
+        let mut out = Vec::new();
+        let options = TxfOptions {
+            basis_reported: false,
+            ..options
+        };
+        write_rows(&mut out, &rows, &options).unwrap();
+        let txf = String::from_utf8(out).unwrap();
+        assert!(txf.contains("N715\n") && txf.contains("N713\n"));
+    }
+}
@@ -0,0 +1,307 @@
+//! Excel workbook export
+//!
+//! [`write_statements`] writes statements as one `.xlsx` workbook with six
+//! sheets:
+//!
+//! - `Summary`: one row per statement, with its period, base currency, row
+//!   counts, and starting and ending cash in the base currency;
+//! - `Trades`, `Positions`, and `Cash`: the columns of
+//!   [`csv::write_trades`], [`csv::write_positions`], and
+//!   [`csv::write_cash_transactions`];
+//! - `Dividends`: the columns of [`csv::write_dividends`];
+//! - `Fees`: fee cash transactions, with the columns of `Cash`.
+//!
+//! Cells are typed: amounts are numbers, dates and date/times are Excel
+//! dates formatted `yyyy-mm-dd` and `yyyy-mm-dd hh:mm:ss`, and everything
+//! else is text. Missing values are empty cells. Header rows are bold and
+//! frozen, and columns are sized to their type. Excel holds numbers as
+//! doubles, so amounts with more than 15 significant digits are rounded;
+//! use [`csv`] where every digit matters.
+//!
+//! Workbooks are written with `rust_xlsxwriter`. Requires the `xlsx`
+//! feature.
+//!
+//! # Example
+//! ```
+//! use ib_flex::export::xlsx::write_statements;
+//! use ib_flex::parse_activity_flex;
+//!
+//! let xml = include_str!("../../tests/fixtures/activity_minimal.xml");
+//! let statement = parse_activity_flex(xml).unwrap();
+//!
+//! let mut out = Vec::new();
+//! write_statements(&mut out, [&statement]).unwrap();
+//! assert!(out.starts_with(b"PK\x03\x04"));
+//! ```
+//!
+//! [`csv`]: super::csv
+//! [`csv::write_trades`]: super::csv::write_trades
+//! [`csv::write_positions`]: super::csv::write_positions
+//! [`csv::write_cash_transactions`]: super::csv::write_cash_transactions
+//! [`csv::write_dividends`]: super::csv::write_dividends
+
+use std::borrow::Cow;
+use std::io::Write;
+
+use rust_decimal::prelude::ToPrimitive;
+use rust_xlsxwriter::{Format, Workbook, Worksheet, XlsxError};
+
+use super::columns::{
+    Cell, Column, Kind, CASH_COLUMNS, DIVIDEND_COLUMNS, POSITION_COLUMNS, TRADE_COLUMNS,
+};
+use crate::types::{ActivityFlexStatement, CashReportCurrency};
+use crate::{ParseError, Result};
+
+/// Write `statements` as an `.xlsx` workbook
+///
+/// Each sheet holds the rows of all statements, in order.
+pub fn write_statements<'a, W, I>(mut writer: W, statements: I) -> Result<()>
+where
+    W: Write,
+    I: IntoIterator<Item = &'a ActivityFlexStatement>,
+{
+    let statements: Vec<&ActivityFlexStatement> = statements.into_iter().collect();
+    let trades = statements.iter().flat_map(|s| &s.trades);
+    let positions = statements.iter().flat_map(|s| &s.positions);
+    let cash = statements.iter().flat_map(|s| &s.cash_transactions);
+
+    let mut workbook = Workbook::new();
+    let styles = Styles::default();
+    sheet(
+        workbook.add_worksheet(),
+        "Summary",
+        &styles,
+        SUMMARY_COLUMNS,
+        statements.iter().copied(),
+    )?;
+    sheet(
+        workbook.add_worksheet(),
+        "Trades",
+        &styles,
+        TRADE_COLUMNS,
+        trades,
+    )?;
+    sheet(
+        workbook.add_worksheet(),
+        "Positions",
+        &styles,
+        POSITION_COLUMNS,
+        positions,
+    )?;
+    sheet(
+        workbook.add_worksheet(),
+        "Cash",
+        &styles,
+        CASH_COLUMNS,
+        cash.clone(),
+    )?;
+    sheet(
+        workbook.add_worksheet(),
+        "Dividends",
+        &styles,
+        DIVIDEND_COLUMNS,
+        cash.clone()
+            .filter(|tx| tx.is_dividend() || tx.is_withholding_tax()),
+    )?;
+    sheet(
+        workbook.add_worksheet(),
+        "Fees",
+        &styles,
+        CASH_COLUMNS,
+        cash.filter(|tx| tx.is_fee()),
+    )?;
+
+    writer.write_all(&workbook.save_to_buffer().map_err(xlsx_error)?)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Summary columns, per statement
+const SUMMARY_COLUMNS: &[Column<ActivityFlexStatement>] = &[
+    Column {
+        name: "account_id",
+        kind: Kind::Text,
+        value: |s| Cell::Text(Some(Cow::Borrowed(s.account_id.as_str()))),
+    },
+    Column {
+        name: "from_date",
+        kind: Kind::Date,
+        value: |s| Cell::Date(Some(s.from_date)),
+    },
+    Column {
+        name: "to_date",
+        kind: Kind::Date,
+        value: |s| Cell::Date(Some(s.to_date)),
+    },
+    Column {
+        name: "base_currency",
+        kind: Kind::Text,
+        value: |s| {
+            Cell::Text(
+                s.account_information
+                    .as_ref()
+                    .and_then(|info| info.currency.as_deref())
+                    .map(Cow::Borrowed),
+            )
+        },
+    },
+    Column {
+        name: "trades",
+        kind: Kind::Decimal,
+        value: |s| Cell::Decimal(Some(s.trades.len().into())),
+    },
+    Column {
+        name: "positions",
+        kind: Kind::Decimal,
+        value: |s| Cell::Decimal(Some(s.positions.len().into())),
+    },
+    Column {
+        name: "cash_transactions",
+        kind: Kind::Decimal,
+        value: |s| Cell::Decimal(Some(s.cash_transactions.len().into())),
+    },
+    Column {
+        name: "starting_cash",
+        kind: Kind::Decimal,
+        value: |s| Cell::Decimal(base_summary(s).map(|row| row.starting_cash)),
+    },
+    Column {
+        name: "ending_cash",
+        kind: Kind::Decimal,
+        value: |s| Cell::Decimal(base_summary(s).map(|row| row.ending_cash)),
+    },
+];
+
+fn base_summary(s: &ActivityFlexStatement) -> Option<&CashReportCurrency> {
+    s.cash_report
+        .iter()
+        .find(|row| row.currency == "BASE_SUMMARY")
+}
+
+/// Header, date, and date/time cell formats
+struct Styles {
+    header: Format,
+    date: Format,
+    date_time: Format,
+}
+
+impl Default for Styles {
+    fn default() -> Self {
+        Styles {
+            header: Format::new().set_bold(),
+            date: Format::new().set_num_format("yyyy-mm-dd"),
+            date_time: Format::new().set_num_format("yyyy-mm-dd hh:mm:ss"),
+        }
+    }
+}
+
+/// Fill `worksheet` with a bold, frozen header row, then one row per item
+fn sheet<'a, T: 'a>(
+    worksheet: &mut Worksheet,
+    name: &str,
+    styles: &Styles,
+    columns: &[Column<T>],
+    rows: impl Iterator<Item = &'a T>,
+) -> Result<()> {
+    worksheet.set_name(name).map_err(xlsx_error)?;
+    worksheet.set_freeze_panes(1, 0).map_err(xlsx_error)?;
+    for (col, column) in (0..).zip(columns) {
+        let width = match column.kind {
+            Kind::Text => 14,
+            Kind::Decimal => 14,
+            Kind::Date => 12,
+            Kind::DateTime => 20,
+        }
+        .max(column.name.len() + 2);
+        worksheet
+            .set_column_width(col, width as f64)
+            .map_err(xlsx_error)?;
+        worksheet
+            .write_string_with_format(0, col, column.name, &styles.header)
+            .map_err(xlsx_error)?;
+    }
+    for (row, item) in (1..).zip(rows) {
+        for (col, column) in (0..).zip(columns) {
+            let written = match (column.value)(item) {
+                Cell::Text(Some(text)) => worksheet.write_string(row, col, text),
+                Cell::Decimal(Some(value)) => match value.to_f64() {
+                    Some(number) => worksheet.write_number(row, col, number),
+                    None => continue,
+                },
+                Cell::Date(Some(date)) => {
+                    worksheet.write_datetime_with_format(row, col, date, &styles.date)
+                }
+                Cell::DateTime(Some(dt)) => {
+                    worksheet.write_datetime_with_format(row, col, dt, &styles.date_time)
+                }
+                Cell::Text(None)
+                | Cell::Decimal(None)
+                | Cell::Date(None)
+                | Cell::DateTime(None) => continue,
+            };
+            written.map_err(xlsx_error)?;
+        }
+    }
+    Ok(())
+}
+
+fn xlsx_error(err: XlsxError) -> ParseError {
+    ParseError::XlsxError(err.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Read};
+
+    use super::*;
+
+    /// Contents of the entry `name` in the workbook
+    fn entry(workbook: &[u8], name: &str) -> String {
+        let mut archive = zip::ZipArchive::new(Cursor::new(workbook)).unwrap();
+        let mut contents = String::new();
+        archive
+            .by_name(name)
+            .unwrap_or_else(|_| panic!("no {}", name))
+            .read_to_string(&mut contents)
+            .unwrap();
+        contents
+    }
+
+    #[test]
+    fn test_workbook_sheets_and_cells() {
+        let xml = include_str!("../../tests/fixtures/activity_cash.xml");
+        let statement = crate::parse_activity_flex(xml).unwrap();
+        let mut out = Vec::new();
+        write_statements(&mut out, [&statement]).unwrap();
+
+        let workbook = entry(&out, "xl/workbook.xml");
+        for name in [
+            "Summary",
+            "Trades",
+            "Positions",
+            "Cash",
+            "Dividends",
+            "Fees",
+        ] {
+            assert!(workbook.contains(&format!("<sheet name=\"{}\"", name)));
+        }
+
+        let summary = entry(&out, "xl/worksheets/sheet1.xml");
+        assert!(summary.contains("state=\"frozen\""));
+        // 2025-01-15 as an Excel serial date, in the date format
+        assert!(summary.contains("<c r=\"B2\" s=\"2\"><v>45672</v></c>"));
+
+        let cash = entry(&out, "xl/worksheets/sheet4.xml");
+        let rows = cash.matches("<row ").count();
+        assert_eq!(rows, 1 + statement.cash_transactions.len());
+
+        let fees = entry(&out, "xl/worksheets/sheet6.xml");
+        let fee_count = statement
+            .cash_transactions
+            .iter()
+            .filter(|c| c.is_fee())
+            .count();
+        assert!(fee_count > 0);
+        assert_eq!(fees.matches("<row ").count(), 1 + fee_count);
+    }
+}
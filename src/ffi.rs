@@ -0,0 +1,379 @@
+//! C API (requires the `ffi` feature)
+//!
+//! `extern "C"` functions for embedding the parser in C, C++, or C#
+//! programs, declared in [`include/ib_flex.h`]. Build a shared or static
+//! library with
+//!
+//! ```text
+//! cargo rustc --lib --release --features ffi --crate-type cdylib
+//! cargo rustc --lib --release --features ffi --crate-type staticlib
+//! ```
+//!
+//! Parsing returns an opaque handle owning the statements of one document;
+//! accessors take the handle and a statement index, and the handle is freed
+//! with [`ib_flex_free`]. Rows are read as JSON, in the format of
+//! [`export::json`], rather than through a struct per record type, so the
+//! API stays stable as fields are added:
+//!
+//! ```c
+//! IbFlexStatements *statements = ib_flex_parse_file("activity.xml");
+//! if (!statements) {
+//!     fprintf(stderr, "%s\n", ib_flex_last_error());
+//!     return 1;
+//! }
+//! for (size_t i = 0; i < ib_flex_statement_count(statements); i++) {
+//!     printf("%s: %zu trades\n", ib_flex_account_id(statements, i),
+//!            ib_flex_trade_count(statements, i));
+//!     char *json = ib_flex_statement_json(statements, i);
+//!     /* ... */
+//!     ib_flex_string_free(json);
+//! }
+//! ib_flex_free(statements);
+//! ```
+//!
+//! Strings returned as `const char *` belong to the handle and live until
+//! it is freed; strings returned as `char *` belong to the caller, who
+//! frees them with [`ib_flex_string_free`]. Functions that fail return
+//! null (or zero for counts) and set a message read with
+//! [`ib_flex_last_error`], kept per thread.
+//!
+//! [`include/ib_flex.h`]: https://github.com/clifton/ib-flex/blob/main/include/ib_flex.h
+//! [`export::json`]: crate::export::json
+
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::ptr;
+
+use crate::export::json;
+use crate::types::ActivityFlexStatement;
+use crate::{ParseError, Result};
+
+/// The statements of one parsed document
+pub struct IbFlexStatements {
+    statements: Vec<ActivityFlexStatement>,
+    /// Account IDs as C strings, for [`ib_flex_account_id`]
+    accounts: Vec<CString>,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<CString> = RefCell::new(CString::default());
+}
+
+/// Parse an Activity FLEX file
+///
+/// Returns null on failure.
+///
+/// # Safety
+///
+/// `path` must be null or a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn ib_flex_parse_file(path: *const c_char) -> *mut IbFlexStatements {
+    handle(str_arg(path, "path").and_then(|path| {
+        let xml = std::fs::read_to_string(path)?;
+        crate::parse_activity_flex_all(&xml)
+    }))
+}
+
+/// Parse an Activity FLEX document held in memory
+///
+/// Returns null on failure.
+///
+/// # Safety
+///
+/// `xml` must be null or a NUL-terminated UTF-8 string.
+#[no_mangle]
+pub unsafe extern "C" fn ib_flex_parse_string(xml: *const c_char) -> *mut IbFlexStatements {
+    handle(str_arg(xml, "xml").and_then(crate::parse_activity_flex_all))
+}
+
+/// Free a handle returned by a parse function; null is ignored
+///
+/// # Safety
+///
+/// `statements` must be null or a handle not yet freed.
+#[no_mangle]
+pub unsafe extern "C" fn ib_flex_free(statements: *mut IbFlexStatements) {
+    if !statements.is_null() {
+        drop(Box::from_raw(statements));
+    }
+}
+
+/// Free a string returned as `char *`; null is ignored
+///
+/// # Safety
+///
+/// `string` must be null or a string returned as `char *` by this API and
+/// not yet freed.
+#[no_mangle]
+pub unsafe extern "C" fn ib_flex_string_free(string: *mut c_char) {
+    if !string.is_null() {
+        drop(CString::from_raw(string));
+    }
+}
+
+/// The message of the last failure on this thread, or an empty string
+///
+/// The string lives until the next failing call on the same thread.
+#[no_mangle]
+pub extern "C" fn ib_flex_last_error() -> *const c_char {
+    LAST_ERROR.with(|error| error.borrow().as_ptr())
+}
+
+/// Number of statements in the document
+///
+/// # Safety
+///
+/// `statements` must be null or a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn ib_flex_statement_count(statements: *const IbFlexStatements) -> usize {
+    statements.as_ref().map_or(0, |s| s.statements.len())
+}
+
+/// Account ID of a statement, owned by the handle
+///
+/// # Safety
+///
+/// `statements` must be null or a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn ib_flex_account_id(
+    statements: *const IbFlexStatements,
+    index: usize,
+) -> *const c_char {
+    match statements.as_ref().and_then(|s| s.accounts.get(index)) {
+        Some(account) => account.as_ptr(),
+        None => {
+            set_error(&out_of_range(index));
+            ptr::null()
+        }
+    }
+}
+
+/// Number of trades in a statement
+///
+/// # Safety
+///
+/// `statements` must be null or a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn ib_flex_trade_count(
+    statements: *const IbFlexStatements,
+    index: usize,
+) -> usize {
+    statement(statements, index).map_or(0, |s| s.trades.len())
+}
+
+/// Number of open positions in a statement
+///
+/// # Safety
+///
+/// `statements` must be null or a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn ib_flex_position_count(
+    statements: *const IbFlexStatements,
+    index: usize,
+) -> usize {
+    statement(statements, index).map_or(0, |s| s.positions.len())
+}
+
+/// Number of cash transactions in a statement
+///
+/// # Safety
+///
+/// `statements` must be null or a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn ib_flex_cash_transaction_count(
+    statements: *const IbFlexStatements,
+    index: usize,
+) -> usize {
+    statement(statements, index).map_or(0, |s| s.cash_transactions.len())
+}
+
+/// Every statement as a JSON array, owned by the caller
+///
+/// # Safety
+///
+/// `statements` must be null or a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn ib_flex_to_json(statements: *const IbFlexStatements) -> *mut c_char {
+    let all = statements
+        .as_ref()
+        .ok_or_else(|| null_argument("statements"));
+    json_string(all.and_then(|s| json::to_string(&s.statements)))
+}
+
+/// A statement as a JSON object, owned by the caller
+///
+/// # Safety
+///
+/// `statements` must be null or a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn ib_flex_statement_json(
+    statements: *const IbFlexStatements,
+    index: usize,
+) -> *mut c_char {
+    json_string(statement(statements, index).and_then(json::to_string))
+}
+
+/// A statement's trades as a JSON array, owned by the caller
+///
+/// # Safety
+///
+/// `statements` must be null or a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn ib_flex_trades_json(
+    statements: *const IbFlexStatements,
+    index: usize,
+) -> *mut c_char {
+    json_string(statement(statements, index).and_then(|s| json::to_string(&s.trades.items)))
+}
+
+/// A statement's open positions as a JSON array, owned by the caller
+///
+/// # Safety
+///
+/// `statements` must be null or a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn ib_flex_positions_json(
+    statements: *const IbFlexStatements,
+    index: usize,
+) -> *mut c_char {
+    json_string(statement(statements, index).and_then(|s| json::to_string(&s.positions.items)))
+}
+
+/// A statement's cash transactions as a JSON array, owned by the caller
+///
+/// # Safety
+///
+/// `statements` must be null or a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn ib_flex_cash_transactions_json(
+    statements: *const IbFlexStatements,
+    index: usize,
+) -> *mut c_char {
+    json_string(
+        statement(statements, index).and_then(|s| json::to_string(&s.cash_transactions.items)),
+    )
+}
+
+/// The handle for parsed statements, or null after recording the error
+fn handle(statements: Result<Vec<ActivityFlexStatement>>) -> *mut IbFlexStatements {
+    match statements {
+        Ok(statements) => {
+            let accounts = statements
+                .iter()
+                .map(|s| c_string(s.account_id.as_str()))
+                .collect();
+            Box::into_raw(Box::new(IbFlexStatements {
+                statements,
+                accounts,
+            }))
+        }
+        Err(err) => {
+            set_error(&err);
+            ptr::null_mut()
+        }
+    }
+}
+
+/// A caller-owned string, or null after recording the error
+fn json_string(json: Result<String>) -> *mut c_char {
+    match json {
+        Ok(json) => c_string(&json).into_raw(),
+        Err(err) => {
+            set_error(&err);
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Statement `index`, or the error for a null handle or bad index
+unsafe fn statement<'a>(
+    statements: *const IbFlexStatements,
+    index: usize,
+) -> Result<&'a ActivityFlexStatement> {
+    let statements = statements
+        .as_ref()
+        .ok_or_else(|| null_argument("statements"))?;
+    let statement = statements.statements.get(index);
+    statement.ok_or_else(|| out_of_range(index)).map_err(|err| {
+        set_error(&err);
+        err
+    })
+}
+
+unsafe fn str_arg<'a>(value: *const c_char, name: &str) -> Result<&'a str> {
+    if value.is_null() {
+        return Err(null_argument(name));
+    }
+    CStr::from_ptr(value)
+        .to_str()
+        .map_err(|err| ParseError::InvalidArgument(format!("{} is not UTF-8: {}", name, err)))
+}
+
+fn null_argument(name: &str) -> ParseError {
+    let err = ParseError::InvalidArgument(format!("{} is null", name));
+    set_error(&err);
+    err
+}
+
+fn out_of_range(index: usize) -> ParseError {
+    ParseError::InvalidArgument(format!("No statement at index {}", index))
+}
+
+fn set_error(err: &ParseError) {
+    LAST_ERROR.with(|error| *error.borrow_mut() = c_string(&err.to_string()));
+}
+
+/// `value` as a C string, cut at any interior NUL
+fn c_string(value: &str) -> CString {
+    let value = value.split('\0').next().unwrap_or_default();
+    CString::new(value).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_read() {
+        let xml = CString::new(include_str!("../tests/fixtures/activity_minimal.xml")).unwrap();
+        unsafe {
+            let statements = ib_flex_parse_string(xml.as_ptr());
+            assert!(!statements.is_null());
+            assert_eq!(ib_flex_statement_count(statements), 1);
+            let account = CStr::from_ptr(ib_flex_account_id(statements, 0));
+            assert_eq!(account.to_str().unwrap(), "U1234567");
+            assert_eq!(ib_flex_trade_count(statements, 0), 1);
+
+            let trades = ib_flex_trades_json(statements, 0);
+            let json = CStr::from_ptr(trades).to_str().unwrap().to_string();
+            assert!(json.starts_with(r#"[{"account_id":"U1234567""#));
+            ib_flex_string_free(trades);
+
+            assert!(ib_flex_statement_json(statements, 1).is_null());
+            let error = CStr::from_ptr(ib_flex_last_error());
+            assert_eq!(
+                error.to_str().unwrap(),
+                "Invalid argument: No statement at index 1"
+            );
+            ib_flex_free(statements);
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        let xml = CString::new("<FlexQueryResponse").unwrap();
+        unsafe {
+            assert!(ib_flex_parse_string(xml.as_ptr()).is_null());
+            assert!(!CStr::from_ptr(ib_flex_last_error()).to_bytes().is_empty());
+            assert!(ib_flex_parse_string(ptr::null()).is_null());
+            assert!(CStr::from_ptr(ib_flex_last_error())
+                .to_str()
+                .unwrap()
+                .contains("xml is null"));
+            let missing = CString::new("/nonexistent/activity.xml").unwrap();
+            assert!(ib_flex_parse_file(missing.as_ptr()).is_null());
+            assert_eq!(ib_flex_trade_count(ptr::null(), 0), 0);
+            ib_flex_free(ptr::null_mut());
+        }
+    }
+}
@@ -47,27 +47,77 @@
 //!
 //! ## Known Limitations
 //!
-//! 1. **Date formats**: Only ISO-8601 (yyyy-MM-dd) and yyyyMMdd supported
+//! 1. **Date formats**: Ambiguous `dd/MM` vs `MM/dd` dates are read month-first
+//!    (see [`parsers::datetime`])
 //! 2. **Schema versions**: Tested with FLEX schema version 3
+//! 3. **Numeric types**: Amounts in the full records are always
+//!    `rust_decimal::Decimal`; only the compact records of
+//!    [`parse_activity_flex_compact_f64`] come with `f64` amounts.
 
 #![warn(missing_docs)]
 #![warn(clippy::all)]
 
+pub mod analytics;
+pub mod anonymize;
+#[cfg(feature = "proptest")]
+pub mod arbitrary;
+#[cfg(feature = "cache")]
+pub mod cache;
+pub mod diagnostics;
 pub mod error;
+pub mod export;
+#[cfg(feature = "ffi")]
+pub mod ffi;
 pub mod parsers;
+#[cfg(feature = "proto")]
+pub mod proto;
+pub mod provenance;
+#[cfg(feature = "python")]
+mod python;
+#[cfg(feature = "test-util")]
+pub mod snapshot;
+#[cfg(feature = "spill")]
+pub mod spill;
+#[cfg(feature = "test-util")]
+pub mod test_util;
 pub mod types;
+pub mod validate;
 pub mod version;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "watch")]
+pub mod watch;
+#[cfg(feature = "serialize")]
+pub mod writer;
 
 #[cfg(feature = "api-client")]
 pub mod api;
 
 // Re-export commonly used types
-pub use error::{ParseError, Result};
+#[cfg(feature = "serialize")]
+pub use anonymize::anonymize;
+pub use anonymize::{anonymize_xml, AnonymizeOptions};
+pub use error::{ParseError, Result, SectionError};
+pub use parsers::{
+    parse_activity_flex_all_with_options, parse_activity_flex_all_with_warnings,
+    parse_activity_flex_compact, parse_activity_flex_compact_f64, parse_activity_flex_with_options,
+    parse_activity_statement_csv, parse_trade_confirmation_with_options, parse_tws_trades_csv,
+    parse_untrusted, parse_untrusted_with_options, ParseOptions, ParseWarning,
+};
+#[cfg(feature = "serialize")]
+pub use parsers::{parse_activity_flex_all_with_report, IgnoredReport};
+pub use provenance::{ParsedStatement, Provenance};
+#[cfg(all(feature = "serialize", feature = "eq"))]
+pub use types::diff_statements;
+#[cfg(feature = "eq")]
+pub use types::merge_statements;
 pub use types::{
     ActivityFlexStatement, AssetCategory, BuySell, CashTransaction, CorporateAction, OpenClose,
     OrderType, Position, PutCall, Trade, TradeConfirmationStatement,
 };
 pub use version::FlexSchemaVersion;
+#[cfg(feature = "serialize")]
+pub use writer::{write_activity_flex, write_activity_flex_to};
 
 /// Parse an Activity FLEX XML statement
 ///
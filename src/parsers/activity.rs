@@ -1,7 +1,11 @@
 //! Activity FLEX parser
 
-use crate::error::{ParseError, Result};
+use quick_xml::events::Event;
+
+use super::options::ParseOptions;
+use crate::error::{ParseError, Result, SectionError};
 use crate::types::activity::FlexQueryResponse;
+use crate::types::summary::{CompactResponse, CompactStatement, CompactStatementF64};
 use crate::types::ActivityFlexStatement;
 
 /// Parse an Activity FLEX XML statement
@@ -20,15 +24,52 @@ use crate::types::ActivityFlexStatement;
 /// Returns `ParseError` if XML is malformed, required fields are missing,
 /// or date/decimal formats are invalid.
 pub fn parse_activity_flex(xml: &str) -> Result<ActivityFlexStatement> {
+    first_statement(parse_response(xml, &ParseOptions::new(), &mut Vec::new())?)
+}
+
+/// Parse all Activity FLEX statements from XML
+///
+/// FLEX queries can contain multiple statements (e.g., for different days
+/// in a date range backfill). This function returns all of them.
+///
+/// # Arguments
+///
+/// * `xml` - XML string from IB Activity FLEX query
+///
+/// # Returns
+///
+/// * `Ok(Vec<ActivityFlexStatement>)` - All parsed statements
+/// * `Err(ParseError)` - Parse error with context
+///
+/// # Errors
+///
+/// Returns `ParseError` if XML is malformed, required fields are missing,
+/// or date/decimal formats are invalid.
+pub fn parse_activity_flex_all(xml: &str) -> Result<Vec<ActivityFlexStatement>> {
+    let response = parse_response(xml, &ParseOptions::new(), &mut Vec::new())?;
+    Ok(response.statements.statements)
+}
+
+/// Parse the `FlexQueryResponse` of `xml` with `options`' deserializer
+/// settings, adding each decimal read in tolerant form to `normalized`
+fn parse_response(
+    xml: &str,
+    options: &ParseOptions,
+    normalized: &mut Vec<String>,
+) -> Result<FlexQueryResponse> {
     // Parse XML using quick-xml with serde
-    let response: FlexQueryResponse =
-        quick_xml::de::from_str(xml).map_err(|e| ParseError::XmlError {
+    let xml = crate::version::to_current_schema(xml)?;
+    options
+        .deserialize(&xml, normalized)
+        .map_err(|e| ParseError::XmlError {
             message: format!("Failed to parse FLEX XML: {}", e),
             location: None,
-        })?;
+        })
+}
 
-    // Extract the first statement
-    let statement = response
+/// The first statement of a response
+fn first_statement(response: FlexQueryResponse) -> Result<ActivityFlexStatement> {
+    response
         .statements
         .statements
         .into_iter()
@@ -36,33 +77,218 @@ pub fn parse_activity_flex(xml: &str) -> Result<ActivityFlexStatement> {
         .ok_or_else(|| ParseError::MissingField {
             field: "FlexStatement".to_string(),
             context: "FlexQueryResponse".to_string(),
-        })?;
+        })
+}
 
+/// Parse an Activity FLEX XML statement with explicit [`ParseOptions`]
+///
+/// # Errors
+///
+/// Same as [`parse_activity_flex`].
+pub fn parse_activity_flex_with_options(
+    xml: &str,
+    options: &ParseOptions,
+) -> Result<ActivityFlexStatement> {
+    if options.recover_sections || options.strict_enums {
+        return parse_activity_flex_all_with_options(xml, options)?
+            .into_iter()
+            .next()
+            .ok_or_else(|| ParseError::MissingField {
+                field: "FlexStatement".to_string(),
+                context: "FlexQueryResponse".to_string(),
+            });
+    }
+    let mut statement = first_statement(parse_response(xml, options, &mut Vec::new())?)?;
+    statement.timezone = options.timezone_name();
     Ok(statement)
 }
 
-/// Parse all Activity FLEX statements from XML
+/// Parse all Activity FLEX statements with explicit [`ParseOptions`]
 ///
-/// FLEX queries can contain multiple statements (e.g., for different days
-/// in a date range backfill). This function returns all of them.
+/// # Errors
 ///
-/// # Arguments
+/// Same as [`parse_activity_flex_all`].
+pub fn parse_activity_flex_all_with_options(
+    xml: &str,
+    options: &ParseOptions,
+) -> Result<Vec<ActivityFlexStatement>> {
+    parse_activity_flex_all_recording(xml, options, &mut Vec::new())
+}
+
+/// [`parse_activity_flex_all_with_options`], adding each decimal read in
+/// tolerant form to `normalized`, as written
+pub(crate) fn parse_activity_flex_all_recording(
+    xml: &str,
+    options: &ParseOptions,
+    normalized: &mut Vec<String>,
+) -> Result<Vec<ActivityFlexStatement>> {
+    let mut statements = match parse_response(xml, options, normalized) {
+        Ok(response) => response.statements.statements,
+        Err(err) if options.recover_sections => {
+            parse_recovering_sections(xml, options, normalized).ok_or(err)?
+        }
+        Err(err) => return Err(err),
+    };
+    if options.strict_enums {
+        for (i, statement) in statements.iter().enumerate() {
+            if let Some(unknown) = super::warnings::unknown_values(statement)
+                .into_iter()
+                .next()
+            {
+                let statement = match statements.len() {
+                    1 => String::new(),
+                    _ => format!("FlexStatement[{}]/", i),
+                };
+                return Err(ParseError::UnknownEnumVariant {
+                    variant: unknown.value,
+                    enum_type: unknown.enum_type.to_string(),
+                    location: Some(format!("{}{} {}", statement, unknown.path, unknown.field)),
+                });
+            }
+        }
+    }
+    let timezone = options.timezone_name();
+    for statement in &mut statements {
+        statement.timezone = timezone.clone();
+    }
+    Ok(statements)
+}
+
+/// A section's element name and byte range in the document
+type Section = (String, usize, usize);
+
+/// Parse all statements, leaving out the sections that fail on their own
 ///
-/// * `xml` - XML string from IB Activity FLEX query
+/// Each section is tried in a copy of its statement with no other
+/// sections; the document is then parsed without the ones that fail.
+/// Returns `None` if the XML can't be read or still fails without them.
+fn parse_recovering_sections(
+    xml: &str,
+    options: &ParseOptions,
+    normalized: &mut Vec<String>,
+) -> Option<Vec<ActivityFlexStatement>> {
+    let mut reader = quick_xml::Reader::from_str(xml);
+    // Start tags of FlexQueryResponse and FlexStatements
+    let mut envelope: Vec<&str> = Vec::new();
+    // Each statement's start tag and its sections' names and spans
+    let mut statements: Vec<(&str, Vec<Section>)> = Vec::new();
+    let mut depth = 0;
+    let mut section_start = 0;
+    loop {
+        let start = reader.buffer_position() as usize;
+        let event = reader.read_event().ok()?;
+        let end = reader.buffer_position() as usize;
+        let (name, empty) = match &event {
+            Event::Start(tag) => (tag.name(), false),
+            Event::Empty(tag) => (tag.name(), true),
+            Event::End(tag) => {
+                depth -= 1;
+                if depth == 3 {
+                    let sections = &mut statements.last_mut()?.1;
+                    let name = String::from_utf8_lossy(tag.name().as_ref()).into_owned();
+                    sections.push((name, section_start, end));
+                }
+                continue;
+            }
+            Event::Eof => break,
+            _ => continue,
+        };
+        match depth {
+            0 | 1 => envelope.push(&xml[start..end]),
+            2 if !empty => statements.push((&xml[start..end], Vec::new())),
+            3 if empty => {
+                let name = String::from_utf8_lossy(name.as_ref()).into_owned();
+                statements.last_mut()?.1.push((name, start, end));
+            }
+            3 => section_start = start,
+            _ => {}
+        }
+        if !empty {
+            depth += 1;
+        }
+    }
+    let [response, wrapper] = envelope[..] else {
+        return None;
+    };
+
+    let mut errors: Vec<Vec<SectionError>> = Vec::new();
+    let mut skipped: Vec<(usize, usize)> = Vec::new();
+    for (statement, sections) in &statements {
+        let mut statement_errors = Vec::new();
+        for (name, start, end) in sections {
+            let alone = format!(
+                "{}{}{}{}</FlexStatement></FlexStatements></FlexQueryResponse>",
+                response,
+                wrapper,
+                statement,
+                &xml[*start..*end]
+            );
+            if let Err(err) = parse_response(&alone, options, &mut Vec::new()) {
+                let message = match err {
+                    ParseError::XmlError { message, .. } => message,
+                    other => other.to_string(),
+                };
+                statement_errors.push(SectionError {
+                    section: name.clone(),
+                    message,
+                });
+                skipped.push((*start, *end));
+            }
+        }
+        errors.push(statement_errors);
+    }
+
+    let mut kept = String::with_capacity(xml.len());
+    let mut position = 0;
+    for (start, end) in skipped {
+        kept.push_str(&xml[position..start]);
+        position = end;
+    }
+    kept.push_str(&xml[position..]);
+    let mut parsed = parse_response(&kept, options, normalized)
+        .ok()?
+        .statements
+        .statements;
+    for (statement, errors) in parsed.iter_mut().zip(errors) {
+        statement.section_errors = errors;
+    }
+    Some(parsed)
+}
+
+/// Parse Activity FLEX statements into compact trade and position records
 ///
-/// # Returns
+/// Only the fields of [`TradeSummary`](crate::types::TradeSummary) and
+/// [`PositionSummary`](crate::types::PositionSummary) are kept, and every
+/// other section is skipped, so large statements take a fraction of the
+/// memory of [`parse_activity_flex_all`].
 ///
-/// * `Ok(Vec<ActivityFlexStatement>)` - All parsed statements
-/// * `Err(ParseError)` - Parse error with context
+/// # Errors
+///
+/// Returns `ParseError` if XML is malformed or a kept field is missing or
+/// invalid.
+pub fn parse_activity_flex_compact(xml: &str) -> Result<Vec<CompactStatement>> {
+    parse_compact(xml)
+}
+
+/// Parse Activity FLEX statements into compact records with `f64` amounts
+///
+/// Like [`parse_activity_flex_compact`], but amounts are parsed straight to
+/// `f64` (see [`TradeSummaryF64`](crate::types::TradeSummaryF64)), for
+/// consumers that trade exactness for speed. Only trades and positions
+/// have an `f64` form; the full statement types keep `Decimal` either way.
 ///
 /// # Errors
 ///
-/// Returns `ParseError` if XML is malformed, required fields are missing,
-/// or date/decimal formats are invalid.
-pub fn parse_activity_flex_all(xml: &str) -> Result<Vec<ActivityFlexStatement>> {
-    // Parse XML using quick-xml with serde
-    let response: FlexQueryResponse =
-        quick_xml::de::from_str(xml).map_err(|e| ParseError::XmlError {
+/// Returns `ParseError` if XML is malformed or a kept field is missing or
+/// invalid.
+pub fn parse_activity_flex_compact_f64(xml: &str) -> Result<Vec<CompactStatementF64>> {
+    parse_compact(xml)
+}
+
+fn parse_compact<S: serde::de::DeserializeOwned>(xml: &str) -> Result<Vec<S>> {
+    let xml = crate::version::to_current_schema(xml)?;
+    let response: CompactResponse<S> =
+        quick_xml::de::from_str(&xml).map_err(|e| ParseError::XmlError {
             message: format!("Failed to parse FLEX XML: {}", e),
             location: None,
         })?;
@@ -91,4 +317,39 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_section_recovery() {
+        let xml = include_str!("../../tests/fixtures/activity_daily_portfolio.xml");
+        let broken = xml.replacen("quantity=\"", "quantity=\"x", 1);
+        assert!(parse_activity_flex_all(&broken).is_err());
+
+        let options = ParseOptions::new().with_section_recovery(true);
+        let statements = parse_activity_flex_all_with_options(&broken, &options).unwrap();
+        let statement = &statements[0];
+        assert!(statement.trades.items.is_empty());
+        assert_eq!(statement.positions.items.len(), 5);
+        assert_eq!(statement.section_errors.len(), 1);
+        assert_eq!(statement.section_errors[0].section, "Trades");
+
+        let statement = parse_activity_flex_with_options(xml, &options).unwrap();
+        assert_eq!(statement.trades.items.len(), 3);
+        assert!(statement.section_errors.is_empty());
+        assert!(parse_activity_flex_with_options("<FlexQueryResponse>", &options).is_err());
+    }
+
+    #[test]
+    fn test_strict_enums() {
+        let xml = include_str!("../../tests/fixtures/activity_minimal.xml")
+            .replace("buySell=\"BUY\"", "buySell=\"BUY_OPEN\"");
+        let statement = parse_activity_flex_with_options(&xml, &ParseOptions::new()).unwrap();
+        assert!(statement.trades.items[0].buy_sell.is_some());
+
+        let options = ParseOptions::new().with_strict_enums(true);
+        let err = parse_activity_flex_with_options(&xml, &options).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Unknown enum variant: BUY_OPEN for type BuySell at Trades/Trade[0] buySell"
+        );
+    }
 }
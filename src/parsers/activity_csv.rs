@@ -0,0 +1,621 @@
+//! Activity Statement CSV importer
+//!
+//! Accounts without FLEX queries configured can still download their
+//! Activity Statement as CSV from Client Portal. That file holds every
+//! section in one table: each row starts with the section name and a row
+//! type (`Header`, `Data`, `SubTotal`, or `Total`), and a section's
+//! `Header` row names the columns of the `Data` rows that follow it, until
+//! the next `Header`.
+//!
+//! [`parse_activity_statement_csv`] reads the sections that map onto FLEX
+//! records into an [`ActivityFlexStatement`], so the same analytics and
+//! exports work on it:
+//!
+//! | CSV section | FLEX records |
+//! |---|---|
+//! | Statement, Account Information | period, account, account information |
+//! | Trades (`Order` rows) | trades |
+//! | Open Positions (`Summary` rows) | positions |
+//! | Dividends, Withholding Tax, Payment In Lieu Of Dividends, Interest, Fees, Deposits & Withdrawals | cash transactions |
+//! | Base Currency Exchange Rate | conversion rates on the last day |
+//! | Financial Instrument Information | contract IDs, ISINs, and option terms of the above |
+//!
+//! The import is best-effort. The CSV carries far fewer fields than FLEX:
+//! there are no transaction, trade, or order IDs, trades are whole orders
+//! rather than executions, and cash transactions take their symbol and
+//! ISIN from the description. Other sections, and `SubTotal` and `Total`
+//! rows, are skipped. Statements must be in English.
+//!
+//! # Example
+//! ```
+//! use ib_flex::parse_activity_statement_csv;
+//!
+//! let csv = include_str!("../../tests/fixtures/activity_statement.csv");
+//! let statement = parse_activity_statement_csv(csv).unwrap();
+//! assert_eq!(statement.account_id, "U1234567");
+//! assert_eq!(statement.trades.items[0].symbol, "AAPL");
+//! assert_eq!(statement.trades.items[0].conid.0, 265598);
+//! ```
+
+use std::collections::HashMap;
+
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+
+use super::datetime::{parse_ib_date, parse_ib_datetime};
+use crate::error::{ParseError, Result};
+use crate::types::extended::AccountInformation;
+use crate::types::{
+    AccountId, ActivityFlexStatement, AssetCategory, BuySell, CashTransaction, CashTransactionType,
+    Conid, ConversionRate, LongShort, OpenClose, Position, PutCall, Trade,
+};
+
+/// Parse an IB Activity Statement CSV export
+///
+/// # Errors
+///
+/// Returns [`ParseError::MissingField`] if the file has no statement
+/// period or account, and [`ParseError::InvalidDecimal`] or
+/// [`ParseError::InvalidDate`] for a value in a mapped row that does not
+/// parse.
+pub fn parse_activity_statement_csv(csv: &str) -> Result<ActivityFlexStatement> {
+    let rows = rows(csv);
+    let fields = |section: &str| -> HashMap<&str, &str> {
+        rows.iter()
+            .filter(|row| row.section == section)
+            .filter_map(|row| Some((row.get("Field Name")?, row.get("Field Value")?)))
+            .collect()
+    };
+    let statement_fields = fields("Statement");
+    let account_fields = fields("Account Information");
+    let missing = |field: &str, context: &str| ParseError::MissingField {
+        field: field.to_string(),
+        context: context.to_string(),
+    };
+
+    let period = statement_fields
+        .get("Period")
+        .ok_or_else(|| missing("Period", "Statement"))?;
+    let (from_date, to_date) = parse_period(period)?;
+    let account_id = account_fields
+        .get("Account")
+        .and_then(|account| account.split_whitespace().next())
+        .ok_or_else(|| missing("Account", "Account Information"))?;
+    let base_currency = account_fields.get("Base Currency").map(|c| c.to_string());
+    let when_generated = statement_fields
+        .get("WhenGenerated")
+        .map(|s| when_generated(s))
+        .unwrap_or_default();
+
+    let mut statement = ActivityFlexStatement::empty(
+        AccountId::new(account_id),
+        from_date,
+        to_date,
+        when_generated,
+    );
+    let text = |field: &str| account_fields.get(field).map(|v| v.to_string());
+    statement.account_information = Some(AccountInformation {
+        account_id: statement.account_id.clone(),
+        account_type: text("Account Type"),
+        acct_alias: None,
+        currency: base_currency.clone(),
+        name: text("Name"),
+        master_name: None,
+        customer_type: text("Customer Type"),
+        date_opened: None,
+        date_funded: None,
+        date_closed: None,
+        primary_email: None,
+        street_address: None,
+        street_address2: None,
+        city: None,
+        state: None,
+        country: None,
+        postal_code: None,
+        account_capabilities: text("Account Capabilities"),
+        trading_permissions: text("Trading Permissions"),
+        registered_rep_name: None,
+        registered_rep_phone: None,
+        ib_entity: None,
+        model: None,
+    });
+
+    let instruments: HashMap<&str, &Row> = rows
+        .iter()
+        .filter(|row| row.section == "Financial Instrument Information")
+        .filter_map(|row| Some((row.get("Symbol")?, row)))
+        .collect();
+    let importer = Importer {
+        account_id: &statement.account_id,
+        report_date: to_date,
+        base_currency: base_currency.as_deref(),
+        instruments,
+    };
+    for row in &rows {
+        match row.section.as_str() {
+            "Trades" if row.get("DataDiscriminator") == Some("Order") => {
+                statement.trades.items.push(importer.trade(row)?);
+            }
+            "Open Positions" if row.get("DataDiscriminator") == Some("Summary") => {
+                statement.positions.items.push(importer.position(row)?);
+            }
+            "Base Currency Exchange Rate" => {
+                if let (Some(from), Some(rate), Some(base)) =
+                    (row.get("Currency"), number(row, "Rate")?, &base_currency)
+                {
+                    statement.conversion_rates.items.push(ConversionRate {
+                        report_date: to_date,
+                        from_currency: from.to_string(),
+                        to_currency: base.clone(),
+                        rate,
+                    });
+                }
+            }
+            section => {
+                if let Some(c) = importer.cash_transaction(section, row)? {
+                    statement.cash_transactions.items.push(c);
+                }
+            }
+        }
+    }
+    Ok(statement)
+}
+
+/// A `Data` row with the column names of its section's latest `Header`
+struct Row {
+    section: String,
+    columns: HashMap<String, String>,
+}
+
+impl Row {
+    /// Value of a column, trimmed, if present and not empty
+    fn get(&self, column: &str) -> Option<&str> {
+        self.columns
+            .get(column)
+            .map(|v| v.trim())
+            .filter(|v| !v.is_empty() && *v != "--")
+    }
+}
+
+/// Maps rows to records, with the statement-wide context they need
+struct Importer<'a> {
+    account_id: &'a AccountId,
+    report_date: NaiveDate,
+    base_currency: Option<&'a str>,
+    instruments: HashMap<&'a str, &'a Row>,
+}
+
+impl Importer<'_> {
+    fn instrument(&self, symbol: &str, column: &str) -> Option<&str> {
+        self.instruments.get(symbol)?.get(column)
+    }
+
+    fn conid(&self, symbol: &str) -> Option<Conid> {
+        Some(Conid(self.instrument(symbol, "Conid")?.parse().ok()?))
+    }
+
+    fn trade(&self, row: &Row) -> Result<Trade> {
+        let symbol = row.get("Symbol").unwrap_or_default();
+        let terms = self.terms(symbol)?;
+        let quantity = number(row, "Quantity")?;
+        let proceeds = number(row, "Proceeds")?;
+        // Forex trades report the commission and MTM in the base currency,
+        // under `Comm in USD`-style columns
+        let base = self.base_currency.unwrap_or("USD");
+        let (commission, commission_currency) = match number(row, "Comm/Fee")? {
+            Some(commission) => (Some(commission), row.get("Currency")),
+            None => (number(row, &format!("Comm in {}", base))?, Some(base)),
+        };
+        let net_cash = match (proceeds, commission) {
+            (Some(p), Some(c)) if commission_currency == row.get("Currency") => Some(p + c),
+            (p, _) => p,
+        };
+        let time = row.get("Date/Time").map(parse_ib_datetime).transpose()?;
+        let code = row.get("Code");
+        let has_code = |c: &str| code.is_some_and(|code| code.split(';').any(|x| x == c));
+        Ok(Trade {
+            account_id: self.account_id.clone(),
+            conid: terms.conid,
+            symbol: symbol.to_string(),
+            description: terms.description,
+            asset_category: asset_category(row.get("Asset Category")),
+            isin: terms.isin,
+            multiplier: terms.multiplier,
+            strike: terms.strike,
+            expiry: terms.expiry,
+            put_call: terms.put_call,
+            underlying_symbol: terms.underlying_symbol,
+            listing_exchange: terms.listing_exchange,
+            trade_date: time.map(|t| t.date()),
+            trade_time: time,
+            buy_sell: quantity.map(|q| {
+                if q.is_sign_negative() {
+                    BuySell::Sell
+                } else {
+                    BuySell::Buy
+                }
+            }),
+            open_close: if has_code("O") {
+                Some(OpenClose::Open)
+            } else if has_code("C") {
+                Some(OpenClose::Close)
+            } else {
+                None
+            },
+            quantity,
+            price: number(row, "T. Price")?,
+            close_price: number(row, "C. Price")?,
+            proceeds,
+            commission,
+            commission_currency: commission_currency.map(str::to_string),
+            net_cash,
+            cost: number(row, "Basis")?,
+            fifo_pnl_realized: number(row, "Realized P/L")?,
+            mtm_pnl: match number(row, "MTM P/L")? {
+                Some(mtm) => Some(mtm),
+                None => number(row, &format!("MTM in {}", base))?,
+            },
+            currency: row.get("Currency").unwrap_or_default().to_string(),
+            notes: code.map(str::to_string),
+            report_date: time.map(|t| t.date()),
+            ..Default::default()
+        })
+    }
+
+    fn position(&self, row: &Row) -> Result<Position> {
+        let symbol = row.get("Symbol").unwrap_or_default();
+        let terms = self.terms(symbol)?;
+        let quantity = number(row, "Quantity")?.unwrap_or_default();
+        Ok(Position {
+            account_id: self.account_id.clone(),
+            conid: terms.conid,
+            symbol: symbol.to_string(),
+            description: terms.description,
+            asset_category: asset_category(row.get("Asset Category")),
+            isin: terms.isin,
+            multiplier: number(row, "Mult")?.or(terms.multiplier),
+            strike: terms.strike,
+            expiry: terms.expiry,
+            put_call: terms.put_call,
+            underlying_symbol: terms.underlying_symbol,
+            listing_exchange: terms.listing_exchange,
+            quantity,
+            mark_price: number(row, "Close Price")?.unwrap_or_default(),
+            position_value: number(row, "Value")?.unwrap_or_default(),
+            side: (!quantity.is_zero()).then(|| {
+                if quantity.is_sign_negative() {
+                    LongShort::Short
+                } else {
+                    LongShort::Long
+                }
+            }),
+            cost_basis_price: number(row, "Cost Price")?,
+            cost_basis_money: number(row, "Cost Basis")?,
+            fifo_pnl_unrealized: number(row, "Unrealized P/L")?,
+            currency: row.get("Currency").unwrap_or_default().to_string(),
+            report_date: self.report_date,
+            code: row.get("Code").map(str::to_string),
+            ..Default::default()
+        })
+    }
+
+    /// A cash transaction from a row of a cash section; `None` for rows of
+    /// other sections and total rows
+    fn cash_transaction(&self, section: &str, row: &Row) -> Result<Option<CashTransaction>> {
+        let fixed = match section {
+            "Dividends" => Some(CashTransactionType::Dividends),
+            "Withholding Tax" => Some(CashTransactionType::WithholdingTax),
+            "Payment In Lieu Of Dividends" => Some(CashTransactionType::PaymentInLieuOfDividends),
+            "Fees" => Some(CashTransactionType::OtherFees),
+            "Deposits & Withdrawals" => Some(CashTransactionType::DepositsWithdrawals),
+            "Interest" => None,
+            _ => return Ok(None),
+        };
+        let currency = match row.get("Currency") {
+            Some(currency) if !currency.starts_with("Total") => currency,
+            _ => return Ok(None),
+        };
+        if row.get("Subtitle").is_some_and(|s| s.starts_with("Total")) {
+            return Ok(None);
+        }
+        let Some(amount) = number(row, "Amount")? else {
+            return Ok(None);
+        };
+        let transaction_type = fixed.unwrap_or(if amount.is_sign_negative() {
+            CashTransactionType::BrokerInterestPaid
+        } else {
+            CashTransactionType::BrokerInterestReceived
+        });
+        let settle_date = row.get("Settle Date").map(parse_ib_date).transpose()?;
+        let date = match row.get("Date") {
+            Some(date) => Some(parse_ib_date(date)?),
+            None => settle_date,
+        };
+
+        // Security rows describe themselves as `SYMBOL(ISIN) ...`
+        let description = row.get("Description");
+        let (symbol, isin) = match description.and_then(|d| d.split_once('(')) {
+            Some((symbol, rest)) if !symbol.is_empty() && !symbol.contains(' ') => {
+                let isin = rest.split_once(')').map(|(isin, _)| isin);
+                (Some(symbol), isin.filter(|isin| isin.len() == 12))
+            }
+            _ => (None, None),
+        };
+        Ok(Some(CashTransaction {
+            account_id: self.account_id.clone(),
+            transaction_type: Some(transaction_type),
+            description: description.map(str::to_string),
+            amount,
+            currency: currency.to_string(),
+            date,
+            settle_date,
+            report_date: date,
+            conid: symbol
+                .and_then(|s| self.instrument(s, "Conid"))
+                .map(str::to_string),
+            symbol: symbol.map(str::to_string),
+            asset_category: symbol
+                .and_then(|s| self.instrument(s, "Asset Category"))
+                .map(|c| asset_category(Some(c))),
+            isin: isin.map(str::to_string),
+            code: row.get("Code").map(str::to_string),
+            ..Default::default()
+        }))
+    }
+
+    /// Contract terms of `symbol` from the instrument section
+    fn terms(&self, symbol: &str) -> Result<Terms> {
+        let field = |column: &str| self.instrument(symbol, column).map(str::to_string);
+        let number = |column: &str| -> Result<Option<Decimal>> {
+            self.instruments
+                .get(symbol)
+                .map_or(Ok(None), |row| number(row, column))
+        };
+        Ok(Terms {
+            conid: self.conid(symbol).unwrap_or_default(),
+            description: field("Description"),
+            isin: field("Security ID").filter(|id| id.len() == 12),
+            multiplier: number("Multiplier")?,
+            strike: number("Strike")?,
+            expiry: self
+                .instrument(symbol, "Expiry")
+                .map(parse_ib_date)
+                .transpose()?,
+            put_call: match self.instrument(symbol, "Type") {
+                Some("C") => Some(PutCall::Call),
+                Some("P") => Some(PutCall::Put),
+                _ => None,
+            },
+            underlying_symbol: field("Underlying"),
+            listing_exchange: field("Listing Exch"),
+        })
+    }
+}
+
+/// Fields of a trade or position taken from its instrument
+struct Terms {
+    conid: Conid,
+    description: Option<String>,
+    isin: Option<String>,
+    multiplier: Option<Decimal>,
+    strike: Option<Decimal>,
+    expiry: Option<NaiveDate>,
+    put_call: Option<PutCall>,
+    underlying_symbol: Option<String>,
+    listing_exchange: Option<String>,
+}
+
+/// `Data` rows of every section, keyed by their section's latest header
+fn rows(csv: &str) -> Vec<Row> {
+    let mut headers: HashMap<String, Vec<String>> = HashMap::new();
+    let mut rows = Vec::new();
+    for record in records(csv.trim_start_matches('\u{feff}')) {
+        let [section, kind, values @ ..] = record.as_slice() else {
+            continue;
+        };
+        match kind.as_str() {
+            "Header" => {
+                headers.insert(section.clone(), values.to_vec());
+            }
+            "Data" => {
+                if let Some(header) = headers.get(section) {
+                    let columns = header
+                        .iter()
+                        .zip(values)
+                        .filter(|(name, _)| !name.is_empty())
+                        .map(|(name, value)| (name.clone(), value.clone()))
+                        .collect();
+                    rows.push(Row {
+                        section: section.clone(),
+                        columns,
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+    rows
+}
+
+/// Fields of each CSV record, with quoted fields unquoted
+pub(super) fn records(csv: &str) -> Vec<Vec<String>> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = csv.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => record.push(std::mem::take(&mut field)),
+            '\n' if !quoted => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            '\r' if !quoted => {}
+            c => field.push(c),
+        }
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    records
+}
+
+/// A number column, without thousands separators
+fn number(row: &Row, column: &str) -> Result<Option<Decimal>> {
+    row.get(column).map(parse_number).transpose()
+}
+
+/// A number with optional thousands separators, such as `-1,000.5`
+pub(super) fn parse_number(value: &str) -> Result<Decimal> {
+    let digits = value.replace(',', "");
+    digits
+        .parse::<Decimal>()
+        .or_else(|_| Decimal::from_scientific(&digits))
+        .map_err(|_| ParseError::InvalidDecimal(value.to_string()))
+}
+
+/// Asset category from a section's spelled-out name
+fn asset_category(name: Option<&str>) -> AssetCategory {
+    match name.unwrap_or_default() {
+        "Stocks" => AssetCategory::Stock,
+        "Equity and Index Options" => AssetCategory::Option,
+        "Futures" => AssetCategory::Future,
+        "Options On Futures" => AssetCategory::FutureOption,
+        "Forex" => AssetCategory::Cash,
+        "Bonds" => AssetCategory::Bond,
+        "Treasury Bills" => AssetCategory::Bill,
+        "Warrants" => AssetCategory::Warrant,
+        "CFDs" => AssetCategory::Cfd,
+        "Forex CFDs" => AssetCategory::ForexCfd,
+        "Mutual Funds" => AssetCategory::Fund,
+        "Structured Products" => AssetCategory::StructuredProduct,
+        "Crypto" | "Cryptocurrency" => AssetCategory::Cryptocurrency,
+        other => AssetCategory::Unknown(other.to_string()),
+    }
+}
+
+/// First and last day of `January 1, 2025 - January 31, 2025`, or of a
+/// single day
+fn parse_period(period: &str) -> Result<(NaiveDate, NaiveDate)> {
+    let day = |s: &str| {
+        NaiveDate::parse_from_str(s.trim(), "%B %d, %Y")
+            .map_err(|_| ParseError::InvalidDate(period.to_string()))
+    };
+    match period.split_once(" - ") {
+        Some((from, to)) => Ok((day(from)?, day(to)?)),
+        None => {
+            let date = day(period)?;
+            Ok((date, date))
+        }
+    }
+}
+
+/// `2025-02-01, 10:23:45 EST` in FLEX's `yyyyMMdd;HHmmss` form, or as
+/// given if it does not parse
+fn when_generated(value: &str) -> String {
+    let without_zone = match value.rsplit_once(' ') {
+        Some((rest, zone)) if zone.chars().all(|c| c.is_ascii_alphabetic()) => rest,
+        _ => value,
+    };
+    parse_ib_datetime(without_zone)
+        .map(|dt| dt.format("%Y%m%d;%H%M%S").to_string())
+        .unwrap_or_else(|_| value.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn statement() -> ActivityFlexStatement {
+        parse_activity_statement_csv(include_str!("../../tests/fixtures/activity_statement.csv"))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_statement_and_trades() {
+        let statement = statement();
+        assert_eq!(
+            statement.from_date,
+            NaiveDate::from_ymd_opt(2025, 1, 1).unwrap()
+        );
+        assert_eq!(
+            statement.to_date,
+            NaiveDate::from_ymd_opt(2025, 1, 31).unwrap()
+        );
+        assert_eq!(statement.when_generated, "20250201;102345");
+        let info = statement.account_information.as_ref().unwrap();
+        assert_eq!(info.currency.as_deref(), Some("USD"));
+
+        let trades = &statement.trades.items;
+        assert_eq!(trades.len(), 4);
+        let msft = &trades[1];
+        assert_eq!(msft.conid, Conid(272093));
+        assert_eq!(msft.quantity, Some(Decimal::from(-1000)));
+        assert_eq!(msft.buy_sell, Some(BuySell::Sell));
+        assert_eq!(msft.open_close, Some(OpenClose::Close));
+        assert_eq!(msft.net_cash, Some(Decimal::new(4202448, 1)));
+        assert_eq!(msft.isin.as_deref(), Some("US5949181045"));
+
+        let option = &trades[2];
+        assert_eq!(option.asset_category, AssetCategory::Option);
+        assert_eq!(option.put_call, Some(PutCall::Call));
+        assert_eq!(option.strike, Some(Decimal::from(200)));
+        assert_eq!(option.multiplier, Some(Decimal::from(100)));
+        assert_eq!(option.underlying_symbol.as_deref(), Some("AAPL"));
+
+        let fx = &trades[3];
+        assert_eq!(fx.asset_category, AssetCategory::Cash);
+        assert_eq!(fx.commission, Some(Decimal::from(-2)));
+
+        assert_eq!(statement.positions.items.len(), 2);
+        assert_eq!(
+            statement.positions.items[0].position_value,
+            Decimal::from(18600)
+        );
+        assert_eq!(statement.conversion_rates.items[0].to_currency, "USD");
+    }
+
+    #[test]
+    fn test_cash_transactions() {
+        let statement = statement();
+        let types: Vec<_> = statement
+            .cash_transactions
+            .items
+            .iter()
+            .map(|c| c.transaction_type.clone().unwrap())
+            .collect();
+        assert_eq!(
+            types,
+            [
+                CashTransactionType::DepositsWithdrawals,
+                CashTransactionType::Dividends,
+                CashTransactionType::WithholdingTax,
+                CashTransactionType::BrokerInterestReceived,
+                CashTransactionType::BrokerInterestPaid,
+                CashTransactionType::OtherFees,
+            ]
+        );
+        let dividend = &statement.cash_transactions.items[1];
+        assert_eq!(dividend.symbol.as_deref(), Some("AAPL"));
+        assert_eq!(dividend.isin.as_deref(), Some("US0378331005"));
+        assert_eq!(dividend.conid.as_deref(), Some("265598"));
+        assert_eq!(dividend.amount, Decimal::from(25));
+    }
+
+    #[test]
+    fn test_requires_period_and_account() {
+        let err = parse_activity_statement_csv("Trades,Header,Symbol\n").unwrap_err();
+        assert!(matches!(err, ParseError::MissingField { .. }));
+        assert_eq!(
+            records("a,\"b, \"\"c\"\"\",d\r\n"),
+            [vec!["a", "b, \"c\"", "d"]]
+        );
+    }
+}
@@ -0,0 +1,171 @@
+//! Date and time parsing for IB FLEX formats
+//!
+//! FLEX queries let the user pick the date format, time format, and the
+//! separator between them, so the same statement can arrive as
+//! `20250115;093000`, `2025-01-15, 09:30:00`, or `01/15/2025 09:30:00`.
+//! These helpers accept every combination IB offers and are what the
+//! deserializers use internally, so raw strings stored elsewhere can be
+//! normalized the same way.
+//!
+//! # Example
+//! ```
+//! use ib_flex::parsers::datetime::{parse_ib_date, parse_ib_datetime, parse_ib_time};
+//!
+//! let date = parse_ib_date("15-Jan-25").unwrap();
+//! assert_eq!(date, parse_ib_date("20250115").unwrap());
+//!
+//! let dt = parse_ib_datetime("2025-01-15;09:30:00").unwrap();
+//! assert_eq!(dt.time(), parse_ib_time("093000").unwrap());
+//! ```
+
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+
+use crate::error::{ParseError, Result};
+
+/// Date formats offered by FLEX query settings
+///
+/// `MM/dd` is tried before `dd/MM`, so ambiguous dates such as `03/04/2025`
+/// resolve to March 4th (IB's default ordering). Two-digit year variants
+/// come first because `%Y` would happily read `25` as the year 25 AD.
+const DATE_FORMATS: &[&str] = &[
+    "%Y%m%d", "%Y-%m-%d", "%m/%d/%y", "%m/%d/%Y", "%d/%m/%y", "%d/%m/%Y", "%d-%b-%y", "%d-%b-%Y",
+];
+
+/// Time formats offered by FLEX query settings
+const TIME_FORMATS: &[&str] = &["%H%M%S", "%H:%M:%S", "%H:%M"];
+
+/// Parse a date in any FLEX date format
+///
+/// Supported: `yyyyMMdd`, `yyyy-MM-dd`, `MM/dd/yyyy`, `MM/dd/yy`,
+/// `dd/MM/yyyy`, `dd/MM/yy`, and `dd-MMM-yy`.
+pub fn parse_ib_date(s: &str) -> Result<NaiveDate> {
+    let s = s.trim();
+    DATE_FORMATS
+        .iter()
+        .find_map(|fmt| NaiveDate::parse_from_str(s, fmt).ok())
+        .ok_or_else(|| ParseError::InvalidDate(s.to_string()))
+}
+
+/// Parse a time of day in any FLEX time format
+///
+/// Supported: `HHmmss`, `HH:mm:ss`, and `HH:mm`.
+pub fn parse_ib_time(s: &str) -> Result<NaiveTime> {
+    let s = s.trim();
+    TIME_FORMATS
+        .iter()
+        .find_map(|fmt| NaiveTime::parse_from_str(s, fmt).ok())
+        .ok_or_else(|| ParseError::InvalidDate(s.to_string()))
+}
+
+/// Parse a date/time in any FLEX format
+///
+/// The date and time may be separated by `;`, `,`, a space, or `T`.
+/// A bare date parses as midnight.
+pub fn parse_ib_datetime(s: &str) -> Result<NaiveDateTime> {
+    let s = s.trim();
+    let (date, time) = match s.find([';', ',', ' ', 'T']) {
+        Some(idx) => (&s[..idx], s[idx + 1..].trim()),
+        None => (s, ""),
+    };
+    let date = parse_ib_date(date).map_err(|_| ParseError::InvalidDate(s.to_string()))?;
+    if time.is_empty() {
+        return Ok(date.and_time(NaiveTime::MIN));
+    }
+    let time = parse_ib_time(time).map_err(|_| ParseError::InvalidDate(s.to_string()))?;
+    Ok(date.and_time(time))
+}
+
+/// Resolve a FLEX date/time in its reporting timezone
+///
+/// Times repeated by a daylight-saving fall-back resolve to the earlier
+/// instant. Times skipped by a spring-forward gap cannot have been reported
+/// and return [`ParseError::InvalidDate`].
+#[cfg(feature = "chrono-tz")]
+pub fn localize_ib_datetime(
+    datetime: NaiveDateTime,
+    timezone: chrono_tz::Tz,
+) -> Result<chrono::DateTime<chrono_tz::Tz>> {
+    use chrono::TimeZone;
+
+    timezone
+        .from_local_datetime(&datetime)
+        .earliest()
+        .ok_or_else(|| ParseError::InvalidDate(format!("{} in {}", datetime, timezone.name())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ib_date_formats() {
+        let expected = NaiveDate::from_ymd_opt(2025, 1, 15).unwrap();
+        for value in [
+            "20250115",
+            "2025-01-15",
+            "01/15/2025",
+            "01/15/25",
+            "15/01/2025",
+            "15/01/25",
+            "15-Jan-25",
+            " 2025-01-15 ",
+        ] {
+            assert_eq!(parse_ib_date(value).unwrap(), expected, "{}", value);
+        }
+
+        // Ambiguous day/month resolves month-first
+        assert_eq!(
+            parse_ib_date("03/04/2025").unwrap(),
+            NaiveDate::from_ymd_opt(2025, 3, 4).unwrap()
+        );
+        assert!(parse_ib_date("2025/13/45").is_err());
+        assert!(parse_ib_date("").is_err());
+    }
+
+    #[test]
+    fn test_parse_ib_time_formats() {
+        let expected = NaiveTime::from_hms_opt(9, 30, 0).unwrap();
+        assert_eq!(parse_ib_time("093000").unwrap(), expected);
+        assert_eq!(parse_ib_time("09:30:00").unwrap(), expected);
+        assert_eq!(parse_ib_time("09:30").unwrap(), expected);
+        assert!(parse_ib_time("9am").is_err());
+    }
+
+    #[test]
+    fn test_parse_ib_datetime_separators() {
+        let expected = NaiveDate::from_ymd_opt(2025, 1, 15)
+            .unwrap()
+            .and_hms_opt(9, 30, 0)
+            .unwrap();
+        for value in [
+            "20250115;093000",
+            "2025-01-15, 09:30:00",
+            "01/15/2025 09:30:00",
+            "2025-01-15T09:30:00",
+        ] {
+            assert_eq!(parse_ib_datetime(value).unwrap(), expected, "{}", value);
+        }
+        assert!(matches!(
+            parse_ib_datetime("20250115;9am"),
+            Err(ParseError::InvalidDate(s)) if s == "20250115;9am"
+        ));
+    }
+
+    #[cfg(feature = "chrono-tz")]
+    #[test]
+    fn test_localize_ib_datetime_dst() {
+        use chrono_tz::America::New_York;
+
+        let at = |s| parse_ib_datetime(s).unwrap();
+        let winter = localize_ib_datetime(at("20250115;093000"), New_York).unwrap();
+        assert_eq!(winter.to_rfc3339(), "2025-01-15T09:30:00-05:00");
+        let summer = localize_ib_datetime(at("20250715;093000"), New_York).unwrap();
+        assert_eq!(summer.to_rfc3339(), "2025-07-15T09:30:00-04:00");
+
+        // 01:30 happens twice on 2025-11-02; the EDT reading comes first
+        let repeated = localize_ib_datetime(at("20251102;013000"), New_York).unwrap();
+        assert_eq!(repeated.to_rfc3339(), "2025-11-02T01:30:00-04:00");
+        // 02:30 never happens on 2025-03-09
+        assert!(localize_ib_datetime(at("20250309;023000"), New_York).is_err());
+    }
+}
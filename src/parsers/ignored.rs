@@ -0,0 +1,329 @@
+//! Reporting data the parser drops (requires the `serialize` feature, on by
+//! default)
+//!
+//! The parser keeps what the typed statement has a field for and quietly
+//! skips the rest: sections it doesn't model, `<Order>` and summary rows in
+//! `<Trades>`, attributes added in newer FLEX versions, and values such as
+//! IB's `MULTI` dates that read as absent.
+//! [`parse_activity_flex_all_with_report`] parses as usual and also returns
+//! an [`IgnoredReport`] listing each of these by element path and attribute,
+//! with how often it occurred and how many bytes of input it took up, so an
+//! ingestion pipeline can log or alert on data loss.
+//!
+//! The report is found by writing the parsed statements back out with
+//! [`write_activity_flex`](crate::write_activity_flex) and comparing with
+//! the input, which costs about as much again as parsing.
+//!
+//! # Example
+//! ```
+//! use ib_flex::parsers::ignored::parse_activity_flex_all_with_report;
+//! use ib_flex::ParseOptions;
+//!
+//! let xml = include_str!("../../tests/fixtures/activity_minimal.xml");
+//! let (statements, report) = parse_activity_flex_all_with_report(xml, &ParseOptions::new())?;
+//! assert_eq!(statements[0].trades.items.len(), 1);
+//! // The statement has no field for the FLEX query's `period`
+//! assert_eq!(report.to_string(), "  FlexStatement period: 1 dropped (18 bytes)\n");
+//! # Ok::<(), ib_flex::ParseError>(())
+//! ```
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use quick_xml::events::{BytesStart, Event};
+
+use super::options::ParseOptions;
+use crate::types::ActivityFlexStatement;
+use crate::{ParseError, Result};
+
+/// Input the parser read past without keeping
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IgnoredData {
+    /// Element path below the statement, without row positions (e.g.
+    /// `Trades/Order`); `FlexStatement` for the statement's own attributes
+    pub path: String,
+    /// Attribute whose value was dropped, or `None` when the whole element
+    /// was
+    pub attribute: Option<String>,
+    /// Number of times it was dropped, across all statements
+    pub count: usize,
+    /// Total size in the input, in bytes
+    pub bytes: usize,
+}
+
+impl fmt::Display for IgnoredData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.attribute {
+            Some(attribute) => write!(f, "{} {}", self.path, attribute)?,
+            None => f.write_str(&self.path)?,
+        }
+        write!(f, ": {} dropped ({} bytes)", self.count, self.bytes)
+    }
+}
+
+/// Everything dropped while parsing a document, in document order
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct IgnoredReport {
+    /// Dropped elements and attributes; an element's attributes and
+    /// children are counted in the element, not listed again
+    pub items: Vec<IgnoredData>,
+}
+
+impl IgnoredReport {
+    /// Returns true if nothing was dropped
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Total bytes of input dropped
+    pub fn total_bytes(&self) -> usize {
+        self.items.iter().map(|item| item.bytes).sum()
+    }
+
+    fn record(&mut self, path: &str, attribute: Option<&str>, bytes: usize) {
+        let existing = self
+            .items
+            .iter_mut()
+            .find(|item| item.path == path && item.attribute.as_deref() == attribute);
+        match existing {
+            Some(item) => {
+                item.count += 1;
+                item.bytes += bytes;
+            }
+            None => self.items.push(IgnoredData {
+                path: path.to_string(),
+                attribute: attribute.map(str::to_string),
+                count: 1,
+                bytes,
+            }),
+        }
+    }
+}
+
+impl fmt::Display for IgnoredReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for item in &self.items {
+            writeln!(f, "  {}", item)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parse all Activity FLEX statements, reporting what was dropped
+///
+/// # Errors
+///
+/// Same as [`parse_activity_flex_all_with_options`](super::parse_activity_flex_all_with_options).
+pub fn parse_activity_flex_all_with_report(
+    xml: &str,
+    options: &ParseOptions,
+) -> Result<(Vec<ActivityFlexStatement>, IgnoredReport)> {
+    let statements = super::parse_activity_flex_all_with_options(xml, options)?;
+    let written = crate::write_activity_flex(&statements)?;
+    let kept: HashMap<String, HashSet<String>> = elements(&written)?
+        .into_iter()
+        .map(|element| {
+            let attributes = element
+                .attributes
+                .into_iter()
+                .filter(|attribute| attribute.has_value)
+                .map(|attribute| attribute.name)
+                .collect();
+            (element.position, attributes)
+        })
+        .collect();
+
+    let mut report = IgnoredReport::default();
+    // The last element reported; its children follow it and are part of it
+    let mut dropped = String::new();
+    for element in elements(&crate::version::to_current_schema(xml)?)? {
+        if !dropped.is_empty() && is_below(&element.position, &dropped) {
+            continue;
+        }
+        let Some(kept_attributes) = kept.get(&element.position) else {
+            report.record(&element.path, None, element.bytes);
+            dropped = element.position;
+            continue;
+        };
+        for attribute in &element.attributes {
+            if attribute.has_value && !kept_attributes.contains(&attribute.name) {
+                report.record(&element.path, Some(&attribute.name), attribute.bytes);
+            }
+        }
+    }
+    Ok((statements, report))
+}
+
+/// Returns true if `position` is below `parent`
+fn is_below(position: &str, parent: &str) -> bool {
+    position
+        .strip_prefix(parent)
+        .is_some_and(|rest| rest.starts_with('/'))
+}
+
+/// An element below a statement
+struct Element {
+    /// Path with each element's position among same-named siblings, e.g.
+    /// `FlexStatement[0]/Trades[0]/Order[3]`
+    position: String,
+    /// Path without positions or the statement, e.g. `Trades/Order`
+    path: String,
+    attributes: Vec<Attribute>,
+    /// Size of the element and its children
+    bytes: usize,
+}
+
+struct Attribute {
+    name: String,
+    has_value: bool,
+    bytes: usize,
+}
+
+/// Sections and the containers inside them that the parser reads through,
+/// keeping their rows as the section's
+const CONTAINERS: &[(&str, &str)] = &[("FxPositions", "FxLots")];
+
+/// Every element of a FLEX document inside its `FlexStatement`s, and the
+/// statements themselves, in document order
+fn elements(xml: &str) -> Result<Vec<Element>> {
+    let mut elements: Vec<Element> = Vec::new();
+    let mut reader = quick_xml::Reader::from_str(xml);
+    // Index into `elements` of each open element (None for the envelope),
+    // whether the element is its own rather than a container's parent, and
+    // how often each child name was seen under it
+    let mut stack: Vec<(Option<usize>, bool, HashMap<String, usize>)> = Vec::new();
+    let mut root_seen = HashMap::new();
+    loop {
+        let start_position = reader.buffer_position() as usize;
+        let event = reader.read_event().map_err(|e| ParseError::XmlError {
+            message: format!("Failed to parse FLEX XML: {}", e),
+            location: None,
+        })?;
+        let end_position = reader.buffer_position() as usize;
+        let (start, empty) = match event {
+            Event::Start(start) => (start, false),
+            Event::Empty(start) => (start, true),
+            Event::End(_) => {
+                if let Some((Some(index), true, _)) = stack.pop() {
+                    elements[index].bytes = end_position - elements[index].bytes;
+                }
+                continue;
+            }
+            Event::Eof => break,
+            _ => continue,
+        };
+
+        let name = String::from_utf8_lossy(start.name().as_ref()).into_owned();
+        let depth = stack.len();
+        let parent = stack.last().and_then(|(index, _, _)| *index);
+        if let Some(parent) = parent {
+            if CONTAINERS.contains(&(elements[parent].path.as_str(), name.as_str())) && !empty {
+                stack.push((Some(parent), false, HashMap::new()));
+                continue;
+            }
+        }
+        let seen = match stack.last_mut() {
+            Some((_, _, seen)) => seen,
+            None => &mut root_seen,
+        };
+        let nth = seen.entry(name.clone()).or_default();
+        *nth += 1;
+        let nth = *nth - 1;
+
+        // FlexQueryResponse and FlexStatements are the envelope
+        let index = (depth >= 2).then(|| {
+            let (position, path) = match parent {
+                Some(parent) => {
+                    let parent = &elements[parent];
+                    let path = if parent.path == "FlexStatement" {
+                        name.clone()
+                    } else {
+                        format!("{}/{}", parent.path, name)
+                    };
+                    (format!("{}/{}[{}]", parent.position, name, nth), path)
+                }
+                None => (format!("{}[{}]", name, nth), name.clone()),
+            };
+            elements.push(Element {
+                position,
+                path,
+                attributes: attributes(&start),
+                // The start offset until the end tag is seen
+                bytes: if empty {
+                    end_position - start_position
+                } else {
+                    start_position
+                },
+            });
+            elements.len() - 1
+        });
+        if !empty {
+            stack.push((index, true, HashMap::new()));
+        }
+    }
+    Ok(elements)
+}
+
+fn attributes(start: &BytesStart<'_>) -> Vec<Attribute> {
+    start
+        .attributes()
+        .flatten()
+        .map(|attribute| Attribute {
+            name: String::from_utf8_lossy(attribute.key.as_ref()).into_owned(),
+            has_value: !attribute.value.iter().all(u8::is_ascii_whitespace),
+            // name="value"
+            bytes: attribute.key.as_ref().len() + attribute.value.len() + 3,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nested_rows_are_kept() {
+        let xml = include_str!("../../tests/fixtures/activity_extended.xml");
+        let (statements, report) =
+            parse_activity_flex_all_with_report(xml, &ParseOptions::new()).unwrap();
+        assert!(!statements[0].fx_lots.items.is_empty());
+        assert!(
+            report.items.iter().all(|item| item.attribute.is_some()),
+            "{}",
+            report
+        );
+    }
+
+    #[test]
+    fn test_report_dropped_data() {
+        let xml = include_str!("../../tests/fixtures/activity_minimal.xml");
+        let trade = xml[xml.find("<Trade ").unwrap()..xml.find("</Trades>").unwrap()].trim_end();
+        let order = trade.replace("<Trade ", "<Order ");
+        let xml = xml
+            .replace("</Trades>", &format!("{}{}</Trades>", order, order))
+            .replace("<Trade ", "<Trade futureField=\"1\" ")
+            .replace(
+                "</FlexStatement>",
+                "<UnknownSection><Row a=\"1\" /></UnknownSection></FlexStatement>",
+            );
+        let (statements, report) =
+            parse_activity_flex_all_with_report(&xml, &ParseOptions::new()).unwrap();
+        assert_eq!(statements[0].trades.items.len(), 1);
+        let dropped = |path: &str, attribute: Option<&str>, count, bytes| IgnoredData {
+            path: path.to_string(),
+            attribute: attribute.map(str::to_string),
+            count,
+            bytes,
+        };
+        assert_eq!(
+            report.items,
+            [
+                dropped("FlexStatement", Some("period"), 1, 18),
+                dropped("Trades/Trade", Some("futureField"), 1, 15),
+                dropped("Trades/Order", None, 2, 2 * order.len()),
+                dropped("UnknownSection", None, 1, 46),
+            ]
+        );
+        assert_eq!(report.total_bytes(), 79 + 2 * order.len());
+    }
+}
@@ -1,8 +1,30 @@
 //! FLEX XML parsers
 
 pub mod activity;
+pub mod activity_csv;
+pub mod datetime;
+#[cfg(feature = "serialize")]
+pub mod ignored;
+pub mod options;
+mod tolerant;
 pub mod trade_confirmation;
+pub mod tws_csv;
+pub mod untrusted;
+pub mod warnings;
 pub mod xml_utils;
 
-pub use activity::{parse_activity_flex, parse_activity_flex_all};
-pub use trade_confirmation::parse_trade_confirmation;
+pub use activity::{
+    parse_activity_flex, parse_activity_flex_all, parse_activity_flex_all_with_options,
+    parse_activity_flex_compact, parse_activity_flex_compact_f64, parse_activity_flex_with_options,
+};
+pub use activity_csv::parse_activity_statement_csv;
+#[cfg(feature = "chrono-tz")]
+pub use datetime::localize_ib_datetime;
+pub use datetime::{parse_ib_date, parse_ib_datetime, parse_ib_time};
+#[cfg(feature = "serialize")]
+pub use ignored::{parse_activity_flex_all_with_report, IgnoredReport};
+pub use options::ParseOptions;
+pub use trade_confirmation::{parse_trade_confirmation, parse_trade_confirmation_with_options};
+pub use tws_csv::parse_tws_trades_csv;
+pub use untrusted::{parse_untrusted, parse_untrusted_with_options};
+pub use warnings::{parse_activity_flex_all_with_warnings, ParseWarning};
@@ -0,0 +1,136 @@
+//! Parser configuration
+//!
+//! The plain `parse_*` functions cover the common case. [`ParseOptions`]
+//! carries settings that need to be chosen up front, and is accepted by the
+//! `parse_*_with_options` variants.
+//!
+//! # Example
+//! ```
+//! use ib_flex::parsers::{parse_activity_flex_with_options, ParseOptions};
+//!
+//! let xml = include_str!("../../tests/fixtures/activity_minimal.xml");
+//! let statement = parse_activity_flex_with_options(xml, &ParseOptions::new()).unwrap();
+//! assert_eq!(statement.account_id, "U1234567");
+//! ```
+
+use std::cell::RefCell;
+
+use quick_xml::DeError;
+use serde::de::DeserializeOwned;
+
+use super::tolerant::TolerantDecimals;
+
+/// Options controlling how FLEX XML is parsed
+///
+/// Construct with [`ParseOptions::new`] (or `Default`) and the `with_*`
+/// methods; new settings may be added in minor releases.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ParseOptions {
+    /// Timezone the statement's date/time values are reported in
+    ///
+    /// IB writes datetimes without an offset, in the timezone configured on
+    /// the FLEX query (US/Eastern unless changed). When set, it is recorded
+    /// on the parsed statement so its times can be resolved to `DateTime`s.
+    #[cfg(feature = "chrono-tz")]
+    pub timezone: Option<chrono_tz::Tz>,
+
+    /// Largest input, in bytes, that
+    /// [`parse_untrusted_with_options`](super::untrusted::parse_untrusted_with_options)
+    /// accepts (default `None`: no limit)
+    pub max_input_size: Option<usize>,
+
+    /// Leave sections that fail to parse empty instead of failing the
+    /// whole document (default `false`)
+    ///
+    /// Each skipped section is recorded in the statement's
+    /// [`section_errors`](crate::ActivityFlexStatement::section_errors).
+    /// Errors outside a section, such as malformed XML or a bad statement
+    /// date, still fail the parse.
+    pub recover_sections: bool,
+
+    /// Fail with [`ParseError::UnknownEnumVariant`](crate::ParseError::UnknownEnumVariant)
+    /// on an enum value the parser doesn't recognize, instead of keeping it
+    /// in the type's `Unknown` variant (default `false`)
+    ///
+    /// Checks asset categories, sides, open/close indicators, and other
+    /// enums on Activity statements' trades, positions, cash transactions,
+    /// and corporate actions.
+    pub strict_enums: bool,
+
+    /// Accept decimals IB occasionally writes irregularly: surrounded by
+    /// spaces, with thousands separators (`1,234.50`), or as `--` for a
+    /// missing optional value (default `false`)
+    ///
+    /// [`parse_activity_flex_all_with_warnings`](super::warnings::parse_activity_flex_all_with_warnings)
+    /// lists each value read this way.
+    pub tolerant_decimals: bool,
+}
+
+impl ParseOptions {
+    /// Default options
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the reporting timezone of the statement
+    #[cfg(feature = "chrono-tz")]
+    pub fn with_timezone(mut self, timezone: chrono_tz::Tz) -> Self {
+        self.timezone = Some(timezone);
+        self
+    }
+
+    /// Reject untrusted input larger than `bytes`
+    pub fn with_max_input_size(mut self, bytes: usize) -> Self {
+        self.max_input_size = Some(bytes);
+        self
+    }
+
+    /// Skip sections that fail to parse, recording why on the statement
+    pub fn with_section_recovery(mut self, recover: bool) -> Self {
+        self.recover_sections = recover;
+        self
+    }
+
+    /// Reject enum values the parser doesn't recognize
+    pub fn with_strict_enums(mut self, strict: bool) -> Self {
+        self.strict_enums = strict;
+        self
+    }
+
+    /// Accept irregularly formatted decimals
+    pub fn with_tolerant_decimals(mut self, tolerant: bool) -> Self {
+        self.tolerant_decimals = tolerant;
+        self
+    }
+
+    /// Deserialize `xml` with the deserializer settings these options
+    /// choose, adding each decimal read in tolerant form to `normalized`, as
+    /// written
+    pub(crate) fn deserialize<T: DeserializeOwned>(
+        &self,
+        xml: &str,
+        normalized: &mut Vec<String>,
+    ) -> Result<T, DeError> {
+        let mut deserializer = quick_xml::de::Deserializer::from_str(xml);
+        if !self.tolerant_decimals {
+            return T::deserialize(&mut deserializer);
+        }
+        let tolerant = RefCell::new(Vec::new());
+        let value = T::deserialize(TolerantDecimals::new(&mut deserializer, &tolerant));
+        normalized.extend(tolerant.into_inner());
+        value
+    }
+
+    /// IANA name of the configured timezone, as stored on statements
+    pub(crate) fn timezone_name(&self) -> Option<String> {
+        #[cfg(feature = "chrono-tz")]
+        {
+            self.timezone.map(|tz| tz.name().to_string())
+        }
+        #[cfg(not(feature = "chrono-tz"))]
+        {
+            None
+        }
+    }
+}
@@ -1,8 +1,8 @@
 //! Trade Confirmation FLEX parser
 
+use super::options::ParseOptions;
 use crate::error::{ParseError, Result};
 use crate::types::TradeConfirmationStatement;
-use quick_xml::de::from_str;
 
 /// Parse a Trade Confirmation FLEX XML statement
 ///
@@ -39,11 +39,31 @@ use quick_xml::de::from_str;
 /// # }
 /// ```
 pub fn parse_trade_confirmation(xml: &str) -> Result<TradeConfirmationStatement> {
+    parse(xml, &ParseOptions::new())
+}
+
+fn parse(xml: &str, options: &ParseOptions) -> Result<TradeConfirmationStatement> {
     // Parse the XML using serde and quick-xml
-    from_str(xml).map_err(|e| ParseError::XmlError {
-        message: format!("Failed to parse Trade Confirmation FLEX XML: {}", e),
-        location: None,
-    })
+    options
+        .deserialize(xml, &mut Vec::new())
+        .map_err(|e| ParseError::XmlError {
+            message: format!("Failed to parse Trade Confirmation FLEX XML: {}", e),
+            location: None,
+        })
+}
+
+/// Parse a Trade Confirmation FLEX XML statement with explicit [`ParseOptions`]
+///
+/// # Errors
+///
+/// Same as [`parse_trade_confirmation`].
+pub fn parse_trade_confirmation_with_options(
+    xml: &str,
+    options: &ParseOptions,
+) -> Result<TradeConfirmationStatement> {
+    let mut statement = parse(xml, options)?;
+    statement.timezone = options.timezone_name();
+    Ok(statement)
 }
 
 #[cfg(test)]
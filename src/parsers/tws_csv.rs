@@ -0,0 +1,390 @@
+//! TWS trade export CSV importer
+//!
+//! The TWS Trades window (and the Trade Log of the desktop and IB Gateway
+//! clients) exports executions as CSV with whichever columns the layout
+//! shows, under display names that differ between versions: `Action` or
+//! `Side`, `Qty` or `Quantity`, `Exch.` or `Exchange`. TWS also writes times
+//! in several forms, such as `20250115 09:30:15 US/Eastern`,
+//! `20250115-09:30:15`, or a separate `Date` and `Time`.
+//!
+//! [`parse_tws_trades_csv`] matches columns by name, ignoring case,
+//! spacing, and punctuation, and reads each execution into a [`Trade`], so
+//! intraday fills can be combined with FLEX history before the next FLEX
+//! statement covers them.
+//!
+//! | Field | Accepted column names |
+//! |---|---|
+//! | `account_id` | Account, Acct ID |
+//! | `buy_sell` | Action, Side, Buy/Sell (`BOT`, `BUY`, `B`, `SLD`, `SELL`, `S`) |
+//! | `quantity` | Quantity, Qty, Shares, Filled |
+//! | `symbol` | Local Symbol, Symbol, Financial Instrument, Ticker |
+//! | `asset_category` | Sec Type, Security Type, Asset Class |
+//! | `underlying_symbol` | Underlying, or Symbol when Local Symbol is present |
+//! | `expiry` | Last Trading Day, Expiry, Expiration |
+//! | `strike`, `put_call`, `multiplier` | Strike; Put/Call, Right; Multiplier |
+//! | `price` | Price, Fill Price, Avg Price, Trade Price |
+//! | `currency`, `exchange` | Currency; Exch., Exchange |
+//! | `trade_time` | Time, Date/Time, Execution Time, with Date if separate |
+//! | `commission` | Comm., Commission |
+//! | `fifo_pnl_realized` | Realized P&L, Realized PnL |
+//! | `exec_id`, `ib_order_id` | Exec ID; Perm ID, Order ID |
+//! | `order_reference`, `conid`, `description` | Order Ref; Con ID; Description |
+//!
+//! FLEX signs are applied: quantities of sales and commissions are
+//! negative, whatever the export shows. `proceeds` and `net_cash` are
+//! computed from the fill, since TWS does not export them. TWS times are in
+//! the timezone of the TWS session, which may differ from the FLEX query's
+//! reporting timezone; a trailing timezone name is dropped.
+//!
+//! # Example
+//! ```
+//! use ib_flex::parse_tws_trades_csv;
+//! use ib_flex::types::BuySell;
+//!
+//! let csv = include_str!("../../tests/fixtures/tws_trades.csv");
+//! let trades = parse_tws_trades_csv(csv).unwrap();
+//! assert_eq!(trades[1].symbol, "MSFT");
+//! assert_eq!(trades[1].buy_sell, Some(BuySell::Sell));
+//! assert_eq!(trades[1].quantity, Some((-1000).into()));
+//! ```
+
+use std::collections::HashMap;
+
+use chrono::{NaiveDate, NaiveDateTime};
+use rust_decimal::Decimal;
+
+use super::activity_csv::{parse_number, records};
+use super::datetime::{parse_ib_date, parse_ib_datetime};
+use crate::error::{ParseError, Result};
+use crate::types::{
+    AccountId, AssetCategory, BuySell, Conid, ExecutionId, LevelOfDetail, OrderId, PutCall, Trade,
+};
+
+/// Parse the executions of a TWS trades CSV export
+///
+/// The first row names the columns. Rows without a symbol are skipped.
+///
+/// # Errors
+///
+/// Returns [`ParseError::MissingField`] if there is no symbol or quantity
+/// column, and [`ParseError::InvalidDecimal`] or [`ParseError::InvalidDate`]
+/// for a value that does not parse.
+pub fn parse_tws_trades_csv(csv: &str) -> Result<Vec<Trade>> {
+    let mut records = records(csv.trim_start_matches('\u{feff}')).into_iter();
+    let mut columns: HashMap<Column, usize> = HashMap::new();
+    for (index, name) in records.next().unwrap_or_default().iter().enumerate() {
+        if let Some(column) = Column::from_name(name) {
+            columns.entry(column).or_insert(index);
+        }
+    }
+    let has = |column| columns.contains_key(&column);
+    let missing = if !has(Column::Symbol) && !has(Column::LocalSymbol) {
+        Some("Symbol")
+    } else if !has(Column::Quantity) {
+        Some("Quantity")
+    } else {
+        None
+    };
+    if let Some(field) = missing {
+        return Err(ParseError::MissingField {
+            field: field.to_string(),
+            context: "TWS trades CSV header".to_string(),
+        });
+    }
+
+    let mut trades = Vec::new();
+    for record in records {
+        let row = Row {
+            columns: &columns,
+            record: &record,
+        };
+        if row.get(Column::Symbol).is_none() && row.get(Column::LocalSymbol).is_none() {
+            continue;
+        }
+        trades.push(row.trade()?);
+    }
+    Ok(trades)
+}
+
+/// Fields read from the export
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Column {
+    Account,
+    Side,
+    Quantity,
+    Symbol,
+    LocalSymbol,
+    SecType,
+    Underlying,
+    Expiry,
+    Strike,
+    Right,
+    Multiplier,
+    Price,
+    Currency,
+    Exchange,
+    Time,
+    Date,
+    Commission,
+    RealizedPnl,
+    ExecId,
+    OrderId,
+    OrderRef,
+    Conid,
+    Description,
+}
+
+impl Column {
+    /// The field a column header names, compared as lowercase letters and
+    /// digits only
+    fn from_name(name: &str) -> Option<Self> {
+        let key: String = name
+            .chars()
+            .filter(char::is_ascii_alphanumeric)
+            .map(|c| c.to_ascii_lowercase())
+            .collect();
+        Some(match key.as_str() {
+            "account" | "acct" | "acctid" | "accountid" => Column::Account,
+            "action" | "side" | "buysell" => Column::Side,
+            "quantity" | "qty" | "shares" | "filled" => Column::Quantity,
+            "symbol" | "financialinstrument" | "fininstrument" | "ticker" => Column::Symbol,
+            "localsymbol" => Column::LocalSymbol,
+            "sectype" | "securitytype" | "assetclass" | "assetcategory" => Column::SecType,
+            "underlying" | "underlyingsymbol" => Column::Underlying,
+            "lasttradingday"
+            | "lasttradedate"
+            | "lasttradedateorcontractmonth"
+            | "expiry"
+            | "expiration" => Column::Expiry,
+            "strike" => Column::Strike,
+            "putcall" | "right" => Column::Right,
+            "multiplier" | "mult" => Column::Multiplier,
+            "price" | "fillprice" | "avgprice" | "averageprice" | "tradeprice" => Column::Price,
+            "currency" | "ccy" => Column::Currency,
+            "exch" | "exchange" | "lastexchange" => Column::Exchange,
+            "time" | "datetime" | "executiontime" | "tradetime" | "filltime" => Column::Time,
+            "date" | "tradedate" => Column::Date,
+            "comm" | "commission" | "commissions" => Column::Commission,
+            "realizedpl" | "realizedpnl" => Column::RealizedPnl,
+            "execid" | "executionid" => Column::ExecId,
+            "permid" | "orderid" => Column::OrderId,
+            "orderref" | "orderreference" => Column::OrderRef,
+            "conid" | "contractid" => Column::Conid,
+            "description" => Column::Description,
+            _ => return None,
+        })
+    }
+}
+
+/// One record of the export, read through the header's columns
+struct Row<'a> {
+    columns: &'a HashMap<Column, usize>,
+    record: &'a [String],
+}
+
+impl Row<'_> {
+    /// Value of a column, trimmed, if present and not empty
+    fn get(&self, column: Column) -> Option<&str> {
+        let value = self.record.get(*self.columns.get(&column)?)?.trim();
+        (!value.is_empty() && value != "--").then_some(value)
+    }
+
+    fn text(&self, column: Column) -> Option<String> {
+        self.get(column).map(str::to_string)
+    }
+
+    fn number(&self, column: Column) -> Result<Option<Decimal>> {
+        self.get(column).map(parse_number).transpose()
+    }
+
+    fn trade(&self) -> Result<Trade> {
+        let side = self
+            .get(Column::Side)
+            .map(|side| match side.to_ascii_uppercase().as_str() {
+                "BOT" | "BUY" | "B" => BuySell::Buy,
+                "SLD" | "SELL" | "S" => BuySell::Sell,
+                _ => BuySell::Unknown(side.to_string()),
+            });
+        let quantity = self.number(Column::Quantity)?.map(|q| match side {
+            Some(BuySell::Sell) => -q.abs(),
+            Some(BuySell::Buy) => q.abs(),
+            _ => q,
+        });
+        let buy_sell = side.or_else(|| {
+            quantity.map(|q| {
+                if q.is_sign_negative() {
+                    BuySell::Sell
+                } else {
+                    BuySell::Buy
+                }
+            })
+        });
+        let asset_category = self
+            .get(Column::SecType)
+            .map(|s| s.parse().unwrap_or(AssetCategory::Unknown(s.to_string())))
+            .unwrap_or(AssetCategory::Stock);
+        // TWS's `Symbol` is the underlying of a derivative, `Local Symbol`
+        // is the contract itself, as FLEX reports it
+        let (symbol, underlying_symbol) = match self.get(Column::LocalSymbol) {
+            Some(local) => (
+                local,
+                self.get(Column::Underlying).or(self.get(Column::Symbol)),
+            ),
+            None => (
+                self.get(Column::Symbol).unwrap_or_default(),
+                self.get(Column::Underlying),
+            ),
+        };
+        let underlying_symbol = underlying_symbol.filter(|_| asset_category.is_derivative());
+
+        let price = self.number(Column::Price)?;
+        let multiplier = self.number(Column::Multiplier)?;
+        let commission = self.number(Column::Commission)?.map(|c| -c.abs());
+        let proceeds = match (quantity, price) {
+            (Some(q), Some(p)) => Some(-q * p * multiplier.unwrap_or(Decimal::ONE)),
+            _ => None,
+        };
+        let trade_time = self.time()?;
+        let trade_date = trade_time.map(|t| t.date());
+        let currency = self.text(Column::Currency).unwrap_or_default();
+        Ok(Trade {
+            account_id: AccountId::new(self.get(Column::Account).unwrap_or_default()),
+            conid: match self.get(Column::Conid) {
+                Some(conid) => Conid(
+                    conid
+                        .parse()
+                        .map_err(|_| ParseError::InvalidDecimal(conid.to_string()))?,
+                ),
+                None => Conid::default(),
+            },
+            symbol: symbol.to_string(),
+            description: self.text(Column::Description),
+            asset_category,
+            multiplier,
+            strike: self.number(Column::Strike)?,
+            expiry: self.expiry()?,
+            put_call: match self.get(Column::Right).map(|r| r.to_ascii_uppercase()) {
+                Some(r) if r == "C" || r == "CALL" => Some(PutCall::Call),
+                Some(r) if r == "P" || r == "PUT" => Some(PutCall::Put),
+                _ => None,
+            },
+            underlying_symbol: underlying_symbol.map(str::to_string),
+            trade_date,
+            report_date: trade_date,
+            trade_time,
+            buy_sell,
+            quantity,
+            price,
+            proceeds,
+            commission,
+            commission_currency: commission.map(|_| currency.clone()),
+            net_cash: proceeds.map(|p| p + commission.unwrap_or_default()),
+            fifo_pnl_realized: self.number(Column::RealizedPnl)?,
+            currency,
+            exchange: self.text(Column::Exchange),
+            exec_id: self.get(Column::ExecId).map(ExecutionId::from),
+            ib_order_id: self.get(Column::OrderId).map(OrderId::from),
+            order_reference: self.text(Column::OrderRef),
+            level_of_detail: Some(LevelOfDetail::Execution),
+            ..Default::default()
+        })
+    }
+
+    /// Execution time from `Time`, joined with `Date` when separate
+    fn time(&self) -> Result<Option<NaiveDateTime>> {
+        let time = self.get(Column::Time).map(normalize_time);
+        Ok(match (self.get(Column::Date), time) {
+            (Some(date), Some(time)) if !time.contains([' ', ',', ';']) => {
+                Some(parse_ib_datetime(&format!("{} {}", date, time))?)
+            }
+            (_, Some(time)) => Some(parse_ib_datetime(&time)?),
+            (Some(date), None) => Some(parse_ib_datetime(date)?),
+            (None, None) => None,
+        })
+    }
+
+    /// Expiry date; a futures contract month (`202503`) has no day and
+    /// leaves it unset
+    fn expiry(&self) -> Result<Option<NaiveDate>> {
+        match self.get(Column::Expiry) {
+            Some(month) if month.len() == 6 && month.bytes().all(|b| b.is_ascii_digit()) => {
+                Ok(None)
+            }
+            Some(date) => parse_ib_date(date).map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+/// `20250115-09:30:15 US/Eastern` as `20250115 09:30:15`
+fn normalize_time(time: &str) -> String {
+    let time = match time.rsplit_once(' ') {
+        Some((rest, zone))
+            if zone.contains('/') || zone.chars().all(|c| c.is_ascii_alphabetic()) =>
+        {
+            rest.trim_end()
+        }
+        _ => time,
+    };
+    match time.split_once('-') {
+        Some((date, rest)) if date.len() == 8 && date.bytes().all(|b| b.is_ascii_digit()) => {
+            format!("{} {}", date, rest)
+        }
+        _ => time.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trades_export() {
+        let trades =
+            parse_tws_trades_csv(include_str!("../../tests/fixtures/tws_trades.csv")).unwrap();
+        assert_eq!(trades.len(), 4);
+
+        let buy = &trades[0];
+        assert_eq!(buy.account_id, "U1234567");
+        assert_eq!(
+            buy.trade_time,
+            NaiveDate::from_ymd_opt(2025, 1, 15)
+                .unwrap()
+                .and_hms_opt(9, 30, 15)
+        );
+        assert_eq!(buy.commission, Some(Decimal::from(-1)));
+        assert_eq!(buy.net_cash, Some(Decimal::new(-185510, 1)));
+        assert_eq!(buy.ib_order_id.as_deref(), Some("1234567890"));
+
+        let sell = &trades[1];
+        assert_eq!(sell.proceeds, Some(Decimal::from(420250)));
+        assert_eq!(sell.fifo_pnl_realized, Some(Decimal::new(2024480, 2)));
+        assert_eq!(sell.order_reference.as_deref(), Some("rebalance"));
+
+        let option = &trades[2];
+        assert_eq!(option.symbol, "AAPL  250221C00200000");
+        assert_eq!(option.underlying_symbol.as_deref(), Some("AAPL"));
+        assert_eq!(option.put_call, Some(PutCall::Call));
+        assert_eq!(option.proceeds, Some(Decimal::from(-620)));
+
+        let future = &trades[3];
+        assert_eq!(future.asset_category, AssetCategory::Future);
+        assert_eq!(future.expiry, None);
+        assert_eq!(future.exchange.as_deref(), Some("CME"));
+    }
+
+    #[test]
+    fn test_other_column_names() {
+        let csv = "Side,Qty,Financial Instrument,Fill Price,Date,Time,Commission\n\
+                   SELL,10,SPY,600.5,01/15/2025,15:59:00,0.35\n\
+                   ,,,,,,\n";
+        let trades = parse_tws_trades_csv(csv).unwrap();
+        assert_eq!(trades.len(), 1);
+        assert_eq!(trades[0].quantity, Some(Decimal::from(-10)));
+        assert_eq!(trades[0].asset_category, AssetCategory::Stock);
+        assert_eq!(trades[0].trade_date, NaiveDate::from_ymd_opt(2025, 1, 15));
+
+        let err = parse_tws_trades_csv("Symbol,Price\nAAPL,1\n").unwrap_err();
+        assert!(matches!(err, ParseError::MissingField { .. }));
+    }
+}
@@ -0,0 +1,235 @@
+//! Parsing files from untrusted sources
+//!
+//! [`parse_untrusted`] is the entry point for services that accept
+//! user-uploaded statements: it takes raw bytes and returns a
+//! [`ParseError`] for anything it cannot read, never a panic. The parser
+//! itself does not panic on malformed input; as a second line of defence,
+//! any panic raised while parsing is caught and returned as an error (this
+//! needs the default `panic = "unwind"`). A byte order mark is skipped, and
+//! input that is not UTF-8 or is over [`ParseOptions::max_input_size`] is
+//! rejected before parsing.
+//!
+//! [`seed_corpus`] derives fuzzing inputs from known-good statements: each
+//! document, each section of each statement on its own, and each section
+//! cut down to its first row, so a fuzzer starts from inputs that reach
+//! every row deserializer. [`write_seed_corpus`] writes the corpus of a
+//! fixture directory for `cargo fuzz`:
+//!
+//! ```no_run
+//! ib_flex::parsers::untrusted::write_seed_corpus("tests/fixtures", "fuzz/corpus/parse")?;
+//! # Ok::<(), ib_flex::ParseError>(())
+//! ```
+//!
+//! # Example
+//! ```
+//! use ib_flex::parse_untrusted;
+//!
+//! let upload = include_bytes!("../../tests/fixtures/activity_minimal.xml");
+//! assert_eq!(parse_untrusted(upload).unwrap().len(), 1);
+//! assert!(parse_untrusted(&upload[..upload.len() / 2]).is_err());
+//! assert!(parse_untrusted(&[0xff, 0xfe, 0x00]).is_err());
+//! ```
+
+use std::collections::HashSet;
+use std::fs;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+
+use quick_xml::events::Event;
+
+use super::options::ParseOptions;
+use crate::types::ActivityFlexStatement;
+use crate::{ParseError, Result};
+
+/// Parse every Activity FLEX statement in untrusted bytes
+///
+/// # Errors
+///
+/// Returns `ParseError` for input that is not UTF-8 or not a valid
+/// statement; never panics.
+pub fn parse_untrusted(bytes: &[u8]) -> Result<Vec<ActivityFlexStatement>> {
+    parse_untrusted_with_options(bytes, &ParseOptions::new())
+}
+
+/// Parse untrusted bytes with explicit [`ParseOptions`]
+///
+/// # Errors
+///
+/// Same as [`parse_untrusted`], and [`ParseError::InputTooLarge`] for input
+/// over [`ParseOptions::max_input_size`].
+pub fn parse_untrusted_with_options(
+    bytes: &[u8],
+    options: &ParseOptions,
+) -> Result<Vec<ActivityFlexStatement>> {
+    if let Some(limit) = options.max_input_size {
+        if bytes.len() > limit {
+            return Err(ParseError::InputTooLarge {
+                size: bytes.len(),
+                limit,
+            });
+        }
+    }
+    let bytes = bytes.strip_prefix(b"\xef\xbb\xbf").unwrap_or(bytes);
+    let xml = std::str::from_utf8(bytes).map_err(|e| ParseError::XmlError {
+        message: format!("Input is not UTF-8: {}", e),
+        location: None,
+    })?;
+    panic::catch_unwind(AssertUnwindSafe(|| {
+        super::parse_activity_flex_all_with_options(xml, options)
+    }))
+    .unwrap_or_else(|payload| {
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_default();
+        Err(ParseError::XmlError {
+            message: format!("Parser panicked on malformed input: {}", message),
+            location: None,
+        })
+    })
+}
+
+/// Fuzzing inputs derived from a FLEX document
+///
+/// The document itself comes first, then each section of each statement
+/// in a document of its own, then each such section with only its first
+/// row; duplicates are dropped. A document that is not well-formed XML
+/// gives only itself.
+pub fn seed_corpus(xml: &str) -> Vec<String> {
+    let mut inputs = vec![xml.to_string()];
+    let mut sections = Vec::new();
+    let mut first_rows = Vec::new();
+
+    let mut reader = quick_xml::Reader::from_str(xml);
+    let mut depth = 0;
+    let mut statement = "";
+    // Byte ranges of the open section's start tag and first row
+    let mut section_tag = (0, 0);
+    let mut first_row: Option<(usize, usize)> = None;
+    let mut row_start = 0;
+    loop {
+        let start = reader.buffer_position() as usize;
+        let Ok(event) = reader.read_event() else {
+            return inputs;
+        };
+        let end = reader.buffer_position() as usize;
+        match event {
+            Event::Start(_) => {
+                depth += 1;
+                match depth {
+                    3 => statement = &xml[start..end],
+                    4 => {
+                        section_tag = (start, end);
+                        first_row = None;
+                    }
+                    5 => row_start = start,
+                    _ => {}
+                }
+            }
+            Event::Empty(_) => match depth + 1 {
+                4 => sections.push(statement_document(statement, &xml[start..end])),
+                5 if first_row.is_none() => first_row = Some((start, end)),
+                _ => {}
+            },
+            Event::End(_) => {
+                match depth {
+                    4 => {
+                        sections.push(statement_document(statement, &xml[section_tag.0..end]));
+                        if let Some((row_start, row_end)) = first_row {
+                            let section = format!(
+                                "{}{}{}",
+                                &xml[section_tag.0..section_tag.1],
+                                &xml[row_start..row_end],
+                                &xml[start..end]
+                            );
+                            first_rows.push(statement_document(statement, &section));
+                        }
+                    }
+                    5 if first_row.is_none() => first_row = Some((row_start, end)),
+                    _ => {}
+                }
+                depth -= 1;
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    inputs.extend(sections);
+    inputs.extend(first_rows);
+    let mut seen = HashSet::new();
+    inputs.retain(|input| seen.insert(input.clone()));
+    inputs
+}
+
+/// Write the [`seed_corpus`] of every `.xml` file in `fixtures` to `out`
+///
+/// Files are named after the fixture and a sequence number. Returns the
+/// number of files written.
+pub fn write_seed_corpus(fixtures: impl AsRef<Path>, out: impl AsRef<Path>) -> Result<usize> {
+    let out = out.as_ref();
+    fs::create_dir_all(out)?;
+    let mut paths: Vec<_> = fs::read_dir(fixtures)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<std::io::Result<_>>()?;
+    paths.retain(|p| p.extension().is_some_and(|ext| ext == "xml"));
+    paths.sort();
+
+    let mut written = 0;
+    for path in paths {
+        let xml = fs::read_to_string(&path)?;
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+        for (i, input) in seed_corpus(&xml).iter().enumerate() {
+            fs::write(out.join(format!("{}-{:03}.xml", stem, i)), input)?;
+            written += 1;
+        }
+    }
+    Ok(written)
+}
+
+/// A one-statement document holding `content`
+fn statement_document(statement: &str, content: &str) -> String {
+    format!(
+        "<FlexQueryResponse queryName=\"corpus\" type=\"AF\"><FlexStatements count=\"1\">{}{}</FlexStatement></FlexStatements></FlexQueryResponse>",
+        statement, content
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_untrusted_input() {
+        let xml = include_str!("../../tests/fixtures/activity_minimal.xml");
+        let with_bom = [b"\xef\xbb\xbf".as_slice(), xml.as_bytes()].concat();
+        assert_eq!(parse_untrusted(&with_bom).unwrap().len(), 1);
+
+        let options = ParseOptions::new().with_max_input_size(100);
+        assert!(matches!(
+            parse_untrusted_with_options(xml.as_bytes(), &options),
+            Err(ParseError::InputTooLarge { limit: 100, .. })
+        ));
+        let err = parse_untrusted(b"<FlexQueryResponse \xc3\x28>").unwrap_err();
+        assert!(err.to_string().contains("not UTF-8"));
+    }
+
+    #[test]
+    fn test_seed_corpus() {
+        let xml = include_str!("../../tests/fixtures/activity_daily_portfolio.xml");
+        let corpus = seed_corpus(xml);
+        assert_eq!(corpus[0], xml);
+        assert!(corpus.len() > 10);
+        // Every derived input is a statement with a single section
+        for input in &corpus[1..] {
+            let statements = parse_untrusted(input.as_bytes()).unwrap();
+            assert_eq!(statements[0].account_id, "U1234567");
+        }
+        assert!(corpus.iter().any(|input| {
+            let statement = &parse_untrusted(input.as_bytes()).unwrap()[0];
+            statement.trades.len() == 1 && statement.positions.is_empty()
+        }));
+        assert_eq!(seed_corpus("<FlexQueryResponse"), ["<FlexQueryResponse"]);
+    }
+}
@@ -0,0 +1,420 @@
+//! Warnings about values the parser read but had to guess at
+//!
+//! Some input parses without error yet may not mean what the typed
+//! statement says: an enum value the crate doesn't know yet, kept in the
+//! type's `Unknown` variant; a placeholder such as IB's `MULTI` date, read
+//! as absent; a slash date like `03/04/2025` that is read month-first but
+//! could be day-first; a decimal such as `1,234.50` read with
+//! [`ParseOptions::with_tolerant_decimals`]; or, with a reporting timezone
+//! set, a time the clocks pass twice when daylight saving ends.
+//! [`parse_activity_flex_all_with_warnings`] parses as usual and lists each
+//! of these with the row and attribute it came from, so a pipeline can log
+//! them instead of learning about them from wrong numbers.
+//!
+//! # Example
+//! ```
+//! use ib_flex::parsers::warnings::parse_activity_flex_all_with_warnings;
+//! use ib_flex::ParseOptions;
+//!
+//! let xml = include_str!("../../tests/fixtures/activity_minimal.xml")
+//!     .replace("buySell=\"BUY\"", "buySell=\"BUY_OPEN\"");
+//! let (statements, warnings) = parse_activity_flex_all_with_warnings(&xml, &ParseOptions::new())?;
+//! assert_eq!(statements.len(), 1);
+//! assert_eq!(
+//!     warnings[0].to_string(),
+//!     "Trades/Trade[0] buySell: \"BUY_OPEN\" is not a known value, kept as Unknown"
+//! );
+//! # Ok::<(), ib_flex::ParseError>(())
+//! ```
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use quick_xml::events::Event;
+
+use super::datetime::parse_ib_date;
+use super::options::ParseOptions;
+use crate::types::{
+    ActivityFlexStatement, AssetCategory, BuySell, CashTransactionType, LongShort, OpenClose,
+    OrderType, PutCall, TradeType,
+};
+use crate::{ParseError, Result};
+
+/// Why a value was flagged
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum WarningKind {
+    /// An enum value the parser does not recognize, kept verbatim in the
+    /// type's `Unknown` variant
+    UnknownValue,
+    /// A placeholder IB writes in place of a value, such as `MULTI` on
+    /// summary rows, a non-numeric `conid`, or a `--` decimal, read as absent
+    ReadAsAbsent,
+    /// A decimal with surrounding spaces or thousands separators, read with
+    /// [`ParseOptions::with_tolerant_decimals`]
+    NormalizedDecimal,
+    /// A date that reads as a valid date both month-first and day-first,
+    /// read month-first
+    AmbiguousDate,
+    /// A local time repeated when daylight saving ends in the statement's
+    /// timezone, resolved to the earlier instant
+    AmbiguousTime,
+}
+
+/// A value that parsed, but possibly not as intended
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ParseWarning {
+    /// Position of the statement in the document, from zero
+    pub statement: usize,
+    /// Row the value is on, e.g. `Trades/Trade[0]`; `FlexStatement` for the
+    /// statement's own attributes
+    pub path: String,
+    /// XML attribute name (e.g. `buySell`)
+    pub field: String,
+    /// The value as written in the input
+    pub value: String,
+    /// What is uncertain about it
+    pub kind: WarningKind,
+}
+
+impl fmt::Display for ParseWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}: {:?} ", self.path, self.field, self.value)?;
+        match self.kind {
+            WarningKind::UnknownValue => f.write_str("is not a known value, kept as Unknown"),
+            WarningKind::ReadAsAbsent => f.write_str("read as absent"),
+            WarningKind::NormalizedDecimal => {
+                match super::xml_utils::normalize_decimal(&self.value) {
+                    Some(decimal) => write!(f, "read as {}", decimal),
+                    None => f.write_str("read as absent"),
+                }
+            }
+            WarningKind::AmbiguousDate => {
+                match date_part(&self.value).and_then(|d| parse_ib_date(d).ok()) {
+                    Some(date) => write!(f, "could be day-first, read as {}", date),
+                    None => f.write_str("could be day-first"),
+                }
+            }
+            WarningKind::AmbiguousTime => {
+                f.write_str("occurs twice as daylight saving ends, read as the earlier")
+            }
+        }
+    }
+}
+
+/// Parse all Activity FLEX statements, listing values that may be misread
+///
+/// # Errors
+///
+/// Same as [`parse_activity_flex_all_with_options`](super::parse_activity_flex_all_with_options).
+pub fn parse_activity_flex_all_with_warnings(
+    xml: &str,
+    options: &ParseOptions,
+) -> Result<(Vec<ActivityFlexStatement>, Vec<ParseWarning>)> {
+    let mut normalized = Vec::new();
+    let statements =
+        super::activity::parse_activity_flex_all_recording(xml, options, &mut normalized)?;
+    let normalized: HashSet<String> = normalized.into_iter().collect();
+    let unknown: Vec<HashSet<(String, &str)>> = statements
+        .iter()
+        .map(|statement| {
+            unknown_values(statement)
+                .into_iter()
+                .map(|unknown| (unknown.path, unknown.field))
+                .collect()
+        })
+        .collect();
+
+    let mut warnings = Vec::new();
+    let mut reader = quick_xml::Reader::from_str(xml);
+    // Path of each open element, and how often each child name was seen
+    let mut stack: Vec<(String, HashMap<String, usize>)> = Vec::new();
+    let mut statement = 0;
+    loop {
+        let event = reader.read_event().map_err(|e| ParseError::XmlError {
+            message: format!("Failed to parse FLEX XML: {}", e),
+            location: None,
+        })?;
+        let (start, empty) = match event {
+            Event::Start(start) => (start, false),
+            Event::Empty(start) => (start, true),
+            Event::End(_) => {
+                if stack.pop().is_some_and(|(path, _)| path == "FlexStatement") {
+                    statement += 1;
+                }
+                continue;
+            }
+            Event::Eof => break,
+            _ => continue,
+        };
+        let name = String::from_utf8_lossy(start.name().as_ref()).into_owned();
+        let path = match (stack.len(), stack.last_mut()) {
+            // FlexQueryResponse and FlexStatements
+            (0 | 1, _) => String::new(),
+            (2, _) => "FlexStatement".to_string(),
+            (3, _) => name.clone(),
+            (_, Some((parent, seen))) => {
+                let seen = seen.entry(name.clone()).or_default();
+                *seen += 1;
+                format!("{}/{}[{}]", parent, name, *seen - 1)
+            }
+            (_, None) => String::new(),
+        };
+
+        if !path.is_empty() {
+            for attribute in start.attributes().flatten() {
+                let field = String::from_utf8_lossy(attribute.key.as_ref()).into_owned();
+                let value = attribute
+                    .normalized_value(quick_xml::XmlVersion::Implicit1_0)
+                    .map(|v| v.into_owned())
+                    .unwrap_or_default();
+                let is_unknown = unknown
+                    .get(statement)
+                    .is_some_and(|set| set.contains(&(path.clone(), field.as_str())));
+                let placeholder = value.trim().eq_ignore_ascii_case("MULTI")
+                    || (value.trim() == "--" && normalized.contains(&value))
+                    || (field == "conid"
+                        && !value.trim().is_empty()
+                        && value.trim().parse::<u64>().is_err());
+                let kind = if is_unknown {
+                    WarningKind::UnknownValue
+                } else if placeholder {
+                    WarningKind::ReadAsAbsent
+                } else if normalized.contains(&value) {
+                    WarningKind::NormalizedDecimal
+                } else if is_ambiguous_date(&value) {
+                    WarningKind::AmbiguousDate
+                } else if is_ambiguous_time(&value, options) {
+                    WarningKind::AmbiguousTime
+                } else {
+                    continue;
+                };
+                warnings.push(ParseWarning {
+                    statement,
+                    path: path.clone(),
+                    field,
+                    value,
+                    kind,
+                });
+            }
+        }
+        if !empty {
+            stack.push((path, HashMap::new()));
+        }
+    }
+    Ok((statements, warnings))
+}
+
+/// An enum value kept in its type's `Unknown` variant
+pub(crate) struct UnknownValue {
+    /// Row, as in [`ParseWarning::path`]
+    pub(crate) path: String,
+    /// XML attribute name
+    pub(crate) field: &'static str,
+    /// Name of the enum type
+    pub(crate) enum_type: &'static str,
+    /// The value as parsed
+    pub(crate) value: String,
+}
+
+/// Enum values of the statement's rows kept as `Unknown`, section by
+/// section
+pub(crate) fn unknown_values(statement: &ActivityFlexStatement) -> Vec<UnknownValue> {
+    macro_rules! unknown {
+        ($value:expr, $enum:ident) => {
+            match $value {
+                Some($enum::Unknown(raw)) if !raw.is_empty() => Some((stringify!($enum), raw)),
+                _ => None,
+            }
+        };
+    }
+    let mut values = Vec::new();
+    let mut add = |path: &dyn Fn() -> String,
+                   field: &'static str,
+                   unknown: Option<(&'static str, &String)>| {
+        if let Some((enum_type, value)) = unknown {
+            values.push(UnknownValue {
+                path: path(),
+                field,
+                enum_type,
+                value: value.clone(),
+            });
+        }
+    };
+    for (i, t) in statement.trades.items.iter().enumerate() {
+        let path = || format!("Trades/Trade[{}]", i);
+        add(
+            &path,
+            "assetCategory",
+            unknown!(Some(&t.asset_category), AssetCategory),
+        );
+        add(&path, "putCall", unknown!(&t.put_call, PutCall));
+        add(&path, "buySell", unknown!(&t.buy_sell, BuySell));
+        add(
+            &path,
+            "openCloseIndicator",
+            unknown!(&t.open_close, OpenClose),
+        );
+        add(
+            &path,
+            "transactionType",
+            unknown!(&t.transaction_type, TradeType),
+        );
+        add(&path, "orderType", unknown!(&t.order_type, OrderType));
+    }
+    for (i, p) in statement.positions.items.iter().enumerate() {
+        let path = || format!("OpenPositions/OpenPosition[{}]", i);
+        add(
+            &path,
+            "assetCategory",
+            unknown!(Some(&p.asset_category), AssetCategory),
+        );
+        add(&path, "putCall", unknown!(&p.put_call, PutCall));
+        add(&path, "side", unknown!(&p.side, LongShort));
+    }
+    for (i, c) in statement.cash_transactions.items.iter().enumerate() {
+        let path = || format!("CashTransactions/CashTransaction[{}]", i);
+        add(
+            &path,
+            "type",
+            unknown!(&c.transaction_type, CashTransactionType),
+        );
+        add(
+            &path,
+            "assetCategory",
+            unknown!(&c.asset_category, AssetCategory),
+        );
+        add(&path, "putCall", unknown!(&c.put_call, PutCall));
+    }
+    for (i, a) in statement.corporate_actions.items.iter().enumerate() {
+        let path = || format!("CorporateActions/CorporateAction[{}]", i);
+        add(
+            &path,
+            "assetCategory",
+            unknown!(&a.asset_category, AssetCategory),
+        );
+        add(&path, "putCall", unknown!(&a.put_call, PutCall));
+    }
+    values
+}
+
+/// The date of a FLEX date or date/time value
+fn date_part(value: &str) -> Option<&str> {
+    let value = value.trim();
+    match value.find([';', ',', ' ', 'T']) {
+        Some(idx) => Some(&value[..idx]),
+        None => Some(value),
+    }
+    .filter(|date| !date.is_empty())
+}
+
+/// Returns true for a slash date whose first two parts could each be the
+/// month, e.g. `03/04/2025`
+fn is_ambiguous_date(value: &str) -> bool {
+    let Some(date) = date_part(value) else {
+        return false;
+    };
+    let month = |part: &str| part.parse::<u8>().ok().filter(|m| (1..=12).contains(m));
+    match date.split('/').collect::<Vec<_>>()[..] {
+        [first, second, _] => match (month(first), month(second)) {
+            (Some(a), Some(b)) => a != b && parse_ib_date(date).is_ok(),
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// Returns true for a date/time the reporting timezone repeats
+#[cfg(feature = "chrono-tz")]
+fn is_ambiguous_time(value: &str, options: &ParseOptions) -> bool {
+    use chrono::{LocalResult, TimeZone};
+
+    let Some(timezone) = options.timezone else {
+        return false;
+    };
+    // Bare dates are midnight, which no timezone repeats
+    match super::datetime::parse_ib_datetime(value) {
+        Ok(datetime) => matches!(
+            timezone.from_local_datetime(&datetime),
+            LocalResult::Ambiguous(..)
+        ),
+        Err(_) => false,
+    }
+}
+
+#[cfg(not(feature = "chrono-tz"))]
+fn is_ambiguous_time(_value: &str, _options: &ParseOptions) -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_warnings() {
+        let xml = include_str!("../../tests/fixtures/activity_minimal.xml");
+        let (_, warnings) =
+            parse_activity_flex_all_with_warnings(xml, &ParseOptions::new()).unwrap();
+        assert!(warnings.is_empty());
+
+        let xml = xml
+            .replace("assetCategory=\"STK\"", "assetCategory=\"CRYPTO2\"")
+            .replace(
+                "settleDateTarget=\"2025-01-17\"",
+                "settleDateTarget=\"MULTI\"",
+            )
+            .replace("conid=\"265598\"", "conid=\"--\"")
+            .replace("tradeDate=\"2025-01-15\"", "tradeDate=\"01/02/2025\"");
+        let (statements, warnings) =
+            parse_activity_flex_all_with_warnings(&xml, &ParseOptions::new()).unwrap();
+        assert_eq!(
+            statements[0].trades.items[0].asset_category,
+            AssetCategory::Unknown("CRYPTO2".to_string())
+        );
+        let lines: Vec<String> = warnings.iter().map(|w| w.to_string()).collect();
+        assert_eq!(
+            lines,
+            [
+                "Trades/Trade[0] assetCategory: \"CRYPTO2\" is not a known value, kept as Unknown",
+                "Trades/Trade[0] conid: \"--\" read as absent",
+                "Trades/Trade[0] tradeDate: \"01/02/2025\" could be day-first, read as 2025-01-02",
+                "Trades/Trade[0] settleDateTarget: \"MULTI\" read as absent",
+            ]
+        );
+        let xml = include_str!("../../tests/fixtures/activity_minimal.xml")
+            .replace("proceeds=\"-18550.00\"", "proceeds=\"-18,550.00\"")
+            .replace("netCash=\"-18551.00\"", "netCash=\"--\"");
+        let options = ParseOptions::new().with_tolerant_decimals(true);
+        let (statements, warnings) = parse_activity_flex_all_with_warnings(&xml, &options).unwrap();
+        assert_eq!(statements[0].trades.items[0].net_cash, None);
+        let lines: Vec<String> = warnings.iter().map(|w| w.to_string()).collect();
+        assert_eq!(
+            lines,
+            [
+                "Trades/Trade[0] proceeds: \"-18,550.00\" read as -18550.00",
+                "Trades/Trade[0] netCash: \"--\" read as absent",
+            ]
+        );
+        assert!(!is_ambiguous_date("13/02/2025"));
+        assert!(!is_ambiguous_date("05/05/2025"));
+    }
+
+    #[cfg(feature = "chrono-tz")]
+    #[test]
+    fn test_repeated_local_time() {
+        let xml = include_str!("../../tests/fixtures/activity_minimal.xml").replace(
+            "dateTime=\"2025-01-15;093015\"",
+            "dateTime=\"2025-11-02;013000\"",
+        );
+        let options = ParseOptions::new().with_timezone(chrono_tz::America::New_York);
+        let (_, warnings) = parse_activity_flex_all_with_warnings(&xml, &options).unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].field, "dateTime");
+        assert_eq!(warnings[0].kind, WarningKind::AmbiguousTime);
+
+        let (_, warnings) =
+            parse_activity_flex_all_with_warnings(&xml, &ParseOptions::new()).unwrap();
+        assert!(warnings.is_empty());
+    }
+}
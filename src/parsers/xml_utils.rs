@@ -1,50 +1,197 @@
 //! XML parsing utilities and custom deserializers
 
-use chrono::NaiveDate;
+use chrono::{NaiveDate, NaiveDateTime};
 use rust_decimal::Decimal;
-use serde::{Deserialize, Deserializer};
+use serde::de::Visitor;
+use serde::{Deserialize, Deserializer, Serializer};
 
-/// Parse a date string in either YYYY-MM-DD or YYYYMMDD format
-fn parse_flex_date(s: &str) -> Result<NaiveDate, chrono::ParseError> {
-    // Try ISO format first (YYYY-MM-DD)
-    if let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
-        return Ok(date);
+use super::datetime::{parse_ib_date, parse_ib_datetime};
+
+/// Name of the newtype struct decimals are read through, so
+/// [`TolerantDecimals`](super::tolerant::TolerantDecimals) can tell them
+/// from other text
+pub(crate) const DECIMAL: &str = "$ib_flex::Decimal";
+
+/// Name of the newtype struct dates are written through in IB's format, so
+/// the JSON export can tell them from other text
+pub(crate) const DATE: &str = "$ib_flex::Date";
+
+/// Name of the newtype struct date/times are written through, as [`DATE`]
+pub(crate) const DATE_TIME: &str = "$ib_flex::DateTime";
+
+/// Name of the newtype struct booleans are written through, as [`DATE`]
+pub(crate) const BOOL: &str = "$ib_flex::Bool";
+
+/// Visitor reading a value as text, or None
+struct Text;
+
+impl<'de> Visitor<'de> for Text {
+    type Value = Option<String>;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("a string or number")
+    }
+
+    fn visit_none<E: serde::de::Error>(self) -> Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_unit<E: serde::de::Error>(self) -> Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_str(Text)
+    }
+
+    fn visit_newtype_struct<D: Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_option(Text)
+    }
+
+    fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        Ok(Some(v.to_string()))
     }
-    // Try compact format (YYYYMMDD)
-    NaiveDate::parse_from_str(s, "%Y%m%d")
+
+    fn visit_string<E: serde::de::Error>(self, v: String) -> Result<Self::Value, E> {
+        Ok(Some(v))
+    }
+
+    fn visit_u64<E: serde::de::Error>(self, v: u64) -> Result<Self::Value, E> {
+        Ok(Some(v.to_string()))
+    }
+
+    fn visit_i64<E: serde::de::Error>(self, v: i64) -> Result<Self::Value, E> {
+        Ok(Some(v.to_string()))
+    }
+
+    fn visit_f64<E: serde::de::Error>(self, v: f64) -> Result<Self::Value, E> {
+        Ok(Some(v.to_string()))
+    }
+}
+
+/// Deserialize an optional value as text
+///
+/// FLEX XML gives every value as a string. Formats that keep the numbers a
+/// type serializes to, such as MessagePack, give those as numbers; they
+/// read back as their decimal text.
+pub(crate) fn optional_text<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_option(Text)
+}
+
+/// Deserialize an optional decimal as text, through the [`DECIMAL`] newtype
+fn decimal_text<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_newtype_struct(DECIMAL, Text)
 }
 
-/// Deserialize a NaiveDate from either YYYY-MM-DD or YYYYMMDD format
+/// Deserialize a NaiveDate from any FLEX date format (see [`parse_ib_date`])
 pub fn deserialize_flex_date<'de, D>(deserializer: D) -> Result<NaiveDate, D::Error>
 where
     D: Deserializer<'de>,
 {
     let s = String::deserialize(deserializer)?;
-    parse_flex_date(&s).map_err(serde::de::Error::custom)
+    parse_ib_date(&s).map_err(serde::de::Error::custom)
+}
+
+/// Deserialize a Decimal
+///
+/// With [`ParseOptions::with_tolerant_decimals`], also accepts surrounding
+/// whitespace and thousands separators.
+///
+/// [`ParseOptions::with_tolerant_decimals`]: crate::ParseOptions::with_tolerant_decimals
+pub fn deserialize_decimal<'de, D>(deserializer: D) -> Result<Decimal, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match decimal_text(deserializer)? {
+        Some(s) => s.parse().map_err(serde::de::Error::custom),
+        None => Err(serde::de::Error::custom("missing decimal value")),
+    }
+}
+
+/// Deserialize an f64, for the float records of
+/// [`parse_activity_flex_compact_f64`](crate::parsers::parse_activity_flex_compact_f64)
+pub fn deserialize_f64<'de, D>(deserializer: D) -> Result<f64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserialize_optional_f64(deserializer)?
+        .ok_or_else(|| serde::de::Error::custom("missing number value"))
+}
+
+/// Deserialize an optional f64, treating empty strings as None
+pub fn deserialize_optional_f64<'de, D>(deserializer: D) -> Result<Option<f64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = optional_text(deserializer)?;
+    match s.as_deref().map(str::trim) {
+        None | Some("") => Ok(None),
+        Some(s) => s
+            .parse()
+            .map(Some)
+            .map_err(|_| serde::de::Error::custom(format!("invalid number {:?}", s))),
+    }
 }
 
 /// Deserialize an optional Decimal, treating empty strings as None
+///
+/// With [`ParseOptions::with_tolerant_decimals`], also accepts surrounding
+/// whitespace and thousands separators, and reads `--` as `None`.
+///
+/// [`ParseOptions::with_tolerant_decimals`]: crate::ParseOptions::with_tolerant_decimals
 pub fn deserialize_optional_decimal<'de, D>(deserializer: D) -> Result<Option<Decimal>, D::Error>
 where
     D: Deserializer<'de>,
 {
-    let s = Option::<String>::deserialize(deserializer)?;
+    let s = decimal_text(deserializer)?;
     match s.as_deref() {
         None | Some("") => Ok(None),
-        Some(s) => s
-            .parse::<Decimal>()
-            .map(Some)
-            .map_err(serde::de::Error::custom),
+        Some(s) => s.parse().map(Some).map_err(serde::de::Error::custom),
+    }
+}
+
+/// Rewrite a decimal as IB sometimes formats it into plain form
+///
+/// Surrounding whitespace is trimmed and thousands separators removed
+/// (`" 1,234.50 "` is `1234.50`). Returns `None` for a value standing for
+/// a missing one: empty or `--`. Commas not grouping three digits are left,
+/// so the value still fails to parse rather than reading `1,5` as 15.
+pub(crate) fn normalize_decimal(s: &str) -> Option<String> {
+    let s = s.trim();
+    if s.is_empty() || s == "--" {
+        return None;
     }
+    let (sign, digits) = match s.strip_prefix('-') {
+        Some(rest) => ("-", rest),
+        None => ("", s),
+    };
+    let integer = digits.split('.').next().unwrap_or_default();
+    let mut groups = integer.split(',');
+    let grouped = integer.contains(',')
+        && groups.next().is_some_and(|g| (1..=3).contains(&g.len()))
+        && groups.all(|g| g.len() == 3);
+    if !grouped {
+        return Some(s.to_string());
+    }
+    Some(format!("{}{}", sign, digits.replace(',', "")))
 }
 
 /// Deserialize an optional NaiveDate, treating empty strings as None
-/// Supports both YYYY-MM-DD and YYYYMMDD formats
+/// Supports every FLEX date format (see [`parse_ib_date`])
 pub fn deserialize_optional_date<'de, D>(deserializer: D) -> Result<Option<NaiveDate>, D::Error>
 where
     D: Deserializer<'de>,
 {
-    let s = Option::<String>::deserialize(deserializer)?;
+    let s = optional_text(deserializer)?;
     match s.as_deref() {
         None | Some("") => Ok(None),
         // IBKR emits `MULTI` for date attributes on aggregate summary rows when
@@ -55,9 +202,82 @@ where
         // The detailed rows are sibling Trade/Lot records, not child elements,
         // so optional date fields should treat this sentinel as absent.
         Some(s) if s.eq_ignore_ascii_case("MULTI") => Ok(None),
-        Some(s) => parse_flex_date(s)
-            .map(Some)
-            .map_err(serde::de::Error::custom),
+        Some(s) => parse_ib_date(s).map(Some).map_err(serde::de::Error::custom),
+    }
+}
+
+/// Deserialize an optional NaiveDateTime, treating empty strings as None
+/// Supports every FLEX date/time format (see [`parse_ib_datetime`]); a value
+/// in any other format also reads as None rather than failing the parse.
+pub fn deserialize_optional_datetime<'de, D>(
+    deserializer: D,
+) -> Result<Option<NaiveDateTime>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = optional_text(deserializer)?;
+    Ok(s.as_deref().and_then(parse_optional_datetime))
+}
+
+/// Parse a FLEX date/time, returning None for empty, `MULTI` or
+/// unrecognized values
+pub(crate) fn parse_optional_datetime(s: &str) -> Option<NaiveDateTime> {
+    match s.trim() {
+        "" => None,
+        s if s.eq_ignore_ascii_case("MULTI") => None,
+        s => parse_ib_datetime(s).ok(),
+    }
+}
+
+/// Whether `raw` parses to `date_time`
+#[cfg(feature = "serialize")]
+pub(crate) fn raw_datetime_matches(raw: &Option<String>, date_time: Option<NaiveDateTime>) -> bool {
+    raw.as_deref().and_then(parse_optional_datetime) == date_time
+}
+
+/// Set `raw` to `date_time` in FLEX format, unless it already parses to it
+#[cfg(feature = "serialize")]
+pub(crate) fn sync_raw_datetime(raw: &mut Option<String>, date_time: Option<NaiveDateTime>) {
+    if !raw_datetime_matches(raw, date_time) {
+        *raw = date_time.map(|dt| dt.format("%Y%m%d;%H%M%S").to_string());
+    }
+}
+
+/// Deserialize an optional value through its `FromStr` impl, treating empty
+/// strings as None
+pub fn deserialize_optional_from_str<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: std::str::FromStr,
+    T::Err: std::fmt::Display,
+{
+    let s = optional_text(deserializer)?;
+    match s.as_deref().map(str::trim) {
+        None | Some("") => Ok(None),
+        Some(s) => s.parse().map(Some).map_err(serde::de::Error::custom),
+    }
+}
+
+/// Deserialize an optional enum from its serde string form, treating empty
+/// strings as None rather than the enum's catch-all variant
+pub fn deserialize_optional_enum<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: serde::de::DeserializeOwned,
+{
+    use serde::de::IntoDeserializer;
+
+    let s = optional_text(deserializer)?;
+    match s {
+        None => Ok(None),
+        Some(s) if s.trim().is_empty() => Ok(None),
+        Some(s) => {
+            let de: serde::de::value::StringDeserializer<serde::de::value::Error> =
+                s.into_deserializer();
+            T::deserialize(de)
+                .map(Some)
+                .map_err(serde::de::Error::custom)
+        }
     }
 }
 
@@ -66,7 +286,7 @@ pub fn deserialize_optional_string<'de, D>(deserializer: D) -> Result<Option<Str
 where
     D: Deserializer<'de>,
 {
-    let s = Option::<String>::deserialize(deserializer)?;
+    let s = optional_text(deserializer)?;
     match s.as_deref() {
         None | Some("") => Ok(None),
         Some(_) => Ok(s),
@@ -81,7 +301,7 @@ pub fn deserialize_optional_bool<'de, D>(deserializer: D) -> Result<Option<bool>
 where
     D: Deserializer<'de>,
 {
-    let s = Option::<String>::deserialize(deserializer)?;
+    let s = optional_text(deserializer)?;
     match s.as_deref() {
         None | Some("") => Ok(None),
         Some("Y") | Some("y") => Ok(Some(true)),
@@ -93,6 +313,70 @@ where
     }
 }
 
+/// Serialize a NaiveDate in IB's compact `yyyyMMdd` format
+pub fn serialize_flex_date<S>(date: &NaiveDate, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_newtype_struct(DATE, &date.format("%Y%m%d").to_string())
+}
+
+/// Serialize an optional NaiveDate as `yyyyMMdd`, writing None as an empty string
+pub fn serialize_optional_date<S>(
+    date: &Option<NaiveDate>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    match date {
+        Some(date) => serialize_flex_date(date, serializer),
+        None => serializer.serialize_newtype_struct(DATE, ""),
+    }
+}
+
+/// Serialize an optional NaiveDateTime as `yyyyMMdd;HHmmss`, writing None as
+/// an empty string
+pub fn serialize_optional_datetime<S>(
+    date_time: &Option<NaiveDateTime>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    match date_time {
+        Some(dt) => {
+            serializer.serialize_newtype_struct(DATE_TIME, &dt.format("%Y%m%d;%H%M%S").to_string())
+        }
+        None => serializer.serialize_newtype_struct(DATE_TIME, ""),
+    }
+}
+
+/// Serialize an optional FLEX date/time as written, writing None as an
+/// empty string
+pub fn serialize_optional_raw_datetime<S>(
+    raw: &Option<String>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_newtype_struct(DATE_TIME, raw.as_deref().unwrap_or_default())
+}
+
+/// Serialize an optional boolean in IB's Y/N format, writing None as an empty string
+pub fn serialize_optional_bool<S>(value: &Option<bool>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    let text = match value {
+        Some(true) => "Y",
+        Some(false) => "N",
+        None => "",
+    };
+    serializer.serialize_newtype_struct(BOOL, text)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -114,6 +398,13 @@ mod tests {
         )]
         date: Option<NaiveDate>,
 
+        #[serde(
+            rename = "@dateTime",
+            default,
+            deserialize_with = "deserialize_optional_datetime"
+        )]
+        date_time: Option<NaiveDateTime>,
+
         #[serde(
             rename = "@text",
             default,
@@ -145,6 +436,40 @@ mod tests {
         assert_eq!(result.unwrap().value, Some(Decimal::new(12345, 2)));
     }
 
+    #[test]
+    fn test_tolerant_decimals() {
+        use std::cell::RefCell;
+
+        use crate::parsers::tolerant::TolerantDecimals;
+
+        let normalized = RefCell::new(Vec::new());
+        let parse = |value: &str, tolerant: bool| {
+            let xml = format!(r#"<TestStruct value="{}" />"#, value);
+            let mut deserializer = quick_xml::de::Deserializer::from_str(&xml);
+            let result = if tolerant {
+                TestStruct::deserialize(TolerantDecimals::new(&mut deserializer, &normalized))
+            } else {
+                TestStruct::deserialize(&mut deserializer)
+            };
+            result.map(|t| t.value).ok()
+        };
+        assert_eq!(parse("1,234.50", false), None);
+        assert_eq!(parse("--", false), None);
+
+        let values = ["-1,234.50", " 12.5 ", "--", "1,5", "7"].map(|value| parse(value, true));
+        assert_eq!(
+            values,
+            [
+                Some(Some(Decimal::new(-123450, 2))),
+                Some(Some(Decimal::new(125, 1))),
+                Some(None),
+                None,
+                Some(Some(Decimal::from(7)))
+            ]
+        );
+        assert_eq!(normalized.into_inner(), ["-1,234.50", " 12.5 ", "--"]);
+    }
+
     #[test]
     fn test_empty_string_date() {
         let xml = r#"<TestStruct date="" />"#;
@@ -161,6 +486,51 @@ mod tests {
         assert_eq!(result.unwrap().date, None);
     }
 
+    #[test]
+    fn test_datetime_formats() {
+        let expected = NaiveDate::from_ymd_opt(2025, 1, 15)
+            .unwrap()
+            .and_hms_opt(9, 30, 0)
+            .unwrap();
+        for value in [
+            "20250115;093000",
+            "2025-01-15;093000",
+            "2025-01-15;09:30:00",
+            "2025-01-15, 09:30:00",
+            "20250115 093000",
+        ] {
+            let xml = format!(r#"<TestStruct dateTime="{}" />"#, value);
+            let result: TestStruct = quick_xml::de::from_str(&xml).unwrap();
+            assert_eq!(result.date_time, Some(expected), "{}", value);
+        }
+
+        let result: TestStruct =
+            quick_xml::de::from_str(r#"<TestStruct dateTime="2025-01-15" />"#).unwrap();
+        assert_eq!(
+            result.date_time,
+            Some(
+                NaiveDate::from_ymd_opt(2025, 1, 15)
+                    .unwrap()
+                    .and_hms_opt(0, 0, 0)
+                    .unwrap()
+            )
+        );
+    }
+
+    #[test]
+    fn test_empty_and_invalid_datetime() {
+        let result: TestStruct = quick_xml::de::from_str(r#"<TestStruct dateTime="" />"#).unwrap();
+        assert_eq!(result.date_time, None);
+
+        let result: TestStruct =
+            quick_xml::de::from_str(r#"<TestStruct dateTime="MULTI" />"#).unwrap();
+        assert_eq!(result.date_time, None);
+
+        let result: TestStruct =
+            quick_xml::de::from_str(r#"<TestStruct dateTime="20250115;9am" />"#).unwrap();
+        assert_eq!(result.date_time, None);
+    }
+
     #[test]
     fn test_empty_string_text() {
         let xml = r#"<TestStruct text="" />"#;
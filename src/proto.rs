@@ -0,0 +1,679 @@
+//! Protocol Buffers messages for the core statement model
+//!
+//! The messages here derive [`prost::Message`] and match
+//! `proto/ib_flex.proto` field for field, so anything generated from that
+//! file (tonic, protoc for other languages) reads what
+//! [`Message::encode_to_vec`] writes and writes what [`Message::decode`]
+//! reads. `Message` is re-exported from prost. They cover the core of a statement: the
+//! account and period, trades, open positions, and cash transactions.
+//!
+//! Statements convert to messages with `From`, and back with `TryFrom`,
+//! which fails on a decimal or date that does not parse. Fields outside the
+//! core model are left at their defaults on the way back.
+//!
+//! Requires the `proto` feature.
+//!
+//! # Example
+//! ```
+//! use ib_flex::proto::{self, Message};
+//! use ib_flex::parse_activity_flex;
+//! use ib_flex::types::ActivityFlexStatement;
+//!
+//! let xml = include_str!("../tests/fixtures/activity_minimal.xml");
+//! let statement = parse_activity_flex(xml).unwrap();
+//!
+//! let bytes = proto::ActivityStatement::from(&statement).encode_to_vec();
+//! let message = proto::ActivityStatement::decode(&bytes[..]).unwrap();
+//! let decoded = ActivityFlexStatement::try_from(message).unwrap();
+//! assert_eq!(decoded.trades.items[0].symbol, statement.trades.items[0].symbol);
+//! ```
+
+use std::str::FromStr;
+
+use chrono::{NaiveDate, NaiveDateTime};
+use rust_decimal::Decimal;
+
+use crate::types::{self as flex, ActivityFlexStatement, Conid, ExecutionId, OrderId, TradeId};
+use crate::{ParseError, Result};
+
+pub use prost::Message;
+
+/// Format of date-times in messages
+const DATETIME_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+
+/// One account's statement for a period
+#[derive(Clone, PartialEq, Eq, prost::Message)]
+pub struct ActivityStatement {
+    /// IB account number
+    #[prost(string, tag = "1")]
+    pub account_id: String,
+    /// First day of the period
+    #[prost(string, tag = "2")]
+    pub from_date: String,
+    /// Last day of the period
+    #[prost(string, tag = "3")]
+    pub to_date: String,
+    /// When IB generated the statement, as IB writes it
+    #[prost(string, tag = "4")]
+    pub when_generated: String,
+    /// Trade executions
+    #[prost(message, repeated, tag = "5")]
+    pub trades: Vec<Trade>,
+    /// Open positions at the end of the period
+    #[prost(message, repeated, tag = "6")]
+    pub positions: Vec<Position>,
+    /// Cash transactions
+    #[prost(message, repeated, tag = "7")]
+    pub cash_transactions: Vec<CashTransaction>,
+}
+
+/// A trade execution
+#[derive(Clone, PartialEq, Eq, prost::Message)]
+pub struct Trade {
+    /// IB account number
+    #[prost(string, tag = "1")]
+    pub account_id: String,
+    /// IB transaction ID
+    #[prost(string, optional, tag = "2")]
+    pub transaction_id: Option<String>,
+    /// IB contract ID
+    #[prost(uint64, tag = "3")]
+    pub conid: u64,
+    /// Ticker symbol
+    #[prost(string, tag = "4")]
+    pub symbol: String,
+    /// Security description
+    #[prost(string, optional, tag = "5")]
+    pub description: Option<String>,
+    /// Asset category code (`STK`, `OPT`, ...)
+    #[prost(string, tag = "6")]
+    pub asset_category: String,
+    /// Trade currency
+    #[prost(string, tag = "7")]
+    pub currency: String,
+    /// ISIN
+    #[prost(string, optional, tag = "8")]
+    pub isin: Option<String>,
+    /// CUSIP
+    #[prost(string, optional, tag = "9")]
+    pub cusip: Option<String>,
+    /// Contract multiplier
+    #[prost(string, optional, tag = "10")]
+    pub multiplier: Option<String>,
+    /// Option strike
+    #[prost(string, optional, tag = "11")]
+    pub strike: Option<String>,
+    /// Expiry date
+    #[prost(string, optional, tag = "12")]
+    pub expiry: Option<String>,
+    /// `P` or `C`
+    #[prost(string, optional, tag = "13")]
+    pub put_call: Option<String>,
+    /// Underlying symbol of a derivative
+    #[prost(string, optional, tag = "14")]
+    pub underlying_symbol: Option<String>,
+    /// Trade date
+    #[prost(string, optional, tag = "15")]
+    pub trade_date: Option<String>,
+    /// Execution time
+    #[prost(string, optional, tag = "16")]
+    pub trade_time: Option<String>,
+    /// Settlement date
+    #[prost(string, optional, tag = "17")]
+    pub settle_date: Option<String>,
+    /// `BUY` or `SELL`
+    #[prost(string, optional, tag = "18")]
+    pub buy_sell: Option<String>,
+    /// `O` or `C`
+    #[prost(string, optional, tag = "19")]
+    pub open_close: Option<String>,
+    /// Quantity, negative for sales
+    #[prost(string, optional, tag = "20")]
+    pub quantity: Option<String>,
+    /// Execution price
+    #[prost(string, optional, tag = "21")]
+    pub price: Option<String>,
+    /// Proceeds
+    #[prost(string, optional, tag = "22")]
+    pub proceeds: Option<String>,
+    /// Cost basis
+    #[prost(string, optional, tag = "23")]
+    pub cost: Option<String>,
+    /// Commission, usually negative
+    #[prost(string, optional, tag = "24")]
+    pub commission: Option<String>,
+    /// Currency of the commission
+    #[prost(string, optional, tag = "25")]
+    pub commission_currency: Option<String>,
+    /// Transaction taxes
+    #[prost(string, optional, tag = "26")]
+    pub taxes: Option<String>,
+    /// Net cash
+    #[prost(string, optional, tag = "27")]
+    pub net_cash: Option<String>,
+    /// Realized FIFO P&L
+    #[prost(string, optional, tag = "28")]
+    pub fifo_pnl_realized: Option<String>,
+    /// FX rate to the base currency
+    #[prost(string, optional, tag = "29")]
+    pub fx_rate_to_base: Option<String>,
+    /// IB trade ID
+    #[prost(string, optional, tag = "30")]
+    pub trade_id: Option<String>,
+    /// IB order ID
+    #[prost(string, optional, tag = "31")]
+    pub ib_order_id: Option<String>,
+    /// Execution ID
+    #[prost(string, optional, tag = "32")]
+    pub exec_id: Option<String>,
+    /// Trade notes and codes
+    #[prost(string, optional, tag = "33")]
+    pub notes: Option<String>,
+    /// Level of detail (`EXECUTION`, `ORDER`, ...)
+    #[prost(string, optional, tag = "34")]
+    pub level_of_detail: Option<String>,
+}
+
+/// An open position at the end of the statement period
+#[derive(Clone, PartialEq, Eq, prost::Message)]
+pub struct Position {
+    /// IB account number
+    #[prost(string, tag = "1")]
+    pub account_id: String,
+    /// IB contract ID
+    #[prost(uint64, tag = "2")]
+    pub conid: u64,
+    /// Ticker symbol
+    #[prost(string, tag = "3")]
+    pub symbol: String,
+    /// Security description
+    #[prost(string, optional, tag = "4")]
+    pub description: Option<String>,
+    /// Asset category code (`STK`, `OPT`, ...)
+    #[prost(string, tag = "5")]
+    pub asset_category: String,
+    /// Position currency
+    #[prost(string, tag = "6")]
+    pub currency: String,
+    /// ISIN
+    #[prost(string, optional, tag = "7")]
+    pub isin: Option<String>,
+    /// CUSIP
+    #[prost(string, optional, tag = "8")]
+    pub cusip: Option<String>,
+    /// Contract multiplier
+    #[prost(string, optional, tag = "9")]
+    pub multiplier: Option<String>,
+    /// Option strike
+    #[prost(string, optional, tag = "10")]
+    pub strike: Option<String>,
+    /// Expiry date
+    #[prost(string, optional, tag = "11")]
+    pub expiry: Option<String>,
+    /// `P` or `C`
+    #[prost(string, optional, tag = "12")]
+    pub put_call: Option<String>,
+    /// Underlying symbol of a derivative
+    #[prost(string, optional, tag = "13")]
+    pub underlying_symbol: Option<String>,
+    /// Quantity, negative for short positions
+    #[prost(string, tag = "14")]
+    pub quantity: String,
+    /// Mark price
+    #[prost(string, tag = "15")]
+    pub mark_price: String,
+    /// Position value
+    #[prost(string, tag = "16")]
+    pub position_value: String,
+    /// `Long` or `Short`
+    #[prost(string, optional, tag = "17")]
+    pub side: Option<String>,
+    /// Cost basis per unit
+    #[prost(string, optional, tag = "18")]
+    pub cost_basis_price: Option<String>,
+    /// Total cost basis
+    #[prost(string, optional, tag = "19")]
+    pub cost_basis_money: Option<String>,
+    /// Unrealized FIFO P&L
+    #[prost(string, optional, tag = "20")]
+    pub fifo_pnl_unrealized: Option<String>,
+    /// FX rate to the base currency
+    #[prost(string, optional, tag = "21")]
+    pub fx_rate_to_base: Option<String>,
+    /// Report date
+    #[prost(string, tag = "22")]
+    pub report_date: String,
+    /// When the position was opened
+    #[prost(string, optional, tag = "23")]
+    pub open_date_time: Option<String>,
+    /// Level of detail (`SUMMARY`, `LOT`, ...)
+    #[prost(string, optional, tag = "24")]
+    pub level_of_detail: Option<String>,
+}
+
+/// A dividend, interest payment, fee, deposit, or other cash movement
+#[derive(Clone, PartialEq, Eq, prost::Message)]
+pub struct CashTransaction {
+    /// IB account number
+    #[prost(string, tag = "1")]
+    pub account_id: String,
+    /// IB transaction ID
+    #[prost(string, optional, tag = "2")]
+    pub transaction_id: Option<String>,
+    /// Transaction type as IB writes it (`Dividends`, ...); `type` in the
+    /// proto file
+    #[prost(string, optional, tag = "3")]
+    pub transaction_type: Option<String>,
+    /// Description
+    #[prost(string, optional, tag = "4")]
+    pub description: Option<String>,
+    /// Amount, negative for debits
+    #[prost(string, tag = "5")]
+    pub amount: String,
+    /// Currency
+    #[prost(string, tag = "6")]
+    pub currency: String,
+    /// FX rate to the base currency
+    #[prost(string, optional, tag = "7")]
+    pub fx_rate_to_base: Option<String>,
+    /// Transaction date
+    #[prost(string, optional, tag = "8")]
+    pub date: Option<String>,
+    /// Transaction date and time, as IB writes it
+    #[prost(string, optional, tag = "9")]
+    pub date_time: Option<String>,
+    /// Settlement date
+    #[prost(string, optional, tag = "10")]
+    pub settle_date: Option<String>,
+    /// Report date
+    #[prost(string, optional, tag = "11")]
+    pub report_date: Option<String>,
+    /// Ex-dividend date
+    #[prost(string, optional, tag = "12")]
+    pub ex_date: Option<String>,
+    /// IB contract ID of the security, if any
+    #[prost(string, optional, tag = "13")]
+    pub conid: Option<String>,
+    /// Symbol of the security, if any
+    #[prost(string, optional, tag = "14")]
+    pub symbol: Option<String>,
+    /// Asset category code of the security, if any
+    #[prost(string, optional, tag = "15")]
+    pub asset_category: Option<String>,
+    /// ISIN of the security, if any
+    #[prost(string, optional, tag = "16")]
+    pub isin: Option<String>,
+    /// Transaction codes
+    #[prost(string, optional, tag = "17")]
+    pub code: Option<String>,
+    /// IB action ID
+    #[prost(string, optional, tag = "18")]
+    pub action_id: Option<String>,
+}
+
+impl From<&ActivityFlexStatement> for ActivityStatement {
+    fn from(s: &ActivityFlexStatement) -> Self {
+        ActivityStatement {
+            account_id: s.account_id.to_string(),
+            from_date: s.from_date.to_string(),
+            to_date: s.to_date.to_string(),
+            when_generated: s.when_generated.clone(),
+            trades: s.trades.items.iter().map(Trade::from).collect(),
+            positions: s.positions.items.iter().map(Position::from).collect(),
+            cash_transactions: s
+                .cash_transactions
+                .items
+                .iter()
+                .map(CashTransaction::from)
+                .collect(),
+        }
+    }
+}
+
+impl TryFrom<ActivityStatement> for ActivityFlexStatement {
+    type Error = ParseError;
+
+    fn try_from(m: ActivityStatement) -> Result<Self> {
+        let mut statement = ActivityFlexStatement::empty(
+            m.account_id.as_str().into(),
+            date(&m.from_date)?,
+            date(&m.to_date)?,
+            m.when_generated,
+        );
+        statement.trades.items = m
+            .trades
+            .into_iter()
+            .map(TryFrom::try_from)
+            .collect::<Result<_>>()?;
+        statement.positions.items = m
+            .positions
+            .into_iter()
+            .map(TryFrom::try_from)
+            .collect::<Result<_>>()?;
+        statement.cash_transactions.items = m
+            .cash_transactions
+            .into_iter()
+            .map(TryFrom::try_from)
+            .collect::<Result<_>>()?;
+        Ok(statement)
+    }
+}
+
+impl From<&flex::Trade> for Trade {
+    fn from(t: &flex::Trade) -> Self {
+        Trade {
+            account_id: t.account_id.to_string(),
+            transaction_id: t.transaction_id.clone(),
+            conid: t.conid.0,
+            symbol: t.symbol.clone(),
+            description: t.description.clone(),
+            asset_category: t.asset_category.to_string(),
+            currency: t.currency.clone(),
+            isin: t.isin.clone(),
+            cusip: t.cusip.clone(),
+            multiplier: text(&t.multiplier),
+            strike: text(&t.strike),
+            expiry: text(&t.expiry),
+            put_call: text(&t.put_call),
+            underlying_symbol: t.underlying_symbol.clone(),
+            trade_date: text(&t.trade_date),
+            trade_time: datetime_text(&t.trade_time),
+            settle_date: text(&t.settle_date),
+            buy_sell: text(&t.buy_sell),
+            open_close: text(&t.open_close),
+            quantity: text(&t.quantity),
+            price: text(&t.price),
+            proceeds: text(&t.proceeds),
+            cost: text(&t.cost),
+            commission: text(&t.commission),
+            commission_currency: t.commission_currency.clone(),
+            taxes: text(&t.taxes),
+            net_cash: text(&t.net_cash),
+            fifo_pnl_realized: text(&t.fifo_pnl_realized),
+            fx_rate_to_base: text(&t.fx_rate_to_base),
+            trade_id: text(&t.trade_id),
+            ib_order_id: text(&t.ib_order_id),
+            exec_id: text(&t.exec_id),
+            notes: t.notes.clone(),
+            level_of_detail: text(&t.level_of_detail),
+        }
+    }
+}
+
+impl TryFrom<Trade> for flex::Trade {
+    type Error = ParseError;
+
+    fn try_from(m: Trade) -> Result<Self> {
+        Ok(flex::Trade {
+            account_id: m.account_id.as_str().into(),
+            transaction_id: m.transaction_id,
+            conid: Conid(m.conid),
+            symbol: m.symbol,
+            description: m.description,
+            asset_category: m.asset_category.parse()?,
+            currency: m.currency,
+            isin: m.isin,
+            cusip: m.cusip,
+            multiplier: parse(m.multiplier)?,
+            strike: parse(m.strike)?,
+            expiry: parse(m.expiry)?,
+            put_call: parse(m.put_call)?,
+            underlying_symbol: m.underlying_symbol,
+            trade_date: parse(m.trade_date)?,
+            trade_time: m.trade_time.as_deref().map(datetime).transpose()?,
+            settle_date: parse(m.settle_date)?,
+            buy_sell: parse(m.buy_sell)?,
+            open_close: parse(m.open_close)?,
+            quantity: parse(m.quantity)?,
+            price: parse(m.price)?,
+            proceeds: parse(m.proceeds)?,
+            cost: parse(m.cost)?,
+            commission: parse(m.commission)?,
+            commission_currency: m.commission_currency,
+            taxes: parse(m.taxes)?,
+            net_cash: parse(m.net_cash)?,
+            fifo_pnl_realized: parse(m.fifo_pnl_realized)?,
+            fx_rate_to_base: parse(m.fx_rate_to_base)?,
+            trade_id: m.trade_id.map(TradeId),
+            ib_order_id: m.ib_order_id.map(OrderId),
+            exec_id: m.exec_id.map(ExecutionId),
+            notes: m.notes,
+            level_of_detail: parse(m.level_of_detail)?,
+            ..Default::default()
+        })
+    }
+}
+
+impl From<&flex::Position> for Position {
+    fn from(p: &flex::Position) -> Self {
+        Position {
+            account_id: p.account_id.to_string(),
+            conid: p.conid.0,
+            symbol: p.symbol.clone(),
+            description: p.description.clone(),
+            asset_category: p.asset_category.to_string(),
+            currency: p.currency.clone(),
+            isin: p.isin.clone(),
+            cusip: p.cusip.clone(),
+            multiplier: text(&p.multiplier),
+            strike: text(&p.strike),
+            expiry: text(&p.expiry),
+            put_call: text(&p.put_call),
+            underlying_symbol: p.underlying_symbol.clone(),
+            quantity: p.quantity.to_string(),
+            mark_price: p.mark_price.to_string(),
+            position_value: p.position_value.to_string(),
+            side: text(&p.side),
+            cost_basis_price: text(&p.cost_basis_price),
+            cost_basis_money: text(&p.cost_basis_money),
+            fifo_pnl_unrealized: text(&p.fifo_pnl_unrealized),
+            fx_rate_to_base: text(&p.fx_rate_to_base),
+            report_date: p.report_date.to_string(),
+            open_date_time: datetime_text(&p.open_date_time),
+            level_of_detail: text(&p.level_of_detail),
+        }
+    }
+}
+
+impl TryFrom<Position> for flex::Position {
+    type Error = ParseError;
+
+    fn try_from(m: Position) -> Result<Self> {
+        Ok(flex::Position {
+            account_id: m.account_id.as_str().into(),
+            conid: Conid(m.conid),
+            symbol: m.symbol,
+            description: m.description,
+            asset_category: m.asset_category.parse()?,
+            currency: m.currency,
+            isin: m.isin,
+            cusip: m.cusip,
+            multiplier: parse(m.multiplier)?,
+            strike: parse(m.strike)?,
+            expiry: parse(m.expiry)?,
+            put_call: parse(m.put_call)?,
+            underlying_symbol: m.underlying_symbol,
+            quantity: decimal(&m.quantity)?,
+            mark_price: decimal(&m.mark_price)?,
+            position_value: decimal(&m.position_value)?,
+            side: parse(m.side)?,
+            cost_basis_price: parse(m.cost_basis_price)?,
+            cost_basis_money: parse(m.cost_basis_money)?,
+            fifo_pnl_unrealized: parse(m.fifo_pnl_unrealized)?,
+            fx_rate_to_base: parse(m.fx_rate_to_base)?,
+            report_date: date(&m.report_date)?,
+            open_date_time: m.open_date_time.as_deref().map(datetime).transpose()?,
+            level_of_detail: parse(m.level_of_detail)?,
+            ..Default::default()
+        })
+    }
+}
+
+impl From<&flex::CashTransaction> for CashTransaction {
+    fn from(c: &flex::CashTransaction) -> Self {
+        CashTransaction {
+            account_id: c.account_id.to_string(),
+            transaction_id: c.transaction_id.clone(),
+            transaction_type: text(&c.transaction_type),
+            description: c.description.clone(),
+            amount: c.amount.to_string(),
+            currency: c.currency.clone(),
+            fx_rate_to_base: text(&c.fx_rate_to_base),
+            date: text(&c.date),
+            date_time: c.date_time.clone(),
+            settle_date: text(&c.settle_date),
+            report_date: text(&c.report_date),
+            ex_date: text(&c.ex_date),
+            conid: c.conid.clone(),
+            symbol: c.symbol.clone(),
+            asset_category: text(&c.asset_category),
+            isin: c.isin.clone(),
+            code: c.code.clone(),
+            action_id: c.action_id.clone(),
+        }
+    }
+}
+
+impl TryFrom<CashTransaction> for flex::CashTransaction {
+    type Error = ParseError;
+
+    fn try_from(m: CashTransaction) -> Result<Self> {
+        Ok(flex::CashTransaction {
+            account_id: m.account_id.as_str().into(),
+            transaction_id: m.transaction_id,
+            transaction_type: m
+                .transaction_type
+                .map(|t| t.parse().unwrap_or_else(|never| match never {})),
+            description: m.description,
+            amount: decimal(&m.amount)?,
+            currency: m.currency,
+            fx_rate_to_base: parse(m.fx_rate_to_base)?,
+            date: parse(m.date)?,
+            date_time: m.date_time,
+            settle_date: parse(m.settle_date)?,
+            report_date: parse(m.report_date)?,
+            ex_date: parse(m.ex_date)?,
+            conid: m.conid,
+            symbol: m.symbol,
+            asset_category: parse(m.asset_category)?,
+            isin: m.isin,
+            code: m.code,
+            action_id: m.action_id,
+            ..Default::default()
+        })
+    }
+}
+
+impl From<prost::DecodeError> for ParseError {
+    fn from(err: prost::DecodeError) -> Self {
+        ParseError::ProtoError(err.to_string())
+    }
+}
+
+fn text<T: ToString>(value: &Option<T>) -> Option<String> {
+    value.as_ref().map(ToString::to_string)
+}
+
+fn datetime_text(value: &Option<NaiveDateTime>) -> Option<String> {
+    value.map(|dt| dt.format(DATETIME_FORMAT).to_string())
+}
+
+/// Parse an optional field back into its FLEX type
+fn parse<T: Parse>(value: Option<String>) -> Result<Option<T>> {
+    value.as_deref().map(T::parse).transpose()
+}
+
+/// Field types parsed from message text
+trait Parse: Sized {
+    fn parse(s: &str) -> Result<Self>;
+}
+
+impl Parse for Decimal {
+    fn parse(s: &str) -> Result<Self> {
+        decimal(s)
+    }
+}
+
+impl Parse for NaiveDate {
+    fn parse(s: &str) -> Result<Self> {
+        date(s)
+    }
+}
+
+macro_rules! parse_ib_code {
+    ($($ty:ident),+ $(,)?) => {
+        $(
+            impl Parse for flex::$ty {
+                fn parse(s: &str) -> Result<Self> {
+                    s.parse()
+                }
+            }
+        )+
+    };
+}
+
+parse_ib_code!(
+    AssetCategory,
+    BuySell,
+    OpenClose,
+    PutCall,
+    LongShort,
+    LevelOfDetail
+);
+
+fn decimal(s: &str) -> Result<Decimal> {
+    Decimal::from_str(s).map_err(|_| ParseError::InvalidDecimal(s.to_string()))
+}
+
+fn date(s: &str) -> Result<NaiveDate> {
+    NaiveDate::parse_from_str(s, "%Y-%m-%d").map_err(|_| ParseError::InvalidDate(s.to_string()))
+}
+
+fn datetime(s: &str) -> Result<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(s, DATETIME_FORMAT)
+        .map_err(|_| ParseError::InvalidDate(s.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_statement_round_trip() {
+        let xml = include_str!("../tests/fixtures/activity_daily_portfolio.xml");
+        let statement = crate::parse_activity_flex(xml).unwrap();
+        let message = ActivityStatement::from(&statement);
+        assert_eq!(
+            ActivityStatement::decode(&message.encode_to_vec()[..]).unwrap(),
+            message
+        );
+
+        let decoded = ActivityFlexStatement::try_from(message.clone()).unwrap();
+        assert_eq!(decoded.account_id, statement.account_id);
+        assert_eq!(decoded.to_date, statement.to_date);
+        assert_eq!(ActivityStatement::from(&decoded), message);
+    }
+
+    #[test]
+    fn test_wire_format() {
+        let trade = Trade {
+            conid: 265598,
+            symbol: "AAPL".to_string(),
+            quantity: Some("100".to_string()),
+            ..Default::default()
+        };
+        // Tags 3 (varint), 4, and 20 (length-delimited), in field order
+        let bytes = trade.encode_to_vec();
+        assert_eq!(
+            bytes,
+            b"\x18\xfe\x9a\x10\x22\x04AAPL\xa2\x01\x03100".to_vec()
+        );
+
+        // Unknown fields are skipped; truncated input is an error
+        let mut with_unknown = b"\xf8\x07\x01\x85\x08\x00\x00\x00\x00".to_vec();
+        with_unknown.extend_from_slice(&bytes);
+        assert_eq!(Trade::decode(&with_unknown[..]).unwrap(), trade);
+        let err = Trade::decode(&bytes[..bytes.len() - 1]).unwrap_err();
+        assert!(matches!(ParseError::from(err), ParseError::ProtoError(_)));
+    }
+}
@@ -0,0 +1,193 @@
+//! Where parsed statements came from
+//!
+//! A [`ParsedStatement`] pairs a statement with its [`Provenance`]: the
+//! file or URL it was read from, the SHA-256 of the raw XML, the query that
+//! produced it, when IB generated it, and when it was parsed. Keeping the
+//! two together lets a number in a report be traced back to the exact
+//! source file, and the hash shows whether that file has changed since.
+//!
+//! Provenance travels with the statement through `cache::write_parsed`
+//! snapshots (with the `cache` feature), JSON export, and the manifest
+//! written by
+//! [`csv::write_provenance`](crate::export::csv::write_provenance).
+//! `ParsedStatement` dereferences to the statement, so it can be used
+//! wherever an [`ActivityFlexStatement`] is read.
+//!
+//! # Example
+//! ```
+//! use ib_flex::ParsedStatement;
+//!
+//! let xml = include_str!("../tests/fixtures/activity_minimal.xml");
+//! let parsed = ParsedStatement::parse_all(xml, Some("activity_minimal.xml")).unwrap();
+//!
+//! let provenance = &parsed[0].provenance;
+//! assert_eq!(provenance.source.as_deref(), Some("activity_minimal.xml"));
+//! assert_eq!(provenance.sha256.len(), 64);
+//! assert_eq!(parsed[0].account_id.as_str(), "U1234567");
+//! ```
+
+use std::ops::Deref;
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use quick_xml::events::Event;
+#[cfg(feature = "schemars")]
+use schemars::JsonSchema;
+use serde::Deserialize;
+#[cfg(feature = "serialize")]
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::parsers::ParseOptions;
+use crate::types::ActivityFlexStatement;
+use crate::Result;
+
+/// Where a statement came from and when it was parsed
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct Provenance {
+    /// Path or URL the XML was read from, when known
+    pub source: Option<String>,
+    /// SHA-256 of the raw XML, as lowercase hex
+    pub sha256: String,
+    /// `queryName` of the `FlexQueryResponse`
+    pub query_name: Option<String>,
+    /// `whenGenerated` of the statement, as IB wrote it
+    pub when_generated: String,
+    /// When the XML was parsed
+    pub parsed_at: DateTime<Utc>,
+}
+
+/// A statement and its [`Provenance`]
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct ParsedStatement {
+    /// The parsed statement
+    pub statement: ActivityFlexStatement,
+    /// Where it came from
+    pub provenance: Provenance,
+}
+
+impl ParsedStatement {
+    /// Parse every statement in `xml`, recording `source` as where it came
+    /// from
+    pub fn parse_all(xml: &str, source: Option<&str>) -> Result<Vec<ParsedStatement>> {
+        Self::parse_all_with_options(xml, source, &ParseOptions::default())
+    }
+
+    /// Parse every statement in `xml` with explicit [`ParseOptions`]
+    pub fn parse_all_with_options(
+        xml: &str,
+        source: Option<&str>,
+        options: &ParseOptions,
+    ) -> Result<Vec<ParsedStatement>> {
+        let statements = crate::parse_activity_flex_all_with_options(xml, options)?;
+        let sha256 = hex(&Sha256::digest(xml.as_bytes()));
+        let query_name = query_name(xml);
+        let parsed_at = Utc::now();
+        Ok(statements
+            .into_iter()
+            .map(|statement| ParsedStatement {
+                provenance: Provenance {
+                    source: source.map(str::to_string),
+                    sha256: sha256.clone(),
+                    query_name: query_name.clone(),
+                    when_generated: statement.when_generated.clone(),
+                    parsed_at,
+                },
+                statement,
+            })
+            .collect())
+    }
+
+    /// Read and parse every statement in the file at `path`
+    pub fn read(path: impl AsRef<Path>) -> Result<Vec<ParsedStatement>> {
+        let path = path.as_ref();
+        let xml = std::fs::read_to_string(path)?;
+        Self::parse_all(&xml, Some(&path.display().to_string()))
+    }
+
+    /// The statement, dropping its provenance
+    pub fn into_statement(self) -> ActivityFlexStatement {
+        self.statement
+    }
+}
+
+impl Deref for ParsedStatement {
+    type Target = ActivityFlexStatement;
+
+    fn deref(&self) -> &ActivityFlexStatement {
+        &self.statement
+    }
+}
+
+/// `queryName` of the root `FlexQueryResponse`, if it has one
+fn query_name(xml: &str) -> Option<String> {
+    let mut reader = quick_xml::Reader::from_str(xml);
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(start) | Event::Empty(start)) => {
+                if start.name().as_ref() != b"FlexQueryResponse" {
+                    return None;
+                }
+                let attribute = start
+                    .attributes()
+                    .flatten()
+                    .find(|a| a.key.as_ref() == b"queryName")?;
+                let name = attribute
+                    .normalized_value(quick_xml::XmlVersion::Implicit1_0)
+                    .ok()?;
+                return (!name.is_empty()).then(|| name.into_owned());
+            }
+            Ok(Event::Eof) | Err(_) => return None,
+            Ok(_) => {}
+        }
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hex_digest() {
+        assert_eq!(
+            hex(&Sha256::digest(b"abc")),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn test_provenance_of_each_statement() {
+        let xml = include_str!("../tests/fixtures/activity_backfill.xml");
+        let parsed = ParsedStatement::parse_all(xml, None).unwrap();
+        let statements = crate::parse_activity_flex_all(xml).unwrap();
+        assert_eq!(parsed.len(), statements.len());
+
+        for (parsed, statement) in parsed.iter().zip(&statements) {
+            assert_eq!(&parsed.statement, statement);
+            assert_eq!(parsed.provenance.source, None);
+            assert_eq!(
+                parsed.provenance.sha256,
+                hex(&Sha256::digest(xml.as_bytes()))
+            );
+            assert_eq!(parsed.provenance.when_generated, statement.when_generated);
+        }
+        assert_eq!(
+            parsed[0].provenance.query_name.as_deref(),
+            Some("Last X Days Portfolio Feed")
+        );
+        assert_eq!(
+            query_name(r#"<?xml version="1.0"?><FlexQueryResponse queryName="Daily">"#).as_deref(),
+            Some("Daily")
+        );
+        assert_eq!(query_name("<FlexStatement accountId=\"U1\"/>"), None);
+    }
+}
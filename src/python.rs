@@ -0,0 +1,377 @@
+//! Python bindings (requires the `python` feature)
+//!
+//! A PyO3 extension module named `ib_flex`, built with
+//! [maturin](https://www.maturin.rs) from the `pyproject.toml` at the root
+//! of the repository:
+//!
+//! ```text
+//! pip install maturin
+//! maturin develop --release
+//! ```
+//!
+//! ```python
+//! import ib_flex
+//!
+//! statement = ib_flex.parse_activity_flex(open("activity.xml").read())
+//! print(statement.account_id, statement.from_date, statement.to_date)
+//! for trade in statement.trades:
+//!     print(trade.trade_date, trade.symbol, trade.quantity, trade.price)
+//!
+//! # Every field, as plain Python values
+//! rows = [trade.to_dict() for trade in statement.trades]
+//! ```
+//!
+//! `Trade` and `Position` objects read like dataclasses: attributes for the
+//! common fields, with dates as `datetime.date`, date/times as
+//! `datetime.datetime`, amounts as `decimal.Decimal`, and codes such as
+//! `asset_category` as IB's strings (`"STK"`). `to_dict()` returns every
+//! field with the names and values of [`export::json`], in which amounts are
+//! strings. Parsing runs without holding the GIL, and errors are raised as
+//! `ValueError`.
+//!
+//! [`export::json`]: crate::export::json
+
+use chrono::{NaiveDate, NaiveDateTime};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::{PyBool, PyDict, PyFloat, PyList, PyString};
+use rust_decimal::Decimal;
+use serde_json::Value;
+
+use crate::export::json;
+use crate::types::{ActivityFlexStatement, Position, Trade};
+
+/// Parse an Activity FLEX statement
+#[pyfunction]
+fn parse_activity_flex(py: Python<'_>, xml: &str) -> PyResult<Statement> {
+    let statement = py
+        .allow_threads(|| crate::parse_activity_flex(xml))
+        .map_err(value_error)?;
+    Ok(Statement(statement))
+}
+
+/// Parse every statement of an Activity FLEX document
+#[pyfunction]
+fn parse_activity_flex_all(py: Python<'_>, xml: &str) -> PyResult<Vec<Statement>> {
+    let statements = py
+        .allow_threads(|| crate::parse_activity_flex_all(xml))
+        .map_err(value_error)?;
+    Ok(statements.into_iter().map(Statement).collect())
+}
+
+/// An Activity FLEX statement
+#[pyclass(module = "ib_flex", frozen)]
+struct Statement(ActivityFlexStatement);
+
+#[pymethods]
+impl Statement {
+    #[getter]
+    fn account_id(&self) -> &str {
+        self.0.account_id.as_str()
+    }
+
+    // Named after the Python attribute
+    #[allow(clippy::wrong_self_convention)]
+    #[getter]
+    fn from_date(&self) -> NaiveDate {
+        self.0.from_date
+    }
+
+    #[getter]
+    fn to_date(&self) -> NaiveDate {
+        self.0.to_date
+    }
+
+    #[getter]
+    fn when_generated(&self) -> &str {
+        &self.0.when_generated
+    }
+
+    #[getter]
+    fn trades(&self) -> Vec<PyTrade> {
+        self.0.trades.iter().cloned().map(PyTrade).collect()
+    }
+
+    #[getter]
+    fn positions(&self) -> Vec<PyPosition> {
+        self.0.positions.iter().cloned().map(PyPosition).collect()
+    }
+
+    /// Cash transactions, as dicts
+    #[getter]
+    fn cash_transactions<'py>(&self, py: Python<'py>) -> PyResult<Vec<Bound<'py, PyAny>>> {
+        self.0
+            .cash_transactions
+            .iter()
+            .map(|c| to_dict(py, c))
+            .collect()
+    }
+
+    /// The whole statement, as dicts and lists
+    fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        to_dict(py, &self.0)
+    }
+
+    /// The whole statement, as a JSON string
+    fn to_json(&self) -> PyResult<String> {
+        json::to_string(&self.0).map_err(value_error)
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Statement(account_id={:?}, from_date={}, to_date={}, trades={}, positions={})",
+            self.0.account_id.as_str(),
+            self.0.from_date,
+            self.0.to_date,
+            self.0.trades.len(),
+            self.0.positions.len()
+        )
+    }
+}
+
+/// A trade
+#[pyclass(name = "Trade", module = "ib_flex", frozen)]
+struct PyTrade(Trade);
+
+#[pymethods]
+impl PyTrade {
+    #[getter]
+    fn account_id(&self) -> &str {
+        self.0.account_id.as_str()
+    }
+
+    #[getter]
+    fn transaction_id(&self) -> Option<&str> {
+        self.0.transaction_id.as_deref()
+    }
+
+    #[getter]
+    fn conid(&self) -> u64 {
+        self.0.conid.0
+    }
+
+    #[getter]
+    fn symbol(&self) -> &str {
+        &self.0.symbol
+    }
+
+    #[getter]
+    fn description(&self) -> Option<&str> {
+        self.0.description.as_deref()
+    }
+
+    #[getter]
+    fn asset_category(&self) -> &str {
+        self.0.asset_category.as_str()
+    }
+
+    #[getter]
+    fn buy_sell(&self) -> Option<&str> {
+        self.0.buy_sell.as_ref().map(|b| b.as_str())
+    }
+
+    #[getter]
+    fn trade_date(&self) -> Option<NaiveDate> {
+        self.0.trade_date
+    }
+
+    #[getter]
+    fn trade_time(&self) -> Option<NaiveDateTime> {
+        self.0.trade_time
+    }
+
+    #[getter]
+    fn settle_date(&self) -> Option<NaiveDate> {
+        self.0.settle_date
+    }
+
+    #[getter]
+    fn quantity(&self) -> Option<Decimal> {
+        self.0.quantity
+    }
+
+    #[getter]
+    fn price(&self) -> Option<Decimal> {
+        self.0.price
+    }
+
+    #[getter]
+    fn proceeds(&self) -> Option<Decimal> {
+        self.0.proceeds
+    }
+
+    #[getter]
+    fn commission(&self) -> Option<Decimal> {
+        self.0.commission
+    }
+
+    #[getter]
+    fn net_cash(&self) -> Option<Decimal> {
+        self.0.net_cash
+    }
+
+    #[getter]
+    fn fifo_pnl_realized(&self) -> Option<Decimal> {
+        self.0.fifo_pnl_realized
+    }
+
+    #[getter]
+    fn currency(&self) -> &str {
+        &self.0.currency
+    }
+
+    #[getter]
+    fn fx_rate_to_base(&self) -> Option<Decimal> {
+        self.0.fx_rate_to_base
+    }
+
+    /// Every field, as a dict
+    fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        to_dict(py, &self.0)
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Trade(symbol={:?}, trade_date={}, quantity={}, price={})",
+            self.0.symbol,
+            display(self.0.trade_date),
+            display(self.0.quantity),
+            display(self.0.price)
+        )
+    }
+}
+
+/// An open position
+#[pyclass(name = "Position", module = "ib_flex", frozen)]
+struct PyPosition(Position);
+
+#[pymethods]
+impl PyPosition {
+    #[getter]
+    fn account_id(&self) -> &str {
+        self.0.account_id.as_str()
+    }
+
+    #[getter]
+    fn conid(&self) -> u64 {
+        self.0.conid.0
+    }
+
+    #[getter]
+    fn symbol(&self) -> &str {
+        &self.0.symbol
+    }
+
+    #[getter]
+    fn description(&self) -> Option<&str> {
+        self.0.description.as_deref()
+    }
+
+    #[getter]
+    fn asset_category(&self) -> &str {
+        self.0.asset_category.as_str()
+    }
+
+    #[getter]
+    fn quantity(&self) -> Decimal {
+        self.0.quantity
+    }
+
+    #[getter]
+    fn mark_price(&self) -> Decimal {
+        self.0.mark_price
+    }
+
+    #[getter]
+    fn position_value(&self) -> Decimal {
+        self.0.position_value
+    }
+
+    #[getter]
+    fn cost_basis_money(&self) -> Option<Decimal> {
+        self.0.cost_basis_money
+    }
+
+    #[getter]
+    fn fifo_pnl_unrealized(&self) -> Option<Decimal> {
+        self.0.fifo_pnl_unrealized
+    }
+
+    #[getter]
+    fn currency(&self) -> &str {
+        &self.0.currency
+    }
+
+    #[getter]
+    fn fx_rate_to_base(&self) -> Option<Decimal> {
+        self.0.fx_rate_to_base
+    }
+
+    #[getter]
+    fn report_date(&self) -> NaiveDate {
+        self.0.report_date
+    }
+
+    /// Every field, as a dict
+    fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        to_dict(py, &self.0)
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Position(symbol={:?}, quantity={}, mark_price={}, report_date={})",
+            self.0.symbol, self.0.quantity, self.0.mark_price, self.0.report_date
+        )
+    }
+}
+
+/// The `ib_flex` extension module
+#[pymodule]
+fn ib_flex(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(parse_activity_flex, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_activity_flex_all, m)?)?;
+    m.add_class::<Statement>()?;
+    m.add_class::<PyTrade>()?;
+    m.add_class::<PyPosition>()?;
+    m.add("__version__", env!("CARGO_PKG_VERSION"))?;
+    Ok(())
+}
+
+fn value_error(err: crate::ParseError) -> PyErr {
+    PyValueError::new_err(err.to_string())
+}
+
+fn display<T: std::fmt::Display>(value: Option<T>) -> String {
+    value.map_or_else(|| "None".to_string(), |v| v.to_string())
+}
+
+/// `value` as the Python equivalent of its [`export::json`](crate::export::json) form
+fn to_dict<'py, T: serde::Serialize>(py: Python<'py>, value: &T) -> PyResult<Bound<'py, PyAny>> {
+    to_python(py, &json::to_value(value).map_err(value_error)?)
+}
+
+fn to_python<'py>(py: Python<'py>, value: &Value) -> PyResult<Bound<'py, PyAny>> {
+    Ok(match value {
+        Value::Null => py.None().into_bound(py),
+        Value::Bool(b) => PyBool::new(py, *b).to_owned().into_any(),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => i.into_pyobject(py)?.into_any(),
+            None => PyFloat::new(py, n.as_f64().unwrap_or_default()).into_any(),
+        },
+        Value::String(s) => PyString::new(py, s).into_any(),
+        Value::Array(items) => {
+            let list = PyList::empty(py);
+            for item in items {
+                list.append(to_python(py, item)?)?;
+            }
+            list.into_any()
+        }
+        Value::Object(fields) => {
+            let dict = PyDict::new(py);
+            for (key, value) in fields {
+                dict.set_item(key, to_python(py, value)?)?;
+            }
+            dict.into_any()
+        }
+    })
+}
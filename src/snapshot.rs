@@ -0,0 +1,384 @@
+//! Golden-file assertions (requires the `test-util` feature)
+//!
+//! [`diff_report`] compares two parsed statements field by field and lists
+//! every difference by row and IB attribute name, so a test against a
+//! golden fixture fails with something more useful than two `Debug` dumps.
+//! [`assert_statement_eq!`](crate::assert_statement_eq) panics with the
+//! report:
+//!
+//! ```text
+//! statements differ:
+//!   Trades/Trade[0] price: expected "185.50", got "185.25"
+//!   CashTransactions/CashTransaction[2]: missing
+//! ```
+//!
+//! Statements are compared in the form [`write_activity_flex`] writes them:
+//! rows by position within each section, values as IB formats them. Fields
+//! named in [`SnapshotOptions::ignore_fields`] are skipped wherever they
+//! appear; by default that is the statement's `whenGenerated`, which
+//! changes with every download.
+//!
+//! # Example
+//! ```
+//! use ib_flex::assert_statement_eq;
+//! use ib_flex::parse_activity_flex;
+//! use ib_flex::snapshot::{diff_report, SnapshotOptions};
+//! use rust_decimal::Decimal;
+//!
+//! let xml = include_str!("../tests/fixtures/activity_minimal.xml");
+//! let golden = parse_activity_flex(xml).unwrap();
+//! let mut parsed = golden.clone();
+//! parsed.when_generated = "2025-02-01;000000".to_string();
+//! assert_statement_eq!(golden, parsed);
+//!
+//! parsed.trades.items[0].price = Some(Decimal::new(18525, 2));
+//! let report = diff_report(&golden, &parsed, &SnapshotOptions::default());
+//! assert_eq!(report.differences.len(), 1);
+//! ```
+//!
+//! [`write_activity_flex`]: crate::write_activity_flex
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use quick_xml::events::{BytesStart, Event};
+
+use crate::types::ActivityFlexStatement;
+
+/// Settings for [`diff_report`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnapshotOptions {
+    /// XML attribute names, as IB writes them, not compared on any row
+    /// (default `whenGenerated`)
+    pub ignore_fields: Vec<String>,
+}
+
+impl Default for SnapshotOptions {
+    fn default() -> Self {
+        SnapshotOptions {
+            ignore_fields: vec!["whenGenerated".to_string()],
+        }
+    }
+}
+
+/// One difference between an expected and an actual statement
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Difference {
+    /// An attribute with different values, `None` when empty or absent
+    Field {
+        /// Row the attribute is on, e.g. `Trades/Trade[0]`
+        path: String,
+        /// XML attribute name (e.g. `price`)
+        field: String,
+        /// Value in the expected statement
+        expected: Option<String>,
+        /// Value in the actual statement
+        actual: Option<String>,
+    },
+    /// A row or section only in the expected statement
+    Missing {
+        /// Path of the row or section
+        path: String,
+    },
+    /// A row or section only in the actual statement
+    Unexpected {
+        /// Path of the row or section
+        path: String,
+    },
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = |v: &Option<String>| match v {
+            Some(v) => format!("{:?}", v),
+            None => "(empty)".to_string(),
+        };
+        match self {
+            Difference::Field {
+                path,
+                field,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "{} {}: expected {}, got {}",
+                path,
+                field,
+                value(expected),
+                value(actual)
+            ),
+            Difference::Missing { path } => write!(f, "{}: missing", path),
+            Difference::Unexpected { path } => write!(f, "{}: unexpected", path),
+        }
+    }
+}
+
+/// Differences between two statements, in document order
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct DiffReport {
+    /// Every difference; rows only in one statement are listed once,
+    /// without their attributes
+    pub differences: Vec<Difference>,
+}
+
+impl DiffReport {
+    /// Returns true if the statements match
+    pub fn is_empty(&self) -> bool {
+        self.differences.is_empty()
+    }
+}
+
+impl fmt::Display for DiffReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for difference in &self.differences {
+            writeln!(f, "  {}", difference)?;
+        }
+        Ok(())
+    }
+}
+
+/// Compare `actual` against `expected`
+pub fn diff_report(
+    expected: &ActivityFlexStatement,
+    actual: &ActivityFlexStatement,
+    options: &SnapshotOptions,
+) -> DiffReport {
+    let ignored: HashSet<&str> = options.ignore_fields.iter().map(String::as_str).collect();
+    let expected = flatten(expected, &ignored);
+    let actual = flatten(actual, &ignored);
+    let actual_rows: HashMap<&str, &Element> =
+        actual.iter().map(|e| (e.path.as_str(), e)).collect();
+    let expected_rows: HashSet<&str> = expected.iter().map(|e| e.path.as_str()).collect();
+
+    let mut report = DiffReport::default();
+    // Rows under a missing or unexpected section are not listed again
+    let mut reported: Vec<&str> = Vec::new();
+    for element in &expected {
+        let Some(other) = actual_rows.get(element.path.as_str()) else {
+            if !is_below(&element.path, &reported) {
+                report.differences.push(Difference::Missing {
+                    path: element.path.clone(),
+                });
+                reported.push(&element.path);
+            }
+            continue;
+        };
+        for (field, value) in &element.attributes {
+            let other_value = other.value(field);
+            if value.as_ref() != other_value {
+                report.differences.push(Difference::Field {
+                    path: element.path.clone(),
+                    field: field.clone(),
+                    expected: value.clone(),
+                    actual: other_value.cloned(),
+                });
+            }
+        }
+        for (field, value) in &other.attributes {
+            if value.is_some() && !element.attributes.iter().any(|(f, _)| f == field) {
+                report.differences.push(Difference::Field {
+                    path: element.path.clone(),
+                    field: field.clone(),
+                    expected: None,
+                    actual: value.clone(),
+                });
+            }
+        }
+    }
+    let mut reported: Vec<&str> = Vec::new();
+    for element in &actual {
+        if !expected_rows.contains(element.path.as_str()) && !is_below(&element.path, &reported) {
+            report.differences.push(Difference::Unexpected {
+                path: element.path.clone(),
+            });
+            reported.push(&element.path);
+        }
+    }
+    report
+}
+
+/// Returns true if `path` is below one of `parents`
+fn is_below(path: &str, parents: &[&str]) -> bool {
+    parents.iter().any(|parent| {
+        path.strip_prefix(parent)
+            .is_some_and(|rest| rest.starts_with('/'))
+    })
+}
+
+/// A statement, section, or row, with its attributes in document order
+struct Element {
+    path: String,
+    attributes: Vec<(String, Option<String>)>,
+}
+
+impl Element {
+    fn value(&self, field: &str) -> Option<&String> {
+        self.attributes
+            .iter()
+            .find(|(f, _)| f == field)
+            .and_then(|(_, v)| v.as_ref())
+    }
+}
+
+/// Every element of the statement's XML below the envelope
+///
+/// The statement is `FlexStatement`, sections go by name, and rows by name
+/// and position among their siblings.
+fn flatten(statement: &ActivityFlexStatement, ignored: &HashSet<&str>) -> Vec<Element> {
+    let xml = match crate::write_activity_flex(std::slice::from_ref(statement)) {
+        Ok(xml) => xml,
+        Err(err) => {
+            return vec![Element {
+                path: "FlexStatement".to_string(),
+                attributes: vec![("error".to_string(), Some(err.to_string()))],
+            }]
+        }
+    };
+    let mut elements = Vec::new();
+    let mut reader = quick_xml::Reader::from_str(&xml);
+    // Path of each open element, and how often each child name was seen
+    let mut stack: Vec<(String, HashMap<String, usize>)> = Vec::new();
+    loop {
+        let (start, empty) = match reader.read_event() {
+            Ok(Event::Start(start)) => (start, false),
+            Ok(Event::Empty(start)) => (start, true),
+            Ok(Event::End(_)) => {
+                stack.pop();
+                continue;
+            }
+            Ok(Event::Eof) | Err(_) => break,
+            Ok(_) => continue,
+        };
+        let name = String::from_utf8_lossy(start.name().as_ref()).into_owned();
+        let path = match (stack.len(), stack.last_mut()) {
+            // FlexQueryResponse and FlexStatements
+            (0 | 1, _) => String::new(),
+            (2, _) => "FlexStatement".to_string(),
+            (3, _) => name.clone(),
+            (_, Some((parent, seen))) => {
+                let seen = seen.entry(name.clone()).or_default();
+                *seen += 1;
+                format!("{}/{}[{}]", parent, name, *seen - 1)
+            }
+            (_, None) => String::new(),
+        };
+        if !path.is_empty() {
+            elements.push(Element {
+                path: path.clone(),
+                attributes: attributes(&start, ignored),
+            });
+        }
+        if !empty {
+            stack.push((path, HashMap::new()));
+        }
+    }
+    elements
+}
+
+fn attributes(start: &BytesStart<'_>, ignored: &HashSet<&str>) -> Vec<(String, Option<String>)> {
+    start
+        .attributes()
+        .flatten()
+        .filter_map(|attribute| {
+            let name = String::from_utf8_lossy(attribute.key.as_ref()).into_owned();
+            if ignored.contains(name.as_str()) {
+                return None;
+            }
+            let value = attribute
+                .normalized_value(quick_xml::XmlVersion::Implicit1_0)
+                .map(|v| v.into_owned())
+                .unwrap_or_default();
+            Some((name, Some(value).filter(|v| !v.is_empty())))
+        })
+        .collect()
+}
+
+/// Assert that two statements match, printing a [`DiffReport`] if not
+///
+/// Takes the expected statement, the actual one, and optionally
+/// [`SnapshotOptions`]:
+///
+/// ```
+/// use ib_flex::{assert_statement_eq, parse_activity_flex};
+/// use ib_flex::snapshot::SnapshotOptions;
+///
+/// let xml = include_str!("../tests/fixtures/activity_minimal.xml");
+/// let golden = parse_activity_flex(xml).unwrap();
+/// let mut parsed = golden.clone();
+/// parsed.to_date = parsed.to_date.succ_opt().unwrap();
+///
+/// let options = SnapshotOptions {
+///     ignore_fields: vec!["whenGenerated".into(), "toDate".into()],
+/// };
+/// assert_statement_eq!(golden, parsed, options);
+/// ```
+#[macro_export]
+macro_rules! assert_statement_eq {
+    ($expected:expr, $actual:expr $(,)?) => {
+        $crate::assert_statement_eq!(
+            $expected,
+            $actual,
+            $crate::snapshot::SnapshotOptions::default()
+        )
+    };
+    ($expected:expr, $actual:expr, $options:expr $(,)?) => {
+        match (&$expected, &$actual, &$options) {
+            (expected, actual, options) => {
+                let report = $crate::snapshot::diff_report(expected, actual, options);
+                if !report.is_empty() {
+                    panic!("statements differ:\n{}", report);
+                }
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal::Decimal;
+
+    fn statement() -> ActivityFlexStatement {
+        let xml = include_str!("../tests/fixtures/activity_daily_portfolio.xml");
+        crate::parse_activity_flex(xml).unwrap()
+    }
+
+    #[test]
+    fn test_diff_report() {
+        let expected = statement();
+        let mut actual = expected.clone();
+        actual.when_generated = "20250116;090000".to_string();
+        assert!(diff_report(&expected, &actual, &SnapshotOptions::default()).is_empty());
+
+        actual.trades.items[0].price = Some(Decimal::new(18525, 2));
+        actual.trades.items[1].notes = Some("C".to_string());
+        actual.positions.items.pop();
+        actual.cash_transactions.items.push(Default::default());
+        let report = diff_report(&expected, &actual, &SnapshotOptions::default());
+        let lines: Vec<String> = report.differences.iter().map(|d| d.to_string()).collect();
+        assert_eq!(
+            lines,
+            [
+                "Trades/Trade[0] price: expected \"175.50\", got \"185.25\"",
+                "Trades/Trade[1] notes: expected \"P\", got \"C\"",
+                "OpenPositions/OpenPosition[4]: missing",
+                "CashTransactions/CashTransaction[3]: unexpected",
+            ]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "statements differ:\n  FlexStatement whenGenerated")]
+    fn test_assert_statement_eq() {
+        let expected = statement();
+        let mut actual = expected.clone();
+        actual.when_generated = "20250116;090000".to_string();
+        crate::assert_statement_eq!(
+            expected,
+            actual,
+            SnapshotOptions {
+                ignore_fields: vec![]
+            }
+        );
+    }
+}
@@ -0,0 +1,385 @@
+//! Parsing very large backfills with bounded memory
+//!
+//! [`parse_activity_flex_all`](crate::parse_activity_flex_all) holds the
+//! whole document and every parsed statement in memory, which a
+//! multi-gigabyte consolidated backfill may not fit in. A [`SpillStore`]
+//! reads the XML as a stream instead and writes the rows of the large
+//! sections — trades, open positions, cash transactions, and corporate
+//! actions — to segment files in a temporary directory as it goes. They are
+//! read back one at a time through iterators such as
+//! [`SpillStore::trades`], so resident memory stays bounded by one
+//! statement's smaller sections whatever the size of the input.
+//!
+//! Everything else in each statement is parsed as usual and kept in
+//! [`SpillStore::statements`], with the spilled sections left empty.
+//! Segments are MessagePack, as in [`cache`](crate::cache), and the
+//! directory is removed when the store is dropped.
+//!
+//! Requires the `spill` feature.
+//!
+//! # Example
+//! ```
+//! use ib_flex::spill::SpillStore;
+//!
+//! let xml = include_str!("../tests/fixtures/activity_minimal.xml");
+//! let store = SpillStore::from_reader(xml.as_bytes()).unwrap();
+//! assert!(store.statements()[0].trades.items.is_empty());
+//!
+//! for row in store.trades().unwrap() {
+//!     let (statement, trade) = row.unwrap();
+//!     assert_eq!(statement, 0);
+//!     assert_eq!(trade.symbol, "AAPL");
+//! }
+//! ```
+
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use quick_xml::events::Event;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::types::{ActivityFlexStatement, CashTransaction, CorporateAction, Position, Trade};
+use crate::version::{self, FlexSchemaVersion};
+use crate::{ParseError, Result};
+
+/// Spilled sections, as `(section element, row element, segment file)`
+const SEGMENTS: [(&str, &str, &str); 4] = [
+    ("Trades", "Trade", "trades.mp"),
+    ("OpenPositions", "OpenPosition", "positions.mp"),
+    (
+        "CashTransactions",
+        "CashTransaction",
+        "cash_transactions.mp",
+    ),
+    (
+        "CorporateActions",
+        "CorporateAction",
+        "corporate_actions.mp",
+    ),
+];
+
+/// Statements parsed with their largest sections on disk
+///
+/// See the [module documentation](self).
+#[derive(Debug)]
+pub struct SpillStore {
+    dir: PathBuf,
+    statements: Vec<ActivityFlexStatement>,
+    counts: [usize; 4],
+}
+
+impl SpillStore {
+    /// Parse every statement of the FLEX XML file at `path`
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        Self::from_reader(BufReader::new(File::open(path)?))
+    }
+
+    /// Parse every statement of the FLEX XML read from `reader`, spilling to
+    /// the system temporary directory
+    pub fn from_reader<R: BufRead>(reader: R) -> Result<Self> {
+        Self::from_reader_in(reader, std::env::temp_dir())
+    }
+
+    /// Parse every statement of the FLEX XML read from `reader`, spilling to
+    /// a new directory inside `parent`
+    ///
+    /// Use this when the system temporary directory is too small for the
+    /// backfill.
+    pub fn from_reader_in<R: BufRead>(reader: R, parent: impl AsRef<Path>) -> Result<Self> {
+        let dir = create_dir(parent.as_ref())?;
+        let mut store = SpillStore {
+            dir,
+            statements: Vec::new(),
+            counts: [0; 4],
+        };
+        store.fill(reader)?;
+        Ok(store)
+    }
+
+    /// Statements in document order, with trades, open positions, cash
+    /// transactions, and corporate actions left empty
+    pub fn statements(&self) -> &[ActivityFlexStatement] {
+        &self.statements
+    }
+
+    /// Take the statements, leaving the spilled rows readable
+    pub fn take_statements(&mut self) -> Vec<ActivityFlexStatement> {
+        std::mem::take(&mut self.statements)
+    }
+
+    /// Trades of every statement in document order, each with the index of
+    /// its statement
+    pub fn trades(&self) -> Result<Records<Trade>> {
+        self.records(0)
+    }
+
+    /// Open positions of every statement in document order, each with the
+    /// index of its statement
+    pub fn positions(&self) -> Result<Records<Position>> {
+        self.records(1)
+    }
+
+    /// Cash transactions of every statement in document order, each with
+    /// the index of its statement
+    pub fn cash_transactions(&self) -> Result<Records<CashTransaction>> {
+        self.records(2)
+    }
+
+    /// Corporate actions of every statement in document order, each with
+    /// the index of its statement
+    pub fn corporate_actions(&self) -> Result<Records<CorporateAction>> {
+        self.records(3)
+    }
+
+    fn records<T>(&self, segment: usize) -> Result<Records<T>> {
+        Ok(Records {
+            reader: BufReader::new(File::open(self.dir.join(SEGMENTS[segment].2))?),
+            remaining: self.counts[segment],
+            row: PhantomData,
+        })
+    }
+
+    fn fill<R: BufRead>(&mut self, reader: R) -> Result<()> {
+        let mut segments = Vec::with_capacity(SEGMENTS.len());
+        for (_, _, file) in SEGMENTS {
+            segments.push(BufWriter::new(File::create(self.dir.join(file))?));
+        }
+
+        let mut reader = quick_xml::Reader::from_reader(reader);
+        let mut buf = Vec::new();
+        let mut document = FlexSchemaVersion::V3;
+        let mut version = None;
+        let mut depth = 0;
+        let mut response = b"<FlexQueryResponse>".to_vec();
+        let mut statement: Option<quick_xml::Writer<Vec<u8>>> = None;
+        let mut section = None;
+        let mut row: Option<(usize, quick_xml::Writer<Vec<u8>>)> = None;
+        loop {
+            {
+                let event = reader.read_event_into(&mut buf).map_err(xml_error)?;
+                // Depth of the element the event opens or closes, or one
+                // below the element its content is in
+                let (event, level, opens, closes) = match event {
+                    Event::Start(start) => {
+                        depth += 1;
+                        let start = version::rename(start, &mut document, &mut version);
+                        (Event::Start(start), depth, true, false)
+                    }
+                    Event::Empty(start) => {
+                        let start = version::rename(start, &mut document, &mut version);
+                        (Event::Empty(start), depth + 1, true, true)
+                    }
+                    Event::End(end) => {
+                        depth -= 1;
+                        (Event::End(end), depth + 1, false, true)
+                    }
+                    Event::Eof => break,
+                    other => (other, depth + 1, false, false),
+                };
+
+                if let (true, Event::Start(start) | Event::Empty(start)) = (opens, &event) {
+                    let name = start.name();
+                    let name = name.as_ref();
+                    match level {
+                        1 => {
+                            response.clear();
+                            quick_xml::Writer::new(&mut response)
+                                .write_event(Event::Start(start.borrow()))
+                                .map_err(xml_error)?;
+                        }
+                        3 if name == b"FlexStatement" => {
+                            statement = Some(quick_xml::Writer::new(Vec::new()));
+                        }
+                        4 if statement.is_some() => {
+                            section = SEGMENTS
+                                .iter()
+                                .position(|(section, _, _)| section.as_bytes() == name);
+                        }
+                        5 if row.is_none() => {
+                            if let Some(i) = section.filter(|&i| SEGMENTS[i].1.as_bytes() == name) {
+                                row = Some((i, quick_xml::Writer::new(Vec::new())));
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+
+                if let Some((_, writer)) = &mut row {
+                    writer.write_event(event).map_err(xml_error)?;
+                } else if let Some(writer) = &mut statement {
+                    writer.write_event(event).map_err(xml_error)?;
+                }
+                if closes {
+                    match level {
+                        5 => {
+                            if let Some((i, writer)) = row.take() {
+                                let xml =
+                                    String::from_utf8(writer.into_inner()).map_err(xml_error)?;
+                                let index = self.statements.len();
+                                let segment = &mut segments[i];
+                                match i {
+                                    0 => spill::<Trade>(segment, index, &xml)?,
+                                    1 => spill::<Position>(segment, index, &xml)?,
+                                    2 => spill::<CashTransaction>(segment, index, &xml)?,
+                                    _ => spill::<CorporateAction>(segment, index, &xml)?,
+                                }
+                                self.counts[i] += 1;
+                            }
+                        }
+                        4 => section = None,
+                        3 => {
+                            version = None;
+                            if let Some(writer) = statement.take() {
+                                self.statements.push(parse_statement(&response, writer)?);
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            buf.clear();
+        }
+
+        for mut segment in segments {
+            segment.flush()?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for SpillStore {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Rows read back from a [`SpillStore`] segment, as `(statement index, row)`
+#[derive(Debug)]
+pub struct Records<T> {
+    reader: BufReader<File>,
+    remaining: usize,
+    row: PhantomData<T>,
+}
+
+impl<T: DeserializeOwned> Iterator for Records<T> {
+    type Item = Result<(usize, T)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        let reader = &mut self.reader;
+        Some(
+            rmp_serde::decode::from_read(reader).map_err(|e| ParseError::CacheError(e.to_string())),
+        )
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T: DeserializeOwned> ExactSizeIterator for Records<T> {}
+
+/// Parse one row element and append it to its segment
+fn spill<T: DeserializeOwned + Serialize>(
+    segment: &mut BufWriter<File>,
+    statement: usize,
+    xml: &str,
+) -> Result<()> {
+    let row: T = quick_xml::de::from_str(xml).map_err(xml_error)?;
+    rmp_serde::encode::write_named(segment, &(statement, row))
+        .map_err(|e| ParseError::CacheError(e.to_string()))
+}
+
+/// Parse a statement with its spilled rows removed
+fn parse_statement(
+    response: &[u8],
+    statement: quick_xml::Writer<Vec<u8>>,
+) -> Result<ActivityFlexStatement> {
+    let mut xml = response.to_vec();
+    xml.extend_from_slice(b"<FlexStatements count=\"1\">");
+    xml.extend_from_slice(&statement.into_inner());
+    xml.extend_from_slice(b"</FlexStatements></FlexQueryResponse>");
+    let xml = String::from_utf8(xml).map_err(xml_error)?;
+    crate::parse_activity_flex(&xml)
+}
+
+fn create_dir(parent: &Path) -> io::Result<PathBuf> {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.subsec_nanos());
+    let dir = parent.join(format!(
+        "ib-flex-spill-{}-{}-{}",
+        std::process::id(),
+        nanos,
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    fs::create_dir(&dir)?;
+    Ok(dir)
+}
+
+fn xml_error(e: impl std::fmt::Display) -> ParseError {
+    ParseError::XmlError {
+        message: format!("Failed to parse FLEX XML: {}", e),
+        location: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches_parsing_in_memory() {
+        let fixtures = [
+            include_str!("../tests/fixtures/activity_backfill.xml"),
+            include_str!("../tests/fixtures/activity_cash.xml"),
+            include_str!("../tests/fixtures/activity_complex_corporate_actions.xml"),
+            include_str!("../tests/fixtures/activity_daily_portfolio.xml"),
+            include_str!("../tests/fixtures/activity_extended.xml"),
+            include_str!("../tests/fixtures/activity_options.xml"),
+        ];
+        for xml in fixtures {
+            let expected = crate::parse_activity_flex_all(xml).unwrap();
+            let mut store = SpillStore::from_reader(xml.as_bytes()).unwrap();
+
+            let mut statements = store.take_statements();
+            for row in store.trades().unwrap() {
+                let (i, trade) = row.unwrap();
+                statements[i].trades.items.push(trade);
+            }
+            for row in store.positions().unwrap() {
+                let (i, position) = row.unwrap();
+                statements[i].positions.items.push(position);
+            }
+            for row in store.cash_transactions().unwrap() {
+                let (i, tx) = row.unwrap();
+                statements[i].cash_transactions.items.push(tx);
+            }
+            for row in store.corporate_actions().unwrap() {
+                let (i, action) = row.unwrap();
+                statements[i].corporate_actions.items.push(action);
+            }
+            assert_eq!(statements, expected);
+        }
+    }
+
+    #[test]
+    fn test_removes_directory_on_drop() {
+        let xml = include_str!("../tests/fixtures/activity_minimal.xml");
+        let store = SpillStore::from_reader(xml.as_bytes()).unwrap();
+        let dir = store.dir.clone();
+        assert!(dir.join("trades.mp").exists());
+        drop(store);
+        assert!(!dir.exists());
+        assert!(SpillStore::from_reader("<FlexQueryResponse><Bad".as_bytes()).is_err());
+    }
+}
@@ -0,0 +1,595 @@
+//! Synthetic FLEX statements for tests (requires the `test-util` feature)
+//!
+//! [`generate`] writes a valid Activity FLEX document from a [`FixtureSpec`]:
+//! so many stock trades across so many symbols, option trades, dividends,
+//! and splits, so tests can cover edge cases and volumes without shipping
+//! real statements. The same spec always gives the same document.
+//!
+//! The statement is internally consistent: quantities are tracked lot by
+//! lot (first in, first out), `fifoPnlRealized` follows from the lots a
+//! sale closes, and `OpenPositions` holds what is left at the end of the
+//! period, valued at the last trade price. Sections:
+//!
+//! | Section | Rows |
+//! |---|---|
+//! | `Trades` | Stock trades, starting with a buy of each symbol, then buys and sales of at most half the holding; option buys to open, expiring after the period |
+//! | `CashTransactions` | Each dividend and its 15% withholding tax |
+//! | `CorporateActions` | 2-for-1 forward splits (`FS`) |
+//! | `OpenPositions` | Summary rows for every contract held at the end |
+//! | `SecuritiesInfo` | Every contract traded |
+//!
+//! Dividends and splits go to symbols held at the time, so they need at
+//! least one stock trade. Events fall on weekdays, spread evenly over the
+//! period.
+//!
+//! # Example
+//! ```
+//! use ib_flex::parse_activity_flex;
+//! use ib_flex::test_util::{generate, FixtureSpec};
+//!
+//! let xml = generate(&FixtureSpec {
+//!     trades: 50,
+//!     symbols: 5,
+//!     option_trades: 3,
+//!     dividends: 4,
+//!     corporate_actions: 1,
+//!     ..Default::default()
+//! });
+//! let statement = parse_activity_flex(&xml).unwrap();
+//! assert_eq!(statement.trades.len(), 53);
+//! assert_eq!(statement.cash_transactions.len(), 8);
+//! assert_eq!(statement.corporate_actions.len(), 1);
+//! ```
+
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+
+use chrono::{Datelike, Days, NaiveDate, Weekday};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+
+/// What [`generate`] puts in a statement
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FixtureSpec {
+    /// Account of the statement and every row (default `U1234567`)
+    pub account_id: String,
+    /// First day of the period (default 2025-01-01)
+    pub from_date: NaiveDate,
+    /// Last day of the period (default 2025-03-31)
+    pub to_date: NaiveDate,
+    /// Number of stock symbols traded (default 3); the first ten are real
+    /// tickers with their conids, later ones `SYM11`, `SYM12`, ...
+    pub symbols: usize,
+    /// Number of stock trades (default 20)
+    pub trades: usize,
+    /// Number of option trades (default 0)
+    pub option_trades: usize,
+    /// Number of dividends, each with a withholding tax row (default 0)
+    pub dividends: usize,
+    /// Number of 2-for-1 splits (default 0)
+    pub corporate_actions: usize,
+    /// Seed for prices, quantities, and the order of events (default 1)
+    pub seed: u64,
+}
+
+impl Default for FixtureSpec {
+    fn default() -> Self {
+        FixtureSpec {
+            account_id: "U1234567".to_string(),
+            from_date: NaiveDate::from_ymd_opt(2025, 1, 1).expect("valid date"),
+            to_date: NaiveDate::from_ymd_opt(2025, 3, 31).expect("valid date"),
+            symbols: 3,
+            trades: 20,
+            option_trades: 0,
+            dividends: 0,
+            corporate_actions: 0,
+            seed: 1,
+        }
+    }
+}
+
+/// Tickers, descriptions, and conids of the first symbols
+const SYMBOLS: &[(&str, &str, u64)] = &[
+    ("AAPL", "APPLE INC", 265598),
+    ("MSFT", "MICROSOFT CORP", 272093),
+    ("NVDA", "NVIDIA CORP", 4815747),
+    ("AMZN", "AMAZON.COM INC", 3691937),
+    ("GOOG", "ALPHABET INC-CL C", 208813720),
+    ("META", "META PLATFORMS INC-CLASS A", 107113386),
+    ("TSLA", "TESLA INC", 76792991),
+    ("JPM", "JPMORGAN CHASE & CO", 1520593),
+    ("XOM", "EXXON MOBIL CORP", 13977),
+    ("KO", "COCA-COLA CO/THE", 8894),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Trade,
+    Option,
+    Dividend,
+    Split,
+}
+
+/// A contract and the lots held in it
+struct Contract {
+    symbol: String,
+    description: String,
+    conid: u64,
+    price: Decimal,
+    multiplier: Decimal,
+    /// Strike, expiry, put or call, and underlying conid and symbol
+    option: Option<(Decimal, NaiveDate, char, u64, String)>,
+    /// Quantity and total cost of each open lot, oldest first
+    lots: VecDeque<(Decimal, Decimal)>,
+}
+
+impl Contract {
+    fn quantity(&self) -> Decimal {
+        self.lots.iter().map(|(q, _)| q).sum()
+    }
+
+    fn cost(&self) -> Decimal {
+        self.lots.iter().map(|(_, c)| c).sum()
+    }
+}
+
+/// Generate an Activity FLEX document from `spec`
+pub fn generate(spec: &FixtureSpec) -> String {
+    let mut rng = Rng(spec.seed);
+    let mut stocks: Vec<Contract> = (0..spec.symbols)
+        .map(|i| {
+            let (symbol, description, conid) = match SYMBOLS.get(i) {
+                Some((s, d, c)) => (s.to_string(), d.to_string(), *c),
+                None => (
+                    format!("SYM{}", i + 1),
+                    format!("SYNTHETIC STOCK {}", i + 1),
+                    9_000_000 + i as u64,
+                ),
+            };
+            Contract {
+                symbol,
+                description,
+                conid,
+                price: Decimal::new(2000 + rng.below(48000) as i64, 2),
+                multiplier: Decimal::ONE,
+                option: None,
+                lots: VecDeque::new(),
+            }
+        })
+        .collect();
+    let mut options: Vec<Contract> = Vec::new();
+
+    // The opening buys come first so dividends and splits find holdings
+    let opening = spec.trades.min(spec.symbols);
+    let mut kinds: Vec<Kind> = std::iter::repeat(Kind::Trade)
+        .take(spec.trades - opening)
+        .chain(std::iter::repeat(Kind::Option).take(spec.option_trades))
+        .chain(std::iter::repeat(Kind::Dividend).take(spec.dividends))
+        .chain(std::iter::repeat(Kind::Split).take(spec.corporate_actions))
+        .collect();
+    for i in (1..kinds.len()).rev() {
+        kinds.swap(i, rng.below(i as u64 + 1) as usize);
+    }
+    let events: Vec<Kind> = std::iter::repeat(Kind::Trade)
+        .take(opening)
+        .chain(kinds)
+        .collect();
+
+    let days = weekdays(spec.from_date, spec.to_date);
+    let mut trades = String::new();
+    let mut cash = String::new();
+    let mut actions = String::new();
+    let account = escape(&spec.account_id);
+    for (i, kind) in events.iter().enumerate() {
+        let date = days[i * days.len() / events.len().max(1)];
+        let time = format!("{};{:02}{:02}00", ib_date(date), 9 + i % 7, (i * 7) % 60);
+        let id = i as u64 + 1;
+        let held: Vec<usize> = (0..stocks.len())
+            .filter(|&s| !stocks[s].lots.is_empty())
+            .collect();
+        match kind {
+            Kind::Trade => {
+                let s = if i < opening {
+                    i
+                } else {
+                    rng.below(stocks.len() as u64) as usize
+                };
+                let stock = &mut stocks[s];
+                stock.price = walk(&mut rng, stock.price);
+                let holding = stock.quantity();
+                // Sales are round lots of at most half the holding
+                let round_lots = (holding / Decimal::from(20)).floor().to_u64().unwrap_or(0);
+                let quantity = if round_lots > 0 && rng.below(2) == 0 {
+                    -(Decimal::from(rng.below(round_lots) + 1) * Decimal::TEN)
+                } else {
+                    Decimal::from(rng.below(10) + 1) * Decimal::TEN
+                };
+                trade(&mut trades, &account, stock, id, &time, date, quantity);
+            }
+            Kind::Option => {
+                let underlying = &stocks[rng.below(stocks.len() as u64) as usize];
+                let five = Decimal::from(5);
+                let strike = ((underlying.price / five).round() * five).max(five);
+                let put_call = if rng.below(2) == 0 { 'C' } else { 'P' };
+                let mut expiry = (date + Days::new(30)).max(spec.to_date + Days::new(1));
+                while expiry.weekday() != Weekday::Fri {
+                    expiry = expiry + Days::new(1);
+                }
+                let symbol = format!(
+                    "{:<6}{}{}{:08}",
+                    underlying.symbol,
+                    expiry.format("%y%m%d"),
+                    put_call,
+                    strike * Decimal::from(1000)
+                );
+                let description = format!(
+                    "{} {} {} {}",
+                    underlying.symbol,
+                    expiry.format("%d%b%y").to_string().to_uppercase(),
+                    strike.normalize(),
+                    put_call
+                );
+                let premium = (underlying.price * Decimal::new(3, 2))
+                    .round_dp(2)
+                    .max(Decimal::new(5, 2));
+                options.push(Contract {
+                    symbol,
+                    description,
+                    conid: 700_000_000 + options.len() as u64,
+                    price: premium,
+                    multiplier: Decimal::ONE_HUNDRED,
+                    option: Some((
+                        strike,
+                        expiry,
+                        put_call,
+                        underlying.conid,
+                        underlying.symbol.clone(),
+                    )),
+                    lots: VecDeque::new(),
+                });
+                let option = options.last_mut().expect("just pushed");
+                let quantity = Decimal::from(rng.below(5) + 1);
+                trade(&mut trades, &account, option, id, &time, date, quantity);
+            }
+            Kind::Dividend => {
+                let Some(&s) = held.get(rng.below(held.len().max(1) as u64) as usize) else {
+                    continue;
+                };
+                let stock = &stocks[s];
+                let rate = Decimal::new(5 + rng.below(96) as i64, 2);
+                let gross = (stock.quantity() * rate).round_dp(2);
+                let tax = -(gross * Decimal::new(15, 2)).round_dp(2);
+                let description = format!(
+                    "{} Cash Dividend USD {} per Share (Ordinary Dividend)",
+                    stock.symbol, rate
+                );
+                for (n, (kind, amount, text)) in [
+                    ("Dividends", gross, description.clone()),
+                    ("Withholding Tax", tax, format!("{} - US Tax", description)),
+                ]
+                .into_iter()
+                .enumerate()
+                {
+                    let _ = writeln!(
+                        cash,
+                        r#"                <CashTransaction accountId="{}" currency="USD" fxRateToBase="1" assetCategory="STK" symbol="{}" description="{}" conid="{}" type="{}" amount="{}" dateTime="{}" settleDate="{}" reportDate="{}" transactionID="{}" actionID="A{}" levelOfDetail="DETAIL" />"#,
+                        account,
+                        escape(&stock.symbol),
+                        escape(&text),
+                        stock.conid,
+                        kind,
+                        amount,
+                        time,
+                        ib_date(date),
+                        ib_date(date),
+                        id * 10 + n as u64,
+                        id
+                    );
+                }
+            }
+            Kind::Split => {
+                let Some(&s) = held.get(rng.below(held.len().max(1) as u64) as usize) else {
+                    continue;
+                };
+                let stock = &mut stocks[s];
+                let added = stock.quantity();
+                for lot in &mut stock.lots {
+                    lot.0 *= Decimal::TWO;
+                }
+                stock.price = (stock.price / Decimal::TWO).round_dp(2);
+                let _ = writeln!(
+                    actions,
+                    r#"                <CorporateAction accountId="{}" currency="USD" fxRateToBase="1" assetCategory="STK" symbol="{}" description="{} SPLIT 2 FOR 1 ({})" conid="{}" type="FS" quantity="{}" amount="0" proceeds="0" value="0" fifoPnlRealized="0" dateTime="{}" reportDate="{}" transactionID="{}" actionID="A{}" levelOfDetail="DETAIL" />"#,
+                    account,
+                    escape(&stock.symbol),
+                    escape(&stock.symbol),
+                    escape(&stock.description),
+                    stock.conid,
+                    added,
+                    time,
+                    ib_date(date),
+                    id * 10,
+                    id
+                );
+            }
+        }
+    }
+
+    let mut positions = String::new();
+    let mut securities = String::new();
+    for contract in stocks.iter().chain(&options) {
+        let category = if contract.option.is_some() {
+            "OPT"
+        } else {
+            "STK"
+        };
+        let fields = contract_attributes(contract, category);
+        let _ = writeln!(
+            securities,
+            r#"                <SecurityInfo {} currency="USD" />"#,
+            fields
+        );
+        let quantity = contract.quantity();
+        if quantity.is_zero() {
+            continue;
+        }
+        let value = quantity * contract.price * contract.multiplier;
+        let cost = contract.cost().round_dp(2);
+        let _ = writeln!(
+            positions,
+            r#"                <OpenPosition accountId="{}" currency="USD" fxRateToBase="1" {} position="{}" markPrice="{}" positionValue="{}" costBasisPrice="{}" costBasisMoney="{}" fifoPnlUnrealized="{}" side="Long" reportDate="{}" levelOfDetail="SUMMARY" />"#,
+            account,
+            fields,
+            quantity,
+            contract.price,
+            value.round_dp(2),
+            (cost / quantity / contract.multiplier).round_dp(4),
+            cost,
+            (value - cost).round_dp(2),
+            ib_date(spec.to_date)
+        );
+    }
+
+    let mut xml = String::new();
+    xml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str("<FlexQueryResponse queryName=\"Synthetic\" type=\"AF\">\n");
+    xml.push_str("    <FlexStatements count=\"1\">\n");
+    let _ = writeln!(
+        xml,
+        r#"        <FlexStatement accountId="{}" fromDate="{}" toDate="{}" period="Custom" whenGenerated="{};080000">"#,
+        account,
+        ib_date(spec.from_date),
+        ib_date(spec.to_date),
+        ib_date(spec.to_date + Days::new(1))
+    );
+    for (section, rows) in [
+        ("Trades", trades),
+        ("OpenPositions", positions),
+        ("CashTransactions", cash),
+        ("CorporateActions", actions),
+        ("SecuritiesInfo", securities),
+    ] {
+        let _ = write!(
+            xml,
+            "            <{0}>\n{1}            </{0}>\n",
+            section, rows
+        );
+    }
+    xml.push_str("        </FlexStatement>\n");
+    xml.push_str("    </FlexStatements>\n");
+    xml.push_str("</FlexQueryResponse>\n");
+    xml
+}
+
+/// Append a trade of `quantity` at the contract's price and update its lots
+fn trade(
+    out: &mut String,
+    account: &str,
+    contract: &mut Contract,
+    id: u64,
+    time: &str,
+    date: NaiveDate,
+    quantity: Decimal,
+) {
+    let commission = if contract.option.is_some() {
+        -(Decimal::new(65, 2) * quantity.abs())
+    } else {
+        -Decimal::ONE
+    };
+    let proceeds = -(quantity * contract.price * contract.multiplier);
+    let mut realized = Decimal::ZERO;
+    if quantity.is_sign_positive() {
+        contract.lots.push_back((quantity, -proceeds - commission));
+    } else {
+        let mut remaining = -quantity;
+        let mut basis = Decimal::ZERO;
+        while remaining > Decimal::ZERO {
+            let Some(lot) = contract.lots.front_mut() else {
+                break;
+            };
+            let take = remaining.min(lot.0);
+            let cost = lot.1 * take / lot.0;
+            basis += cost;
+            lot.0 -= take;
+            lot.1 -= cost;
+            remaining -= take;
+            if lot.0.is_zero() {
+                contract.lots.pop_front();
+            }
+        }
+        realized = (proceeds + commission - basis).round_dp(2);
+    }
+    let (side, open_close) = if quantity.is_sign_positive() {
+        ("BUY", "O")
+    } else {
+        ("SELL", "C")
+    };
+    let category = if contract.option.is_some() {
+        "OPT"
+    } else {
+        "STK"
+    };
+    let mut settle = date + Days::new(1);
+    while matches!(settle.weekday(), Weekday::Sat | Weekday::Sun) {
+        settle = settle + Days::new(1);
+    }
+    let _ = writeln!(
+        out,
+        r#"                <Trade accountId="{}" currency="USD" fxRateToBase="1" {} transactionID="{}" tradeID="{}" ibOrderID="{}" tradeDate="{}" dateTime="{}" settleDateTarget="{}" buySell="{}" openCloseIndicator="{}" quantity="{}" price="{}" proceeds="{}" ibCommission="{}" ibCommissionCurrency="USD" netCash="{}" fifoPnlRealized="{}" levelOfDetail="EXECUTION" />"#,
+        account,
+        contract_attributes(contract, category),
+        id * 10,
+        id,
+        id * 10 + 1,
+        ib_date(date),
+        time,
+        ib_date(settle),
+        side,
+        open_close,
+        quantity,
+        contract.price,
+        proceeds.round_dp(2),
+        commission,
+        (proceeds + commission).round_dp(2),
+        realized
+    );
+}
+
+/// Category, symbol, description, conid, multiplier, and option fields
+fn contract_attributes(contract: &Contract, category: &str) -> String {
+    let mut fields = format!(
+        r#"assetCategory="{}" symbol="{}" description="{}" conid="{}" multiplier="{}""#,
+        category,
+        escape(&contract.symbol),
+        escape(&contract.description),
+        contract.conid,
+        contract.multiplier
+    );
+    if let Some((strike, expiry, put_call, underlying_conid, underlying)) = &contract.option {
+        let _ = write!(
+            fields,
+            r#" strike="{}" expiry="{}" putCall="{}" underlyingConid="{}" underlyingSymbol="{}""#,
+            strike.normalize(),
+            ib_date(*expiry),
+            put_call,
+            underlying_conid,
+            escape(underlying)
+        );
+    }
+    fields
+}
+
+/// Next price: up to 3% up or down
+fn walk(rng: &mut Rng, price: Decimal) -> Decimal {
+    let change = Decimal::new(rng.below(601) as i64 - 300, 4);
+    (price * (Decimal::ONE + change))
+        .round_dp(2)
+        .max(Decimal::ONE)
+}
+
+/// Weekdays from `from` through `to`, or `from` alone if there are none
+fn weekdays(from: NaiveDate, to: NaiveDate) -> Vec<NaiveDate> {
+    let days: Vec<NaiveDate> = from
+        .iter_days()
+        .take_while(|d| *d <= to)
+        .filter(|d| !matches!(d.weekday(), Weekday::Sat | Weekday::Sun))
+        .collect();
+    if days.is_empty() {
+        vec![from]
+    } else {
+        days
+    }
+}
+
+fn ib_date(date: NaiveDate) -> String {
+    date.format("%Y%m%d").to_string()
+}
+
+fn escape(value: &str) -> String {
+    quick_xml::escape::escape(value).into_owned()
+}
+
+/// SplitMix64, so a seed gives the same document on every platform
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform-enough value in `0..n`
+    fn below(&mut self, n: u64) -> u64 {
+        self.next() % n.max(1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analytics::{reconstruct_positions, LotLedger};
+    use crate::types::LevelOfDetail;
+
+    #[test]
+    fn test_generated_statement_is_consistent() {
+        let spec = FixtureSpec {
+            trades: 200,
+            symbols: 12,
+            option_trades: 5,
+            dividends: 6,
+            corporate_actions: 2,
+            seed: 7,
+            ..Default::default()
+        };
+        let xml = generate(&spec);
+        assert_eq!(xml, generate(&spec));
+        assert_ne!(
+            xml,
+            generate(&FixtureSpec {
+                seed: 8,
+                ..spec.clone()
+            })
+        );
+
+        let statement = crate::parse_activity_flex(&xml).unwrap();
+        assert_eq!(statement.trades.len(), 205);
+        assert_eq!(statement.cash_transactions.len(), 12);
+        assert_eq!(statement.corporate_actions.len(), 2);
+        assert_eq!(statement.securities_info.len(), 17);
+        assert!(statement.trades.iter().any(|t| t.symbol == "SYM12"));
+        assert!(statement
+            .trades
+            .iter()
+            .all(|t| t.level_of_detail == Some(LevelOfDetail::Execution)));
+
+        // The positions follow from the trades and splits
+        let rebuilt = reconstruct_positions([&statement], &[], statement.to_date);
+        for p in &statement.positions {
+            let r = rebuilt.iter().find(|r| r.conid == p.conid).unwrap();
+            assert_eq!(r.quantity, p.quantity, "{}", p.symbol);
+        }
+        assert_eq!(rebuilt.len(), statement.positions.len());
+
+        let mut ledger = LotLedger::new();
+        ledger.apply_trades(&statement.trades);
+        assert!(ledger.open_lots().all(|lot| lot.is_long()));
+    }
+
+    #[test]
+    fn test_empty_spec() {
+        let spec = FixtureSpec {
+            trades: 0,
+            dividends: 3,
+            corporate_actions: 1,
+            ..Default::default()
+        };
+        let statement = crate::parse_activity_flex(&generate(&spec)).unwrap();
+        assert!(statement.trades.is_empty());
+        assert!(statement.cash_transactions.is_empty());
+        assert!(statement.positions.is_empty());
+        assert_eq!(statement.securities_info.len(), 3);
+    }
+}
@@ -1,15 +1,29 @@
 //! Activity FLEX statement types
 
-use chrono::NaiveDate;
+use chrono::{NaiveDate, NaiveDateTime};
 use rust_decimal::Decimal;
-use serde::{Deserialize, Serialize};
+#[cfg(feature = "schemars")]
+use schemars::JsonSchema;
+use serde::Deserialize;
+#[cfg(feature = "serialize")]
+use serde::Serialize;
 
 use super::common::{
-    AssetCategory, BuySell, DerivativeInfo, LevelOfDetail, OpenClose, OrderType, PutCall,
-    SecurityIdType, SubCategory, TradeType,
+    AccountId, AssetCategory, BuySell, CashTransactionType, Conid, DerivativeInfo, ExecutionId,
+    LevelOfDetail, LongShort, OpenClose, OrderId, OrderType, PutCall, SecurityIdType, SubCategory,
+    TradeCodes, TradeId, TradeType,
 };
+use super::money::Money;
+use super::traits::Dated;
 use crate::parsers::xml_utils::{
     deserialize_optional_bool, deserialize_optional_date, deserialize_optional_decimal,
+    deserialize_optional_enum, deserialize_optional_from_str, deserialize_optional_string,
+    parse_optional_datetime,
+};
+#[cfg(feature = "serialize")]
+use crate::parsers::xml_utils::{
+    raw_datetime_matches, serialize_optional_bool, serialize_optional_date,
+    serialize_optional_raw_datetime, sync_raw_datetime,
 };
 
 /// Top-level FLEX query response
@@ -45,15 +59,26 @@ use crate::parsers::xml_utils::{
 /// assert_eq!(response.statements.statements.len(), 1);
 /// # Ok::<(), Box<dyn std::error::Error>>(())
 /// ```
-#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 #[serde(rename = "FlexQueryResponse")]
 pub struct FlexQueryResponse {
     /// Query name
-    #[serde(rename = "@queryName", default)]
+    #[serde(
+        rename = "@queryName",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub query_name: Option<String>,
 
     /// Query type
-    #[serde(rename = "@type", default)]
+    #[serde(
+        rename = "@type",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub query_type: Option<String>,
 
     /// FlexStatements wrapper
@@ -61,11 +86,43 @@ pub struct FlexQueryResponse {
     pub statements: FlexStatementsWrapper,
 }
 
+impl FlexQueryResponse {
+    /// Wrap statements in a response envelope
+    pub fn new(statements: Vec<ActivityFlexStatement>) -> Self {
+        FlexQueryResponse {
+            query_name: None,
+            query_type: Some("AF".to_string()),
+            statements: FlexStatementsWrapper {
+                count: Some(statements.len().to_string()),
+                statements,
+            },
+        }
+    }
+
+    /// Serialize back to FLEX XML
+    ///
+    /// See the [`writer`](crate::writer) module for the formats used. The
+    /// output parses back with
+    /// [`parse_activity_flex_all`](crate::parse_activity_flex_all) into the
+    /// same statements. Requires the `serialize` feature.
+    #[cfg(feature = "serialize")]
+    pub fn to_xml(&self) -> crate::Result<String> {
+        crate::writer::write_response(self)
+    }
+}
+
 /// Wrapper for FlexStatements
-#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct FlexStatementsWrapper {
     /// Count
-    #[serde(rename = "@count", default)]
+    #[serde(
+        rename = "@count",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub count: Option<String>,
 
     /// Flex statement(s)
@@ -118,24 +175,31 @@ pub struct FlexStatementsWrapper {
 /// }
 /// # Ok::<(), Box<dyn std::error::Error>>(())
 /// ```
-#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+// The catch-all sections are only read by the derived comparisons
+#[cfg_attr(not(feature = "eq"), allow(dead_code))]
 #[serde(rename = "FlexStatement")]
 pub struct ActivityFlexStatement {
     /// IB account number
     #[serde(rename = "@accountId")]
-    pub account_id: String,
+    pub account_id: AccountId,
 
     /// Statement date range - start date
     #[serde(
         rename = "@fromDate",
-        deserialize_with = "crate::parsers::xml_utils::deserialize_flex_date"
+        deserialize_with = "crate::parsers::xml_utils::deserialize_flex_date",
+        serialize_with = "crate::parsers::xml_utils::serialize_flex_date"
     )]
     pub from_date: NaiveDate,
 
     /// Statement date range - end date
     #[serde(
         rename = "@toDate",
-        deserialize_with = "crate::parsers::xml_utils::deserialize_flex_date"
+        deserialize_with = "crate::parsers::xml_utils::deserialize_flex_date",
+        serialize_with = "crate::parsers::xml_utils::serialize_flex_date"
     )]
     pub to_date: NaiveDate,
 
@@ -143,6 +207,22 @@ pub struct ActivityFlexStatement {
     #[serde(rename = "@whenGenerated")]
     pub when_generated: String, // Parse separately due to IB format
 
+    /// IANA name of the reporting timezone, when supplied through
+    /// [`ParseOptions`](crate::parsers::ParseOptions)
+    ///
+    /// FLEX XML does not record the timezone, so this is never read from or
+    /// written to XML.
+    #[serde(skip)]
+    pub timezone: Option<String>,
+
+    /// Sections left empty because they failed to parse, when
+    /// [`ParseOptions::with_section_recovery`](crate::parsers::ParseOptions::with_section_recovery)
+    /// is set
+    ///
+    /// Like `timezone`, never read from or written to XML.
+    #[serde(skip)]
+    pub section_errors: Vec<crate::error::SectionError>,
+
     /// All trades in the period
     #[serde(rename = "Trades", default)]
     pub trades: TradesWrapper,
@@ -169,11 +249,19 @@ pub struct ActivityFlexStatement {
 
     // Extended v0.2.0+ sections
     /// Account information
-    #[serde(rename = "AccountInformation", default)]
+    #[serde(
+        rename = "AccountInformation",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
     pub account_information: Option<super::extended::AccountInformation>,
 
     /// Change in NAV - single element (not wrapped like other sections)
-    #[serde(rename = "ChangeInNAV", default)]
+    #[serde(
+        rename = "ChangeInNAV",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
     pub change_in_nav: Option<super::extended::ChangeInNAV>,
 
     /// Equity summary by report date in base currency
@@ -212,6 +300,10 @@ pub struct ActivityFlexStatement {
     #[serde(rename = "Transfers", default)]
     pub transfers: TransfersWrapper,
 
+    /// Deposits and withdrawals with funding method details
+    #[serde(rename = "DepositsWithdrawals", default)]
+    pub deposits_withdrawals: DepositsWithdrawalsWrapper,
+
     // v0.3.0+ sections - Performance and advanced features
     /// MTM performance summary by underlying
     #[serde(rename = "MTMPerformanceSummaryInBase", default)]
@@ -258,7 +350,10 @@ pub struct ActivityFlexStatement {
     pub hard_to_borrow_details: HardToBorrowDetailsWrapper,
 
     /// FX position lots
-    #[serde(rename = "FxLots", default)]
+    ///
+    /// IB nests these as `<FxPositions><FxLots><FxLot/></FxLots></FxPositions>`;
+    /// a bare top-level `<FxLots>` section is accepted as well.
+    #[serde(rename = "FxPositions", alias = "FxLots", default)]
     pub fx_lots: FxLotsWrapper,
 
     /// Unsettled transfers
@@ -294,8 +389,6 @@ pub struct ActivityFlexStatement {
     // These prevent parse errors when XML contains these sections
     #[serde(rename = "DepositsOnHold", default, skip_serializing)]
     deposits_on_hold: IgnoredSection,
-    #[serde(rename = "FxPositions", default, skip_serializing)]
-    fx_positions: IgnoredSection,
     #[serde(rename = "NetStockPositions", default, skip_serializing)]
     net_stock_positions: IgnoredSection,
     #[serde(rename = "ComplexPositions", default, skip_serializing)]
@@ -338,8 +431,229 @@ pub struct ActivityFlexStatement {
     // All unknown sections should be explicitly listed above with IgnoredSection
 }
 
+impl ActivityFlexStatement {
+    /// A statement for the period with no records in any section
+    pub(crate) fn empty(
+        account_id: AccountId,
+        from_date: NaiveDate,
+        to_date: NaiveDate,
+        when_generated: String,
+    ) -> Self {
+        ActivityFlexStatement {
+            account_id,
+            from_date,
+            to_date,
+            when_generated,
+            timezone: None,
+            section_errors: Vec::new(),
+            trades: Default::default(),
+            positions: Default::default(),
+            cash_transactions: Default::default(),
+            corporate_actions: Default::default(),
+            securities_info: Default::default(),
+            conversion_rates: Default::default(),
+            account_information: Default::default(),
+            change_in_nav: Default::default(),
+            equity_summary: Default::default(),
+            cash_report: Default::default(),
+            trade_confirms: Default::default(),
+            option_eae: Default::default(),
+            fx_transactions: Default::default(),
+            change_in_dividend_accruals: Default::default(),
+            open_dividend_accruals: Default::default(),
+            interest_accruals: Default::default(),
+            transfers: Default::default(),
+            deposits_withdrawals: Default::default(),
+            mtm_performance_summary: Default::default(),
+            fifo_performance_summary: Default::default(),
+            mtd_ytd_performance_summary: Default::default(),
+            statement_of_funds: Default::default(),
+            change_in_position_values: Default::default(),
+            unbundled_commission_details: Default::default(),
+            client_fees: Default::default(),
+            client_fees_detail: Default::default(),
+            slb_activities: Default::default(),
+            slb_fees: Default::default(),
+            hard_to_borrow_details: Default::default(),
+            fx_lots: Default::default(),
+            unsettled_transfers: Default::default(),
+            trade_transfers: Default::default(),
+            prior_period_positions: Default::default(),
+            tier_interest_details: Default::default(),
+            debit_card_activities: Default::default(),
+            sales_tax: Default::default(),
+            deposits_on_hold: Default::default(),
+            net_stock_positions: Default::default(),
+            complex_positions: Default::default(),
+            cfd_charges: Default::default(),
+            commission_credits: Default::default(),
+            fdic_insured_deposits: Default::default(),
+            hk_ipo_open_subscriptions: Default::default(),
+            hk_ipo_subscription_activity: Default::default(),
+            ibg_note_transactions: Default::default(),
+            incentive_coupon_accruals: Default::default(),
+            mutual_fund_dividends: Default::default(),
+            net_stock_position_summary: Default::default(),
+            pending_exercises: Default::default(),
+            routing_commissions: Default::default(),
+            slb_collaterals: Default::default(),
+            slb_open_contracts: Default::default(),
+            soft_dollars: Default::default(),
+            stock_grant_activities: Default::default(),
+            transaction_taxes: Default::default(),
+            unbooked_trades: Default::default(),
+        }
+    }
+
+    /// Serialize this statement as a standalone FLEX XML document
+    ///
+    /// See [`FlexQueryResponse::to_xml`] for the formats used. Requires the
+    /// `serialize` feature.
+    #[cfg(feature = "serialize")]
+    pub fn to_xml(&self) -> crate::Result<String> {
+        crate::writer::write_activity_flex(std::slice::from_ref(self))
+    }
+
+    /// `whenGenerated` parsed as a naive date/time in the reporting timezone
+    pub fn when_generated_datetime(&self) -> crate::Result<NaiveDateTime> {
+        crate::parsers::datetime::parse_ib_datetime(&self.when_generated)
+    }
+
+    /// Trades in `symbol`
+    pub fn trades_for_symbol<'a>(&'a self, symbol: &'a str) -> impl Iterator<Item = &'a Trade> {
+        self.trades.iter().filter(move |t| t.symbol == symbol)
+    }
+
+    /// Trades whose trade date falls within `range`
+    ///
+    /// Falls back to the report date for rows without a trade date; rows with
+    /// neither are skipped.
+    pub fn trades_in<R>(&self, range: R) -> impl Iterator<Item = &Trade>
+    where
+        R: std::ops::RangeBounds<NaiveDate>,
+    {
+        self.trades
+            .iter()
+            .filter(move |t| t.date().is_some_and(|date| range.contains(&date)))
+    }
+
+    /// Open position in the given contract, if any
+    pub fn position_by_conid(&self, conid: Conid) -> Option<&Position> {
+        self.positions.iter().find(|p| p.conid == conid)
+    }
+
+    /// Dividend cash transactions, including payments in lieu
+    pub fn dividends(&self) -> impl Iterator<Item = &CashTransaction> {
+        self.cash_transactions.iter().filter(|tx| {
+            tx.transaction_type
+                .as_ref()
+                .is_some_and(|t| t.is_dividend())
+        })
+    }
+
+    /// Total commissions per commission currency, sorted by currency code
+    ///
+    /// IB reports commissions as negative amounts, so the totals are
+    /// normally negative too.
+    pub fn total_commissions(&self) -> Vec<Money> {
+        let mut totals: std::collections::BTreeMap<String, Decimal> = Default::default();
+        for money in self.trades.iter().filter_map(Trade::commission_money) {
+            *totals.entry(money.currency).or_default() += money.amount;
+        }
+        totals
+            .into_iter()
+            .map(|(currency, amount)| Money::new(amount, currency))
+            .collect()
+    }
+
+    /// Executions grouped under their parent order by `ibOrderID`
+    ///
+    /// Groups appear in the order their first execution appears in the
+    /// statement. Only execution-level rows are grouped, so order-level
+    /// summaries don't double count; executions without an order ID are
+    /// skipped.
+    pub fn order_groups(&self) -> Vec<OrderGroup<'_>> {
+        let mut groups: Vec<OrderGroup<'_>> = Vec::new();
+        let mut index: std::collections::HashMap<&OrderId, usize> = Default::default();
+        let executions = self
+            .trades
+            .iter()
+            .filter(|t| matches!(t.level_of_detail, None | Some(LevelOfDetail::Execution)));
+        for trade in executions {
+            let Some(order_id) = trade.ib_order_id.as_ref().filter(|id| !id.is_empty()) else {
+                continue;
+            };
+            let idx = *index.entry(order_id).or_insert_with(|| {
+                groups.push(OrderGroup {
+                    order_id: order_id.clone(),
+                    executions: Vec::new(),
+                });
+                groups.len() - 1
+            });
+            groups[idx].executions.push(trade);
+        }
+        groups
+    }
+}
+
+/// The executions that filled one IB order
+///
+/// IB statements present executions nested under their order with the
+/// order's aggregate quantity and average price; this reproduces that view.
+/// Returned by [`ActivityFlexStatement::order_groups`].
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "eq", derive(PartialEq))]
+pub struct OrderGroup<'a> {
+    /// IB order ID shared by the executions
+    pub order_id: OrderId,
+
+    /// Executions in statement order
+    pub executions: Vec<&'a Trade>,
+}
+
+impl OrderGroup<'_> {
+    /// Symbol of the order's first execution
+    pub fn symbol(&self) -> &str {
+        self.executions.first().map_or("", |t| t.symbol.as_str())
+    }
+
+    /// Total filled quantity (signed, so sells are negative)
+    pub fn total_quantity(&self) -> Decimal {
+        self.executions
+            .iter()
+            .filter_map(|t| t.signed_quantity())
+            .sum()
+    }
+
+    /// Quantity-weighted average fill price
+    ///
+    /// Returns `None` if no execution has both a quantity and a price, or the
+    /// filled quantity is zero.
+    pub fn average_price(&self) -> Option<Decimal> {
+        let (notional, quantity) = self
+            .executions
+            .iter()
+            .filter_map(|t| Some((t.quantity?.abs(), t.price?)))
+            .fold(
+                (Decimal::ZERO, Decimal::ZERO),
+                |(notional, total), (qty, price)| (notional + qty * price, total + qty),
+            );
+        if quantity.is_zero() {
+            None
+        } else {
+            Some(notional / quantity)
+        }
+    }
+
+    /// Total commission across executions, in the trade currency
+    pub fn total_commission(&self) -> Decimal {
+        self.executions.iter().filter_map(|t| t.commission).sum()
+    }
+}
+
 /// Helper type for sections we want to ignore during parsing
-#[derive(Debug, Clone, PartialEq, Default)]
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
 struct IgnoredSection;
 
 impl<'de> serde::Deserialize<'de> for IgnoredSection {
@@ -353,11 +667,26 @@ impl<'de> serde::Deserialize<'de> for IgnoredSection {
     }
 }
 
+#[cfg(feature = "schemars")]
+impl JsonSchema for IgnoredSection {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        "IgnoredSection".into()
+    }
+
+    // Never serialized, so any value
+    fn json_schema(_: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        schemars::Schema::default()
+    }
+}
+
 /// Element types that can appear in the `<Trades>` section.
 ///
 /// IB FLEX interleaves different element types by symbol, so we parse them all
 /// into an enum and then filter by type for user access.
-#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(not(feature = "eq"), allow(dead_code))]
 #[serde(rename_all = "PascalCase")]
 enum TradesItem {
     Trade(Trade),
@@ -377,13 +706,66 @@ enum TradesItem {
 /// - `<SymbolSummary>`, `<AssetSummary>`, `<WashSale>`, `<Lot>` - various summary records
 ///
 /// These elements can be interleaved (grouped by symbol), not by type.
-#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
 pub struct TradesWrapper {
     /// Trade executions (main trading data)
     pub items: Vec<Trade>,
 
     /// Wash sale records
     pub wash_sales: Vec<Trade>,
+
+    /// Per-symbol totals of the executions (`<SymbolSummary>` rows)
+    pub symbol_summaries: Vec<Trade>,
+}
+
+#[cfg(feature = "serialize")]
+impl Serialize for TradesWrapper {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        // Write the same element names IB uses so the output parses back
+        #[derive(Serialize)]
+        struct Raw<'a> {
+            #[serde(rename = "Trade")]
+            items: &'a [Trade],
+            #[serde(rename = "WashSale")]
+            wash_sales: &'a [Trade],
+            #[serde(rename = "SymbolSummary")]
+            symbol_summaries: &'a [Trade],
+        }
+
+        Raw {
+            items: &self.items,
+            wash_sales: &self.wash_sales,
+            symbol_summaries: &self.symbol_summaries,
+        }
+        .serialize(serializer)
+    }
+}
+
+#[cfg(feature = "schemars")]
+impl JsonSchema for TradesWrapper {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        "TradesWrapper".into()
+    }
+
+    fn json_schema(generator: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        // The shape written by the `Serialize` impl
+        #[derive(JsonSchema)]
+        #[allow(dead_code)]
+        struct Raw {
+            #[serde(rename = "Trade")]
+            items: Vec<Trade>,
+            #[serde(rename = "WashSale")]
+            wash_sales: Vec<Trade>,
+            #[serde(rename = "SymbolSummary")]
+            symbol_summaries: Vec<Trade>,
+        }
+
+        Raw::json_schema(generator)
+    }
 }
 
 impl<'de> serde::Deserialize<'de> for TradesWrapper {
@@ -397,16 +779,39 @@ impl<'de> serde::Deserialize<'de> for TradesWrapper {
             items: Vec<TradesItem>,
         }
 
+        #[derive(Deserialize)]
+        struct Serialized {
+            #[serde(rename = "Trade", default)]
+            items: Vec<Trade>,
+            #[serde(rename = "WashSale", default)]
+            wash_sales: Vec<Trade>,
+            #[serde(rename = "SymbolSummary", default)]
+            symbol_summaries: Vec<Trade>,
+        }
+
+        // FLEX XML interleaves the rows as child elements; binary formats
+        // such as the cache's MessagePack hold the shape serialized above
+        if !deserializer.is_human_readable() {
+            let serialized = Serialized::deserialize(deserializer)?;
+            return Ok(TradesWrapper {
+                items: serialized.items,
+                wash_sales: serialized.wash_sales,
+                symbol_summaries: serialized.symbol_summaries,
+            });
+        }
+
         let raw = Raw::deserialize(deserializer)?;
 
         let mut trades = Vec::new();
         let mut wash_sales = Vec::new();
+        let mut symbol_summaries = Vec::new();
 
         for item in raw.items {
             match item {
                 TradesItem::Trade(t) => trades.push(t),
                 TradesItem::WashSale(t) => wash_sales.push(t),
-                // Ignore Order, SymbolSummary, AssetSummary, Lot for items
+                TradesItem::SymbolSummary(t) => symbol_summaries.push(t),
+                // Ignore Order, AssetSummary, Lot for items
                 _ => {}
             }
         }
@@ -414,12 +819,16 @@ impl<'de> serde::Deserialize<'de> for TradesWrapper {
         Ok(TradesWrapper {
             items: trades,
             wash_sales,
+            symbol_summaries,
         })
     }
 }
 
 /// Wrapper for positions section
-#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct PositionsWrapper {
     /// List of positions
     #[serde(rename = "OpenPosition", default)]
@@ -427,7 +836,10 @@ pub struct PositionsWrapper {
 }
 
 /// Wrapper for cash transactions section
-#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct CashTransactionsWrapper {
     /// List of cash transactions
     #[serde(rename = "CashTransaction", default)]
@@ -435,7 +847,10 @@ pub struct CashTransactionsWrapper {
 }
 
 /// Wrapper for corporate actions section
-#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct CorporateActionsWrapper {
     /// List of corporate actions
     #[serde(rename = "CorporateAction", default)]
@@ -488,7 +903,11 @@ pub struct CorporateActionsWrapper {
 /// }
 /// # Ok::<(), Box<dyn std::error::Error>>(())
 /// ```
-#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[serde(remote = "Self")]
 pub struct Trade {
     // ==================== CORE FIELDS ====================
     // Essential for tax reporting and portfolio analytics
@@ -496,23 +915,31 @@ pub struct Trade {
     // --- Account ---
     /// IB account number
     #[serde(rename = "@accountId")]
-    pub account_id: String,
+    pub account_id: AccountId,
 
     /// IB transaction ID (unique identifier for idempotency)
-    #[serde(rename = "@transactionID", default)]
+    #[serde(
+        rename = "@transactionID",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub transaction_id: Option<String>,
 
     // --- Security Identification ---
     /// IB contract ID (unique per security)
-    #[serde(rename = "@conid")]
-    pub conid: String,
+    #[serde(rename = "@conid", default)]
+    pub conid: Conid,
 
     /// Ticker symbol
     #[serde(rename = "@symbol")]
     pub symbol: String,
 
     /// Security description
-    #[serde(rename = "@description", default)]
+    #[serde(
+        rename = "@description",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub description: Option<String>,
 
     /// Asset category (stock, option, future, etc.)
@@ -520,23 +947,43 @@ pub struct Trade {
     pub asset_category: AssetCategory,
 
     /// CUSIP
-    #[serde(rename = "@cusip", default)]
+    #[serde(
+        rename = "@cusip",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub cusip: Option<String>,
 
     /// ISIN
-    #[serde(rename = "@isin", default)]
+    #[serde(
+        rename = "@isin",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub isin: Option<String>,
 
     /// FIGI
-    #[serde(rename = "@figi", default)]
+    #[serde(
+        rename = "@figi",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub figi: Option<String>,
 
     /// Security ID
-    #[serde(rename = "@securityID", default)]
+    #[serde(
+        rename = "@securityID",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub security_id: Option<String>,
 
     /// Security ID type
-    #[serde(rename = "@securityIDType", default)]
+    #[serde(
+        rename = "@securityIDType",
+        default,
+        deserialize_with = "deserialize_optional_enum"
+    )]
     pub security_id_type: Option<SecurityIdType>,
 
     // --- Derivatives (Options/Futures) ---
@@ -560,20 +1007,33 @@ pub struct Trade {
     #[serde(
         rename = "@expiry",
         default,
-        deserialize_with = "deserialize_optional_date"
+        deserialize_with = "deserialize_optional_date",
+        serialize_with = "serialize_optional_date"
     )]
     pub expiry: Option<NaiveDate>,
 
     /// Put or Call (for options)
-    #[serde(rename = "@putCall", default)]
+    #[serde(
+        rename = "@putCall",
+        default,
+        deserialize_with = "deserialize_optional_enum"
+    )]
     pub put_call: Option<PutCall>,
 
     /// Underlying security's contract ID (for derivatives)
-    #[serde(rename = "@underlyingConid", default)]
-    pub underlying_conid: Option<String>,
+    #[serde(
+        rename = "@underlyingConid",
+        default,
+        deserialize_with = "deserialize_optional_from_str"
+    )]
+    pub underlying_conid: Option<Conid>,
 
     /// Underlying symbol
-    #[serde(rename = "@underlyingSymbol", default)]
+    #[serde(
+        rename = "@underlyingSymbol",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub underlying_symbol: Option<String>,
 
     // --- Trade Execution ---
@@ -581,7 +1041,8 @@ pub struct Trade {
     #[serde(
         rename = "@tradeDate",
         default,
-        deserialize_with = "deserialize_optional_date"
+        deserialize_with = "deserialize_optional_date",
+        serialize_with = "serialize_optional_date"
     )]
     pub trade_date: Option<NaiveDate>,
 
@@ -589,20 +1050,33 @@ pub struct Trade {
     #[serde(
         rename = "@settleDateTarget",
         default,
-        deserialize_with = "deserialize_optional_date"
+        deserialize_with = "deserialize_optional_date",
+        serialize_with = "serialize_optional_date"
     )]
     pub settle_date: Option<NaiveDate>,
 
     /// Buy or Sell
-    #[serde(rename = "@buySell", default)]
+    #[serde(
+        rename = "@buySell",
+        default,
+        deserialize_with = "deserialize_optional_enum"
+    )]
     pub buy_sell: Option<BuySell>,
 
     /// Open or Close indicator (for options/futures)
-    #[serde(rename = "@openCloseIndicator", default)]
+    #[serde(
+        rename = "@openCloseIndicator",
+        default,
+        deserialize_with = "deserialize_optional_enum"
+    )]
     pub open_close: Option<OpenClose>,
 
     /// Transaction type (ExchTrade, BookTrade, etc.)
-    #[serde(rename = "@transactionType", default)]
+    #[serde(
+        rename = "@transactionType",
+        default,
+        deserialize_with = "deserialize_optional_enum"
+    )]
     pub transaction_type: Option<TradeType>,
 
     // --- Quantities and Prices ---
@@ -706,7 +1180,8 @@ pub struct Trade {
     #[serde(
         rename = "@origTradeDate",
         default,
-        deserialize_with = "deserialize_optional_date"
+        deserialize_with = "deserialize_optional_date",
+        serialize_with = "serialize_optional_date"
     )]
     pub orig_trade_date: Option<NaiveDate>,
 
@@ -719,23 +1194,71 @@ pub struct Trade {
     pub orig_trade_price: Option<Decimal>,
 
     /// Original trade ID (links closing trade to opening trade)
-    #[serde(rename = "@origTradeID", default)]
-    pub orig_trade_id: Option<String>,
+    #[serde(
+        rename = "@origTradeID",
+        default,
+        deserialize_with = "deserialize_optional_from_str"
+    )]
+    pub orig_trade_id: Option<TradeId>,
 
     /// Holding period date/time (for long-term vs short-term determination)
-    #[serde(rename = "@holdingPeriodDateTime", default)]
-    pub holding_period_date_time: Option<String>,
+    ///
+    /// `None` when `holdingPeriodDateTime` is empty or in a format the parser doesn't
+    /// recognize; the text as written is kept in
+    /// [`holding_period_date_time_raw`](Self::holding_period_date_time_raw).
+    #[serde(skip)]
+    pub holding_period_date_time: Option<NaiveDateTime>,
+
+    /// `holdingPeriodDateTime` as written in the XML
+    #[serde(
+        rename = "@holdingPeriodDateTime",
+        default,
+        deserialize_with = "deserialize_optional_string",
+        serialize_with = "serialize_optional_raw_datetime"
+    )]
+    pub holding_period_date_time_raw: Option<String>,
 
     /// When position was opened
-    #[serde(rename = "@openDateTime", default)]
-    pub open_date_time: Option<String>,
+    ///
+    /// `None` when `openDateTime` is empty or in a format the parser doesn't
+    /// recognize; the text as written is kept in
+    /// [`open_date_time_raw`](Self::open_date_time_raw).
+    #[serde(skip)]
+    pub open_date_time: Option<NaiveDateTime>,
+
+    /// `openDateTime` as written in the XML
+    #[serde(
+        rename = "@openDateTime",
+        default,
+        deserialize_with = "deserialize_optional_string",
+        serialize_with = "serialize_optional_raw_datetime"
+    )]
+    pub open_date_time_raw: Option<String>,
 
     /// When position was reopened (for wash sale tracking)
-    #[serde(rename = "@whenReopened", default)]
-    pub when_reopened: Option<String>,
+    ///
+    /// `None` when `whenReopened` is empty or in a format the parser doesn't
+    /// recognize; the text as written is kept in
+    /// [`when_reopened_raw`](Self::when_reopened_raw).
+    #[serde(skip)]
+    pub when_reopened: Option<NaiveDateTime>,
+
+    /// `whenReopened` as written in the XML
+    #[serde(
+        rename = "@whenReopened",
+        default,
+        deserialize_with = "deserialize_optional_string",
+        serialize_with = "serialize_optional_raw_datetime"
+    )]
+    pub when_reopened_raw: Option<String>,
 
-    /// Trade notes/codes (may contain wash sale indicator "W")
-    #[serde(rename = "@notes", default)]
+    /// Trade notes/codes, semicolon-separated (e.g., "C;W" for a closing
+    /// trade with a wash sale). Use [`Trade::codes`] for structured access.
+    #[serde(
+        rename = "@notes",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub notes: Option<String>,
 
     // ==================== EXTENDED FIELDS ====================
@@ -743,112 +1266,231 @@ pub struct Trade {
 
     // --- Order/Execution IDs ---
     /// IB order ID (may be shared across multiple executions)
-    #[serde(rename = "@orderID", default)]
-    pub ib_order_id: Option<String>,
+    #[serde(
+        rename = "@ibOrderID",
+        alias = "@orderID",
+        default,
+        deserialize_with = "deserialize_optional_from_str"
+    )]
+    pub ib_order_id: Option<OrderId>,
 
     /// Execution ID
-    #[serde(rename = "@execID", default)]
-    pub exec_id: Option<String>,
+    #[serde(
+        rename = "@execID",
+        default,
+        deserialize_with = "deserialize_optional_from_str"
+    )]
+    pub exec_id: Option<ExecutionId>,
 
     /// Trade ID
-    #[serde(rename = "@tradeID", default)]
-    pub trade_id: Option<String>,
+    #[serde(
+        rename = "@tradeID",
+        default,
+        deserialize_with = "deserialize_optional_from_str"
+    )]
+    pub trade_id: Option<TradeId>,
 
     /// Original transaction ID
-    #[serde(rename = "@origTransactionID", default)]
+    #[serde(
+        rename = "@origTransactionID",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub orig_transaction_id: Option<String>,
 
     /// Original order ID
-    #[serde(rename = "@origOrderID", default)]
+    #[serde(
+        rename = "@origOrderID",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub orig_order_id: Option<String>,
 
     // --- Timestamps ---
-    /// Trade time (date + time)
-    #[serde(rename = "@dateTime", default)]
-    pub trade_time: Option<String>,
+    /// Trade execution timestamp, parsed from `dateTime`
+    ///
+    /// Accepts both `yyyyMMdd;HHmmss` and `yyyy-MM-dd;HH:mm:ss` forms; a
+    /// date-only value parses as midnight.
+    ///
+    /// `None` when `dateTime` is empty or in a format the parser doesn't
+    /// recognize; the text as written is kept in
+    /// [`trade_time_raw`](Self::trade_time_raw).
+    #[serde(skip)]
+    pub trade_time: Option<NaiveDateTime>,
+
+    /// `dateTime` as written in the XML
+    #[serde(
+        rename = "@dateTime",
+        default,
+        deserialize_with = "deserialize_optional_string",
+        serialize_with = "serialize_optional_raw_datetime"
+    )]
+    pub trade_time_raw: Option<String>,
 
     /// When P&L was realized
-    #[serde(rename = "@whenRealized", default)]
-    pub when_realized: Option<String>,
+    ///
+    /// `None` when `whenRealized` is empty or in a format the parser doesn't
+    /// recognize; the text as written is kept in
+    /// [`when_realized_raw`](Self::when_realized_raw).
+    #[serde(skip)]
+    pub when_realized: Option<NaiveDateTime>,
+
+    /// `whenRealized` as written in the XML
+    #[serde(
+        rename = "@whenRealized",
+        default,
+        deserialize_with = "deserialize_optional_string",
+        serialize_with = "serialize_optional_raw_datetime"
+    )]
+    pub when_realized_raw: Option<String>,
 
     /// Order time
-    #[serde(rename = "@orderTime", default)]
+    #[serde(
+        rename = "@orderTime",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub order_time: Option<String>,
 
     // --- Order Details ---
     /// Order type (market, limit, stop, etc.)
-    #[serde(rename = "@orderType", default)]
+    #[serde(
+        rename = "@orderType",
+        default,
+        deserialize_with = "deserialize_optional_enum"
+    )]
     pub order_type: Option<OrderType>,
 
     /// Brokerage order ID
-    #[serde(rename = "@brokerageOrderID", default)]
+    #[serde(
+        rename = "@brokerageOrderID",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub brokerage_order_id: Option<String>,
 
     /// Order reference
-    #[serde(rename = "@orderReference", default)]
+    #[serde(
+        rename = "@orderReference",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub order_reference: Option<String>,
 
     /// Exchange order ID
-    #[serde(rename = "@exchOrderId", default)]
+    #[serde(
+        rename = "@exchOrderId",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub exch_order_id: Option<String>,
 
     /// External execution ID
-    #[serde(rename = "@extExecID", default)]
+    #[serde(
+        rename = "@extExecID",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub ext_exec_id: Option<String>,
 
     /// IB execution ID
-    #[serde(rename = "@ibExecID", default)]
-    pub ib_exec_id: Option<String>,
+    #[serde(
+        rename = "@ibExecID",
+        default,
+        deserialize_with = "deserialize_optional_from_str"
+    )]
+    pub ib_exec_id: Option<ExecutionId>,
 
     // --- Issuer/Security Metadata ---
     /// Issuer
-    #[serde(rename = "@issuer", default)]
+    #[serde(
+        rename = "@issuer",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub issuer: Option<String>,
 
     /// Issuer country code
-    #[serde(rename = "@issuerCountryCode", default)]
+    #[serde(
+        rename = "@issuerCountryCode",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub issuer_country_code: Option<String>,
 
     /// Sub-category
-    #[serde(rename = "@subCategory", default)]
+    #[serde(
+        rename = "@subCategory",
+        default,
+        deserialize_with = "deserialize_optional_enum"
+    )]
     pub sub_category: Option<SubCategory>,
 
     /// Listing exchange
-    #[serde(rename = "@listingExchange", default)]
+    #[serde(
+        rename = "@listingExchange",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub listing_exchange: Option<String>,
 
     // --- Underlying Extended ---
     /// Underlying listing exchange
-    #[serde(rename = "@underlyingListingExchange", default)]
+    #[serde(
+        rename = "@underlyingListingExchange",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub underlying_listing_exchange: Option<String>,
 
     /// Underlying security ID
-    #[serde(rename = "@underlyingSecurityID", default)]
+    #[serde(
+        rename = "@underlyingSecurityID",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub underlying_security_id: Option<String>,
 
     // --- Execution Metadata ---
     /// Trader ID
-    #[serde(rename = "@traderID", default)]
+    #[serde(
+        rename = "@traderID",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub trader_id: Option<String>,
 
     /// Is API order (true if order was placed via API)
     #[serde(
         rename = "@isAPIOrder",
         default,
-        deserialize_with = "deserialize_optional_bool"
+        deserialize_with = "deserialize_optional_bool",
+        serialize_with = "serialize_optional_bool"
     )]
     pub is_api_order: Option<bool>,
 
     /// Volatility order link
-    #[serde(rename = "@volatilityOrderLink", default)]
+    #[serde(
+        rename = "@volatilityOrderLink",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub volatility_order_link: Option<String>,
 
     /// Clearing firm ID
-    #[serde(rename = "@clearingFirmID", default)]
+    #[serde(
+        rename = "@clearingFirmID",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub clearing_firm_id: Option<String>,
 
     /// Level of detail (EXECUTION, ORDER, CLOSED_LOT, etc.)
-    #[serde(rename = "@levelOfDetail", default)]
+    #[serde(
+        rename = "@levelOfDetail",
+        default,
+        deserialize_with = "deserialize_optional_enum"
+    )]
     pub level_of_detail: Option<LevelOfDetail>,
 
     // --- Price/Quantity Changes ---
@@ -893,16 +1535,28 @@ pub struct Trade {
     pub change_in_quantity: Option<Decimal>,
 
     /// Commission currency
-    #[serde(rename = "@ibCommissionCurrency", default)]
+    #[serde(
+        rename = "@ibCommissionCurrency",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub commission_currency: Option<String>,
 
     // --- Related Trade Tracking ---
     /// Related trade ID
-    #[serde(rename = "@relatedTradeID", default)]
+    #[serde(
+        rename = "@relatedTradeID",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub related_trade_id: Option<String>,
 
     /// Related transaction ID
-    #[serde(rename = "@relatedTransactionID", default)]
+    #[serde(
+        rename = "@relatedTransactionID",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub related_transaction_id: Option<String>,
 
     // --- Bond Fields ---
@@ -924,15 +1578,27 @@ pub struct Trade {
 
     // --- Commodity/Physical Delivery ---
     /// Serial number (for physical delivery)
-    #[serde(rename = "@serialNumber", default)]
+    #[serde(
+        rename = "@serialNumber",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub serial_number: Option<String>,
 
     /// Delivery type
-    #[serde(rename = "@deliveryType", default)]
+    #[serde(
+        rename = "@deliveryType",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub delivery_type: Option<String>,
 
     /// Commodity type
-    #[serde(rename = "@commodityType", default)]
+    #[serde(
+        rename = "@commodityType",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub commodity_type: Option<String>,
 
     /// Fineness (for precious metals)
@@ -944,7 +1610,11 @@ pub struct Trade {
     pub fineness: Option<Decimal>,
 
     /// Weight
-    #[serde(rename = "@weight", default)]
+    #[serde(
+        rename = "@weight",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub weight: Option<String>,
 
     // --- Other Metadata ---
@@ -952,28 +1622,49 @@ pub struct Trade {
     #[serde(
         rename = "@reportDate",
         default,
-        deserialize_with = "deserialize_optional_date"
+        deserialize_with = "deserialize_optional_date",
+        serialize_with = "serialize_optional_date"
     )]
     pub report_date: Option<NaiveDate>,
 
     /// Exchange where trade executed
-    #[serde(rename = "@exchange", default)]
+    #[serde(
+        rename = "@exchange",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub exchange: Option<String>,
 
     /// Model (for model portfolios)
-    #[serde(rename = "@model", default)]
+    #[serde(
+        rename = "@model",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub model: Option<String>,
 
     /// Account alias
-    #[serde(rename = "@acctAlias", default)]
+    #[serde(
+        rename = "@acctAlias",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub acct_alias: Option<String>,
 
     /// RTN
-    #[serde(rename = "@rtn", default)]
+    #[serde(
+        rename = "@rtn",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub rtn: Option<String>,
 
     /// Position action ID
-    #[serde(rename = "@positionActionID", default)]
+    #[serde(
+        rename = "@positionActionID",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub position_action_id: Option<String>,
 
     /// Initial investment
@@ -985,7 +1676,174 @@ pub struct Trade {
     pub initial_investment: Option<Decimal>,
 }
 
+// Derived with `remote = "Self"`, so these wrap the derived
+// `Trade::deserialize`/`Trade::serialize` to keep the typed date/times and
+// their raw text in step.
+impl<'de> Deserialize<'de> for Trade {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut trade = Trade::deserialize(deserializer)?;
+        trade.holding_period_date_time = trade
+            .holding_period_date_time_raw
+            .as_deref()
+            .and_then(parse_optional_datetime);
+        trade.open_date_time = trade
+            .open_date_time_raw
+            .as_deref()
+            .and_then(parse_optional_datetime);
+        trade.when_reopened = trade
+            .when_reopened_raw
+            .as_deref()
+            .and_then(parse_optional_datetime);
+        trade.trade_time = trade
+            .trade_time_raw
+            .as_deref()
+            .and_then(parse_optional_datetime);
+        trade.when_realized = trade
+            .when_realized_raw
+            .as_deref()
+            .and_then(parse_optional_datetime);
+        Ok(trade)
+    }
+}
+
+#[cfg(feature = "serialize")]
+impl Serialize for Trade {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if raw_datetime_matches(
+            &self.holding_period_date_time_raw,
+            self.holding_period_date_time,
+        ) && raw_datetime_matches(&self.open_date_time_raw, self.open_date_time)
+            && raw_datetime_matches(&self.when_reopened_raw, self.when_reopened)
+            && raw_datetime_matches(&self.trade_time_raw, self.trade_time)
+            && raw_datetime_matches(&self.when_realized_raw, self.when_realized)
+        {
+            return Trade::serialize(self, serializer);
+        }
+        // Date/times set or changed after parsing are written from the typed
+        // value
+        let mut trade = self.clone();
+        sync_raw_datetime(
+            &mut trade.holding_period_date_time_raw,
+            trade.holding_period_date_time,
+        );
+        sync_raw_datetime(&mut trade.open_date_time_raw, trade.open_date_time);
+        sync_raw_datetime(&mut trade.when_reopened_raw, trade.when_reopened);
+        sync_raw_datetime(&mut trade.trade_time_raw, trade.trade_time);
+        sync_raw_datetime(&mut trade.when_realized_raw, trade.when_realized);
+        Trade::serialize(&trade, serializer)
+    }
+}
+
 impl Trade {
+    /// Parses the `notes` attribute into a [`TradeCodes`] set
+    ///
+    /// # Example
+    /// ```no_run
+    /// use ib_flex::parse_activity_flex;
+    ///
+    /// let xml = std::fs::read_to_string("activity.xml")?;
+    /// let statement = parse_activity_flex(&xml)?;
+    ///
+    /// let wash_sales = statement
+    ///     .trades
+    ///     .items
+    ///     .iter()
+    ///     .filter(|t| t.codes().is_wash_sale())
+    ///     .count();
+    /// println!("{} wash sale trades", wash_sales);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn codes(&self) -> TradeCodes {
+        TradeCodes::from_notes(self.notes.as_deref())
+    }
+
+    /// Mark-to-market P&L of this trade against the day's closing price
+    ///
+    /// Computed as `(closePrice - price) * quantity * multiplier`, which matches
+    /// IB's per-trade `mtmPnl` before commissions. Returns `None` if the close
+    /// price, trade price, or quantity is missing.
+    pub fn mtm_vs_close(&self) -> Option<Decimal> {
+        let close = self.close_price?;
+        let price = self.price?;
+        let quantity = self.quantity?;
+        Some((close - price) * quantity * self.multiplier_or_one())
+    }
+
+    /// Quantity signed by side: positive for buys, negative for sells
+    ///
+    /// IB normally reports sells with a negative `quantity` already; this
+    /// normalizes rows where it doesn't. Cancellations carry the opposite
+    /// sign of the side they cancel. Falls back to the raw quantity when the
+    /// side is missing or unrecognized.
+    pub fn signed_quantity(&self) -> Option<Decimal> {
+        let quantity = self.quantity?;
+        Some(match self.buy_sell {
+            Some(BuySell::Buy) | Some(BuySell::CancelSell) => quantity.abs(),
+            Some(BuySell::Sell) | Some(BuySell::CancelBuy) => -quantity.abs(),
+            _ => quantity,
+        })
+    }
+
+    /// Total cash cost of the trade in the trade currency, including commission
+    ///
+    /// Computed as `signed_quantity * price * multiplier - commission`, so it
+    /// is positive when cash leaves the account (buys) and negative when it
+    /// comes in (sells). IB reports commissions as negative amounts.
+    pub fn total_cost(&self) -> Option<Decimal> {
+        let notional = self.signed_quantity()? * self.price? * self.multiplier_or_one();
+        Some(notional - self.commission.unwrap_or_default())
+    }
+
+    /// Cash effect of the trade in the trade currency, after commission and taxes
+    ///
+    /// Uses IB's `netCash` when reported, otherwise `proceeds + commission +
+    /// taxes`. Positive for sells, negative for buys.
+    pub fn net_proceeds(&self) -> Option<Decimal> {
+        self.net_cash.or_else(|| {
+            Some(
+                self.proceeds?
+                    + self.commission.unwrap_or_default()
+                    + self.taxes.unwrap_or_default(),
+            )
+        })
+    }
+
+    /// Proceeds converted to the account's base currency using `fxRateToBase`
+    ///
+    /// A missing rate is treated as 1, which is what IB reports for trades
+    /// already in the base currency.
+    pub fn proceeds_in_base(&self) -> Option<Decimal> {
+        Some(self.proceeds? * self.fx_rate_to_base.unwrap_or(Decimal::ONE))
+    }
+
+    /// Commission converted to the account's base currency using `fxRateToBase`
+    ///
+    /// Returns `None` when the commission was charged in a different currency
+    /// than the trade, since `fxRateToBase` only applies to the trade currency.
+    pub fn commission_in_base(&self) -> Option<Decimal> {
+        let commission = self.commission?;
+        match self.commission_currency.as_deref() {
+            Some(currency) if !currency.is_empty() && currency != self.currency => None,
+            _ => Some(commission * self.fx_rate_to_base.unwrap_or(Decimal::ONE)),
+        }
+    }
+
+    fn multiplier_or_one(&self) -> Decimal {
+        self.multiplier.unwrap_or(Decimal::ONE)
+    }
+
+    /// Finds the original execution this trade corrects or cancels
+    ///
+    /// Matches `origTradeID` against the `tradeID` of the given trades.
+    /// Returns `None` if this trade has no original trade linkage or the
+    /// original is not among `trades` (e.g., it was booked in an earlier period).
+    pub fn original_trade<'a>(&self, trades: &'a [Trade]) -> Option<&'a Trade> {
+        let orig_id = self.orig_trade_id.as_deref().filter(|id| !id.is_empty())?;
+        trades
+            .iter()
+            .find(|t| t.trade_id.as_deref() == Some(orig_id))
+    }
+
     /// Constructs derivative information from flat fields based on asset category
     ///
     /// This method consolidates derivative-specific fields (strike, expiry, put_call,
@@ -1024,7 +1882,7 @@ impl Trade {
                 // For options, we need: strike, expiry, put_call, underlying_symbol
                 let strike = self.strike?;
                 let expiry = self.expiry?;
-                let put_call = self.put_call?;
+                let put_call = self.put_call.clone()?;
                 let underlying_symbol = self.underlying_symbol.clone()?;
 
                 Some(DerivativeInfo::Option {
@@ -1032,7 +1890,7 @@ impl Trade {
                     expiry,
                     put_call,
                     underlying_symbol,
-                    underlying_conid: self.underlying_conid.clone(),
+                    underlying_conid: self.underlying_conid,
                 })
             }
             AssetCategory::Future => {
@@ -1043,14 +1901,14 @@ impl Trade {
                 Some(DerivativeInfo::Future {
                     expiry,
                     underlying_symbol,
-                    underlying_conid: self.underlying_conid.clone(),
+                    underlying_conid: self.underlying_conid,
                 })
             }
             AssetCategory::FutureOption => {
                 // For future options, we need: strike, expiry, put_call, underlying_symbol
                 let strike = self.strike?;
                 let expiry = self.expiry?;
-                let put_call = self.put_call?;
+                let put_call = self.put_call.clone()?;
                 let underlying_symbol = self.underlying_symbol.clone()?;
 
                 Some(DerivativeInfo::FutureOption {
@@ -1058,7 +1916,7 @@ impl Trade {
                     expiry,
                     put_call,
                     underlying_symbol,
-                    underlying_conid: self.underlying_conid.clone(),
+                    underlying_conid: self.underlying_conid,
                 })
             }
             AssetCategory::Warrant => {
@@ -1115,7 +1973,11 @@ impl Trade {
 /// }
 /// # Ok::<(), Box<dyn std::error::Error>>(())
 /// ```
-#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[serde(remote = "Self")]
 pub struct Position {
     // ==================== CORE FIELDS ====================
     // Essential for tax reporting and portfolio analytics
@@ -1123,19 +1985,23 @@ pub struct Position {
     // --- Account ---
     /// IB account number
     #[serde(rename = "@accountId")]
-    pub account_id: String,
+    pub account_id: AccountId,
 
     // --- Security Identification ---
     /// IB contract ID
-    #[serde(rename = "@conid")]
-    pub conid: String,
+    #[serde(rename = "@conid", default)]
+    pub conid: Conid,
 
     /// Ticker symbol
     #[serde(rename = "@symbol")]
     pub symbol: String,
 
     /// Security description
-    #[serde(rename = "@description", default)]
+    #[serde(
+        rename = "@description",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub description: Option<String>,
 
     /// Asset category
@@ -1143,23 +2009,43 @@ pub struct Position {
     pub asset_category: AssetCategory,
 
     /// CUSIP
-    #[serde(rename = "@cusip", default)]
+    #[serde(
+        rename = "@cusip",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub cusip: Option<String>,
 
     /// ISIN
-    #[serde(rename = "@isin", default)]
+    #[serde(
+        rename = "@isin",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub isin: Option<String>,
 
     /// FIGI
-    #[serde(rename = "@figi", default)]
+    #[serde(
+        rename = "@figi",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub figi: Option<String>,
 
     /// Security ID
-    #[serde(rename = "@securityID", default)]
+    #[serde(
+        rename = "@securityID",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub security_id: Option<String>,
 
     /// Security ID type
-    #[serde(rename = "@securityIDType", default)]
+    #[serde(
+        rename = "@securityIDType",
+        default,
+        deserialize_with = "deserialize_optional_enum"
+    )]
     pub security_id_type: Option<SecurityIdType>,
 
     // --- Derivatives (Options/Futures) ---
@@ -1183,38 +2069,64 @@ pub struct Position {
     #[serde(
         rename = "@expiry",
         default,
-        deserialize_with = "deserialize_optional_date"
+        deserialize_with = "deserialize_optional_date",
+        serialize_with = "serialize_optional_date"
     )]
     pub expiry: Option<NaiveDate>,
 
     /// Put or Call
-    #[serde(rename = "@putCall", default)]
+    #[serde(
+        rename = "@putCall",
+        default,
+        deserialize_with = "deserialize_optional_enum"
+    )]
     pub put_call: Option<PutCall>,
 
     /// Underlying contract ID
-    #[serde(rename = "@underlyingConid", default)]
-    pub underlying_conid: Option<String>,
+    #[serde(
+        rename = "@underlyingConid",
+        default,
+        deserialize_with = "deserialize_optional_from_str"
+    )]
+    pub underlying_conid: Option<Conid>,
 
     /// Underlying symbol
-    #[serde(rename = "@underlyingSymbol", default)]
+    #[serde(
+        rename = "@underlyingSymbol",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub underlying_symbol: Option<String>,
 
     // --- Position and Value ---
     /// Position quantity (negative for short)
-    #[serde(rename = "@position")]
+    #[serde(
+        rename = "@position",
+        deserialize_with = "crate::parsers::xml_utils::deserialize_decimal"
+    )]
     pub quantity: Decimal,
 
     /// Mark price (current market price)
-    #[serde(rename = "@markPrice")]
+    #[serde(
+        rename = "@markPrice",
+        deserialize_with = "crate::parsers::xml_utils::deserialize_decimal"
+    )]
     pub mark_price: Decimal,
 
     /// Position value (quantity * mark_price * multiplier)
-    #[serde(rename = "@positionValue")]
+    #[serde(
+        rename = "@positionValue",
+        deserialize_with = "crate::parsers::xml_utils::deserialize_decimal"
+    )]
     pub position_value: Decimal,
 
     /// Side (Long/Short)
-    #[serde(rename = "@side", default)]
-    pub side: Option<String>,
+    #[serde(
+        rename = "@side",
+        default,
+        deserialize_with = "deserialize_optional_enum"
+    )]
+    pub side: Option<LongShort>,
 
     // --- Cost Basis and P&L ---
     /// Open price
@@ -1274,25 +2186,60 @@ pub struct Position {
     /// Date of this position snapshot
     #[serde(
         rename = "@reportDate",
-        deserialize_with = "crate::parsers::xml_utils::deserialize_flex_date"
+        deserialize_with = "crate::parsers::xml_utils::deserialize_flex_date",
+        serialize_with = "crate::parsers::xml_utils::serialize_flex_date"
     )]
     pub report_date: NaiveDate,
 
     // --- Tax Lot Tracking (Critical for tax reporting) ---
     /// Holding period date/time (for long-term vs short-term determination)
-    #[serde(rename = "@holdingPeriodDateTime", default)]
-    pub holding_period_date_time: Option<String>,
+    ///
+    /// `None` when `holdingPeriodDateTime` is empty or in a format the parser doesn't
+    /// recognize; the text as written is kept in
+    /// [`holding_period_date_time_raw`](Self::holding_period_date_time_raw).
+    #[serde(skip)]
+    pub holding_period_date_time: Option<NaiveDateTime>,
+
+    /// `holdingPeriodDateTime` as written in the XML
+    #[serde(
+        rename = "@holdingPeriodDateTime",
+        default,
+        deserialize_with = "deserialize_optional_string",
+        serialize_with = "serialize_optional_raw_datetime"
+    )]
+    pub holding_period_date_time_raw: Option<String>,
 
     /// When position was opened
-    #[serde(rename = "@openDateTime", default)]
-    pub open_date_time: Option<String>,
+    ///
+    /// `None` when `openDateTime` is empty or in a format the parser doesn't
+    /// recognize; the text as written is kept in
+    /// [`open_date_time_raw`](Self::open_date_time_raw).
+    #[serde(skip)]
+    pub open_date_time: Option<NaiveDateTime>,
+
+    /// `openDateTime` as written in the XML
+    #[serde(
+        rename = "@openDateTime",
+        default,
+        deserialize_with = "deserialize_optional_string",
+        serialize_with = "serialize_optional_raw_datetime"
+    )]
+    pub open_date_time_raw: Option<String>,
 
     /// Originating transaction ID
-    #[serde(rename = "@originatingTransactionID", default)]
+    #[serde(
+        rename = "@originatingTransactionID",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub originating_transaction_id: Option<String>,
 
     /// Position code (may contain tax-related codes)
-    #[serde(rename = "@code", default)]
+    #[serde(
+        rename = "@code",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub code: Option<String>,
 
     // ==================== EXTENDED FIELDS ====================
@@ -1300,39 +2247,68 @@ pub struct Position {
 
     // --- Extended IDs ---
     /// Originating order ID (links to opening trade)
-    #[serde(rename = "@originatingOrderID", default)]
-    pub originating_order_id: Option<String>,
+    #[serde(
+        rename = "@originatingOrderID",
+        default,
+        deserialize_with = "deserialize_optional_from_str"
+    )]
+    pub originating_order_id: Option<OrderId>,
 
     // --- Issuer/Security Metadata ---
     /// Issuer
-    #[serde(rename = "@issuer", default)]
+    #[serde(
+        rename = "@issuer",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub issuer: Option<String>,
 
     /// Issuer country code
-    #[serde(rename = "@issuerCountryCode", default)]
+    #[serde(
+        rename = "@issuerCountryCode",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub issuer_country_code: Option<String>,
 
     /// Sub-category
-    #[serde(rename = "@subCategory", default)]
+    #[serde(
+        rename = "@subCategory",
+        default,
+        deserialize_with = "deserialize_optional_enum"
+    )]
     pub sub_category: Option<SubCategory>,
 
     /// Listing exchange
-    #[serde(rename = "@listingExchange", default)]
+    #[serde(
+        rename = "@listingExchange",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub listing_exchange: Option<String>,
 
     // --- Underlying Extended ---
     /// Underlying listing exchange
-    #[serde(rename = "@underlyingListingExchange", default)]
+    #[serde(
+        rename = "@underlyingListingExchange",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub underlying_listing_exchange: Option<String>,
 
     /// Underlying security ID
-    #[serde(rename = "@underlyingSecurityID", default)]
+    #[serde(
+        rename = "@underlyingSecurityID",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub underlying_security_id: Option<String>,
 
     // --- Bond Fields ---
-    /// Accrued interest
+    /// Accrued interest (bonds)
     #[serde(
         rename = "@accruedInt",
+        alias = "@accruedInterest",
         default,
         deserialize_with = "deserialize_optional_decimal"
     )]
@@ -1348,15 +2324,27 @@ pub struct Position {
 
     // --- Commodity/Physical Delivery ---
     /// Serial number (for physical delivery)
-    #[serde(rename = "@serialNumber", default)]
+    #[serde(
+        rename = "@serialNumber",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub serial_number: Option<String>,
 
     /// Delivery type
-    #[serde(rename = "@deliveryType", default)]
+    #[serde(
+        rename = "@deliveryType",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub delivery_type: Option<String>,
 
     /// Commodity type
-    #[serde(rename = "@commodityType", default)]
+    #[serde(
+        rename = "@commodityType",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub commodity_type: Option<String>,
 
     /// Fineness (for precious metals)
@@ -1368,31 +2356,88 @@ pub struct Position {
     pub fineness: Option<Decimal>,
 
     /// Weight
-    #[serde(rename = "@weight", default)]
+    #[serde(
+        rename = "@weight",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub weight: Option<String>,
 
     // --- Other Metadata ---
     /// Level of detail
-    #[serde(rename = "@levelOfDetail", default)]
+    #[serde(
+        rename = "@levelOfDetail",
+        default,
+        deserialize_with = "deserialize_optional_enum"
+    )]
     pub level_of_detail: Option<LevelOfDetail>,
 
     /// Model (for model portfolios)
-    #[serde(rename = "@model", default)]
+    #[serde(
+        rename = "@model",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub model: Option<String>,
 
     /// Account alias
-    #[serde(rename = "@acctAlias", default)]
+    #[serde(
+        rename = "@acctAlias",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub acct_alias: Option<String>,
 
     /// Vesting date (for restricted stock)
     #[serde(
         rename = "@vestingDate",
         default,
-        deserialize_with = "deserialize_optional_date"
+        deserialize_with = "deserialize_optional_date",
+        serialize_with = "serialize_optional_date"
     )]
     pub vesting_date: Option<NaiveDate>,
 }
 
+// Derived with `remote = "Self"`, so these wrap the derived
+// `Position::deserialize`/`Position::serialize` to keep the typed date/times and
+// their raw text in step.
+impl<'de> Deserialize<'de> for Position {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut position = Position::deserialize(deserializer)?;
+        position.holding_period_date_time = position
+            .holding_period_date_time_raw
+            .as_deref()
+            .and_then(parse_optional_datetime);
+        position.open_date_time = position
+            .open_date_time_raw
+            .as_deref()
+            .and_then(parse_optional_datetime);
+        Ok(position)
+    }
+}
+
+#[cfg(feature = "serialize")]
+impl Serialize for Position {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if raw_datetime_matches(
+            &self.holding_period_date_time_raw,
+            self.holding_period_date_time,
+        ) && raw_datetime_matches(&self.open_date_time_raw, self.open_date_time)
+        {
+            return Position::serialize(self, serializer);
+        }
+        // Date/times set or changed after parsing are written from the typed
+        // value
+        let mut position = self.clone();
+        sync_raw_datetime(
+            &mut position.holding_period_date_time_raw,
+            position.holding_period_date_time,
+        );
+        sync_raw_datetime(&mut position.open_date_time_raw, position.open_date_time);
+        Position::serialize(&position, serializer)
+    }
+}
+
 impl Position {
     /// Constructs structured derivative info from flat fields
     ///
@@ -1406,8 +2451,8 @@ impl Position {
     /// # use rust_decimal::Decimal;
     /// # use chrono::NaiveDate;
     /// # let mut position = Position {
-    /// #     account_id: "U1234567".to_string(),
-    /// #     conid: "12345".to_string(),
+    /// #     account_id: "U1234567".into(),
+    /// #     conid: ib_flex::types::Conid(12345),
     /// #     symbol: "AAPL".to_string(),
     /// #     description: None,
     /// #     asset_category: AssetCategory::Option,
@@ -1420,12 +2465,12 @@ impl Position {
     /// #     strike: Some(Decimal::new(150, 0)),
     /// #     expiry: Some(NaiveDate::from_ymd_opt(2024, 12, 20).unwrap()),
     /// #     put_call: Some(PutCall::Call),
-    /// #     underlying_conid: Some("67890".to_string()),
+    /// #     underlying_conid: Some(ib_flex::types::Conid(67890)),
     /// #     underlying_symbol: Some("AAPL".to_string()),
     /// #     quantity: Decimal::new(10, 0),
     /// #     mark_price: Decimal::new(5, 0),
     /// #     position_value: Decimal::new(5000, 0),
-    /// #     side: Some("Long".to_string()),
+    /// #     side: Some(ib_flex::types::LongShort::Long),
     /// #     open_price: None,
     /// #     cost_basis_price: None,
     /// #     cost_basis_money: None,
@@ -1435,7 +2480,9 @@ impl Position {
     /// #     fx_rate_to_base: None,
     /// #     report_date: NaiveDate::from_ymd_opt(2024, 1, 15).unwrap(),
     /// #     holding_period_date_time: None,
+    /// #     holding_period_date_time_raw: None,
     /// #     open_date_time: None,
+    /// #     open_date_time_raw: None,
     /// #     originating_transaction_id: None,
     /// #     code: None,
     /// #     originating_order_id: None,
@@ -1472,7 +2519,7 @@ impl Position {
                 // For options, we need: strike, expiry, put_call, underlying_symbol
                 let strike = self.strike?;
                 let expiry = self.expiry?;
-                let put_call = self.put_call?;
+                let put_call = self.put_call.clone()?;
                 let underlying_symbol = self.underlying_symbol.clone()?;
 
                 Some(DerivativeInfo::Option {
@@ -1480,7 +2527,7 @@ impl Position {
                     expiry,
                     put_call,
                     underlying_symbol,
-                    underlying_conid: self.underlying_conid.clone(),
+                    underlying_conid: self.underlying_conid,
                 })
             }
             AssetCategory::Future => {
@@ -1491,14 +2538,14 @@ impl Position {
                 Some(DerivativeInfo::Future {
                     expiry,
                     underlying_symbol,
-                    underlying_conid: self.underlying_conid.clone(),
+                    underlying_conid: self.underlying_conid,
                 })
             }
             AssetCategory::FutureOption => {
                 // For future options, we need: strike, expiry, put_call, underlying_symbol
                 let strike = self.strike?;
                 let expiry = self.expiry?;
-                let put_call = self.put_call?;
+                let put_call = self.put_call.clone()?;
                 let underlying_symbol = self.underlying_symbol.clone()?;
 
                 Some(DerivativeInfo::FutureOption {
@@ -1506,7 +2553,7 @@ impl Position {
                     expiry,
                     put_call,
                     underlying_symbol,
-                    underlying_conid: self.underlying_conid.clone(),
+                    underlying_conid: self.underlying_conid,
                 })
             }
             AssetCategory::Warrant => {
@@ -1523,6 +2570,133 @@ impl Position {
             _ => None,
         }
     }
+
+    /// Quantity signed according to `side`
+    ///
+    /// IB normally reports short positions with a negative quantity already;
+    /// this normalizes rows where only `side` carries the direction. Falls back
+    /// to the raw quantity when `side` is absent or unrecognized.
+    pub fn signed_quantity(&self) -> Decimal {
+        match self.side {
+            Some(LongShort::Long) => self.quantity.abs(),
+            Some(LongShort::Short) => -self.quantity.abs(),
+            _ => self.quantity,
+        }
+    }
+
+    /// Returns false if `side` contradicts the sign of `quantity`
+    ///
+    /// Flat positions and rows without a recognized side are always consistent.
+    pub fn side_matches_quantity(&self) -> bool {
+        match self.side {
+            Some(LongShort::Long) => self.quantity >= Decimal::ZERO,
+            Some(LongShort::Short) => self.quantity <= Decimal::ZERO,
+            _ => true,
+        }
+    }
+
+    /// Position value including accrued interest
+    ///
+    /// For bonds this is the "dirty" value a buyer would pay; for positions
+    /// without accrued interest it equals `position_value`.
+    pub fn value_with_accrued(&self) -> Decimal {
+        self.position_value + self.accrued_int.unwrap_or_default()
+    }
+
+    /// Market value in the position currency
+    ///
+    /// Uses IB's `positionValue`, which already applies the multiplier. If
+    /// IB reported zero for a non-flat position, derives it as
+    /// `signed_quantity * mark_price * multiplier` instead.
+    pub fn market_value(&self) -> Decimal {
+        if self.position_value.is_zero() && !self.quantity.is_zero() {
+            self.signed_quantity() * self.mark_price * self.multiplier_or_one()
+        } else {
+            self.position_value
+        }
+    }
+
+    /// Market value converted to the base currency using `fxRateToBase`
+    ///
+    /// A missing rate is treated as 1 (base currency position).
+    pub fn market_value_in_base(&self) -> Decimal {
+        self.market_value() * self.fx_rate_or_one()
+    }
+
+    /// Total cost basis in the position currency
+    ///
+    /// Uses `costBasisMoney` when reported, otherwise
+    /// `cost_basis_price * signed_quantity * multiplier`.
+    pub fn cost_basis_total(&self) -> Option<Decimal> {
+        self.cost_basis_money.or_else(|| {
+            Some(self.cost_basis_price? * self.signed_quantity() * self.multiplier_or_one())
+        })
+    }
+
+    /// Total cost basis converted to the base currency using `fxRateToBase`
+    pub fn cost_basis_in_base(&self) -> Option<Decimal> {
+        Some(self.cost_basis_total()? * self.fx_rate_or_one())
+    }
+
+    /// Unrealized P&L in the position currency
+    ///
+    /// Uses IB's `fifoPnlUnrealized` when reported, otherwise market value
+    /// minus cost basis. This sign convention holds for shorts as well, since
+    /// both values are negative for short positions.
+    pub fn unrealized_pnl(&self) -> Option<Decimal> {
+        self.fifo_pnl_unrealized
+            .or_else(|| Some(self.market_value() - self.cost_basis_total()?))
+    }
+
+    /// Unrealized P&L as a percentage of the absolute cost basis
+    ///
+    /// Returns `None` when the cost basis is unknown or zero.
+    pub fn unrealized_pnl_pct(&self) -> Option<Decimal> {
+        let basis = self.cost_basis_total()?.abs();
+        if basis.is_zero() {
+            return None;
+        }
+        Some(self.unrealized_pnl()? / basis * Decimal::ONE_HUNDRED)
+    }
+
+    fn multiplier_or_one(&self) -> Decimal {
+        self.multiplier.unwrap_or(Decimal::ONE)
+    }
+
+    fn fx_rate_or_one(&self) -> Decimal {
+        self.fx_rate_to_base.unwrap_or(Decimal::ONE)
+    }
+
+    /// Returns true if this position has no vesting date or has vested by `as_of`
+    pub fn is_vested(&self, as_of: NaiveDate) -> bool {
+        self.vesting_date.map_or(true, |vesting| vesting <= as_of)
+    }
+
+    /// Finds the trades that opened this position lot
+    ///
+    /// Matches `originatingTransactionID` against trade transaction IDs first;
+    /// if that yields nothing, falls back to trades on the same contract with
+    /// a matching `originatingOrderID`. Returns an empty vector if the position
+    /// carries neither ID or the opening trades aren't in `trades`.
+    pub fn originating_trades<'a>(&self, trades: &'a [Trade]) -> Vec<&'a Trade> {
+        if let Some(txn_id) = self.originating_transaction_id.as_deref() {
+            let matched: Vec<_> = trades
+                .iter()
+                .filter(|t| t.transaction_id.as_deref() == Some(txn_id))
+                .collect();
+            if !matched.is_empty() {
+                return matched;
+            }
+        }
+
+        match self.originating_order_id.as_deref() {
+            Some(order_id) => trades
+                .iter()
+                .filter(|t| t.conid == self.conid && t.ib_order_id.as_deref() == Some(order_id))
+                .collect(),
+            None => Vec::new(),
+        }
+    }
 }
 
 /// A cash transaction (deposit, withdrawal, dividend, interest, fee)
@@ -1534,6 +2708,7 @@ impl Position {
 /// # Example
 /// ```no_run
 /// use ib_flex::parse_activity_flex;
+/// use ib_flex::types::CashTransactionType;
 /// use rust_decimal::Decimal;
 ///
 /// let xml = std::fs::read_to_string("activity.xml")?;
@@ -1545,18 +2720,18 @@ impl Position {
 /// let mut fees = Decimal::ZERO;
 ///
 /// for cash_txn in &statement.cash_transactions.items {
-///     match cash_txn.transaction_type.as_deref() {
-///         Some("Dividends") => {
+///     match &cash_txn.transaction_type {
+///         Some(CashTransactionType::Dividends) => {
 ///             dividends += cash_txn.amount;
 ///             println!("Dividend from {}: {}",
 ///                 cash_txn.symbol.as_ref().unwrap_or(&"N/A".to_string()),
 ///                 cash_txn.amount
 ///             );
 ///         }
-///         Some("Broker Interest Paid") | Some("Broker Interest Received") => {
+///         Some(t) if t.is_interest() => {
 ///             interest += cash_txn.amount;
 ///         }
-///         Some("Other Fees") | Some("Commission Adjustments") => {
+///         Some(CashTransactionType::OtherFees | CashTransactionType::CommissionAdjustments) => {
 ///             fees += cash_txn.amount;
 ///         }
 ///         _ => {
@@ -1571,7 +2746,10 @@ impl Position {
 /// println!("  Fees: {}", fees);
 /// # Ok::<(), Box<dyn std::error::Error>>(())
 /// ```
-#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct CashTransaction {
     // ==================== CORE FIELDS ====================
     // Essential for tax reporting and portfolio analytics
@@ -1579,23 +2757,38 @@ pub struct CashTransaction {
     // --- Account ---
     /// IB account number
     #[serde(rename = "@accountId")]
-    pub account_id: String,
+    pub account_id: AccountId,
 
     /// IB transaction ID
-    #[serde(rename = "@transactionID", default)]
+    #[serde(
+        rename = "@transactionID",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub transaction_id: Option<String>,
 
     // --- Transaction Details ---
     /// Transaction type (Deposits, Dividends, WithholdingTax, BrokerInterest, etc.)
-    #[serde(rename = "@type", default)]
-    pub transaction_type: Option<String>,
+    #[serde(
+        rename = "@type",
+        default,
+        deserialize_with = "deserialize_optional_from_str"
+    )]
+    pub transaction_type: Option<CashTransactionType>,
 
     /// Description of transaction
-    #[serde(rename = "@description", default)]
+    #[serde(
+        rename = "@description",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub description: Option<String>,
 
     /// Amount (positive for credits, negative for debits)
-    #[serde(rename = "@amount")]
+    #[serde(
+        rename = "@amount",
+        deserialize_with = "crate::parsers::xml_utils::deserialize_decimal"
+    )]
     pub amount: Decimal,
 
     /// Currency
@@ -1615,7 +2808,8 @@ pub struct CashTransaction {
     #[serde(
         rename = "@date",
         default,
-        deserialize_with = "deserialize_optional_date"
+        deserialize_with = "deserialize_optional_date",
+        serialize_with = "serialize_optional_date"
     )]
     pub date: Option<NaiveDate>,
 
@@ -1623,7 +2817,8 @@ pub struct CashTransaction {
     #[serde(
         rename = "@settleDate",
         default,
-        deserialize_with = "deserialize_optional_date"
+        deserialize_with = "deserialize_optional_date",
+        serialize_with = "serialize_optional_date"
     )]
     pub settle_date: Option<NaiveDate>,
 
@@ -1631,41 +2826,74 @@ pub struct CashTransaction {
     #[serde(
         rename = "@exDate",
         default,
-        deserialize_with = "deserialize_optional_date"
+        deserialize_with = "deserialize_optional_date",
+        serialize_with = "serialize_optional_date"
     )]
     pub ex_date: Option<NaiveDate>,
 
     // --- Security Identification ---
     /// Related security's contract ID (for dividends)
-    #[serde(rename = "@conid", default)]
+    #[serde(
+        rename = "@conid",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub conid: Option<String>,
 
     /// Related security's symbol
-    #[serde(rename = "@symbol", default)]
+    #[serde(
+        rename = "@symbol",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub symbol: Option<String>,
 
     /// Asset category
-    #[serde(rename = "@assetCategory", default)]
+    #[serde(
+        rename = "@assetCategory",
+        default,
+        deserialize_with = "deserialize_optional_enum"
+    )]
     pub asset_category: Option<AssetCategory>,
 
     /// CUSIP
-    #[serde(rename = "@cusip", default)]
+    #[serde(
+        rename = "@cusip",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub cusip: Option<String>,
 
     /// ISIN
-    #[serde(rename = "@isin", default)]
+    #[serde(
+        rename = "@isin",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub isin: Option<String>,
 
     /// FIGI
-    #[serde(rename = "@figi", default)]
+    #[serde(
+        rename = "@figi",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub figi: Option<String>,
 
     /// Security ID
-    #[serde(rename = "@securityID", default)]
+    #[serde(
+        rename = "@securityID",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub security_id: Option<String>,
 
     /// Security ID type
-    #[serde(rename = "@securityIDType", default)]
+    #[serde(
+        rename = "@securityIDType",
+        default,
+        deserialize_with = "deserialize_optional_enum"
+    )]
     pub security_id_type: Option<SecurityIdType>,
 
     // --- Derivatives ---
@@ -1689,24 +2917,41 @@ pub struct CashTransaction {
     #[serde(
         rename = "@expiry",
         default,
-        deserialize_with = "deserialize_optional_date"
+        deserialize_with = "deserialize_optional_date",
+        serialize_with = "serialize_optional_date"
     )]
     pub expiry: Option<NaiveDate>,
 
     /// Put or Call
-    #[serde(rename = "@putCall", default)]
+    #[serde(
+        rename = "@putCall",
+        default,
+        deserialize_with = "deserialize_optional_enum"
+    )]
     pub put_call: Option<PutCall>,
 
     /// Underlying contract ID
-    #[serde(rename = "@underlyingConid", default)]
+    #[serde(
+        rename = "@underlyingConid",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub underlying_conid: Option<String>,
 
     /// Underlying symbol
-    #[serde(rename = "@underlyingSymbol", default)]
+    #[serde(
+        rename = "@underlyingSymbol",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub underlying_symbol: Option<String>,
 
     /// Transaction code (tax-relevant codes)
-    #[serde(rename = "@code", default)]
+    #[serde(
+        rename = "@code",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub code: Option<String>,
 
     // ==================== EXTENDED FIELDS ====================
@@ -1714,14 +2959,19 @@ pub struct CashTransaction {
 
     // --- Timestamps ---
     /// Transaction datetime
-    #[serde(rename = "@dateTime", default)]
+    #[serde(
+        rename = "@dateTime",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub date_time: Option<String>,
 
     /// Report date
     #[serde(
         rename = "@reportDate",
         default,
-        deserialize_with = "deserialize_optional_date"
+        deserialize_with = "deserialize_optional_date",
+        serialize_with = "serialize_optional_date"
     )]
     pub report_date: Option<NaiveDate>,
 
@@ -1729,47 +2979,84 @@ pub struct CashTransaction {
     #[serde(
         rename = "@availableForTradingDate",
         default,
-        deserialize_with = "deserialize_optional_date"
+        deserialize_with = "deserialize_optional_date",
+        serialize_with = "serialize_optional_date"
     )]
     pub available_for_trading_date: Option<NaiveDate>,
 
     // --- Extended IDs ---
     /// Action ID
-    #[serde(rename = "@actionID", default)]
+    #[serde(
+        rename = "@actionID",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub action_id: Option<String>,
 
     /// Trade ID (for dividend/interest related to specific trade)
-    #[serde(rename = "@tradeID", default)]
-    pub trade_id: Option<String>,
+    #[serde(
+        rename = "@tradeID",
+        default,
+        deserialize_with = "deserialize_optional_from_str"
+    )]
+    pub trade_id: Option<TradeId>,
 
     /// Client reference
-    #[serde(rename = "@clientReference", default)]
+    #[serde(
+        rename = "@clientReference",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub client_reference: Option<String>,
 
     // --- Issuer/Security Metadata ---
     /// Issuer
-    #[serde(rename = "@issuer", default)]
+    #[serde(
+        rename = "@issuer",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub issuer: Option<String>,
 
     /// Issuer country code
-    #[serde(rename = "@issuerCountryCode", default)]
+    #[serde(
+        rename = "@issuerCountryCode",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub issuer_country_code: Option<String>,
 
     /// Sub-category
-    #[serde(rename = "@subCategory", default)]
+    #[serde(
+        rename = "@subCategory",
+        default,
+        deserialize_with = "deserialize_optional_enum"
+    )]
     pub sub_category: Option<SubCategory>,
 
     /// Listing exchange
-    #[serde(rename = "@listingExchange", default)]
+    #[serde(
+        rename = "@listingExchange",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub listing_exchange: Option<String>,
 
     // --- Underlying Extended ---
     /// Underlying listing exchange
-    #[serde(rename = "@underlyingListingExchange", default)]
+    #[serde(
+        rename = "@underlyingListingExchange",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub underlying_listing_exchange: Option<String>,
 
     /// Underlying security ID
-    #[serde(rename = "@underlyingSecurityID", default)]
+    #[serde(
+        rename = "@underlyingSecurityID",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub underlying_security_id: Option<String>,
 
     // --- Bond Fields ---
@@ -1783,15 +3070,27 @@ pub struct CashTransaction {
 
     // --- Commodity/Physical Delivery ---
     /// Serial number
-    #[serde(rename = "@serialNumber", default)]
+    #[serde(
+        rename = "@serialNumber",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub serial_number: Option<String>,
 
     /// Delivery type
-    #[serde(rename = "@deliveryType", default)]
+    #[serde(
+        rename = "@deliveryType",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub delivery_type: Option<String>,
 
     /// Commodity type
-    #[serde(rename = "@commodityType", default)]
+    #[serde(
+        rename = "@commodityType",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub commodity_type: Option<String>,
 
     /// Fineness
@@ -1802,22 +3101,137 @@ pub struct CashTransaction {
     )]
     pub fineness: Option<Decimal>,
 
-    /// Weight
-    #[serde(rename = "@weight", default)]
-    pub weight: Option<String>,
+    /// Weight
+    #[serde(
+        rename = "@weight",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
+    pub weight: Option<String>,
+
+    // --- Other Metadata ---
+    /// Level of detail
+    #[serde(
+        rename = "@levelOfDetail",
+        default,
+        deserialize_with = "deserialize_optional_enum"
+    )]
+    pub level_of_detail: Option<LevelOfDetail>,
+
+    /// Model
+    #[serde(
+        rename = "@model",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
+    pub model: Option<String>,
+
+    /// Account alias
+    #[serde(
+        rename = "@acctAlias",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
+    pub acct_alias: Option<String>,
+}
+
+impl CashTransaction {
+    /// Parses the `code` attribute into a [`TradeCodes`] set
+    pub fn codes(&self) -> TradeCodes {
+        TradeCodes::from_notes(self.code.as_deref())
+    }
+
+    fn type_matches(&self, predicate: impl Fn(&CashTransactionType) -> bool) -> bool {
+        self.transaction_type.as_ref().is_some_and(predicate)
+    }
+
+    /// Returns true for dividends, including payments in lieu
+    pub fn is_dividend(&self) -> bool {
+        self.type_matches(CashTransactionType::is_dividend)
+    }
+
+    /// Returns true for tax withheld at source, including 871(m) withholding
+    pub fn is_withholding_tax(&self) -> bool {
+        self.type_matches(CashTransactionType::is_withholding)
+    }
+
+    /// Returns true for broker or bond interest, paid or received
+    pub fn is_interest(&self) -> bool {
+        self.type_matches(CashTransactionType::is_interest)
+    }
+
+    /// Returns true for fees, advisor fees, and commission adjustments
+    pub fn is_fee(&self) -> bool {
+        self.type_matches(CashTransactionType::is_fee)
+    }
+
+    /// Returns true for deposits, withdrawals, and cash receipts
+    pub fn is_deposit_or_withdrawal(&self) -> bool {
+        self.type_matches(CashTransactionType::is_deposit_or_withdrawal)
+    }
+}
+
+/// Sort key for IB transaction IDs
+///
+/// IDs are numeric strings of varying length, so compare them as numbers
+/// where possible instead of lexically.
+fn transaction_id_key(id: Option<&str>) -> (Option<u64>, Option<&str>) {
+    (id.and_then(|id| id.trim().parse().ok()), id)
+}
 
-    // --- Other Metadata ---
-    /// Level of detail
-    #[serde(rename = "@levelOfDetail", default)]
-    pub level_of_detail: Option<String>,
+impl Trade {
+    /// Trade date, execution time, and transaction ID, the [`Ord`] key
+    pub(crate) fn chronological_key(&self) -> impl Ord + '_ {
+        (
+            self.date(),
+            self.trade_time,
+            transaction_id_key(self.transaction_id.as_deref()),
+        )
+    }
+}
 
-    /// Model
-    #[serde(rename = "@model", default)]
-    pub model: Option<String>,
+/// Orders chronologically by trade date, then execution time, then
+/// transaction ID
+///
+/// IB transaction IDs are unique, so distinct parsed trades never compare
+/// equal; hand-built records sharing a date and ID compare equal here even
+/// if other fields differ.
+#[cfg(feature = "eq")]
+impl Ord for Trade {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.chronological_key().cmp(&other.chronological_key())
+    }
+}
 
-    /// Account alias
-    #[serde(rename = "@acctAlias", default)]
-    pub acct_alias: Option<String>,
+#[cfg(feature = "eq")]
+impl PartialOrd for Trade {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Orders chronologically by date, then transaction ID
+///
+/// See the [`Trade`] ordering for the caveat on records sharing an ID.
+#[cfg(feature = "eq")]
+impl Ord for CashTransaction {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        (
+            self.date(),
+            transaction_id_key(self.transaction_id.as_deref()),
+        )
+            .cmp(&(
+                other.date(),
+                transaction_id_key(other.transaction_id.as_deref()),
+            ))
+    }
+}
+
+#[cfg(feature = "eq")]
+impl PartialOrd for CashTransaction {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
 }
 
 /// A corporate action (split, merger, spinoff, etc.)
@@ -1863,7 +3277,10 @@ pub struct CashTransaction {
 /// }
 /// # Ok::<(), Box<dyn std::error::Error>>(())
 /// ```
-#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct CorporateAction {
     // ==================== CORE FIELDS ====================
     // Essential for tax reporting and portfolio analytics
@@ -1871,19 +3288,31 @@ pub struct CorporateAction {
     // --- Account ---
     /// IB account number
     #[serde(rename = "@accountId")]
-    pub account_id: String,
+    pub account_id: AccountId,
 
     /// IB transaction ID
-    #[serde(rename = "@transactionID", default)]
+    #[serde(
+        rename = "@transactionID",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub transaction_id: Option<String>,
 
     // --- Action Details ---
     /// Action type (Split, Merger, Spinoff, etc.)
-    #[serde(rename = "@type", default)]
+    #[serde(
+        rename = "@type",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub action_type: Option<String>,
 
     /// Description of corporate action
-    #[serde(rename = "@description", default)]
+    #[serde(
+        rename = "@description",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub description: Option<String>,
 
     // --- Dates (Tax-critical) ---
@@ -1891,14 +3320,16 @@ pub struct CorporateAction {
     #[serde(
         rename = "@date",
         default,
-        deserialize_with = "deserialize_optional_date"
+        deserialize_with = "deserialize_optional_date",
+        serialize_with = "serialize_optional_date"
     )]
     pub action_date: Option<NaiveDate>,
 
     /// Report date
     #[serde(
         rename = "@reportDate",
-        deserialize_with = "crate::parsers::xml_utils::deserialize_flex_date"
+        deserialize_with = "crate::parsers::xml_utils::deserialize_flex_date",
+        serialize_with = "crate::parsers::xml_utils::serialize_flex_date"
     )]
     pub report_date: NaiveDate,
 
@@ -1906,7 +3337,8 @@ pub struct CorporateAction {
     #[serde(
         rename = "@exDate",
         default,
-        deserialize_with = "deserialize_optional_date"
+        deserialize_with = "deserialize_optional_date",
+        serialize_with = "serialize_optional_date"
     )]
     pub ex_date: Option<NaiveDate>,
 
@@ -1914,7 +3346,8 @@ pub struct CorporateAction {
     #[serde(
         rename = "@payDate",
         default,
-        deserialize_with = "deserialize_optional_date"
+        deserialize_with = "deserialize_optional_date",
+        serialize_with = "serialize_optional_date"
     )]
     pub pay_date: Option<NaiveDate>,
 
@@ -1922,41 +3355,66 @@ pub struct CorporateAction {
     #[serde(
         rename = "@recordDate",
         default,
-        deserialize_with = "deserialize_optional_date"
+        deserialize_with = "deserialize_optional_date",
+        serialize_with = "serialize_optional_date"
     )]
     pub record_date: Option<NaiveDate>,
 
     // --- Security Identification ---
     /// IB contract ID
-    #[serde(rename = "@conid")]
-    pub conid: String,
+    #[serde(rename = "@conid", default)]
+    pub conid: Conid,
 
     /// Ticker symbol
     #[serde(rename = "@symbol")]
     pub symbol: String,
 
     /// Asset category
-    #[serde(rename = "@assetCategory", default)]
+    #[serde(
+        rename = "@assetCategory",
+        default,
+        deserialize_with = "deserialize_optional_enum"
+    )]
     pub asset_category: Option<AssetCategory>,
 
     /// CUSIP
-    #[serde(rename = "@cusip", default)]
+    #[serde(
+        rename = "@cusip",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub cusip: Option<String>,
 
     /// ISIN
-    #[serde(rename = "@isin", default)]
+    #[serde(
+        rename = "@isin",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub isin: Option<String>,
 
     /// FIGI
-    #[serde(rename = "@figi", default)]
+    #[serde(
+        rename = "@figi",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub figi: Option<String>,
 
     /// Security ID
-    #[serde(rename = "@securityID", default)]
+    #[serde(
+        rename = "@securityID",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub security_id: Option<String>,
 
     /// Security ID type
-    #[serde(rename = "@securityIDType", default)]
+    #[serde(
+        rename = "@securityIDType",
+        default,
+        deserialize_with = "deserialize_optional_enum"
+    )]
     pub security_id_type: Option<SecurityIdType>,
 
     // --- Derivatives ---
@@ -1980,20 +3438,33 @@ pub struct CorporateAction {
     #[serde(
         rename = "@expiry",
         default,
-        deserialize_with = "deserialize_optional_date"
+        deserialize_with = "deserialize_optional_date",
+        serialize_with = "serialize_optional_date"
     )]
     pub expiry: Option<NaiveDate>,
 
     /// Put or Call
-    #[serde(rename = "@putCall", default)]
+    #[serde(
+        rename = "@putCall",
+        default,
+        deserialize_with = "deserialize_optional_enum"
+    )]
     pub put_call: Option<PutCall>,
 
     /// Underlying contract ID
-    #[serde(rename = "@underlyingConid", default)]
-    pub underlying_conid: Option<String>,
+    #[serde(
+        rename = "@underlyingConid",
+        default,
+        deserialize_with = "deserialize_optional_from_str"
+    )]
+    pub underlying_conid: Option<Conid>,
 
     /// Underlying symbol
-    #[serde(rename = "@underlyingSymbol", default)]
+    #[serde(
+        rename = "@underlyingSymbol",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub underlying_symbol: Option<String>,
 
     // --- Quantities and Values ---
@@ -2056,7 +3527,11 @@ pub struct CorporateAction {
 
     // --- Currency ---
     /// Currency
-    #[serde(rename = "@currency", default)]
+    #[serde(
+        rename = "@currency",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub currency: Option<String>,
 
     /// FX rate to base
@@ -2068,7 +3543,11 @@ pub struct CorporateAction {
     pub fx_rate_to_base: Option<Decimal>,
 
     /// Code (may contain tax-relevant info)
-    #[serde(rename = "@code", default)]
+    #[serde(
+        rename = "@code",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub code: Option<String>,
 
     // ==================== EXTENDED FIELDS ====================
@@ -2076,38 +3555,70 @@ pub struct CorporateAction {
 
     // --- Extended IDs ---
     /// Action ID
-    #[serde(rename = "@actionID", default)]
+    #[serde(
+        rename = "@actionID",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub action_id: Option<String>,
 
     // --- Timestamps ---
     /// Action datetime
-    #[serde(rename = "@dateTime", default)]
+    #[serde(
+        rename = "@dateTime",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub date_time: Option<String>,
 
     // --- Issuer/Security Metadata ---
     /// Issuer
-    #[serde(rename = "@issuer", default)]
+    #[serde(
+        rename = "@issuer",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub issuer: Option<String>,
 
     /// Issuer country code
-    #[serde(rename = "@issuerCountryCode", default)]
+    #[serde(
+        rename = "@issuerCountryCode",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub issuer_country_code: Option<String>,
 
     /// Sub-category
-    #[serde(rename = "@subCategory", default)]
+    #[serde(
+        rename = "@subCategory",
+        default,
+        deserialize_with = "deserialize_optional_enum"
+    )]
     pub sub_category: Option<SubCategory>,
 
     /// Listing exchange
-    #[serde(rename = "@listingExchange", default)]
+    #[serde(
+        rename = "@listingExchange",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub listing_exchange: Option<String>,
 
     // --- Underlying Extended ---
     /// Underlying listing exchange
-    #[serde(rename = "@underlyingListingExchange", default)]
+    #[serde(
+        rename = "@underlyingListingExchange",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub underlying_listing_exchange: Option<String>,
 
     /// Underlying security ID
-    #[serde(rename = "@underlyingSecurityID", default)]
+    #[serde(
+        rename = "@underlyingSecurityID",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub underlying_security_id: Option<String>,
 
     // --- Bond Fields ---
@@ -2129,15 +3640,27 @@ pub struct CorporateAction {
 
     // --- Commodity/Physical Delivery ---
     /// Serial number
-    #[serde(rename = "@serialNumber", default)]
+    #[serde(
+        rename = "@serialNumber",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub serial_number: Option<String>,
 
     /// Delivery type
-    #[serde(rename = "@deliveryType", default)]
+    #[serde(
+        rename = "@deliveryType",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub delivery_type: Option<String>,
 
     /// Commodity type
-    #[serde(rename = "@commodityType", default)]
+    #[serde(
+        rename = "@commodityType",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub commodity_type: Option<String>,
 
     /// Fineness (for precious metals)
@@ -2149,20 +3672,36 @@ pub struct CorporateAction {
     pub fineness: Option<Decimal>,
 
     /// Weight
-    #[serde(rename = "@weight", default)]
+    #[serde(
+        rename = "@weight",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub weight: Option<String>,
 
     // --- Other Metadata ---
     /// Level of detail
-    #[serde(rename = "@levelOfDetail", default)]
+    #[serde(
+        rename = "@levelOfDetail",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub level_of_detail: Option<String>,
 
     /// Model (for model portfolios)
-    #[serde(rename = "@model", default)]
+    #[serde(
+        rename = "@model",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub model: Option<String>,
 
     /// Account alias
-    #[serde(rename = "@acctAlias", default)]
+    #[serde(
+        rename = "@acctAlias",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub acct_alias: Option<String>,
 }
 
@@ -2207,7 +3746,10 @@ pub struct CorporateAction {
 /// }
 /// # Ok::<(), Box<dyn std::error::Error>>(())
 /// ```
-#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct SecurityInfo {
     // ==================== CORE FIELDS ====================
     // Essential for tax reporting and portfolio analytics
@@ -2222,35 +3764,63 @@ pub struct SecurityInfo {
     pub symbol: String,
 
     /// Security description
-    #[serde(rename = "@description", default)]
+    #[serde(
+        rename = "@description",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub description: Option<String>,
 
     /// IB contract ID
-    #[serde(rename = "@conid")]
-    pub conid: String,
+    #[serde(rename = "@conid", default)]
+    pub conid: Conid,
 
     /// Security ID
-    #[serde(rename = "@securityID", default)]
+    #[serde(
+        rename = "@securityID",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub security_id: Option<String>,
 
     /// Security ID type
-    #[serde(rename = "@securityIDType", default)]
+    #[serde(
+        rename = "@securityIDType",
+        default,
+        deserialize_with = "deserialize_optional_enum"
+    )]
     pub security_id_type: Option<SecurityIdType>,
 
     /// CUSIP
-    #[serde(rename = "@cusip", default)]
+    #[serde(
+        rename = "@cusip",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub cusip: Option<String>,
 
     /// ISIN
-    #[serde(rename = "@isin", default)]
+    #[serde(
+        rename = "@isin",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub isin: Option<String>,
 
     /// FIGI
-    #[serde(rename = "@figi", default)]
+    #[serde(
+        rename = "@figi",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub figi: Option<String>,
 
     /// SEDOL
-    #[serde(rename = "@sedol", default)]
+    #[serde(
+        rename = "@sedol",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub sedol: Option<String>,
 
     // --- Derivatives (Options/Futures) ---
@@ -2274,20 +3844,33 @@ pub struct SecurityInfo {
     #[serde(
         rename = "@expiry",
         default,
-        deserialize_with = "deserialize_optional_date"
+        deserialize_with = "deserialize_optional_date",
+        serialize_with = "serialize_optional_date"
     )]
     pub expiry: Option<NaiveDate>,
 
     /// Put or Call
-    #[serde(rename = "@putCall", default)]
+    #[serde(
+        rename = "@putCall",
+        default,
+        deserialize_with = "deserialize_optional_enum"
+    )]
     pub put_call: Option<PutCall>,
 
     /// Underlying contract ID
-    #[serde(rename = "@underlyingConid", default)]
-    pub underlying_conid: Option<String>,
+    #[serde(
+        rename = "@underlyingConid",
+        default,
+        deserialize_with = "deserialize_optional_from_str"
+    )]
+    pub underlying_conid: Option<Conid>,
 
     /// Underlying symbol
-    #[serde(rename = "@underlyingSymbol", default)]
+    #[serde(
+        rename = "@underlyingSymbol",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub underlying_symbol: Option<String>,
 
     // --- Bond/Fixed Income ---
@@ -2295,7 +3878,8 @@ pub struct SecurityInfo {
     #[serde(
         rename = "@maturity",
         default,
-        deserialize_with = "deserialize_optional_date"
+        deserialize_with = "deserialize_optional_date",
+        serialize_with = "serialize_optional_date"
     )]
     pub maturity: Option<NaiveDate>,
 
@@ -2307,9 +3891,38 @@ pub struct SecurityInfo {
     )]
     pub principal_adjust_factor: Option<Decimal>,
 
+    /// Coupon rate in percent (for bonds)
+    #[serde(
+        rename = "@couponRate",
+        default,
+        deserialize_with = "deserialize_optional_decimal"
+    )]
+    pub coupon_rate: Option<Decimal>,
+
+    /// Issue date (for bonds)
+    #[serde(
+        rename = "@issueDate",
+        default,
+        deserialize_with = "deserialize_optional_date",
+        serialize_with = "serialize_optional_date"
+    )]
+    pub issue_date: Option<NaiveDate>,
+
+    /// Settlement policy method
+    #[serde(
+        rename = "@settlementPolicyMethod",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
+    pub settlement_policy_method: Option<String>,
+
     // --- Currency ---
     /// Currency
-    #[serde(rename = "@currency", default)]
+    #[serde(
+        rename = "@currency",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub currency: Option<String>,
 
     // ==================== EXTENDED FIELDS ====================
@@ -2317,46 +3930,86 @@ pub struct SecurityInfo {
 
     // --- Exchange Info ---
     /// Listing exchange
-    #[serde(rename = "@listingExchange", default)]
+    #[serde(
+        rename = "@listingExchange",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub listing_exchange: Option<String>,
 
     /// Underlying security ID
-    #[serde(rename = "@underlyingSecurityID", default)]
+    #[serde(
+        rename = "@underlyingSecurityID",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub underlying_security_id: Option<String>,
 
     /// Underlying listing exchange
-    #[serde(rename = "@underlyingListingExchange", default)]
+    #[serde(
+        rename = "@underlyingListingExchange",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub underlying_listing_exchange: Option<String>,
 
     // --- Issuer/Security Metadata ---
     /// Issuer
-    #[serde(rename = "@issuer", default)]
+    #[serde(
+        rename = "@issuer",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub issuer: Option<String>,
 
     /// Issuer country code
-    #[serde(rename = "@issuerCountryCode", default)]
+    #[serde(
+        rename = "@issuerCountryCode",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub issuer_country_code: Option<String>,
 
     /// Sub-category
-    #[serde(rename = "@subCategory", default)]
+    #[serde(
+        rename = "@subCategory",
+        default,
+        deserialize_with = "deserialize_optional_enum"
+    )]
     pub sub_category: Option<SubCategory>,
 
     // --- Futures ---
     /// Delivery month (for futures)
-    #[serde(rename = "@deliveryMonth", default)]
+    #[serde(
+        rename = "@deliveryMonth",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub delivery_month: Option<String>,
 
     // --- Commodity/Physical Delivery ---
     /// Serial number
-    #[serde(rename = "@serialNumber", default)]
+    #[serde(
+        rename = "@serialNumber",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub serial_number: Option<String>,
 
     /// Delivery type
-    #[serde(rename = "@deliveryType", default)]
+    #[serde(
+        rename = "@deliveryType",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub delivery_type: Option<String>,
 
     /// Commodity type
-    #[serde(rename = "@commodityType", default)]
+    #[serde(
+        rename = "@commodityType",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub commodity_type: Option<String>,
 
     /// Fineness (for precious metals)
@@ -2368,12 +4021,20 @@ pub struct SecurityInfo {
     pub fineness: Option<Decimal>,
 
     /// Weight
-    #[serde(rename = "@weight", default)]
+    #[serde(
+        rename = "@weight",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub weight: Option<String>,
 
     // --- Other ---
     /// Code
-    #[serde(rename = "@code", default)]
+    #[serde(
+        rename = "@code",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub code: Option<String>,
 }
 
@@ -2410,12 +4071,16 @@ pub struct SecurityInfo {
 /// }
 /// # Ok::<(), Box<dyn std::error::Error>>(())
 /// ```
-#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct ConversionRate {
     /// Report date
     #[serde(
         rename = "@reportDate",
-        deserialize_with = "crate::parsers::xml_utils::deserialize_flex_date"
+        deserialize_with = "crate::parsers::xml_utils::deserialize_flex_date",
+        serialize_with = "crate::parsers::xml_utils::serialize_flex_date"
     )]
     pub report_date: NaiveDate,
 
@@ -2428,12 +4093,18 @@ pub struct ConversionRate {
     pub to_currency: String,
 
     /// Exchange rate
-    #[serde(rename = "@rate")]
+    #[serde(
+        rename = "@rate",
+        deserialize_with = "crate::parsers::xml_utils::deserialize_decimal"
+    )]
     pub rate: Decimal,
 }
 
 /// Wrapper for securities info section
-#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct SecuritiesInfoWrapper {
     /// List of securities
     #[serde(rename = "SecurityInfo", default)]
@@ -2441,7 +4112,10 @@ pub struct SecuritiesInfoWrapper {
 }
 
 /// Wrapper for conversion rates section
-#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct ConversionRatesWrapper {
     /// List of conversion rates
     #[serde(rename = "ConversionRate", default)]
@@ -2451,7 +4125,10 @@ pub struct ConversionRatesWrapper {
 // Extended v0.2.0+ wrappers
 
 /// Wrapper for equity summary section
-#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct EquitySummaryWrapper {
     /// List of equity summaries
     #[serde(rename = "EquitySummaryByReportDateInBase", default)]
@@ -2459,7 +4136,10 @@ pub struct EquitySummaryWrapper {
 }
 
 /// Wrapper for cash report section
-#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct CashReportWrapper {
     /// List of cash reports
     #[serde(rename = "CashReportCurrency", default)]
@@ -2467,7 +4147,10 @@ pub struct CashReportWrapper {
 }
 
 /// Wrapper for trade confirmations section
-#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct TradeConfirmsWrapper {
     /// List of trade confirmations
     #[serde(rename = "TradeConfirm", default)]
@@ -2475,7 +4158,10 @@ pub struct TradeConfirmsWrapper {
 }
 
 /// Wrapper for option EAE section
-#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct OptionEAEWrapper {
     /// List of option exercises/assignments/expirations
     #[serde(rename = "OptionEAE", default)]
@@ -2483,7 +4169,10 @@ pub struct OptionEAEWrapper {
 }
 
 /// Wrapper for FX transactions section
-#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct FxTransactionsWrapper {
     /// List of FX transactions
     #[serde(rename = "FxTransaction", default)]
@@ -2491,7 +4180,10 @@ pub struct FxTransactionsWrapper {
 }
 
 /// Wrapper for change in dividend accruals section
-#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct ChangeInDividendAccrualsWrapper {
     /// List of dividend accrual changes
     #[serde(rename = "ChangeInDividendAccrual", default)]
@@ -2499,7 +4191,10 @@ pub struct ChangeInDividendAccrualsWrapper {
 }
 
 /// Wrapper for open dividend accruals section
-#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct OpenDividendAccrualsWrapper {
     /// List of open dividend accruals
     #[serde(rename = "OpenDividendAccrual", default)]
@@ -2507,7 +4202,10 @@ pub struct OpenDividendAccrualsWrapper {
 }
 
 /// Wrapper for interest accruals section
-#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct InterestAccrualsWrapper {
     /// List of interest accruals
     #[serde(rename = "InterestAccrualsCurrency", default)]
@@ -2515,17 +4213,34 @@ pub struct InterestAccrualsWrapper {
 }
 
 /// Wrapper for transfers section
-#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct TransfersWrapper {
     /// List of transfers
     #[serde(rename = "Transfer", default)]
     pub items: Vec<super::extended::Transfer>,
 }
 
+/// Wrapper for deposits and withdrawals section
+#[derive(Debug, Clone, Default, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct DepositsWithdrawalsWrapper {
+    /// List of deposits and withdrawals
+    #[serde(rename = "DepositWithdrawal", default)]
+    pub items: Vec<super::extended::DepositWithdrawal>,
+}
+
 // v0.3.0+ wrappers for performance and advanced features
 
 /// Wrapper for MTM performance summary section
-#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct MTMPerformanceSummaryWrapper {
     /// List of MTM performance summaries by underlying
     #[serde(rename = "MTMPerformanceSummaryUnderlying", default)]
@@ -2533,7 +4248,10 @@ pub struct MTMPerformanceSummaryWrapper {
 }
 
 /// Wrapper for FIFO performance summary section
-#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct FIFOPerformanceSummaryWrapper {
     /// List of FIFO performance summaries by underlying
     #[serde(rename = "FIFOPerformanceSummaryUnderlying", default)]
@@ -2541,7 +4259,10 @@ pub struct FIFOPerformanceSummaryWrapper {
 }
 
 /// Wrapper for MTD/YTD performance summary section
-#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct MTDYTDPerformanceSummaryWrapper {
     /// List of MTD/YTD performance summaries
     #[serde(rename = "MTDYTDPerformanceSummaryUnderlying", default)]
@@ -2549,7 +4270,10 @@ pub struct MTDYTDPerformanceSummaryWrapper {
 }
 
 /// Wrapper for statement of funds section
-#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct StatementOfFundsWrapper {
     /// List of statement of funds lines
     #[serde(rename = "StatementOfFundsLine", default)]
@@ -2557,7 +4281,10 @@ pub struct StatementOfFundsWrapper {
 }
 
 /// Wrapper for change in position value section
-#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct ChangeInPositionValueWrapper {
     /// List of position value changes
     #[serde(rename = "ChangeInPositionValue", default)]
@@ -2565,7 +4292,10 @@ pub struct ChangeInPositionValueWrapper {
 }
 
 /// Wrapper for unbundled commission details section
-#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct UnbundledCommissionDetailWrapper {
     /// List of unbundled commission details
     #[serde(rename = "UnbundledCommissionDetail", default)]
@@ -2573,15 +4303,33 @@ pub struct UnbundledCommissionDetailWrapper {
 }
 
 /// Wrapper for client fees section
-#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct ClientFeesWrapper {
     /// List of client fees
     #[serde(rename = "ClientFee", default)]
     pub items: Vec<super::extended::ClientFee>,
 }
 
+impl ClientFeesWrapper {
+    /// Advisor fee charges, excluding reimbursements
+    pub fn advisor_fees(&self) -> impl Iterator<Item = &super::extended::ClientFee> {
+        self.items.iter().filter(|fee| fee.is_advisor_fee())
+    }
+
+    /// Fee reimbursements credited back to the client
+    pub fn reimbursements(&self) -> impl Iterator<Item = &super::extended::ClientFee> {
+        self.items.iter().filter(|fee| fee.is_reimbursement())
+    }
+}
+
 /// Wrapper for client fees detail section
-#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct ClientFeesDetailWrapper {
     /// List of client fee details
     #[serde(rename = "ClientFeesDetail", default)]
@@ -2589,7 +4337,10 @@ pub struct ClientFeesDetailWrapper {
 }
 
 /// Wrapper for SLB activities section
-#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct SLBActivitiesWrapper {
     /// List of SLB activities
     #[serde(rename = "SLBActivity", default)]
@@ -2597,7 +4348,10 @@ pub struct SLBActivitiesWrapper {
 }
 
 /// Wrapper for SLB fees section
-#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct SLBFeesWrapper {
     /// List of SLB fees
     #[serde(rename = "SLBFee", default)]
@@ -2605,7 +4359,10 @@ pub struct SLBFeesWrapper {
 }
 
 /// Wrapper for hard to borrow details section
-#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct HardToBorrowDetailsWrapper {
     /// List of hard to borrow details
     #[serde(rename = "HardToBorrowDetail", default)]
@@ -2613,15 +4370,68 @@ pub struct HardToBorrowDetailsWrapper {
 }
 
 /// Wrapper for FX lots section
-#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+///
+/// Accepts `<FxLot>` elements either directly or inside the `<FxLots>`
+/// container IB emits within `<FxPositions>`.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct FxLotsWrapper {
     /// List of FX lots
-    #[serde(rename = "FxLot", default)]
+    #[cfg_attr(feature = "serialize", serde(rename = "FxLot"))]
     pub items: Vec<super::extended::FxLot>,
 }
 
+impl<'de> serde::Deserialize<'de> for FxLotsWrapper {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct Nested {
+            #[serde(rename = "FxLot", default)]
+            items: Vec<super::extended::FxLot>,
+        }
+
+        #[derive(Deserialize)]
+        enum Item {
+            FxLot(Box<super::extended::FxLot>),
+            FxLots(Nested),
+        }
+
+        #[derive(Deserialize)]
+        struct Raw {
+            #[serde(rename = "$value", default)]
+            items: Vec<Item>,
+        }
+
+        // FLEX XML mixes `FxLot` and `FxLots` children; binary formats such
+        // as the cache's MessagePack hold the list `Serialize` writes
+        if !deserializer.is_human_readable() {
+            let items = Nested::deserialize(deserializer)?.items;
+            return Ok(FxLotsWrapper { items });
+        }
+
+        let raw = Raw::deserialize(deserializer)?;
+
+        let mut items = Vec::new();
+        for item in raw.items {
+            match item {
+                Item::FxLot(lot) => items.push(*lot),
+                Item::FxLots(nested) => items.extend(nested.items),
+            }
+        }
+
+        Ok(FxLotsWrapper { items })
+    }
+}
+
 /// Wrapper for unsettled transfers section
-#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct UnsettledTransfersWrapper {
     /// List of unsettled transfers
     #[serde(rename = "UnsettledTransfer", default)]
@@ -2629,7 +4439,10 @@ pub struct UnsettledTransfersWrapper {
 }
 
 /// Wrapper for trade transfers section
-#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct TradeTransfersWrapper {
     /// List of trade transfers
     #[serde(rename = "TradeTransfer", default)]
@@ -2637,7 +4450,10 @@ pub struct TradeTransfersWrapper {
 }
 
 /// Wrapper for prior period positions section
-#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct PriorPeriodPositionsWrapper {
     /// List of prior period positions
     #[serde(rename = "PriorPeriodPosition", default)]
@@ -2645,7 +4461,10 @@ pub struct PriorPeriodPositionsWrapper {
 }
 
 /// Wrapper for tier interest details section
-#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct TierInterestDetailsWrapper {
     /// List of tier interest details
     #[serde(rename = "TierInterestDetail", default)]
@@ -2653,7 +4472,10 @@ pub struct TierInterestDetailsWrapper {
 }
 
 /// Wrapper for debit card activities section
-#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct DebitCardActivitiesWrapper {
     /// List of debit card activities
     #[serde(rename = "DebitCardActivity", default)]
@@ -2661,7 +4483,10 @@ pub struct DebitCardActivitiesWrapper {
 }
 
 /// Wrapper for sales tax section
-#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct SalesTaxWrapper {
     /// List of sales tax entries
     #[serde(rename = "SalesTax", default)]
@@ -2669,7 +4494,10 @@ pub struct SalesTaxWrapper {
 }
 
 /// Wrapper for symbol summary section
-#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct SymbolSummaryWrapper {
     /// List of symbol summaries
     #[serde(rename = "SymbolSummary", default)]
@@ -2677,7 +4505,10 @@ pub struct SymbolSummaryWrapper {
 }
 
 /// Wrapper for asset summary section
-#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct AssetSummaryWrapper {
     /// List of asset summaries
     #[serde(rename = "AssetSummary", default)]
@@ -2685,9 +4516,126 @@ pub struct AssetSummaryWrapper {
 }
 
 /// Wrapper for orders section
-#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct OrdersWrapper {
     /// List of orders
     #[serde(rename = "Order", default)]
     pub items: Vec<super::extended::Order>,
 }
+
+/// Make section wrappers behave like collections of their `items`
+///
+/// Each wrapper derefs to a slice of its rows and supports iteration,
+/// `extend`, and `collect`. For [`TradesWrapper`] this covers the trade
+/// executions only; wash sales stay in `wash_sales`.
+macro_rules! impl_wrapper_collection {
+    ($($wrapper:ident => $item:ty,)+) => {
+        $(
+            impl $wrapper {
+                /// Number of rows in the section
+                pub fn len(&self) -> usize {
+                    self.items.len()
+                }
+
+                /// Returns true if the section has no rows
+                pub fn is_empty(&self) -> bool {
+                    self.items.is_empty()
+                }
+
+                /// Iterate over the rows in the section
+                pub fn iter(&self) -> std::slice::Iter<'_, $item> {
+                    self.items.iter()
+                }
+            }
+
+            impl std::ops::Deref for $wrapper {
+                type Target = [$item];
+
+                fn deref(&self) -> &[$item] {
+                    &self.items
+                }
+            }
+
+            impl std::ops::DerefMut for $wrapper {
+                fn deref_mut(&mut self) -> &mut [$item] {
+                    &mut self.items
+                }
+            }
+
+            impl IntoIterator for $wrapper {
+                type Item = $item;
+                type IntoIter = std::vec::IntoIter<$item>;
+
+                fn into_iter(self) -> Self::IntoIter {
+                    self.items.into_iter()
+                }
+            }
+
+            impl<'a> IntoIterator for &'a $wrapper {
+                type Item = &'a $item;
+                type IntoIter = std::slice::Iter<'a, $item>;
+
+                fn into_iter(self) -> Self::IntoIter {
+                    self.items.iter()
+                }
+            }
+
+            impl Extend<$item> for $wrapper {
+                fn extend<I: IntoIterator<Item = $item>>(&mut self, iter: I) {
+                    self.items.extend(iter);
+                }
+            }
+
+            impl FromIterator<$item> for $wrapper {
+                fn from_iter<I: IntoIterator<Item = $item>>(iter: I) -> Self {
+                    let mut wrapper = $wrapper::default();
+                    wrapper.items = iter.into_iter().collect();
+                    wrapper
+                }
+            }
+        )+
+    };
+}
+
+impl_wrapper_collection! {
+    TradesWrapper => Trade,
+    PositionsWrapper => Position,
+    CashTransactionsWrapper => CashTransaction,
+    CorporateActionsWrapper => CorporateAction,
+    SecuritiesInfoWrapper => SecurityInfo,
+    ConversionRatesWrapper => ConversionRate,
+    EquitySummaryWrapper => super::extended::EquitySummaryByReportDateInBase,
+    CashReportWrapper => super::extended::CashReportCurrency,
+    TradeConfirmsWrapper => super::extended::TradeConfirm,
+    OptionEAEWrapper => super::extended::OptionEAE,
+    FxTransactionsWrapper => super::extended::FxTransaction,
+    ChangeInDividendAccrualsWrapper => super::extended::ChangeInDividendAccrual,
+    OpenDividendAccrualsWrapper => super::extended::OpenDividendAccrual,
+    InterestAccrualsWrapper => super::extended::InterestAccrualsCurrency,
+    TransfersWrapper => super::extended::Transfer,
+    DepositsWithdrawalsWrapper => super::extended::DepositWithdrawal,
+    MTMPerformanceSummaryWrapper => super::extended::MTMPerformanceSummaryUnderlying,
+    FIFOPerformanceSummaryWrapper => super::extended::FIFOPerformanceSummaryUnderlying,
+    MTDYTDPerformanceSummaryWrapper => super::extended::MTDYTDPerformanceSummary,
+    StatementOfFundsWrapper => super::extended::StatementOfFundsLine,
+    ChangeInPositionValueWrapper => super::extended::ChangeInPositionValue,
+    UnbundledCommissionDetailWrapper => super::extended::UnbundledCommissionDetail,
+    ClientFeesWrapper => super::extended::ClientFee,
+    ClientFeesDetailWrapper => super::extended::ClientFeesDetail,
+    SLBActivitiesWrapper => super::extended::SLBActivity,
+    SLBFeesWrapper => super::extended::SLBFee,
+    HardToBorrowDetailsWrapper => super::extended::HardToBorrowDetail,
+    FxLotsWrapper => super::extended::FxLot,
+    UnsettledTransfersWrapper => super::extended::UnsettledTransfer,
+    TradeTransfersWrapper => super::extended::TradeTransfer,
+    PriorPeriodPositionsWrapper => super::extended::PriorPeriodPosition,
+    TierInterestDetailsWrapper => super::extended::TierInterestDetail,
+    DebitCardActivitiesWrapper => super::extended::DebitCardActivity,
+    SalesTaxWrapper => super::extended::SalesTax,
+    SymbolSummaryWrapper => super::extended::SymbolSummary,
+    AssetSummaryWrapper => super::extended::AssetSummary,
+    OrdersWrapper => super::extended::Order,
+}
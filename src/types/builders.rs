@@ -0,0 +1,330 @@
+//! Builders for constructing records in tests and fixtures
+//!
+//! FLEX records carry dozens of optional fields, so writing them out as
+//! struct literals is tedious. The builders start from sensible defaults
+//! (a `U1234567` stock record in USD) and only need the fields a test
+//! actually cares about.
+//!
+//! # Example
+//! ```
+//! use ib_flex::types::{BuySell, TradeBuilder};
+//! use rust_decimal::Decimal;
+//!
+//! let trade = TradeBuilder::new("AAPL")
+//!     .conid(265598)
+//!     .buy_sell(BuySell::Buy)
+//!     .quantity(100)
+//!     .price(Decimal::new(15025, 2))
+//!     .build();
+//!
+//! assert_eq!(trade.symbol, "AAPL");
+//! assert_eq!(trade.quantity, Some(Decimal::from(100)));
+//! ```
+
+use chrono::{NaiveDate, NaiveDateTime};
+use rust_decimal::Decimal;
+
+use super::activity::{CashTransaction, Position, Trade};
+use super::common::{
+    AccountId, AssetCategory, BuySell, CashTransactionType, Conid, LevelOfDetail, LongShort,
+    OpenClose, OrderId, PutCall, TradeId,
+};
+
+const DEFAULT_ACCOUNT: &str = "U1234567";
+const DEFAULT_CURRENCY: &str = "USD";
+
+/// Generate chainable setters on a builder wrapping `$record`
+///
+/// `required` fields are assigned directly; `optional` fields wrap the value
+/// in `Some`.
+macro_rules! setters {
+    (
+        $field_name:ident;
+        required { $($req:ident: $req_ty:ty),* $(,)? }
+        optional { $($opt:ident: $opt_ty:ty),* $(,)? }
+    ) => {
+        $(
+            #[doc = concat!("Set `", stringify!($req), "`")]
+            pub fn $req(mut self, value: impl Into<$req_ty>) -> Self {
+                self.$field_name.$req = value.into();
+                self
+            }
+        )*
+        $(
+            #[doc = concat!("Set `", stringify!($opt), "`")]
+            pub fn $opt(mut self, value: impl Into<$opt_ty>) -> Self {
+                self.$field_name.$opt = Some(value.into());
+                self
+            }
+        )*
+    };
+}
+
+/// Builder for [`Trade`]
+#[derive(Debug, Clone)]
+pub struct TradeBuilder {
+    trade: Trade,
+}
+
+impl TradeBuilder {
+    /// Start a stock trade in USD for `symbol`
+    pub fn new(symbol: impl Into<String>) -> Self {
+        TradeBuilder {
+            trade: Trade {
+                account_id: AccountId::new(DEFAULT_ACCOUNT),
+                symbol: symbol.into(),
+                asset_category: AssetCategory::Stock,
+                currency: DEFAULT_CURRENCY.to_string(),
+                ..Default::default()
+            },
+        }
+    }
+
+    setters! {
+        trade;
+        required {
+            account_id: AccountId,
+            conid: Conid,
+            symbol: String,
+            asset_category: AssetCategory,
+            currency: String,
+        }
+        optional {
+            transaction_id: String,
+            trade_id: TradeId,
+            ib_order_id: OrderId,
+            description: String,
+            trade_date: NaiveDate,
+            settle_date: NaiveDate,
+            trade_time: NaiveDateTime,
+            buy_sell: BuySell,
+            open_close: OpenClose,
+            quantity: Decimal,
+            price: Decimal,
+            proceeds: Decimal,
+            cost: Decimal,
+            commission: Decimal,
+            net_cash: Decimal,
+            fifo_pnl_realized: Decimal,
+            mtm_pnl: Decimal,
+            fx_rate_to_base: Decimal,
+            multiplier: Decimal,
+            strike: Decimal,
+            expiry: NaiveDate,
+            put_call: PutCall,
+            underlying_symbol: String,
+            underlying_conid: Conid,
+            notes: String,
+            level_of_detail: LevelOfDetail,
+        }
+    }
+
+    /// Finish building the trade
+    pub fn build(self) -> Trade {
+        self.trade
+    }
+}
+
+/// Builder for [`Position`]
+#[derive(Debug, Clone)]
+pub struct PositionBuilder {
+    position: Position,
+    value_set: bool,
+}
+
+impl PositionBuilder {
+    /// Start a stock position in USD for `symbol`
+    pub fn new(symbol: impl Into<String>) -> Self {
+        PositionBuilder {
+            position: Position {
+                account_id: AccountId::new(DEFAULT_ACCOUNT),
+                symbol: symbol.into(),
+                asset_category: AssetCategory::Stock,
+                currency: DEFAULT_CURRENCY.to_string(),
+                ..Default::default()
+            },
+            value_set: false,
+        }
+    }
+
+    setters! {
+        position;
+        required {
+            account_id: AccountId,
+            conid: Conid,
+            symbol: String,
+            asset_category: AssetCategory,
+            currency: String,
+            quantity: Decimal,
+            mark_price: Decimal,
+            report_date: NaiveDate,
+        }
+        optional {
+            description: String,
+            side: LongShort,
+            open_price: Decimal,
+            cost_basis_price: Decimal,
+            cost_basis_money: Decimal,
+            fifo_pnl_unrealized: Decimal,
+            fx_rate_to_base: Decimal,
+            multiplier: Decimal,
+            strike: Decimal,
+            expiry: NaiveDate,
+            put_call: PutCall,
+            underlying_symbol: String,
+            underlying_conid: Conid,
+            open_date_time: NaiveDateTime,
+            level_of_detail: LevelOfDetail,
+        }
+    }
+
+    /// Set `position_value` explicitly instead of deriving it
+    pub fn position_value(mut self, value: impl Into<Decimal>) -> Self {
+        self.position.position_value = value.into();
+        self.value_set = true;
+        self
+    }
+
+    /// Finish building the position
+    ///
+    /// Unless set explicitly, `position_value` is derived as
+    /// `quantity * mark_price * multiplier` and `side` from the sign of the
+    /// quantity.
+    pub fn build(mut self) -> Position {
+        let p = &mut self.position;
+        if !self.value_set {
+            p.position_value = p.quantity * p.mark_price * p.multiplier.unwrap_or(Decimal::ONE);
+        }
+        if p.side.is_none() && !p.quantity.is_zero() {
+            p.side = Some(if p.quantity.is_sign_negative() {
+                LongShort::Short
+            } else {
+                LongShort::Long
+            });
+        }
+        self.position
+    }
+}
+
+/// Builder for [`CashTransaction`]
+#[derive(Debug, Clone)]
+pub struct CashTransactionBuilder {
+    transaction: CashTransaction,
+}
+
+impl CashTransactionBuilder {
+    /// Start a USD cash transaction of the given type and amount
+    pub fn new(transaction_type: CashTransactionType, amount: impl Into<Decimal>) -> Self {
+        CashTransactionBuilder {
+            transaction: CashTransaction {
+                account_id: AccountId::new(DEFAULT_ACCOUNT),
+                transaction_type: Some(transaction_type),
+                amount: amount.into(),
+                currency: DEFAULT_CURRENCY.to_string(),
+                ..Default::default()
+            },
+        }
+    }
+
+    setters! {
+        transaction;
+        required {
+            account_id: AccountId,
+            amount: Decimal,
+            currency: String,
+        }
+        optional {
+            transaction_id: String,
+            transaction_type: CashTransactionType,
+            description: String,
+            fx_rate_to_base: Decimal,
+            date: NaiveDate,
+            settle_date: NaiveDate,
+            ex_date: NaiveDate,
+            report_date: NaiveDate,
+            conid: String,
+            symbol: String,
+            asset_category: AssetCategory,
+            code: String,
+            trade_id: TradeId,
+            level_of_detail: LevelOfDetail,
+        }
+    }
+
+    /// Finish building the cash transaction
+    pub fn build(self) -> CashTransaction {
+        self.transaction
+    }
+}
+
+impl Trade {
+    /// Start a [`TradeBuilder`] for `symbol`
+    pub fn builder(symbol: impl Into<String>) -> TradeBuilder {
+        TradeBuilder::new(symbol)
+    }
+}
+
+impl Position {
+    /// Start a [`PositionBuilder`] for `symbol`
+    pub fn builder(symbol: impl Into<String>) -> PositionBuilder {
+        PositionBuilder::new(symbol)
+    }
+}
+
+impl CashTransaction {
+    /// Start a [`CashTransactionBuilder`]
+    pub fn builder(
+        transaction_type: CashTransactionType,
+        amount: impl Into<Decimal>,
+    ) -> CashTransactionBuilder {
+        CashTransactionBuilder::new(transaction_type, amount)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trade_builder_defaults() {
+        let trade = Trade::builder("MSFT").quantity(10).build();
+        assert_eq!(trade.account_id, "U1234567");
+        assert_eq!(trade.asset_category, AssetCategory::Stock);
+        assert_eq!(trade.currency, "USD");
+        assert_eq!(trade.quantity, Some(Decimal::from(10)));
+        assert_eq!(trade.price, None);
+    }
+
+    #[test]
+    fn test_position_builder_derives_value_and_side() {
+        let position = Position::builder("ES")
+            .asset_category(AssetCategory::Future)
+            .quantity(-2)
+            .mark_price(5000)
+            .multiplier(50)
+            .build();
+        assert_eq!(position.position_value, Decimal::from(-500_000));
+        assert_eq!(position.side, Some(LongShort::Short));
+
+        let explicit = Position::builder("AAPL")
+            .quantity(1)
+            .mark_price(100)
+            .position_value(99)
+            .build();
+        assert_eq!(explicit.position_value, Decimal::from(99));
+    }
+
+    #[test]
+    fn test_cash_transaction_builder() {
+        let dividend = CashTransaction::builder(CashTransactionType::Dividends, 25)
+            .symbol("AAPL")
+            .currency("EUR")
+            .build();
+        assert!(dividend
+            .transaction_type
+            .as_ref()
+            .is_some_and(|t| t.is_dividend()));
+        assert_eq!(dividend.amount, Decimal::from(25));
+        assert_eq!(dividend.currency, "EUR");
+    }
+}
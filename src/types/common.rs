@@ -2,12 +2,16 @@
 
 use chrono::NaiveDate;
 use rust_decimal::Decimal;
+#[cfg(feature = "schemars")]
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 /// Asset category (security type)
 ///
 /// Maps to IB's AssetCategory field. Represents the type of financial instrument.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[serde(remote = "Self")]
 #[serde(rename_all = "UPPERCASE")]
 pub enum AssetCategory {
     /// Stock
@@ -86,13 +90,69 @@ pub enum AssetCategory {
     #[serde(rename = "IND")]
     Index,
 
-    /// Unknown or unrecognized asset category
-    #[serde(other)]
-    Unknown,
+    /// Single-stock future
+    #[serde(rename = "SSF")]
+    SingleStockFuture,
+
+    /// Forward contract
+    #[serde(rename = "FWD")]
+    Forward,
+
+    /// Unrecognized asset category, kept verbatim
+    #[serde(untagged)]
+    Unknown(String),
+}
+
+impl AssetCategory {
+    /// Returns true for options on any underlying, including warrants and
+    /// structured products
+    pub fn is_option(&self) -> bool {
+        matches!(
+            self,
+            AssetCategory::Option
+                | AssetCategory::FutureOption
+                | AssetCategory::Warrant
+                | AssetCategory::StructuredProduct
+        )
+    }
+
+    /// Returns true for futures and forwards
+    pub fn is_future(&self) -> bool {
+        matches!(
+            self,
+            AssetCategory::Future | AssetCategory::SingleStockFuture | AssetCategory::Forward
+        )
+    }
+
+    /// Returns true for contracts whose value derives from an underlying
+    pub fn is_derivative(&self) -> bool {
+        self.is_option()
+            || self.is_future()
+            || matches!(
+                self,
+                AssetCategory::Cfd
+                    | AssetCategory::ForexCfd
+                    | AssetCategory::ExchangeForPhysical
+                    | AssetCategory::EventContract
+            )
+    }
+
+    /// Returns true for bonds and bills
+    pub fn is_fixed_income(&self) -> bool {
+        matches!(self, AssetCategory::Bond | AssetCategory::Bill)
+    }
+}
+
+impl Default for AssetCategory {
+    fn default() -> Self {
+        AssetCategory::Unknown(String::new())
+    }
 }
 
 /// Buy or Sell side
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[serde(remote = "Self")]
 #[serde(rename_all = "UPPERCASE")]
 pub enum BuySell {
     /// Buy
@@ -111,13 +171,15 @@ pub enum BuySell {
     #[serde(rename = "SELL (Ca.)")]
     CancelSell,
 
-    /// Unknown
-    #[serde(other)]
-    Unknown,
+    /// Unrecognized value, kept verbatim
+    #[serde(untagged)]
+    Unknown(String),
 }
 
 /// Open or Close indicator (for options/futures)
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[serde(remote = "Self")]
 pub enum OpenClose {
     /// Opening trade
     #[serde(rename = "O")]
@@ -131,13 +193,15 @@ pub enum OpenClose {
     #[serde(rename = "C;O")]
     CloseOpen,
 
-    /// Unknown
-    #[serde(other)]
-    Unknown,
+    /// Unrecognized value, kept verbatim
+    #[serde(untagged)]
+    Unknown(String),
 }
 
 /// Order type
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[serde(remote = "Self")]
 #[serde(rename_all = "UPPERCASE")]
 pub enum OrderType {
     /// Market order
@@ -193,12 +257,14 @@ pub enum OrderType {
     Multiple,
 
     /// Unknown or unrecognized order type
-    #[serde(other)]
-    Unknown,
+    #[serde(untagged)]
+    Unknown(String),
 }
 
 /// Put or Call (for options)
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[serde(remote = "Self")]
 pub enum PutCall {
     /// Put option
     #[serde(rename = "P")]
@@ -208,13 +274,15 @@ pub enum PutCall {
     #[serde(rename = "C")]
     Call,
 
-    /// Unknown
-    #[serde(other)]
-    Unknown,
+    /// Unrecognized value, kept verbatim
+    #[serde(untagged)]
+    Unknown(String),
 }
 
 /// Long or Short position side
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[serde(remote = "Self")]
 pub enum LongShort {
     /// Long position
     Long,
@@ -222,13 +290,15 @@ pub enum LongShort {
     /// Short position
     Short,
 
-    /// Unknown
-    #[serde(other)]
-    Unknown,
+    /// Unrecognized value, kept verbatim
+    #[serde(untagged)]
+    Unknown(String),
 }
 
 /// Transaction type for trades
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[serde(remote = "Self")]
 pub enum TradeType {
     /// Exchange trade
     ExchTrade,
@@ -257,22 +327,22 @@ pub enum TradeType {
     /// IBKR trade
     IBKRTrade,
 
-    /// Unknown
-    #[serde(other)]
-    Unknown,
+    /// Unrecognized value, kept verbatim
+    #[serde(untagged)]
+    Unknown(String),
 }
 
 /// Cash transaction action type
 ///
 /// Represents the type of cash transaction (dividend, interest, fee, etc.).
 /// This enum is used by the `CashTransaction` struct to classify cash activity
-/// in the account statement.
+/// in the account statement. Values IB introduces that aren't listed here are
+/// kept verbatim in [`CashTransactionType::Unknown`].
 ///
 /// **XML Mapping**: Maps to the `type` attribute in `<CashTransaction>` elements.
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum CashTransactionType {
     /// Deposits and withdrawals
-    #[serde(rename = "Deposits & Withdrawals")]
     DepositsWithdrawals,
 
     /// Dividend payments
@@ -281,52 +351,190 @@ pub enum CashTransactionType {
     /// Withholding tax
     WithholdingTax,
 
+    /// Section 871(m) withholding on dividend equivalents
+    Withholding871m,
+
     /// Broker interest paid
-    #[serde(rename = "Broker Interest Paid")]
     BrokerInterestPaid,
 
     /// Broker interest received
-    #[serde(rename = "Broker Interest Received")]
     BrokerInterestReceived,
 
     /// Bond interest received
-    #[serde(rename = "Bond Interest Received")]
     BondInterestReceived,
 
     /// Bond interest paid
-    #[serde(rename = "Bond Interest Paid")]
     BondInterestPaid,
 
     /// Bond interest (generic)
-    #[serde(rename = "Bond Interest")]
     BondInterest,
 
     /// Payment in lieu of dividends
-    #[serde(rename = "Payment In Lieu Of Dividends")]
     PaymentInLieuOfDividends,
 
     /// Other fees
-    #[serde(rename = "Other Fees")]
     OtherFees,
 
     /// Commission adjustments
-    #[serde(rename = "Commission Adjustments")]
     CommissionAdjustments,
 
     /// Advisor fees
-    #[serde(rename = "Advisor Fees")]
     AdvisorFees,
 
-    /// Cash receipts
-    #[serde(rename = "Cash Receipts")]
+    /// Price adjustments
+    PriceAdjustments,
+
+    /// Cash receipts / electronic fund transfers
     CashReceipts,
 
     /// Fees
     Fees,
 
-    /// Unknown type
-    #[serde(other)]
-    Unknown,
+    /// Unrecognized type, with the raw value IB sent
+    Unknown(String),
+}
+
+impl CashTransactionType {
+    /// The value as it appears in IB's `type` attribute
+    pub fn as_str(&self) -> &str {
+        match self {
+            CashTransactionType::DepositsWithdrawals => "Deposits & Withdrawals",
+            CashTransactionType::Dividends => "Dividends",
+            CashTransactionType::WithholdingTax => "Withholding Tax",
+            CashTransactionType::Withholding871m => "871(m) Withholding",
+            CashTransactionType::BrokerInterestPaid => "Broker Interest Paid",
+            CashTransactionType::BrokerInterestReceived => "Broker Interest Received",
+            CashTransactionType::BondInterestReceived => "Bond Interest Received",
+            CashTransactionType::BondInterestPaid => "Bond Interest Paid",
+            CashTransactionType::BondInterest => "Bond Interest",
+            CashTransactionType::PaymentInLieuOfDividends => "Payment In Lieu Of Dividends",
+            CashTransactionType::OtherFees => "Other Fees",
+            CashTransactionType::CommissionAdjustments => "Commission Adjustments",
+            CashTransactionType::AdvisorFees => "Advisor Fees",
+            CashTransactionType::PriceAdjustments => "Price Adjustments",
+            CashTransactionType::CashReceipts => "Cash Receipts",
+            CashTransactionType::Fees => "Fees",
+            CashTransactionType::Unknown(raw) => raw,
+        }
+    }
+
+    /// Returns true for dividend income, including payments in lieu
+    pub fn is_dividend(&self) -> bool {
+        matches!(
+            self,
+            CashTransactionType::Dividends | CashTransactionType::PaymentInLieuOfDividends
+        )
+    }
+
+    /// Returns true for tax withheld at source
+    pub fn is_withholding(&self) -> bool {
+        matches!(
+            self,
+            CashTransactionType::WithholdingTax | CashTransactionType::Withholding871m
+        )
+    }
+
+    /// Returns true for fees charged by IB or an advisor
+    pub fn is_fee(&self) -> bool {
+        matches!(
+            self,
+            CashTransactionType::OtherFees
+                | CashTransactionType::AdvisorFees
+                | CashTransactionType::Fees
+                | CashTransactionType::CommissionAdjustments
+        )
+    }
+
+    /// Returns true for cash moved into or out of the account
+    pub fn is_deposit_or_withdrawal(&self) -> bool {
+        matches!(
+            self,
+            CashTransactionType::DepositsWithdrawals | CashTransactionType::CashReceipts
+        )
+    }
+
+    /// Returns true for interest paid or received (broker or bond)
+    pub fn is_interest(&self) -> bool {
+        matches!(
+            self,
+            CashTransactionType::BrokerInterestPaid
+                | CashTransactionType::BrokerInterestReceived
+                | CashTransactionType::BondInterestReceived
+                | CashTransactionType::BondInterestPaid
+                | CashTransactionType::BondInterest
+        )
+    }
+}
+
+impl std::str::FromStr for CashTransactionType {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.trim() {
+            "Deposits & Withdrawals" | "Deposits/Withdrawals" => {
+                CashTransactionType::DepositsWithdrawals
+            }
+            "Dividends" => CashTransactionType::Dividends,
+            "Withholding Tax" | "WithholdingTax" => CashTransactionType::WithholdingTax,
+            "871(m) Withholding" => CashTransactionType::Withholding871m,
+            "Broker Interest Paid" => CashTransactionType::BrokerInterestPaid,
+            "Broker Interest Received" => CashTransactionType::BrokerInterestReceived,
+            "Bond Interest Received" => CashTransactionType::BondInterestReceived,
+            "Bond Interest Paid" => CashTransactionType::BondInterestPaid,
+            "Bond Interest" => CashTransactionType::BondInterest,
+            "Payment In Lieu Of Dividends" => CashTransactionType::PaymentInLieuOfDividends,
+            "Other Fees" => CashTransactionType::OtherFees,
+            "Commission Adjustments" => CashTransactionType::CommissionAdjustments,
+            "Advisor Fees" => CashTransactionType::AdvisorFees,
+            "Price Adjustments" => CashTransactionType::PriceAdjustments,
+            "Cash Receipts" | "Cash Receipts / Electronic Fund Transfers" => {
+                CashTransactionType::CashReceipts
+            }
+            "Fees" => CashTransactionType::Fees,
+            other => CashTransactionType::Unknown(other.to_string()),
+        })
+    }
+}
+
+impl std::fmt::Display for CashTransactionType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for CashTransactionType {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        Ok(s.parse().unwrap_or_else(|never| match never {}))
+    }
+}
+
+#[cfg(feature = "serialize")]
+impl Serialize for CashTransactionType {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+#[cfg(feature = "schemars")]
+impl JsonSchema for CashTransactionType {
+    fn inline_schema() -> bool {
+        true
+    }
+
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        "CashTransactionType".into()
+    }
+
+    fn json_schema(generator: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        String::json_schema(generator)
+    }
 }
 
 /// Corporate action reorganization type
@@ -336,7 +544,9 @@ pub enum CashTransactionType {
 /// that affect security positions and holdings.
 ///
 /// **XML Mapping**: Maps to the `type` attribute in `<CorporateAction>` elements.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[serde(remote = "Self")]
 pub enum CorporateActionType {
     /// Stock split (forward split)
     #[serde(rename = "Stock Split")]
@@ -478,13 +688,15 @@ pub enum CorporateActionType {
     #[serde(rename = "Purchase (Issue)")]
     PurchaseIssue,
 
-    /// Unknown
-    #[serde(other)]
-    Unknown,
+    /// Unrecognized value, kept verbatim
+    #[serde(untagged)]
+    Unknown(String),
 }
 
 /// Option action type
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[serde(remote = "Self")]
 pub enum OptionAction {
     /// Assignment
     Assignment,
@@ -508,13 +720,15 @@ pub enum OptionAction {
     /// Sell to open/close
     Sell,
 
-    /// Unknown
-    #[serde(other)]
-    Unknown,
+    /// Unrecognized value, kept verbatim
+    #[serde(untagged)]
+    Unknown(String),
 }
 
 /// Transfer type
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[serde(remote = "Self")]
 pub enum TransferType {
     /// ACATS transfer
     ACATS,
@@ -531,9 +745,9 @@ pub enum TransferType {
     /// Delivery vs payment
     DVP,
 
-    /// Unknown
-    #[serde(other)]
-    Unknown,
+    /// Unrecognized value, kept verbatim
+    #[serde(untagged)]
+    Unknown(String),
 }
 
 /// Transaction code
@@ -541,7 +755,10 @@ pub enum TransferType {
 /// Comprehensive list of IB transaction classification codes.
 /// These codes appear in `notes` fields and can be combined (e.g., "C;W" for closing + wash sale).
 /// They provide critical context for tax reporting and trade classification.
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+/// Use [`TradeCodes`] to parse a full multi-code `notes` value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub enum TransactionCode {
     /// Assignment - Option assignment triggering stock delivery
     #[serde(rename = "A")]
@@ -608,11 +825,11 @@ pub enum TransactionCode {
     Etf,
 
     /// Expired - From expired position (option/warrant expiry)
-    #[serde(rename = "Ex")]
+    #[serde(rename = "Ep")]
     Expired,
 
     /// Exercise - Option exercise (long option exercised)
-    #[serde(rename = "O")]
+    #[serde(rename = "Ex")]
     Exercise,
 
     /// Guaranteed - Guaranteed account segment (special margin)
@@ -684,11 +901,11 @@ pub enum TransactionCode {
     ManualExercise,
 
     /// Opening - Opening trade (new position)
-    #[serde(rename = "P")]
+    #[serde(rename = "O")]
     Opening,
 
     /// Partial - Partial execution (partial fill)
-    #[serde(rename = "Pt")]
+    #[serde(rename = "P", alias = "Pt")]
     Partial,
 
     /// Frac Riskless - Fractional riskless principal (fractional share method)
@@ -704,7 +921,7 @@ pub enum TransactionCode {
     PriceImprovement,
 
     /// Post Accrual - Accrual posting (accrual entry)
-    #[serde(rename = "Pa")]
+    #[serde(rename = "Pa", alias = "Po")]
     PostAccrual,
 
     /// Principal - IB principal execution (principal trade)
@@ -768,8 +985,656 @@ pub enum TransactionCode {
     Unknown,
 }
 
+impl TransactionCode {
+    /// Every known code, in declaration order (excludes `Unknown`)
+    pub const ALL: [TransactionCode; 55] = [
+        TransactionCode::Assignment,
+        TransactionCode::Adjustment,
+        TransactionCode::Allocation,
+        TransactionCode::AutoExercise,
+        TransactionCode::AutoFx,
+        TransactionCode::AwayTrade,
+        TransactionCode::BuyIn,
+        TransactionCode::BorrowFee,
+        TransactionCode::Cancelled,
+        TransactionCode::Closing,
+        TransactionCode::CashDelivery,
+        TransactionCode::ComplexPosition,
+        TransactionCode::Correction,
+        TransactionCode::Crossing,
+        TransactionCode::DualAgent,
+        TransactionCode::Etf,
+        TransactionCode::Expired,
+        TransactionCode::Exercise,
+        TransactionCode::Guaranteed,
+        TransactionCode::HighestCost,
+        TransactionCode::HfInvestment,
+        TransactionCode::HfRedemption,
+        TransactionCode::InternalTransfer,
+        TransactionCode::Affiliate,
+        TransactionCode::Investor,
+        TransactionCode::MarginLiquidation,
+        TransactionCode::Lifo,
+        TransactionCode::Loan,
+        TransactionCode::LongTermGain,
+        TransactionCode::ManualEntry,
+        TransactionCode::MaxLoss,
+        TransactionCode::MinLongTermGain,
+        TransactionCode::MaxShortTermGain,
+        TransactionCode::MinShortTermGain,
+        TransactionCode::ManualExercise,
+        TransactionCode::Opening,
+        TransactionCode::Partial,
+        TransactionCode::FracRiskless,
+        TransactionCode::FracPrincipal,
+        TransactionCode::PriceImprovement,
+        TransactionCode::PostAccrual,
+        TransactionCode::Principal,
+        TransactionCode::Reinvestment,
+        TransactionCode::Redemption,
+        TransactionCode::Reopen,
+        TransactionCode::Reverse,
+        TransactionCode::Reimbursement,
+        TransactionCode::SolicitedIb,
+        TransactionCode::SpecificLot,
+        TransactionCode::SolicitedOther,
+        TransactionCode::ShortSettlement,
+        TransactionCode::ShortTermGain,
+        TransactionCode::StockYield,
+        TransactionCode::Transfer,
+        TransactionCode::WashSale,
+    ];
+
+    /// The code as it appears in IB's `notes`/`code` attributes
+    pub fn as_code(&self) -> &'static str {
+        match self {
+            TransactionCode::Assignment => "A",
+            TransactionCode::Adjustment => "Adj",
+            TransactionCode::Allocation => "Al",
+            TransactionCode::AutoExercise => "Ae",
+            TransactionCode::AutoFx => "Af",
+            TransactionCode::AwayTrade => "Aw",
+            TransactionCode::BuyIn => "B",
+            TransactionCode::BorrowFee => "Bo",
+            TransactionCode::Cancelled => "Ca",
+            TransactionCode::Closing => "C",
+            TransactionCode::CashDelivery => "Cd",
+            TransactionCode::ComplexPosition => "Cp",
+            TransactionCode::Correction => "Cr",
+            TransactionCode::Crossing => "Cs",
+            TransactionCode::DualAgent => "D",
+            TransactionCode::Etf => "Et",
+            TransactionCode::Expired => "Ep",
+            TransactionCode::Exercise => "Ex",
+            TransactionCode::Guaranteed => "G",
+            TransactionCode::HighestCost => "Hc",
+            TransactionCode::HfInvestment => "Hi",
+            TransactionCode::HfRedemption => "Hr",
+            TransactionCode::InternalTransfer => "I",
+            TransactionCode::Affiliate => "Ia",
+            TransactionCode::Investor => "Iv",
+            TransactionCode::MarginLiquidation => "L",
+            TransactionCode::Lifo => "Li",
+            TransactionCode::Loan => "Ln",
+            TransactionCode::LongTermGain => "Lt",
+            TransactionCode::ManualEntry => "M",
+            TransactionCode::MaxLoss => "Ml",
+            TransactionCode::MinLongTermGain => "Mn",
+            TransactionCode::MaxShortTermGain => "Ms",
+            TransactionCode::MinShortTermGain => "Mi",
+            TransactionCode::ManualExercise => "Mx",
+            TransactionCode::Opening => "O",
+            TransactionCode::Partial => "P",
+            TransactionCode::FracRiskless => "Fr",
+            TransactionCode::FracPrincipal => "Fp",
+            TransactionCode::PriceImprovement => "Pi",
+            TransactionCode::PostAccrual => "Pa",
+            TransactionCode::Principal => "Pr",
+            TransactionCode::Reinvestment => "Re",
+            TransactionCode::Redemption => "Rd",
+            TransactionCode::Reopen => "R",
+            TransactionCode::Reverse => "Rv",
+            TransactionCode::Reimbursement => "Ri",
+            TransactionCode::SolicitedIb => "Si",
+            TransactionCode::SpecificLot => "Sp",
+            TransactionCode::SolicitedOther => "So",
+            TransactionCode::ShortSettlement => "Ss",
+            TransactionCode::ShortTermGain => "St",
+            TransactionCode::StockYield => "Sy",
+            TransactionCode::Transfer => "T",
+            TransactionCode::WashSale => "W",
+            TransactionCode::Unknown => "",
+        }
+    }
+}
+
+impl std::str::FromStr for TransactionCode {
+    type Err = std::convert::Infallible;
+
+    /// Parses a single code; unrecognized codes map to `Unknown`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use serde::de::IntoDeserializer;
+        let de: serde::de::value::StrDeserializer<'_, serde::de::value::Error> =
+            s.trim().into_deserializer();
+        Ok(TransactionCode::deserialize(de).unwrap_or(TransactionCode::Unknown))
+    }
+}
+
+/// Set of transaction codes parsed from a `notes`/`code` attribute
+///
+/// IB packs several codes into one semicolon-separated string (e.g., `"C;W"`
+/// for a closing trade that triggered a wash sale). `TradeCodes` stores the
+/// known codes as a bit set and keeps any unrecognized tokens verbatim.
+///
+/// # Example
+/// ```
+/// use ib_flex::types::{TradeCodes, TransactionCode};
+///
+/// let codes: TradeCodes = "C;W;P".parse().unwrap();
+/// assert!(codes.is_closing());
+/// assert!(codes.is_wash_sale());
+/// assert!(codes.contains(TransactionCode::Partial));
+/// assert_eq!(codes.to_string(), "C;P;W");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct TradeCodes {
+    bits: u64,
+    unknown: Vec<String>,
+}
+
+impl TradeCodes {
+    /// Creates an empty set
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses an optional attribute value, treating `None` as an empty set
+    pub fn from_notes(notes: Option<&str>) -> Self {
+        notes
+            .map(|s| s.parse().unwrap_or_default())
+            .unwrap_or_default()
+    }
+
+    fn bit(code: TransactionCode) -> u64 {
+        1 << (code as u32)
+    }
+
+    /// Returns true if the set contains `code`
+    pub fn contains(&self, code: TransactionCode) -> bool {
+        code != TransactionCode::Unknown && self.bits & Self::bit(code) != 0
+    }
+
+    /// Adds `code` to the set
+    pub fn insert(&mut self, code: TransactionCode) {
+        if code != TransactionCode::Unknown {
+            self.bits |= Self::bit(code);
+        }
+    }
+
+    /// Removes `code` from the set
+    pub fn remove(&mut self, code: TransactionCode) {
+        if code != TransactionCode::Unknown {
+            self.bits &= !Self::bit(code);
+        }
+    }
+
+    /// Number of known codes in the set
+    pub fn len(&self) -> usize {
+        self.bits.count_ones() as usize
+    }
+
+    /// Returns true if no codes (known or unknown) are present
+    pub fn is_empty(&self) -> bool {
+        self.bits == 0 && self.unknown.is_empty()
+    }
+
+    /// Iterates over the known codes in the set
+    pub fn iter(&self) -> impl Iterator<Item = TransactionCode> + '_ {
+        TransactionCode::ALL
+            .into_iter()
+            .filter(move |code| self.contains(*code))
+    }
+
+    /// Tokens that did not match any known code
+    pub fn unknown(&self) -> &[String] {
+        &self.unknown
+    }
+
+    /// Wash sale (`W`)
+    pub fn is_wash_sale(&self) -> bool {
+        self.contains(TransactionCode::WashSale)
+    }
+
+    /// Opening trade (`O`)
+    pub fn is_opening(&self) -> bool {
+        self.contains(TransactionCode::Opening)
+    }
+
+    /// Closing trade (`C`)
+    pub fn is_closing(&self) -> bool {
+        self.contains(TransactionCode::Closing)
+    }
+
+    /// Partial execution (`P`)
+    pub fn is_partial(&self) -> bool {
+        self.contains(TransactionCode::Partial)
+    }
+
+    /// Option assignment (`A`)
+    pub fn is_assignment(&self) -> bool {
+        self.contains(TransactionCode::Assignment)
+    }
+
+    /// Option exercise (`Ex`)
+    pub fn is_exercise(&self) -> bool {
+        self.contains(TransactionCode::Exercise)
+    }
+
+    /// Expired position (`Ep`)
+    pub fn is_expired(&self) -> bool {
+        self.contains(TransactionCode::Expired)
+    }
+}
+
+impl std::str::FromStr for TradeCodes {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut codes = TradeCodes::new();
+        for token in s.split([';', ',']).map(str::trim).filter(|t| !t.is_empty()) {
+            match token.parse::<TransactionCode>()? {
+                TransactionCode::Unknown => codes.unknown.push(token.to_string()),
+                code => codes.insert(code),
+            }
+        }
+        Ok(codes)
+    }
+}
+
+impl std::fmt::Display for TradeCodes {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut codes: Vec<&str> = self.iter().map(|c| c.as_code()).collect();
+        codes.extend(self.unknown.iter().map(String::as_str));
+        codes.sort_unstable();
+        f.write_str(&codes.join(";"))
+    }
+}
+
+impl FromIterator<TransactionCode> for TradeCodes {
+    fn from_iter<I: IntoIterator<Item = TransactionCode>>(iter: I) -> Self {
+        let mut codes = TradeCodes::new();
+        for code in iter {
+            codes.insert(code);
+        }
+        codes
+    }
+}
+
+/// IB contract identifier
+///
+/// Every instrument IB trades has a numeric `conid` that is stable across
+/// sections and statements, which makes it the natural join key between
+/// trades, positions, and security reference data.
+///
+/// IB leaves `conid` empty, or writes a non-numeric placeholder, on summary
+/// and total rows that aren't about one contract. Those read as `Conid(0)`,
+/// the [`Default`], rather than failing the parse.
+///
+/// # Example
+/// ```
+/// use ib_flex::types::Conid;
+///
+/// let conid: Conid = "265598".parse().unwrap();
+/// assert_eq!(conid, Conid(265598));
+/// assert_eq!(conid.to_string(), "265598");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "serialize", serde(transparent))]
+pub struct Conid(pub u64);
+
+impl Conid {
+    /// Numeric contract ID
+    pub fn get(self) -> u64 {
+        self.0
+    }
+}
+
+impl From<u64> for Conid {
+    fn from(value: u64) -> Self {
+        Conid(value)
+    }
+}
+
+impl std::str::FromStr for Conid {
+    type Err = std::num::ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.trim().parse().map(Conid)
+    }
+}
+
+impl std::fmt::Display for Conid {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Conid {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct ConidVisitor;
+
+        impl serde::de::Visitor<'_> for ConidVisitor {
+            type Value = Conid;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str("a numeric contract ID")
+            }
+
+            fn visit_u64<E: serde::de::Error>(self, v: u64) -> Result<Conid, E> {
+                Ok(Conid(v))
+            }
+
+            fn visit_i64<E: serde::de::Error>(self, v: i64) -> Result<Conid, E> {
+                u64::try_from(v).map(Conid).map_err(E::custom)
+            }
+
+            fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<Conid, E> {
+                Ok(v.parse().unwrap_or_default())
+            }
+
+            fn visit_unit<E: serde::de::Error>(self) -> Result<Conid, E> {
+                Ok(Conid::default())
+            }
+        }
+
+        deserializer.deserialize_any(ConidVisitor)
+    }
+}
+
+/// IB account identifier
+///
+/// Cheap to clone (backed by `Arc<str>`) so it can be copied onto every row
+/// or used as a map key in multi-account code without reallocating.
+///
+/// Deserialization accepts any string, since consolidated and redacted
+/// statements don't always carry a real account number. Use
+/// [`AccountId::kind`] or the strict [`FromStr`](std::str::FromStr) impl to
+/// check the IB format (`U1234567`, `DU1234567`, `F1234567`, ...).
+///
+/// # Example
+/// ```
+/// use ib_flex::types::{AccountId, AccountKind};
+///
+/// let account: AccountId = "DU1234567".parse().unwrap();
+/// assert_eq!(account.kind(), AccountKind::Paper);
+/// assert!(account.is_paper());
+/// assert_eq!(account, "DU1234567");
+/// assert!("1234567".parse::<AccountId>().is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct AccountId(std::sync::Arc<str>);
+
+/// Kind of IB account, derived from the account ID prefix
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AccountKind {
+    /// Live client account (`U` prefix)
+    Individual,
+
+    /// Paper trading account (`DU` prefix)
+    Paper,
+
+    /// Financial advisor or master account (`F` prefix)
+    Advisor,
+
+    /// Paper advisor account (`DF` prefix)
+    PaperAdvisor,
+
+    /// Introducing broker account (`I` prefix)
+    IntroducingBroker,
+
+    /// ID that doesn't match a known IB format
+    Unknown,
+}
+
+impl AccountId {
+    /// Wrap an account ID without validating its format
+    pub fn new(id: impl AsRef<str>) -> Self {
+        AccountId(id.as_ref().into())
+    }
+
+    /// Account ID as a string slice
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Kind of account implied by the ID prefix
+    pub fn kind(&self) -> AccountKind {
+        let id = self.as_str();
+        let split = id.find(|c: char| c.is_ascii_digit()).unwrap_or(id.len());
+        let (prefix, digits) = id.split_at(split);
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return AccountKind::Unknown;
+        }
+        match prefix {
+            "U" => AccountKind::Individual,
+            "DU" => AccountKind::Paper,
+            "F" => AccountKind::Advisor,
+            "DF" => AccountKind::PaperAdvisor,
+            "I" => AccountKind::IntroducingBroker,
+            _ => AccountKind::Unknown,
+        }
+    }
+
+    /// Returns true if the ID matches a known IB account format
+    pub fn is_valid(&self) -> bool {
+        self.kind() != AccountKind::Unknown
+    }
+
+    /// Returns true for paper trading accounts
+    pub fn is_paper(&self) -> bool {
+        matches!(self.kind(), AccountKind::Paper | AccountKind::PaperAdvisor)
+    }
+}
+
+impl std::str::FromStr for AccountId {
+    type Err = crate::error::ParseError;
+
+    /// Parses an account ID, rejecting strings that don't match an IB format
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let id = AccountId::new(s.trim());
+        if id.is_valid() {
+            Ok(id)
+        } else {
+            Err(crate::error::ParseError::UnknownEnumVariant {
+                variant: s.to_string(),
+                enum_type: "AccountId".to_string(),
+                location: None,
+            })
+        }
+    }
+}
+
+impl std::fmt::Display for AccountId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::ops::Deref for AccountId {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for AccountId {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::borrow::Borrow<str> for AccountId {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<&str> for AccountId {
+    fn from(id: &str) -> Self {
+        AccountId::new(id)
+    }
+}
+
+impl From<String> for AccountId {
+    fn from(id: String) -> Self {
+        AccountId(id.into())
+    }
+}
+
+impl PartialEq<str> for AccountId {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for AccountId {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl PartialEq<String> for AccountId {
+    fn eq(&self, other: &String) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+#[cfg(feature = "serialize")]
+impl Serialize for AccountId {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+#[cfg(feature = "schemars")]
+impl JsonSchema for AccountId {
+    fn inline_schema() -> bool {
+        true
+    }
+
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        "AccountId".into()
+    }
+
+    fn json_schema(generator: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        String::json_schema(generator)
+    }
+}
+
+impl<'de> Deserialize<'de> for AccountId {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(AccountId::from)
+    }
+}
+
+/// Define a string-backed IB identifier newtype
+///
+/// IDs are opaque to IB consumers, so these only exist to keep execution,
+/// trade, and order IDs from being mixed up with each other or with free text.
+macro_rules! string_id {
+    ($(#[$meta:meta])* $name:ident) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Default, Deserialize)]
+        #[cfg_attr(feature = "serialize", derive(Serialize))]
+        #[cfg_attr(feature = "schemars", derive(JsonSchema))]
+        #[serde(transparent)]
+        pub struct $name(pub String);
+
+        impl $name {
+            /// The ID as it appears in FLEX XML
+            pub fn as_str(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl std::str::FromStr for $name {
+            type Err = std::convert::Infallible;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                Ok($name(s.trim().to_string()))
+            }
+        }
+
+        impl std::fmt::Display for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str(&self.0)
+            }
+        }
+
+        impl std::ops::Deref for $name {
+            type Target = str;
+
+            fn deref(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl From<&str> for $name {
+            fn from(s: &str) -> Self {
+                $name(s.to_string())
+            }
+        }
+
+        impl From<String> for $name {
+            fn from(s: String) -> Self {
+                $name(s)
+            }
+        }
+
+        impl PartialEq<str> for $name {
+            fn eq(&self, other: &str) -> bool {
+                self.0 == other
+            }
+        }
+
+        impl PartialEq<&str> for $name {
+            fn eq(&self, other: &&str) -> bool {
+                self.0 == *other
+            }
+        }
+    };
+}
+
+string_id!(
+    /// IB execution ID (`execID` / `ibExecID`), unique per fill
+    ExecutionId
+);
+
+string_id!(
+    /// IB trade ID (`tradeID`), used to link corrections back to the original trade
+    TradeId
+);
+
+string_id!(
+    /// IB order ID (`ibOrderID`), shared by every execution of one order
+    ///
+    /// # Example
+    /// ```
+    /// use ib_flex::types::OrderId;
+    ///
+    /// let id: OrderId = "987654321".into();
+    /// assert_eq!(id, "987654321");
+    /// ```
+    OrderId
+);
+
 /// Direction (To/From)
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[serde(remote = "Self")]
 pub enum ToFrom {
     /// To
     To,
@@ -777,27 +1642,35 @@ pub enum ToFrom {
     /// From
     From,
 
-    /// Unknown
-    #[serde(other)]
-    Unknown,
+    /// Unrecognized value, kept verbatim
+    #[serde(untagged)]
+    Unknown(String),
 }
 
 /// Direction (In/Out)
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+///
+/// **Used by**: `Transfer`, `TradeTransfer`
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[serde(remote = "Self")]
 pub enum InOut {
     /// Incoming
+    #[serde(alias = "In")]
     IN,
 
     /// Outgoing
+    #[serde(alias = "Out")]
     OUT,
 
-    /// Unknown
-    #[serde(other)]
-    Unknown,
+    /// Unrecognized value, kept verbatim
+    #[serde(untagged)]
+    Unknown(String),
 }
 
 /// Delivered or Received
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[serde(remote = "Self")]
 pub enum DeliveredReceived {
     /// Delivered
     Delivered,
@@ -805,35 +1678,87 @@ pub enum DeliveredReceived {
     /// Received
     Received,
 
-    /// Unknown
-    #[serde(other)]
-    Unknown,
+    /// Unrecognized value, kept verbatim
+    #[serde(untagged)]
+    Unknown(String),
 }
 
 /// Level of detail for reporting
 ///
 /// Specifies the granularity of data in FLEX reports.
-/// Used by `Trade`, `Position`, and `CashTransaction` structs to indicate
-/// the level of detail requested in the FLEX query.
+/// Used by `Trade`, `Position`, and `CashTransaction` structs to distinguish
+/// execution rows from aggregated summary rows and closing-lot rows.
 ///
 /// **XML Mapping**: Maps to the `levelOfDetail` attribute in various elements.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[serde(remote = "Self")]
 pub enum LevelOfDetail {
     /// Summary level - aggregated data with minimal details
+    #[serde(rename = "SUMMARY", alias = "Summary")]
     Summary,
 
     /// Detail level - standard reporting with all key fields
+    #[serde(rename = "DETAIL", alias = "Detail")]
     Detail,
 
     /// Execution level - detailed execution information including time and venue
+    #[serde(rename = "EXECUTION", alias = "Execution")]
     Execution,
 
+    /// Order level - executions aggregated per order
+    #[serde(rename = "ORDER", alias = "Order")]
+    Order,
+
+    /// Closed lot - tax lot closed by a trade
+    #[serde(rename = "CLOSED_LOT", alias = "ClosedLot")]
+    ClosedLot,
+
     /// Lot level - tax lot level details for cost basis tracking
+    #[serde(rename = "LOT", alias = "Lot")]
     Lot,
 
+    /// Symbol summary - per-symbol aggregate row
+    #[serde(rename = "SYMBOL_SUMMARY")]
+    SymbolSummary,
+
+    /// Asset summary - per-asset-class aggregate row
+    #[serde(rename = "ASSET_SUMMARY")]
+    AssetSummary,
+
+    /// Wash sale adjustment row
+    #[serde(rename = "WASH_SALE")]
+    WashSale,
+
+    /// Contract level (futures)
+    #[serde(rename = "CONTRACT")]
+    Contract,
+
+    /// Per-currency row
+    #[serde(rename = "CURRENCY", alias = "Currency")]
+    Currency,
+
+    /// Base currency summary row
+    #[serde(rename = "BASE_CURRENCY", alias = "BaseCurrency")]
+    BaseCurrency,
+
     /// Unknown or unrecognized level of detail
-    #[serde(other)]
-    Unknown,
+    #[serde(untagged)]
+    Unknown(String),
+}
+
+impl LevelOfDetail {
+    /// Returns true for aggregate rows (summaries) rather than individual
+    /// executions or lots
+    pub fn is_summary(&self) -> bool {
+        matches!(
+            self,
+            LevelOfDetail::Summary
+                | LevelOfDetail::SymbolSummary
+                | LevelOfDetail::AssetSummary
+                | LevelOfDetail::BaseCurrency
+        )
+    }
 }
 
 /// Security identifier type
@@ -844,7 +1769,9 @@ pub enum LevelOfDetail {
 /// **XML Mapping**: Maps to the `securityIDType` attribute in various elements.
 ///
 /// **Used by**: `Trade`, `SecurityInfo`
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[serde(remote = "Self")]
 pub enum SecurityIdType {
     /// CUSIP - Committee on Uniform Securities Identification Procedures
     /// 9-character alphanumeric identifier for North American securities
@@ -867,19 +1794,22 @@ pub enum SecurityIdType {
     Sedol,
 
     /// Unknown or unrecognized security ID type
-    #[serde(other)]
-    Unknown,
+    #[serde(untagged)]
+    Unknown(String),
 }
 
 /// Security sub-category
 ///
 /// Provides additional classification for securities beyond the basic asset category.
 /// Most commonly used for stocks to distinguish between common shares, ETFs, ADRs, REITs, etc.
+/// For derivatives it separates index from single-name contracts, and for bonds the issuer type.
 ///
 /// **XML Mapping**: Maps to the `subCategory` attribute in various elements.
 ///
 /// **Used by**: `Trade`, `Position`, `SecurityInfo`
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[serde(remote = "Self")]
 pub enum SubCategory {
     /// Exchange-traded fund
     #[serde(rename = "ETF")]
@@ -937,9 +1867,68 @@ pub enum SubCategory {
     #[serde(rename = "CEF")]
     ClosedEndFund,
 
+    // --- Derivatives ---
+    /// Option on a single equity
+    #[serde(rename = "EQO")]
+    EquityOption,
+
+    /// Index-based derivative (index options and futures)
+    #[serde(rename = "INDEX", alias = "IDX")]
+    Index,
+
+    /// Energy commodity derivative
+    #[serde(rename = "ENERGY")]
+    Energy,
+
+    /// Metals commodity derivative
+    #[serde(rename = "METAL")]
+    Metal,
+
+    // --- Fixed income ---
+    /// Corporate bond
+    #[serde(rename = "CORP")]
+    Corporate,
+
+    /// Municipal bond
+    #[serde(rename = "MUNI")]
+    Municipal,
+
+    /// US Treasury note
+    #[serde(rename = "T-NOTE")]
+    TreasuryNote,
+
+    /// US Treasury bond
+    #[serde(rename = "T-BOND")]
+    TreasuryBond,
+
     /// Unknown or unrecognized sub-category
-    #[serde(other)]
-    Unknown,
+    #[serde(untagged)]
+    Unknown(String),
+}
+
+impl SubCategory {
+    /// Returns true for exchange-traded and closed-end funds
+    pub fn is_fund(&self) -> bool {
+        matches!(self, SubCategory::Etf | SubCategory::ClosedEndFund)
+    }
+
+    /// Returns true for depositary receipts (ADR, GDR, and generic DR)
+    pub fn is_depositary_receipt(&self) -> bool {
+        matches!(
+            self,
+            SubCategory::Adr | SubCategory::Gdr | SubCategory::DepositaryReceipt
+        )
+    }
+
+    /// Returns true for ordinary equity shares (common or preferred)
+    pub fn is_share(&self) -> bool {
+        matches!(self, SubCategory::Common | SubCategory::Preferred)
+    }
+
+    /// Returns true for derivatives on an index rather than a single name
+    pub fn is_index(&self) -> bool {
+        matches!(self, SubCategory::Index)
+    }
 }
 
 /// Derivative instrument information
@@ -948,7 +1937,9 @@ pub enum SubCategory {
 /// This enum consolidates derivative-specific fields based on the instrument type.
 ///
 /// **Used by**: `Trade`, `Position`
-#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 #[serde(tag = "type")]
 pub enum DerivativeInfo {
     /// Option contract (equity or index option)
@@ -972,7 +1963,7 @@ pub enum DerivativeInfo {
 
         /// IB contract ID of the underlying security
         #[serde(rename = "underlyingConid")]
-        underlying_conid: Option<String>,
+        underlying_conid: Option<Conid>,
     },
 
     /// Future contract
@@ -989,7 +1980,7 @@ pub enum DerivativeInfo {
 
         /// IB contract ID of the underlying asset
         #[serde(rename = "underlyingConid")]
-        underlying_conid: Option<String>,
+        underlying_conid: Option<Conid>,
     },
 
     /// Future option (option on a futures contract)
@@ -1012,7 +2003,7 @@ pub enum DerivativeInfo {
 
         /// IB contract ID of the underlying futures
         #[serde(rename = "underlyingConid")]
-        underlying_conid: Option<String>,
+        underlying_conid: Option<Conid>,
     },
 
     /// Warrant
@@ -1032,10 +2023,341 @@ pub enum DerivativeInfo {
     },
 }
 
+/// Serializer that captures a unit variant's serde name
+///
+/// Lets `Display` reuse the `#[serde(rename)]` attributes so the text form
+/// always matches what IB writes in the XML.
+struct VariantName;
+
+type VariantNameError = serde::de::value::Error;
+
+macro_rules! variant_name_unsupported {
+    ($($method:ident($($arg:ty),*)),+ $(,)?) => {
+        $(
+            fn $method(self, $(_: $arg),*) -> Result<Self::Ok, Self::Error> {
+                Err(serde::ser::Error::custom("expected a unit variant"))
+            }
+        )+
+    };
+}
+
+impl serde::Serializer for VariantName {
+    type Ok = &'static str;
+    type Error = VariantNameError;
+    type SerializeSeq = serde::ser::Impossible<&'static str, VariantNameError>;
+    type SerializeTuple = serde::ser::Impossible<&'static str, VariantNameError>;
+    type SerializeTupleStruct = serde::ser::Impossible<&'static str, VariantNameError>;
+    type SerializeTupleVariant = serde::ser::Impossible<&'static str, VariantNameError>;
+    type SerializeMap = serde::ser::Impossible<&'static str, VariantNameError>;
+    type SerializeStruct = serde::ser::Impossible<&'static str, VariantNameError>;
+    type SerializeStructVariant = serde::ser::Impossible<&'static str, VariantNameError>;
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<Self::Ok, Self::Error> {
+        Ok(variant)
+    }
+
+    variant_name_unsupported!(
+        serialize_bool(bool),
+        serialize_i8(i8),
+        serialize_i16(i16),
+        serialize_i32(i32),
+        serialize_i64(i64),
+        serialize_u8(u8),
+        serialize_u16(u16),
+        serialize_u32(u32),
+        serialize_u64(u64),
+        serialize_f32(f32),
+        serialize_f64(f64),
+        serialize_char(char),
+        serialize_str(&str),
+        serialize_bytes(&[u8]),
+        serialize_none(),
+        serialize_unit(),
+        serialize_unit_struct(&'static str),
+    );
+
+    fn serialize_some<T: ?Sized + Serialize>(self, _: &T) -> Result<Self::Ok, Self::Error> {
+        Err(serde::ser::Error::custom("expected a unit variant"))
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _: &'static str,
+        _: &T,
+    ) -> Result<Self::Ok, Self::Error> {
+        Err(serde::ser::Error::custom("expected a unit variant"))
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: &T,
+    ) -> Result<Self::Ok, Self::Error> {
+        Err(serde::ser::Error::custom("expected a unit variant"))
+    }
+
+    fn serialize_seq(self, _: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+        Err(serde::ser::Error::custom("expected a unit variant"))
+    }
+
+    fn serialize_tuple(self, _: usize) -> Result<Self::SerializeTuple, Self::Error> {
+        Err(serde::ser::Error::custom("expected a unit variant"))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeTupleStruct, Self::Error> {
+        Err(serde::ser::Error::custom("expected a unit variant"))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeTupleVariant, Self::Error> {
+        Err(serde::ser::Error::custom("expected a unit variant"))
+    }
+
+    fn serialize_map(self, _: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+        Err(serde::ser::Error::custom("expected a unit variant"))
+    }
+
+    fn serialize_struct(
+        self,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeStruct, Self::Error> {
+        Err(serde::ser::Error::custom("expected a unit variant"))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeStructVariant, Self::Error> {
+        Err(serde::ser::Error::custom("expected a unit variant"))
+    }
+}
+
+/// Implement `as_str`, `Display`, and `FromStr` for IB code enums using
+/// their serde names, so text round-trips through IB's codes
+///
+/// Each enum has unit variants for known codes plus an untagged
+/// `Unknown(String)` catch-all holding unrecognized values verbatim.
+///
+/// The enums derive with `#[serde(remote = "Self")]`, which makes the
+/// derived code inherent functions; `as_str` reads variant names through
+/// them with or without the `serialize` feature, and the trait impls here
+/// forward to them, `Serialize` only with the feature.
+macro_rules! impl_ib_code_str {
+    ($($ty:ident),+ $(,)?) => {
+        $(
+            impl<'de> Deserialize<'de> for $ty {
+                fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                    $ty::deserialize(deserializer)
+                }
+            }
+
+            #[cfg(feature = "serialize")]
+            impl Serialize for $ty {
+                fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                    $ty::serialize(self, serializer)
+                }
+            }
+
+            impl $ty {
+                /// The value as IB writes it in FLEX XML
+                pub fn as_str(&self) -> &str {
+                    match self {
+                        $ty::Unknown(raw) => raw,
+                        known => $ty::serialize(known, VariantName)
+                            .expect("known variants always serialize to a variant name"),
+                    }
+                }
+
+                /// Returns true for the `Unknown` catch-all
+                pub fn is_unknown(&self) -> bool {
+                    matches!(self, $ty::Unknown(_))
+                }
+            }
+
+            impl std::fmt::Display for $ty {
+                fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    f.write_str(self.as_str())
+                }
+            }
+
+            impl std::str::FromStr for $ty {
+                type Err = crate::error::ParseError;
+
+                /// Parses IB's code (or any accepted alias); unrecognized
+                /// values are kept in `Unknown`
+                fn from_str(s: &str) -> Result<Self, Self::Err> {
+                    use serde::de::IntoDeserializer;
+                    let de: serde::de::value::StrDeserializer<'_, serde::de::value::Error> =
+                        s.trim().into_deserializer();
+                    $ty::deserialize(de).map_err(|_| crate::error::ParseError::UnknownEnumVariant {
+                        variant: s.to_string(),
+                        enum_type: stringify!($ty).to_string(),
+                        location: None,
+                    })
+                }
+            }
+        )+
+    };
+}
+
+impl_ib_code_str!(
+    AssetCategory,
+    BuySell,
+    OpenClose,
+    OrderType,
+    PutCall,
+    LongShort,
+    TradeType,
+    CorporateActionType,
+    OptionAction,
+    TransferType,
+    ToFrom,
+    InOut,
+    DeliveredReceived,
+    LevelOfDetail,
+    SecurityIdType,
+    SubCategory,
+);
+
+impl std::fmt::Display for TransactionCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_code())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_cash_transaction_type_round_trip() {
+        for raw in [
+            "Deposits & Withdrawals",
+            "Dividends",
+            "Withholding Tax",
+            "Payment In Lieu Of Dividends",
+            "Broker Interest Received",
+            "Other Fees",
+        ] {
+            let parsed: CashTransactionType = raw.parse().unwrap();
+            assert!(
+                !matches!(parsed, CashTransactionType::Unknown(_)),
+                "{}",
+                raw
+            );
+            assert_eq!(parsed.to_string(), raw);
+        }
+
+        let parsed: CashTransactionType = "Deposits/Withdrawals".parse().unwrap();
+        assert_eq!(parsed, CashTransactionType::DepositsWithdrawals);
+
+        let parsed: CashTransactionType = "Realized P/L".parse().unwrap();
+        assert_eq!(
+            parsed,
+            CashTransactionType::Unknown("Realized P/L".to_string())
+        );
+        assert_eq!(parsed.as_str(), "Realized P/L");
+    }
+
+    #[test]
+    fn test_level_of_detail_deserialize() {
+        #[derive(Deserialize)]
+        struct Row {
+            #[serde(rename = "@levelOfDetail")]
+            level: LevelOfDetail,
+        }
+
+        for (raw, expected) in [
+            ("EXECUTION", LevelOfDetail::Execution),
+            ("ORDER", LevelOfDetail::Order),
+            ("CLOSED_LOT", LevelOfDetail::ClosedLot),
+            ("LOT", LevelOfDetail::Lot),
+            ("SUMMARY", LevelOfDetail::Summary),
+            ("SYMBOL_SUMMARY", LevelOfDetail::SymbolSummary),
+            ("Currency", LevelOfDetail::Currency),
+            (
+                "SOMETHING_NEW",
+                LevelOfDetail::Unknown("SOMETHING_NEW".into()),
+            ),
+        ] {
+            let xml = format!(r#"<Row levelOfDetail="{}" />"#, raw);
+            let row: Row = quick_xml::de::from_str(&xml).unwrap();
+            assert_eq!(row.level, expected, "{}", raw);
+        }
+
+        assert!(LevelOfDetail::SymbolSummary.is_summary());
+        assert!(!LevelOfDetail::Execution.is_summary());
+    }
+
+    #[test]
+    fn test_trade_codes_parse() {
+        let codes: TradeCodes = "C;W;P".parse().unwrap();
+        assert_eq!(codes.len(), 3);
+        assert!(codes.is_closing());
+        assert!(codes.is_wash_sale());
+        assert!(codes.is_partial());
+        assert!(!codes.is_opening());
+        assert!(codes.unknown().is_empty());
+
+        let codes: TradeCodes = "A; O ,Zz".parse().unwrap();
+        assert!(codes.is_assignment());
+        assert!(codes.is_opening());
+        assert_eq!(codes.unknown(), ["Zz".to_string()]);
+
+        assert!(TradeCodes::from_notes(None).is_empty());
+        assert!(TradeCodes::from_notes(Some("")).is_empty());
+        assert!(TradeCodes::from_notes(Some("Ep")).is_expired());
+        assert!(TradeCodes::from_notes(Some("Ex")).is_exercise());
+    }
+
+    #[test]
+    fn test_trade_codes_set_ops() {
+        let mut codes = TradeCodes::new();
+        codes.insert(TransactionCode::Opening);
+        codes.insert(TransactionCode::WashSale);
+        codes.insert(TransactionCode::Unknown);
+        assert_eq!(codes.len(), 2);
+        assert_eq!(codes.to_string(), "O;W");
+
+        codes.remove(TransactionCode::WashSale);
+        assert!(!codes.is_wash_sale());
+        assert_eq!(
+            codes.iter().collect::<Vec<_>>(),
+            vec![TransactionCode::Opening]
+        );
+    }
+
+    #[test]
+    fn test_transaction_code_round_trip() {
+        for code in TransactionCode::ALL {
+            assert_eq!(code.as_code().parse::<TransactionCode>().unwrap(), code);
+        }
+        assert_eq!(
+            "Pt".parse::<TransactionCode>().unwrap(),
+            TransactionCode::Partial
+        );
+    }
+
     #[test]
     fn test_asset_category_basic() {
         // Test enum construction and comparison
@@ -1065,4 +2387,114 @@ mod tests {
         assert_eq!(call, PutCall::Call);
         assert_ne!(call, PutCall::Put);
     }
+
+    #[test]
+    fn test_account_id_kinds() {
+        let cases = [
+            ("U1234567", AccountKind::Individual),
+            ("DU1234567", AccountKind::Paper),
+            ("F1234567", AccountKind::Advisor),
+            ("DF1234567", AccountKind::PaperAdvisor),
+            ("I1234567", AccountKind::IntroducingBroker),
+            ("X1234567", AccountKind::Unknown),
+            ("U12A4567", AccountKind::Unknown),
+            ("U", AccountKind::Unknown),
+            ("", AccountKind::Unknown),
+        ];
+        for (id, kind) in cases {
+            assert_eq!(AccountId::new(id).kind(), kind, "{}", id);
+            assert_eq!(
+                id.parse::<AccountId>().is_ok(),
+                kind != AccountKind::Unknown
+            );
+        }
+    }
+
+    #[test]
+    fn test_account_id_is_cheap_to_clone() {
+        let id = AccountId::new("U1234567");
+        let clone = id.clone();
+        assert!(std::ptr::eq(id.as_str(), clone.as_str()));
+
+        let mut totals = std::collections::HashMap::new();
+        totals.insert(id, 1);
+        assert_eq!(totals.get("U1234567"), Some(&1));
+    }
+
+    #[test]
+    fn test_enum_display_round_trips_ib_codes() {
+        assert_eq!(AssetCategory::Stock.to_string(), "STK");
+        assert_eq!(
+            "OPT".parse::<AssetCategory>().unwrap(),
+            AssetCategory::Option
+        );
+        assert_eq!(BuySell::Buy.to_string(), "BUY");
+        assert_eq!(PutCall::Put.to_string(), "P");
+        assert_eq!(LevelOfDetail::Execution.to_string(), "EXECUTION");
+        assert_eq!(TransactionCode::WashSale.to_string(), "W");
+
+        for code in TransactionCode::ALL {
+            assert_eq!(code.to_string().parse::<TransactionCode>().unwrap(), code);
+        }
+        for value in [OpenClose::Open, OpenClose::Close] {
+            assert_eq!(value.to_string().parse::<OpenClose>().unwrap(), value);
+        }
+    }
+
+    #[test]
+    fn test_enum_from_str_unknown_values() {
+        let parsed = "NOT_A_CATEGORY".parse::<AssetCategory>().unwrap();
+        assert_eq!(parsed, AssetCategory::Unknown("NOT_A_CATEGORY".into()));
+        assert!(parsed.is_unknown());
+        assert_eq!(parsed.as_str(), "NOT_A_CATEGORY");
+        assert_eq!(parsed.to_string().parse::<AssetCategory>().unwrap(), parsed);
+        assert_eq!(
+            "sideways".parse::<LongShort>().unwrap(),
+            LongShort::Unknown("sideways".into())
+        );
+        assert!(!AssetCategory::Stock.is_unknown());
+    }
+
+    #[test]
+    fn test_asset_and_sub_category_classification() {
+        assert_eq!(
+            "SSF".parse::<AssetCategory>().unwrap(),
+            AssetCategory::SingleStockFuture
+        );
+        assert!(AssetCategory::SingleStockFuture.is_future());
+        assert!(AssetCategory::FutureOption.is_option());
+        assert!(AssetCategory::Cfd.is_derivative());
+        assert!(!AssetCategory::Stock.is_derivative());
+
+        assert_eq!(
+            "COMMON".parse::<SubCategory>().unwrap(),
+            SubCategory::Common
+        );
+        assert_eq!("IDX".parse::<SubCategory>().unwrap(), SubCategory::Index);
+        assert_eq!(SubCategory::Index.as_str(), "INDEX");
+        assert!(SubCategory::Etf.is_fund());
+        assert!(SubCategory::Adr.is_depositary_receipt());
+        assert!(SubCategory::Preferred.is_share());
+        assert!(!SubCategory::EquityOption.is_index());
+    }
+
+    #[test]
+    fn test_unknown_variant_survives_serde_round_trip() {
+        #[derive(Deserialize)]
+        #[cfg_attr(feature = "serialize", derive(Serialize))]
+        struct Row {
+            #[serde(rename = "@assetCategory")]
+            asset_category: AssetCategory,
+            #[serde(rename = "@buySell")]
+            buy_sell: BuySell,
+        }
+
+        let xml = r#"<Row assetCategory="SPACE" buySell="BUY"/>"#;
+        let row: Row = quick_xml::de::from_str(xml).unwrap();
+        assert_eq!(row.asset_category, AssetCategory::Unknown("SPACE".into()));
+        assert_eq!(row.buy_sell, BuySell::Buy);
+
+        let out = quick_xml::se::to_string(&row).unwrap();
+        assert_eq!(out, xml);
+    }
 }
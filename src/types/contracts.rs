@@ -0,0 +1,419 @@
+//! Normalized derivative contract descriptions
+//!
+//! IB identifies the same option in different ways depending on the section:
+//! OSI-style symbols like `AAPL  250221C00180000` on trades and positions,
+//! separate `strike`/`expiry`/`putCall` attributes elsewhere. [`OptionContract`]
+//! normalizes both so records can be joined on the contract itself.
+//! [`FuturesContract`] does the same for futures symbols like `ESH5`.
+
+use chrono::{Datelike, NaiveDate};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+
+use super::activity::{Position, Trade};
+use super::common::{AssetCategory, PutCall};
+use crate::error::{ParseError, Result};
+
+/// An option contract: underlying, expiry, right, and strike
+///
+/// # Example
+/// ```
+/// use ib_flex::types::{OptionContract, PutCall};
+/// use rust_decimal::Decimal;
+///
+/// let contract: OptionContract = "AAPL  250221C00180000".parse().unwrap();
+/// assert_eq!(contract.underlying, "AAPL");
+/// assert_eq!(contract.put_call, PutCall::Call);
+/// assert_eq!(contract.strike, Decimal::from(180));
+///
+/// // Compact symbols without padding normalize to the canonical form
+/// let compact: OptionContract = "AAPL250221C00180000".parse().unwrap();
+/// assert_eq!(compact, contract);
+/// assert_eq!(compact.osi_symbol(), "AAPL  250221C00180000");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct OptionContract {
+    /// Underlying root symbol (e.g., "AAPL", "SPXW")
+    pub underlying: String,
+    /// Expiration date
+    pub expiry: NaiveDate,
+    /// Call or put
+    pub put_call: PutCall,
+    /// Strike price
+    pub strike: Decimal,
+}
+
+impl OptionContract {
+    /// Build a contract from separate fields
+    pub fn new(
+        underlying: impl Into<String>,
+        expiry: NaiveDate,
+        put_call: PutCall,
+        strike: Decimal,
+    ) -> Self {
+        OptionContract {
+            underlying: underlying.into().trim().to_uppercase(),
+            expiry,
+            put_call,
+            strike: strike.normalize(),
+        }
+    }
+
+    /// Parse an OCC/OSI option symbol
+    ///
+    /// The last 15 characters are `YYMMDD`, `C` or `P`, and the strike times
+    /// 1000 as 8 digits; everything before them is the underlying root,
+    /// with or without IB's space padding to six characters.
+    pub fn parse_osi(symbol: &str) -> Result<Self> {
+        let invalid = || ParseError::InvalidSymbol {
+            symbol: symbol.to_string(),
+            kind: "OSI option".to_string(),
+        };
+        let trimmed = symbol.trim();
+        if trimmed.len() < 16 || !trimmed.is_ascii() {
+            return Err(invalid());
+        }
+        let (root, tail) = trimmed.split_at(trimmed.len() - 15);
+        let root = root.trim_end();
+        if root.is_empty() {
+            return Err(invalid());
+        }
+
+        let expiry = NaiveDate::parse_from_str(&tail[..6], "%y%m%d").map_err(|_| invalid())?;
+        let put_call = match &tail[6..7] {
+            "C" | "c" => PutCall::Call,
+            "P" | "p" => PutCall::Put,
+            _ => return Err(invalid()),
+        };
+        let strike_digits = &tail[7..];
+        if !strike_digits.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(invalid());
+        }
+        let strike = Decimal::new(strike_digits.parse().map_err(|_| invalid())?, 3);
+
+        Ok(OptionContract::new(root, expiry, put_call, strike))
+    }
+
+    /// Canonical OSI symbol, with the root padded to six characters
+    pub fn osi_symbol(&self) -> String {
+        let right = match self.put_call {
+            PutCall::Put => 'P',
+            _ => 'C',
+        };
+        let strike = (self.strike * Decimal::from(1000))
+            .trunc()
+            .to_u64()
+            .unwrap_or_default();
+        format!(
+            "{:<6}{}{}{:08}",
+            self.underlying,
+            self.expiry.format("%y%m%d"),
+            right,
+            strike
+        )
+    }
+}
+
+impl std::str::FromStr for OptionContract {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        OptionContract::parse_osi(s)
+    }
+}
+
+impl std::fmt::Display for OptionContract {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.osi_symbol())
+    }
+}
+
+/// Builds an [`OptionContract`] from a row's flat fields, falling back to
+/// parsing its symbol
+macro_rules! impl_option_contract {
+    ($($ty:ty),+) => {
+        $(
+            impl $ty {
+                /// The option contract this row refers to
+                ///
+                /// Uses `underlyingSymbol`, `expiry`, `putCall`, and `strike`
+                /// when all are present, otherwise parses `symbol` as OSI.
+                /// Returns `None` for non-option rows.
+                pub fn option_contract(&self) -> Option<OptionContract> {
+                    if !matches!(
+                        self.asset_category,
+                        AssetCategory::Option | AssetCategory::FutureOption
+                    ) {
+                        return None;
+                    }
+                    let from_fields = || {
+                        Some(OptionContract::new(
+                            self.underlying_symbol.as_deref().filter(|s| !s.is_empty())?,
+                            self.expiry?,
+                            self.put_call.clone().filter(|pc| !pc.is_unknown())?,
+                            self.strike?,
+                        ))
+                    };
+                    from_fields().or_else(|| OptionContract::parse_osi(&self.symbol).ok())
+                }
+            }
+        )+
+    };
+}
+
+impl_option_contract!(Trade, Position);
+
+/// CME month codes, January through December
+const MONTH_CODES: [char; 12] = ['F', 'G', 'H', 'J', 'K', 'M', 'N', 'Q', 'U', 'V', 'X', 'Z'];
+
+/// A futures contract: root symbol and contract month
+///
+/// Contracts order by root, then contract month, so sorting a set of
+/// contracts yields each root's roll sequence.
+///
+/// # Example
+/// ```
+/// use chrono::NaiveDate;
+/// use ib_flex::types::FuturesContract;
+///
+/// let today = NaiveDate::from_ymd_opt(2025, 1, 15).unwrap();
+/// let es = FuturesContract::parse("ESH5", today).unwrap();
+/// assert_eq!(es.root, "ES");
+/// assert_eq!((es.year, es.month), (2025, 3));
+/// assert_eq!(es.month_code(), 'H');
+/// assert_eq!(es.to_string(), "ESH5");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct FuturesContract {
+    /// Root symbol (e.g., "ES", "CL")
+    pub root: String,
+    /// Contract year
+    pub year: i32,
+    /// Contract month (1-12)
+    pub month: u32,
+    /// Last trading date, when known from the statement
+    pub expiry: Option<NaiveDate>,
+}
+
+impl FuturesContract {
+    /// Build a contract from its root and expiry date
+    ///
+    /// Uses the expiry's month as the contract month. That holds for equity
+    /// index and metals futures; energy contracts such as CL expire in the
+    /// month before delivery, so prefer [`FuturesContract::parse`] on the
+    /// symbol when it's available.
+    pub fn from_expiry(root: impl Into<String>, expiry: NaiveDate) -> Self {
+        FuturesContract {
+            root: root.into().trim().to_uppercase(),
+            year: expiry.year(),
+            month: expiry.month(),
+            expiry: Some(expiry),
+        }
+    }
+
+    /// Parse a symbol like `ESH5` or `ESH25`
+    ///
+    /// Single-digit years are ambiguous, so they resolve to the first
+    /// matching year no earlier than one year before `reference` (usually
+    /// the statement or trade date).
+    pub fn parse(symbol: &str, reference: NaiveDate) -> Result<Self> {
+        let invalid = || ParseError::InvalidSymbol {
+            symbol: symbol.to_string(),
+            kind: "futures".to_string(),
+        };
+        let trimmed = symbol.trim();
+        let digits = trimmed
+            .bytes()
+            .rev()
+            .take_while(|b| b.is_ascii_digit())
+            .count();
+        if !(1..=2).contains(&digits) || trimmed.len() < digits + 2 {
+            return Err(invalid());
+        }
+        let (head, year_digits) = trimmed.split_at(trimmed.len() - digits);
+        let code = head.chars().last().ok_or_else(invalid)?;
+        let root = &head[..head.len() - code.len_utf8()];
+        if root.is_empty() {
+            return Err(invalid());
+        }
+        let month = MONTH_CODES
+            .iter()
+            .position(|&c| c == code.to_ascii_uppercase())
+            .ok_or_else(invalid)? as u32
+            + 1;
+
+        let modulus = 10i32.pow(digits as u32);
+        let suffix: i32 = year_digits.parse().map_err(|_| invalid())?;
+        let earliest = reference.year() - 1;
+        let mut year = earliest - earliest.rem_euclid(modulus) + suffix;
+        if year < earliest {
+            year += modulus;
+        }
+
+        Ok(FuturesContract {
+            root: root.to_uppercase(),
+            year,
+            month,
+            expiry: None,
+        })
+    }
+
+    /// CME month code letter for the contract month
+    pub fn month_code(&self) -> char {
+        MONTH_CODES[(self.month.clamp(1, 12) - 1) as usize]
+    }
+
+    /// First day of the contract month
+    ///
+    /// A month outside 1-12 is clamped, and a year outside chrono's range
+    /// gives [`NaiveDate::MIN`] or [`NaiveDate::MAX`].
+    pub fn contract_month(&self) -> NaiveDate {
+        NaiveDate::from_ymd_opt(self.year, self.month.clamp(1, 12), 1).unwrap_or(if self.year < 0 {
+            NaiveDate::MIN
+        } else {
+            NaiveDate::MAX
+        })
+    }
+
+    /// Expiry date if known, otherwise the first day of the contract month
+    pub fn expiry_or_month(&self) -> NaiveDate {
+        self.expiry.unwrap_or_else(|| self.contract_month())
+    }
+}
+
+impl std::fmt::Display for FuturesContract {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}{}{}",
+            self.root,
+            self.month_code(),
+            self.year.rem_euclid(10)
+        )
+    }
+}
+
+/// Builds a [`FuturesContract`] from a row's symbol, backed by its expiry
+macro_rules! impl_futures_contract {
+    ($($ty:ty => |$row:ident| $reference:expr;)+) => {
+        $(
+            impl $ty {
+                /// The futures contract this row refers to
+                ///
+                /// Parses `symbol` (resolving the year against the row's
+                /// date) and attaches `expiry` when reported. Falls back to
+                /// `underlyingSymbol` and `expiry` if the symbol doesn't parse.
+                /// Returns `None` for non-futures rows.
+                pub fn futures_contract(&self) -> Option<FuturesContract> {
+                    if !self.asset_category.is_future() {
+                        return None;
+                    }
+                    let $row = self;
+                    let reference = $reference.or(self.expiry)?;
+                    match FuturesContract::parse(&self.symbol, reference) {
+                        Ok(mut contract) => {
+                            contract.expiry = self.expiry;
+                            Some(contract)
+                        }
+                        Err(_) => Some(FuturesContract::from_expiry(
+                            self.underlying_symbol.as_deref().filter(|s| !s.is_empty())?,
+                            self.expiry?,
+                        )),
+                    }
+                }
+            }
+        )+
+    };
+}
+
+impl_futures_contract! {
+    Trade => |t| t.trade_date.or(t.report_date);
+    Position => |p| Some(p.report_date);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_osi_symbols() {
+        let spx: OptionContract = "SPXW  250117P05875500".parse().unwrap();
+        assert_eq!(spx.underlying, "SPXW");
+        assert_eq!(spx.expiry, NaiveDate::from_ymd_opt(2025, 1, 17).unwrap());
+        assert_eq!(spx.put_call, PutCall::Put);
+        assert_eq!(spx.strike, Decimal::new(58755, 1));
+        assert_eq!(spx.to_string(), "SPXW  250117P05875500");
+
+        let long_root: OptionContract = "BRKB1 250321C00450000".parse().unwrap();
+        assert_eq!(long_root.underlying, "BRKB1");
+    }
+
+    #[test]
+    fn test_parse_osi_rejects_malformed() {
+        for bad in [
+            "",
+            "AAPL",
+            "250221C00180000",
+            "AAPL  251321C00180000",
+            "AAPL  250221X00180000",
+            "AAPL  250221C0018000A",
+        ] {
+            assert!(OptionContract::parse_osi(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_fields_and_symbol_agree() {
+        let from_fields = OptionContract::new(
+            "aapl",
+            NaiveDate::from_ymd_opt(2025, 2, 21).unwrap(),
+            PutCall::Call,
+            Decimal::new(18000, 2),
+        );
+        assert_eq!(
+            from_fields,
+            OptionContract::parse_osi("AAPL  250221C00180000").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parse_futures_symbols() {
+        let jan_2025 = NaiveDate::from_ymd_opt(2025, 1, 15).unwrap();
+        let cl = FuturesContract::parse("CLG5", jan_2025).unwrap();
+        assert_eq!((cl.root.as_str(), cl.year, cl.month), ("CL", 2025, 2));
+
+        // Single-digit years roll into the next decade when needed
+        let dec_2029 = NaiveDate::from_ymd_opt(2029, 12, 1).unwrap();
+        assert_eq!(FuturesContract::parse("ESH0", dec_2029).unwrap().year, 2030);
+        assert_eq!(FuturesContract::parse("ESZ8", dec_2029).unwrap().year, 2028);
+        assert_eq!(
+            FuturesContract::parse("ESH26", jan_2025).unwrap().year,
+            2026
+        );
+
+        for bad in ["", "ES", "H5", "ESA5", "ESH", "ESH123"] {
+            assert!(FuturesContract::parse(bad, jan_2025).is_err(), "{}", bad);
+        }
+
+        // Years past chrono's range still parse, and don't panic later
+        let far = FuturesContract::parse("ESZ9", NaiveDate::MAX).unwrap();
+        assert_eq!(far.contract_month(), NaiveDate::MAX);
+        assert_eq!(FuturesContract { month: 0, ..far }.month_code(), 'F');
+    }
+
+    #[test]
+    fn test_futures_roll_ordering() {
+        let today = NaiveDate::from_ymd_opt(2025, 1, 15).unwrap();
+        let mut contracts: Vec<_> = ["ESZ5", "ESH5", "ESM5", "ESU5"]
+            .iter()
+            .map(|s| FuturesContract::parse(s, today).unwrap())
+            .collect();
+        contracts.sort();
+        let symbols: Vec<_> = contracts.iter().map(|c| c.to_string()).collect();
+        assert_eq!(symbols, ["ESH5", "ESM5", "ESU5", "ESZ5"]);
+        assert_eq!(
+            contracts[0].contract_month(),
+            NaiveDate::from_ymd_opt(2025, 3, 1).unwrap()
+        );
+    }
+}
@@ -0,0 +1,216 @@
+//! ISO 4217 currency codes (requires the `iso-currency` feature)
+//!
+//! FLEX statements carry currencies as plain strings. [`Currency`] validates
+//! and normalizes those strings so that typos and case differences surface
+//! as errors instead of silently splitting aggregates.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use super::activity::{CashTransaction, ConversionRate, Position, Trade};
+use crate::error::ParseError;
+
+macro_rules! iso_currencies {
+    ($($variant:ident => $name:literal,)+) => {
+        /// Currency code as reported by IB
+        ///
+        /// Known ISO 4217 codes map to named variants. Well-formed codes that
+        /// aren't in the list (e.g., IB's offshore yuan `CNH`) fall back to
+        /// `Other`, and IB's `BASE_SUMMARY` pseudo-currency gets its own variant.
+        ///
+        /// # Example
+        /// ```
+        /// use ib_flex::types::Currency;
+        ///
+        /// assert_eq!("usd".parse::<Currency>().unwrap(), Currency::USD);
+        /// assert_eq!("CNH".parse::<Currency>().unwrap(), Currency::Other("CNH".into()));
+        /// assert!("US$".parse::<Currency>().is_err());
+        /// ```
+        #[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+        pub enum Currency {
+            $(
+                #[doc = $name]
+                $variant,
+            )+
+            /// Aggregate row across all currencies, expressed in base currency
+            BaseSummary,
+            /// Well-formed three-letter code not in the known list
+            Other(String),
+        }
+
+        impl Currency {
+            /// All named ISO currencies
+            pub const ALL: &'static [Currency] = &[$(Currency::$variant,)+];
+
+            /// Three-letter code as it appears in FLEX XML
+            pub fn as_str(&self) -> &str {
+                match self {
+                    $(Currency::$variant => stringify!($variant),)+
+                    Currency::BaseSummary => "BASE_SUMMARY",
+                    Currency::Other(code) => code,
+                }
+            }
+
+            /// English name of the currency, if known
+            pub fn name(&self) -> Option<&'static str> {
+                match self {
+                    $(Currency::$variant => Some($name),)+
+                    Currency::BaseSummary | Currency::Other(_) => None,
+                }
+            }
+
+            fn from_known(code: &str) -> Option<Self> {
+                match code {
+                    $(stringify!($variant) => Some(Currency::$variant),)+
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+iso_currencies! {
+    AED => "UAE Dirham",
+    AUD => "Australian Dollar",
+    BRL => "Brazilian Real",
+    CAD => "Canadian Dollar",
+    CHF => "Swiss Franc",
+    CLP => "Chilean Peso",
+    CNY => "Chinese Yuan Renminbi",
+    CZK => "Czech Koruna",
+    DKK => "Danish Krone",
+    EUR => "Euro",
+    GBP => "Pound Sterling",
+    HKD => "Hong Kong Dollar",
+    HUF => "Hungarian Forint",
+    IDR => "Indonesian Rupiah",
+    ILS => "Israeli New Shekel",
+    INR => "Indian Rupee",
+    JPY => "Japanese Yen",
+    KRW => "South Korean Won",
+    MXN => "Mexican Peso",
+    MYR => "Malaysian Ringgit",
+    NOK => "Norwegian Krone",
+    NZD => "New Zealand Dollar",
+    PHP => "Philippine Peso",
+    PLN => "Polish Zloty",
+    RON => "Romanian Leu",
+    RUB => "Russian Ruble",
+    SAR => "Saudi Riyal",
+    SEK => "Swedish Krona",
+    SGD => "Singapore Dollar",
+    THB => "Thai Baht",
+    TRY => "Turkish Lira",
+    TWD => "New Taiwan Dollar",
+    USD => "US Dollar",
+    ZAR => "South African Rand",
+}
+
+impl Currency {
+    /// Returns true for named ISO 4217 currencies
+    pub fn is_iso(&self) -> bool {
+        !matches!(self, Currency::BaseSummary | Currency::Other(_))
+    }
+
+    /// Returns true for IB's `BASE_SUMMARY` aggregate rows
+    pub fn is_base_summary(&self) -> bool {
+        matches!(self, Currency::BaseSummary)
+    }
+}
+
+impl std::str::FromStr for Currency {
+    type Err = ParseError;
+
+    /// Parses a code case-insensitively, rejecting anything that isn't three
+    /// ASCII letters or `BASE_SUMMARY`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let code = s.trim().to_ascii_uppercase();
+        if code == "BASE_SUMMARY" {
+            return Ok(Currency::BaseSummary);
+        }
+        if code.len() != 3 || !code.bytes().all(|b| b.is_ascii_uppercase()) {
+            return Err(ParseError::UnknownEnumVariant {
+                variant: s.to_string(),
+                enum_type: "Currency".to_string(),
+                location: None,
+            });
+        }
+        Ok(Currency::from_known(&code).unwrap_or(Currency::Other(code)))
+    }
+}
+
+impl std::fmt::Display for Currency {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Serialize for Currency {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Currency {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+macro_rules! impl_currency_code {
+    ($($ty:ident),+) => {
+        $(
+            impl $ty {
+                /// Parsed and validated [`Currency`] for this row
+                pub fn currency_code(&self) -> Result<Currency, ParseError> {
+                    self.currency.parse()
+                }
+            }
+        )+
+    };
+}
+
+impl_currency_code!(Trade, Position, CashTransaction);
+
+impl ConversionRate {
+    /// Parsed source and target currencies for this rate
+    pub fn currency_pair(&self) -> Result<(Currency, Currency), ParseError> {
+        Ok((self.from_currency.parse()?, self.to_currency.parse()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_known_and_exotic_codes() {
+        assert_eq!("EUR".parse::<Currency>().unwrap(), Currency::EUR);
+        assert_eq!(" gbp ".parse::<Currency>().unwrap(), Currency::GBP);
+        assert_eq!(
+            "cnh".parse::<Currency>().unwrap(),
+            Currency::Other("CNH".to_string())
+        );
+        assert_eq!(
+            "BASE_SUMMARY".parse::<Currency>().unwrap(),
+            Currency::BaseSummary
+        );
+    }
+
+    #[test]
+    fn test_parse_rejects_malformed_codes() {
+        for bad in ["", "US", "USDD", "U$D", "12A"] {
+            assert!(bad.parse::<Currency>().is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for currency in Currency::ALL {
+            assert_eq!(&currency.as_str().parse::<Currency>().unwrap(), currency);
+            assert!(currency.is_iso());
+            assert!(currency.name().is_some());
+        }
+        assert_eq!(Currency::BaseSummary.to_string(), "BASE_SUMMARY");
+    }
+}
@@ -0,0 +1,267 @@
+//! Finding and removing repeated transactions
+//!
+//! Every trade, cash transaction, and corporate action has an ID, but a
+//! parse result can hold a row more than once: a query with overlapping
+//! level-of-detail settings repeats rows within a statement, and a
+//! backfill of overlapping periods repeats them across statements.
+//! [`find_duplicates`] lists each repeated ID with the rows that carry it,
+//! and [`canonicalize`] removes all but the first.
+//!
+//! Rows are keyed by `transactionID`, or for trade executions without one
+//! by `tradeID`, then `ibExecID`, and only compared with rows at the same
+//! level of detail: a `CLOSED_LOT` row repeating its execution's `tradeID`
+//! is not a duplicate.
+//!
+//! # Example
+//! ```
+//! use ib_flex::parse_activity_flex;
+//! use ib_flex::types::dedup::{canonicalize, find_duplicates};
+//!
+//! let xml = include_str!("../../tests/fixtures/activity_minimal.xml");
+//! let statement = parse_activity_flex(xml).unwrap();
+//! let mut statements = vec![statement.clone(), statement];
+//!
+//! let report = find_duplicates(&statements);
+//! assert_eq!(
+//!     report.to_string(),
+//!     "  Trades transactionID 567890123: FlexStatement[0]/Trades/Trade[0], FlexStatement[1]/Trades/Trade[0]\n"
+//! );
+//! assert_eq!(canonicalize(&mut statements), 1);
+//! assert!(statements[1].trades.items.is_empty());
+//! ```
+
+use std::collections::HashMap;
+use std::fmt;
+
+use super::activity::{ActivityFlexStatement, CashTransaction, CorporateAction, Trade};
+use super::common::LevelOfDetail;
+
+/// An ID carried by more than one row
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Duplicate {
+    /// Section the rows are in (e.g. `Trades`)
+    pub section: String,
+    /// XML attribute the rows share: `transactionID`, `tradeID`, or
+    /// `ibExecID`
+    pub field: String,
+    /// The repeated ID
+    pub id: String,
+    /// Every row carrying it, in document order, as in
+    /// `FlexStatement[1]/Trades/Trade[0]`; the statement is left out when
+    /// there is only one
+    pub rows: Vec<String>,
+}
+
+impl fmt::Display for Duplicate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {}: {}",
+            self.section,
+            self.field,
+            self.id,
+            self.rows.join(", ")
+        )
+    }
+}
+
+/// Repeated IDs in a parse result, in order of first appearance
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct DuplicateReport {
+    /// Each repeated ID once
+    pub items: Vec<Duplicate>,
+}
+
+impl DuplicateReport {
+    /// Returns true if no ID is repeated
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+}
+
+impl fmt::Display for DuplicateReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for item in &self.items {
+            writeln!(f, "  {}", item)?;
+        }
+        Ok(())
+    }
+}
+
+/// List IDs repeated within or across `statements`
+pub fn find_duplicates(statements: &[ActivityFlexStatement]) -> DuplicateReport {
+    let mut report = DuplicateReport::default();
+    for (section, rows) in keyed_rows(statements) {
+        let mut index: HashMap<&Key, usize> = HashMap::new();
+        let mut groups: Vec<(&Key, Vec<String>)> = Vec::new();
+        for (key, path) in &rows {
+            match index.get(key) {
+                Some(&i) => groups[i].1.push(path.clone()),
+                None => {
+                    index.insert(key, groups.len());
+                    groups.push((key, vec![path.clone()]));
+                }
+            }
+        }
+        report
+            .items
+            .extend(groups.into_iter().filter(|(_, rows)| rows.len() > 1).map(
+                |((_, field, id), rows)| Duplicate {
+                    section: section.to_string(),
+                    field: field.to_string(),
+                    id: id.clone(),
+                    rows,
+                },
+            ));
+    }
+    report
+}
+
+/// Remove every row repeating an earlier row's ID, returning how many were
+/// removed
+///
+/// The first row with each ID is kept, counting statements in order, so
+/// the result only depends on the order of `statements`.
+pub fn canonicalize(statements: &mut [ActivityFlexStatement]) -> usize {
+    let mut removed = 0;
+    let mut trades = Seen::default();
+    let mut cash = Seen::default();
+    let mut actions = Seen::default();
+    for statement in statements.iter_mut() {
+        removed += trades.retain(&mut statement.trades.items, trade_key);
+        removed += cash.retain(&mut statement.cash_transactions.items, cash_key);
+        removed += actions.retain(&mut statement.corporate_actions.items, action_key);
+    }
+    removed
+}
+
+/// Level of detail, keyed attribute, and ID
+type Key = (Option<String>, &'static str, String);
+
+#[derive(Default)]
+struct Seen(std::collections::HashSet<Key>);
+
+impl Seen {
+    fn retain<T>(&mut self, rows: &mut Vec<T>, key: fn(&T) -> Option<Key>) -> usize {
+        let before = rows.len();
+        rows.retain(|row| key(row).map_or(true, |key| self.0.insert(key)));
+        before - rows.len()
+    }
+}
+
+/// Each section's keyed rows with their paths, in document order
+fn keyed_rows(statements: &[ActivityFlexStatement]) -> Vec<(&'static str, Vec<(Key, String)>)> {
+    let prefix = |i: usize| match statements.len() {
+        1 => String::new(),
+        _ => format!("FlexStatement[{}]/", i),
+    };
+    let mut trades = Vec::new();
+    let mut cash = Vec::new();
+    let mut actions = Vec::new();
+    for (i, statement) in statements.iter().enumerate() {
+        let prefix = prefix(i);
+        collect(&mut trades, &statement.trades.items, trade_key, |j| {
+            format!("{}Trades/Trade[{}]", prefix, j)
+        });
+        collect(
+            &mut cash,
+            &statement.cash_transactions.items,
+            cash_key,
+            |j| format!("{}CashTransactions/CashTransaction[{}]", prefix, j),
+        );
+        collect(
+            &mut actions,
+            &statement.corporate_actions.items,
+            action_key,
+            |j| format!("{}CorporateActions/CorporateAction[{}]", prefix, j),
+        );
+    }
+    vec![
+        ("Trades", trades),
+        ("CashTransactions", cash),
+        ("CorporateActions", actions),
+    ]
+}
+
+fn collect<T>(
+    keyed: &mut Vec<(Key, String)>,
+    rows: &[T],
+    key: fn(&T) -> Option<Key>,
+    path: impl Fn(usize) -> String,
+) {
+    for (j, row) in rows.iter().enumerate() {
+        if let Some(key) = key(row) {
+            keyed.push((key, path(j)));
+        }
+    }
+}
+
+fn id(id: Option<&str>) -> Option<String> {
+    id.map(str::trim)
+        .filter(|id| !id.is_empty())
+        .map(str::to_string)
+}
+
+fn level(level: &Option<LevelOfDetail>) -> Option<String> {
+    level.as_ref().map(|level| format!("{:?}", level))
+}
+
+fn trade_key(trade: &Trade) -> Option<Key> {
+    let execution = matches!(trade.level_of_detail, None | Some(LevelOfDetail::Execution));
+    let (field, id) = match id(trade.transaction_id.as_deref()) {
+        Some(id) => ("transactionID", id),
+        None if execution => match id(trade.trade_id.as_ref().map(|id| id.as_str())) {
+            Some(id) => ("tradeID", id),
+            None => (
+                "ibExecID",
+                id(trade.ib_exec_id.as_ref().map(|id| id.as_str()))?,
+            ),
+        },
+        None => return None,
+    };
+    Some((level(&trade.level_of_detail), field, id))
+}
+
+fn cash_key(tx: &CashTransaction) -> Option<Key> {
+    let id = id(tx.transaction_id.as_deref())?;
+    Some((level(&tx.level_of_detail), "transactionID", id))
+}
+
+fn action_key(action: &CorporateAction) -> Option<Key> {
+    let id = id(action.transaction_id.as_deref())?;
+    Some((action.level_of_detail.clone(), "transactionID", id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_duplicates_within_a_statement() {
+        let xml = include_str!("../../tests/fixtures/activity_daily_portfolio.xml");
+        let mut statement = crate::parse_activity_flex(xml).unwrap();
+        assert!(find_duplicates(std::slice::from_ref(&statement)).is_empty());
+
+        let trade = statement.trades.items[1].clone();
+        statement.trades.items.push(trade.clone());
+        let mut lot = trade;
+        lot.level_of_detail = Some(LevelOfDetail::ClosedLot);
+        statement.trades.items.push(lot);
+        let tx = statement.cash_transactions.items[0].clone();
+        statement.cash_transactions.items.insert(0, tx);
+
+        let mut statements = vec![statement];
+        let report = find_duplicates(&statements);
+        let rows: Vec<_> = report.items.iter().map(|d| d.rows.join(" ")).collect();
+        assert_eq!(
+            rows,
+            [
+                "Trades/Trade[1] Trades/Trade[3]",
+                "CashTransactions/CashTransaction[0] CashTransactions/CashTransaction[1]",
+            ]
+        );
+        assert_eq!(canonicalize(&mut statements), 2);
+        assert_eq!(statements[0].trades.items.len(), 4);
+        assert!(find_duplicates(&statements).is_empty());
+    }
+}
@@ -0,0 +1,316 @@
+//! Comparing two versions of a statement (requires the `serialize` and `eq`
+//! features, on by default)
+//!
+//! IB occasionally restates history: trades are busted or corrected and
+//! dividends are reversed and rebooked. [`diff_statements`] compares two
+//! statements for the same period (an earlier download and a fresh one)
+//! and reports transactions present in only one of them, plus field-level
+//! changes to those present in both.
+//!
+//! # Example
+//! ```
+//! use ib_flex::{diff_statements, parse_activity_flex};
+//! use rust_decimal::Decimal;
+//!
+//! let xml = include_str!("../../tests/fixtures/activity_minimal.xml");
+//! let before = parse_activity_flex(xml).unwrap();
+//! let mut after = before.clone();
+//! after.trades.items[0].price = Some(Decimal::new(18550, 2));
+//!
+//! let diff = diff_statements(&before, &after);
+//! for change in &diff.trades.changed {
+//!     for field in &change.fields {
+//!         println!("{} {}: {:?} -> {:?}", change.id, field.field, field.before, field.after);
+//!     }
+//! }
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+
+use quick_xml::events::Event;
+use serde::Deserialize;
+#[cfg(feature = "serialize")]
+use serde::Serialize;
+
+use super::activity::{ActivityFlexStatement, CashTransaction, CorporateAction, Trade};
+use super::extended::{DepositWithdrawal, OptionEAE, Transfer};
+use super::merge::{cash_transaction_key, corporate_action_key, id, trade_key};
+
+/// One attribute whose value differs between two versions of a record
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct FieldChange {
+    /// XML attribute name, as IB writes it (e.g. `tradePrice`)
+    pub field: String,
+    /// Value in the first statement, `None` when empty or absent
+    pub before: Option<String>,
+    /// Value in the second statement, `None` when empty or absent
+    pub after: Option<String>,
+}
+
+/// A record present in both statements with different values
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct RecordChange<T> {
+    /// ID the records were matched on
+    pub id: String,
+    /// Record in the first statement
+    pub before: T,
+    /// Record in the second statement
+    pub after: T,
+    /// Attributes that differ, in attribute name order
+    pub fields: Vec<FieldChange>,
+}
+
+/// Differences in one section
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct SectionDiff<T> {
+    /// Records only in the first statement
+    pub removed: Vec<T>,
+    /// Records only in the second statement
+    pub added: Vec<T>,
+    /// Records in both with changed values
+    pub changed: Vec<RecordChange<T>>,
+}
+
+impl<T> Default for SectionDiff<T> {
+    fn default() -> Self {
+        SectionDiff {
+            removed: Vec::new(),
+            added: Vec::new(),
+            changed: Vec::new(),
+        }
+    }
+}
+
+impl<T> SectionDiff<T> {
+    /// Returns true if the section is the same in both statements
+    pub fn is_empty(&self) -> bool {
+        self.removed.is_empty() && self.added.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Differences between two statements, by section
+#[derive(Debug, Clone, PartialEq, Eq, Default, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct StatementDiff {
+    /// Trades, matched on `transactionID` or execution `tradeID`
+    pub trades: SectionDiff<Trade>,
+    /// Cash transactions, matched on `transactionID`
+    pub cash_transactions: SectionDiff<CashTransaction>,
+    /// Corporate actions, matched on `transactionID`
+    pub corporate_actions: SectionDiff<CorporateAction>,
+    /// Option exercises, assignments, and expirations, matched on `transactionID`
+    pub option_eae: SectionDiff<OptionEAE>,
+    /// Position transfers, matched on `transactionID`
+    pub transfers: SectionDiff<Transfer>,
+    /// Deposits and withdrawals, matched on `transactionID`
+    pub deposits_withdrawals: SectionDiff<DepositWithdrawal>,
+}
+
+impl StatementDiff {
+    /// Returns true if no section differs
+    pub fn is_empty(&self) -> bool {
+        self.trades.is_empty()
+            && self.cash_transactions.is_empty()
+            && self.corporate_actions.is_empty()
+            && self.option_eae.is_empty()
+            && self.transfers.is_empty()
+            && self.deposits_withdrawals.is_empty()
+    }
+}
+
+/// Compare the transactions of `a` and `b`
+///
+/// Records are matched by ID within their level of detail; rows without an
+/// ID cannot be matched and are left out. A busted trade shows up as
+/// removed, and IB's correction as added.
+pub fn diff_statements(a: &ActivityFlexStatement, b: &ActivityFlexStatement) -> StatementDiff {
+    StatementDiff {
+        trades: diff_section(&a.trades, &b.trades, trade_key),
+        cash_transactions: diff_section(
+            &a.cash_transactions,
+            &b.cash_transactions,
+            cash_transaction_key,
+        ),
+        corporate_actions: diff_section(
+            &a.corporate_actions,
+            &b.corporate_actions,
+            corporate_action_key,
+        ),
+        option_eae: diff_section(&a.option_eae, &b.option_eae, |e| {
+            Some(((), id(&e.transaction_id)?))
+        }),
+        transfers: diff_section(&a.transfers, &b.transfers, |t| {
+            Some(((), id(&t.transaction_id)?))
+        }),
+        deposits_withdrawals: diff_section(&a.deposits_withdrawals, &b.deposits_withdrawals, |d| {
+            Some(((), id(&d.transaction_id)?))
+        }),
+    }
+}
+
+fn diff_section<T, K>(a: &[T], b: &[T], key: impl Fn(&T) -> Option<(K, String)>) -> SectionDiff<T>
+where
+    T: Clone + PartialEq + Serialize,
+    K: Eq + Hash,
+{
+    let index = |rows: &[T]| -> HashMap<(K, String), usize> {
+        rows.iter()
+            .enumerate()
+            .filter_map(|(i, row)| Some((key(row)?, i)))
+            .collect()
+    };
+    let in_a = index(a);
+    let in_b = index(b);
+
+    let mut diff = SectionDiff::default();
+    for (i, row) in a.iter().enumerate() {
+        let Some(k) = key(row) else {
+            continue;
+        };
+        // Only the last row with a repeated ID takes part
+        if in_a.get(&k) != Some(&i) {
+            continue;
+        }
+        match in_b.get(&k).map(|&j| &b[j]) {
+            None => diff.removed.push(row.clone()),
+            Some(other) if other != row => {
+                let fields = field_changes(row, other);
+                diff.changed.push(RecordChange {
+                    id: k.1,
+                    before: row.clone(),
+                    after: other.clone(),
+                    fields,
+                });
+            }
+            Some(_) => {}
+        }
+    }
+    for (j, row) in b.iter().enumerate() {
+        if let Some(k) = key(row) {
+            if in_b.get(&k) == Some(&j) && !in_a.contains_key(&k) {
+                diff.added.push(row.clone());
+            }
+        }
+    }
+    diff
+}
+
+fn field_changes<T: Serialize>(before: &T, after: &T) -> Vec<FieldChange> {
+    let before = attributes(before);
+    let mut after = attributes(after);
+    let mut changes = Vec::new();
+    for (field, value) in before {
+        let other = after.remove(&field).flatten();
+        if value != other {
+            changes.push(FieldChange {
+                field,
+                before: value,
+                after: other,
+            });
+        }
+    }
+    for (field, value) in after.into_iter().filter(|(_, v)| v.is_some()) {
+        changes.push(FieldChange {
+            field,
+            before: None,
+            after: value,
+        });
+    }
+    changes.sort_by(|x, y| x.field.cmp(&y.field));
+    changes
+}
+
+/// Attributes of a record as written by its XML serialization
+fn attributes<T: Serialize>(record: &T) -> BTreeMap<String, Option<String>> {
+    let mut attributes = BTreeMap::new();
+    let Ok(xml) = quick_xml::se::to_string_with_root("Row", record) else {
+        return attributes;
+    };
+    let mut reader = quick_xml::Reader::from_str(&xml);
+    if let Ok(Event::Start(element) | Event::Empty(element)) = reader.read_event() {
+        for attribute in element.attributes().flatten() {
+            let name = String::from_utf8_lossy(attribute.key.as_ref()).into_owned();
+            let value = attribute
+                .normalized_value(quick_xml::XmlVersion::Implicit1_0)
+                .map(|v| v.into_owned())
+                .unwrap_or_default();
+            attributes.insert(name, Some(value).filter(|v| !v.is_empty()));
+        }
+    }
+    attributes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{CashTransactionBuilder, CashTransactionType, TradeBuilder};
+    use rust_decimal::Decimal;
+
+    fn statement() -> ActivityFlexStatement {
+        let xml = include_str!("../../tests/fixtures/activity_minimal.xml");
+        let mut statement = crate::parse_activity_flex(xml).unwrap();
+        statement.trades.items = ["T1", "T2"]
+            .iter()
+            .map(|id| {
+                TradeBuilder::new("AAPL")
+                    .conid(1)
+                    .transaction_id(*id)
+                    .quantity(10)
+                    .price(185)
+                    .build()
+            })
+            .collect();
+        statement.cash_transactions.items =
+            vec![
+                CashTransactionBuilder::new(CashTransactionType::Dividends, 25)
+                    .transaction_id("D1")
+                    .build(),
+            ];
+        statement
+    }
+
+    #[test]
+    fn test_identical_statements() {
+        let a = statement();
+        assert!(diff_statements(&a, &a.clone()).is_empty());
+    }
+
+    #[test]
+    fn test_restated_trades_and_dividends() {
+        let before = statement();
+        let mut after = statement();
+        // T2 busted and rebooked as T3; the dividend corrected
+        after.trades.items[1].transaction_id = Some("T3".to_string());
+        after.trades.items[1].price = Some(Decimal::new(18450, 2));
+        after.cash_transactions.items[0].amount = Decimal::from(24);
+        after.cash_transactions.items[0].description = Some("Corrected".to_string());
+
+        let diff = diff_statements(&before, &after);
+        assert_eq!(diff.trades.removed.len(), 1);
+        assert_eq!(diff.trades.removed[0].transaction_id.as_deref(), Some("T2"));
+        assert_eq!(diff.trades.added.len(), 1);
+        assert!(diff.trades.changed.is_empty());
+
+        let change = &diff.cash_transactions.changed[0];
+        assert_eq!(change.id, "D1");
+        assert_eq!(
+            change.fields,
+            [
+                FieldChange {
+                    field: "amount".to_string(),
+                    before: Some("25".to_string()),
+                    after: Some("24".to_string()),
+                },
+                FieldChange {
+                    field: "description".to_string(),
+                    before: None,
+                    after: Some("Corrected".to_string()),
+                },
+            ]
+        );
+    }
+}
@@ -6,49 +6,88 @@
 
 use chrono::NaiveDate;
 use rust_decimal::Decimal;
-use serde::{Deserialize, Serialize};
-
-use super::common::{AssetCategory, OptionAction, TransferType};
+#[cfg(feature = "schemars")]
+use schemars::JsonSchema;
+use serde::Deserialize;
+#[cfg(feature = "serialize")]
+use serde::Serialize;
+
+use super::activity::Trade;
+use super::common::{
+    AccountId, AssetCategory, Conid, DeliveredReceived, ExecutionId, InOut, OptionAction, OrderId,
+    ToFrom, TradeCodes, TradeId, TransferType,
+};
 use crate::parsers::xml_utils::{
     deserialize_flex_date, deserialize_optional_date, deserialize_optional_decimal,
+    deserialize_optional_enum, deserialize_optional_from_str, deserialize_optional_string,
 };
+#[cfg(feature = "serialize")]
+use crate::parsers::xml_utils::{serialize_flex_date, serialize_optional_date};
 
 /// Account information and metadata
-#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct AccountInformation {
     /// Account ID
     #[serde(rename = "@accountId")]
-    pub account_id: String,
+    pub account_id: AccountId,
 
     /// Account type
-    #[serde(rename = "@accountType", default)]
+    #[serde(
+        rename = "@accountType",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub account_type: Option<String>,
 
     /// Account alias
-    #[serde(rename = "@acctAlias", default)]
+    #[serde(
+        rename = "@acctAlias",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub acct_alias: Option<String>,
 
     /// Currency
-    #[serde(rename = "@currency", default)]
+    #[serde(
+        rename = "@currency",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub currency: Option<String>,
 
     /// Account name
-    #[serde(rename = "@name", default)]
+    #[serde(
+        rename = "@name",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub name: Option<String>,
 
     /// Master account
-    #[serde(rename = "@masterName", default)]
+    #[serde(
+        rename = "@masterName",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub master_name: Option<String>,
 
     /// Customer type
-    #[serde(rename = "@customerType", default)]
+    #[serde(
+        rename = "@customerType",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub customer_type: Option<String>,
 
     /// Date account opened
     #[serde(
         rename = "@dateOpened",
         default,
-        deserialize_with = "deserialize_optional_date"
+        deserialize_with = "deserialize_optional_date",
+        serialize_with = "serialize_optional_date"
     )]
     pub date_opened: Option<NaiveDate>,
 
@@ -56,7 +95,8 @@ pub struct AccountInformation {
     #[serde(
         rename = "@dateFunded",
         default,
-        deserialize_with = "deserialize_optional_date"
+        deserialize_with = "deserialize_optional_date",
+        serialize_with = "serialize_optional_date"
     )]
     pub date_funded: Option<NaiveDate>,
 
@@ -64,96 +104,178 @@ pub struct AccountInformation {
     #[serde(
         rename = "@dateClosed",
         default,
-        deserialize_with = "deserialize_optional_date"
+        deserialize_with = "deserialize_optional_date",
+        serialize_with = "serialize_optional_date"
     )]
     pub date_closed: Option<NaiveDate>,
 
     /// Primary email
-    #[serde(rename = "@primaryEmail", default)]
+    #[serde(
+        rename = "@primaryEmail",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub primary_email: Option<String>,
 
     /// Street address
-    #[serde(rename = "@streetAddress", default)]
+    #[serde(
+        rename = "@streetAddress",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub street_address: Option<String>,
 
     /// Street address 2
-    #[serde(rename = "@streetAddress2", default)]
+    #[serde(
+        rename = "@streetAddress2",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub street_address2: Option<String>,
 
     /// City
-    #[serde(rename = "@city", default)]
+    #[serde(
+        rename = "@city",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub city: Option<String>,
 
     /// State
-    #[serde(rename = "@state", default)]
+    #[serde(
+        rename = "@state",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub state: Option<String>,
 
     /// Country
-    #[serde(rename = "@country", default)]
+    #[serde(
+        rename = "@country",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub country: Option<String>,
 
     /// Postal code
-    #[serde(rename = "@postalCode", default)]
+    #[serde(
+        rename = "@postalCode",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub postal_code: Option<String>,
 
     /// Account capabilities
-    #[serde(rename = "@accountCapabilities", default)]
+    #[serde(
+        rename = "@accountCapabilities",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub account_capabilities: Option<String>,
 
     /// Trading permissions
-    #[serde(rename = "@tradingPermissions", default)]
+    #[serde(
+        rename = "@tradingPermissions",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub trading_permissions: Option<String>,
 
     /// Registered representative name
-    #[serde(rename = "@registeredRepName", default)]
+    #[serde(
+        rename = "@registeredRepName",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub registered_rep_name: Option<String>,
 
     /// Registered representative phone
-    #[serde(rename = "@registeredRepPhone", default)]
+    #[serde(
+        rename = "@registeredRepPhone",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub registered_rep_phone: Option<String>,
 
     /// IB entity
-    #[serde(rename = "@ibEntity", default)]
+    #[serde(
+        rename = "@ibEntity",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub ib_entity: Option<String>,
 
     /// Model
-    #[serde(rename = "@model", default)]
+    #[serde(
+        rename = "@model",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub model: Option<String>,
 }
 
 /// Change in portfolio NAV (Net Asset Value)
-#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct ChangeInNAV {
     /// Account ID
     #[serde(rename = "@accountId")]
-    pub account_id: String,
+    pub account_id: AccountId,
 
     /// Account alias
-    #[serde(rename = "@acctAlias", default)]
+    #[serde(
+        rename = "@acctAlias",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub acct_alias: Option<String>,
 
     /// Model
-    #[serde(rename = "@model", default)]
+    #[serde(
+        rename = "@model",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub model: Option<String>,
 
     /// Currency
-    #[serde(rename = "@currency", default)]
+    #[serde(
+        rename = "@currency",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub currency: Option<String>,
 
     /// From date
-    #[serde(rename = "@fromDate", deserialize_with = "deserialize_flex_date")]
+    #[serde(
+        rename = "@fromDate",
+        deserialize_with = "deserialize_flex_date",
+        serialize_with = "serialize_flex_date"
+    )]
     pub from_date: NaiveDate,
 
     /// To date
-    #[serde(rename = "@toDate", deserialize_with = "deserialize_flex_date")]
+    #[serde(
+        rename = "@toDate",
+        deserialize_with = "deserialize_flex_date",
+        serialize_with = "serialize_flex_date"
+    )]
     pub to_date: NaiveDate,
 
     /// Starting NAV value
-    #[serde(rename = "@startingValue")]
+    #[serde(
+        rename = "@startingValue",
+        deserialize_with = "crate::parsers::xml_utils::deserialize_decimal"
+    )]
     pub starting_value: Decimal,
 
     /// Ending NAV value
-    #[serde(rename = "@endingValue")]
+    #[serde(
+        rename = "@endingValue",
+        deserialize_with = "crate::parsers::xml_utils::deserialize_decimal"
+    )]
     pub ending_value: Decimal,
 
     /// Mark-to-market P&L
@@ -286,22 +408,37 @@ pub struct ChangeInNAV {
 }
 
 /// Equity summary by report date in base currency
-#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct EquitySummaryByReportDateInBase {
     /// Account ID
     #[serde(rename = "@accountId")]
-    pub account_id: String,
+    pub account_id: AccountId,
 
     /// Account alias
-    #[serde(rename = "@acctAlias", default)]
+    #[serde(
+        rename = "@acctAlias",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub acct_alias: Option<String>,
 
     /// Model
-    #[serde(rename = "@model", default)]
+    #[serde(
+        rename = "@model",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub model: Option<String>,
 
     /// Report date
-    #[serde(rename = "@reportDate", deserialize_with = "deserialize_flex_date")]
+    #[serde(
+        rename = "@reportDate",
+        deserialize_with = "deserialize_flex_date",
+        serialize_with = "serialize_flex_date"
+    )]
     pub report_date: NaiveDate,
 
     /// Cash
@@ -602,18 +739,29 @@ pub struct EquitySummaryByReportDateInBase {
 }
 
 /// Cash report by currency
-#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct CashReportCurrency {
     /// Account ID
     #[serde(rename = "@accountId")]
-    pub account_id: String,
+    pub account_id: AccountId,
 
     /// Account alias
-    #[serde(rename = "@acctAlias", default)]
+    #[serde(
+        rename = "@acctAlias",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub acct_alias: Option<String>,
 
     /// Model
-    #[serde(rename = "@model", default)]
+    #[serde(
+        rename = "@model",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub model: Option<String>,
 
     /// Currency
@@ -621,15 +769,26 @@ pub struct CashReportCurrency {
     pub currency: String,
 
     /// From date
-    #[serde(rename = "@fromDate", deserialize_with = "deserialize_flex_date")]
+    #[serde(
+        rename = "@fromDate",
+        deserialize_with = "deserialize_flex_date",
+        serialize_with = "serialize_flex_date"
+    )]
     pub from_date: NaiveDate,
 
     /// To date
-    #[serde(rename = "@toDate", deserialize_with = "deserialize_flex_date")]
+    #[serde(
+        rename = "@toDate",
+        deserialize_with = "deserialize_flex_date",
+        serialize_with = "serialize_flex_date"
+    )]
     pub to_date: NaiveDate,
 
     /// Starting cash
-    #[serde(rename = "@startingCash")]
+    #[serde(
+        rename = "@startingCash",
+        deserialize_with = "crate::parsers::xml_utils::deserialize_decimal"
+    )]
     pub starting_cash: Decimal,
 
     /// Starting cash (securities segment)
@@ -833,7 +992,10 @@ pub struct CashReportCurrency {
     pub soft_dollars: Option<Decimal>,
 
     /// Ending cash
-    #[serde(rename = "@endingCash")]
+    #[serde(
+        rename = "@endingCash",
+        deserialize_with = "crate::parsers::xml_utils::deserialize_decimal"
+    )]
     pub ending_cash: Decimal,
 
     /// Ending cash (securities segment)
@@ -878,53 +1040,89 @@ pub struct CashReportCurrency {
 }
 
 /// Trade confirmation
-#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct TradeConfirm {
     /// Account ID
     #[serde(rename = "@accountId")]
-    pub account_id: String,
+    pub account_id: AccountId,
 
     /// Account alias
-    #[serde(rename = "@acctAlias", default)]
+    #[serde(
+        rename = "@acctAlias",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub acct_alias: Option<String>,
 
     /// Model
-    #[serde(rename = "@model", default)]
+    #[serde(
+        rename = "@model",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub model: Option<String>,
 
     /// Execution ID
     #[serde(rename = "@execID")]
-    pub exec_id: String,
+    pub exec_id: ExecutionId,
 
     /// Transaction ID
-    #[serde(rename = "@transactionID", default)]
+    #[serde(
+        rename = "@transactionID",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub transaction_id: Option<String>,
 
     /// Trade ID
-    #[serde(rename = "@tradeID", default)]
-    pub trade_id: Option<String>,
+    #[serde(
+        rename = "@tradeID",
+        default,
+        deserialize_with = "deserialize_optional_from_str"
+    )]
+    pub trade_id: Option<TradeId>,
 
     /// Order ID
-    #[serde(rename = "@orderID", default)]
-    pub order_id: Option<String>,
+    #[serde(
+        rename = "@orderID",
+        default,
+        deserialize_with = "deserialize_optional_from_str"
+    )]
+    pub order_id: Option<OrderId>,
 
     /// Trade date
-    #[serde(rename = "@tradeDate", deserialize_with = "deserialize_flex_date")]
+    #[serde(
+        rename = "@tradeDate",
+        deserialize_with = "deserialize_flex_date",
+        serialize_with = "serialize_flex_date"
+    )]
     pub trade_date: NaiveDate,
 
     /// Trade time
-    #[serde(rename = "@tradeTime", default)]
+    #[serde(
+        rename = "@tradeTime",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub trade_time: Option<String>,
 
     /// Date time
-    #[serde(rename = "@dateTime", default)]
+    #[serde(
+        rename = "@dateTime",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub date_time: Option<String>,
 
     /// Settlement date
     #[serde(
         rename = "@settleDate",
         default,
-        deserialize_with = "deserialize_optional_date"
+        deserialize_with = "deserialize_optional_date",
+        serialize_with = "serialize_optional_date"
     )]
     pub settle_date: Option<NaiveDate>,
 
@@ -933,11 +1131,19 @@ pub struct TradeConfirm {
     pub symbol: String,
 
     /// Description
-    #[serde(rename = "@description", default)]
+    #[serde(
+        rename = "@description",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub description: Option<String>,
 
     /// Contract ID
-    #[serde(rename = "@conid", default)]
+    #[serde(
+        rename = "@conid",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub conid: Option<String>,
 
     /// Asset category
@@ -946,19 +1152,35 @@ pub struct TradeConfirm {
 
     // Security identifiers
     /// CUSIP
-    #[serde(rename = "@cusip", default)]
+    #[serde(
+        rename = "@cusip",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub cusip: Option<String>,
 
     /// ISIN
-    #[serde(rename = "@isin", default)]
+    #[serde(
+        rename = "@isin",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub isin: Option<String>,
 
     /// FIGI
-    #[serde(rename = "@figi", default)]
+    #[serde(
+        rename = "@figi",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub figi: Option<String>,
 
     /// Listing exchange
-    #[serde(rename = "@listingExchange", default)]
+    #[serde(
+        rename = "@listingExchange",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub listing_exchange: Option<String>,
 
     // Options fields
@@ -974,12 +1196,17 @@ pub struct TradeConfirm {
     #[serde(
         rename = "@expiry",
         default,
-        deserialize_with = "deserialize_optional_date"
+        deserialize_with = "deserialize_optional_date",
+        serialize_with = "serialize_optional_date"
     )]
     pub expiry: Option<NaiveDate>,
 
     /// Put/Call
-    #[serde(rename = "@putCall", default)]
+    #[serde(
+        rename = "@putCall",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub put_call: Option<String>,
 
     /// Multiplier
@@ -992,20 +1219,34 @@ pub struct TradeConfirm {
 
     // Underlying
     /// Underlying symbol
-    #[serde(rename = "@underlyingSymbol", default)]
+    #[serde(
+        rename = "@underlyingSymbol",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub underlying_symbol: Option<String>,
 
     /// Underlying contract ID
-    #[serde(rename = "@underlyingConid", default)]
+    #[serde(
+        rename = "@underlyingConid",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub underlying_conid: Option<String>,
 
     // Trade details
     /// Quantity
-    #[serde(rename = "@quantity")]
+    #[serde(
+        rename = "@quantity",
+        deserialize_with = "crate::parsers::xml_utils::deserialize_decimal"
+    )]
     pub quantity: Decimal,
 
     /// Price
-    #[serde(rename = "@price")]
+    #[serde(
+        rename = "@price",
+        deserialize_with = "crate::parsers::xml_utils::deserialize_decimal"
+    )]
     pub price: Decimal,
 
     /// Proceeds
@@ -1041,7 +1282,11 @@ pub struct TradeConfirm {
     pub net_cash: Option<Decimal>,
 
     /// Currency
-    #[serde(rename = "@currency", default)]
+    #[serde(
+        rename = "@currency",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub currency: Option<String>,
 
     /// FX rate to base
@@ -1053,19 +1298,35 @@ pub struct TradeConfirm {
     pub fx_rate_to_base: Option<Decimal>,
 
     /// Buy/Sell
-    #[serde(rename = "@buySell", default)]
+    #[serde(
+        rename = "@buySell",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub buy_sell: Option<String>,
 
     /// Order type
-    #[serde(rename = "@orderType", default)]
+    #[serde(
+        rename = "@orderType",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub order_type: Option<String>,
 
     /// Exchange
-    #[serde(rename = "@exchange", default)]
+    #[serde(
+        rename = "@exchange",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub exchange: Option<String>,
 
     /// Clearing ID
-    #[serde(rename = "@clearingID", default)]
+    #[serde(
+        rename = "@clearingID",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub clearing_id: Option<String>,
 
     /// Away broker commission
@@ -1085,51 +1346,103 @@ pub struct TradeConfirm {
     pub regulatory_fee: Option<Decimal>,
 
     /// Order reference
-    #[serde(rename = "@orderReference", default)]
+    #[serde(
+        rename = "@orderReference",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub order_reference: Option<String>,
 
     /// Level of detail
-    #[serde(rename = "@levelOfDetail", default)]
+    #[serde(
+        rename = "@levelOfDetail",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub level_of_detail: Option<String>,
 }
 
 /// Option exercise/assignment/expiration
-#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct OptionEAE {
     /// Account ID
     #[serde(rename = "@accountId")]
-    pub account_id: String,
+    pub account_id: AccountId,
 
     /// Account alias
-    #[serde(rename = "@acctAlias", default)]
+    #[serde(
+        rename = "@acctAlias",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub acct_alias: Option<String>,
 
     /// Model
-    #[serde(rename = "@model", default)]
+    #[serde(
+        rename = "@model",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub model: Option<String>,
 
     /// Transaction ID
-    #[serde(rename = "@transactionID", default)]
+    #[serde(
+        rename = "@transactionID",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub transaction_id: Option<String>,
 
     /// Action ID
-    #[serde(rename = "@actionID", default)]
+    #[serde(
+        rename = "@actionID",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub action_id: Option<String>,
 
-    /// Action type
-    #[serde(rename = "@type", default)]
-    pub action_type: Option<OptionAction>,
+    /// Transaction type (Assignment, Exercise, Expiration, Buy, Sell)
+    #[serde(
+        rename = "@transactionType",
+        alias = "@type",
+        default,
+        deserialize_with = "deserialize_optional_enum"
+    )]
+    pub transaction_type: Option<OptionAction>,
+
+    /// ID of the trade booked for this event
+    #[serde(
+        rename = "@tradeID",
+        default,
+        deserialize_with = "deserialize_optional_from_str"
+    )]
+    pub trade_id: Option<TradeId>,
 
     /// Date
-    #[serde(rename = "@date", deserialize_with = "deserialize_flex_date")]
+    #[serde(
+        rename = "@date",
+        deserialize_with = "deserialize_flex_date",
+        serialize_with = "serialize_flex_date"
+    )]
     pub date: NaiveDate,
 
     /// Date time
-    #[serde(rename = "@dateTime", default)]
+    #[serde(
+        rename = "@dateTime",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub date_time: Option<String>,
 
     /// Contract ID
-    #[serde(rename = "@conid", default)]
+    #[serde(
+        rename = "@conid",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub conid: Option<String>,
 
     /// Symbol
@@ -1137,32 +1450,59 @@ pub struct OptionEAE {
     pub symbol: String,
 
     /// Description
-    #[serde(rename = "@description", default)]
+    #[serde(
+        rename = "@description",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub description: Option<String>,
 
     /// Asset category
-    #[serde(rename = "@assetCategory", default)]
+    #[serde(
+        rename = "@assetCategory",
+        default,
+        deserialize_with = "deserialize_optional_enum"
+    )]
     pub asset_category: Option<AssetCategory>,
 
     // Security identifiers
     /// CUSIP
-    #[serde(rename = "@cusip", default)]
+    #[serde(
+        rename = "@cusip",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub cusip: Option<String>,
 
     /// ISIN
-    #[serde(rename = "@isin", default)]
+    #[serde(
+        rename = "@isin",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub isin: Option<String>,
 
     /// FIGI
-    #[serde(rename = "@figi", default)]
+    #[serde(
+        rename = "@figi",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub figi: Option<String>,
 
     /// Listing exchange
-    #[serde(rename = "@listingExchange", default)]
+    #[serde(
+        rename = "@listingExchange",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub listing_exchange: Option<String>,
 
     /// Quantity
-    #[serde(rename = "@quantity")]
+    #[serde(
+        rename = "@quantity",
+        deserialize_with = "crate::parsers::xml_utils::deserialize_decimal"
+    )]
     pub quantity: Decimal,
 
     /// Strike
@@ -1177,12 +1517,17 @@ pub struct OptionEAE {
     #[serde(
         rename = "@expiry",
         default,
-        deserialize_with = "deserialize_optional_date"
+        deserialize_with = "deserialize_optional_date",
+        serialize_with = "serialize_optional_date"
     )]
     pub expiry: Option<NaiveDate>,
 
     /// Put/Call
-    #[serde(rename = "@putCall", default)]
+    #[serde(
+        rename = "@putCall",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub put_call: Option<String>,
 
     /// Multiplier
@@ -1194,11 +1539,19 @@ pub struct OptionEAE {
     pub multiplier: Option<Decimal>,
 
     /// Underlying symbol
-    #[serde(rename = "@underlyingSymbol", default)]
+    #[serde(
+        rename = "@underlyingSymbol",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub underlying_symbol: Option<String>,
 
     /// Underlying contract ID
-    #[serde(rename = "@underlyingConid", default)]
+    #[serde(
+        rename = "@underlyingConid",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub underlying_conid: Option<String>,
 
     /// Trade price
@@ -1226,7 +1579,11 @@ pub struct OptionEAE {
     pub commission: Option<Decimal>,
 
     /// Currency
-    #[serde(rename = "@currency", default)]
+    #[serde(
+        rename = "@currency",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub currency: Option<String>,
 
     /// FX rate to base
@@ -1245,56 +1602,158 @@ pub struct OptionEAE {
     )]
     pub fifo_pnl_realized: Option<Decimal>,
 
+    /// Mark-to-market P&L
+    #[serde(
+        rename = "@mtmPnl",
+        default,
+        deserialize_with = "deserialize_optional_decimal"
+    )]
+    pub mtm_pnl: Option<Decimal>,
+
     /// Notes/codes
-    #[serde(rename = "@notes", default)]
+    #[serde(
+        rename = "@notes",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub notes: Option<String>,
 
     /// Level of detail
-    #[serde(rename = "@levelOfDetail", default)]
+    #[serde(
+        rename = "@levelOfDetail",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub level_of_detail: Option<String>,
 }
 
-/// Foreign exchange transaction
-#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+impl OptionEAE {
+    /// Parses the `notes` attribute into a [`TradeCodes`] set
+    pub fn codes(&self) -> TradeCodes {
+        TradeCodes::from_notes(self.notes.as_deref())
+    }
+
+    /// Finds the trades booked as a result of this exercise/assignment/expiration
+    ///
+    /// Trades are matched by `tradeID` when the EAE row carries one. Otherwise
+    /// a trade is considered related if it falls on the EAE date and is either
+    /// the option contract itself or, for assignments and exercises, the
+    /// underlying delivered against it.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use ib_flex::parse_activity_flex;
+    ///
+    /// let xml = std::fs::read_to_string("activity.xml")?;
+    /// let statement = parse_activity_flex(&xml)?;
+    ///
+    /// for eae in &statement.option_eae.items {
+    ///     for trade in eae.related_trades(&statement.trades.items) {
+    ///         println!("{:?} {} -> {} {}", eae.transaction_type, eae.symbol, trade.symbol, trade.quantity.unwrap_or_default());
+    ///     }
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn related_trades<'a>(&self, trades: &'a [Trade]) -> Vec<&'a Trade> {
+        if let Some(trade_id) = self.trade_id.as_deref().filter(|id| !id.is_empty()) {
+            return trades
+                .iter()
+                .filter(|t| t.trade_id.as_deref() == Some(trade_id))
+                .collect();
+        }
+
+        let delivers_underlying = matches!(
+            self.transaction_type,
+            Some(OptionAction::Assignment) | Some(OptionAction::Exercise)
+        );
+
+        let parse = |conid: &Option<String>| conid.as_deref().and_then(|c| c.parse::<Conid>().ok());
+        let conid = parse(&self.conid);
+        let underlying_conid = parse(&self.underlying_conid);
+
+        trades
+            .iter()
+            .filter(|t| t.trade_date == Some(self.date))
+            .filter(|t| {
+                conid == Some(t.conid) || (delivers_underlying && underlying_conid == Some(t.conid))
+            })
+            .collect()
+    }
+}
+
+/// Foreign exchange transaction
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct FxTransaction {
     /// Account ID
     #[serde(rename = "@accountId")]
-    pub account_id: String,
+    pub account_id: AccountId,
 
     /// Account alias
-    #[serde(rename = "@acctAlias", default)]
+    #[serde(
+        rename = "@acctAlias",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub acct_alias: Option<String>,
 
     /// Model
-    #[serde(rename = "@model", default)]
+    #[serde(
+        rename = "@model",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub model: Option<String>,
 
     /// Transaction ID
-    #[serde(rename = "@transactionID", default)]
+    #[serde(
+        rename = "@transactionID",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub transaction_id: Option<String>,
 
     /// Action ID
-    #[serde(rename = "@actionID", default)]
+    #[serde(
+        rename = "@actionID",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub action_id: Option<String>,
 
     /// Report date
     #[serde(
         rename = "@reportDate",
         default,
-        deserialize_with = "deserialize_optional_date"
+        deserialize_with = "deserialize_optional_date",
+        serialize_with = "serialize_optional_date"
     )]
     pub report_date: Option<NaiveDate>,
 
     /// Date/time
-    #[serde(rename = "@dateTime", default)]
+    #[serde(
+        rename = "@dateTime",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub date_time: Option<String>,
 
     /// Description
-    #[serde(rename = "@description", default)]
+    #[serde(
+        rename = "@description",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub description: Option<String>,
 
     /// Functional currency
-    #[serde(rename = "@functionalCurrency", default)]
+    #[serde(
+        rename = "@functionalCurrency",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub functional_currency: Option<String>,
 
     /// From currency
@@ -1306,11 +1765,17 @@ pub struct FxTransaction {
     pub to_currency: String,
 
     /// Quantity
-    #[serde(rename = "@quantity")]
+    #[serde(
+        rename = "@quantity",
+        deserialize_with = "crate::parsers::xml_utils::deserialize_decimal"
+    )]
     pub quantity: Decimal,
 
     /// Proceeds
-    #[serde(rename = "@proceeds")]
+    #[serde(
+        rename = "@proceeds",
+        deserialize_with = "crate::parsers::xml_utils::deserialize_decimal"
+    )]
     pub proceeds: Decimal,
 
     /// Cost
@@ -1338,31 +1803,54 @@ pub struct FxTransaction {
     pub fx_rate_to_base: Option<Decimal>,
 
     /// Level of detail
-    #[serde(rename = "@levelOfDetail", default)]
+    #[serde(
+        rename = "@levelOfDetail",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub level_of_detail: Option<String>,
 
     /// Asset category
-    #[serde(rename = "@assetCategory", default)]
+    #[serde(
+        rename = "@assetCategory",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub asset_category: Option<String>,
 }
 
 /// Change in dividend accruals
-#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct ChangeInDividendAccrual {
     /// Account ID
     #[serde(rename = "@accountId")]
-    pub account_id: String,
+    pub account_id: AccountId,
 
     /// Account alias
-    #[serde(rename = "@acctAlias", default)]
+    #[serde(
+        rename = "@acctAlias",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub acct_alias: Option<String>,
 
     /// Model
-    #[serde(rename = "@model", default)]
+    #[serde(
+        rename = "@model",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub model: Option<String>,
 
     /// Currency
-    #[serde(rename = "@currency", default)]
+    #[serde(
+        rename = "@currency",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub currency: Option<String>,
 
     /// FX rate to base
@@ -1374,7 +1862,11 @@ pub struct ChangeInDividendAccrual {
     pub fx_rate_to_base: Option<Decimal>,
 
     /// Asset category
-    #[serde(rename = "@assetCategory", default)]
+    #[serde(
+        rename = "@assetCategory",
+        default,
+        deserialize_with = "deserialize_optional_enum"
+    )]
     pub asset_category: Option<AssetCategory>,
 
     /// Symbol
@@ -1382,46 +1874,83 @@ pub struct ChangeInDividendAccrual {
     pub symbol: String,
 
     /// Description
-    #[serde(rename = "@description", default)]
+    #[serde(
+        rename = "@description",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub description: Option<String>,
 
     /// Contract ID
-    #[serde(rename = "@conid", default)]
+    #[serde(
+        rename = "@conid",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub conid: Option<String>,
 
     /// Security ID
-    #[serde(rename = "@securityID", default)]
+    #[serde(
+        rename = "@securityID",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub security_id: Option<String>,
 
     /// Security ID type
-    #[serde(rename = "@securityIDType", default)]
+    #[serde(
+        rename = "@securityIDType",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub security_id_type: Option<String>,
 
     /// CUSIP
-    #[serde(rename = "@cusip", default)]
+    #[serde(
+        rename = "@cusip",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub cusip: Option<String>,
 
     /// ISIN
-    #[serde(rename = "@isin", default)]
+    #[serde(
+        rename = "@isin",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub isin: Option<String>,
 
     /// FIGI
-    #[serde(rename = "@figi", default)]
+    #[serde(
+        rename = "@figi",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub figi: Option<String>,
 
     /// Listing exchange
-    #[serde(rename = "@listingExchange", default)]
+    #[serde(
+        rename = "@listingExchange",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub listing_exchange: Option<String>,
 
     /// Ex date
-    #[serde(rename = "@exDate", deserialize_with = "deserialize_flex_date")]
+    #[serde(
+        rename = "@exDate",
+        deserialize_with = "deserialize_flex_date",
+        serialize_with = "serialize_flex_date"
+    )]
     pub ex_date: NaiveDate,
 
     /// Pay date
     #[serde(
         rename = "@payDate",
         default,
-        deserialize_with = "deserialize_optional_date"
+        deserialize_with = "deserialize_optional_date",
+        serialize_with = "serialize_optional_date"
     )]
     pub pay_date: Option<NaiveDate>,
 
@@ -1429,7 +1958,8 @@ pub struct ChangeInDividendAccrual {
     #[serde(
         rename = "@date",
         default,
-        deserialize_with = "deserialize_optional_date"
+        deserialize_with = "deserialize_optional_date",
+        serialize_with = "serialize_optional_date"
     )]
     pub date: Option<NaiveDate>,
 
@@ -1458,7 +1988,10 @@ pub struct ChangeInDividendAccrual {
     pub fee: Option<Decimal>,
 
     /// Gross rate
-    #[serde(rename = "@grossRate")]
+    #[serde(
+        rename = "@grossRate",
+        deserialize_with = "crate::parsers::xml_utils::deserialize_decimal"
+    )]
     pub gross_rate: Decimal,
 
     /// Gross amount
@@ -1470,7 +2003,10 @@ pub struct ChangeInDividendAccrual {
     pub gross_amount: Option<Decimal>,
 
     /// Net amount
-    #[serde(rename = "@netAmount")]
+    #[serde(
+        rename = "@netAmount",
+        deserialize_with = "crate::parsers::xml_utils::deserialize_decimal"
+    )]
     pub net_amount: Decimal,
 
     /// From accrual (prior period)
@@ -1490,27 +2026,46 @@ pub struct ChangeInDividendAccrual {
     pub to_accrual: Option<Decimal>,
 
     /// Code
-    #[serde(rename = "@code", default)]
+    #[serde(
+        rename = "@code",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub code: Option<String>,
 }
 
 /// Open dividend accruals
-#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct OpenDividendAccrual {
     /// Account ID
     #[serde(rename = "@accountId")]
-    pub account_id: String,
+    pub account_id: AccountId,
 
     /// Account alias
-    #[serde(rename = "@acctAlias", default)]
+    #[serde(
+        rename = "@acctAlias",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub acct_alias: Option<String>,
 
     /// Model
-    #[serde(rename = "@model", default)]
+    #[serde(
+        rename = "@model",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub model: Option<String>,
 
     /// Currency
-    #[serde(rename = "@currency", default)]
+    #[serde(
+        rename = "@currency",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub currency: Option<String>,
 
     /// FX rate to base
@@ -1522,7 +2077,11 @@ pub struct OpenDividendAccrual {
     pub fx_rate_to_base: Option<Decimal>,
 
     /// Asset category
-    #[serde(rename = "@assetCategory", default)]
+    #[serde(
+        rename = "@assetCategory",
+        default,
+        deserialize_with = "deserialize_optional_enum"
+    )]
     pub asset_category: Option<AssetCategory>,
 
     /// Symbol
@@ -1530,51 +2089,91 @@ pub struct OpenDividendAccrual {
     pub symbol: String,
 
     /// Description
-    #[serde(rename = "@description", default)]
+    #[serde(
+        rename = "@description",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub description: Option<String>,
 
     /// Contract ID
-    #[serde(rename = "@conid", default)]
+    #[serde(
+        rename = "@conid",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub conid: Option<String>,
 
     /// Security ID
-    #[serde(rename = "@securityID", default)]
+    #[serde(
+        rename = "@securityID",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub security_id: Option<String>,
 
     /// Security ID type
-    #[serde(rename = "@securityIDType", default)]
+    #[serde(
+        rename = "@securityIDType",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub security_id_type: Option<String>,
 
     /// CUSIP
-    #[serde(rename = "@cusip", default)]
+    #[serde(
+        rename = "@cusip",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub cusip: Option<String>,
 
     /// ISIN
-    #[serde(rename = "@isin", default)]
+    #[serde(
+        rename = "@isin",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub isin: Option<String>,
 
     /// FIGI
-    #[serde(rename = "@figi", default)]
+    #[serde(
+        rename = "@figi",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub figi: Option<String>,
 
     /// Listing exchange
-    #[serde(rename = "@listingExchange", default)]
+    #[serde(
+        rename = "@listingExchange",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub listing_exchange: Option<String>,
 
     /// Ex date
-    #[serde(rename = "@exDate", deserialize_with = "deserialize_flex_date")]
+    #[serde(
+        rename = "@exDate",
+        deserialize_with = "deserialize_flex_date",
+        serialize_with = "serialize_flex_date"
+    )]
     pub ex_date: NaiveDate,
 
     /// Pay date
     #[serde(
         rename = "@payDate",
         default,
-        deserialize_with = "deserialize_optional_date"
+        deserialize_with = "deserialize_optional_date",
+        serialize_with = "serialize_optional_date"
     )]
     pub pay_date: Option<NaiveDate>,
 
     /// Quantity
-    #[serde(rename = "@quantity")]
+    #[serde(
+        rename = "@quantity",
+        deserialize_with = "crate::parsers::xml_utils::deserialize_decimal"
+    )]
     pub quantity: Decimal,
 
     /// Tax
@@ -1594,7 +2193,10 @@ pub struct OpenDividendAccrual {
     pub fee: Option<Decimal>,
 
     /// Gross rate
-    #[serde(rename = "@grossRate")]
+    #[serde(
+        rename = "@grossRate",
+        deserialize_with = "crate::parsers::xml_utils::deserialize_decimal"
+    )]
     pub gross_rate: Decimal,
 
     /// Gross amount
@@ -1614,67 +2216,114 @@ pub struct OpenDividendAccrual {
     pub net_amount: Option<Decimal>,
 
     /// Code
-    #[serde(rename = "@code", default)]
+    #[serde(
+        rename = "@code",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub code: Option<String>,
 }
 
 /// Interest accruals by currency
-#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct InterestAccrualsCurrency {
     /// Account ID
     #[serde(rename = "@accountId")]
-    pub account_id: String,
+    pub account_id: AccountId,
 
     /// Currency
     #[serde(rename = "@currency")]
     pub currency: String,
 
     /// From date
-    #[serde(rename = "@fromDate", deserialize_with = "deserialize_flex_date")]
+    #[serde(
+        rename = "@fromDate",
+        deserialize_with = "deserialize_flex_date",
+        serialize_with = "serialize_flex_date"
+    )]
     pub from_date: NaiveDate,
 
     /// To date
-    #[serde(rename = "@toDate", deserialize_with = "deserialize_flex_date")]
+    #[serde(
+        rename = "@toDate",
+        deserialize_with = "deserialize_flex_date",
+        serialize_with = "serialize_flex_date"
+    )]
     pub to_date: NaiveDate,
 
     /// Starting accrual balance
-    #[serde(rename = "@startingAccrualBalance")]
+    #[serde(
+        rename = "@startingAccrualBalance",
+        deserialize_with = "crate::parsers::xml_utils::deserialize_decimal"
+    )]
     pub starting_balance: Decimal,
 
     /// Interest accrued
-    #[serde(rename = "@interestAccrued")]
+    #[serde(
+        rename = "@interestAccrued",
+        deserialize_with = "crate::parsers::xml_utils::deserialize_decimal"
+    )]
     pub interest_accrued: Decimal,
 
     /// Ending accrual balance
-    #[serde(rename = "@endingAccrualBalance")]
+    #[serde(
+        rename = "@endingAccrualBalance",
+        deserialize_with = "crate::parsers::xml_utils::deserialize_decimal"
+    )]
     pub ending_balance: Decimal,
 }
 
 /// Security transfer
-#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct Transfer {
     /// Account ID
     #[serde(rename = "@accountId")]
-    pub account_id: String,
+    pub account_id: AccountId,
 
     /// Account alias
-    #[serde(rename = "@acctAlias", default)]
+    #[serde(
+        rename = "@acctAlias",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub acct_alias: Option<String>,
 
     /// Model
-    #[serde(rename = "@model", default)]
+    #[serde(
+        rename = "@model",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub model: Option<String>,
 
     /// Transaction ID
-    #[serde(rename = "@transactionID", default)]
+    #[serde(
+        rename = "@transactionID",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub transaction_id: Option<String>,
 
     /// Transfer type
-    #[serde(rename = "@type", default)]
+    #[serde(
+        rename = "@type",
+        default,
+        deserialize_with = "deserialize_optional_enum"
+    )]
     pub transfer_type: Option<TransferType>,
 
     /// Contract ID
-    #[serde(rename = "@conid", default)]
+    #[serde(
+        rename = "@conid",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub conid: Option<String>,
 
     /// Symbol
@@ -1682,32 +2331,59 @@ pub struct Transfer {
     pub symbol: String,
 
     /// Description
-    #[serde(rename = "@description", default)]
+    #[serde(
+        rename = "@description",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub description: Option<String>,
 
     /// Asset category
-    #[serde(rename = "@assetCategory", default)]
+    #[serde(
+        rename = "@assetCategory",
+        default,
+        deserialize_with = "deserialize_optional_enum"
+    )]
     pub asset_category: Option<AssetCategory>,
 
     // Security identifiers
     /// CUSIP
-    #[serde(rename = "@cusip", default)]
+    #[serde(
+        rename = "@cusip",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub cusip: Option<String>,
 
     /// ISIN
-    #[serde(rename = "@isin", default)]
+    #[serde(
+        rename = "@isin",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub isin: Option<String>,
 
     /// FIGI
-    #[serde(rename = "@figi", default)]
+    #[serde(
+        rename = "@figi",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub figi: Option<String>,
 
     /// Listing exchange
-    #[serde(rename = "@listingExchange", default)]
+    #[serde(
+        rename = "@listingExchange",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub listing_exchange: Option<String>,
 
     /// Quantity
-    #[serde(rename = "@quantity")]
+    #[serde(
+        rename = "@quantity",
+        deserialize_with = "crate::parsers::xml_utils::deserialize_decimal"
+    )]
     pub quantity: Decimal,
 
     /// Price
@@ -1743,7 +2419,11 @@ pub struct Transfer {
     pub cash_transfer: Option<Decimal>,
 
     /// Currency
-    #[serde(rename = "@currency", default)]
+    #[serde(
+        rename = "@currency",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub currency: Option<String>,
 
     /// FX rate to base
@@ -1754,22 +2434,71 @@ pub struct Transfer {
     )]
     pub fx_rate_to_base: Option<Decimal>,
 
-    /// Direction
-    #[serde(rename = "@direction", default)]
-    pub direction: Option<String>,
+    /// Direction (IN = received into this account, OUT = delivered away)
+    #[serde(
+        rename = "@direction",
+        default,
+        deserialize_with = "deserialize_optional_enum"
+    )]
+    pub direction: Option<InOut>,
 
     /// Date
-    #[serde(rename = "@date", deserialize_with = "deserialize_flex_date")]
+    #[serde(
+        rename = "@date",
+        deserialize_with = "deserialize_flex_date",
+        serialize_with = "serialize_flex_date"
+    )]
     pub date: NaiveDate,
 
     /// Payer/payee account
-    #[serde(rename = "@ppiPayerPayeeAccount", default)]
+    #[serde(
+        rename = "@ppiPayerPayeeAccount",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub ppi_payer_payee_account: Option<String>,
 
     /// Delivering/receiving broker
-    #[serde(rename = "@deliveringReceivingBroker", default)]
+    #[serde(
+        rename = "@deliveringReceivingBroker",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub delivering_receiving_broker: Option<String>,
 
+    // ACATS counterparty
+    /// Counterparty company (contra broker name for ACATS/ATON transfers)
+    #[serde(
+        rename = "@company",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
+    pub company: Option<String>,
+
+    /// Counterparty account number at the contra broker
+    #[serde(
+        rename = "@account",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
+    pub account: Option<String>,
+
+    /// Counterparty account name
+    #[serde(
+        rename = "@accountName",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
+    pub account_name: Option<String>,
+
+    /// Delivering broker (DTC participant number or name)
+    #[serde(
+        rename = "@deliveringBroker",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
+    pub delivering_broker: Option<String>,
+
     // Options fields
     /// Strike
     #[serde(
@@ -1783,12 +2512,17 @@ pub struct Transfer {
     #[serde(
         rename = "@expiry",
         default,
-        deserialize_with = "deserialize_optional_date"
+        deserialize_with = "deserialize_optional_date",
+        serialize_with = "serialize_optional_date"
     )]
     pub expiry: Option<NaiveDate>,
 
     /// Put/Call
-    #[serde(rename = "@putCall", default)]
+    #[serde(
+        rename = "@putCall",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub put_call: Option<String>,
 
     /// Multiplier
@@ -1800,6 +2534,131 @@ pub struct Transfer {
     pub multiplier: Option<Decimal>,
 }
 
+/// Deposit or withdrawal detail
+///
+/// Funding movements with the bank-side metadata (ACH/Wire method, sending or
+/// receiving institution) that the generic `CashTransaction` rows don't carry.
+/// Appears in the `<DepositsWithdrawals>` section.
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct DepositWithdrawal {
+    /// Account ID
+    #[serde(rename = "@accountId")]
+    pub account_id: AccountId,
+
+    /// Account alias
+    #[serde(
+        rename = "@acctAlias",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
+    pub acct_alias: Option<String>,
+
+    /// Model
+    #[serde(
+        rename = "@model",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
+    pub model: Option<String>,
+
+    /// Transaction ID
+    #[serde(
+        rename = "@transactionID",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
+    pub transaction_id: Option<String>,
+
+    /// Currency
+    #[serde(rename = "@currency")]
+    pub currency: String,
+
+    /// FX rate to base
+    #[serde(
+        rename = "@fxRateToBase",
+        default,
+        deserialize_with = "deserialize_optional_decimal"
+    )]
+    pub fx_rate_to_base: Option<Decimal>,
+
+    /// Amount (positive for deposits, negative for withdrawals)
+    #[serde(
+        rename = "@amount",
+        deserialize_with = "crate::parsers::xml_utils::deserialize_decimal"
+    )]
+    pub amount: Decimal,
+
+    /// Description
+    #[serde(
+        rename = "@description",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
+    pub description: Option<String>,
+
+    /// Funding method (e.g. "ACH", "Wire", "Check")
+    #[serde(
+        rename = "@method",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
+    pub method: Option<String>,
+
+    /// Sending/receiving institution (bank name)
+    #[serde(
+        rename = "@institution",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
+    pub institution: Option<String>,
+
+    /// Bank account at the institution (usually masked)
+    #[serde(
+        rename = "@bankAccount",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
+    pub bank_account: Option<String>,
+
+    /// Date
+    #[serde(
+        rename = "@date",
+        default,
+        deserialize_with = "deserialize_optional_date",
+        serialize_with = "serialize_optional_date"
+    )]
+    pub date: Option<NaiveDate>,
+
+    /// Settlement date
+    #[serde(
+        rename = "@settleDate",
+        default,
+        deserialize_with = "deserialize_optional_date",
+        serialize_with = "serialize_optional_date"
+    )]
+    pub settle_date: Option<NaiveDate>,
+
+    /// Report date
+    #[serde(
+        rename = "@reportDate",
+        default,
+        deserialize_with = "deserialize_optional_date",
+        serialize_with = "serialize_optional_date"
+    )]
+    pub report_date: Option<NaiveDate>,
+
+    /// Client reference
+    #[serde(
+        rename = "@clientReference",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
+    pub client_reference: Option<String>,
+}
+
 // =============================================================================
 // Performance Summary Types
 // =============================================================================
@@ -1808,67 +2667,119 @@ pub struct Transfer {
 ///
 /// Provides MTM performance metrics for each security in the portfolio,
 /// including realized and unrealized P&L, commissions, and transaction MTM.
-#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct MTMPerformanceSummaryUnderlying {
     /// Account ID
     #[serde(rename = "@accountId")]
-    pub account_id: String,
+    pub account_id: AccountId,
 
     /// Account alias
-    #[serde(rename = "@acctAlias", default)]
+    #[serde(
+        rename = "@acctAlias",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub acct_alias: Option<String>,
 
     /// Model
-    #[serde(rename = "@model", default)]
+    #[serde(
+        rename = "@model",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub model: Option<String>,
 
     /// Report date
     #[serde(
         rename = "@reportDate",
         default,
-        deserialize_with = "deserialize_optional_date"
+        deserialize_with = "deserialize_optional_date",
+        serialize_with = "serialize_optional_date"
     )]
     pub report_date: Option<NaiveDate>,
 
     /// Symbol
-    #[serde(rename = "@symbol", default)]
+    #[serde(
+        rename = "@symbol",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub symbol: Option<String>,
 
     /// Description
-    #[serde(rename = "@description", default)]
+    #[serde(
+        rename = "@description",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub description: Option<String>,
 
     /// Contract ID
-    #[serde(rename = "@conid", default)]
+    #[serde(
+        rename = "@conid",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub conid: Option<String>,
 
     /// Asset category
-    #[serde(rename = "@assetCategory", default)]
+    #[serde(
+        rename = "@assetCategory",
+        default,
+        deserialize_with = "deserialize_optional_enum"
+    )]
     pub asset_category: Option<AssetCategory>,
 
     // Security identifiers
     /// CUSIP
-    #[serde(rename = "@cusip", default)]
+    #[serde(
+        rename = "@cusip",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub cusip: Option<String>,
 
     /// ISIN
-    #[serde(rename = "@isin", default)]
+    #[serde(
+        rename = "@isin",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub isin: Option<String>,
 
     /// Listing exchange
-    #[serde(rename = "@listingExchange", default)]
+    #[serde(
+        rename = "@listingExchange",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub listing_exchange: Option<String>,
 
     /// Underlying symbol
-    #[serde(rename = "@underlyingSymbol", default)]
+    #[serde(
+        rename = "@underlyingSymbol",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub underlying_symbol: Option<String>,
 
     /// Underlying contract ID
-    #[serde(rename = "@underlyingConid", default)]
+    #[serde(
+        rename = "@underlyingConid",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub underlying_conid: Option<String>,
 
     /// Underlying listing exchange
-    #[serde(rename = "@underlyingListingExchange", default)]
+    #[serde(
+        rename = "@underlyingListingExchange",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub underlying_listing_exchange: Option<String>,
 
     // P&L fields
@@ -1952,6 +2863,14 @@ pub struct MTMPerformanceSummaryUnderlying {
     )]
     pub transaction_mtm: Option<Decimal>,
 
+    /// MTM on positions held at the start of the period
+    #[serde(
+        rename = "@priorOpenMtm",
+        default,
+        deserialize_with = "deserialize_optional_decimal"
+    )]
+    pub prior_open_mtm: Option<Decimal>,
+
     /// Commissions
     #[serde(
         rename = "@commissions",
@@ -1968,8 +2887,20 @@ pub struct MTMPerformanceSummaryUnderlying {
     )]
     pub other: Option<Decimal>,
 
+    /// Total MTM P&L
+    #[serde(
+        rename = "@total",
+        default,
+        deserialize_with = "deserialize_optional_decimal"
+    )]
+    pub total: Option<Decimal>,
+
     /// Level of detail
-    #[serde(rename = "@levelOfDetail", default)]
+    #[serde(
+        rename = "@levelOfDetail",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub level_of_detail: Option<String>,
 }
 
@@ -1977,63 +2908,111 @@ pub struct MTMPerformanceSummaryUnderlying {
 ///
 /// Provides FIFO-based performance metrics including realized and unrealized
 /// P&L calculated using FIFO cost basis method.
-#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct FIFOPerformanceSummaryUnderlying {
     /// Account ID
     #[serde(rename = "@accountId")]
-    pub account_id: String,
+    pub account_id: AccountId,
 
     /// Account alias
-    #[serde(rename = "@acctAlias", default)]
+    #[serde(
+        rename = "@acctAlias",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub acct_alias: Option<String>,
 
     /// Model
-    #[serde(rename = "@model", default)]
+    #[serde(
+        rename = "@model",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub model: Option<String>,
 
     /// Report date
     #[serde(
         rename = "@reportDate",
         default,
-        deserialize_with = "deserialize_optional_date"
+        deserialize_with = "deserialize_optional_date",
+        serialize_with = "serialize_optional_date"
     )]
     pub report_date: Option<NaiveDate>,
 
     /// Symbol
-    #[serde(rename = "@symbol", default)]
+    #[serde(
+        rename = "@symbol",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub symbol: Option<String>,
 
     /// Description
-    #[serde(rename = "@description", default)]
+    #[serde(
+        rename = "@description",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub description: Option<String>,
 
     /// Contract ID
-    #[serde(rename = "@conid", default)]
+    #[serde(
+        rename = "@conid",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub conid: Option<String>,
 
     /// Asset category
-    #[serde(rename = "@assetCategory", default)]
+    #[serde(
+        rename = "@assetCategory",
+        default,
+        deserialize_with = "deserialize_optional_enum"
+    )]
     pub asset_category: Option<AssetCategory>,
 
     // Security identifiers
     /// CUSIP
-    #[serde(rename = "@cusip", default)]
+    #[serde(
+        rename = "@cusip",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub cusip: Option<String>,
 
     /// ISIN
-    #[serde(rename = "@isin", default)]
+    #[serde(
+        rename = "@isin",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub isin: Option<String>,
 
     /// Listing exchange
-    #[serde(rename = "@listingExchange", default)]
+    #[serde(
+        rename = "@listingExchange",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub listing_exchange: Option<String>,
 
     /// Underlying symbol
-    #[serde(rename = "@underlyingSymbol", default)]
+    #[serde(
+        rename = "@underlyingSymbol",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub underlying_symbol: Option<String>,
 
     /// Underlying contract ID
-    #[serde(rename = "@underlyingConid", default)]
+    #[serde(
+        rename = "@underlyingConid",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub underlying_conid: Option<String>,
 
     // P&L fields
@@ -2094,35 +3073,62 @@ pub struct FIFOPerformanceSummaryUnderlying {
     pub total_income: Option<Decimal>,
 
     /// Level of detail
-    #[serde(rename = "@levelOfDetail", default)]
+    #[serde(
+        rename = "@levelOfDetail",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub level_of_detail: Option<String>,
 }
 
 /// Month-to-date/Year-to-date performance summary
-#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct MTDYTDPerformanceSummary {
     /// Account ID
     #[serde(rename = "@accountId")]
-    pub account_id: String,
+    pub account_id: AccountId,
 
     /// Account alias
-    #[serde(rename = "@acctAlias", default)]
+    #[serde(
+        rename = "@acctAlias",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub acct_alias: Option<String>,
 
     /// Model
-    #[serde(rename = "@model", default)]
+    #[serde(
+        rename = "@model",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub model: Option<String>,
 
     /// Symbol
-    #[serde(rename = "@symbol", default)]
+    #[serde(
+        rename = "@symbol",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub symbol: Option<String>,
 
     /// Contract ID
-    #[serde(rename = "@conid", default)]
+    #[serde(
+        rename = "@conid",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub conid: Option<String>,
 
     /// Asset category
-    #[serde(rename = "@assetCategory", default)]
+    #[serde(
+        rename = "@assetCategory",
+        default,
+        deserialize_with = "deserialize_optional_enum"
+    )]
     pub asset_category: Option<AssetCategory>,
 
     // MTD fields
@@ -2192,7 +3198,11 @@ pub struct MTDYTDPerformanceSummary {
     pub ytd_fees: Option<Decimal>,
 
     /// Level of detail
-    #[serde(rename = "@levelOfDetail", default)]
+    #[serde(
+        rename = "@levelOfDetail",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub level_of_detail: Option<String>,
 }
 
@@ -2204,25 +3214,37 @@ pub struct MTDYTDPerformanceSummary {
 ///
 /// Represents a single cash flow entry in the statement of funds,
 /// tracking debits and credits with running balance.
-#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct StatementOfFundsLine {
     /// Account ID
     #[serde(rename = "@accountId")]
-    pub account_id: String,
+    pub account_id: AccountId,
 
     /// Account alias
-    #[serde(rename = "@acctAlias", default)]
+    #[serde(
+        rename = "@acctAlias",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub acct_alias: Option<String>,
 
     /// Model
-    #[serde(rename = "@model", default)]
+    #[serde(
+        rename = "@model",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub model: Option<String>,
 
     /// Report date
     #[serde(
         rename = "@reportDate",
         default,
-        deserialize_with = "deserialize_optional_date"
+        deserialize_with = "deserialize_optional_date",
+        serialize_with = "serialize_optional_date"
     )]
     pub report_date: Option<NaiveDate>,
 
@@ -2230,32 +3252,57 @@ pub struct StatementOfFundsLine {
     #[serde(
         rename = "@date",
         default,
-        deserialize_with = "deserialize_optional_date"
+        deserialize_with = "deserialize_optional_date",
+        serialize_with = "serialize_optional_date"
     )]
     pub date: Option<NaiveDate>,
 
     /// Currency
-    #[serde(rename = "@currency", default)]
+    #[serde(
+        rename = "@currency",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub currency: Option<String>,
 
     /// Activity code
-    #[serde(rename = "@activityCode", default)]
+    #[serde(
+        rename = "@activityCode",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub activity_code: Option<String>,
 
     /// Activity description
-    #[serde(rename = "@activityDescription", default)]
+    #[serde(
+        rename = "@activityDescription",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub activity_description: Option<String>,
 
     /// Trade ID
-    #[serde(rename = "@tradeID", default)]
-    pub trade_id: Option<String>,
+    #[serde(
+        rename = "@tradeID",
+        default,
+        deserialize_with = "deserialize_optional_from_str"
+    )]
+    pub trade_id: Option<TradeId>,
 
     /// Symbol
-    #[serde(rename = "@symbol", default)]
+    #[serde(
+        rename = "@symbol",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub symbol: Option<String>,
 
     /// Contract ID
-    #[serde(rename = "@conid", default)]
+    #[serde(
+        rename = "@conid",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub conid: Option<String>,
 
     /// Debit amount
@@ -2299,7 +3346,11 @@ pub struct StatementOfFundsLine {
     pub fx_rate_to_base: Option<Decimal>,
 
     /// Level of detail
-    #[serde(rename = "@levelOfDetail", default)]
+    #[serde(
+        rename = "@levelOfDetail",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub level_of_detail: Option<String>,
 }
 
@@ -2311,42 +3362,70 @@ pub struct StatementOfFundsLine {
 ///
 /// Tracks how position values changed due to various factors like
 /// transactions, MTM changes, corporate actions, and FX translation.
-#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct ChangeInPositionValue {
     /// Account ID
     #[serde(rename = "@accountId")]
-    pub account_id: String,
+    pub account_id: AccountId,
 
     /// Account alias
-    #[serde(rename = "@acctAlias", default)]
+    #[serde(
+        rename = "@acctAlias",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub acct_alias: Option<String>,
 
     /// Model
-    #[serde(rename = "@model", default)]
+    #[serde(
+        rename = "@model",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub model: Option<String>,
 
     /// Report date
     #[serde(
         rename = "@reportDate",
         default,
-        deserialize_with = "deserialize_optional_date"
+        deserialize_with = "deserialize_optional_date",
+        serialize_with = "serialize_optional_date"
     )]
     pub report_date: Option<NaiveDate>,
 
     /// Symbol
-    #[serde(rename = "@symbol", default)]
+    #[serde(
+        rename = "@symbol",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub symbol: Option<String>,
 
     /// Contract ID
-    #[serde(rename = "@conid", default)]
+    #[serde(
+        rename = "@conid",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub conid: Option<String>,
 
     /// Asset category
-    #[serde(rename = "@assetCategory", default)]
+    #[serde(
+        rename = "@assetCategory",
+        default,
+        deserialize_with = "deserialize_optional_enum"
+    )]
     pub asset_category: Option<AssetCategory>,
 
     /// Currency
-    #[serde(rename = "@currency", default)]
+    #[serde(
+        rename = "@currency",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub currency: Option<String>,
 
     /// Prior period value
@@ -2414,7 +3493,11 @@ pub struct ChangeInPositionValue {
     pub ending_value: Option<Decimal>,
 
     /// Level of detail
-    #[serde(rename = "@levelOfDetail", default)]
+    #[serde(
+        rename = "@levelOfDetail",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub level_of_detail: Option<String>,
 }
 
@@ -2426,54 +3509,101 @@ pub struct ChangeInPositionValue {
 ///
 /// Breaks down commission charges into components: execution, clearing,
 /// regulatory, third-party, and exchange fees.
-#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct UnbundledCommissionDetail {
     /// Account ID
     #[serde(rename = "@accountId")]
-    pub account_id: String,
+    pub account_id: AccountId,
 
     /// Account alias
-    #[serde(rename = "@acctAlias", default)]
+    #[serde(
+        rename = "@acctAlias",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub acct_alias: Option<String>,
 
     /// Model
-    #[serde(rename = "@model", default)]
+    #[serde(
+        rename = "@model",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub model: Option<String>,
 
     /// Symbol
-    #[serde(rename = "@symbol", default)]
+    #[serde(
+        rename = "@symbol",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub symbol: Option<String>,
 
     /// Description
-    #[serde(rename = "@description", default)]
+    #[serde(
+        rename = "@description",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub description: Option<String>,
 
     /// Contract ID
-    #[serde(rename = "@conid", default)]
+    #[serde(
+        rename = "@conid",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub conid: Option<String>,
 
     /// Asset category
-    #[serde(rename = "@assetCategory", default)]
+    #[serde(
+        rename = "@assetCategory",
+        default,
+        deserialize_with = "deserialize_optional_enum"
+    )]
     pub asset_category: Option<AssetCategory>,
 
     /// Execution ID
-    #[serde(rename = "@execID", default)]
-    pub exec_id: Option<String>,
+    #[serde(
+        rename = "@execID",
+        default,
+        deserialize_with = "deserialize_optional_from_str"
+    )]
+    pub exec_id: Option<ExecutionId>,
 
     /// Order ID
-    #[serde(rename = "@orderID", default)]
-    pub order_id: Option<String>,
+    #[serde(
+        rename = "@orderID",
+        default,
+        deserialize_with = "deserialize_optional_from_str"
+    )]
+    pub order_id: Option<OrderId>,
 
     /// Trade ID
-    #[serde(rename = "@tradeID", default)]
-    pub trade_id: Option<String>,
+    #[serde(
+        rename = "@tradeID",
+        default,
+        deserialize_with = "deserialize_optional_from_str"
+    )]
+    pub trade_id: Option<TradeId>,
 
     /// Trade date/time (format: YYYYMMDD;HHMMSS)
-    #[serde(rename = "@dateTime", default)]
+    #[serde(
+        rename = "@dateTime",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub date_time: Option<String>,
 
     /// Exchange
-    #[serde(rename = "@exchange", default)]
+    #[serde(
+        rename = "@exchange",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub exchange: Option<String>,
 
     /// Quantity
@@ -2541,35 +3671,55 @@ pub struct UnbundledCommissionDetail {
     pub total_commission: Option<Decimal>,
 
     /// Currency
-    #[serde(rename = "@currency", default)]
+    #[serde(
+        rename = "@currency",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub currency: Option<String>,
 }
 
 /// Client fee (advisory/service fees)
-#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct ClientFee {
     /// Account ID
     #[serde(rename = "@accountId")]
-    pub account_id: String,
+    pub account_id: AccountId,
 
     /// Account alias
-    #[serde(rename = "@acctAlias", default)]
+    #[serde(
+        rename = "@acctAlias",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub acct_alias: Option<String>,
 
     /// Model
-    #[serde(rename = "@model", default)]
+    #[serde(
+        rename = "@model",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub model: Option<String>,
 
     /// Date
     #[serde(
         rename = "@date",
         default,
-        deserialize_with = "deserialize_optional_date"
+        deserialize_with = "deserialize_optional_date",
+        serialize_with = "serialize_optional_date"
     )]
     pub date: Option<NaiveDate>,
 
     /// Currency
-    #[serde(rename = "@currency", default)]
+    #[serde(
+        rename = "@currency",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub currency: Option<String>,
 
     /// Revenue
@@ -2597,39 +3747,168 @@ pub struct ClientFee {
     pub net: Option<Decimal>,
 
     /// Description
-    #[serde(rename = "@description", default)]
+    #[serde(
+        rename = "@description",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub description: Option<String>,
+
+    /// Fee type (e.g., "Advisor Fee", "Advisor Fee Reimbursement")
+    #[serde(
+        rename = "@feeType",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
+    pub fee_type: Option<String>,
+
+    /// Expense indicator
+    #[serde(
+        rename = "@expenseIndicator",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
+    pub expense_indicator: Option<String>,
+
+    /// FX rate to base
+    #[serde(
+        rename = "@fxRateToBase",
+        default,
+        deserialize_with = "deserialize_optional_decimal"
+    )]
+    pub fx_rate_to_base: Option<Decimal>,
+
+    /// Revenue in base currency
+    #[serde(
+        rename = "@revenueInBase",
+        default,
+        deserialize_with = "deserialize_optional_decimal"
+    )]
+    pub revenue_in_base: Option<Decimal>,
+
+    /// Expense in base currency
+    #[serde(
+        rename = "@expenseInBase",
+        default,
+        deserialize_with = "deserialize_optional_decimal"
+    )]
+    pub expense_in_base: Option<Decimal>,
+
+    /// Net in base currency
+    #[serde(
+        rename = "@netInBase",
+        default,
+        deserialize_with = "deserialize_optional_decimal"
+    )]
+    pub net_in_base: Option<Decimal>,
+
+    /// Trade ID the fee was charged against
+    #[serde(
+        rename = "@tradeID",
+        default,
+        deserialize_with = "deserialize_optional_from_str"
+    )]
+    pub trade_id: Option<TradeId>,
+
+    /// Order ID the fee was charged against
+    #[serde(
+        rename = "@orderID",
+        default,
+        deserialize_with = "deserialize_optional_from_str"
+    )]
+    pub order_id: Option<OrderId>,
+
+    /// Execution ID the fee was charged against
+    #[serde(
+        rename = "@execID",
+        default,
+        deserialize_with = "deserialize_optional_from_str"
+    )]
+    pub exec_id: Option<ExecutionId>,
+
+    /// Level of detail (e.g., "ADVISOR_FEE", "REIMBURSEMENT")
+    #[serde(
+        rename = "@levelOfDetail",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
+    pub level_of_detail: Option<String>,
+}
+
+impl ClientFee {
+    /// Returns true if this row reimburses a previously charged fee
+    ///
+    /// IB marks reimbursements through the fee type or level of detail
+    /// rather than a dedicated section, so both are checked.
+    pub fn is_reimbursement(&self) -> bool {
+        [&self.fee_type, &self.level_of_detail, &self.description]
+            .into_iter()
+            .flatten()
+            .any(|s| s.to_ascii_uppercase().contains("REIMBURSE"))
+    }
+
+    /// Returns true if this row is an advisor fee charge (not a reimbursement)
+    pub fn is_advisor_fee(&self) -> bool {
+        !self.is_reimbursement()
+            && [&self.fee_type, &self.level_of_detail]
+                .into_iter()
+                .flatten()
+                .any(|s| {
+                    let s = s.to_ascii_uppercase();
+                    s.contains("ADVISOR") || s.contains("ADVISER")
+                })
+    }
 }
 
 /// Client fee detail
-#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct ClientFeesDetail {
     /// Account ID
     #[serde(rename = "@accountId")]
-    pub account_id: String,
+    pub account_id: AccountId,
 
     /// Account alias
-    #[serde(rename = "@acctAlias", default)]
+    #[serde(
+        rename = "@acctAlias",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub acct_alias: Option<String>,
 
     /// Model
-    #[serde(rename = "@model", default)]
+    #[serde(
+        rename = "@model",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub model: Option<String>,
 
     /// Date
     #[serde(
         rename = "@date",
         default,
-        deserialize_with = "deserialize_optional_date"
+        deserialize_with = "deserialize_optional_date",
+        serialize_with = "serialize_optional_date"
     )]
     pub date: Option<NaiveDate>,
 
     /// Currency
-    #[serde(rename = "@currency", default)]
+    #[serde(
+        rename = "@currency",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub currency: Option<String>,
 
     /// Fee type
-    #[serde(rename = "@feeType", default)]
+    #[serde(
+        rename = "@feeType",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub fee_type: Option<String>,
 
     /// Revenue
@@ -2657,7 +3936,11 @@ pub struct ClientFeesDetail {
     pub net: Option<Decimal>,
 
     /// Description
-    #[serde(rename = "@description", default)]
+    #[serde(
+        rename = "@description",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub description: Option<String>,
 
     /// FX rate to base
@@ -2674,46 +3957,78 @@ pub struct ClientFeesDetail {
 // =============================================================================
 
 /// Securities lending/borrowing activity
-#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct SLBActivity {
     /// Account ID
     #[serde(rename = "@accountId")]
-    pub account_id: String,
+    pub account_id: AccountId,
 
     /// Account alias
-    #[serde(rename = "@acctAlias", default)]
+    #[serde(
+        rename = "@acctAlias",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub acct_alias: Option<String>,
 
     /// Model
-    #[serde(rename = "@model", default)]
+    #[serde(
+        rename = "@model",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub model: Option<String>,
 
     /// Symbol
-    #[serde(rename = "@symbol", default)]
+    #[serde(
+        rename = "@symbol",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub symbol: Option<String>,
 
     /// Description
-    #[serde(rename = "@description", default)]
+    #[serde(
+        rename = "@description",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub description: Option<String>,
 
     /// Contract ID
-    #[serde(rename = "@conid", default)]
+    #[serde(
+        rename = "@conid",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub conid: Option<String>,
 
     /// Asset category
-    #[serde(rename = "@assetCategory", default)]
+    #[serde(
+        rename = "@assetCategory",
+        default,
+        deserialize_with = "deserialize_optional_enum"
+    )]
     pub asset_category: Option<AssetCategory>,
 
     /// Activity date
     #[serde(
         rename = "@date",
         default,
-        deserialize_with = "deserialize_optional_date"
+        deserialize_with = "deserialize_optional_date",
+        serialize_with = "serialize_optional_date"
     )]
     pub date: Option<NaiveDate>,
 
     /// Activity type
-    #[serde(rename = "@type", default)]
+    #[serde(
+        rename = "@type",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub activity_type: Option<String>,
 
     /// Quantity
@@ -2749,7 +4064,11 @@ pub struct SLBActivity {
     pub net_lend_fee: Option<Decimal>,
 
     /// Currency
-    #[serde(rename = "@currency", default)]
+    #[serde(
+        rename = "@currency",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub currency: Option<String>,
 
     /// FX rate to base
@@ -2762,41 +4081,69 @@ pub struct SLBActivity {
 }
 
 /// Securities lending fee
-#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct SLBFee {
     /// Account ID
     #[serde(rename = "@accountId")]
-    pub account_id: String,
+    pub account_id: AccountId,
 
     /// Account alias
-    #[serde(rename = "@acctAlias", default)]
+    #[serde(
+        rename = "@acctAlias",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub acct_alias: Option<String>,
 
     /// Model
-    #[serde(rename = "@model", default)]
+    #[serde(
+        rename = "@model",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub model: Option<String>,
 
     /// Symbol
-    #[serde(rename = "@symbol", default)]
+    #[serde(
+        rename = "@symbol",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub symbol: Option<String>,
 
     /// Description
-    #[serde(rename = "@description", default)]
+    #[serde(
+        rename = "@description",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub description: Option<String>,
 
     /// Contract ID
-    #[serde(rename = "@conid", default)]
+    #[serde(
+        rename = "@conid",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub conid: Option<String>,
 
     /// Asset category
-    #[serde(rename = "@assetCategory", default)]
+    #[serde(
+        rename = "@assetCategory",
+        default,
+        deserialize_with = "deserialize_optional_enum"
+    )]
     pub asset_category: Option<AssetCategory>,
 
     /// Value date
     #[serde(
         rename = "@valueDate",
         default,
-        deserialize_with = "deserialize_optional_date"
+        deserialize_with = "deserialize_optional_date",
+        serialize_with = "serialize_optional_date"
     )]
     pub value_date: Option<NaiveDate>,
 
@@ -2804,7 +4151,8 @@ pub struct SLBFee {
     #[serde(
         rename = "@startDate",
         default,
-        deserialize_with = "deserialize_optional_date"
+        deserialize_with = "deserialize_optional_date",
+        serialize_with = "serialize_optional_date"
     )]
     pub start_date: Option<NaiveDate>,
 
@@ -2849,7 +4197,11 @@ pub struct SLBFee {
     pub carry_charge: Option<Decimal>,
 
     /// Currency
-    #[serde(rename = "@currency", default)]
+    #[serde(
+        rename = "@currency",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub currency: Option<String>,
 
     /// FX rate to base
@@ -2862,41 +4214,69 @@ pub struct SLBFee {
 }
 
 /// Hard to borrow stock details
-#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct HardToBorrowDetail {
     /// Account ID
     #[serde(rename = "@accountId")]
-    pub account_id: String,
+    pub account_id: AccountId,
 
     /// Account alias
-    #[serde(rename = "@acctAlias", default)]
+    #[serde(
+        rename = "@acctAlias",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub acct_alias: Option<String>,
 
     /// Model
-    #[serde(rename = "@model", default)]
+    #[serde(
+        rename = "@model",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub model: Option<String>,
 
     /// Symbol
-    #[serde(rename = "@symbol", default)]
+    #[serde(
+        rename = "@symbol",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub symbol: Option<String>,
 
     /// Description
-    #[serde(rename = "@description", default)]
+    #[serde(
+        rename = "@description",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub description: Option<String>,
 
     /// Contract ID
-    #[serde(rename = "@conid", default)]
+    #[serde(
+        rename = "@conid",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub conid: Option<String>,
 
     /// Asset category
-    #[serde(rename = "@assetCategory", default)]
+    #[serde(
+        rename = "@assetCategory",
+        default,
+        deserialize_with = "deserialize_optional_enum"
+    )]
     pub asset_category: Option<AssetCategory>,
 
     /// Value date
     #[serde(
         rename = "@valueDate",
         default,
-        deserialize_with = "deserialize_optional_date"
+        deserialize_with = "deserialize_optional_date",
+        serialize_with = "serialize_optional_date"
     )]
     pub value_date: Option<NaiveDate>,
 
@@ -2941,7 +4321,11 @@ pub struct HardToBorrowDetail {
     pub borrow_fee: Option<Decimal>,
 
     /// Currency
-    #[serde(rename = "@currency", default)]
+    #[serde(
+        rename = "@currency",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub currency: Option<String>,
 
     /// FX rate to base
@@ -2958,38 +4342,62 @@ pub struct HardToBorrowDetail {
 // =============================================================================
 
 /// FX position lot detail
-#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct FxLot {
     /// Account ID
     #[serde(rename = "@accountId")]
-    pub account_id: String,
+    pub account_id: AccountId,
 
     /// Account alias
-    #[serde(rename = "@acctAlias", default)]
+    #[serde(
+        rename = "@acctAlias",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub acct_alias: Option<String>,
 
     /// Model
-    #[serde(rename = "@model", default)]
+    #[serde(
+        rename = "@model",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub model: Option<String>,
 
     /// Asset category
-    #[serde(rename = "@assetCategory", default)]
+    #[serde(
+        rename = "@assetCategory",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub asset_category: Option<String>,
 
     /// Report date
     #[serde(
         rename = "@reportDate",
         default,
-        deserialize_with = "deserialize_optional_date"
+        deserialize_with = "deserialize_optional_date",
+        serialize_with = "serialize_optional_date"
     )]
     pub report_date: Option<NaiveDate>,
 
     /// Functional currency
-    #[serde(rename = "@functionalCurrency", default)]
+    #[serde(
+        rename = "@functionalCurrency",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub functional_currency: Option<String>,
 
     /// FX currency
-    #[serde(rename = "@fxCurrency", default)]
+    #[serde(
+        rename = "@fxCurrency",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub fx_currency: Option<String>,
 
     /// Quantity
@@ -3041,7 +4449,11 @@ pub struct FxLot {
     pub unrealized_pl: Option<Decimal>,
 
     /// Level of detail
-    #[serde(rename = "@levelOfDetail", default)]
+    #[serde(
+        rename = "@levelOfDetail",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub level_of_detail: Option<String>,
 }
 
@@ -3050,45 +4462,93 @@ pub struct FxLot {
 // =============================================================================
 
 /// Unsettled transfer
-#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct UnsettledTransfer {
     /// Account ID
     #[serde(rename = "@accountId")]
-    pub account_id: String,
+    pub account_id: AccountId,
 
     /// Account alias
-    #[serde(rename = "@acctAlias", default)]
+    #[serde(
+        rename = "@acctAlias",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub acct_alias: Option<String>,
 
     /// Model
-    #[serde(rename = "@model", default)]
+    #[serde(
+        rename = "@model",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub model: Option<String>,
 
     /// Symbol
-    #[serde(rename = "@symbol", default)]
+    #[serde(
+        rename = "@symbol",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub symbol: Option<String>,
 
     /// Description
-    #[serde(rename = "@description", default)]
+    #[serde(
+        rename = "@description",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub description: Option<String>,
 
     /// Contract ID
-    #[serde(rename = "@conid", default)]
+    #[serde(
+        rename = "@conid",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub conid: Option<String>,
 
     /// Asset category
-    #[serde(rename = "@assetCategory", default)]
+    #[serde(
+        rename = "@assetCategory",
+        default,
+        deserialize_with = "deserialize_optional_enum"
+    )]
     pub asset_category: Option<AssetCategory>,
 
-    /// Direction
-    #[serde(rename = "@direction", default)]
-    pub direction: Option<String>,
+    /// Direction (To = outgoing to the target account, From = incoming)
+    #[serde(
+        rename = "@direction",
+        default,
+        deserialize_with = "deserialize_optional_enum"
+    )]
+    pub direction: Option<ToFrom>,
+
+    /// Target (counterparty) account
+    #[serde(
+        rename = "@targetAccount",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
+    pub target_account: Option<String>,
+
+    /// Transfer type
+    #[serde(
+        rename = "@type",
+        default,
+        deserialize_with = "deserialize_optional_enum"
+    )]
+    pub transfer_type: Option<TransferType>,
 
     /// Date
     #[serde(
         rename = "@date",
         default,
-        deserialize_with = "deserialize_optional_date"
+        deserialize_with = "deserialize_optional_date",
+        serialize_with = "serialize_optional_date"
     )]
     pub date: Option<NaiveDate>,
 
@@ -3096,7 +4556,8 @@ pub struct UnsettledTransfer {
     #[serde(
         rename = "@expectedDate",
         default,
-        deserialize_with = "deserialize_optional_date"
+        deserialize_with = "deserialize_optional_date",
+        serialize_with = "serialize_optional_date"
     )]
     pub expected_date: Option<NaiveDate>,
 
@@ -3109,7 +4570,11 @@ pub struct UnsettledTransfer {
     pub quantity: Option<Decimal>,
 
     /// Currency
-    #[serde(rename = "@currency", default)]
+    #[serde(
+        rename = "@currency",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub currency: Option<String>,
 
     /// FX rate to base
@@ -3122,46 +4587,93 @@ pub struct UnsettledTransfer {
 }
 
 /// Trade transfer between accounts/brokers
-#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct TradeTransfer {
     /// Account ID
     #[serde(rename = "@accountId")]
-    pub account_id: String,
+    pub account_id: AccountId,
 
     /// Account alias
-    #[serde(rename = "@acctAlias", default)]
+    #[serde(
+        rename = "@acctAlias",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub acct_alias: Option<String>,
 
     /// Model
-    #[serde(rename = "@model", default)]
+    #[serde(
+        rename = "@model",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub model: Option<String>,
 
     /// Symbol
-    #[serde(rename = "@symbol", default)]
+    #[serde(
+        rename = "@symbol",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub symbol: Option<String>,
 
     /// Description
-    #[serde(rename = "@description", default)]
+    #[serde(
+        rename = "@description",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub description: Option<String>,
 
     /// Contract ID
-    #[serde(rename = "@conid", default)]
+    #[serde(
+        rename = "@conid",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub conid: Option<String>,
 
     /// Asset category
-    #[serde(rename = "@assetCategory", default)]
+    #[serde(
+        rename = "@assetCategory",
+        default,
+        deserialize_with = "deserialize_optional_enum"
+    )]
     pub asset_category: Option<AssetCategory>,
 
     /// Transfer type
-    #[serde(rename = "@transferType", default)]
-    pub transfer_type: Option<String>,
+    #[serde(
+        rename = "@transferType",
+        default,
+        deserialize_with = "deserialize_optional_enum"
+    )]
+    pub transfer_type: Option<TransferType>,
 
     /// Direction
-    #[serde(rename = "@direction", default)]
-    pub direction: Option<String>,
+    #[serde(
+        rename = "@direction",
+        default,
+        deserialize_with = "deserialize_optional_enum"
+    )]
+    pub direction: Option<InOut>,
+
+    /// Whether the trade was delivered away or received from the other broker
+    #[serde(
+        rename = "@deliveredReceived",
+        default,
+        deserialize_with = "deserialize_optional_enum"
+    )]
+    pub delivered_received: Option<DeliveredReceived>,
 
     /// Delivery type
-    #[serde(rename = "@deliveryType", default)]
+    #[serde(
+        rename = "@deliveryType",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub delivery_type: Option<String>,
 
     /// Quantity
@@ -3184,16 +4696,41 @@ pub struct TradeTransfer {
     #[serde(
         rename = "@date",
         default,
-        deserialize_with = "deserialize_optional_date"
+        deserialize_with = "deserialize_optional_date",
+        serialize_with = "serialize_optional_date"
     )]
     pub date: Option<NaiveDate>,
 
     /// Executing broker
-    #[serde(rename = "@executingBroker", default)]
+    #[serde(
+        rename = "@executingBroker",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub executing_broker: Option<String>,
 
+    /// Counterparty broker name
+    #[serde(
+        rename = "@brokerName",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
+    pub broker_name: Option<String>,
+
+    /// Counterparty broker account
+    #[serde(
+        rename = "@brokerAccount",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
+    pub broker_account: Option<String>,
+
     /// Currency
-    #[serde(rename = "@currency", default)]
+    #[serde(
+        rename = "@currency",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub currency: Option<String>,
 
     /// FX rate to base
@@ -3210,34 +4747,61 @@ pub struct TradeTransfer {
 // =============================================================================
 
 /// Prior period position
-#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct PriorPeriodPosition {
     /// Account ID
     #[serde(rename = "@accountId")]
-    pub account_id: String,
+    pub account_id: AccountId,
 
     /// Account alias
-    #[serde(rename = "@acctAlias", default)]
+    #[serde(
+        rename = "@acctAlias",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub acct_alias: Option<String>,
 
     /// Model
-    #[serde(rename = "@model", default)]
+    #[serde(
+        rename = "@model",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub model: Option<String>,
 
     /// Symbol
-    #[serde(rename = "@symbol", default)]
+    #[serde(
+        rename = "@symbol",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub symbol: Option<String>,
 
     /// Description
-    #[serde(rename = "@description", default)]
+    #[serde(
+        rename = "@description",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub description: Option<String>,
 
     /// Contract ID
-    #[serde(rename = "@conid", default)]
+    #[serde(
+        rename = "@conid",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub conid: Option<String>,
 
     /// Asset category
-    #[serde(rename = "@assetCategory", default)]
+    #[serde(
+        rename = "@assetCategory",
+        default,
+        deserialize_with = "deserialize_optional_enum"
+    )]
     pub asset_category: Option<AssetCategory>,
 
     /// Prior MTM P&L
@@ -3252,7 +4816,8 @@ pub struct PriorPeriodPosition {
     #[serde(
         rename = "@date",
         default,
-        deserialize_with = "deserialize_optional_date"
+        deserialize_with = "deserialize_optional_date",
+        serialize_with = "serialize_optional_date"
     )]
     pub date: Option<NaiveDate>,
 
@@ -3273,7 +4838,11 @@ pub struct PriorPeriodPosition {
     pub price: Option<Decimal>,
 
     /// Currency
-    #[serde(rename = "@currency", default)]
+    #[serde(
+        rename = "@currency",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub currency: Option<String>,
 }
 
@@ -3282,22 +4851,37 @@ pub struct PriorPeriodPosition {
 // =============================================================================
 
 /// Tier interest detail
-#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct TierInterestDetail {
     /// Account ID
     #[serde(rename = "@accountId")]
-    pub account_id: String,
+    pub account_id: AccountId,
 
     /// Account alias
-    #[serde(rename = "@acctAlias", default)]
+    #[serde(
+        rename = "@acctAlias",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub acct_alias: Option<String>,
 
     /// Model
-    #[serde(rename = "@model", default)]
+    #[serde(
+        rename = "@model",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub model: Option<String>,
 
     /// Currency
-    #[serde(rename = "@currency", default)]
+    #[serde(
+        rename = "@currency",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub currency: Option<String>,
 
     /// FX rate to base currency
@@ -3309,14 +4893,19 @@ pub struct TierInterestDetail {
     pub fx_rate_to_base: Option<Decimal>,
 
     /// Interest type (e.g., "Debit Interest", "Short Credit Interest")
-    #[serde(rename = "@interestType", default)]
+    #[serde(
+        rename = "@interestType",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub interest_type: Option<String>,
 
     /// Report date
     #[serde(
         rename = "@reportDate",
         default,
-        deserialize_with = "deserialize_optional_date"
+        deserialize_with = "deserialize_optional_date",
+        serialize_with = "serialize_optional_date"
     )]
     pub report_date: Option<NaiveDate>,
 
@@ -3324,12 +4913,17 @@ pub struct TierInterestDetail {
     #[serde(
         rename = "@valueDate",
         default,
-        deserialize_with = "deserialize_optional_date"
+        deserialize_with = "deserialize_optional_date",
+        serialize_with = "serialize_optional_date"
     )]
     pub value_date: Option<NaiveDate>,
 
     /// Tier break (Roman numerals like "I", "II", etc.)
-    #[serde(rename = "@tierBreak", default)]
+    #[serde(
+        rename = "@tierBreak",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub tier_break: Option<String>,
 
     /// Balance threshold
@@ -3413,19 +5007,35 @@ pub struct TierInterestDetail {
     pub total_interest: Option<Decimal>,
 
     /// Code
-    #[serde(rename = "@code", default)]
+    #[serde(
+        rename = "@code",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub code: Option<String>,
 
     /// From account
-    #[serde(rename = "@fromAcct", default)]
+    #[serde(
+        rename = "@fromAcct",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub from_acct: Option<String>,
 
     /// To account
-    #[serde(rename = "@toAcct", default)]
+    #[serde(
+        rename = "@toAcct",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub to_acct: Option<String>,
 
     /// Margin balance
-    #[serde(rename = "@marginBalance", default)]
+    #[serde(
+        rename = "@marginBalance",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub margin_balance: Option<String>,
 
     // Legacy fields (may appear in older reports)
@@ -3433,7 +5043,8 @@ pub struct TierInterestDetail {
     #[serde(
         rename = "@date",
         default,
-        deserialize_with = "deserialize_optional_date"
+        deserialize_with = "deserialize_optional_date",
+        serialize_with = "serialize_optional_date"
     )]
     pub date: Option<NaiveDate>,
 
@@ -3441,7 +5052,8 @@ pub struct TierInterestDetail {
     #[serde(
         rename = "@fromDate",
         default,
-        deserialize_with = "deserialize_optional_date"
+        deserialize_with = "deserialize_optional_date",
+        serialize_with = "serialize_optional_date"
     )]
     pub from_date: Option<NaiveDate>,
 
@@ -3449,7 +5061,8 @@ pub struct TierInterestDetail {
     #[serde(
         rename = "@toDate",
         default,
-        deserialize_with = "deserialize_optional_date"
+        deserialize_with = "deserialize_optional_date",
+        serialize_with = "serialize_optional_date"
     )]
     pub to_date: Option<NaiveDate>,
 
@@ -3483,42 +5096,70 @@ pub struct TierInterestDetail {
 // =============================================================================
 
 /// Debit card activity
-#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct DebitCardActivity {
     /// Account ID
     #[serde(rename = "@accountId")]
-    pub account_id: String,
+    pub account_id: AccountId,
 
     /// Account alias
-    #[serde(rename = "@acctAlias", default)]
+    #[serde(
+        rename = "@acctAlias",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub acct_alias: Option<String>,
 
     /// Model
-    #[serde(rename = "@model", default)]
+    #[serde(
+        rename = "@model",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub model: Option<String>,
 
     /// Date
     #[serde(
         rename = "@date",
         default,
-        deserialize_with = "deserialize_optional_date"
+        deserialize_with = "deserialize_optional_date",
+        serialize_with = "serialize_optional_date"
     )]
     pub date: Option<NaiveDate>,
 
     /// Merchant
-    #[serde(rename = "@merchant", default)]
+    #[serde(
+        rename = "@merchant",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub merchant: Option<String>,
 
     /// Category
-    #[serde(rename = "@category", default)]
+    #[serde(
+        rename = "@category",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub category: Option<String>,
 
     /// Status
-    #[serde(rename = "@status", default)]
+    #[serde(
+        rename = "@status",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub status: Option<String>,
 
     /// Transaction type
-    #[serde(rename = "@transactionType", default)]
+    #[serde(
+        rename = "@transactionType",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub transaction_type: Option<String>,
 
     /// Amount
@@ -3530,7 +5171,11 @@ pub struct DebitCardActivity {
     pub amount: Option<Decimal>,
 
     /// Currency
-    #[serde(rename = "@currency", default)]
+    #[serde(
+        rename = "@currency",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub currency: Option<String>,
 
     /// FX rate to base
@@ -3543,42 +5188,70 @@ pub struct DebitCardActivity {
 }
 
 /// Sales tax
-#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct SalesTax {
     /// Account ID
     #[serde(rename = "@accountId")]
-    pub account_id: String,
+    pub account_id: AccountId,
 
     /// Account alias
-    #[serde(rename = "@acctAlias", default)]
+    #[serde(
+        rename = "@acctAlias",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub acct_alias: Option<String>,
 
     /// Model
-    #[serde(rename = "@model", default)]
+    #[serde(
+        rename = "@model",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub model: Option<String>,
 
     /// Date
     #[serde(
         rename = "@date",
         default,
-        deserialize_with = "deserialize_optional_date"
+        deserialize_with = "deserialize_optional_date",
+        serialize_with = "serialize_optional_date"
     )]
     pub date: Option<NaiveDate>,
 
     /// Symbol
-    #[serde(rename = "@symbol", default)]
+    #[serde(
+        rename = "@symbol",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub symbol: Option<String>,
 
     /// Description
-    #[serde(rename = "@description", default)]
+    #[serde(
+        rename = "@description",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub description: Option<String>,
 
     /// Contract ID
-    #[serde(rename = "@conid", default)]
+    #[serde(
+        rename = "@conid",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub conid: Option<String>,
 
     /// Tax type
-    #[serde(rename = "@taxType", default)]
+    #[serde(
+        rename = "@taxType",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub tax_type: Option<String>,
 
     /// Tax amount
@@ -3598,7 +5271,11 @@ pub struct SalesTax {
     pub proceeds: Option<Decimal>,
 
     /// Currency
-    #[serde(rename = "@currency", default)]
+    #[serde(
+        rename = "@currency",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub currency: Option<String>,
 
     /// FX rate to base
@@ -3615,34 +5292,61 @@ pub struct SalesTax {
 // =============================================================================
 
 /// Symbol summary (aggregated trading data by symbol)
-#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct SymbolSummary {
     /// Account ID
     #[serde(rename = "@accountId")]
-    pub account_id: String,
+    pub account_id: AccountId,
 
     /// Account alias
-    #[serde(rename = "@acctAlias", default)]
+    #[serde(
+        rename = "@acctAlias",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub acct_alias: Option<String>,
 
     /// Model
-    #[serde(rename = "@model", default)]
+    #[serde(
+        rename = "@model",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub model: Option<String>,
 
     /// Symbol
-    #[serde(rename = "@symbol", default)]
+    #[serde(
+        rename = "@symbol",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub symbol: Option<String>,
 
     /// Description
-    #[serde(rename = "@description", default)]
+    #[serde(
+        rename = "@description",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub description: Option<String>,
 
     /// Contract ID
-    #[serde(rename = "@conid", default)]
+    #[serde(
+        rename = "@conid",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub conid: Option<String>,
 
     /// Asset category
-    #[serde(rename = "@assetCategory", default)]
+    #[serde(
+        rename = "@assetCategory",
+        default,
+        deserialize_with = "deserialize_optional_enum"
+    )]
     pub asset_category: Option<AssetCategory>,
 
     /// Total buy quantity
@@ -3694,27 +5398,46 @@ pub struct SymbolSummary {
     pub realized_pnl: Option<Decimal>,
 
     /// Currency
-    #[serde(rename = "@currency", default)]
+    #[serde(
+        rename = "@currency",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub currency: Option<String>,
 }
 
 /// Asset summary (aggregated trading data by asset class)
-#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct AssetSummary {
     /// Account ID
     #[serde(rename = "@accountId")]
-    pub account_id: String,
+    pub account_id: AccountId,
 
     /// Account alias
-    #[serde(rename = "@acctAlias", default)]
+    #[serde(
+        rename = "@acctAlias",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub acct_alias: Option<String>,
 
     /// Model
-    #[serde(rename = "@model", default)]
+    #[serde(
+        rename = "@model",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub model: Option<String>,
 
     /// Asset category
-    #[serde(rename = "@assetCategory", default)]
+    #[serde(
+        rename = "@assetCategory",
+        default,
+        deserialize_with = "deserialize_optional_enum"
+    )]
     pub asset_category: Option<AssetCategory>,
 
     /// Total buy quantity
@@ -3766,59 +5489,110 @@ pub struct AssetSummary {
     pub realized_pnl: Option<Decimal>,
 
     /// Currency
-    #[serde(rename = "@currency", default)]
+    #[serde(
+        rename = "@currency",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub currency: Option<String>,
 }
 
 /// Order record
-#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct Order {
     /// Account ID
     #[serde(rename = "@accountId")]
-    pub account_id: String,
+    pub account_id: AccountId,
 
     /// Account alias
-    #[serde(rename = "@acctAlias", default)]
+    #[serde(
+        rename = "@acctAlias",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub acct_alias: Option<String>,
 
     /// Model
-    #[serde(rename = "@model", default)]
+    #[serde(
+        rename = "@model",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub model: Option<String>,
 
     /// Order ID
-    #[serde(rename = "@orderID", default)]
-    pub order_id: Option<String>,
+    #[serde(
+        rename = "@orderID",
+        default,
+        deserialize_with = "deserialize_optional_from_str"
+    )]
+    pub order_id: Option<OrderId>,
 
     /// Symbol
-    #[serde(rename = "@symbol", default)]
+    #[serde(
+        rename = "@symbol",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub symbol: Option<String>,
 
     /// Description
-    #[serde(rename = "@description", default)]
+    #[serde(
+        rename = "@description",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub description: Option<String>,
 
     /// Contract ID
-    #[serde(rename = "@conid", default)]
+    #[serde(
+        rename = "@conid",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub conid: Option<String>,
 
     /// Asset category
-    #[serde(rename = "@assetCategory", default)]
+    #[serde(
+        rename = "@assetCategory",
+        default,
+        deserialize_with = "deserialize_optional_enum"
+    )]
     pub asset_category: Option<AssetCategory>,
 
     /// Order time
-    #[serde(rename = "@orderTime", default)]
+    #[serde(
+        rename = "@orderTime",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub order_time: Option<String>,
 
     /// Order type
-    #[serde(rename = "@orderType", default)]
+    #[serde(
+        rename = "@orderType",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub order_type: Option<String>,
 
     /// Side (buy/sell)
-    #[serde(rename = "@side", default)]
+    #[serde(
+        rename = "@side",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub side: Option<String>,
 
     /// Time in force
-    #[serde(rename = "@tif", default)]
+    #[serde(
+        rename = "@tif",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub tif: Option<String>,
 
     /// Order quantity
@@ -3870,10 +5644,18 @@ pub struct Order {
     pub remaining_qty: Option<Decimal>,
 
     /// Order status
-    #[serde(rename = "@status", default)]
+    #[serde(
+        rename = "@status",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub status: Option<String>,
 
     /// Currency
-    #[serde(rename = "@currency", default)]
+    #[serde(
+        rename = "@currency",
+        default,
+        deserialize_with = "deserialize_optional_string"
+    )]
     pub currency: Option<String>,
 }
@@ -0,0 +1,211 @@
+//! Currency conversion lookups built from FLEX conversion rates
+
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use std::collections::{BTreeMap, HashMap};
+
+use super::activity::{ConversionRate, ConversionRatesWrapper};
+
+/// Lookup table of FX rates keyed by currency pair and report date
+///
+/// Built from the `ConversionRates` section via [`ConversionRatesWrapper::to_table`].
+/// IB reports every rate against the account's base currency, so lookups
+/// between two non-base currencies are triangulated through the base.
+///
+/// When no rate exists for the requested date, the most recent earlier rate
+/// is used, which covers weekends and holidays in multi-day statements.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct FxRateTable {
+    base_currency: Option<String>,
+    rates: HashMap<(String, String), BTreeMap<NaiveDate, Decimal>>,
+}
+
+impl FxRateTable {
+    /// Create an empty table
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Build a table from conversion rate rows
+    ///
+    /// The base currency is inferred as the most common `toCurrency`.
+    pub fn from_rates<'a>(rates: impl IntoIterator<Item = &'a ConversionRate>) -> Self {
+        let mut table = Self::new();
+        let mut targets: HashMap<&str, usize> = HashMap::new();
+        for rate in rates {
+            *targets.entry(rate.to_currency.as_str()).or_default() += 1;
+            table.insert(
+                &rate.from_currency,
+                &rate.to_currency,
+                rate.report_date,
+                rate.rate,
+            );
+        }
+        table.base_currency = targets
+            .into_iter()
+            .max_by(|(a_ccy, a), (b_ccy, b)| a.cmp(b).then_with(|| b_ccy.cmp(a_ccy)))
+            .map(|(ccy, _)| ccy.to_string());
+        table
+    }
+
+    /// Base currency that rates are quoted against, if known
+    pub fn base_currency(&self) -> Option<&str> {
+        self.base_currency.as_deref()
+    }
+
+    /// Override the base currency used for triangulation
+    pub fn with_base_currency(mut self, currency: impl Into<String>) -> Self {
+        self.base_currency = Some(currency.into());
+        self
+    }
+
+    /// Record a rate converting one unit of `from` into `to`
+    pub fn insert(&mut self, from: &str, to: &str, date: NaiveDate, rate: Decimal) {
+        self.rates
+            .entry((from.to_string(), to.to_string()))
+            .or_default()
+            .insert(date, rate);
+    }
+
+    /// Returns true if the table holds no rates
+    pub fn is_empty(&self) -> bool {
+        self.rates.is_empty()
+    }
+
+    /// Rate converting one unit of `from` into `to` on `date`
+    ///
+    /// Tries the direct pair, then the inverse, then triangulates through the
+    /// base currency. Returns `None` if no path is available.
+    pub fn rate(&self, from: &str, to: &str, date: NaiveDate) -> Option<Decimal> {
+        if from == to {
+            return Some(Decimal::ONE);
+        }
+        if let Some(rate) = self.pair_rate(from, to, date) {
+            return Some(rate);
+        }
+        let base = self.base_currency.as_deref()?;
+        if from == base || to == base {
+            return None;
+        }
+        let to_base = self.pair_rate(from, base, date)?;
+        let from_base = self.pair_rate(base, to, date)?;
+        Some(to_base * from_base)
+    }
+
+    /// Convert `amount` from one currency to another on `date`
+    pub fn convert(
+        &self,
+        amount: Decimal,
+        from: &str,
+        to: &str,
+        date: NaiveDate,
+    ) -> Option<Decimal> {
+        self.rate(from, to, date).map(|rate| amount * rate)
+    }
+
+    /// Convert `amount` into the base currency on `date`
+    pub fn to_base(&self, amount: Decimal, from: &str, date: NaiveDate) -> Option<Decimal> {
+        let base = self.base_currency.as_deref()?;
+        self.convert(amount, from, base, date)
+    }
+
+    fn pair_rate(&self, from: &str, to: &str, date: NaiveDate) -> Option<Decimal> {
+        if let Some(rate) = self.lookup(from, to, date) {
+            return Some(rate);
+        }
+        self.lookup(to, from, date)
+            .filter(|rate| !rate.is_zero())
+            .map(|rate| Decimal::ONE / rate)
+    }
+
+    fn lookup(&self, from: &str, to: &str, date: NaiveDate) -> Option<Decimal> {
+        self.rates
+            .get(&(from.to_string(), to.to_string()))?
+            .range(..=date)
+            .next_back()
+            .map(|(_, rate)| *rate)
+    }
+}
+
+impl ConversionRatesWrapper {
+    /// Build an [`FxRateTable`] from the conversion rates in this section
+    pub fn to_table(&self) -> FxRateTable {
+        FxRateTable::from_rates(&self.items)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 1, day).unwrap()
+    }
+
+    fn rate(from: &str, to: &str, day: u32, value: &str) -> ConversionRate {
+        ConversionRate {
+            report_date: date(day),
+            from_currency: from.to_string(),
+            to_currency: to.to_string(),
+            rate: Decimal::from_str(value).unwrap(),
+        }
+    }
+
+    fn table() -> FxRateTable {
+        let rates = [
+            rate("EUR", "USD", 2, "1.10"),
+            rate("GBP", "USD", 2, "1.25"),
+            rate("EUR", "USD", 3, "1.12"),
+        ];
+        FxRateTable::from_rates(&rates)
+    }
+
+    #[test]
+    fn test_direct_and_inverse_rates() {
+        let table = table();
+        assert_eq!(table.base_currency(), Some("USD"));
+        assert_eq!(table.rate("USD", "USD", date(2)), Some(Decimal::ONE));
+        assert_eq!(
+            table.rate("EUR", "USD", date(2)),
+            Decimal::from_str("1.10").ok()
+        );
+        assert_eq!(
+            table.rate("USD", "GBP", date(2)),
+            Decimal::from_str("0.8").ok()
+        );
+    }
+
+    #[test]
+    fn test_triangulation_through_base() {
+        let table = table();
+        // 1 GBP = 1.25 USD, 1 USD = 1/1.10 EUR
+        let gbp_eur = table.rate("GBP", "EUR", date(2)).unwrap().round_dp(6);
+        assert_eq!(gbp_eur, Decimal::from_str("1.136364").unwrap());
+        assert_eq!(table.rate("JPY", "EUR", date(2)), None);
+    }
+
+    #[test]
+    fn test_falls_back_to_prior_date() {
+        let table = table();
+        assert_eq!(
+            table.rate("EUR", "USD", date(3)),
+            Decimal::from_str("1.12").ok()
+        );
+        assert_eq!(
+            table.rate("EUR", "USD", date(6)),
+            Decimal::from_str("1.12").ok()
+        );
+        assert_eq!(table.rate("EUR", "USD", date(1)), None);
+    }
+
+    #[test]
+    fn test_to_base() {
+        let table = table();
+        let amount = Decimal::from(100);
+        assert_eq!(
+            table.to_base(amount, "GBP", date(2)),
+            Some(Decimal::from(125))
+        );
+    }
+}
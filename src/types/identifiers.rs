@@ -0,0 +1,379 @@
+//! Validated security identifiers (requires the `security-ids` feature)
+//!
+//! FLEX rows carry ISIN, CUSIP, and FIGI codes as plain strings, along with a
+//! generic `securityID`/`securityIDType` pair. The types here normalize those
+//! strings and verify their check digits, so a truncated or mistyped
+//! identifier is reported instead of silently failing a join.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use super::activity::{CashTransaction, Position, SecurityInfo, Trade};
+use super::common::SecurityIdType;
+use crate::error::{ParseError, Result};
+
+/// Value of an identifier character in the check-digit schemes (0-9, A=10 ... Z=35)
+fn char_value(c: u8) -> Option<u32> {
+    match c {
+        b'0'..=b'9' => Some((c - b'0') as u32),
+        b'A'..=b'Z' => Some((c - b'A') as u32 + 10),
+        _ => None,
+    }
+}
+
+/// Sum of the decimal digits of `n`
+fn digit_sum(n: u32) -> u32 {
+    n / 10 + n % 10
+}
+
+fn normalize(s: &str) -> String {
+    s.trim().to_ascii_uppercase()
+}
+
+fn invalid(kind: &str, value: &str) -> ParseError {
+    ParseError::InvalidIdentifier {
+        value: value.to_string(),
+        kind: kind.to_string(),
+    }
+}
+
+/// Luhn check over the digit expansion used by ISIN (letters become two digits)
+fn isin_check(code: &str) -> bool {
+    let digits: Vec<u32> = code
+        .bytes()
+        .filter_map(char_value)
+        .flat_map(|v| {
+            if v >= 10 {
+                vec![v / 10, v % 10]
+            } else {
+                vec![v]
+            }
+        })
+        .collect();
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &d)| if i % 2 == 1 { digit_sum(d * 2) } else { d })
+        .sum();
+    sum % 10 == 0
+}
+
+/// Check digit shared by CUSIP and FIGI: double every second character's
+/// value and sum the digits
+fn doubled_check_digit(body: &[u8], value: impl Fn(u8) -> Option<u32>) -> Option<u32> {
+    let mut sum = 0;
+    for (i, &c) in body.iter().enumerate() {
+        let v = value(c)?;
+        sum += digit_sum(if i % 2 == 1 { v * 2 } else { v });
+    }
+    Some((10 - sum % 10) % 10)
+}
+
+macro_rules! identifier {
+    ($(#[$meta:meta])* $name:ident, $kind:literal, |$code:ident| $valid:expr) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+        pub struct $name(String);
+
+        impl $name {
+            /// The normalized (trimmed, uppercase) identifier
+            pub fn as_str(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl std::str::FromStr for $name {
+            type Err = ParseError;
+
+            /// Normalizes case and whitespace, then verifies length,
+            /// character set, and check digit
+            fn from_str(s: &str) -> Result<Self> {
+                let $code = normalize(s);
+                let valid: bool = $valid;
+                if valid {
+                    Ok($name($code))
+                } else {
+                    Err(invalid($kind, s))
+                }
+            }
+        }
+
+        impl std::fmt::Display for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str(&self.0)
+            }
+        }
+
+        impl AsRef<str> for $name {
+            fn as_ref(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl Serialize for $name {
+            fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+                serializer.serialize_str(&self.0)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+                let s = String::deserialize(deserializer)?;
+                s.parse().map_err(serde::de::Error::custom)
+            }
+        }
+    };
+}
+
+identifier!(
+    /// International Securities Identification Number (ISO 6166)
+    ///
+    /// # Example
+    /// ```
+    /// use ib_flex::types::Isin;
+    ///
+    /// let isin: Isin = "us0378331005".parse().unwrap();
+    /// assert_eq!(isin.as_str(), "US0378331005");
+    /// assert_eq!(isin.country_code(), "US");
+    /// assert!("US0378331006".parse::<Isin>().is_err());
+    /// ```
+    Isin,
+    "ISIN",
+    |code| {
+        let bytes = code.as_bytes();
+        bytes.len() == 12
+            && bytes[..2].iter().all(u8::is_ascii_uppercase)
+            && bytes[2..11].iter().all(u8::is_ascii_alphanumeric)
+            && bytes[11].is_ascii_digit()
+            && isin_check(&code)
+    }
+);
+
+identifier!(
+    /// CUSIP, the 9-character North American security identifier
+    Cusip,
+    "CUSIP",
+    |code| {
+        let bytes = code.as_bytes();
+        let value = |c: u8| match c {
+            b'*' => Some(36),
+            b'@' => Some(37),
+            b'#' => Some(38),
+            _ => char_value(c),
+        };
+        bytes.len() == 9
+            && bytes[8].is_ascii_digit()
+            && doubled_check_digit(&bytes[..8], value) == Some((bytes[8] - b'0') as u32)
+    }
+);
+
+identifier!(
+    /// Financial Instrument Global Identifier (e.g., `BBG000B9XRY4`)
+    Figi,
+    "FIGI",
+    |code| {
+        let bytes = code.as_bytes();
+        let consonant = |c: &u8| c.is_ascii_uppercase() && !b"AEIOU".contains(c);
+        bytes.len() == 12
+            && consonant(&bytes[0])
+            && consonant(&bytes[1])
+            && bytes[2] == b'G'
+            && bytes[3..11]
+                .iter()
+                .all(|c| c.is_ascii_digit() || consonant(c))
+            && bytes[11].is_ascii_digit()
+            && doubled_check_digit(&bytes[..11], char_value) == Some((bytes[11] - b'0') as u32)
+    }
+);
+
+identifier!(
+    /// SEDOL, the 7-character London Stock Exchange identifier
+    Sedol,
+    "SEDOL",
+    |code| {
+        const WEIGHTS: [u32; 6] = [1, 3, 1, 7, 3, 9];
+        let bytes = code.as_bytes();
+        bytes.len() == 7
+            && bytes[6].is_ascii_digit()
+            && bytes[..6]
+                .iter()
+                .all(|c| c.is_ascii_digit() || (c.is_ascii_uppercase() && !b"AEIOU".contains(c)))
+            && {
+                let sum: u32 = bytes[..6]
+                    .iter()
+                    .zip(WEIGHTS)
+                    .filter_map(|(&c, w)| Some(char_value(c)? * w))
+                    .sum();
+                (10 - sum % 10) % 10 == (bytes[6] - b'0') as u32
+            }
+    }
+);
+
+impl Isin {
+    /// Two-letter ISO 3166 country prefix
+    pub fn country_code(&self) -> &str {
+        &self.0[..2]
+    }
+
+    /// The embedded national identifier (the CUSIP for US and CA ISINs)
+    pub fn national_id(&self) -> &str {
+        &self.0[2..11]
+    }
+}
+
+/// A security identifier from `securityID` + `securityIDType`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum SecurityId {
+    /// Validated ISIN
+    Isin(Isin),
+    /// Validated CUSIP
+    Cusip(Cusip),
+    /// Validated FIGI
+    Figi(Figi),
+    /// Validated SEDOL
+    Sedol(Sedol),
+    /// Identifier of a type with no checksum scheme, kept verbatim
+    Other {
+        /// The `securityIDType` as reported
+        id_type: String,
+        /// The `securityID` as reported
+        value: String,
+    },
+}
+
+impl SecurityId {
+    /// Combine a raw ID with its reported type, validating known types
+    pub fn new(value: &str, id_type: &SecurityIdType) -> Result<Self> {
+        Ok(match id_type {
+            SecurityIdType::Isin => SecurityId::Isin(value.parse()?),
+            SecurityIdType::Cusip => SecurityId::Cusip(value.parse()?),
+            SecurityIdType::Figi => SecurityId::Figi(value.parse()?),
+            SecurityIdType::Sedol => SecurityId::Sedol(value.parse()?),
+            other => SecurityId::Other {
+                id_type: other.as_str().to_string(),
+                value: value.trim().to_string(),
+            },
+        })
+    }
+
+    /// The identifier string
+    pub fn as_str(&self) -> &str {
+        match self {
+            SecurityId::Isin(id) => id.as_str(),
+            SecurityId::Cusip(id) => id.as_str(),
+            SecurityId::Figi(id) => id.as_str(),
+            SecurityId::Sedol(id) => id.as_str(),
+            SecurityId::Other { value, .. } => value,
+        }
+    }
+}
+
+impl std::fmt::Display for SecurityId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Parse an optional identifier field, treating empty strings as absent
+fn parse_optional<T: std::str::FromStr<Err = ParseError>>(
+    value: &Option<String>,
+) -> Result<Option<T>> {
+    match value.as_deref().map(str::trim) {
+        None | Some("") => Ok(None),
+        Some(s) => s.parse().map(Some),
+    }
+}
+
+macro_rules! impl_identifier_accessors {
+    ($($ty:ident),+) => {
+        $(
+            impl $ty {
+                /// Validated ISIN, if the row has one
+                pub fn isin_code(&self) -> Result<Option<Isin>> {
+                    parse_optional(&self.isin)
+                }
+
+                /// Validated CUSIP, if the row has one
+                pub fn cusip_code(&self) -> Result<Option<Cusip>> {
+                    parse_optional(&self.cusip)
+                }
+
+                /// Validated FIGI, if the row has one
+                pub fn figi_code(&self) -> Result<Option<Figi>> {
+                    parse_optional(&self.figi)
+                }
+
+                /// `securityID` combined with `securityIDType`, validated when
+                /// the type has a checksum scheme
+                pub fn security_identifier(&self) -> Result<Option<SecurityId>> {
+                    let value = match self.security_id.as_deref().map(str::trim) {
+                        None | Some("") => return Ok(None),
+                        Some(value) => value,
+                    };
+                    match &self.security_id_type {
+                        Some(id_type) => SecurityId::new(value, id_type).map(Some),
+                        None => Ok(Some(SecurityId::Other {
+                            id_type: String::new(),
+                            value: value.to_string(),
+                        })),
+                    }
+                }
+            }
+        )+
+    };
+}
+
+impl_identifier_accessors!(Trade, Position, CashTransaction, SecurityInfo);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_valid_identifiers() {
+        for isin in [
+            "US0378331005",
+            "US5949181045",
+            "US0231351067",
+            "GB0002634946",
+        ] {
+            assert!(isin.parse::<Isin>().is_ok(), "{}", isin);
+        }
+        for cusip in ["037833100", "594918104", "023135106", "67066G104"] {
+            assert!(cusip.parse::<Cusip>().is_ok(), "{}", cusip);
+        }
+        assert!("BBG000B9XRY4".parse::<Figi>().is_ok());
+        assert!("0263494".parse::<Sedol>().is_ok());
+        assert!("B0YBKJ7".parse::<Sedol>().is_ok());
+    }
+
+    #[test]
+    fn test_invalid_identifiers() {
+        assert!("US0378331004".parse::<Isin>().is_err());
+        assert!("US037833100".parse::<Isin>().is_err());
+        assert!("037833101".parse::<Cusip>().is_err());
+        assert!("BBG000B9XRY5".parse::<Figi>().is_err());
+        assert!("BAG000B9XRY4".parse::<Figi>().is_err());
+        assert!("0263495".parse::<Sedol>().is_err());
+
+        let err = "XX".parse::<Cusip>().unwrap_err();
+        assert!(matches!(err, ParseError::InvalidIdentifier { kind, .. } if kind == "CUSIP"));
+    }
+
+    #[test]
+    fn test_isin_embeds_cusip() {
+        let isin: Isin = "US0378331005".parse().unwrap();
+        let cusip: Cusip = isin.national_id().parse().unwrap();
+        assert_eq!(cusip.as_str(), "037833100");
+    }
+
+    #[test]
+    fn test_security_id_from_type() {
+        let id = SecurityId::new(" us0378331005 ", &SecurityIdType::Isin).unwrap();
+        assert_eq!(id, SecurityId::Isin("US0378331005".parse().unwrap()));
+
+        let other = SecurityId::new("ESH5", &SecurityIdType::Unknown("CONID".to_string())).unwrap();
+        assert_eq!(other.to_string(), "ESH5");
+        assert!(SecurityId::new("bogus", &SecurityIdType::Cusip).is_err());
+    }
+}
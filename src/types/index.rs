@@ -0,0 +1,228 @@
+//! Lookup tables across statement sections
+//!
+//! Joining sections (the security behind a trade, the executions of an
+//! order, everything booked on a day) otherwise means scanning a section
+//! per lookup. [`StatementIndex`] builds the maps once, borrowing the rows
+//! from one or more statements.
+//!
+//! # Example
+//! ```
+//! use ib_flex::parse_activity_flex;
+//! use ib_flex::types::StatementIndex;
+//!
+//! let xml = include_str!("../../tests/fixtures/activity_minimal.xml");
+//! let statement = parse_activity_flex(xml).unwrap();
+//! let index = StatementIndex::new(&statement);
+//!
+//! for trade in &statement.trades.items {
+//!     if let Some(info) = index.security(trade.conid) {
+//!         println!("{} {:?}", trade.symbol, info.description);
+//!     }
+//! }
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::NaiveDate;
+
+use super::activity::{
+    ActivityFlexStatement, CashTransaction, CorporateAction, Position, SecurityInfo, Trade,
+};
+use super::common::{Conid, LevelOfDetail, OrderId, TradeId};
+use super::traits::Dated;
+
+impl ActivityFlexStatement {
+    /// Build a [`StatementIndex`] over this statement
+    pub fn index(&self) -> StatementIndex<'_> {
+        StatementIndex::new(self)
+    }
+}
+
+/// Transactions booked on one day
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "eq", derive(PartialEq))]
+pub struct DayRecords<'a> {
+    /// Trades by trade date (report date when absent)
+    pub trades: Vec<&'a Trade>,
+    /// Cash transactions by settle date (report date when absent)
+    pub cash_transactions: Vec<&'a CashTransaction>,
+    /// Corporate actions by action date (report date when absent)
+    pub corporate_actions: Vec<&'a CorporateAction>,
+}
+
+/// Maps from IDs and dates to statement rows, built once
+///
+/// Rows are borrowed, in statement order. Execution lookups only cover
+/// execution-level trades, so closed lots and order summaries sharing a
+/// `tradeID` don't shadow the execution.
+#[derive(Debug, Clone, Default)]
+pub struct StatementIndex<'a> {
+    securities: HashMap<Conid, &'a SecurityInfo>,
+    positions: HashMap<Conid, Vec<&'a Position>>,
+    trades: HashMap<&'a TradeId, &'a Trade>,
+    orders: HashMap<&'a OrderId, Vec<&'a Trade>>,
+    days: BTreeMap<NaiveDate, DayRecords<'a>>,
+}
+
+impl<'a> StatementIndex<'a> {
+    /// Index one statement
+    pub fn new(statement: &'a ActivityFlexStatement) -> Self {
+        Self::from_statements([statement])
+    }
+
+    /// Index several statements, such as a backfill of daily downloads
+    ///
+    /// When a `SecuritiesInfo` row or execution ID repeats, the one from the
+    /// later statement wins.
+    pub fn from_statements(
+        statements: impl IntoIterator<Item = &'a ActivityFlexStatement>,
+    ) -> Self {
+        let mut index = StatementIndex::default();
+        for statement in statements {
+            index.add(statement);
+        }
+        index
+    }
+
+    fn add(&mut self, statement: &'a ActivityFlexStatement) {
+        for info in statement.securities_info.iter() {
+            self.securities.insert(info.conid, info);
+        }
+        for position in statement.positions.iter() {
+            self.positions
+                .entry(position.conid)
+                .or_default()
+                .push(position);
+        }
+        for trade in statement.trades.iter() {
+            if let Some(date) = trade.date() {
+                self.days.entry(date).or_default().trades.push(trade);
+            }
+            if !matches!(trade.level_of_detail, None | Some(LevelOfDetail::Execution)) {
+                continue;
+            }
+            if let Some(id) = trade.trade_id.as_ref().filter(|id| !id.is_empty()) {
+                self.trades.insert(id, trade);
+            }
+            if let Some(id) = trade.ib_order_id.as_ref().filter(|id| !id.is_empty()) {
+                self.orders.entry(id).or_default().push(trade);
+            }
+        }
+        for tx in statement.cash_transactions.iter() {
+            if let Some(date) = tx.date() {
+                self.days
+                    .entry(date)
+                    .or_default()
+                    .cash_transactions
+                    .push(tx);
+            }
+        }
+        for action in statement.corporate_actions.iter() {
+            if let Some(date) = action.date() {
+                self.days
+                    .entry(date)
+                    .or_default()
+                    .corporate_actions
+                    .push(action);
+            }
+        }
+    }
+
+    /// `SecuritiesInfo` row for a contract
+    pub fn security(&self, conid: Conid) -> Option<&'a SecurityInfo> {
+        self.securities.get(&conid).copied()
+    }
+
+    /// Open position rows for a contract (one per account, lot, or report date)
+    pub fn positions(&self, conid: Conid) -> &[&'a Position] {
+        self.positions.get(&conid).map_or(&[], Vec::as_slice)
+    }
+
+    /// Execution with the given `tradeID`
+    pub fn trade(&self, trade_id: &str) -> Option<&'a Trade> {
+        self.trades.get(&TradeId::from(trade_id)).copied()
+    }
+
+    /// Executions of the order with the given `ibOrderID`
+    pub fn executions(&self, order_id: &str) -> &[&'a Trade] {
+        self.orders
+            .get(&OrderId::from(order_id))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// Transactions booked on `date`
+    pub fn on(&self, date: NaiveDate) -> Option<&DayRecords<'a>> {
+        self.days.get(&date)
+    }
+
+    /// Days with transactions within `from..=to`, in date order
+    pub fn days_between(
+        &self,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> impl Iterator<Item = (NaiveDate, &DayRecords<'a>)> {
+        self.days
+            .range(from..=to)
+            .map(|(date, records)| (*date, records))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{
+        CashTransactionBuilder, CashTransactionType, PositionBuilder, TradeBuilder,
+    };
+
+    fn day(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 6, d).unwrap()
+    }
+
+    #[test]
+    fn test_lookups_across_sections() {
+        let xml = include_str!("../../tests/fixtures/activity_minimal.xml");
+        let mut statement = crate::parse_activity_flex(xml).unwrap();
+        let mut lot = TradeBuilder::new("AAPL")
+            .conid(1)
+            .trade_id("E2")
+            .trade_date(day(3))
+            .build();
+        lot.level_of_detail = Some(LevelOfDetail::ClosedLot);
+        statement.trades.items = vec![
+            TradeBuilder::new("AAPL")
+                .conid(1)
+                .trade_id("E1")
+                .ib_order_id("O1")
+                .trade_date(day(2))
+                .build(),
+            TradeBuilder::new("AAPL")
+                .conid(1)
+                .trade_id("E2")
+                .ib_order_id("O1")
+                .trade_date(day(2))
+                .build(),
+            lot,
+        ];
+        statement.positions.items = vec![PositionBuilder::new("AAPL").conid(1).build()];
+        statement.cash_transactions.items =
+            vec![
+                CashTransactionBuilder::new(CashTransactionType::Dividends, 25)
+                    .date(day(3))
+                    .build(),
+            ];
+
+        let index = statement.index();
+        assert_eq!(index.positions(Conid(1)).len(), 1);
+        assert!(index.positions(Conid(2)).is_empty());
+        assert_eq!(index.executions("O1").len(), 2);
+        // The closed lot shares E2 but doesn't replace the execution
+        assert_eq!(index.trade("E2").unwrap().trade_date, Some(day(2)));
+        assert!(index.trade("E3").is_none());
+
+        assert_eq!(index.on(day(2)).unwrap().trades.len(), 2);
+        let third = index.on(day(3)).unwrap();
+        assert_eq!(third.trades.len(), 1);
+        assert_eq!(third.cash_transactions.len(), 1);
+        assert_eq!(index.days_between(day(1), day(30)).count(), 2);
+    }
+}
@@ -0,0 +1,308 @@
+//! Merging overlapping statements (requires the `eq` feature, on by default)
+//!
+//! Backfills often fetch daily or weekly statements whose periods overlap,
+//! so the same trade or cash transaction appears in several of them.
+//! [`merge_statements`] combines such statements for one account into a
+//! single statement covering their whole period, with each transaction
+//! kept once.
+//!
+//! # Example
+//! ```
+//! use ib_flex::{merge_statements, parse_activity_flex};
+//!
+//! let xml = include_str!("../../tests/fixtures/activity_minimal.xml");
+//! let monday = parse_activity_flex(xml).unwrap();
+//! let week = parse_activity_flex(xml).unwrap();
+//!
+//! let merged = merge_statements(vec![monday, week]).unwrap();
+//! assert_eq!(merged.trades.len(), 1);
+//! ```
+
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
+use super::activity::{ActivityFlexStatement, CashTransaction, CorporateAction, Trade};
+use super::common::LevelOfDetail;
+use crate::error::{ParseError, Result};
+
+/// Merge statements for one account into a single statement
+///
+/// The merged statement runs from the earliest `fromDate` to the latest
+/// `toDate`. Sections are combined by kind:
+///
+/// - Transaction rows (trades, cash transactions, corporate actions,
+///   confirmations, exercises and assignments, FX, transfers, deposits and
+///   withdrawals) are deduplicated by `transactionID`, or `tradeID` for
+///   executions without one, keeping the version from the latest
+///   statement. Rows with neither, and all other row-level sections, are
+///   deduplicated when identical.
+/// - Daily rows (`EquitySummaryInBase`) are kept once per report date and
+///   `SecuritiesInfo` once per contract, again preferring the latest.
+/// - Snapshots (open positions, dividend accruals, FX positions, pending
+///   transfers, account information, MTD/YTD performance) come from the
+///   statement with the latest `toDate`, then the latest `whenGenerated`.
+/// - Period totals (`ChangeInNAV`, `CashReport`, `InterestAccruals`,
+///   performance summaries, and `ChangeInPositionValues`) cannot be added
+///   up across overlapping periods, so they come from the statement
+///   covering the longest period and only describe that period.
+///
+/// # Errors
+///
+/// Returns [`ParseError::CannotMerge`] if `statements` is empty or covers
+/// more than one account.
+pub fn merge_statements(
+    mut statements: Vec<ActivityFlexStatement>,
+) -> Result<ActivityFlexStatement> {
+    let Some(first) = statements.first() else {
+        return Err(ParseError::CannotMerge("no statements".to_string()));
+    };
+    if let Some(other) = statements.iter().find(|s| s.account_id != first.account_id) {
+        return Err(ParseError::CannotMerge(format!(
+            "statements for accounts {} and {}",
+            first.account_id, other.account_id
+        )));
+    }
+    statements.sort_by_key(|s| (s.to_date, s.when_generated_datetime().ok()));
+
+    let from_date = statements.iter().map(|s| s.from_date).min();
+    let longest = statements
+        .iter()
+        .enumerate()
+        .max_by_key(|(index, s)| (s.to_date - s.from_date, *index))
+        .map(|(index, _)| index)
+        .unwrap_or_default();
+    let all = &statements;
+    let longest = &statements[longest];
+    let latest = &statements[statements.len() - 1];
+
+    let mut merged = latest.clone();
+    merged.from_date = from_date.unwrap_or(latest.from_date);
+    merged.timezone = all.iter().rev().find_map(|s| s.timezone.clone());
+    merged.section_errors = all.iter().flat_map(|s| s.section_errors.clone()).collect();
+
+    merged.trades.items = dedup(all.iter().flat_map(|s| s.trades.iter()), trade_key);
+    merged.trades.wash_sales = dedup(all.iter().flat_map(|s| &s.trades.wash_sales), no_key);
+    // Each statement's summaries total its own trades only
+    merged.trades.symbol_summaries.clear();
+    merged.cash_transactions.items = dedup(
+        all.iter().flat_map(|s| s.cash_transactions.iter()),
+        cash_transaction_key,
+    );
+    merged.corporate_actions.items = dedup(
+        all.iter().flat_map(|s| s.corporate_actions.iter()),
+        corporate_action_key,
+    );
+    merged.trade_confirms.items = dedup(all.iter().flat_map(|s| s.trade_confirms.iter()), |c| {
+        id(&c.transaction_id)
+    });
+    merged.option_eae.items = dedup(all.iter().flat_map(|s| s.option_eae.iter()), |e| {
+        id(&e.transaction_id)
+    });
+    merged.fx_transactions.items = dedup(all.iter().flat_map(|s| s.fx_transactions.iter()), |f| {
+        id(&f.transaction_id)
+    });
+    merged.transfers.items = dedup(all.iter().flat_map(|s| s.transfers.iter()), |t| {
+        id(&t.transaction_id)
+    });
+    merged.deposits_withdrawals.items = dedup(
+        all.iter().flat_map(|s| s.deposits_withdrawals.iter()),
+        |d| id(&d.transaction_id),
+    );
+
+    merged.equity_summary.items = dedup(all.iter().flat_map(|s| s.equity_summary.iter()), |e| {
+        Some(e.report_date)
+    });
+    merged.securities_info.items = dedup(all.iter().flat_map(|s| s.securities_info.iter()), |i| {
+        Some(i.conid)
+    });
+
+    merged.conversion_rates.items =
+        dedup(all.iter().flat_map(|s| s.conversion_rates.iter()), no_key);
+    merged.change_in_dividend_accruals.items = dedup(
+        all.iter()
+            .flat_map(|s| s.change_in_dividend_accruals.iter()),
+        no_key,
+    );
+    merged.statement_of_funds.items =
+        dedup(all.iter().flat_map(|s| s.statement_of_funds.iter()), no_key);
+    merged.unbundled_commission_details.items = dedup(
+        all.iter()
+            .flat_map(|s| s.unbundled_commission_details.iter()),
+        no_key,
+    );
+    merged.client_fees.items = dedup(all.iter().flat_map(|s| s.client_fees.iter()), no_key);
+    merged.client_fees_detail.items =
+        dedup(all.iter().flat_map(|s| s.client_fees_detail.iter()), no_key);
+    merged.slb_activities.items = dedup(all.iter().flat_map(|s| s.slb_activities.iter()), no_key);
+    merged.slb_fees.items = dedup(all.iter().flat_map(|s| s.slb_fees.iter()), no_key);
+    merged.hard_to_borrow_details.items = dedup(
+        all.iter().flat_map(|s| s.hard_to_borrow_details.iter()),
+        no_key,
+    );
+    merged.trade_transfers.items = dedup(all.iter().flat_map(|s| s.trade_transfers.iter()), no_key);
+    merged.prior_period_positions.items = dedup(
+        all.iter().flat_map(|s| s.prior_period_positions.iter()),
+        no_key,
+    );
+    merged.tier_interest_details.items = dedup(
+        all.iter().flat_map(|s| s.tier_interest_details.iter()),
+        no_key,
+    );
+    merged.debit_card_activities.items = dedup(
+        all.iter().flat_map(|s| s.debit_card_activities.iter()),
+        no_key,
+    );
+    merged.sales_tax.items = dedup(all.iter().flat_map(|s| s.sales_tax.iter()), no_key);
+
+    merged.change_in_nav = longest.change_in_nav.clone();
+    merged.cash_report = longest.cash_report.clone();
+    merged.interest_accruals = longest.interest_accruals.clone();
+    merged.mtm_performance_summary = longest.mtm_performance_summary.clone();
+    merged.fifo_performance_summary = longest.fifo_performance_summary.clone();
+    merged.change_in_position_values = longest.change_in_position_values.clone();
+
+    Ok(merged)
+}
+
+/// Rows in order of first appearance, keeping the last version of each key
+///
+/// Rows without a key are dropped only when identical to an earlier row.
+fn dedup<'a, T, K>(rows: impl IntoIterator<Item = &'a T>, key: impl Fn(&T) -> Option<K>) -> Vec<T>
+where
+    T: Clone + Eq + Hash + 'a,
+    K: Eq + Hash,
+{
+    let mut merged: Vec<T> = Vec::new();
+    let mut keyed: HashMap<K, usize> = HashMap::new();
+    let mut seen: HashSet<&T> = HashSet::new();
+    for row in rows {
+        match key(row) {
+            Some(key) => match keyed.entry(key) {
+                Entry::Occupied(entry) => merged[*entry.get()] = row.clone(),
+                Entry::Vacant(entry) => {
+                    entry.insert(merged.len());
+                    merged.push(row.clone());
+                }
+            },
+            None => {
+                if seen.insert(row) {
+                    merged.push(row.clone());
+                }
+            }
+        }
+    }
+    merged
+}
+
+fn no_key<T>(_: &T) -> Option<()> {
+    None
+}
+
+pub(super) fn id(id: &Option<String>) -> Option<String> {
+    id.as_deref()
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .map(str::to_string)
+}
+
+/// `transactionID`, or `tradeID` for executions, per level of detail
+///
+/// `CLOSED_LOT` rows repeat the `tradeID` of their closing execution, so
+/// they only fall back to equality.
+pub(super) fn trade_key(trade: &Trade) -> Option<(Option<LevelOfDetail>, String)> {
+    let id = id(&trade.transaction_id).or_else(|| {
+        matches!(trade.level_of_detail, None | Some(LevelOfDetail::Execution))
+            .then(|| trade.trade_id.as_ref().map(|id| id.as_str().to_string()))
+            .flatten()
+    })?;
+    Some((trade.level_of_detail.clone(), id))
+}
+
+pub(super) fn cash_transaction_key(
+    tx: &CashTransaction,
+) -> Option<(Option<LevelOfDetail>, String)> {
+    Some((tx.level_of_detail.clone(), id(&tx.transaction_id)?))
+}
+
+pub(super) fn corporate_action_key(action: &CorporateAction) -> Option<(Option<String>, String)> {
+    Some((action.level_of_detail.clone(), id(&action.transaction_id)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{
+        CashTransactionBuilder, CashTransactionType, PositionBuilder, TradeBuilder,
+    };
+    use chrono::NaiveDate;
+    use rust_decimal::Decimal;
+
+    fn day(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 6, d).unwrap()
+    }
+
+    fn statement(from: u32, to: u32, trade_ids: &[&str]) -> ActivityFlexStatement {
+        let xml = include_str!("../../tests/fixtures/activity_minimal.xml");
+        let mut statement = crate::parse_activity_flex(xml).unwrap();
+        statement.from_date = day(from);
+        statement.to_date = day(to);
+        statement.trades.items = trade_ids
+            .iter()
+            .map(|id| {
+                TradeBuilder::new("AAPL")
+                    .conid(1)
+                    .transaction_id(*id)
+                    .trade_date(day(from))
+                    .quantity(10)
+                    .build()
+            })
+            .collect();
+        statement.positions.items.clear();
+        statement.cash_transactions.items =
+            vec![
+                CashTransactionBuilder::new(CashTransactionType::Dividends, 25)
+                    .transaction_id("D1")
+                    .date(day(2))
+                    .build(),
+            ];
+        statement
+    }
+
+    #[test]
+    fn test_merge_overlapping_statements() {
+        let monday = statement(2, 2, &["T1"]);
+        let tuesday = statement(3, 3, &["T2"]);
+        let mut week = statement(2, 6, &["T1", "T2", "T3"]);
+        week.trades.items[0].price = Some(Decimal::from(190));
+        week.positions.items = vec![PositionBuilder::new("AAPL").quantity(30).build()];
+
+        let merged = merge_statements(vec![week, tuesday, monday]).unwrap();
+        assert_eq!(merged.from_date, day(2));
+        assert_eq!(merged.to_date, day(6));
+
+        let ids: Vec<_> = merged
+            .trades
+            .iter()
+            .map(|t| t.transaction_id.as_deref().unwrap())
+            .collect();
+        assert_eq!(ids, ["T1", "T2", "T3"]);
+        // The latest statement's version of T1 wins
+        assert_eq!(merged.trades.items[0].price, Some(Decimal::from(190)));
+        assert_eq!(merged.cash_transactions.len(), 1);
+        assert_eq!(merged.positions.items[0].quantity, Decimal::from(30));
+    }
+
+    #[test]
+    fn test_merge_rejects_mixed_accounts() {
+        assert!(matches!(
+            merge_statements(Vec::new()),
+            Err(ParseError::CannotMerge(_))
+        ));
+
+        let mut other = statement(3, 3, &[]);
+        other.account_id = "U7654321".into();
+        let err = merge_statements(vec![statement(2, 2, &[]), other]).unwrap_err();
+        assert!(err.to_string().contains("U7654321"));
+    }
+}
@@ -1,22 +1,52 @@
 //! FLEX data types
 
 pub mod activity;
+pub mod builders;
 pub mod common;
+pub mod contracts;
+#[cfg(feature = "iso-currency")]
+pub mod currency;
+pub mod dedup;
+#[cfg(all(feature = "serialize", feature = "eq"))]
+pub mod diff;
 pub mod extended;
+pub mod fx;
+#[cfg(feature = "security-ids")]
+pub mod identifiers;
+pub mod index;
+#[cfg(feature = "eq")]
+pub mod merge;
+pub mod money;
+pub mod query;
+pub mod security_master;
+pub mod summary;
+#[cfg(feature = "chrono-tz")]
+pub mod timezone;
 pub mod trade_confirmation;
+pub mod traits;
+#[cfg(feature = "tws")]
+pub mod tws;
 
 // Re-export commonly used types
 pub use activity::{
     ActivityFlexStatement, CashTransaction, CashTransactionsWrapper, ConversionRate,
     ConversionRatesWrapper, CorporateAction, CorporateActionsWrapper, FlexQueryResponse,
-    FlexStatementsWrapper, Position, PositionsWrapper, SecuritiesInfoWrapper, SecurityInfo, Trade,
-    TradesWrapper,
+    FlexStatementsWrapper, OrderGroup, Position, PositionsWrapper, SecuritiesInfoWrapper,
+    SecurityInfo, Trade, TradesWrapper,
 };
+pub use builders::{CashTransactionBuilder, PositionBuilder, TradeBuilder};
 pub use common::{
-    AssetCategory, BuySell, CashTransactionType, CorporateActionType, DeliveredReceived,
-    DerivativeInfo, InOut, LevelOfDetail, LongShort, OpenClose, OptionAction, OrderType, PutCall,
-    SecurityIdType, SubCategory, ToFrom, TradeType, TransactionCode, TransferType,
+    AccountId, AccountKind, AssetCategory, BuySell, CashTransactionType, Conid,
+    CorporateActionType, DeliveredReceived, DerivativeInfo, ExecutionId, InOut, LevelOfDetail,
+    LongShort, OpenClose, OptionAction, OrderId, OrderType, PutCall, SecurityIdType, SubCategory,
+    ToFrom, TradeCodes, TradeId, TradeType, TransactionCode, TransferType,
 };
+pub use contracts::{FuturesContract, OptionContract};
+#[cfg(feature = "iso-currency")]
+pub use currency::Currency;
+pub use dedup::{canonicalize, find_duplicates, Duplicate, DuplicateReport};
+#[cfg(all(feature = "serialize", feature = "eq"))]
+pub use diff::{diff_statements, FieldChange, RecordChange, SectionDiff, StatementDiff};
 pub use extended::{
     // Account info
     AccountInformation,
@@ -33,6 +63,7 @@ pub use extended::{
     ClientFeesDetail,
     // Miscellaneous
     DebitCardActivity,
+    DepositWithdrawal,
     EquitySummaryByReportDateInBase,
     FIFOPerformanceSummaryUnderlying,
     // FX
@@ -67,4 +98,20 @@ pub use extended::{
     UnbundledCommissionDetail,
     UnsettledTransfer,
 };
+pub use fx::FxRateTable;
+#[cfg(feature = "security-ids")]
+pub use identifiers::{Cusip, Figi, Isin, SecurityId, Sedol};
+pub use index::{DayRecords, StatementIndex};
+#[cfg(feature = "eq")]
+pub use merge::merge_statements;
+pub use money::{CurrencyMismatch, Money};
+pub use query::{Query, StatementQuery};
+pub use security_master::{SecurityMaster, SymbolChange};
+pub use summary::{
+    CompactStatement, CompactStatementF64, PositionSummary, PositionSummaryF64, TradeSummary,
+    TradeSummaryF64,
+};
+#[cfg(feature = "chrono-tz")]
+pub use timezone::DEFAULT_TIMEZONE;
 pub use trade_confirmation::TradeConfirmationStatement;
+pub use traits::{Dated, HasAccount, HasAssetCategory, HasConid, HasSymbol};
@@ -0,0 +1,238 @@
+//! Monetary amounts tagged with their currency
+//!
+//! Statement types keep amounts as the bare `Decimal` fields the XML has;
+//! there is no parse mode that changes their type. Accessors such as
+//! [`Trade::proceeds_money`], [`Trade::net_cash_money`] and
+//! [`Trade::commission_money`] pair an amount with its currency as
+//! [`Money`] when it is read.
+
+use rust_decimal::Decimal;
+use serde::Deserialize;
+#[cfg(feature = "serialize")]
+use serde::Serialize;
+use thiserror::Error;
+
+use super::activity::{CashTransaction, Position, Trade};
+
+/// Error returned when combining amounts in different currencies
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("Currency mismatch: cannot combine {left} with {right}")]
+pub struct CurrencyMismatch {
+    /// Currency of the left-hand amount
+    pub left: String,
+    /// Currency of the right-hand amount
+    pub right: String,
+}
+
+/// A decimal amount paired with its currency
+///
+/// FLEX rows report amounts (proceeds, net cash, commissions) as bare numbers
+/// next to a separate `currency` attribute. `Money` keeps the two together so
+/// that summing rows across currencies is caught instead of producing a
+/// meaningless total.
+///
+/// # Example
+/// ```
+/// use ib_flex::types::Money;
+/// use rust_decimal::Decimal;
+///
+/// let a = Money::new(Decimal::from(100), "USD");
+/// let b = Money::new(Decimal::from(25), "USD");
+/// assert_eq!(a.checked_add(&b).unwrap().amount, Decimal::from(125));
+///
+/// let eur = Money::new(Decimal::from(10), "EUR");
+/// assert!(a.checked_add(&eur).is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct Money {
+    /// Amount in `currency`
+    pub amount: Decimal,
+    /// Currency code (e.g., "USD")
+    pub currency: String,
+}
+
+impl Money {
+    /// Create an amount in the given currency
+    pub fn new(amount: Decimal, currency: impl Into<String>) -> Self {
+        Money {
+            amount,
+            currency: currency.into(),
+        }
+    }
+
+    /// Zero in the given currency
+    pub fn zero(currency: impl Into<String>) -> Self {
+        Money::new(Decimal::ZERO, currency)
+    }
+
+    /// Returns true if the amount is zero
+    pub fn is_zero(&self) -> bool {
+        self.amount.is_zero()
+    }
+
+    /// Add two amounts, failing if their currencies differ
+    pub fn checked_add(&self, other: &Money) -> Result<Money, CurrencyMismatch> {
+        self.ensure_same_currency(other)?;
+        Ok(Money::new(
+            self.amount + other.amount,
+            self.currency.clone(),
+        ))
+    }
+
+    /// Subtract `other`, failing if the currencies differ
+    pub fn checked_sub(&self, other: &Money) -> Result<Money, CurrencyMismatch> {
+        self.ensure_same_currency(other)?;
+        Ok(Money::new(
+            self.amount - other.amount,
+            self.currency.clone(),
+        ))
+    }
+
+    /// Scale the amount, keeping the currency
+    pub fn scale(&self, factor: Decimal) -> Money {
+        Money::new(self.amount * factor, self.currency.clone())
+    }
+
+    /// Convert into another currency at `rate` units of `to` per unit of this currency
+    pub fn convert(&self, rate: Decimal, to: impl Into<String>) -> Money {
+        Money::new(self.amount * rate, to)
+    }
+
+    /// Sum amounts, failing on the first currency mismatch
+    ///
+    /// Returns `Ok(None)` for an empty iterator since the currency is unknown.
+    pub fn sum<'a>(
+        amounts: impl IntoIterator<Item = &'a Money>,
+    ) -> Result<Option<Money>, CurrencyMismatch> {
+        let mut total: Option<Money> = None;
+        for amount in amounts {
+            total = Some(match total {
+                Some(total) => total.checked_add(amount)?,
+                None => amount.clone(),
+            });
+        }
+        Ok(total)
+    }
+
+    fn ensure_same_currency(&self, other: &Money) -> Result<(), CurrencyMismatch> {
+        if self.currency == other.currency {
+            Ok(())
+        } else {
+            Err(CurrencyMismatch {
+                left: self.currency.clone(),
+                right: other.currency.clone(),
+            })
+        }
+    }
+}
+
+impl std::ops::Neg for Money {
+    type Output = Money;
+
+    fn neg(self) -> Money {
+        Money::new(-self.amount, self.currency)
+    }
+}
+
+impl std::fmt::Display for Money {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.amount, self.currency)
+    }
+}
+
+impl Trade {
+    fn money(&self, amount: Option<Decimal>) -> Option<Money> {
+        amount.map(|amount| Money::new(amount, self.currency.clone()))
+    }
+
+    /// Proceeds in the trade currency
+    pub fn proceeds_money(&self) -> Option<Money> {
+        self.money(self.proceeds)
+    }
+
+    /// Net cash in the trade currency
+    pub fn net_cash_money(&self) -> Option<Money> {
+        self.money(self.net_cash)
+    }
+
+    /// Commission in its reported currency
+    ///
+    /// Uses `ibCommissionCurrency` when present, since IB may charge
+    /// commissions in a different currency than the trade itself.
+    pub fn commission_money(&self) -> Option<Money> {
+        let currency = self
+            .commission_currency
+            .as_deref()
+            .filter(|c| !c.is_empty())
+            .unwrap_or(&self.currency);
+        self.commission.map(|amount| Money::new(amount, currency))
+    }
+}
+
+impl Position {
+    /// Position value in the position currency
+    pub fn position_value_money(&self) -> Money {
+        Money::new(self.position_value, self.currency.clone())
+    }
+
+    /// Cost basis in the position currency
+    pub fn cost_basis_money(&self) -> Option<Money> {
+        self.cost_basis_money
+            .map(|amount| Money::new(amount, self.currency.clone()))
+    }
+}
+
+impl CashTransaction {
+    /// Amount in the transaction currency
+    pub fn amount_money(&self) -> Money {
+        Money::new(self.amount, self.currency.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usd(amount: i64) -> Money {
+        Money::new(Decimal::from(amount), "USD")
+    }
+
+    #[test]
+    fn test_checked_arithmetic() {
+        assert_eq!(usd(100).checked_sub(&usd(30)).unwrap(), usd(70));
+        assert_eq!(-usd(5), usd(-5));
+        assert_eq!(usd(3).scale(Decimal::from(2)), usd(6));
+
+        let err = usd(1).checked_add(&Money::zero("EUR")).unwrap_err();
+        assert_eq!(err.left, "USD");
+        assert_eq!(err.right, "EUR");
+    }
+
+    #[test]
+    fn test_sum() {
+        assert_eq!(Money::sum(&[usd(1), usd(2), usd(3)]).unwrap(), Some(usd(6)));
+        assert_eq!(Money::sum(&[]).unwrap(), None);
+        assert!(Money::sum(&[usd(1), Money::zero("GBP")]).is_err());
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(
+            Money::new(Decimal::new(1050, 2), "EUR").to_string(),
+            "10.50 EUR"
+        );
+    }
+
+    #[test]
+    fn test_position_money() {
+        let position = Position {
+            currency: "USD".to_string(),
+            position_value: Decimal::from(200),
+            cost_basis_money: Some(Decimal::from(150)),
+            ..Default::default()
+        };
+        assert_eq!(position.position_value_money(), usd(200));
+        assert_eq!(position.cost_basis_money(), Some(usd(150)));
+    }
+}
@@ -0,0 +1,247 @@
+//! Fluent filters over statement sections
+//!
+//! [`ActivityFlexStatement::query`] starts a [`Query`] over one section of
+//! a statement. Filters are chained and applied lazily when the rows are
+//! collected or iterated, and the ones available depend on the accessor
+//! traits the row type implements: [`symbol`](Query::symbol) needs
+//! [`HasSymbol`], [`between`](Query::between) needs [`Dated`], and so on.
+//!
+//! # Example
+//! ```
+//! use chrono::NaiveDate;
+//! use ib_flex::{parse_activity_flex, AssetCategory};
+//!
+//! let xml = include_str!("../../tests/fixtures/activity_minimal.xml");
+//! let statement = parse_activity_flex(xml).unwrap();
+//!
+//! let from = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+//! let to = NaiveDate::from_ymd_opt(2025, 1, 31).unwrap();
+//! let trades = statement
+//!     .query()
+//!     .trades()
+//!     .symbol("AAPL")
+//!     .between(from, to)
+//!     .asset(AssetCategory::Stock)
+//!     .collect();
+//! assert_eq!(trades.len(), 1);
+//! ```
+
+use chrono::NaiveDate;
+
+use super::activity::{
+    ActivityFlexStatement, CashTransaction, CorporateAction, Position, SecurityInfo, Trade,
+};
+use super::common::{AssetCategory, Conid};
+use super::extended::{
+    ChangeInDividendAccrual, DepositWithdrawal, FxTransaction, OpenDividendAccrual, OptionEAE,
+    TradeConfirm, Transfer,
+};
+use super::traits::{Dated, HasAccount, HasAssetCategory, HasConid, HasSymbol};
+
+impl ActivityFlexStatement {
+    /// Start a query over one section of this statement
+    pub fn query(&self) -> StatementQuery<'_> {
+        StatementQuery { statement: self }
+    }
+}
+
+/// Picks the section a [`Query`] runs over
+#[derive(Debug, Clone, Copy)]
+pub struct StatementQuery<'a> {
+    statement: &'a ActivityFlexStatement,
+}
+
+macro_rules! sections {
+    ($($section:ident => $item:ty,)+) => {
+        impl<'a> StatementQuery<'a> {
+            $(
+                #[doc = concat!("Query the `", stringify!($section), "` section")]
+                pub fn $section(self) -> Query<'a, $item> {
+                    Query::new(&self.statement.$section)
+                }
+            )+
+        }
+    };
+}
+
+sections! {
+    trades => Trade,
+    positions => Position,
+    cash_transactions => CashTransaction,
+    corporate_actions => CorporateAction,
+    securities_info => SecurityInfo,
+    trade_confirms => TradeConfirm,
+    option_eae => OptionEAE,
+    fx_transactions => FxTransaction,
+    change_in_dividend_accruals => ChangeInDividendAccrual,
+    open_dividend_accruals => OpenDividendAccrual,
+    transfers => Transfer,
+    deposits_withdrawals => DepositWithdrawal,
+}
+
+type Filter<'a, T> = Box<dyn Fn(&T) -> bool + 'a>;
+
+/// Rows of one section with the filters chained so far
+///
+/// A row is returned when it passes every filter.
+pub struct Query<'a, T> {
+    rows: &'a [T],
+    filters: Vec<Filter<'a, T>>,
+}
+
+impl<'a, T> Query<'a, T> {
+    /// Query over any slice of rows, such as the trades of several statements
+    pub fn new(rows: &'a [T]) -> Self {
+        Query {
+            rows,
+            filters: Vec::new(),
+        }
+    }
+
+    /// Keep rows for which `predicate` returns true
+    pub fn filter(mut self, predicate: impl Fn(&T) -> bool + 'a) -> Self {
+        self.filters.push(Box::new(predicate));
+        self
+    }
+
+    /// Iterate over the matching rows
+    pub fn iter(&self) -> impl Iterator<Item = &'a T> + '_ {
+        let rows: &'a [T] = self.rows;
+        rows.iter()
+            .filter(move |row| self.filters.iter().all(|keep| keep(row)))
+    }
+
+    /// Matching rows, in section order
+    pub fn collect(self) -> Vec<&'a T> {
+        self.iter().collect()
+    }
+
+    /// Number of matching rows
+    pub fn count(&self) -> usize {
+        self.iter().count()
+    }
+
+    /// First matching row
+    pub fn first(&self) -> Option<&'a T> {
+        self.iter().next()
+    }
+}
+
+impl<'a, T: HasSymbol + 'a> Query<'a, T> {
+    /// Keep rows for `symbol`
+    pub fn symbol(self, symbol: impl Into<String>) -> Self {
+        let symbol = symbol.into();
+        self.filter(move |row| row.symbol() == Some(symbol.as_str()))
+    }
+}
+
+impl<'a, T: HasConid + 'a> Query<'a, T> {
+    /// Keep rows for contract `conid`
+    pub fn conid(self, conid: impl Into<Conid>) -> Self {
+        let conid = conid.into();
+        self.filter(move |row| row.conid() == Some(conid))
+    }
+}
+
+impl<'a, T: HasAccount + 'a> Query<'a, T> {
+    /// Keep rows for `account`
+    pub fn account(self, account: impl Into<String>) -> Self {
+        let account = account.into();
+        self.filter(move |row| row.account_id().as_str() == account)
+    }
+}
+
+impl<'a, T: HasAssetCategory + 'a> Query<'a, T> {
+    /// Keep rows in `category`
+    pub fn asset(self, category: AssetCategory) -> Self {
+        self.filter(move |row| row.asset_category() == Some(&category))
+    }
+}
+
+impl<'a, T: Dated + 'a> Query<'a, T> {
+    /// Keep rows dated within `from..=to`
+    pub fn between(self, from: NaiveDate, to: NaiveDate) -> Self {
+        self.filter(move |row| row.is_between(from, to))
+    }
+
+    /// Keep rows dated on or after `from`
+    pub fn since(self, from: NaiveDate) -> Self {
+        self.filter(move |row| row.date().is_some_and(|date| date >= from))
+    }
+
+    /// Keep rows dated on or before `to`
+    pub fn until(self, to: NaiveDate) -> Self {
+        self.filter(move |row| row.date().is_some_and(|date| date <= to))
+    }
+}
+
+impl<'a, T> IntoIterator for Query<'a, T> {
+    type Item = &'a T;
+    type IntoIter = std::vec::IntoIter<&'a T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.collect().into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::TradeBuilder;
+
+    fn day(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 6, d).unwrap()
+    }
+
+    #[test]
+    fn test_chained_filters() {
+        let xml = include_str!("../../tests/fixtures/activity_minimal.xml");
+        let mut statement = crate::parse_activity_flex(xml).unwrap();
+        statement.trades.items = vec![
+            TradeBuilder::new("AAPL")
+                .conid(1)
+                .trade_date(day(2))
+                .build(),
+            TradeBuilder::new("AAPL  250620C00200000")
+                .conid(2)
+                .asset_category(AssetCategory::Option)
+                .trade_date(day(3))
+                .build(),
+            TradeBuilder::new("AAPL")
+                .conid(1)
+                .trade_date(day(20))
+                .build(),
+            TradeBuilder::new("MSFT")
+                .conid(3)
+                .trade_date(day(3))
+                .build(),
+        ];
+
+        let june = statement.query().trades().between(day(1), day(10));
+        assert_eq!(june.count(), 3);
+        assert_eq!(june.symbol("AAPL").count(), 1);
+
+        let options = statement
+            .query()
+            .trades()
+            .asset(AssetCategory::Option)
+            .collect();
+        assert_eq!(options.len(), 1);
+        assert_eq!(options[0].conid, Conid(2));
+
+        let later: Vec<_> = statement
+            .query()
+            .trades()
+            .conid(1)
+            .since(day(3))
+            .into_iter()
+            .collect();
+        assert_eq!(later.len(), 1);
+        assert_eq!(later[0].trade_date, Some(day(20)));
+
+        let custom = Query::new(&statement.trades)
+            .filter(|t| t.symbol.starts_with("MS"))
+            .first();
+        assert_eq!(custom.map(|t| t.conid), Some(Conid(3)));
+    }
+}
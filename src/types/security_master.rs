@@ -0,0 +1,346 @@
+//! Security reference data merged across statements
+//!
+//! Every statement repeats `SecuritiesInfo` for the contracts it touches.
+//! [`SecurityMaster`] merges those rows from any number of statements into
+//! one table keyed by conid, and follows symbol changes (`IC` corporate
+//! actions) so the symbol a contract went by on any date can be looked up.
+//! Unlike [`StatementIndex`](super::StatementIndex) it owns its data and
+//! locks internally: a long-running service can share one master behind an
+//! `Arc`, adding statements as they arrive while other threads resolve
+//! conids without re-scanning every statement.
+//!
+//! # Example
+//! ```
+//! use std::sync::Arc;
+//!
+//! use ib_flex::parse_activity_flex;
+//! use ib_flex::types::SecurityMaster;
+//!
+//! let xml = include_str!("../../tests/fixtures/activity_options.xml");
+//! let statement = parse_activity_flex(xml).unwrap();
+//!
+//! let master = Arc::new(SecurityMaster::new());
+//! let shared = Arc::clone(&master);
+//! let loaded = statement.clone();
+//! std::thread::spawn(move || shared.add_statement(&loaded))
+//!     .join()
+//!     .unwrap();
+//!
+//! assert!(!master.is_empty());
+//! for conid in master.conids() {
+//!     let info = master.get(conid).unwrap();
+//!     let symbol = master.symbol_on(conid, statement.to_date).unwrap();
+//!     assert_eq!(symbol, info.symbol);
+//! }
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use chrono::NaiveDate;
+use serde::Deserialize;
+#[cfg(feature = "serialize")]
+use serde::Serialize;
+
+use super::activity::{ActivityFlexStatement, CorporateAction, SecurityInfo};
+use super::common::Conid;
+use super::traits::Dated;
+use crate::analytics::{group_actions, CorporateActionKind};
+
+/// A contract renamed or moved to a new conid by an `IC` corporate action
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct SymbolChange {
+    /// Effective date of the change
+    pub date: NaiveDate,
+    /// Conid before the change
+    pub conid: Conid,
+    /// Symbol before the change
+    pub symbol: String,
+    /// Conid after the change, the same as `conid` when IB kept it
+    pub new_conid: Conid,
+    /// Symbol after the change
+    pub new_symbol: String,
+    /// IB action ID, if reported
+    pub action_id: Option<String>,
+}
+
+/// `SecuritiesInfo` of many statements, merged by conid
+///
+/// Cheap lookups return shared [`Arc`]s of the rows. When statements
+/// disagree on a contract, the row from the statement ending latest wins,
+/// whatever order they were added in.
+#[derive(Debug, Default)]
+pub struct SecurityMaster {
+    inner: RwLock<Inner>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    /// Each contract's row, with the end date of the statement it came from
+    securities: HashMap<Conid, (NaiveDate, Arc<SecurityInfo>)>,
+    /// Symbols seen per contract, by the date they were seen
+    symbols: HashMap<Conid, BTreeMap<NaiveDate, String>>,
+    changes: Vec<SymbolChange>,
+}
+
+impl SecurityMaster {
+    /// Empty master
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Master built from `statements`
+    pub fn from_statements<'a, I>(statements: I) -> Self
+    where
+        I: IntoIterator<Item = &'a ActivityFlexStatement>,
+    {
+        let master = SecurityMaster::new();
+        for statement in statements {
+            master.add_statement(statement);
+        }
+        master
+    }
+
+    /// Merge the securities and symbol changes of `statement`
+    ///
+    /// Adding a statement again, or one overlapping earlier ones, changes
+    /// nothing.
+    pub fn add_statement(&self, statement: &ActivityFlexStatement) {
+        let mut inner = self.write();
+        let date = statement.to_date;
+        for info in statement.securities_info.iter() {
+            inner
+                .symbols
+                .entry(info.conid)
+                .or_default()
+                .insert(date, info.symbol.clone());
+            match inner.securities.get(&info.conid) {
+                Some((seen, _)) if *seen > date => {}
+                _ => {
+                    inner
+                        .securities
+                        .insert(info.conid, (date, Arc::new(info.clone())));
+                }
+            }
+        }
+
+        for action in group_actions(statement.corporate_actions.iter()) {
+            if CorporateActionKind::of(action[0]) != CorporateActionKind::SymbolChange {
+                continue;
+            }
+            inner.add_symbol_change(&action);
+        }
+    }
+
+    /// `SecuritiesInfo` row for a contract
+    pub fn get(&self, conid: Conid) -> Option<Arc<SecurityInfo>> {
+        self.read()
+            .securities
+            .get(&conid)
+            .map(|(_, info)| Arc::clone(info))
+    }
+
+    /// Symbol a contract went by on `date`
+    ///
+    /// The symbol last seen on or before `date`, from `SecuritiesInfo` as of
+    /// each statement's end date and from symbol changes; for dates before
+    /// the first sighting, the first symbol seen.
+    pub fn symbol_on(&self, conid: Conid, date: NaiveDate) -> Option<String> {
+        let inner = self.read();
+        let symbols = inner.symbols.get(&conid)?;
+        symbols
+            .range(..=date)
+            .next_back()
+            .or_else(|| symbols.iter().next())
+            .map(|(_, symbol)| symbol.clone())
+    }
+
+    /// Symbols of a contract over time, each with the first date it was seen
+    pub fn symbol_history(&self, conid: Conid) -> Vec<(NaiveDate, String)> {
+        let inner = self.read();
+        let mut history: Vec<(NaiveDate, String)> = Vec::new();
+        for (date, symbol) in inner.symbols.get(&conid).into_iter().flatten() {
+            if history.last().map(|(_, last)| last) != Some(symbol) {
+                history.push((*date, symbol.clone()));
+            }
+        }
+        history
+    }
+
+    /// Symbol changes, by date
+    pub fn symbol_changes(&self) -> Vec<SymbolChange> {
+        self.read().changes.clone()
+    }
+
+    /// Conid a contract trades under now, following symbol changes that
+    /// moved it to new conids
+    pub fn current_conid(&self, conid: Conid) -> Conid {
+        let inner = self.read();
+        let mut current = conid;
+        // Each step moves forward in time, so at most one per change
+        for _ in 0..inner.changes.len() {
+            let next = inner
+                .changes
+                .iter()
+                .filter(|c| c.conid == current && c.new_conid != current)
+                .max_by_key(|c| c.date);
+            match next {
+                Some(change) => current = change.new_conid,
+                None => break,
+            }
+        }
+        current
+    }
+
+    /// Conids of every contract with a `SecuritiesInfo` row, sorted
+    pub fn conids(&self) -> Vec<Conid> {
+        let mut conids: Vec<Conid> = self.read().securities.keys().copied().collect();
+        conids.sort();
+        conids
+    }
+
+    /// Number of contracts with a `SecuritiesInfo` row
+    pub fn len(&self) -> usize {
+        self.read().securities.len()
+    }
+
+    /// Whether no contract has a `SecuritiesInfo` row
+    pub fn is_empty(&self) -> bool {
+        self.read().securities.is_empty()
+    }
+
+    // A panic while holding the lock leaves the maps consistent, since every
+    // write is a single insert
+    fn read(&self) -> RwLockReadGuard<'_, Inner> {
+        self.inner.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, Inner> {
+        self.inner.write().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Inner {
+    /// Record the symbols of an `IC` action's rows, and the change when it
+    /// takes one contract to another
+    ///
+    /// IB books a change as the old contract removed and the new one added;
+    /// the old symbol is recorded as last seen the day before.
+    fn add_symbol_change(&mut self, rows: &[&CorporateAction]) {
+        let date = rows[0].date().unwrap_or(rows[0].report_date);
+        let sign = |row: &&&CorporateAction| row.quantity.unwrap_or_default();
+        let removed: Vec<&CorporateAction> = rows
+            .iter()
+            .filter(|r| sign(r).is_sign_negative() && !sign(r).is_zero())
+            .copied()
+            .collect();
+        let added: Vec<&CorporateAction> = rows
+            .iter()
+            .filter(|r| sign(r).is_sign_positive() && !sign(r).is_zero())
+            .copied()
+            .collect();
+
+        for row in &removed {
+            let before = date.pred_opt().unwrap_or(date);
+            self.symbols
+                .entry(row.conid)
+                .or_default()
+                .insert(before, row.symbol.clone());
+        }
+        for row in &added {
+            self.symbols
+                .entry(row.conid)
+                .or_default()
+                .insert(date, row.symbol.clone());
+        }
+
+        let ([old], [new]) = (removed.as_slice(), added.as_slice()) else {
+            return;
+        };
+        let change = SymbolChange {
+            date,
+            conid: old.conid,
+            symbol: old.symbol.clone(),
+            new_conid: new.conid,
+            new_symbol: new.symbol.clone(),
+            action_id: old.action_id.clone().filter(|id| !id.is_empty()),
+        };
+        if !self.changes.contains(&change) {
+            let at = self.changes.partition_point(|c| c.date <= date);
+            self.changes.insert(at, change);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(month: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2022, month, d).unwrap()
+    }
+
+    fn statement(to_date: &str, symbol: &str, actions: &str) -> ActivityFlexStatement {
+        let xml = format!(
+            r#"<FlexQueryResponse queryName="Test" type="AF">
+<FlexStatements count="1">
+<FlexStatement accountId="U1234567" fromDate="2022-01-01" toDate="{to_date}" period="Custom" whenGenerated="2022-07-01;120000">
+<SecuritiesInfo>
+<SecurityInfo assetCategory="STK" symbol="{symbol}" description="META PLATFORMS INC" conid="107113386" isin="US30303M1027" currency="USD" />
+</SecuritiesInfo>
+<CorporateActions>{actions}</CorporateActions>
+</FlexStatement>
+</FlexStatements>
+</FlexQueryResponse>"#
+        );
+        crate::parse_activity_flex(&xml).unwrap()
+    }
+
+    #[test]
+    fn test_merge_and_symbol_history() {
+        let ic = r#"
+<CorporateAction accountId="U1234567" type="IC" reportDate="2022-06-09" dateTime="2022-06-09;202500" symbol="FB" conid="107113386" quantity="-10" actionID="1" description="FB CHANGE TO META" />
+<CorporateAction accountId="U1234567" type="IC" reportDate="2022-06-09" dateTime="2022-06-09;202500" symbol="META" conid="107113386" quantity="10" actionID="1" description="FB CHANGE TO META" />"#;
+        let march = statement("2022-03-31", "FB", "");
+        let june = statement("2022-06-30", "META", ic);
+
+        // Later statement wins regardless of the order added
+        let master = Arc::new(SecurityMaster::from_statements([&june, &march]));
+        let conid = Conid::from(107113386);
+        assert_eq!(master.len(), 1);
+        assert_eq!(master.get(conid).unwrap().symbol, "META");
+
+        assert_eq!(master.symbol_on(conid, day(1, 3)).as_deref(), Some("FB"));
+        assert_eq!(master.symbol_on(conid, day(6, 8)).as_deref(), Some("FB"));
+        assert_eq!(master.symbol_on(conid, day(6, 9)).as_deref(), Some("META"));
+        assert_eq!(
+            master.symbol_history(conid),
+            [
+                (day(3, 31), "FB".to_string()),
+                (day(6, 9), "META".to_string())
+            ]
+        );
+
+        master.add_statement(&june);
+        let changes = master.symbol_changes();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].new_symbol, "META");
+        assert_eq!(master.current_conid(conid), conid);
+        assert!(master.get(Conid::from(1)).is_none());
+    }
+
+    #[test]
+    fn test_change_to_new_conid() {
+        let ic = r#"
+<CorporateAction accountId="U1234567" type="IC" reportDate="2022-06-09" symbol="OLD" conid="111" quantity="-5" />
+<CorporateAction accountId="U1234567" type="IC" reportDate="2022-06-09" symbol="NEW" conid="222" quantity="5" />"#;
+        let master = SecurityMaster::from_statements([&statement("2022-06-30", "META", ic)]);
+        assert_eq!(master.current_conid(Conid::from(111)), Conid::from(222));
+        assert_eq!(
+            master.symbol_on(Conid::from(111), day(7, 1)).as_deref(),
+            Some("OLD")
+        );
+        assert_eq!(master.symbol_changes()[0].action_id, None);
+    }
+}
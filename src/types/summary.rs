@@ -0,0 +1,606 @@
+//! Compact trade and position records
+//!
+//! [`Trade`] and [`Position`] carry over a hundred fields each, most of them
+//! `None` for any given row. Pipelines that only need symbol, date,
+//! quantity, price, and P&L can convert to [`TradeSummary`] and
+//! [`PositionSummary`], or skip the full records entirely with
+//! [`parse_activity_flex_compact`](crate::parsers::parse_activity_flex_compact).
+//!
+//! Dashboards that chart large histories and don't need exact amounts can
+//! use [`TradeSummaryF64`] and [`PositionSummaryF64`] instead, with amounts
+//! as `f64`, parsed directly by
+//! [`parse_activity_flex_compact_f64`](crate::parsers::parse_activity_flex_compact_f64).
+//! The full records always keep `Decimal`: these are separate types, so
+//! nothing changes for other crates in the same build. The `f64` forms
+//! cover only these trade and position fields; other sections have none.
+//!
+//! # Example
+//! ```
+//! use ib_flex::parsers::parse_activity_flex_compact;
+//!
+//! let xml = include_str!("../../tests/fixtures/activity_minimal.xml");
+//! let statements = parse_activity_flex_compact(xml).unwrap();
+//!
+//! let trade = &statements[0].trades[0];
+//! assert_eq!(trade.symbol, "AAPL");
+//! assert_eq!(trade.quantity, Some(100.into()));
+//! ```
+
+use chrono::NaiveDate;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+#[cfg(feature = "serialize")]
+use serde::Serialize;
+
+use super::activity::{ActivityFlexStatement, Position, Trade};
+use super::common::{AccountId, AssetCategory, BuySell, Conid};
+#[cfg(feature = "serialize")]
+use crate::parsers::xml_utils::serialize_optional_date;
+use crate::parsers::xml_utils::{
+    deserialize_f64, deserialize_optional_date, deserialize_optional_decimal,
+    deserialize_optional_enum, deserialize_optional_f64,
+};
+
+/// The most-used fields of a [`Trade`]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct TradeSummary {
+    /// IB contract ID
+    #[serde(rename = "@conid")]
+    pub conid: Conid,
+
+    /// Ticker symbol
+    #[serde(rename = "@symbol")]
+    pub symbol: String,
+
+    /// Asset category
+    #[serde(rename = "@assetCategory")]
+    pub asset_category: AssetCategory,
+
+    /// Trade currency
+    #[serde(rename = "@currency")]
+    pub currency: String,
+
+    /// Trade date
+    #[serde(
+        rename = "@tradeDate",
+        default,
+        deserialize_with = "deserialize_optional_date",
+        serialize_with = "serialize_optional_date"
+    )]
+    pub trade_date: Option<NaiveDate>,
+
+    /// Buy or Sell
+    #[serde(
+        rename = "@buySell",
+        default,
+        deserialize_with = "deserialize_optional_enum"
+    )]
+    pub buy_sell: Option<BuySell>,
+
+    /// Quantity (number of shares/contracts)
+    #[serde(
+        rename = "@quantity",
+        default,
+        deserialize_with = "deserialize_optional_decimal"
+    )]
+    pub quantity: Option<Decimal>,
+
+    /// Trade price per share/contract
+    #[serde(
+        rename = "@price",
+        default,
+        deserialize_with = "deserialize_optional_decimal"
+    )]
+    pub price: Option<Decimal>,
+
+    /// Commission paid
+    #[serde(
+        rename = "@ibCommission",
+        default,
+        deserialize_with = "deserialize_optional_decimal"
+    )]
+    pub commission: Option<Decimal>,
+
+    /// Net cash (proceeds + commission + taxes)
+    #[serde(
+        rename = "@netCash",
+        default,
+        deserialize_with = "deserialize_optional_decimal"
+    )]
+    pub net_cash: Option<Decimal>,
+
+    /// FIFO realized P&L
+    #[serde(
+        rename = "@fifoPnlRealized",
+        default,
+        deserialize_with = "deserialize_optional_decimal"
+    )]
+    pub fifo_pnl_realized: Option<Decimal>,
+}
+
+impl From<&Trade> for TradeSummary {
+    fn from(trade: &Trade) -> Self {
+        TradeSummary {
+            conid: trade.conid,
+            symbol: trade.symbol.clone(),
+            asset_category: trade.asset_category.clone(),
+            currency: trade.currency.clone(),
+            trade_date: trade.trade_date,
+            buy_sell: trade.buy_sell.clone(),
+            quantity: trade.quantity,
+            price: trade.price,
+            commission: trade.commission,
+            net_cash: trade.net_cash,
+            fifo_pnl_realized: trade.fifo_pnl_realized,
+        }
+    }
+}
+
+/// The most-used fields of a [`Position`]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct PositionSummary {
+    /// IB contract ID
+    #[serde(rename = "@conid")]
+    pub conid: Conid,
+
+    /// Ticker symbol
+    #[serde(rename = "@symbol")]
+    pub symbol: String,
+
+    /// Asset category
+    #[serde(rename = "@assetCategory")]
+    pub asset_category: AssetCategory,
+
+    /// Position currency
+    #[serde(rename = "@currency")]
+    pub currency: String,
+
+    /// Date of this position snapshot
+    #[serde(
+        rename = "@reportDate",
+        deserialize_with = "crate::parsers::xml_utils::deserialize_flex_date",
+        serialize_with = "crate::parsers::xml_utils::serialize_flex_date"
+    )]
+    pub report_date: NaiveDate,
+
+    /// Position quantity (negative for short)
+    #[serde(
+        rename = "@position",
+        deserialize_with = "crate::parsers::xml_utils::deserialize_decimal"
+    )]
+    pub quantity: Decimal,
+
+    /// Mark price
+    #[serde(
+        rename = "@markPrice",
+        deserialize_with = "crate::parsers::xml_utils::deserialize_decimal"
+    )]
+    pub mark_price: Decimal,
+
+    /// Position value (quantity * mark_price * multiplier)
+    #[serde(
+        rename = "@positionValue",
+        deserialize_with = "crate::parsers::xml_utils::deserialize_decimal"
+    )]
+    pub position_value: Decimal,
+
+    /// Total cost basis
+    #[serde(
+        rename = "@costBasisMoney",
+        default,
+        deserialize_with = "deserialize_optional_decimal"
+    )]
+    pub cost_basis_money: Option<Decimal>,
+
+    /// FIFO unrealized P&L
+    #[serde(
+        rename = "@fifoPnlUnrealized",
+        default,
+        deserialize_with = "deserialize_optional_decimal"
+    )]
+    pub fifo_pnl_unrealized: Option<Decimal>,
+}
+
+impl From<&Position> for PositionSummary {
+    fn from(position: &Position) -> Self {
+        PositionSummary {
+            conid: position.conid,
+            symbol: position.symbol.clone(),
+            asset_category: position.asset_category.clone(),
+            currency: position.currency.clone(),
+            report_date: position.report_date,
+            quantity: position.quantity,
+            mark_price: position.mark_price,
+            position_value: position.position_value,
+            cost_basis_money: position.cost_basis_money,
+            fifo_pnl_unrealized: position.fifo_pnl_unrealized,
+        }
+    }
+}
+
+/// [`TradeSummary`] with amounts as `f64`
+///
+/// Faster to parse and half the size, but amounts lose exactness past about
+/// 15 significant digits and sums pick up float rounding.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct TradeSummaryF64 {
+    /// IB contract ID
+    #[serde(rename = "@conid")]
+    pub conid: Conid,
+
+    /// Ticker symbol
+    #[serde(rename = "@symbol")]
+    pub symbol: String,
+
+    /// Asset category
+    #[serde(rename = "@assetCategory")]
+    pub asset_category: AssetCategory,
+
+    /// Trade currency
+    #[serde(rename = "@currency")]
+    pub currency: String,
+
+    /// Trade date
+    #[serde(
+        rename = "@tradeDate",
+        default,
+        deserialize_with = "deserialize_optional_date",
+        serialize_with = "serialize_optional_date"
+    )]
+    pub trade_date: Option<NaiveDate>,
+
+    /// Buy or Sell
+    #[serde(
+        rename = "@buySell",
+        default,
+        deserialize_with = "deserialize_optional_enum"
+    )]
+    pub buy_sell: Option<BuySell>,
+
+    /// Quantity (number of shares/contracts)
+    #[serde(
+        rename = "@quantity",
+        default,
+        deserialize_with = "deserialize_optional_f64"
+    )]
+    pub quantity: Option<f64>,
+
+    /// Trade price per share/contract
+    #[serde(
+        rename = "@price",
+        default,
+        deserialize_with = "deserialize_optional_f64"
+    )]
+    pub price: Option<f64>,
+
+    /// Commission paid
+    #[serde(
+        rename = "@ibCommission",
+        default,
+        deserialize_with = "deserialize_optional_f64"
+    )]
+    pub commission: Option<f64>,
+
+    /// Net cash (proceeds + commission + taxes)
+    #[serde(
+        rename = "@netCash",
+        default,
+        deserialize_with = "deserialize_optional_f64"
+    )]
+    pub net_cash: Option<f64>,
+
+    /// FIFO realized P&L
+    #[serde(
+        rename = "@fifoPnlRealized",
+        default,
+        deserialize_with = "deserialize_optional_f64"
+    )]
+    pub fifo_pnl_realized: Option<f64>,
+}
+
+impl From<&TradeSummary> for TradeSummaryF64 {
+    fn from(trade: &TradeSummary) -> Self {
+        TradeSummaryF64 {
+            conid: trade.conid,
+            symbol: trade.symbol.clone(),
+            asset_category: trade.asset_category.clone(),
+            currency: trade.currency.clone(),
+            trade_date: trade.trade_date,
+            buy_sell: trade.buy_sell.clone(),
+            quantity: float(trade.quantity),
+            price: float(trade.price),
+            commission: float(trade.commission),
+            net_cash: float(trade.net_cash),
+            fifo_pnl_realized: float(trade.fifo_pnl_realized),
+        }
+    }
+}
+
+impl From<&Trade> for TradeSummaryF64 {
+    fn from(trade: &Trade) -> Self {
+        TradeSummaryF64::from(&TradeSummary::from(trade))
+    }
+}
+
+/// [`PositionSummary`] with amounts as `f64`
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct PositionSummaryF64 {
+    /// IB contract ID
+    #[serde(rename = "@conid")]
+    pub conid: Conid,
+
+    /// Ticker symbol
+    #[serde(rename = "@symbol")]
+    pub symbol: String,
+
+    /// Asset category
+    #[serde(rename = "@assetCategory")]
+    pub asset_category: AssetCategory,
+
+    /// Position currency
+    #[serde(rename = "@currency")]
+    pub currency: String,
+
+    /// Date of this position snapshot
+    #[serde(
+        rename = "@reportDate",
+        deserialize_with = "crate::parsers::xml_utils::deserialize_flex_date",
+        serialize_with = "crate::parsers::xml_utils::serialize_flex_date"
+    )]
+    pub report_date: NaiveDate,
+
+    /// Position quantity (negative for short)
+    #[serde(rename = "@position", deserialize_with = "deserialize_f64")]
+    pub quantity: f64,
+
+    /// Mark price
+    #[serde(rename = "@markPrice", deserialize_with = "deserialize_f64")]
+    pub mark_price: f64,
+
+    /// Position value (quantity * mark_price * multiplier)
+    #[serde(rename = "@positionValue", deserialize_with = "deserialize_f64")]
+    pub position_value: f64,
+
+    /// Total cost basis
+    #[serde(
+        rename = "@costBasisMoney",
+        default,
+        deserialize_with = "deserialize_optional_f64"
+    )]
+    pub cost_basis_money: Option<f64>,
+
+    /// FIFO unrealized P&L
+    #[serde(
+        rename = "@fifoPnlUnrealized",
+        default,
+        deserialize_with = "deserialize_optional_f64"
+    )]
+    pub fifo_pnl_unrealized: Option<f64>,
+}
+
+impl From<&PositionSummary> for PositionSummaryF64 {
+    fn from(position: &PositionSummary) -> Self {
+        PositionSummaryF64 {
+            conid: position.conid,
+            symbol: position.symbol.clone(),
+            asset_category: position.asset_category.clone(),
+            currency: position.currency.clone(),
+            report_date: position.report_date,
+            quantity: position.quantity.to_f64().unwrap_or_default(),
+            mark_price: position.mark_price.to_f64().unwrap_or_default(),
+            position_value: position.position_value.to_f64().unwrap_or_default(),
+            cost_basis_money: float(position.cost_basis_money),
+            fifo_pnl_unrealized: float(position.fifo_pnl_unrealized),
+        }
+    }
+}
+
+impl From<&Position> for PositionSummaryF64 {
+    fn from(position: &Position) -> Self {
+        PositionSummaryF64::from(&PositionSummary::from(position))
+    }
+}
+
+fn float(amount: Option<Decimal>) -> Option<f64> {
+    amount.and_then(|a| a.to_f64())
+}
+
+/// An Activity FLEX statement reduced to compact trades and positions
+///
+/// Produced by
+/// [`parse_activity_flex_compact`](crate::parsers::parse_activity_flex_compact),
+/// or from a full statement with `From`. Only `<Trade>` rows are kept from
+/// the trades section, matching [`TradesWrapper::items`](super::TradesWrapper).
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[serde(rename = "FlexStatement")]
+pub struct CompactStatement {
+    /// IB account number
+    #[serde(rename = "@accountId")]
+    pub account_id: AccountId,
+
+    /// Statement date range - start date
+    #[serde(
+        rename = "@fromDate",
+        deserialize_with = "crate::parsers::xml_utils::deserialize_flex_date",
+        serialize_with = "crate::parsers::xml_utils::serialize_flex_date"
+    )]
+    pub from_date: NaiveDate,
+
+    /// Statement date range - end date
+    #[serde(
+        rename = "@toDate",
+        deserialize_with = "crate::parsers::xml_utils::deserialize_flex_date",
+        serialize_with = "crate::parsers::xml_utils::serialize_flex_date"
+    )]
+    pub to_date: NaiveDate,
+
+    /// Trade executions
+    #[serde(
+        rename = "Trades",
+        default,
+        deserialize_with = "deserialize_trade_summaries"
+    )]
+    pub trades: Vec<TradeSummary>,
+
+    /// Open positions at end of period
+    #[serde(
+        rename = "OpenPositions",
+        default,
+        deserialize_with = "deserialize_position_summaries"
+    )]
+    pub positions: Vec<PositionSummary>,
+}
+
+impl From<&ActivityFlexStatement> for CompactStatement {
+    fn from(statement: &ActivityFlexStatement) -> Self {
+        CompactStatement {
+            account_id: statement.account_id.clone(),
+            from_date: statement.from_date,
+            to_date: statement.to_date,
+            trades: statement.trades.iter().map(TradeSummary::from).collect(),
+            positions: statement
+                .positions
+                .iter()
+                .map(PositionSummary::from)
+                .collect(),
+        }
+    }
+}
+
+/// [`CompactStatement`] with amounts as `f64`
+///
+/// Produced by
+/// [`parse_activity_flex_compact_f64`](crate::parsers::parse_activity_flex_compact_f64),
+/// or from a full or compact statement with `From`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[serde(rename = "FlexStatement")]
+pub struct CompactStatementF64 {
+    /// IB account number
+    #[serde(rename = "@accountId")]
+    pub account_id: AccountId,
+
+    /// Statement date range - start date
+    #[serde(
+        rename = "@fromDate",
+        deserialize_with = "crate::parsers::xml_utils::deserialize_flex_date",
+        serialize_with = "crate::parsers::xml_utils::serialize_flex_date"
+    )]
+    pub from_date: NaiveDate,
+
+    /// Statement date range - end date
+    #[serde(
+        rename = "@toDate",
+        deserialize_with = "crate::parsers::xml_utils::deserialize_flex_date",
+        serialize_with = "crate::parsers::xml_utils::serialize_flex_date"
+    )]
+    pub to_date: NaiveDate,
+
+    /// Trade executions
+    #[serde(
+        rename = "Trades",
+        default,
+        deserialize_with = "deserialize_trade_summaries"
+    )]
+    pub trades: Vec<TradeSummaryF64>,
+
+    /// Open positions at end of period
+    #[serde(
+        rename = "OpenPositions",
+        default,
+        deserialize_with = "deserialize_position_summaries"
+    )]
+    pub positions: Vec<PositionSummaryF64>,
+}
+
+impl From<&CompactStatement> for CompactStatementF64 {
+    fn from(statement: &CompactStatement) -> Self {
+        CompactStatementF64 {
+            account_id: statement.account_id.clone(),
+            from_date: statement.from_date,
+            to_date: statement.to_date,
+            trades: statement.trades.iter().map(TradeSummaryF64::from).collect(),
+            positions: statement
+                .positions
+                .iter()
+                .map(PositionSummaryF64::from)
+                .collect(),
+        }
+    }
+}
+
+impl From<&ActivityFlexStatement> for CompactStatementF64 {
+    fn from(statement: &ActivityFlexStatement) -> Self {
+        CompactStatementF64::from(&CompactStatement::from(statement))
+    }
+}
+
+/// `<Trades>` interleaves `<Trade>` with order, lot, and summary rows; keep
+/// only the trades
+fn deserialize_trade_summaries<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    // Items are unpacked immediately, so boxing the large variant buys nothing
+    #[allow(clippy::large_enum_variant)]
+    #[derive(Deserialize)]
+    enum Item<T> {
+        Trade(T),
+        #[serde(other)]
+        Other,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(bound = "T: Deserialize<'de>")]
+    struct Raw<T> {
+        #[serde(rename = "$value", default = "Vec::new")]
+        items: Vec<Item<T>>,
+    }
+
+    let raw = Raw::deserialize(deserializer)?;
+    Ok(raw
+        .items
+        .into_iter()
+        .filter_map(|item| match item {
+            Item::Trade(trade) => Some(trade),
+            Item::Other => None,
+        })
+        .collect())
+}
+
+fn deserialize_position_summaries<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(bound = "T: Deserialize<'de>")]
+    struct Raw<T> {
+        #[serde(rename = "OpenPosition", default = "Vec::new")]
+        items: Vec<T>,
+    }
+
+    Ok(Raw::deserialize(deserializer)?.items)
+}
+
+/// Top-level envelope for [`CompactStatement`]s or [`CompactStatementF64`]s
+#[derive(Deserialize)]
+#[serde(bound = "S: Deserialize<'de>")]
+pub(crate) struct CompactResponse<S> {
+    #[serde(rename = "FlexStatements")]
+    pub(crate) statements: CompactStatements<S>,
+}
+
+#[derive(Deserialize)]
+#[serde(bound = "S: Deserialize<'de>")]
+pub(crate) struct CompactStatements<S> {
+    #[serde(rename = "FlexStatement", default = "Vec::new")]
+    pub(crate) statements: Vec<S>,
+}
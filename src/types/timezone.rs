@@ -0,0 +1,133 @@
+//! Timezone-aware date/time accessors (`chrono-tz` feature)
+//!
+//! IB writes every date/time without an offset, in the timezone configured
+//! on the FLEX query. Pass that timezone through
+//! [`ParseOptions`](crate::parsers::ParseOptions) and the statement can turn
+//! its naive values into [`DateTime<Tz>`] or [`DateTime<Utc>`]. Statements
+//! parsed without one assume [`DEFAULT_TIMEZONE`], IB's default.
+//!
+//! # Example
+//! ```
+//! use chrono_tz::Europe::London;
+//! use ib_flex::parsers::{parse_activity_flex_with_options, ParseOptions};
+//!
+//! let xml = include_str!("../../tests/fixtures/activity_minimal.xml");
+//! let options = ParseOptions::new().with_timezone(London);
+//! let statement = parse_activity_flex_with_options(xml, &options).unwrap();
+//!
+//! let generated = statement.when_generated_utc().unwrap();
+//! assert_eq!(generated.to_rfc3339(), "2025-01-15T23:59:59+00:00");
+//!
+//! let trade = &statement.trades[0];
+//! let executed = trade.trade_time_in(statement.reporting_timezone()).unwrap();
+//! assert!(executed.is_some());
+//! ```
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use chrono_tz::Tz;
+
+use super::activity::{ActivityFlexStatement, Trade};
+use super::trade_confirmation::TradeConfirmationStatement;
+use crate::parsers::datetime::localize_ib_datetime;
+use crate::Result;
+
+/// Timezone assumed when none was configured: IB reports in US/Eastern
+/// unless the FLEX query says otherwise
+pub const DEFAULT_TIMEZONE: Tz = chrono_tz::America::New_York;
+
+macro_rules! impl_statement_timezone {
+    ($($ty:ty),+ $(,)?) => {
+        $(
+            impl $ty {
+                /// Timezone the statement reports in
+                ///
+                /// Falls back to [`DEFAULT_TIMEZONE`] when none was configured
+                /// or the stored name is not a known IANA timezone.
+                pub fn reporting_timezone(&self) -> Tz {
+                    self.timezone
+                        .as_deref()
+                        .and_then(|name| name.parse().ok())
+                        .unwrap_or(DEFAULT_TIMEZONE)
+                }
+
+                /// Resolve a naive date/time from this statement in its
+                /// reporting timezone
+                pub fn localize(&self, datetime: NaiveDateTime) -> Result<DateTime<Tz>> {
+                    localize_ib_datetime(datetime, self.reporting_timezone())
+                }
+
+                /// Resolve a naive date/time from this statement to UTC
+                pub fn to_utc(&self, datetime: NaiveDateTime) -> Result<DateTime<Utc>> {
+                    self.localize(datetime).map(|dt| dt.with_timezone(&Utc))
+                }
+            }
+        )+
+    };
+}
+
+impl_statement_timezone!(ActivityFlexStatement, TradeConfirmationStatement);
+
+impl ActivityFlexStatement {
+    /// `whenGenerated` in the reporting timezone
+    pub fn when_generated_at(&self) -> Result<DateTime<Tz>> {
+        self.localize(self.when_generated_datetime()?)
+    }
+
+    /// `whenGenerated` in UTC
+    pub fn when_generated_utc(&self) -> Result<DateTime<Utc>> {
+        self.to_utc(self.when_generated_datetime()?)
+    }
+}
+
+impl Trade {
+    /// `dateTime` of the execution resolved in `timezone`
+    ///
+    /// Use the owning statement's
+    /// [`reporting_timezone`](ActivityFlexStatement::reporting_timezone).
+    pub fn trade_time_in(&self, timezone: Tz) -> Result<Option<DateTime<Tz>>> {
+        self.trade_time
+            .map(|dt| localize_ib_datetime(dt, timezone))
+            .transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono_tz::Asia::Tokyo;
+
+    use super::*;
+    use crate::parsers::{parse_activity_flex, parse_activity_flex_with_options, ParseOptions};
+
+    const XML: &str = include_str!("../../tests/fixtures/activity_minimal.xml");
+
+    #[test]
+    fn test_default_timezone_is_eastern() {
+        let statement = parse_activity_flex(XML).unwrap();
+        assert_eq!(statement.timezone, None);
+        assert_eq!(statement.reporting_timezone(), DEFAULT_TIMEZONE);
+        assert_eq!(
+            statement.when_generated_at().unwrap().to_rfc3339(),
+            "2025-01-15T23:59:59-05:00"
+        );
+        assert_eq!(
+            statement.when_generated_utc().unwrap().to_rfc3339(),
+            "2025-01-16T04:59:59+00:00"
+        );
+    }
+
+    #[test]
+    fn test_configured_timezone_is_recorded() {
+        let options = ParseOptions::new().with_timezone(Tokyo);
+        let statement = parse_activity_flex_with_options(XML, &options).unwrap();
+        assert_eq!(statement.timezone.as_deref(), Some("Asia/Tokyo"));
+        assert_eq!(statement.reporting_timezone(), Tokyo);
+        assert_eq!(
+            statement.when_generated_utc().unwrap().to_rfc3339(),
+            "2025-01-15T14:59:59+00:00"
+        );
+
+        let trade = &statement.trades[0];
+        let local = trade.trade_time_in(Tokyo).unwrap().unwrap();
+        assert_eq!(local.naive_local(), trade.trade_time.unwrap());
+    }
+}
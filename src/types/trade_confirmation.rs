@@ -1,18 +1,32 @@
 //! Trade Confirmation FLEX statement types
 
-use serde::{Deserialize, Serialize};
+#[cfg(feature = "schemars")]
+use schemars::JsonSchema;
+use serde::Deserialize;
+#[cfg(feature = "serialize")]
+use serde::Serialize;
+
+use super::common::AccountId;
 
 /// Trade Confirmation FLEX statement
 ///
 /// Contains real-time trade execution data from a Trade Confirmation FLEX query.
 /// This is refreshed immediately after each trade execution.
-#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(feature = "eq", derive(PartialEq, Eq, Hash))]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
 pub struct TradeConfirmationStatement {
     /// IB account number
     #[serde(rename = "@accountId")]
-    pub account_id: String,
+    pub account_id: AccountId,
 
     /// Trade executions
     #[serde(rename = "Trades", default)]
     pub trades: super::activity::TradesWrapper,
+
+    /// IANA name of the reporting timezone, when supplied through
+    /// [`ParseOptions`](crate::parsers::ParseOptions)
+    #[serde(skip)]
+    pub timezone: Option<String>,
 }
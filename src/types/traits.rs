@@ -0,0 +1,337 @@
+//! Accessor traits shared across record types
+//!
+//! Most FLEX rows carry some combination of an account, a symbol, a contract
+//! ID, and a date, but the field types vary (`Conid` vs. raw string, required
+//! vs. optional). These traits expose them uniformly so that filtering and
+//! grouping helpers can be written once against trait bounds.
+//!
+//! # Example
+//! ```
+//! use ib_flex::types::{HasSymbol, TradeBuilder, CashTransactionBuilder, CashTransactionType};
+//!
+//! fn for_symbol<'a, T: HasSymbol>(rows: &'a [T], symbol: &str) -> Vec<&'a T> {
+//!     rows.iter().filter(|r| r.symbol() == Some(symbol)).collect()
+//! }
+//!
+//! let trades = vec![TradeBuilder::new("AAPL").build(), TradeBuilder::new("MSFT").build()];
+//! assert_eq!(for_symbol(&trades, "AAPL").len(), 1);
+//!
+//! let cash = vec![CashTransactionBuilder::new(CashTransactionType::Dividends, 10)
+//!     .symbol("AAPL")
+//!     .build()];
+//! assert_eq!(for_symbol(&cash, "AAPL").len(), 1);
+//! ```
+
+use chrono::NaiveDate;
+
+use super::activity::{CashTransaction, CorporateAction, Position, SecurityInfo, Trade};
+use super::common::{AccountId, AssetCategory, Conid};
+use super::extended::*;
+
+/// Records belonging to an IB account
+pub trait HasAccount {
+    /// Account the record belongs to
+    fn account_id(&self) -> &AccountId;
+}
+
+/// Records referring to a security by ticker symbol
+pub trait HasSymbol {
+    /// Ticker symbol, if the row has a non-empty one
+    fn symbol(&self) -> Option<&str>;
+}
+
+/// Records referring to a security by IB contract ID
+pub trait HasConid {
+    /// Contract ID, if present and numeric
+    fn conid(&self) -> Option<Conid>;
+}
+
+/// Records of a security in a given asset category
+pub trait HasAssetCategory {
+    /// Asset category, if the row has one
+    fn asset_category(&self) -> Option<&AssetCategory>;
+}
+
+/// Records with a primary date
+///
+/// Each type picks the date most useful for ordering: the trade date for
+/// trades, the report date for positions, the cash date for cash
+/// transactions, and so on.
+pub trait Dated {
+    /// The record's primary date
+    fn date(&self) -> Option<NaiveDate>;
+
+    /// Returns true if the record's date falls within `from..=to`
+    fn is_between(&self, from: NaiveDate, to: NaiveDate) -> bool {
+        self.date().is_some_and(|date| from <= date && date <= to)
+    }
+}
+
+macro_rules! impl_has_account {
+    ($($ty:ty),+ $(,)?) => {
+        $(
+            impl HasAccount for $ty {
+                fn account_id(&self) -> &AccountId {
+                    &self.account_id
+                }
+            }
+        )+
+    };
+}
+
+macro_rules! impl_has_symbol {
+    (required: [$($req:ty),* $(,)?], optional: [$($opt:ty),* $(,)?] $(,)?) => {
+        $(
+            impl HasSymbol for $req {
+                fn symbol(&self) -> Option<&str> {
+                    Some(self.symbol.as_str()).filter(|s| !s.is_empty())
+                }
+            }
+        )*
+        $(
+            impl HasSymbol for $opt {
+                fn symbol(&self) -> Option<&str> {
+                    self.symbol.as_deref().filter(|s| !s.is_empty())
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! impl_has_conid {
+    (typed: [$($typed:ty),* $(,)?], raw: [$($raw:ty),* $(,)?] $(,)?) => {
+        $(
+            impl HasConid for $typed {
+                fn conid(&self) -> Option<Conid> {
+                    Some(self.conid)
+                }
+            }
+        )*
+        $(
+            impl HasConid for $raw {
+                fn conid(&self) -> Option<Conid> {
+                    self.conid.as_deref().and_then(|s| s.trim().parse().ok())
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! impl_has_asset_category {
+    (required: [$($req:ty),* $(,)?], optional: [$($opt:ty),* $(,)?] $(,)?) => {
+        $(
+            impl HasAssetCategory for $req {
+                fn asset_category(&self) -> Option<&AssetCategory> {
+                    Some(&self.asset_category)
+                }
+            }
+        )*
+        $(
+            impl HasAssetCategory for $opt {
+                fn asset_category(&self) -> Option<&AssetCategory> {
+                    self.asset_category.as_ref()
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! impl_dated {
+    ($($ty:ty => |$row:ident| $date:expr;)+) => {
+        $(
+            impl Dated for $ty {
+                fn date(&self) -> Option<NaiveDate> {
+                    let $row = self;
+                    $date
+                }
+            }
+        )+
+    };
+}
+
+impl_has_account!(
+    Trade,
+    Position,
+    CashTransaction,
+    CorporateAction,
+    AccountInformation,
+    ChangeInNAV,
+    EquitySummaryByReportDateInBase,
+    CashReportCurrency,
+    TradeConfirm,
+    OptionEAE,
+    FxTransaction,
+    ChangeInDividendAccrual,
+    OpenDividendAccrual,
+    InterestAccrualsCurrency,
+    Transfer,
+    DepositWithdrawal,
+    MTMPerformanceSummaryUnderlying,
+    FIFOPerformanceSummaryUnderlying,
+    MTDYTDPerformanceSummary,
+    StatementOfFundsLine,
+    ChangeInPositionValue,
+    UnbundledCommissionDetail,
+    ClientFee,
+    ClientFeesDetail,
+    SLBActivity,
+    SLBFee,
+    HardToBorrowDetail,
+    FxLot,
+    UnsettledTransfer,
+    TradeTransfer,
+    PriorPeriodPosition,
+    TierInterestDetail,
+    DebitCardActivity,
+    SalesTax,
+    SymbolSummary,
+    AssetSummary,
+    Order,
+);
+
+impl_has_symbol! {
+    required: [
+        Trade,
+        Position,
+        CorporateAction,
+        SecurityInfo,
+        TradeConfirm,
+        OptionEAE,
+        ChangeInDividendAccrual,
+        OpenDividendAccrual,
+        Transfer,
+    ],
+    optional: [
+        CashTransaction,
+        MTMPerformanceSummaryUnderlying,
+        FIFOPerformanceSummaryUnderlying,
+        MTDYTDPerformanceSummary,
+        StatementOfFundsLine,
+        ChangeInPositionValue,
+        UnbundledCommissionDetail,
+        SLBActivity,
+        SLBFee,
+        HardToBorrowDetail,
+        UnsettledTransfer,
+        TradeTransfer,
+        PriorPeriodPosition,
+        SalesTax,
+        SymbolSummary,
+        Order,
+    ],
+}
+
+impl_has_conid! {
+    typed: [Trade, Position, CorporateAction, SecurityInfo],
+    raw: [
+        CashTransaction,
+        TradeConfirm,
+        OptionEAE,
+        ChangeInDividendAccrual,
+        OpenDividendAccrual,
+        Transfer,
+        MTMPerformanceSummaryUnderlying,
+        FIFOPerformanceSummaryUnderlying,
+        MTDYTDPerformanceSummary,
+        StatementOfFundsLine,
+        ChangeInPositionValue,
+        UnbundledCommissionDetail,
+        SLBActivity,
+        SLBFee,
+        HardToBorrowDetail,
+        UnsettledTransfer,
+        TradeTransfer,
+        PriorPeriodPosition,
+        SalesTax,
+        SymbolSummary,
+        Order,
+    ],
+}
+
+impl_has_asset_category! {
+    required: [Trade, Position, SecurityInfo, TradeConfirm],
+    optional: [
+        CashTransaction,
+        CorporateAction,
+        OptionEAE,
+        ChangeInDividendAccrual,
+        OpenDividendAccrual,
+        Transfer,
+        MTMPerformanceSummaryUnderlying,
+        FIFOPerformanceSummaryUnderlying,
+        MTDYTDPerformanceSummary,
+        ChangeInPositionValue,
+        UnbundledCommissionDetail,
+        SLBActivity,
+        SLBFee,
+        HardToBorrowDetail,
+        UnsettledTransfer,
+        TradeTransfer,
+        PriorPeriodPosition,
+        SymbolSummary,
+        AssetSummary,
+        Order,
+    ],
+}
+
+impl_dated! {
+    Trade => |t| t.trade_date.or(t.report_date);
+    Position => |p| Some(p.report_date);
+    CashTransaction => |c| c.date.or(c.report_date);
+    CorporateAction => |c| c.action_date.or(Some(c.report_date));
+    EquitySummaryByReportDateInBase => |e| Some(e.report_date);
+    TradeConfirm => |t| Some(t.trade_date);
+    OptionEAE => |o| Some(o.date);
+    FxTransaction => |f| f.report_date;
+    ChangeInDividendAccrual => |c| c.date.or(Some(c.ex_date));
+    OpenDividendAccrual => |o| Some(o.ex_date);
+    Transfer => |t| Some(t.date);
+    DepositWithdrawal => |d| d.date.or(d.report_date);
+    MTMPerformanceSummaryUnderlying => |m| m.report_date;
+    FIFOPerformanceSummaryUnderlying => |f| f.report_date;
+    StatementOfFundsLine => |s| s.date.or(s.report_date);
+    ChangeInPositionValue => |c| c.report_date;
+    ClientFee => |c| c.date;
+    ClientFeesDetail => |c| c.date;
+    SLBActivity => |s| s.date;
+    SLBFee => |s| s.value_date;
+    HardToBorrowDetail => |h| h.value_date;
+    FxLot => |f| f.report_date;
+    UnsettledTransfer => |u| u.date;
+    TradeTransfer => |t| t.date;
+    PriorPeriodPosition => |p| p.date;
+    TierInterestDetail => |t| t.value_date.or(t.report_date);
+    DebitCardActivity => |d| d.date;
+    SalesTax => |s| s.date;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{CashTransactionBuilder, CashTransactionType, TradeBuilder};
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 1, day).unwrap()
+    }
+
+    #[test]
+    fn test_accessors_normalize_field_shapes() {
+        let trade = TradeBuilder::new("AAPL")
+            .conid(265598)
+            .trade_date(date(15))
+            .build();
+        assert_eq!(trade.account_id().as_str(), "U1234567");
+        assert_eq!(HasSymbol::symbol(&trade), Some("AAPL"));
+        assert_eq!(HasConid::conid(&trade), Some(Conid(265598)));
+        assert!(trade.is_between(date(1), date(31)));
+        assert!(!trade.is_between(date(16), date(31)));
+
+        let fee = CashTransactionBuilder::new(CashTransactionType::OtherFees, -5)
+            .conid("")
+            .report_date(date(20))
+            .build();
+        assert_eq!(HasSymbol::symbol(&fee), None);
+        assert_eq!(HasConid::conid(&fee), None);
+        assert_eq!(Dated::date(&fee), Some(date(20)));
+    }
+}
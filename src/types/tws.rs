@@ -0,0 +1,221 @@
+//! Conversions to the `ibapi` TWS API contract (requires the `tws` feature)
+//!
+//! Orders and market data requests through TWS or IB Gateway name their
+//! instrument with a `Contract`: a contract ID plus the security type,
+//! symbol, expiry, right, strike, and exchange. A FLEX trade, position, or
+//! security converts with `From` into [`ibapi::contracts::Contract`], so an
+//! instrument from a statement can go straight into an order or market data
+//! request.
+//!
+//! Stocks, funds, and options are routed to `SMART` and FX pairs to
+//! `IDEALPRO`; everything else goes to its listing exchange. A contract ID
+//! too large for TWS's `i32` is left at zero, so TWS resolves the contract
+//! from its other fields.
+//!
+//! # Example
+//! ```
+//! use ib_flex::parse_activity_flex;
+//! use ibapi::contracts::{Contract, SecurityType};
+//!
+//! let xml = include_str!("../../tests/fixtures/activity_minimal.xml");
+//! let statement = parse_activity_flex(xml).unwrap();
+//!
+//! let contract = Contract::from(&statement.trades.items[0]);
+//! assert_eq!(contract.security_type, SecurityType::Stock);
+//! assert_eq!(contract.symbol.as_str(), "AAPL");
+//! assert_eq!(contract.exchange.as_str(), "SMART");
+//! ```
+
+use chrono::NaiveDate;
+use ibapi::contracts::{Contract, OptionRight, SecurityIdType};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+
+use super::activity::{Position, SecurityInfo, Trade};
+use super::common::{AssetCategory, Conid, PutCall};
+
+/// Fields common to the FLEX rows that describe a contract
+struct ContractFields<'a> {
+    conid: Conid,
+    symbol: &'a str,
+    asset_category: &'a AssetCategory,
+    underlying_symbol: Option<&'a str>,
+    expiry: Option<NaiveDate>,
+    put_call: Option<&'a PutCall>,
+    strike: Option<Decimal>,
+    multiplier: Option<Decimal>,
+    listing_exchange: Option<&'a str>,
+    currency: Option<&'a str>,
+    isin: Option<&'a str>,
+    cusip: Option<&'a str>,
+}
+
+impl From<ContractFields<'_>> for Contract {
+    fn from(f: ContractFields<'_>) -> Self {
+        let sec_type = sec_type(f.asset_category);
+        let derivative = f.asset_category.is_derivative();
+        let symbol = match f.asset_category {
+            AssetCategory::Cash => f.symbol.split('.').next().unwrap_or(f.symbol),
+            _ if derivative => f
+                .underlying_symbol
+                .filter(|s| !s.is_empty())
+                .unwrap_or(f.symbol),
+            _ => f.symbol,
+        };
+        let primary_exchange = f.listing_exchange.unwrap_or_default().to_string();
+        let exchange = match sec_type {
+            "STK" | "FUND" | "OPT" | "WAR" => "SMART".to_string(),
+            "CASH" => "IDEALPRO".to_string(),
+            _ => primary_exchange.clone(),
+        };
+        let (security_id_type, security_id) = match (f.isin.filter(|s| !s.is_empty()), f.cusip) {
+            (Some(isin), _) => (Some(SecurityIdType::Isin), isin),
+            (None, Some(cusip)) if !cusip.is_empty() => (Some(SecurityIdType::Cusip), cusip),
+            _ => (None, ""),
+        };
+        Contract {
+            contract_id: i32::try_from(f.conid.0).unwrap_or_default(),
+            symbol: symbol.trim().into(),
+            security_type: sec_type.parse().unwrap_or_default(),
+            last_trade_date_or_contract_month: f
+                .expiry
+                .map(|d| d.format("%Y%m%d").to_string())
+                .unwrap_or_default(),
+            strike: f.strike.and_then(|s| s.to_f64()).unwrap_or_default(),
+            right: match f.put_call {
+                Some(PutCall::Call) => Some(OptionRight::Call),
+                Some(PutCall::Put) => Some(OptionRight::Put),
+                _ => None,
+            },
+            multiplier: match f.multiplier {
+                Some(m) if derivative => m.normalize().to_string(),
+                _ => String::new(),
+            },
+            exchange: exchange.into(),
+            primary_exchange: primary_exchange.into(),
+            currency: f.currency.unwrap_or_default().into(),
+            local_symbol: f.symbol.to_string(),
+            security_id_type,
+            security_id: security_id.to_string(),
+            ..Default::default()
+        }
+    }
+}
+
+/// TWS security type of a FLEX asset category
+///
+/// TWS uses the FLEX codes except where it folds categories together.
+fn sec_type(category: &AssetCategory) -> &str {
+    match category {
+        AssetCategory::Bill => "BOND",
+        AssetCategory::ForexCfd => "CFD",
+        AssetCategory::SingleStockFuture => "FUT",
+        AssetCategory::Metal => "CMDTY",
+        other => other.as_str(),
+    }
+}
+
+/// Implement `From<&Row> for Contract` for rows with the common contract
+/// fields
+macro_rules! impl_tws_contract {
+    ($($ty:ty => |$row:ident| $currency:expr;)+) => {
+        $(
+            impl From<&$ty> for Contract {
+                fn from($row: &$ty) -> Self {
+                    ContractFields {
+                        conid: $row.conid,
+                        symbol: &$row.symbol,
+                        asset_category: &$row.asset_category,
+                        underlying_symbol: $row.underlying_symbol.as_deref(),
+                        expiry: $row.expiry,
+                        put_call: $row.put_call.as_ref(),
+                        strike: $row.strike,
+                        multiplier: $row.multiplier,
+                        listing_exchange: $row.listing_exchange.as_deref(),
+                        currency: $currency,
+                        isin: $row.isin.as_deref(),
+                        cusip: $row.cusip.as_deref(),
+                    }
+                    .into()
+                }
+            }
+        )+
+    };
+}
+
+impl_tws_contract! {
+    Trade => |t| Some(t.currency.as_str());
+    Position => |p| Some(p.currency.as_str());
+    SecurityInfo => |s| s.currency.as_deref();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::TradeBuilder;
+    use ibapi::contracts::SecurityType;
+
+    #[test]
+    fn test_option_contract() {
+        let trade = TradeBuilder::new("AAPL  250221C00180000")
+            .conid(701234567)
+            .asset_category(AssetCategory::Option)
+            .underlying_symbol("AAPL")
+            .expiry(NaiveDate::from_ymd_opt(2025, 2, 21).unwrap())
+            .put_call(PutCall::Call)
+            .strike(Decimal::new(18000, 2))
+            .multiplier(100)
+            .build();
+        let contract = Contract::from(&trade);
+        assert_eq!(contract.contract_id, 701234567);
+        assert_eq!(contract.symbol.as_str(), "AAPL");
+        assert_eq!(contract.security_type, SecurityType::Option);
+        assert_eq!(contract.last_trade_date_or_contract_month, "20250221");
+        assert_eq!(contract.strike, 180.0);
+        assert_eq!(contract.right, Some(OptionRight::Call));
+        assert_eq!(contract.multiplier, "100");
+        assert_eq!(contract.exchange.as_str(), "SMART");
+        assert_eq!(contract.local_symbol, "AAPL  250221C00180000");
+    }
+
+    #[test]
+    fn test_forex_and_futures_contracts() {
+        let fx = TradeBuilder::new("EUR.USD")
+            .asset_category(AssetCategory::Cash)
+            .build();
+        let contract = Contract::from(&fx);
+        assert_eq!(
+            (contract.symbol.as_str(), contract.currency.as_str()),
+            ("EUR", "USD")
+        );
+        assert_eq!(contract.security_type, SecurityType::ForexPair);
+        assert_eq!(contract.exchange.as_str(), "IDEALPRO");
+        assert!(contract.multiplier.is_empty());
+
+        let xml = include_str!("../../tests/fixtures/activity_futures.xml");
+        let statement = crate::parse_activity_flex(xml).unwrap();
+        let future = statement
+            .trades
+            .items
+            .iter()
+            .find(|t| t.asset_category == AssetCategory::Future)
+            .unwrap();
+        let contract = Contract::from(future);
+        assert_eq!(contract.security_type, SecurityType::Future);
+        assert_eq!(contract.local_symbol, future.symbol);
+        assert_eq!(
+            contract.exchange.as_str(),
+            future.listing_exchange.as_deref().unwrap_or_default()
+        );
+    }
+
+    #[test]
+    fn test_security_id_and_large_conid() {
+        let mut trade = TradeBuilder::new("AAPL").conid(u64::from(u32::MAX)).build();
+        trade.isin = Some("US0378331005".to_string());
+        let contract = Contract::from(&trade);
+        assert_eq!(contract.contract_id, 0);
+        assert_eq!(contract.security_id_type, Some(SecurityIdType::Isin));
+        assert_eq!(contract.security_id, "US0378331005");
+    }
+}
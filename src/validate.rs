@@ -0,0 +1,440 @@
+//! Consistency checks within and across a statement's sections
+//!
+//! IB's amounts on a row are tied together: a trade's `netCash` is its
+//! `proceeds` plus commission and taxes, a buy has positive quantity and
+//! negative proceeds, and a position's value is its quantity at the mark
+//! price. Sections agree with each other too: the `CashReport` dividends
+//! line is the sum of the dividend cash transactions, each `SymbolSummary`
+//! row totals its symbol's executions, and ending cash is the last
+//! `StmtFunds` balance. A statement that breaks one of these was usually
+//! edited by hand, truncated, or assembled from a query missing rows.
+//! [`validate_statement`] checks all of them and lists each broken identity
+//! with the row it came from.
+//!
+//! # Example
+//! ```
+//! use ib_flex::parse_activity_flex;
+//! use ib_flex::validate::{validate_statement, ValidateConfig};
+//!
+//! let xml = include_str!("../tests/fixtures/activity_minimal.xml");
+//! let mut statement = parse_activity_flex(xml).unwrap();
+//! assert!(validate_statement(&statement, &ValidateConfig::default()).is_empty());
+//!
+//! statement.trades.items[0].net_cash = Some("-18500.00".parse().unwrap());
+//! let violations = validate_statement(&statement, &ValidateConfig::default());
+//! assert_eq!(
+//!     violations[0].to_string(),
+//!     "Trades/Trade[0] (AAPL): netCash is -18500.00, expected -18551.00 from proceeds + commission + taxes"
+//! );
+//! ```
+
+use std::fmt;
+
+use rust_decimal::Decimal;
+use serde::Deserialize;
+#[cfg(feature = "serialize")]
+use serde::Serialize;
+
+use crate::types::{
+    ActivityFlexStatement, AssetCategory, BuySell, CashReportCurrency, CashTransactionType,
+    LevelOfDetail, LongShort, Position, Trade,
+};
+
+/// Settings for [`validate_statement`]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct ValidateConfig {
+    /// Largest difference between an amount and the amount its identity
+    /// computes that is still a match, for IB's rounding (default 0.01)
+    pub tolerance: Decimal,
+}
+
+impl Default for ValidateConfig {
+    fn default() -> Self {
+        ValidateConfig {
+            tolerance: Decimal::new(1, 2),
+        }
+    }
+}
+
+/// An identity a record can break
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub enum Rule {
+    /// Trade `netCash` equals `proceeds + ibCommission + taxes`
+    NetCash,
+    /// Trade `proceeds` equals `-quantity × price × multiplier`
+    Proceeds,
+    /// Buys have positive quantity, sells negative
+    QuantitySign,
+    /// Buys have proceeds of zero or less, sells zero or more
+    ProceedsSign,
+    /// Position `positionValue` equals `position × markPrice × multiplier`
+    PositionValue,
+    /// Long positions hold a positive quantity, short negative
+    PositionSide,
+    /// `CashReport` `dividends` equals that currency's `Dividends` cash
+    /// transactions
+    Dividends,
+    /// A `SymbolSummary` row's quantity equals its conid's executions
+    SymbolSummary,
+    /// `CashReport` `endingCash` equals that currency's last `StmtFunds`
+    /// balance
+    EndingCash,
+}
+
+impl Rule {
+    /// What the rule compares against, as written in violation messages
+    fn expectation(self) -> &'static str {
+        match self {
+            Rule::NetCash => "proceeds + commission + taxes",
+            Rule::Proceeds => "-quantity × price × multiplier",
+            Rule::QuantitySign => "the buy/sell side",
+            Rule::ProceedsSign => "the buy/sell side",
+            Rule::PositionValue => "position × markPrice × multiplier",
+            Rule::PositionSide => "the long/short side",
+            Rule::Dividends => "the Dividends cash transactions",
+            Rule::SymbolSummary => "the symbol's executions",
+            Rule::EndingCash => "the last StmtFunds balance",
+        }
+    }
+
+    /// The IB attribute the rule found wrong
+    fn field(self) -> &'static str {
+        match self {
+            Rule::NetCash => "netCash",
+            Rule::Proceeds | Rule::ProceedsSign => "proceeds",
+            Rule::QuantitySign | Rule::SymbolSummary => "quantity",
+            Rule::PositionValue => "positionValue",
+            Rule::PositionSide => "position",
+            Rule::Dividends => "dividends",
+            Rule::EndingCash => "endingCash",
+        }
+    }
+}
+
+/// One broken identity on one record
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+pub struct Violation {
+    /// Row the record is, e.g. `Trades/Trade[0]`, counting from zero
+    pub path: String,
+    /// Ticker symbol of the record, or the currency of a `CashReport` row
+    pub symbol: String,
+    /// Which identity it breaks
+    pub rule: Rule,
+    /// Value on the record
+    pub actual: Decimal,
+    /// Value the identity computes, or `None` for sign rules
+    pub expected: Option<Decimal>,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} ({}): {} is {}, expected ",
+            self.path,
+            self.symbol,
+            self.rule.field(),
+            self.actual
+        )?;
+        match self.expected {
+            Some(expected) => write!(f, "{} from {}", expected, self.rule.expectation()),
+            None => write!(f, "the sign of {}", self.rule.expectation()),
+        }
+    }
+}
+
+/// Check every record of `statement`, and its sections against each other
+///
+/// Identities are only checked when the record has every amount they
+/// need; a missing `taxes` or `multiplier` counts as zero or one. Bond and
+/// bill prices are a percentage of face value, so their proceeds and
+/// position values are compared against the price divided by 100. Trades
+/// with accrued interest are not checked against `netCash`, which includes
+/// it, and forex positions not against `positionValue`, which IB gives in
+/// the base currency. Cross-section checks only run when both sections are
+/// in the statement, and skip the `BASE_SUMMARY` cash report row.
+pub fn validate_statement(
+    statement: &ActivityFlexStatement,
+    config: &ValidateConfig,
+) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    let mut report = |path: String, symbol: &str, failures: Vec<Failure>| {
+        for (rule, actual, expected) in failures {
+            violations.push(Violation {
+                path: path.clone(),
+                symbol: symbol.to_string(),
+                rule,
+                actual,
+                expected,
+            });
+        }
+    };
+
+    let trades = &statement.trades;
+    for (i, trade) in trades.items.iter().enumerate() {
+        report(
+            format!("Trades/Trade[{}]", i),
+            &trade.symbol,
+            check_trade(trade, config),
+        );
+    }
+    for (i, summary) in trades.symbol_summaries.iter().enumerate() {
+        report(
+            format!("Trades/SymbolSummary[{}]", i),
+            &summary.symbol,
+            check_symbol_summary(summary, &trades.items, config),
+        );
+    }
+    for (i, position) in statement.positions.items.iter().enumerate() {
+        report(
+            format!("OpenPositions/OpenPosition[{}]", i),
+            &position.symbol,
+            check_position(position, config),
+        );
+    }
+    for (i, row) in statement.cash_report.items.iter().enumerate() {
+        if row.currency != "BASE_SUMMARY" {
+            report(
+                format!("CashReport/CashReportCurrency[{}]", i),
+                &row.currency,
+                check_cash_report(row, statement, config),
+            );
+        }
+    }
+    violations
+}
+
+type Failure = (Rule, Decimal, Option<Decimal>);
+
+fn check_trade(trade: &Trade, config: &ValidateConfig) -> Vec<Failure> {
+    let mut failures = Vec::new();
+    let matches = |a, b| within(a, b, config.tolerance);
+
+    let accrued_interest = trade.accrued_int.is_some_and(|a| !a.is_zero());
+    let net_cash = trade.net_cash.filter(|_| !accrued_interest);
+    if let (Some(net_cash), Some(proceeds), Some(commission)) =
+        (net_cash, trade.proceeds, trade.commission)
+    {
+        let expected = proceeds
+            .checked_add(commission)
+            .and_then(|sum| sum.checked_add(trade.taxes.unwrap_or_default()));
+        if let Some(expected) = expected.filter(|&expected| !matches(net_cash, expected)) {
+            failures.push((Rule::NetCash, net_cash, Some(expected)));
+        }
+    }
+
+    if let (Some(proceeds), Some(quantity), Some(price)) =
+        (trade.proceeds, trade.quantity, trade.price)
+    {
+        let expected = amount(quantity, price, &trade.asset_category, trade.multiplier).map(|a| -a);
+        // Futures settle daily, so IB reports no proceeds for them
+        let settles_daily = matches!(
+            trade.asset_category,
+            AssetCategory::Future | AssetCategory::FutureOption
+        ) && proceeds.is_zero();
+        if let Some(expected) = expected.filter(|&e| !settles_daily && !matches(proceeds, e)) {
+            failures.push((Rule::Proceeds, proceeds, Some(expected)));
+        }
+    }
+
+    let sign = match trade.buy_sell {
+        Some(BuySell::Buy) => Decimal::ONE,
+        Some(BuySell::Sell) => Decimal::NEGATIVE_ONE,
+        _ => return failures,
+    };
+    if let Some(quantity) = trade.quantity {
+        if (quantity * sign).is_sign_negative() && !quantity.is_zero() {
+            failures.push((Rule::QuantitySign, quantity, None));
+        }
+    }
+    if let Some(proceeds) = trade.proceeds {
+        if (proceeds * sign).is_sign_positive() && !proceeds.is_zero() {
+            failures.push((Rule::ProceedsSign, proceeds, None));
+        }
+    }
+    failures
+}
+
+fn check_position(position: &Position, config: &ValidateConfig) -> Vec<Failure> {
+    let mut failures = Vec::new();
+    let expected = amount(
+        position.quantity,
+        position.mark_price,
+        &position.asset_category,
+        position.multiplier,
+    )
+    .filter(|_| position.asset_category != AssetCategory::Cash);
+    if let Some(expected) =
+        expected.filter(|&e| !within(position.position_value, e, config.tolerance))
+    {
+        failures.push((Rule::PositionValue, position.position_value, Some(expected)));
+    }
+
+    let wrong_side = match position.side {
+        Some(LongShort::Long) => position.quantity.is_sign_negative(),
+        Some(LongShort::Short) => position.quantity.is_sign_positive(),
+        _ => false,
+    };
+    if wrong_side && !position.quantity.is_zero() {
+        failures.push((Rule::PositionSide, position.quantity, None));
+    }
+    failures
+}
+
+fn check_symbol_summary(
+    summary: &Trade,
+    executions: &[Trade],
+    config: &ValidateConfig,
+) -> Vec<Failure> {
+    let Some(quantity) = summary.quantity else {
+        return Vec::new();
+    };
+    let expected = total(
+        executions
+            .iter()
+            .filter(|t| t.conid == summary.conid && t.currency == summary.currency)
+            .filter(|t| matches!(t.level_of_detail, None | Some(LevelOfDetail::Execution)))
+            .filter_map(|t| t.quantity),
+    );
+    match expected {
+        Some(expected) if !within(quantity, expected, config.tolerance) => {
+            vec![(Rule::SymbolSummary, quantity, Some(expected))]
+        }
+        _ => Vec::new(),
+    }
+}
+
+fn check_cash_report(
+    row: &CashReportCurrency,
+    statement: &ActivityFlexStatement,
+    config: &ValidateConfig,
+) -> Vec<Failure> {
+    let mut failures = Vec::new();
+
+    let dividends = total(
+        statement
+            .cash_transactions
+            .iter()
+            .filter(|tx| tx.currency == row.currency)
+            .filter(|tx| tx.transaction_type == Some(CashTransactionType::Dividends))
+            .filter(|tx| matches!(tx.level_of_detail, None | Some(LevelOfDetail::Detail)))
+            .map(|tx| tx.amount),
+    );
+    if let (Some(reported), Some(expected)) = (row.dividends, dividends) {
+        if !statement.cash_transactions.is_empty() && !within(reported, expected, config.tolerance)
+        {
+            failures.push((Rule::Dividends, reported, Some(expected)));
+        }
+    }
+
+    let last_balance = statement
+        .statement_of_funds
+        .iter()
+        .filter(|line| line.currency.as_deref() == Some(row.currency.as_str()))
+        .rev()
+        .find_map(|line| line.balance);
+    if let Some(expected) = last_balance {
+        if !within(row.ending_cash, expected, config.tolerance) {
+            failures.push((Rule::EndingCash, row.ending_cash, Some(expected)));
+        }
+    }
+    failures
+}
+
+/// Sum of `amounts`, or `None` if it overflows
+fn total(mut amounts: impl Iterator<Item = Decimal>) -> Option<Decimal> {
+    amounts.try_fold(Decimal::ZERO, |sum, amount| sum.checked_add(amount))
+}
+
+/// Returns true if `a` and `b` differ by at most `tolerance`
+fn within(a: Decimal, b: Decimal, tolerance: Decimal) -> bool {
+    a.checked_sub(b).is_some_and(|diff| diff.abs() <= tolerance)
+}
+
+/// `quantity × price × multiplier`, or `None` if it overflows
+///
+/// Bond and bill prices are a percentage of face value.
+fn amount(
+    quantity: Decimal,
+    price: Decimal,
+    asset_category: &AssetCategory,
+    multiplier: Option<Decimal>,
+) -> Option<Decimal> {
+    let mut multiplier = multiplier.unwrap_or(Decimal::ONE);
+    if matches!(asset_category, AssetCategory::Bond | AssetCategory::Bill) {
+        multiplier /= Decimal::ONE_HUNDRED;
+    }
+    quantity.checked_mul(price)?.checked_mul(multiplier)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn statement() -> ActivityFlexStatement {
+        let xml = include_str!("../tests/fixtures/activity_daily_portfolio.xml");
+        crate::parse_activity_flex(xml).unwrap()
+    }
+
+    #[test]
+    fn test_fixture_is_consistent() {
+        let violations = validate_statement(&statement(), &ValidateConfig::default());
+        assert!(violations.is_empty(), "{:?}", violations);
+    }
+
+    #[test]
+    fn test_violations() {
+        let mut statement = statement();
+        let trade = &mut statement.trades.items[0];
+        trade.quantity = trade.quantity.map(|q| -q);
+        let position = &mut statement.positions.items[0];
+        position.position_value += Decimal::ONE;
+
+        let rules: Vec<(String, Rule)> = validate_statement(&statement, &ValidateConfig::default())
+            .into_iter()
+            .map(|v| (v.path, v.rule))
+            .collect();
+        assert_eq!(
+            rules,
+            [
+                ("Trades/Trade[0]".to_string(), Rule::Proceeds),
+                ("Trades/Trade[0]".to_string(), Rule::QuantitySign),
+                (
+                    "OpenPositions/OpenPosition[0]".to_string(),
+                    Rule::PositionValue
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_cross_section_violations() {
+        let xml = include_str!("../tests/fixtures/activity_daily_portfolio.xml").replace(
+            "</FlexStatement>",
+            r#"<StmtFunds><StatementOfFundsLine accountId="U1234567" currency="USD" balance="149000.50" /></StmtFunds></FlexStatement>"#,
+        );
+        let mut statement = crate::parse_activity_flex(&xml).unwrap();
+        let mut summary = statement.trades.items[0].clone();
+        summary.quantity = summary.quantity.map(|q| q * Decimal::TWO);
+        statement.trades.symbol_summaries.push(summary);
+        statement.cash_transactions.items[0].amount += Decimal::ONE;
+
+        let violations = validate_statement(&statement, &ValidateConfig::default());
+        let paths: Vec<&str> = violations.iter().map(|v| v.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            [
+                "Trades/SymbolSummary[0]",
+                "CashReport/CashReportCurrency[1]",
+                "CashReport/CashReportCurrency[1]",
+            ]
+        );
+        assert_eq!(
+            violations[2].to_string(),
+            "CashReport/CashReportCurrency[1] (USD): endingCash is 150000.50, expected 149000.50 from the last StmtFunds balance"
+        );
+    }
+}
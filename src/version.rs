@@ -1,4 +1,16 @@
 //! FLEX schema version detection
+//!
+//! The types follow version 3 of the FLEX schema. Statements written under
+//! an older version are read through the same parsers: each version lists
+//! the attributes it names differently, and the Activity parsers rename
+//! them to their version 3 names before deserializing, per statement, so an
+//! archive spanning a schema change parses in one call.
+
+use std::borrow::Cow;
+
+use quick_xml::events::attributes::Attribute;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::name::QName;
 
 use crate::error::{ParseError, Result};
 use crate::StatementType;
@@ -6,12 +18,37 @@ use crate::StatementType;
 /// FLEX schema versions supported by this library
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum FlexSchemaVersion {
+    /// FLEX schema version 2
+    V2,
     /// FLEX schema version 3 (current)
     V3,
     /// Unknown or unspecified version (treated as V3)
     Unknown,
 }
 
+impl FlexSchemaVersion {
+    /// Attributes this version names differently from version 3, as
+    /// `(element, name in this version, version 3 name)`
+    pub fn attribute_renames(self) -> &'static [(&'static str, &'static str, &'static str)] {
+        match self {
+            FlexSchemaVersion::V2 => &[
+                ("Trade", "orderID", "ibOrderID"),
+                ("OpenPosition", "accruedInterest", "accruedInt"),
+                ("OptionEAE", "type", "transactionType"),
+            ],
+            FlexSchemaVersion::V3 | FlexSchemaVersion::Unknown => &[],
+        }
+    }
+
+    fn from_attribute(value: &[u8]) -> Self {
+        match value {
+            b"2" => FlexSchemaVersion::V2,
+            b"3" => FlexSchemaVersion::V3,
+            _ => FlexSchemaVersion::Unknown,
+        }
+    }
+}
+
 /// Detect FLEX schema version from XML
 ///
 /// Examines the XML to identify the FLEX schema version attribute.
@@ -38,15 +75,20 @@ pub enum FlexSchemaVersion {
 /// let version = detect_version(xml).unwrap();
 /// ```
 pub fn detect_version(xml: &str) -> Result<FlexSchemaVersion> {
-    // Look for version attribute in FlexQueryResponse or FlexStatement
-    if let Some(pos) = xml.find("version=\"") {
-        let version_start = pos + 9; // length of "version=\""
-        if let Some(version_end) = xml[version_start..].find('"') {
-            let version_str = &xml[version_start..version_start + version_end];
-            return match version_str {
-                "3" => Ok(FlexSchemaVersion::V3),
-                _ => Ok(FlexSchemaVersion::Unknown),
-            };
+    // The version attribute is on FlexQueryResponse or FlexStatement; the
+    // XML declaration's `version` is the XML version
+    let mut reader = quick_xml::Reader::from_str(xml);
+    loop {
+        let start = match reader.read_event() {
+            Ok(Event::Start(start) | Event::Empty(start)) => start,
+            Ok(Event::Eof) | Err(_) => break,
+            Ok(_) => continue,
+        };
+        if let Some(version) = version_attribute(&start) {
+            return Ok(version);
+        }
+        if start.name().as_ref() == b"FlexStatement" {
+            break;
         }
     }
 
@@ -54,6 +96,101 @@ pub fn detect_version(xml: &str) -> Result<FlexSchemaVersion> {
     Ok(FlexSchemaVersion::V3)
 }
 
+fn version_attribute(start: &BytesStart<'_>) -> Option<FlexSchemaVersion> {
+    start
+        .attributes()
+        .flatten()
+        .find(|attribute| attribute.key.as_ref() == b"version")
+        .map(|attribute| FlexSchemaVersion::from_attribute(&attribute.value))
+}
+
+/// Rename attributes of statements written under an older schema to their
+/// version 3 names
+///
+/// A `version` on a `FlexStatement` applies to its rows, overriding one on
+/// `FlexQueryResponse`. Returns `xml` unchanged when no statement uses a
+/// version with renames.
+pub(crate) fn to_current_schema(xml: &str) -> Result<Cow<'_, str>> {
+    if !names_older_version(xml) {
+        return Ok(Cow::Borrowed(xml));
+    }
+    let xml_error = |e: &dyn std::fmt::Display| ParseError::XmlError {
+        message: format!("Failed to parse FLEX XML: {}", e),
+        location: None,
+    };
+    let mut reader = quick_xml::Reader::from_str(xml);
+    let mut writer = quick_xml::Writer::new(Vec::with_capacity(xml.len()));
+    let mut document = FlexSchemaVersion::V3;
+    let mut statement = None;
+    loop {
+        let event = reader.read_event().map_err(|e| xml_error(&e))?;
+        let event = match event {
+            Event::Start(start) => Event::Start(rename(start, &mut document, &mut statement)),
+            Event::Empty(start) => Event::Empty(rename(start, &mut document, &mut statement)),
+            Event::End(end) => {
+                if end.name().as_ref() == b"FlexStatement" {
+                    statement = None;
+                }
+                Event::End(end)
+            }
+            Event::Eof => break,
+            other => other,
+        };
+        writer.write_event(event).map_err(|e| xml_error(&e))?;
+    }
+    String::from_utf8(writer.into_inner())
+        .map(Cow::Owned)
+        .map_err(|e| xml_error(&e))
+}
+
+/// Returns true if a `version` attribute names a version with renames
+fn names_older_version(xml: &str) -> bool {
+    xml.match_indices("version=\"").any(|(pos, pattern)| {
+        let value = &xml[pos + pattern.len()..];
+        let value = &value[..value.find('"').unwrap_or(0)];
+        !FlexSchemaVersion::from_attribute(value.as_bytes())
+            .attribute_renames()
+            .is_empty()
+    })
+}
+
+/// `start` with the attributes its schema version names differently given
+/// their version 3 names, updating the versions in effect as
+/// `FlexQueryResponse` and `FlexStatement` tags go by
+pub(crate) fn rename<'a>(
+    start: BytesStart<'a>,
+    document: &mut FlexSchemaVersion,
+    statement: &mut Option<FlexSchemaVersion>,
+) -> BytesStart<'a> {
+    match start.name().as_ref() {
+        b"FlexQueryResponse" => *document = version_attribute(&start).unwrap_or(*document),
+        b"FlexStatement" => *statement = version_attribute(&start),
+        _ => {}
+    }
+    let renames: Vec<_> = statement
+        .unwrap_or(*document)
+        .attribute_renames()
+        .iter()
+        .filter(|(element, _, _)| element.as_bytes() == start.name().as_ref())
+        .collect();
+    if renames.is_empty() {
+        return start;
+    }
+    let mut renamed = start.to_owned();
+    renamed.clear_attributes();
+    for attribute in start.attributes().flatten() {
+        let name = renames
+            .iter()
+            .find(|(_, old, _)| old.as_bytes() == attribute.key.as_ref())
+            .map(|(_, _, new)| new.as_bytes());
+        renamed.push_attribute(Attribute {
+            key: QName(name.unwrap_or(attribute.key.as_ref())),
+            value: attribute.value,
+        });
+    }
+    renamed
+}
+
 /// Detect FLEX statement type from XML
 ///
 /// Examines the XML structure to determine whether it's an Activity FLEX
@@ -125,6 +262,38 @@ mod tests {
         assert_eq!(version, FlexSchemaVersion::V3);
     }
 
+    #[test]
+    fn test_detect_version_skips_xml_declaration() {
+        let xml = r#"<?xml version="1.0"?><FlexQueryResponse><FlexStatements><FlexStatement version="2">"#;
+        assert_eq!(detect_version(xml).unwrap(), FlexSchemaVersion::V2);
+    }
+
+    #[test]
+    fn test_older_statements_parse_with_current_names() {
+        let current = include_str!("../tests/fixtures/activity_daily_portfolio.xml");
+        let v2 = current
+            .replace("<FlexStatement ", "<FlexStatement version=\"2\" ")
+            .replace(" ibOrderID=", " orderID=");
+        let renamed = to_current_schema(&v2).unwrap();
+        assert!(renamed.contains(" ibOrderID=\"") && !renamed.contains(" orderID=\""));
+
+        let expected = crate::parse_activity_flex_all(current).unwrap();
+        let parsed = crate::parse_activity_flex_all(&v2).unwrap();
+        let order_ids = |statements: &[crate::ActivityFlexStatement]| -> Vec<_> {
+            statements[0]
+                .trades
+                .iter()
+                .map(|t| t.ib_order_id.clone())
+                .collect()
+        };
+        assert!(order_ids(&parsed)[0].is_some());
+        assert_eq!(order_ids(&parsed), order_ids(&expected));
+
+        // Only version 2 statements are renamed
+        let v3 = v2.replace("version=\"2\"", "version=\"3\"");
+        assert!(matches!(to_current_schema(&v3).unwrap(), Cow::Borrowed(_)));
+    }
+
     #[test]
     fn test_detect_version_unknown() {
         let xml = r#"<FlexQueryResponse version="4" queryName="test">"#;
@@ -0,0 +1,102 @@
+//! WebAssembly bindings (requires the `wasm` feature)
+//!
+//! Exposes the parsers to JavaScript through `wasm-bindgen`, so statements
+//! can be parsed in the browser without sending them to a server. Each
+//! function takes FLEX XML and returns the JSON of [`export::json`], which
+//! `JSON.parse` turns into plain objects: snake_case field names, ISO 8601
+//! dates, and amounts as strings, so no precision is lost. Parse errors are
+//! thrown as `Error`s with the parser's message.
+//!
+//! | JavaScript | Rust |
+//! |---|---|
+//! | `parseActivityFlex(xml)` | [`parse_activity_flex`](crate::parse_activity_flex), one statement |
+//! | `parseActivityFlexAll(xml)` | [`parse_activity_flex_all`](crate::parse_activity_flex_all), an array |
+//! | `parseTradeConfirmation(xml)` | [`parse_trade_confirmation`](crate::parse_trade_confirmation) |
+//! | `detectStatementType(xml)` | `"activity"` or `"trade_confirmation"` |
+//!
+//! The bindings are exported from any `cdylib` that links the crate, for
+//! example one built with `wasm-pack build --target web`:
+//!
+//! ```js
+//! import init, { parseActivityFlex } from "./pkg/my_portfolio.js";
+//!
+//! await init();
+//! const statement = JSON.parse(parseActivityFlex(xml));
+//! console.log(statement.account_id, statement.open_positions.length);
+//! ```
+//!
+//! [`export::json`]: crate::export::json
+
+use wasm_bindgen::prelude::*;
+
+use crate::export::json;
+use crate::{Result, StatementType};
+
+/// Parse an Activity FLEX statement to JSON
+#[wasm_bindgen(js_name = parseActivityFlex)]
+pub fn parse_activity_flex(xml: &str) -> std::result::Result<String, JsError> {
+    activity_json(xml).map_err(js_error)
+}
+
+/// Parse every statement of an Activity FLEX document to a JSON array
+#[wasm_bindgen(js_name = parseActivityFlexAll)]
+pub fn parse_activity_flex_all(xml: &str) -> std::result::Result<String, JsError> {
+    activity_all_json(xml).map_err(js_error)
+}
+
+/// Parse a Trade Confirmation FLEX statement to JSON
+#[wasm_bindgen(js_name = parseTradeConfirmation)]
+pub fn parse_trade_confirmation(xml: &str) -> std::result::Result<String, JsError> {
+    trade_confirmation_json(xml).map_err(js_error)
+}
+
+/// `"activity"` or `"trade_confirmation"`, depending on the statement type
+#[wasm_bindgen(js_name = detectStatementType)]
+pub fn detect_statement_type(xml: &str) -> std::result::Result<String, JsError> {
+    statement_type(xml).map_err(js_error)
+}
+
+fn activity_json(xml: &str) -> Result<String> {
+    json::to_string(&crate::parse_activity_flex(xml)?)
+}
+
+fn activity_all_json(xml: &str) -> Result<String> {
+    json::to_string(&crate::parse_activity_flex_all(xml)?)
+}
+
+fn trade_confirmation_json(xml: &str) -> Result<String> {
+    json::to_string(&crate::parse_trade_confirmation(xml)?)
+}
+
+fn statement_type(xml: &str) -> Result<String> {
+    Ok(match crate::detect_statement_type(xml)? {
+        StatementType::Activity => "activity",
+        StatementType::TradeConfirmation => "trade_confirmation",
+    }
+    .to_string())
+}
+
+fn js_error(err: crate::ParseError) -> JsError {
+    JsError::new(&err.to_string())
+}
+
+// The exported functions call into JavaScript only on errors, so the
+// Rust halves are tested natively
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_bindings() {
+        let xml = include_str!("../tests/fixtures/activity_minimal.xml");
+        let value: serde_json::Value = serde_json::from_str(&activity_json(xml).unwrap()).unwrap();
+        assert_eq!(value["account_id"], "U1234567");
+        assert!(value["open_positions"].is_array());
+
+        let all: serde_json::Value =
+            serde_json::from_str(&activity_all_json(xml).unwrap()).unwrap();
+        assert_eq!(all.as_array().unwrap().len(), 1);
+        assert_eq!(statement_type(xml).unwrap(), "activity");
+        assert!(activity_json("<FlexQueryResponse").is_err());
+    }
+}
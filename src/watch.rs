@@ -0,0 +1,258 @@
+//! Directory watching (requires the `watch` feature)
+//!
+//! A [`DirectoryWatcher`] scans a directory for FLEX XML files, parses the
+//! new and changed ones, and hands their statements to a callback, for
+//! pipelines that drop each night's download into a folder:
+//!
+//! - Files are `.xml` files directly in the directory, read once their
+//!   modification time is [`WatchOptions::settle`] old, so files still
+//!   being written are left for a later scan. A file is read again only if
+//!   its size or modification time changes.
+//! - Statements are deduplicated by account, period, and `whenGenerated`:
+//!   a statement already delivered, from this file or another, is dropped,
+//!   and a file with no new statements does not reach the callback.
+//! - With [`WatchOptions::state_file`], delivered statements are
+//!   remembered across restarts.
+//!
+//! Watching polls every [`WatchOptions::interval`], so it works the same on
+//! every platform and on network drives, without a file-notification
+//! dependency.
+//!
+//! # Example
+//! ```no_run
+//! use ib_flex::watch::{DirectoryWatcher, WatchOptions};
+//!
+//! let options = WatchOptions {
+//!     state_file: Some("flex/.seen".into()),
+//!     ..Default::default()
+//! };
+//! let mut watcher = DirectoryWatcher::new("flex", options)?;
+//! watcher.run(|path, statements| match statements {
+//!     Ok(statements) => {
+//!         for statement in statements {
+//!             println!("{}: {} trades", path.display(), statement.trades.len());
+//!         }
+//!     }
+//!     Err(err) => eprintln!("{}: {}", path.display(), err),
+//! })?;
+//! # Ok::<(), ib_flex::ParseError>(())
+//! ```
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::types::ActivityFlexStatement;
+use crate::Result;
+
+/// Settings for a [`DirectoryWatcher`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WatchOptions {
+    /// Time between scans in [`DirectoryWatcher::run`] (default 5 seconds)
+    pub interval: Duration,
+    /// How long a file must go unmodified before it is read (default 2
+    /// seconds)
+    pub settle: Duration,
+    /// File recording delivered statements, one hash per line, read when
+    /// the watcher is created and appended to as statements are delivered
+    /// (default `None`: remembered in memory only)
+    pub state_file: Option<PathBuf>,
+}
+
+impl Default for WatchOptions {
+    fn default() -> Self {
+        WatchOptions {
+            interval: Duration::from_secs(5),
+            settle: Duration::from_secs(2),
+            state_file: None,
+        }
+    }
+}
+
+/// Watches a directory for new FLEX statements
+#[derive(Debug)]
+pub struct DirectoryWatcher {
+    dir: PathBuf,
+    options: WatchOptions,
+    /// Size and modification time of each file when it was last read
+    files: HashMap<PathBuf, (u64, SystemTime)>,
+    /// Hashes of the statements delivered
+    seen: HashSet<u64>,
+}
+
+impl DirectoryWatcher {
+    /// Watch `dir`, loading the state file if there is one
+    pub fn new(dir: impl Into<PathBuf>, options: WatchOptions) -> Result<Self> {
+        let mut seen = HashSet::new();
+        if let Some(state) = &options.state_file {
+            match fs::read_to_string(state) {
+                Ok(text) => seen.extend(
+                    text.lines()
+                        .filter_map(|line| u64::from_str_radix(line.trim(), 16).ok()),
+                ),
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+                Err(err) => return Err(err.into()),
+            }
+        }
+        Ok(DirectoryWatcher {
+            dir: dir.into(),
+            options,
+            files: HashMap::new(),
+            seen,
+        })
+    }
+
+    /// Scan the directory once
+    ///
+    /// `on_file` is called, in file name order, with each new or changed
+    /// file's new statements, or with the error that stopped it from being
+    /// parsed. Returns the number of statements delivered.
+    pub fn poll<F>(&mut self, mut on_file: F) -> Result<usize>
+    where
+        F: FnMut(&Path, Result<Vec<ActivityFlexStatement>>),
+    {
+        let now = SystemTime::now();
+        let mut paths = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            let is_xml = path
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("xml"));
+            let metadata = entry.metadata()?;
+            if !is_xml || !metadata.is_file() {
+                continue;
+            }
+            let modified = metadata.modified()?;
+            let settled = now
+                .duration_since(modified)
+                .is_ok_and(|age| age >= self.options.settle);
+            let stamp = (metadata.len(), modified);
+            if settled && self.files.get(&path) != Some(&stamp) {
+                paths.push((path, stamp));
+            }
+        }
+        paths.sort();
+
+        let mut delivered = 0;
+        for (path, stamp) in paths {
+            self.files.insert(path.clone(), stamp);
+            let statements = fs::read_to_string(&path)
+                .map_err(Into::into)
+                .and_then(|xml| crate::parse_activity_flex_all(&xml));
+            let statements = match statements {
+                Ok(statements) => statements,
+                Err(err) => {
+                    on_file(&path, Err(err));
+                    continue;
+                }
+            };
+            let mut new = Vec::new();
+            let mut hashes = Vec::new();
+            for statement in statements {
+                let hash = statement_hash(&statement);
+                if self.seen.insert(hash) {
+                    hashes.push(hash);
+                    new.push(statement);
+                }
+            }
+            if new.is_empty() {
+                continue;
+            }
+            self.remember(&hashes)?;
+            delivered += new.len();
+            on_file(&path, Ok(new));
+        }
+        Ok(delivered)
+    }
+
+    /// Scan the directory every [`WatchOptions::interval`], forever
+    ///
+    /// Returns only if the directory cannot be read.
+    pub fn run<F>(&mut self, mut on_file: F) -> Result<()>
+    where
+        F: FnMut(&Path, Result<Vec<ActivityFlexStatement>>),
+    {
+        loop {
+            self.poll(&mut on_file)?;
+            std::thread::sleep(self.options.interval);
+        }
+    }
+
+    /// Append delivered hashes to the state file
+    fn remember(&self, hashes: &[u64]) -> Result<()> {
+        let Some(state) = &self.options.state_file else {
+            return Ok(());
+        };
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(state)?;
+        for hash in hashes {
+            writeln!(file, "{:016x}", hash)?;
+        }
+        Ok(())
+    }
+}
+
+/// Identity of a statement: its account, period, and generation time
+fn statement_hash(statement: &ActivityFlexStatement) -> u64 {
+    let from = statement.from_date.to_string();
+    let to = statement.to_date.to_string();
+    crate::anonymize::fnv1a([
+        statement.account_id.as_str().as_bytes(),
+        &[0],
+        from.as_bytes(),
+        &[0],
+        to.as_bytes(),
+        &[0],
+        statement.when_generated.as_bytes(),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_files_are_delivered_once() {
+        let dir = std::env::temp_dir().join(format!("ib-flex-watch-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let options = WatchOptions {
+            settle: Duration::ZERO,
+            state_file: Some(dir.join("seen.txt")),
+            ..Default::default()
+        };
+        let mut watcher = DirectoryWatcher::new(&dir, options.clone()).unwrap();
+        let mut files = Vec::new();
+        let mut collect = |path: &Path, statements: Result<Vec<ActivityFlexStatement>>| {
+            let name = path.file_name().unwrap().to_string_lossy().into_owned();
+            files.push((name, statements.map(|s| s.len()).map_err(|e| e.to_string())));
+        };
+
+        let xml = include_str!("../tests/fixtures/activity_minimal.xml");
+        fs::write(dir.join("a.xml"), xml).unwrap();
+        fs::write(dir.join("notes.txt"), "not a statement").unwrap();
+        assert_eq!(watcher.poll(&mut collect).unwrap(), 1);
+        assert_eq!(watcher.poll(&mut collect).unwrap(), 0);
+
+        // The same statement under another name, and a broken file
+        fs::write(dir.join("b.xml"), xml).unwrap();
+        fs::write(dir.join("c.xml"), "<FlexQueryResponse").unwrap();
+        assert_eq!(watcher.poll(&mut collect).unwrap(), 0);
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0], ("a.xml".to_string(), Ok(1)));
+        assert_eq!(files[1].0, "c.xml");
+        assert!(files[1].1.is_err());
+
+        // A restarted watcher remembers what it delivered
+        let mut restarted = DirectoryWatcher::new(&dir, options).unwrap();
+        let mut calls = 0;
+        restarted.poll(|_, _| calls += 1).unwrap();
+        assert_eq!(calls, 1); // only the broken file
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
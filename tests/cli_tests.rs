@@ -0,0 +1,196 @@
+//! Integration tests for the `ib-flex` binary
+
+#![cfg(feature = "cli")]
+
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+fn ib_flex(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_ib-flex"))
+        .args(args)
+        .output()
+        .expect("failed to run ib-flex")
+}
+
+fn stdout(output: &Output) -> String {
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+#[test]
+fn test_parse_counts_sections() {
+    let out = stdout(&ib_flex(&[
+        "parse",
+        "tests/fixtures/activity_daily_portfolio.xml",
+    ]));
+    assert!(out.contains("account U1234567, 2025-01-15 to 2025-01-15"));
+    assert!(out.contains("  trades                    3\n"));
+    assert!(out.contains("  open positions            5\n"));
+
+    let failed = ib_flex(&["parse", "tests/fixtures/missing.xml"]);
+    assert!(!failed.status.success());
+    assert!(
+        String::from_utf8_lossy(&failed.stderr).starts_with("error: tests/fixtures/missing.xml")
+    );
+}
+
+#[test]
+fn test_summary_and_export() {
+    let out = stdout(&ib_flex(&[
+        "summary",
+        "--period",
+        "year",
+        "tests/fixtures/activity_backfill.xml",
+    ]));
+    assert!(out.starts_with("Accounts U1234567, 2025-01-01 to 2025-01-03\n"));
+    assert!(out.contains("\n2025 "));
+
+    let csv = stdout(&ib_flex(&[
+        "export",
+        "--format",
+        "csv",
+        "--section",
+        "positions",
+        "tests/fixtures/activity_backfill.xml",
+    ]));
+    assert!(csv.starts_with("account_id,report_date,"));
+    assert_eq!(csv.lines().count(), 12);
+
+    let json = stdout(&ib_flex(&["export", "tests/fixtures/activity_minimal.xml"]));
+    let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(value["account_id"], "U1234567");
+}
+
+#[test]
+fn test_gains_for_tax_year() {
+    let out = stdout(&ib_flex(&[
+        "gains",
+        "--year",
+        "2025",
+        "tests/fixtures/activity_gains.xml",
+    ]));
+    assert!(out.starts_with("Realized gains for 2025, 2 lots closed\n"));
+    // Long term from the 2024 purchase; the MSFT loss is washed by the rebuy
+    assert!(out.contains("\nAAPL                    USD              0        1500.00        1500.00              0\n"));
+    assert!(out.contains("\nMSFT                    USD              0              0              0            200\n"));
+
+    let csv = stdout(&ib_flex(&[
+        "gains",
+        "--year",
+        "2024",
+        "--format",
+        "csv",
+        "tests/fixtures/activity_gains.xml",
+    ]));
+    assert_eq!(
+        csv,
+        "Description,Date Acquired,Date Sold,Proceeds,Cost Basis,Code,Adjustment,Gain or Loss\r\n\
+         10 AAPL,2024-01-10,2024-06-03,1600,1500.00,,,100.00\r\n"
+    );
+}
+
+#[test]
+fn test_fetch_needs_token() {
+    let failed = ib_flex(&[
+        "fetch",
+        "--query",
+        "123456",
+        "--token-env",
+        "IB_FLEX_CLI_TEST_UNSET_TOKEN",
+    ]);
+    assert!(!failed.status.success());
+    let stderr = String::from_utf8_lossy(&failed.stderr);
+    if cfg!(feature = "api-client") {
+        assert!(stderr.contains("IB_FLEX_CLI_TEST_UNSET_TOKEN is not set"));
+    } else {
+        assert!(stderr.contains("`api-client` feature"));
+    }
+}
+
+#[test]
+fn test_validate_reports_problems() {
+    stdout(&ib_flex(&[
+        "validate",
+        "tests/fixtures/activity_minimal.xml",
+    ]));
+
+    let failed = ib_flex(&[
+        "validate",
+        "--format",
+        "json",
+        "tests/fixtures/activity_backfill.xml",
+    ]);
+    assert!(!failed.status.success());
+    let problems: serde_json::Value = serde_json::from_slice(&failed.stdout).unwrap();
+    let checks: Vec<&str> = problems
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|p| p["check"].as_str().unwrap())
+        .collect();
+    assert!(checks.contains(&"cash") && checks.contains(&"positions"));
+    assert!(String::from_utf8_lossy(&failed.stderr).contains("problems found"));
+
+    // The same execution twice in one statement
+    let xml = std::fs::read_to_string("tests/fixtures/activity_minimal.xml").unwrap();
+    let trade = &xml[xml.find("<Trade ").unwrap()..xml.find("</Trades>").unwrap()];
+    let doubled = xml.replace(trade, &trade.repeat(2));
+    let mut child = Command::new(env!("CARGO_BIN_EXE_ib-flex"))
+        .args(["validate", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(doubled.as_bytes())
+        .unwrap();
+    let failed = child.wait_with_output().unwrap();
+    assert!(!failed.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&failed.stdout),
+        "-: U1234567 2025-01-15: duplicate-id: trade transaction ID 567890123 repeated\n"
+    );
+}
+
+#[test]
+fn test_anonymize() {
+    let xml = stdout(&ib_flex(&[
+        "anonymize",
+        "--scale",
+        "2",
+        "--key",
+        "secret",
+        "tests/fixtures/activity_daily_portfolio.xml",
+    ]));
+    assert!(!xml.contains("U1234567"));
+    assert!(!xml.contains("Test Fund LP") && !xml.contains("test@example.com"));
+
+    let original = ib_flex::parse_activity_flex(include_str!(
+        "../tests/fixtures/activity_daily_portfolio.xml"
+    ))
+    .unwrap();
+    let scrubbed = ib_flex::parse_activity_flex(&xml).unwrap();
+    let options = ib_flex::AnonymizeOptions {
+        key: "secret".to_string(),
+        ..Default::default()
+    };
+    let account = options.account_pseudonym("U1234567");
+    assert_eq!(scrubbed.account_id, account.as_str());
+    assert_eq!(scrubbed.trades.len(), original.trades.len());
+    for (a, b) in original.trades.iter().zip(&scrubbed.trades) {
+        assert_eq!(b.account_id, account.as_str());
+        assert_eq!(b.price, a.price);
+        assert_eq!(
+            b.quantity,
+            a.quantity.map(|q| q * rust_decimal::Decimal::TWO)
+        );
+    }
+}
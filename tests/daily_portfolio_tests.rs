@@ -4,6 +4,7 @@
 //! including positions, trades, cash flows, and multi-currency support.
 
 use ib_flex::parse_activity_flex;
+use ib_flex::types::{CashTransactionType, LongShort};
 use ib_flex::{AssetCategory, BuySell};
 use rust_decimal::Decimal;
 use std::str::FromStr;
@@ -60,13 +61,17 @@ fn test_position_summary() {
     assert_eq!(aapl.mark_price, Decimal::from_str("185.50").unwrap());
     assert_eq!(aapl.position_value, Decimal::from_str("92750.00").unwrap());
     assert_eq!(aapl.currency, "USD");
-    assert_eq!(aapl.side.as_deref(), Some("Long"));
+    assert_eq!(aapl.side, Some(LongShort::Long));
+    assert!(aapl.side_matches_quantity());
+    assert_eq!(aapl.signed_quantity(), Decimal::from(500));
 
     // Verify short TSLA position
     let tsla = positions.iter().find(|p| p.symbol == "TSLA").unwrap();
     assert_eq!(tsla.quantity, Decimal::from(-200));
     assert!(tsla.position_value < Decimal::ZERO);
-    assert_eq!(tsla.side.as_deref(), Some("Short"));
+    assert_eq!(tsla.side, Some(LongShort::Short));
+    assert!(tsla.side_matches_quantity());
+    assert!(tsla.signed_quantity() < Decimal::ZERO);
 
     // Verify Canadian stock with FX
     let ry = positions.iter().find(|p| p.symbol == "RY").unwrap();
@@ -175,6 +180,40 @@ fn test_trade_parsing() {
         Some(Decimal::from_str("511.75").unwrap())
     );
 
+    // Lot timing fields used for wash-sale basis adjustments
+    let ymd_hms = |y, m, d, h| {
+        chrono::NaiveDate::from_ymd_opt(y, m, d)
+            .unwrap()
+            .and_hms_opt(h, 0, 0)
+            .unwrap()
+    };
+    assert_eq!(
+        sell_trade.holding_period_date_time,
+        Some(ymd_hms(2025, 1, 1, 10))
+    );
+    assert_eq!(sell_trade.when_realized, Some(ymd_hms(2025, 1, 15, 14)));
+    assert_eq!(sell_trade.trade_time, Some(ymd_hms(2025, 1, 15, 14)));
+    assert_eq!(sell_trade.when_reopened, None);
+    assert_eq!(sell_trade.open_date_time, Some(ymd_hms(2025, 1, 1, 10)));
+
+    // Venue and order routing
+    assert_eq!(sell_trade.exchange.as_deref(), Some("NASDAQ"));
+    assert_eq!(sell_trade.brokerage_order_id.as_deref(), Some("brok002"));
+    assert_eq!(sell_trade.ext_exec_id.as_deref(), Some("ext002"));
+    assert_eq!(sell_trade.is_api_order, Some(false));
+    assert_eq!(sell_trade.order_reference, None);
+    assert_eq!(sell_trade.clearing_firm_id, None);
+
+    // Price context
+    assert_eq!(
+        sell_trade.close_price,
+        Some(Decimal::from_str("419.50").unwrap())
+    );
+    assert_eq!(sell_trade.accrued_int, Some(Decimal::ZERO));
+    assert_eq!(sell_trade.change_in_price, Some(Decimal::ZERO));
+    assert_eq!(sell_trade.change_in_quantity, Some(Decimal::ZERO));
+    assert_eq!(sell_trade.mtm_vs_close(), sell_trade.mtm_pnl);
+
     // Verify option trade
     let opt_trade = trades
         .iter()
@@ -231,7 +270,7 @@ fn test_cash_transactions() {
     // Dividend
     let dividend = cash
         .iter()
-        .find(|c| c.transaction_type.as_deref() == Some("Dividends"))
+        .find(|c| c.transaction_type == Some(CashTransactionType::Dividends))
         .unwrap();
     assert_eq!(dividend.symbol.as_deref(), Some("AAPL"));
     assert_eq!(dividend.amount, Decimal::from_str("125.00").unwrap());
@@ -239,14 +278,14 @@ fn test_cash_transactions() {
     // Withholding tax
     let tax = cash
         .iter()
-        .find(|c| c.transaction_type.as_deref() == Some("Withholding Tax"))
+        .find(|c| c.transaction_type == Some(CashTransactionType::WithholdingTax))
         .unwrap();
     assert_eq!(tax.amount, Decimal::from_str("-18.75").unwrap());
 
     // Interest
     let interest = cash
         .iter()
-        .find(|c| c.transaction_type.as_deref() == Some("Broker Interest Paid"))
+        .find(|c| c.transaction_type == Some(CashTransactionType::BrokerInterestPaid))
         .unwrap();
     assert!(interest.amount < Decimal::ZERO);
 }
@@ -265,7 +304,7 @@ fn test_cash_flow_summary() {
         .filter(|c| {
             c.transaction_type
                 .as_ref()
-                .map(|t| t.contains("Dividend"))
+                .map(|t| t.is_dividend())
                 .unwrap_or(false)
         })
         .map(|c| c.amount)
@@ -278,7 +317,7 @@ fn test_cash_flow_summary() {
         .filter(|c| {
             c.transaction_type
                 .as_ref()
-                .map(|t| t.contains("Tax"))
+                .map(|t| t.is_withholding())
                 .unwrap_or(false)
         })
         .map(|c| c.amount)
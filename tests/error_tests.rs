@@ -208,3 +208,75 @@ fn test_very_large_number() {
     // depending on Decimal limits
     let _ = result; // Just test that it doesn't panic
 }
+
+#[test]
+fn test_truncated_and_corrupted_fixtures_fail_gracefully() {
+    // Every failure must come back as a ParseError; a panic fails the test
+    let mut fixtures: Vec<_> = std::fs::read_dir("tests/fixtures")
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "xml"))
+        .collect();
+    fixtures.sort();
+
+    for path in &fixtures {
+        let xml = std::fs::read_to_string(path).unwrap();
+        let end = xml.trim_end().len();
+        for len in (0..end)
+            .step_by(97)
+            .filter(|&len| xml.is_char_boundary(len))
+        {
+            assert!(
+                ib_flex::parse_activity_flex_all(&xml[..len]).is_err(),
+                "{} truncated to {} bytes parsed",
+                path.display(),
+                len
+            );
+            let _ = ib_flex::parse_trade_confirmation(&xml[..len]);
+            let _ = ib_flex::parsers::parse_activity_flex_compact(&xml[..len]);
+        }
+    }
+
+    // Replace attribute values one at a time with values no field accepts,
+    // in the seed corpus's short single-section statements to keep this quick
+    let xml = include_str!("fixtures/activity_daily_portfolio.xml");
+    let corrupt = [
+        "1e400",
+        "99999999999999999999999999999999",
+        "20251340;256161",
+    ];
+    let corpus = ib_flex::parsers::untrusted::seed_corpus(xml);
+    for input in corpus.iter().filter(|input| input.len() < 2048) {
+        let mut rest = 0;
+        while let Some(offset) = input[rest..].find("=\"") {
+            let start = rest + offset + 2;
+            let len = input[start..].find('"').unwrap();
+            for value in corrupt {
+                let corrupted = format!("{}{}{}", &input[..start], value, &input[start + len..]);
+                let _ = ib_flex::parse_activity_flex_all(&corrupted);
+            }
+            rest = start + len;
+        }
+    }
+
+    // Same for the CSV formats, one field at a time
+    for csv in [
+        include_str!("fixtures/activity_statement.csv"),
+        include_str!("fixtures/tws_trades.csv"),
+    ] {
+        let fields: Vec<&str> = csv.split(',').collect();
+        for i in 0..fields.len() {
+            for value in corrupt {
+                let mut corrupted = fields.clone();
+                corrupted[i] = value;
+                let corrupted = corrupted.join(",");
+                let _ = ib_flex::parse_activity_statement_csv(&corrupted);
+                let _ = ib_flex::parse_tws_trades_csv(&corrupted);
+            }
+        }
+        for len in (0..csv.len()).filter(|&len| csv.is_char_boundary(len)) {
+            let _ = ib_flex::parse_activity_statement_csv(&csv[..len]);
+            let _ = ib_flex::parse_tws_trades_csv(&csv[..len]);
+        }
+    }
+}
@@ -1,4 +1,5 @@
 use ib_flex::parse_activity_flex;
+use ib_flex::types::{DeliveredReceived, InOut, OptionAction, ToFrom, TransferType};
 use rust_decimal::Decimal;
 use std::str::FromStr;
 
@@ -141,6 +142,58 @@ fn test_option_eae() {
     assert_eq!(eae.quantity, Decimal::from_str("1").unwrap());
     assert_eq!(eae.strike, Some(Decimal::from_str("145.00").unwrap()));
     assert_eq!(eae.underlying_symbol.as_deref(), Some("AAPL"));
+    assert_eq!(eae.transaction_type, Some(OptionAction::Expiration));
+}
+
+#[test]
+fn test_option_eae_related_trades() {
+    let xml = r#"<FlexQueryResponse queryName="Test" type="AF">
+        <FlexStatements count="1">
+            <FlexStatement accountId="U1234567" fromDate="2025-01-01"
+                           toDate="2025-01-31" whenGenerated="2025-01-31;150000">
+                <Trades>
+                    <Trade accountId="U1234567" conid="700001" symbol="AAPL  250117P00150000"
+                           assetCategory="OPT" tradeDate="2025-01-17" quantity="1"
+                           currency="USD" buySell="BUY" tradeID="9001" notes="A" />
+                    <Trade accountId="U1234567" conid="265598" symbol="AAPL"
+                           assetCategory="STK" tradeDate="2025-01-17" quantity="100"
+                           currency="USD" buySell="BUY" tradeID="9002" notes="A" />
+                    <Trade accountId="U1234567" conid="265598" symbol="AAPL"
+                           assetCategory="STK" tradeDate="2025-01-10" quantity="50"
+                           currency="USD" buySell="BUY" tradeID="8000" />
+                    <Trade accountId="U1234567" conid="700002" symbol="MSFT  250117C00450000"
+                           assetCategory="OPT" tradeDate="2025-01-17" quantity="1"
+                           currency="USD" buySell="BUY" tradeID="9003" notes="Ep" />
+                </Trades>
+                <OptionEAE>
+                    <OptionEAE accountId="U1234567" transactionType="Assignment"
+                               date="2025-01-17" conid="700001" symbol="AAPL  250117P00150000"
+                               underlyingConid="265598" underlyingSymbol="AAPL" quantity="1" />
+                    <OptionEAE accountId="U1234567" transactionType="Expiration"
+                               date="2025-01-17" conid="700002" symbol="MSFT  250117C00450000"
+                               underlyingConid="272093" quantity="1" tradeID="9003" />
+                </OptionEAE>
+            </FlexStatement>
+        </FlexStatements>
+    </FlexQueryResponse>"#;
+
+    let statement = parse_activity_flex(xml).expect("Failed to parse");
+    let trades = &statement.trades.items;
+
+    let assignment = &statement.option_eae.items[0];
+    assert_eq!(assignment.transaction_type, Some(OptionAction::Assignment));
+    let linked: Vec<_> = assignment
+        .related_trades(trades)
+        .iter()
+        .map(|t| t.trade_id.as_deref().unwrap())
+        .collect();
+    assert_eq!(linked, vec!["9001", "9002"]);
+
+    let expiration = &statement.option_eae.items[1];
+    assert_eq!(expiration.transaction_type, Some(OptionAction::Expiration));
+    let linked = expiration.related_trades(trades);
+    assert_eq!(linked.len(), 1);
+    assert_eq!(linked[0].trade_id.as_deref(), Some("9003"));
 }
 
 #[test]
@@ -163,6 +216,44 @@ fn test_fx_transactions() {
     );
 }
 
+#[test]
+fn test_fx_lots_nested_in_fx_positions() {
+    let xml = include_str!("fixtures/activity_extended.xml");
+    let statement = parse_activity_flex(xml).expect("Failed to parse");
+
+    assert_eq!(statement.fx_lots.items.len(), 2);
+    let eur = &statement.fx_lots.items[0];
+    assert_eq!(eur.fx_currency.as_deref(), Some("EUR"));
+    assert_eq!(eur.functional_currency.as_deref(), Some("USD"));
+    assert_eq!(eur.quantity, Some(Decimal::from_str("920.50").unwrap()));
+    assert_eq!(eur.unrealized_pl, Some(Decimal::from_str("7.92").unwrap()));
+
+    let gbp = &statement.fx_lots.items[1];
+    assert_eq!(gbp.fx_currency.as_deref(), Some("GBP"));
+    assert_eq!(gbp.unrealized_pl, Some(Decimal::from_str("-2.50").unwrap()));
+}
+
+#[test]
+fn test_fx_lots_top_level_section() {
+    let xml = r#"<FlexQueryResponse queryName="Test" type="AF">
+        <FlexStatements count="1">
+            <FlexStatement accountId="U1234567" fromDate="2025-01-01"
+                           toDate="2025-01-31" whenGenerated="2025-01-31;150000">
+                <FxLots>
+                    <FxLot accountId="U1234567" fxCurrency="JPY" quantity="100000" />
+                </FxLots>
+            </FlexStatement>
+        </FlexStatements>
+    </FlexQueryResponse>"#;
+
+    let statement = parse_activity_flex(xml).expect("Failed to parse");
+    assert_eq!(statement.fx_lots.items.len(), 1);
+    assert_eq!(
+        statement.fx_lots.items[0].fx_currency.as_deref(),
+        Some("JPY")
+    );
+}
+
 #[test]
 fn test_change_in_dividend_accruals() {
     let xml = include_str!("fixtures/activity_extended.xml");
@@ -238,8 +329,99 @@ fn test_transfers() {
     assert_eq!(transfer.account_id, "U1234567");
     assert_eq!(transfer.symbol, "TSLA");
     assert_eq!(transfer.quantity, Decimal::from_str("25").unwrap());
-    assert_eq!(transfer.direction.as_deref(), Some("IN"));
+    assert_eq!(transfer.direction, Some(InOut::IN));
+    assert_eq!(transfer.transfer_type, Some(TransferType::ACATS));
     assert_eq!(transfer.date.to_string(), "2025-01-05");
+
+    // ACATS counterparty details
+    assert_eq!(transfer.company.as_deref(), Some("Other Brokerage LLC"));
+    assert_eq!(transfer.account.as_deref(), Some("987654321"));
+    assert_eq!(transfer.account_name.as_deref(), Some("John Doe"));
+    assert_eq!(transfer.delivering_broker.as_deref(), Some("0161"));
+}
+
+#[test]
+fn test_deposits_withdrawals() {
+    let xml = include_str!("fixtures/activity_extended.xml");
+    let statement = parse_activity_flex(xml).expect("Failed to parse");
+
+    assert_eq!(statement.deposits_withdrawals.items.len(), 2);
+
+    let deposit = &statement.deposits_withdrawals.items[0];
+    assert_eq!(deposit.transaction_id.as_deref(), Some("3001"));
+    assert_eq!(deposit.amount, Decimal::from_str("5000.00").unwrap());
+    assert_eq!(deposit.method.as_deref(), Some("ACH"));
+    assert_eq!(deposit.institution.as_deref(), Some("First National Bank"));
+    assert_eq!(deposit.bank_account.as_deref(), Some("****6789"));
+    assert_eq!(deposit.date.unwrap().to_string(), "2025-01-03");
+    assert_eq!(deposit.settle_date.unwrap().to_string(), "2025-01-06");
+
+    let withdrawal = &statement.deposits_withdrawals.items[1];
+    assert_eq!(withdrawal.amount, Decimal::from_str("-2000.00").unwrap());
+    assert_eq!(withdrawal.method.as_deref(), Some("Wire"));
+    assert!(withdrawal.fx_rate_to_base.is_none());
+}
+
+#[test]
+fn test_client_fees_advisor_and_reimbursements() {
+    let xml = include_str!("fixtures/activity_extended.xml");
+    let statement = parse_activity_flex(xml).expect("Failed to parse");
+
+    let fees = &statement.client_fees;
+    assert_eq!(fees.items.len(), 3);
+
+    let advisor: Vec<_> = fees.advisor_fees().collect();
+    assert_eq!(advisor.len(), 1);
+    assert_eq!(advisor[0].fee_type.as_deref(), Some("Advisor Fee"));
+    assert_eq!(advisor[0].net, Some(Decimal::from_str("-250.00").unwrap()));
+    assert_eq!(advisor[0].level_of_detail.as_deref(), Some("ADVISOR_FEE"));
+
+    let reimbursements: Vec<_> = fees.reimbursements().collect();
+    assert_eq!(reimbursements.len(), 1);
+    assert_eq!(
+        reimbursements[0].net_in_base,
+        Some(Decimal::from_str("25.00").unwrap())
+    );
+
+    let markup = &fees.items[2];
+    assert!(!markup.is_advisor_fee());
+    assert!(!markup.is_reimbursement());
+    assert_eq!(markup.trade_id.as_deref(), Some("5001"));
+    assert_eq!(markup.exec_id.as_deref(), Some("00012345.001"));
+}
+
+#[test]
+fn test_unsettled_transfers() {
+    let xml = include_str!("fixtures/activity_extended.xml");
+    let statement = parse_activity_flex(xml).expect("Failed to parse");
+
+    assert_eq!(statement.unsettled_transfers.items.len(), 1);
+    let transfer = &statement.unsettled_transfers.items[0];
+
+    assert_eq!(transfer.symbol.as_deref(), Some("MSFT"));
+    assert_eq!(transfer.direction, Some(ToFrom::To));
+    assert_eq!(transfer.transfer_type, Some(TransferType::INTERNAL));
+    assert_eq!(transfer.target_account.as_deref(), Some("U7654321"));
+    assert_eq!(transfer.expected_date.unwrap().to_string(), "2025-02-03");
+}
+
+#[test]
+fn test_trade_transfers() {
+    let xml = include_str!("fixtures/activity_extended.xml");
+    let statement = parse_activity_flex(xml).expect("Failed to parse");
+
+    assert_eq!(statement.trade_transfers.items.len(), 1);
+    let transfer = &statement.trade_transfers.items[0];
+
+    assert_eq!(transfer.symbol.as_deref(), Some("NVDA"));
+    assert_eq!(transfer.transfer_type, Some(TransferType::DVP));
+    assert_eq!(transfer.direction, Some(InOut::IN));
+    assert_eq!(
+        transfer.delivered_received,
+        Some(DeliveredReceived::Received)
+    );
+    assert_eq!(transfer.broker_name.as_deref(), Some("Away Broker Inc"));
+    assert_eq!(transfer.broker_account.as_deref(), Some("AB-1234"));
 }
 
 #[test]
@@ -270,8 +452,10 @@ fn test_extended_types_default_to_empty() {
     assert_eq!(statement.trade_confirms.items.len(), 0);
     assert_eq!(statement.option_eae.items.len(), 0);
     assert_eq!(statement.fx_transactions.items.len(), 0);
+    assert_eq!(statement.fx_lots.items.len(), 0);
     assert_eq!(statement.change_in_dividend_accruals.items.len(), 0);
     assert_eq!(statement.open_dividend_accruals.items.len(), 0);
     assert_eq!(statement.interest_accruals.items.len(), 0);
     assert_eq!(statement.transfers.items.len(), 0);
+    assert_eq!(statement.deposits_withdrawals.items.len(), 0);
 }
@@ -1,5 +1,8 @@
 //! Integration tests for ib-flex parser
 
+use ib_flex::types::{
+    AccountKind, CashTransactionType, Conid, LevelOfDetail, Money, TransactionCode,
+};
 use ib_flex::{parse_activity_flex, AssetCategory, BuySell, OpenClose, PutCall};
 
 #[test]
@@ -49,6 +52,49 @@ fn test_parse_symbol_summary_with_multi_settlement_date() {
 
     assert_eq!(statement.trades.items.len(), 1);
     assert_eq!(trade.symbol, "AAPL");
+    assert_eq!(statement.trades.symbol_summaries.len(), 1);
+    assert_eq!(statement.trades.symbol_summaries[0].symbol, "USD.TWD");
+    assert_eq!(statement.trades.symbol_summaries[0].settle_date, None);
+    assert_eq!(
+        trade.trade_time,
+        chrono::NaiveDate::from_ymd_opt(2025, 1, 15)
+            .unwrap()
+            .and_hms_opt(9, 30, 15)
+    );
+}
+
+#[test]
+fn test_unparseable_date_time_keeps_raw() {
+    let xml = include_str!("fixtures/activity_minimal.xml").replace(
+        "dateTime=\"2025-01-15;093015\"",
+        "dateTime=\"15 Jan 2025 9:30 ET\"",
+    );
+    let statement = parse_activity_flex(&xml).unwrap();
+    let trade = &statement.trades.items[0];
+
+    assert_eq!(trade.trade_time, None);
+    assert_eq!(trade.trade_time_raw.as_deref(), Some("15 Jan 2025 9:30 ET"));
+    assert!(statement
+        .to_xml()
+        .unwrap()
+        .contains("dateTime=\"15 Jan 2025 9:30 ET\""));
+
+    // A time set after parsing replaces the text when written
+    let mut trade = trade.clone();
+    trade.trade_time = chrono::NaiveDate::from_ymd_opt(2025, 1, 15)
+        .unwrap()
+        .and_hms_opt(9, 30, 0);
+    let mut statement = statement.clone();
+    statement.trades.items = vec![trade];
+    let reparsed = parse_activity_flex(&statement.to_xml().unwrap()).unwrap();
+    assert_eq!(
+        reparsed.trades.items[0].trade_time,
+        statement.trades.items[0].trade_time
+    );
+    assert_eq!(
+        reparsed.trades.items[0].trade_time_raw.as_deref(),
+        Some("20250115;093000")
+    );
 }
 
 #[test]
@@ -99,6 +145,50 @@ fn test_securities_info_parsing() {
     }
 }
 
+#[test]
+fn test_level_of_detail_parsing() {
+    let xml = include_str!("fixtures/activity_bonds.xml");
+    let statement = parse_activity_flex(xml).unwrap();
+    for pos in &statement.positions.items {
+        assert_eq!(pos.level_of_detail, Some(LevelOfDetail::Lot));
+    }
+
+    let xml = include_str!("fixtures/activity_futures.xml");
+    let statement = parse_activity_flex(xml).unwrap();
+    for pos in &statement.positions.items {
+        assert_eq!(pos.level_of_detail, Some(LevelOfDetail::Contract));
+    }
+
+    let xml = include_str!("fixtures/activity_daily_portfolio.xml");
+    let statement = parse_activity_flex(xml).unwrap();
+    let executions = statement
+        .trades
+        .items
+        .iter()
+        .filter(|t| t.level_of_detail == Some(LevelOfDetail::Execution))
+        .count();
+    assert!(executions > 0);
+
+    let xml = r#"<FlexQueryResponse queryName="Test" type="AF">
+        <FlexStatements count="1">
+            <FlexStatement accountId="U1234567" fromDate="2025-01-01"
+                           toDate="2025-01-31" whenGenerated="2025-01-31;150000">
+                <CashTransactions>
+                    <CashTransaction accountId="U1234567" currency="USD" amount="37.50"
+                                     type="Dividends" levelOfDetail="DETAIL" />
+                    <CashTransaction accountId="U1234567" currency="USD" amount="37.50"
+                                     type="Dividends" levelOfDetail="SUMMARY" />
+                </CashTransactions>
+            </FlexStatement>
+        </FlexStatements>
+    </FlexQueryResponse>"#;
+    let statement = parse_activity_flex(xml).unwrap();
+    let cash = &statement.cash_transactions.items;
+    assert_eq!(cash[0].level_of_detail, Some(LevelOfDetail::Detail));
+    assert_eq!(cash[1].level_of_detail, Some(LevelOfDetail::Summary));
+    assert!(cash[1].level_of_detail.as_ref().unwrap().is_summary());
+}
+
 #[test]
 fn test_commission_calculation() {
     let xml = include_str!("fixtures/activity_minimal.xml");
@@ -134,6 +224,11 @@ fn test_account_id_extraction() {
 
     assert!(!statement.account_id.is_empty());
     assert_eq!(statement.account_id, "U1234567");
+    assert_eq!(statement.account_id.kind(), AccountKind::Individual);
+    assert!(!statement.account_id.is_paper());
+    for trade in &statement.trades.items {
+        assert_eq!(trade.account_id, statement.account_id);
+    }
 }
 
 #[test]
@@ -189,6 +284,59 @@ fn test_parse_options_assignment() {
     assert_eq!(assignment.open_close, Some(OpenClose::Close));
     assert!(assignment.fifo_pnl_realized.is_some());
     assert_eq!(assignment.fifo_pnl_realized.unwrap().to_string(), "1050");
+    assert_eq!(assignment.notes.as_deref(), Some("A"));
+    assert!(assignment.codes().is_assignment());
+}
+
+#[test]
+fn test_parse_trade_notes_multi_code() {
+    let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<FlexQueryResponse queryName="Activity Query" type="AF">
+    <FlexStatements count="1">
+        <FlexStatement accountId="U1234567" fromDate="2025-01-15" toDate="2025-01-15"
+                       period="SingleDay" whenGenerated="2025-01-15;235959">
+            <Trades>
+                <Trade accountId="U1234567" currency="USD" assetCategory="STK" symbol="AAPL"
+                       conid="265598" tradeDate="2025-01-15" quantity="-100" buySell="SELL"
+                       openCloseIndicator="C" notes="C;W" />
+                <Trade accountId="U1234567" currency="USD" assetCategory="STK" symbol="AAPL"
+                       conid="265598" tradeDate="2025-01-15" quantity="40" buySell="BUY"
+                       openCloseIndicator="O" notes="O;P;R" />
+                <Trade accountId="U1234567" currency="USD" assetCategory="OPT"
+                       symbol="AAPL  250117C00200000" conid="700003" tradeDate="2025-01-15"
+                       quantity="1" buySell="BUY" notes="Ep;C" />
+                <Trade accountId="U1234567" currency="USD" assetCategory="STK" symbol="MSFT"
+                       conid="272093" tradeDate="2025-01-15" quantity="10" buySell="BUY"
+                       notes="" />
+            </Trades>
+        </FlexStatement>
+    </FlexStatements>
+</FlexQueryResponse>"#;
+
+    let statement = parse_activity_flex(xml).unwrap();
+    let trades = &statement.trades.items;
+    assert_eq!(trades.len(), 4);
+
+    assert_eq!(trades[0].notes.as_deref(), Some("C;W"));
+    let codes = trades[0].codes();
+    assert!(codes.is_closing());
+    assert!(codes.is_wash_sale());
+    assert!(!codes.is_opening());
+    assert_eq!(codes.len(), 2);
+
+    let codes = trades[1].codes();
+    assert!(codes.is_opening());
+    assert!(codes.is_partial());
+    assert!(codes.contains(TransactionCode::Reopen));
+    assert!(!codes.is_wash_sale());
+
+    let codes = trades[2].codes();
+    assert!(codes.is_expired());
+    assert!(codes.is_closing());
+    assert_eq!(codes.to_string(), "C;Ep");
+
+    assert!(trades[3].notes.is_none());
+    assert!(trades[3].codes().is_empty());
 }
 
 #[test]
@@ -334,6 +482,317 @@ fn test_parse_bonds_positions() {
         assert_eq!(pos.asset_category, AssetCategory::Bond);
         assert!(pos.quantity > rust_decimal::Decimal::ZERO);
     }
+
+    // Lot-level rows carry the acquisition timestamp
+    let treasury = &statement.positions.items[0];
+    let opened = chrono::NaiveDate::from_ymd_opt(2025, 1, 15)
+        .unwrap()
+        .and_hms_opt(10, 0, 0)
+        .unwrap();
+    assert_eq!(treasury.open_date_time, Some(opened));
+    assert_eq!(treasury.holding_period_date_time, Some(opened));
+
+    assert_eq!(treasury.accrued_int.unwrap().to_string(), "75.50");
+    assert_eq!(treasury.value_with_accrued().to_string(), "9938.00");
+    assert_eq!(treasury.vesting_date, None);
+    assert!(treasury.is_vested(opened.date()));
+}
+
+#[test]
+fn test_parse_bonds_security_info() {
+    let xml = include_str!("fixtures/activity_bonds.xml");
+    let statement = parse_activity_flex(xml).unwrap();
+
+    let treasury = &statement.securities_info.items[0];
+    assert_eq!(treasury.coupon_rate.unwrap().to_string(), "4.5");
+    assert_eq!(
+        treasury.maturity,
+        chrono::NaiveDate::from_ymd_opt(2033, 11, 15)
+    );
+    assert_eq!(
+        treasury.issue_date,
+        chrono::NaiveDate::from_ymd_opt(2003, 11, 15)
+    );
+    assert_eq!(treasury.settlement_policy_method.as_deref(), Some("T+1"));
+
+    let corp = &statement.securities_info.items[1];
+    assert_eq!(corp.coupon_rate, None);
+    assert_eq!(corp.settlement_policy_method, None);
+}
+
+#[test]
+fn test_security_info_sub_categories() {
+    use ib_flex::types::SubCategory;
+
+    let options = parse_activity_flex(include_str!("fixtures/activity_options.xml")).unwrap();
+    let aapl_call = &options.securities_info.items[0];
+    assert!(aapl_call.asset_category.is_option());
+    assert_eq!(aapl_call.sub_category, Some(SubCategory::EquityOption));
+
+    let futures = parse_activity_flex(include_str!("fixtures/activity_futures.xml")).unwrap();
+    let es = &futures.securities_info.items[0];
+    assert!(es.asset_category.is_future());
+    assert!(es.sub_category.as_ref().is_some_and(SubCategory::is_index));
+
+    let bonds = parse_activity_flex(include_str!("fixtures/activity_bonds.xml")).unwrap();
+    assert!(bonds
+        .securities_info
+        .items
+        .iter()
+        .all(|info| info.asset_category.is_fixed_income()));
+}
+
+#[test]
+fn test_summary_rows_without_conid() {
+    let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<FlexQueryResponse queryName="Activity" type="AF">
+  <FlexStatements count="1">
+    <FlexStatement accountId="U1234567" fromDate="2025-01-15" toDate="2025-01-15"
+                   period="LastBusinessDay" whenGenerated="2025-01-15;150000">
+      <Trades>
+        <Trade accountId="U1234567" currency="USD" conid="" symbol="" assetCategory="STK"
+               levelOfDetail="ASSET_SUMMARY" tradeDate="2025-01-15" />
+        <Trade accountId="U1234567" currency="USD" conid="--" symbol="" assetCategory="STK"
+               levelOfDetail="SYMBOL_SUMMARY" tradeDate="2025-01-15" />
+      </Trades>
+      <OpenPositions>
+        <OpenPosition accountId="U1234567" currency="USD" symbol="" assetCategory="STK"
+                      levelOfDetail="SUMMARY" reportDate="2025-01-15" position="0"
+                      markPrice="0" positionValue="0" />
+      </OpenPositions>
+    </FlexStatement>
+  </FlexStatements>
+</FlexQueryResponse>"#;
+
+    let statement = parse_activity_flex(xml).unwrap();
+    assert_eq!(statement.trades.items[0].conid, Conid::default());
+    assert_eq!(statement.trades.items[1].conid, Conid(0));
+    assert_eq!(statement.positions.items[0].conid, Conid(0));
+}
+
+#[test]
+fn test_conid_joins_across_sections() {
+    let xml = include_str!("fixtures/activity_backfill.xml");
+    let statements = ib_flex::parse_activity_flex_all(xml).unwrap();
+    let statement = &statements[0];
+
+    let option = statement
+        .positions
+        .items
+        .iter()
+        .find(|p| p.asset_category == AssetCategory::Option)
+        .unwrap();
+    assert_eq!(option.conid, Conid(123456789));
+    assert_eq!(option.underlying_conid, Some(Conid(265598)));
+
+    let underlying = statement
+        .securities_info
+        .items
+        .iter()
+        .find(|s| Some(s.conid) == option.underlying_conid)
+        .unwrap();
+    assert_eq!(underlying.symbol, "AAPL");
+
+    let bonds = parse_activity_flex(include_str!("fixtures/activity_bonds.xml")).unwrap();
+    assert_eq!(bonds.positions.items[0].underlying_conid, None);
+}
+
+#[test]
+fn test_trade_money_accessors() {
+    let xml = include_str!("fixtures/activity_cancelled_trades.xml");
+    let statement = parse_activity_flex(xml).unwrap();
+    let trades = &statement.trades.items;
+
+    let buy = trades[0].net_cash_money().unwrap();
+    let bust = trades[1].net_cash_money().unwrap();
+    assert_eq!(buy.currency, "USD");
+    assert!(buy.checked_add(&bust).unwrap().is_zero());
+
+    let proceeds: Vec<Money> = trades.iter().filter_map(|t| t.proceeds_money()).collect();
+    assert!(Money::sum(&proceeds).unwrap().unwrap().is_zero());
+
+    let eur = Money::zero("EUR");
+    assert!(buy.checked_add(&eur).is_err());
+}
+
+#[test]
+fn test_trade_cost_and_proceeds_helpers() {
+    use ib_flex::types::TradeBuilder;
+    use rust_decimal::Decimal;
+
+    // Sell 2 option contracts at 1.50, reported with a positive quantity
+    let sell = TradeBuilder::new("AAPL  250221C00180000")
+        .asset_category(AssetCategory::Option)
+        .buy_sell(BuySell::Sell)
+        .quantity(2)
+        .price(Decimal::new(150, 2))
+        .multiplier(100)
+        .proceeds(300)
+        .commission(Decimal::new(-130, 2))
+        .fx_rate_to_base(Decimal::new(9, 1))
+        .build();
+    assert_eq!(sell.signed_quantity(), Some(Decimal::from(-2)));
+    assert_eq!(sell.total_cost(), Some(Decimal::new(-29870, 2)));
+    assert_eq!(sell.net_proceeds(), Some(Decimal::new(29870, 2)));
+    assert_eq!(sell.proceeds_in_base(), Some(Decimal::from(270)));
+    assert_eq!(sell.commission_in_base(), Some(Decimal::new(-117, 2)));
+
+    let mut foreign_commission = sell.clone();
+    foreign_commission.commission_currency = Some("EUR".to_string());
+    assert_eq!(foreign_commission.commission_in_base(), None);
+
+    let xml = include_str!("fixtures/activity_minimal.xml");
+    let statement = parse_activity_flex(xml).unwrap();
+    let trade = &statement.trades.items[0];
+    assert_eq!(trade.net_proceeds(), trade.net_cash);
+    assert!(trade.total_cost().unwrap() > Decimal::ZERO);
+}
+
+#[test]
+fn test_position_valuation_helpers() {
+    use ib_flex::types::PositionBuilder;
+    use rust_decimal::Decimal;
+
+    // Short 3 ES futures, sold at 5000, now 4900
+    let short = PositionBuilder::new("ESH5")
+        .asset_category(AssetCategory::Future)
+        .quantity(-3)
+        .mark_price(4900)
+        .multiplier(50)
+        .cost_basis_price(5000)
+        .fx_rate_to_base(Decimal::new(5, 1))
+        .build();
+    assert_eq!(short.market_value(), Decimal::from(-735_000));
+    assert_eq!(short.market_value_in_base(), Decimal::from(-367_500));
+    assert_eq!(short.cost_basis_total(), Some(Decimal::from(-750_000)));
+    assert_eq!(short.cost_basis_in_base(), Some(Decimal::from(-375_000)));
+    assert_eq!(short.unrealized_pnl(), Some(Decimal::from(15_000)));
+    assert_eq!(short.unrealized_pnl_pct(), Some(Decimal::from(2)));
+
+    let no_basis = PositionBuilder::new("AAPL")
+        .quantity(1)
+        .mark_price(1)
+        .build();
+    assert_eq!(no_basis.unrealized_pnl_pct(), None);
+
+    let xml = include_str!("fixtures/activity_minimal.xml");
+    let statement = parse_activity_flex(xml).unwrap();
+    for position in &statement.positions.items {
+        assert_eq!(position.market_value(), position.position_value);
+        if position.fifo_pnl_unrealized.is_some() {
+            assert_eq!(position.unrealized_pnl(), position.fifo_pnl_unrealized);
+        }
+    }
+}
+
+#[test]
+fn test_conversion_rates_to_table() {
+    let xml = include_str!("fixtures/activity_backfill.xml");
+    let statements = ib_flex::parse_activity_flex_all(xml).unwrap();
+    let table = statements[0].conversion_rates.to_table();
+    let date = chrono::NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+
+    assert_eq!(table.base_currency(), Some("USD"));
+    assert_eq!(
+        table.rate("EUR", "USD", date).unwrap().to_string(),
+        "1.1050"
+    );
+    assert_eq!(
+        table
+            .convert(rust_decimal::Decimal::from(100), "GBP", "EUR", date)
+            .unwrap()
+            .round_dp(4)
+            .to_string(),
+        "115.3846"
+    );
+    assert_eq!(table.rate("JPY", "USD", date), None);
+}
+
+#[test]
+fn test_position_vesting_and_accrued_interest_alias() {
+    let xml = r#"<FlexQueryResponse queryName="Test" type="AF">
+        <FlexStatements count="1">
+            <FlexStatement accountId="U1234567" fromDate="2025-01-15"
+                           toDate="2025-01-15" whenGenerated="2025-01-15;150000">
+                <OpenPositions>
+                    <OpenPosition accountId="U1234567" currency="USD" assetCategory="STK"
+                                  symbol="ACME" conid="111111" reportDate="2025-01-15"
+                                  position="200" markPrice="50" positionValue="10000"
+                                  vestingDate="2025-06-30" />
+                    <OpenPosition accountId="U1234567" currency="USD" assetCategory="BOND"
+                                  symbol="T 4.5 11/15/33" conid="99999999" reportDate="2025-01-15"
+                                  position="10000" markPrice="98.625" positionValue="9862.50"
+                                  accruedInterest="75.50" />
+                </OpenPositions>
+            </FlexStatement>
+        </FlexStatements>
+    </FlexQueryResponse>"#;
+
+    let statement = parse_activity_flex(xml).unwrap();
+    let grant = &statement.positions.items[0];
+    let vesting = chrono::NaiveDate::from_ymd_opt(2025, 6, 30).unwrap();
+    assert_eq!(grant.vesting_date, Some(vesting));
+    assert!(!grant.is_vested(chrono::NaiveDate::from_ymd_opt(2025, 1, 15).unwrap()));
+    assert!(grant.is_vested(vesting));
+
+    let bond = &statement.positions.items[1];
+    assert_eq!(bond.accrued_int.unwrap().to_string(), "75.50");
+}
+
+#[test]
+fn test_position_originating_trades() {
+    let xml = r#"<FlexQueryResponse queryName="Test" type="AF">
+        <FlexStatements count="1">
+            <FlexStatement accountId="U1234567" fromDate="2025-01-15"
+                           toDate="2025-01-15" whenGenerated="2025-01-15;150000">
+                <Trades>
+                    <Trade accountId="U1234567" currency="USD" assetCategory="STK" symbol="AAPL"
+                           conid="265598" tradeDate="2025-01-15" quantity="60" buySell="BUY"
+                           transactionID="30001" ibOrderID="4001" />
+                    <Trade accountId="U1234567" currency="USD" assetCategory="STK" symbol="AAPL"
+                           conid="265598" tradeDate="2025-01-15" quantity="40" buySell="BUY"
+                           transactionID="30002" ibOrderID="4001" />
+                    <Trade accountId="U1234567" currency="USD" assetCategory="STK" symbol="MSFT"
+                           conid="272093" tradeDate="2025-01-15" quantity="10" buySell="BUY"
+                           transactionID="30003" ibOrderID="4002" />
+                </Trades>
+                <OpenPositions>
+                    <OpenPosition accountId="U1234567" currency="USD" assetCategory="STK"
+                                  symbol="MSFT" conid="272093" reportDate="2025-01-15"
+                                  position="10" markPrice="420" positionValue="4200"
+                                  levelOfDetail="LOT" openDateTime="2025-01-15;100000"
+                                  originatingTransactionID="30003" originatingOrderID="4002" />
+                    <OpenPosition accountId="U1234567" currency="USD" assetCategory="STK"
+                                  symbol="AAPL" conid="265598" reportDate="2025-01-15"
+                                  position="100" markPrice="185" positionValue="18500"
+                                  levelOfDetail="LOT" originatingTransactionID=""
+                                  originatingOrderID="4001" />
+                    <OpenPosition accountId="U1234567" currency="USD" assetCategory="STK"
+                                  symbol="AAPL" conid="265598" reportDate="2025-01-15"
+                                  position="100" markPrice="185" positionValue="18500"
+                                  levelOfDetail="SUMMARY" />
+                </OpenPositions>
+            </FlexStatement>
+        </FlexStatements>
+    </FlexQueryResponse>"#;
+
+    let statement = parse_activity_flex(xml).unwrap();
+    let trades = &statement.trades.items;
+    let positions = &statement.positions.items;
+
+    let msft = positions[0].originating_trades(trades);
+    assert_eq!(msft.len(), 1);
+    assert_eq!(msft[0].transaction_id.as_deref(), Some("30003"));
+
+    // Falls back to the order ID, which can span several executions
+    assert_eq!(positions[1].originating_transaction_id, None);
+    let aapl = positions[1].originating_trades(trades);
+    assert_eq!(aapl.len(), 2);
+    assert!(aapl
+        .iter()
+        .all(|t| t.ib_order_id.as_deref() == Some("4001")));
+
+    assert!(positions[2].originating_trades(trades).is_empty());
 }
 
 // ==================== CORPORATE ACTIONS TESTS ====================
@@ -393,6 +852,288 @@ fn test_parse_cash_transactions() {
     assert_eq!(statement.cash_transactions.items.len(), 15);
 }
 
+#[test]
+fn test_statement_query_helpers() {
+    use chrono::NaiveDate;
+    use rust_decimal::Decimal;
+
+    let statement = parse_activity_flex(include_str!("fixtures/activity_options.xml")).unwrap();
+    let symbol = statement.trades.items[0].symbol.clone();
+    let expected = statement
+        .trades
+        .iter()
+        .filter(|t| t.symbol == symbol)
+        .count();
+    assert_eq!(statement.trades_for_symbol(&symbol).count(), expected);
+    assert_eq!(statement.trades_for_symbol("NOPE").count(), 0);
+
+    let from = statement.from_date;
+    let to = statement.to_date;
+    assert_eq!(
+        statement.trades_in(from..=to).count(),
+        statement
+            .trades
+            .iter()
+            .filter(|t| t.trade_date.is_some())
+            .count()
+    );
+    assert_eq!(statement.trades_in(..from).count(), 0);
+
+    let position = &statement.positions.items[0];
+    assert_eq!(statement.position_by_conid(position.conid), Some(position));
+    assert_eq!(statement.position_by_conid(Conid(1)), None);
+
+    let minimal = parse_activity_flex(include_str!("fixtures/activity_minimal.xml")).unwrap();
+    assert_eq!(
+        minimal.total_commissions(),
+        vec![Money::new(Decimal::new(-100, 2), "USD")]
+    );
+    assert!(
+        minimal
+            .trades_in(NaiveDate::from_ymd_opt(2025, 1, 15).unwrap()..)
+            .count()
+            == 1
+    );
+
+    let cash = parse_activity_flex(include_str!("fixtures/activity_cash.xml")).unwrap();
+    assert!(cash.dividends().count() > 0);
+    assert!(cash
+        .dividends()
+        .all(|tx| tx.transaction_type.as_ref().unwrap().is_dividend()));
+}
+
+#[test]
+fn test_records_sort_chronologically_and_dedup() {
+    use std::collections::HashSet;
+
+    let statement = parse_activity_flex(include_str!("fixtures/activity_options.xml")).unwrap();
+    let mut trades = statement.trades.items.clone();
+    trades.reverse();
+    trades.sort();
+    assert!(trades
+        .windows(2)
+        .all(|w| w[0].trade_date <= w[1].trade_date));
+
+    let mut doubled = statement.trades.items.clone();
+    doubled.extend(statement.trades.items.iter().cloned());
+    let unique: HashSet<_> = doubled.into_iter().collect();
+    assert_eq!(unique.len(), statement.trades.items.len());
+
+    let cash = parse_activity_flex(include_str!("fixtures/activity_cash.xml")).unwrap();
+    let mut txs = cash.cash_transactions.items.clone();
+    txs.sort();
+    assert!(txs.windows(2).all(|w| w[0].date <= w[1].date));
+}
+
+#[test]
+fn test_order_groups() {
+    use ib_flex::types::{OrderGroup, OrderId, TradeBuilder};
+    use rust_decimal::Decimal;
+
+    let xml = include_str!("fixtures/activity_cancelled_trades.xml");
+    let statement = parse_activity_flex(xml).unwrap();
+    let groups = statement.order_groups();
+
+    let msft = groups.iter().find(|g| g.order_id == "7001").unwrap();
+    assert_eq!(msft.symbol(), "MSFT");
+    assert_eq!(msft.executions.len(), 2);
+    // The bust reverses the original fill
+    assert_eq!(msft.total_quantity(), Decimal::ZERO);
+    assert_eq!(msft.executions[1].orig_trade_id.as_deref(), Some("5001"));
+
+    let fills = [
+        TradeBuilder::new("AAPL")
+            .ib_order_id("42")
+            .buy_sell(BuySell::Buy)
+            .quantity(100)
+            .price(150)
+            .commission(-1)
+            .build(),
+        TradeBuilder::new("AAPL")
+            .ib_order_id("42")
+            .buy_sell(BuySell::Buy)
+            .quantity(300)
+            .price(154)
+            .commission(Decimal::new(-150, 2))
+            .build(),
+    ];
+    let group = OrderGroup {
+        order_id: OrderId::from("42"),
+        executions: fills.iter().collect(),
+    };
+    assert_eq!(group.total_quantity(), Decimal::from(400));
+    assert_eq!(group.average_price(), Some(Decimal::from(153)));
+    assert_eq!(group.total_commission(), Decimal::new(-250, 2));
+}
+
+#[test]
+fn test_option_contracts_join_across_sections() {
+    let xml = include_str!("fixtures/activity_options.xml");
+    let statement = parse_activity_flex(xml).unwrap();
+
+    for position in statement
+        .positions
+        .iter()
+        .filter(|p| p.asset_category == AssetCategory::Option)
+    {
+        let contract = position.option_contract().unwrap();
+        assert_eq!(contract.osi_symbol(), position.symbol);
+        // The symbol and the flat fields describe the same contract
+        assert_eq!(contract, position.symbol.parse().unwrap());
+    }
+
+    let trade_contracts: Vec<_> = statement
+        .trades
+        .iter()
+        .filter_map(|t| t.option_contract())
+        .collect();
+    assert!(!trade_contracts.is_empty());
+    assert!(statement.positions.iter().any(|p| {
+        p.option_contract()
+            .is_some_and(|c| trade_contracts.contains(&c))
+    }));
+}
+
+#[test]
+fn test_futures_contracts_from_statement() {
+    let xml = include_str!("fixtures/activity_futures.xml");
+    let statement = parse_activity_flex(xml).unwrap();
+
+    let es = statement
+        .positions
+        .iter()
+        .find(|p| p.symbol == "ESH5")
+        .and_then(|p| p.futures_contract())
+        .unwrap();
+    assert_eq!(es.root, "ES");
+    assert_eq!((es.year, es.month), (2025, 3));
+    assert_eq!(es.expiry, chrono::NaiveDate::from_ymd_opt(2025, 3, 21));
+
+    for trade in statement
+        .trades
+        .iter()
+        .filter(|t| t.asset_category.is_future())
+    {
+        let contract = trade.futures_contract().unwrap();
+        assert_eq!(contract.to_string(), trade.symbol);
+    }
+}
+
+#[test]
+fn test_compact_parse_matches_full_parse() {
+    use ib_flex::parsers::parse_activity_flex_compact;
+    use ib_flex::types::CompactStatement;
+
+    for xml in [
+        include_str!("fixtures/activity_minimal.xml"),
+        include_str!("fixtures/activity_options.xml"),
+        include_str!("fixtures/activity_futures.xml"),
+        include_str!("fixtures/activity_daily_portfolio.xml"),
+        include_str!("fixtures/activity_backfill.xml"),
+    ] {
+        let full: Vec<CompactStatement> = ib_flex::parse_activity_flex_all(xml)
+            .unwrap()
+            .iter()
+            .map(CompactStatement::from)
+            .collect();
+        assert_eq!(parse_activity_flex_compact(xml).unwrap(), full);
+    }
+
+    // Interleaved summary rows are skipped like in the full parse
+    let xml = r#"<FlexQueryResponse queryName="Q" type="AF">
+    <FlexStatements count="1">
+        <FlexStatement accountId="U1234567" fromDate="20250115" toDate="20250115" whenGenerated="20250115;235959">
+            <Trades>
+                <Order accountId="U1234567" currency="USD" assetCategory="STK" symbol="AAPL" conid="265598"
+                       levelOfDetail="ORDER" quantity="100" price="185.50" />
+                <Trade accountId="U1234567" currency="USD" assetCategory="STK" symbol="AAPL" conid="265598"
+                       tradeDate="20250115" buySell="BUY" quantity="100" price="185.50" />
+                <SymbolSummary accountId="U1234567" currency="USD" assetCategory="STK" symbol="AAPL"
+                       conid="265598" levelOfDetail="SYMBOL_SUMMARY" quantity="100" />
+            </Trades>
+        </FlexStatement>
+    </FlexStatements>
+</FlexQueryResponse>"#;
+    let compact = parse_activity_flex_compact(xml).unwrap();
+    assert_eq!(compact[0].trades.len(), 1);
+    assert_eq!(compact[0].trades[0].buy_sell, Some(BuySell::Buy));
+    assert!(compact[0].positions.is_empty());
+}
+
+#[test]
+fn test_compact_f64_parse_matches_conversion() {
+    use ib_flex::parsers::parse_activity_flex_compact_f64;
+    use ib_flex::types::CompactStatementF64;
+
+    for xml in [
+        include_str!("fixtures/activity_minimal.xml"),
+        include_str!("fixtures/activity_options.xml"),
+        include_str!("fixtures/activity_daily_portfolio.xml"),
+    ] {
+        let full: Vec<CompactStatementF64> = ib_flex::parse_activity_flex_all(xml)
+            .unwrap()
+            .iter()
+            .map(CompactStatementF64::from)
+            .collect();
+        assert_eq!(parse_activity_flex_compact_f64(xml).unwrap(), full);
+    }
+
+    let xml = include_str!("fixtures/activity_minimal.xml");
+    let statements = parse_activity_flex_compact_f64(xml).unwrap();
+    let trade = &statements[0].trades[0];
+    assert_eq!(trade.quantity, Some(100.0));
+    assert_eq!(trade.price, Some(185.5));
+}
+
+#[test]
+fn test_wrappers_behave_like_collections() {
+    use ib_flex::types::CashTransactionsWrapper;
+
+    let xml = include_str!("fixtures/activity_cash.xml");
+    let statement = parse_activity_flex(xml).unwrap();
+
+    let cash = &statement.cash_transactions;
+    assert_eq!(cash.len(), 15);
+    assert!(!cash.is_empty());
+    assert_eq!(cash.first(), cash.items.first());
+    assert_eq!((&statement.cash_transactions).into_iter().count(), 15);
+
+    let mut dividends: CashTransactionsWrapper = cash
+        .iter()
+        .filter(|tx| {
+            tx.transaction_type
+                .as_ref()
+                .is_some_and(|t| t.is_dividend())
+        })
+        .cloned()
+        .collect();
+    let count = dividends.len();
+    dividends.extend(cash.iter().take(2).cloned());
+    assert_eq!(dividends.len(), count + 2);
+    assert_eq!(dividends.into_iter().count(), count + 2);
+}
+
+#[test]
+fn test_cash_transaction_classification() {
+    let xml = include_str!("fixtures/activity_cash.xml");
+    let statement = parse_activity_flex(xml).unwrap();
+    let cash = &statement.cash_transactions;
+
+    let count = |f: fn(&ib_flex::CashTransaction) -> bool| cash.iter().filter(|tx| f(tx)).count();
+    assert_eq!(count(|tx| tx.is_dividend()), 2);
+    assert_eq!(count(|tx| tx.is_withholding_tax()), 1);
+    assert_eq!(count(|tx| tx.is_interest()), 3);
+    assert_eq!(count(|tx| tx.is_fee()), 5);
+    assert_eq!(count(|tx| tx.is_deposit_or_withdrawal()), 3);
+
+    // "Realized P/L" is unrecognized and matches no category
+    let unknown = cash
+        .iter()
+        .find(|tx| matches!(tx.transaction_type, Some(CashTransactionType::Unknown(_))))
+        .unwrap();
+    assert!(!unknown.is_dividend() && !unknown.is_fee() && !unknown.is_interest());
+}
+
 #[test]
 fn test_parse_deposit_withdrawal() {
     let xml = include_str!("fixtures/activity_cash.xml");
@@ -403,7 +1144,7 @@ fn test_parse_deposit_withdrawal() {
     assert_eq!(deposit.amount.to_string(), "10000.00");
     assert_eq!(
         deposit.transaction_type,
-        Some("Deposits & Withdrawals".to_string())
+        Some(CashTransactionType::DepositsWithdrawals)
     );
 
     // Find withdrawal (index 1)
@@ -420,7 +1161,7 @@ fn test_parse_interest_transactions() {
     let credit = &statement.cash_transactions.items[2];
     assert_eq!(
         credit.transaction_type,
-        Some("Broker Interest Paid".to_string())
+        Some(CashTransactionType::BrokerInterestPaid)
     );
     assert!(credit.amount > rust_decimal::Decimal::ZERO);
 }
@@ -433,8 +1174,25 @@ fn test_parse_dividend_cash() {
     // Find dividend (index 4)
     let dividend = &statement.cash_transactions.items[4];
     assert_eq!(dividend.symbol.clone().unwrap(), "AAPL");
-    assert_eq!(dividend.transaction_type, Some("Dividends".to_string()));
+    assert_eq!(
+        dividend.transaction_type,
+        Some(CashTransactionType::Dividends)
+    );
     assert_eq!(dividend.amount.to_string(), "150.00");
+
+    let ymd = |d| chrono::NaiveDate::from_ymd_opt(2025, 1, d);
+    assert_eq!(dividend.settle_date, ymd(15));
+    assert_eq!(dividend.available_for_trading_date, ymd(16));
+    assert_eq!(dividend.level_of_detail, Some(LevelOfDetail::Detail));
+    assert_eq!(dividend.code.as_deref(), Some("Po"));
+    assert!(dividend.codes().contains(TransactionCode::PostAccrual));
+    assert_eq!(dividend.trade_id, None);
+
+    // Rows without these attributes leave them unset
+    let withholding = &statement.cash_transactions.items[5];
+    assert_eq!(withholding.settle_date, None);
+    assert_eq!(withholding.available_for_trading_date, None);
+    assert_eq!(withholding.code, None);
 }
 
 // ==================== WARRANT TESTS ====================
@@ -544,7 +1302,10 @@ fn test_parse_cfd_financing() {
 
     let financing = &statement.cash_transactions.items[0];
     assert_eq!(financing.asset_category, Some(AssetCategory::Cfd));
-    assert_eq!(financing.transaction_type, Some("Other Fees".to_string()));
+    assert_eq!(
+        financing.transaction_type,
+        Some(CashTransactionType::OtherFees)
+    );
 }
 
 // ==================== CANCELLED TRADES TESTS ====================
@@ -577,6 +1338,35 @@ fn test_parse_cancelled_trades() {
     assert_eq!(cancel_sell.symbol, "GOOGL");
 }
 
+#[test]
+fn test_cancelled_trades_link_to_original() {
+    let xml = include_str!("fixtures/activity_cancelled_trades.xml");
+    let statement = parse_activity_flex(xml).unwrap();
+    let trades = &statement.trades.items;
+
+    let cancel_buy = &trades[1];
+    assert_eq!(cancel_buy.orig_trade_id.as_deref(), Some("5001"));
+    assert_eq!(cancel_buy.orig_order_id.as_deref(), Some("7001"));
+    assert_eq!(
+        cancel_buy.orig_trade_date,
+        Some(chrono::NaiveDate::from_ymd_opt(2025, 1, 15).unwrap())
+    );
+    assert_eq!(cancel_buy.orig_trade_price.unwrap().to_string(), "420.50");
+
+    let original = cancel_buy.original_trade(trades).unwrap();
+    assert_eq!(original.trade_id.as_deref(), Some("5001"));
+    assert_eq!(original.buy_sell, Some(BuySell::Buy));
+
+    let cancel_sell = &trades[3];
+    let original = cancel_sell.original_trade(trades).unwrap();
+    assert_eq!(original.trade_id.as_deref(), Some("5003"));
+    assert_eq!(original.symbol, "GOOGL");
+
+    // Original executions have no linkage of their own
+    assert!(trades[0].orig_trade_id.is_none());
+    assert!(trades[0].original_trade(trades).is_none());
+}
+
 // ==================== FRACTIONAL SHARES TESTS ====================
 
 #[test]
@@ -0,0 +1,88 @@
+//! Tests for serializing parsed statements back to FLEX XML
+
+use ib_flex::{parse_activity_flex, parse_activity_flex_all, write_activity_flex};
+
+const FIXTURES: &[&str] = &[
+    include_str!("fixtures/activity_minimal.xml"),
+    include_str!("fixtures/activity_options.xml"),
+    include_str!("fixtures/activity_bonds.xml"),
+    include_str!("fixtures/activity_cash.xml"),
+    include_str!("fixtures/activity_extended.xml"),
+    include_str!("fixtures/activity_daily_portfolio.xml"),
+    include_str!("fixtures/activity_backfill.xml"),
+    include_str!("fixtures/activity_cancelled_trades.xml"),
+    include_str!("fixtures/activity_cfds.xml"),
+    include_str!("fixtures/activity_complex_corporate_actions.xml"),
+    include_str!("fixtures/activity_corporate_actions.xml"),
+    include_str!("fixtures/activity_forex.xml"),
+    include_str!("fixtures/activity_fractional_shares.xml"),
+    include_str!("fixtures/activity_futures.xml"),
+    include_str!("fixtures/activity_tbills.xml"),
+    include_str!("fixtures/activity_warrants.xml"),
+];
+
+#[test]
+fn test_round_trip_equals_original() {
+    for xml in FIXTURES {
+        let original = parse_activity_flex_all(xml).unwrap();
+        let written = write_activity_flex(&original).unwrap();
+        let reparsed = parse_activity_flex_all(&written).expect("written XML should parse");
+        assert_eq!(reparsed, original);
+    }
+}
+
+#[test]
+fn test_serialized_xml_parses_back() {
+    for xml in FIXTURES {
+        let original = parse_activity_flex(xml).unwrap();
+        let first = original.to_xml().unwrap();
+        let reparsed = parse_activity_flex(&first).expect("serialized XML should parse");
+        let second = reparsed.to_xml().unwrap();
+
+        assert_eq!(first, second, "serialization should be a fixed point");
+        assert_eq!(reparsed.trades.items.len(), original.trades.items.len());
+        assert_eq!(
+            reparsed.positions.items.len(),
+            original.positions.items.len()
+        );
+        assert_eq!(reparsed.from_date, original.from_date);
+    }
+}
+
+#[test]
+fn test_serialized_attributes_use_ib_formats() {
+    let xml = include_str!("fixtures/activity_daily_portfolio.xml");
+    let statement = parse_activity_flex(xml).unwrap();
+    let out = statement.to_xml().unwrap();
+
+    assert!(out.starts_with("<?xml"));
+    assert!(out.contains(r#"type="AF""#));
+    assert!(out.contains(r#"fromDate="20250115""#));
+    assert!(!out.contains(r#"fromDate="2025-01-15""#));
+    // Date/times use IB's `;` separator rather than chrono's `T`
+    assert!(out.contains(r#"dateTime="20250115;"#));
+    // Missing values are written as empty attributes
+    assert!(out.contains(r#"figi="""#));
+    assert!(!out.contains("<AccountInformation/>"));
+}
+
+#[test]
+fn test_serialized_bools_use_y_n() {
+    let xml = r#"<FlexQueryResponse queryName="Test" type="AF">
+  <FlexStatements count="1">
+    <FlexStatement accountId="U1234567" fromDate="20250115" toDate="20250115"
+                   period="LastBusinessDay" whenGenerated="20250115;150000">
+      <Trades>
+        <Trade accountId="U1234567" conid="265598" symbol="AAPL" assetCategory="STK"
+               tradeDate="20250115" isAPIOrder="Y" currency="USD" />
+      </Trades>
+    </FlexStatement>
+  </FlexStatements>
+</FlexQueryResponse>"#;
+    let statement = parse_activity_flex(xml).unwrap();
+    let out = statement.to_xml().unwrap();
+
+    assert!(out.contains(r#"isAPIOrder="Y""#));
+    let reparsed = parse_activity_flex(&out).unwrap();
+    assert_eq!(reparsed.trades.items[0].is_api_order, Some(true));
+}